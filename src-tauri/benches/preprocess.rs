@@ -0,0 +1,42 @@
+//! 识别预处理的耗时：cargo bench --bench preprocess
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use image::{DynamicImage, Rgb, RgbImage};
+use prinsp_lib::{preprocess, Timings};
+
+/// 模拟截图：浅色背景上的深色和彩色文字块
+fn screenshot(w: u32, h: u32) -> RgbImage {
+    RgbImage::from_fn(w, h, |x, y| match ((x / 7) % 9, (y / 11) % 5) {
+        (0..=2, 1) => Rgb([30, 30, 30]),
+        (4, 2) => Rgb([200, 40, 40]),
+        _ => Rgb([240, 240, 236]),
+    })
+}
+
+fn bench_preprocess(c: &mut Criterion) {
+    let mut group = c.benchmark_group("preprocess");
+    group.sample_size(10);
+    for (name, w, h) in [("1080p", 1920, 1080), ("4k", 3840, 2160)] {
+        let rgb = screenshot(w, h);
+        group.bench_with_input(
+            BenchmarkId::new("channel_emphasis", name),
+            &rgb,
+            |b, img| b.iter(|| preprocess::channel_emphasized_gray(img)),
+        );
+        let gray = preprocess::channel_emphasized_gray(&rgb);
+        group.bench_with_input(BenchmarkId::new("upscale_2x", name), &gray, |b, img| {
+            b.iter(|| preprocess::upscale_2x(img))
+        });
+        let img = DynamicImage::ImageRgb8(rgb);
+        group.bench_with_input(
+            BenchmarkId::new("preprocess_for_ocr", name),
+            &img,
+            |b, img| {
+                b.iter(|| preprocess::preprocess_for_ocr(img, &mut Timings::default()).unwrap())
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_preprocess);
+criterion_main!(benches);
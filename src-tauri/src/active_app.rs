@@ -0,0 +1,273 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::capture_id::CaptureId;
+use crate::external::{run_external, ExternalTool, PROBE_TIMEOUT};
+
+/// 各次截图触发时的前台应用，按截图编号记录，截图完成后用于文件名和历史记录
+static BY_CAPTURE: Mutex<VecDeque<(CaptureId, ActiveApp)>> = Mutex::new(VecDeque::new());
+
+/// 最多记录的截图次数，取消的截图不会被取用，靠数量上限淘汰
+const MAX_REMEMBERED: usize = 8;
+
+/// 应用名的最大长度（字符）
+const MAX_NAME_CHARS: usize = 32;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ActiveApp {
+    /// 可直接用于文件名的应用名，如 firefox
+    pub name: String,
+    /// 窗口类名或 app_id 原文
+    pub class: String,
+    pub title: Option<String>,
+    pub pid: Option<u32>,
+}
+
+/// 转为可用于文件名的形式：反向域名只取最后一段，小写，其他字符替换为 _
+pub fn sanitize(name: &str) -> Option<String> {
+    let name = name.trim();
+    let name = name.rsplit('.').find(|s| !s.is_empty()).unwrap_or(name);
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '-' {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    let out: String = out
+        .trim_matches(|c| c == '_' || c == '-')
+        .chars()
+        .take(MAX_NAME_CHARS)
+        .collect();
+    Some(out).filter(|s| !s.is_empty())
+}
+
+impl ActiveApp {
+    fn new(class: &str, title: Option<String>, pid: Option<u32>) -> Option<Self> {
+        // 本应用自己的窗口没有意义
+        if pid == Some(std::process::id()) {
+            return None;
+        }
+        Some(Self {
+            name: sanitize(class)?,
+            class: class.to_string(),
+            title: title.filter(|t| !t.is_empty()),
+            pid,
+        })
+    }
+}
+
+fn json_output(tool: ExternalTool, args: &[&str]) -> Option<Value> {
+    let output = run_external(tool, args, None, PROBE_TIMEOUT).ok()?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn pid_of(node: &Value) -> Option<u32> {
+    node["pid"].as_u64().and_then(|pid| u32::try_from(pid).ok())
+}
+
+fn hyprland() -> Option<ActiveApp> {
+    let window = json_output(ExternalTool::Hyprctl, &["activewindow", "-j"])?;
+    let class = window["class"]
+        .as_str()
+        .filter(|c| !c.is_empty())
+        .or_else(|| window["initialClass"].as_str())?;
+    ActiveApp::new(
+        class,
+        window["title"].as_str().map(str::to_string),
+        pid_of(&window),
+    )
+}
+
+/// 在 sway 的窗口树中查找有焦点的窗口
+fn focused_node(node: &Value) -> Option<&Value> {
+    if node["focused"].as_bool() == Some(true) && node["pid"].is_u64() {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(focused_node)
+}
+
+fn sway() -> Option<ActiveApp> {
+    let tree = json_output(ExternalTool::Swaymsg, &["-t", "get_tree"])?;
+    let node = focused_node(&tree)?;
+    // XWayland 窗口没有 app_id，使用 X11 的窗口类名
+    let class = node["app_id"]
+        .as_str()
+        .or_else(|| node["window_properties"]["class"].as_str())?;
+    ActiveApp::new(
+        class,
+        node["name"].as_str().map(str::to_string),
+        pid_of(node),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn x11() -> Result<Option<ActiveApp>, String> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, GetPropertyReply};
+
+    fn err(e: impl std::fmt::Display) -> String {
+        format!("X11: {e}")
+    }
+
+    let (conn, screen) = x11rb::connect(None).map_err(err)?;
+    let root = conn.setup().roots[screen].root;
+    let atom = |name: &str| -> Result<u32, String> {
+        conn.intern_atom(false, name.as_bytes())
+            .map_err(err)?
+            .reply()
+            .map(|reply| reply.atom)
+            .map_err(err)
+    };
+    let property = |window: u32, name: u32, kind: u32| -> Result<GetPropertyReply, String> {
+        conn.get_property(false, window, name, kind, 0, 1024)
+            .map_err(err)?
+            .reply()
+            .map_err(err)
+    };
+
+    let active = property(root, atom("_NET_ACTIVE_WINDOW")?, AtomEnum::WINDOW.into())?
+        .value32()
+        .and_then(|mut values| values.next())
+        .filter(|&window| window != 0);
+    let Some(window) = active else {
+        return Ok(None);
+    };
+    // WM_CLASS 为 "实例名\0类名\0"，优先使用类名
+    let wm_class = property(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())?.value;
+    let mut parts = wm_class
+        .split(|&b| b == 0)
+        .filter(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).into_owned());
+    let instance = parts.next();
+    let Some(class) = parts.next().or(instance) else {
+        return Ok(None);
+    };
+    let title = property(window, atom("_NET_WM_NAME")?, atom("UTF8_STRING")?)?.value;
+    let title = if title.is_empty() {
+        property(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into())?.value
+    } else {
+        title
+    };
+    let pid = property(window, atom("_NET_WM_PID")?, AtomEnum::CARDINAL.into())?
+        .value32()
+        .and_then(|mut values| values.next());
+    Ok(ActiveApp::new(
+        &class,
+        Some(String::from_utf8_lossy(&title).into_owned()),
+        pid,
+    ))
+}
+
+/// 当前前台窗口所属的应用。
+///
+/// X11 读取活动窗口的 WM_CLASS 与 _NET_WM_PID，Hyprland / sway 通过合成器 IPC 查询；
+/// GNOME 等 Wayland 桌面不提供这类信息，返回 None
+pub fn get_active_app_info() -> Option<ActiveApp> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        return hyprland();
+    }
+    if std::env::var_os("SWAYSOCK").is_some() {
+        return sway();
+    }
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return None;
+    }
+    #[cfg(target_os = "linux")]
+    if std::env::var_os("DISPLAY").is_some() {
+        return x11()
+            .inspect_err(|err| tracing::debug!(error = %err, "读取活动窗口失败"))
+            .ok()
+            .flatten();
+    }
+    None
+}
+
+/// 截图触发时记下前台应用，此时截图界面还没有抢走焦点
+pub fn remember(capture_id: &CaptureId) {
+    let app = get_active_app_info();
+    tracing::debug!(%capture_id, app = ?app.as_ref().map(|a| &a.name), "截图时的前台应用");
+    record(capture_id, app);
+}
+
+fn record(capture_id: &CaptureId, app: Option<ActiveApp>) {
+    let Ok(mut apps) = BY_CAPTURE.lock() else {
+        return;
+    };
+    apps.retain(|(id, _)| id != capture_id);
+    if let Some(app) = app {
+        if apps.len() >= MAX_REMEMBERED {
+            apps.pop_front();
+        }
+        apps.push_back((capture_id.clone(), app));
+    }
+}
+
+/// 该次截图触发时的前台应用
+pub fn for_capture(capture_id: &CaptureId) -> Option<ActiveApp> {
+    let apps = BY_CAPTURE.lock().ok()?;
+    apps.iter()
+        .find(|(id, _)| id == capture_id)
+        .map(|(_, app)| app.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(class: &str) -> ActiveApp {
+        ActiveApp::new(class, Some(format!("{class} 窗口")), None).unwrap()
+    }
+
+    #[test]
+    fn names_are_sanitized_for_filenames() {
+        assert_eq!(sanitize("Firefox").as_deref(), Some("firefox"));
+        assert_eq!(sanitize("org.gnome.Nautilus").as_deref(), Some("nautilus"));
+        assert_eq!(sanitize("Google Chrome").as_deref(), Some("google_chrome"));
+        assert_eq!(sanitize("../etc/passwd").as_deref(), Some("etc_passwd"));
+        assert_eq!(sanitize("  ...  "), None);
+        assert_eq!(
+            sanitize(&"a".repeat(100)).map(|s| s.len()),
+            Some(MAX_NAME_CHARS)
+        );
+    }
+
+    #[test]
+    fn own_windows_are_not_recorded() {
+        assert!(ActiveApp::new("prinsp", None, Some(std::process::id())).is_none());
+    }
+
+    #[test]
+    fn apps_are_kept_per_capture() {
+        let (first, second) = (CaptureId::generate(), CaptureId::generate());
+        record(&first, Some(app("firefox")));
+        record(&second, Some(app("code")));
+        assert_eq!(for_capture(&first).unwrap().name, "firefox");
+        assert_eq!(for_capture(&second).unwrap().name, "code");
+
+        // 同一次截图重新记录时覆盖，未识别到应用时清除
+        record(&first, None);
+        assert!(for_capture(&first).is_none());
+        assert_eq!(for_capture(&second).unwrap().name, "code");
+    }
+
+    #[test]
+    fn only_recent_captures_are_kept() {
+        let ids: Vec<CaptureId> = (0..MAX_REMEMBERED * 2)
+            .map(|_| CaptureId::generate())
+            .collect();
+        for id in &ids {
+            record(id, Some(app("gimp")));
+        }
+        assert!(for_capture(&ids[0]).is_none());
+        assert!(for_capture(ids.last().unwrap()).is_some());
+        assert!(BY_CAPTURE.lock().unwrap().len() <= MAX_REMEMBERED);
+    }
+}
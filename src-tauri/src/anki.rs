@@ -0,0 +1,215 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsState;
+
+/// AnkiConnect 连接与字段映射
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnkiSettings {
+    pub port: u16,
+    pub deck: String,
+    pub model: String,
+    pub front_field: String,
+    pub back_field: String,
+    /// 放置截图的字段，可以与正反面字段相同（追加在文字后）
+    pub image_field: String,
+    /// 牌组不存在时自动创建
+    pub create_deck: bool,
+}
+
+impl Default for AnkiSettings {
+    fn default() -> Self {
+        Self {
+            port: 8765,
+            deck: "PrinSp".into(),
+            model: "Basic".into(),
+            front_field: "Front".into(),
+            back_field: "Back".into(),
+            image_field: "Back".into(),
+            create_deck: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AnkiError {
+    InvalidInput {
+        detail: String,
+    },
+    /// 无法连接 AnkiConnect，通常是 Anki 未启动或未安装插件
+    NotRunning {
+        port: u16,
+    },
+    DeckNotFound {
+        deck: String,
+    },
+    /// AnkiConnect 返回的错误信息
+    Api {
+        action: String,
+        message: String,
+    },
+}
+
+const ANKI_CONNECT_VERSION: u32 = 6;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct AnkiClient {
+    client: reqwest::Client,
+    port: u16,
+}
+
+impl AnkiClient {
+    fn new(client: reqwest::Client, port: u16) -> Self {
+        Self { client, port }
+    }
+
+    async fn send(&self, action: &str, params: &Value) -> Result<Value, reqwest::Error> {
+        self.client
+            .post(format!("http://127.0.0.1:{}", self.port))
+            .timeout(REQUEST_TIMEOUT)
+            .json(&json!({
+                "action": action,
+                "version": ANKI_CONNECT_VERSION,
+                "params": params,
+            }))
+            .send()
+            .await?
+            .json()
+            .await
+    }
+
+    /// 连接失败时等待片刻重试一次（Anki 刚启动时插件可能还没监听）
+    async fn call(&self, action: &str, params: Value) -> Result<Value, AnkiError> {
+        let response = match self.send(action, &params).await {
+            Ok(v) => v,
+            Err(err) if err.is_connect() || err.is_timeout() => {
+                sleep(RETRY_DELAY).await;
+                self.send(action, &params)
+                    .await
+                    .map_err(|_| AnkiError::NotRunning { port: self.port })?
+            }
+            Err(err) => {
+                return Err(AnkiError::Api {
+                    action: action.into(),
+                    message: err.to_string(),
+                })
+            }
+        };
+
+        if let Some(message) = response.get("error").and_then(Value::as_str) {
+            return Err(AnkiError::Api {
+                action: action.into(),
+                message: message.into(),
+            });
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn ensure_deck(&self, deck: &str, create: bool) -> Result<(), AnkiError> {
+        let decks = self.call("deckNames", json!({})).await?;
+        let exists = decks
+            .as_array()
+            .is_some_and(|names| names.iter().any(|n| n.as_str() == Some(deck)));
+        if exists {
+            return Ok(());
+        }
+        if !create {
+            return Err(AnkiError::DeckNotFound { deck: deck.into() });
+        }
+        self.call("createDeck", json!({ "deck": deck })).await?;
+        Ok(())
+    }
+}
+
+async fn sleep(duration: Duration) {
+    let _ = tauri::async_runtime::spawn_blocking(move || std::thread::sleep(duration)).await;
+}
+
+fn build_fields(
+    settings: &AnkiSettings,
+    front_text: &str,
+    back_text: &str,
+    image_name: &str,
+) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    fields.insert(settings.front_field.clone(), front_text.to_string());
+    fields
+        .entry(settings.back_field.clone())
+        .and_modify(|v: &mut String| {
+            v.push_str("<br>");
+            v.push_str(back_text);
+        })
+        .or_insert_with(|| back_text.to_string());
+    let img = format!("<img src=\"{image_name}\">");
+    fields
+        .entry(settings.image_field.clone())
+        .and_modify(|v| {
+            if !v.is_empty() {
+                v.push_str("<br>");
+            }
+            v.push_str(&img);
+        })
+        .or_insert(img);
+    fields
+}
+
+/// 通过 AnkiConnect 新建卡片：先上传截图到媒体库，再添加笔记，返回笔记 id
+#[tauri::command]
+pub async fn export_to_anki(
+    app: AppHandle,
+    base64_data: String,
+    front_text: String,
+    back_text: String,
+) -> Result<u64, AnkiError> {
+    let settings = app.state::<SettingsState>().get().anki;
+    let png = STANDARD
+        .decode(&base64_data)
+        .map_err(|e| AnkiError::InvalidInput {
+            detail: e.to_string(),
+        })?;
+    image::guess_format(&png).map_err(|e| AnkiError::InvalidInput {
+        detail: e.to_string(),
+    })?;
+    if front_text.trim().is_empty() {
+        return Err(AnkiError::InvalidInput {
+            detail: "正面内容不能为空".into(),
+        });
+    }
+
+    let anki = AnkiClient::new(crate::http::client(&app), settings.port);
+    anki.ensure_deck(&settings.deck, settings.create_deck)
+        .await?;
+
+    let image_name = format!("prinsp_{}.png", chrono::Local::now().timestamp_millis());
+    anki.call(
+        "storeMediaFile",
+        json!({ "filename": image_name, "data": base64_data }),
+    )
+    .await?;
+
+    let fields = build_fields(&settings, &front_text, &back_text, &image_name);
+    let note_id = anki
+        .call(
+            "addNote",
+            json!({
+                "note": {
+                    "deckName": settings.deck,
+                    "modelName": settings.model,
+                    "fields": fields,
+                    "tags": ["prinsp"],
+                }
+            }),
+        )
+        .await?;
+    note_id.as_u64().ok_or_else(|| AnkiError::Api {
+        action: "addNote".into(),
+        message: format!("意外的返回值: {note_id}"),
+    })
+}
@@ -0,0 +1,319 @@
+use serde::Serialize;
+use std::sync::Mutex;
+
+use crate::error::AppError;
+
+/// 第一次查询时检测，之后由设置变化的信号更新
+static CURRENT: Mutex<Option<SystemAppearance>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorScheme {
+    Dark,
+    Light,
+    /// 系统没有偏好或无法读取，前端自行决定
+    NoPreference,
+}
+
+/// 桌面的外观与无障碍偏好
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SystemAppearance {
+    pub color_scheme: ColorScheme,
+    /// 强调色，#rrggbb
+    pub accent_color: Option<String>,
+    /// 减少动画
+    pub reduced_motion: bool,
+    /// 读取来源：portal / gsettings / registry / none
+    pub source: &'static str,
+}
+
+impl Default for SystemAppearance {
+    fn default() -> Self {
+        Self {
+            color_scheme: ColorScheme::NoPreference,
+            accent_color: None,
+            reduced_motion: false,
+            source: "none",
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use super::{ColorScheme, SystemAppearance};
+    use crate::external::{run_external, ExternalTool, PROBE_TIMEOUT};
+
+    const APPEARANCE: &str = "org.freedesktop.appearance";
+    const GNOME_INTERFACE: &str = "org.gnome.desktop.interface";
+
+    fn hex(rgb: [f64; 3]) -> Option<String> {
+        if rgb.iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return None;
+        }
+        let [r, g, b] = rgb.map(|c| (c * 255.0).round() as u8);
+        Some(format!("#{r:02x}{g:02x}{b:02x}"))
+    }
+
+    fn gsettings(schema: &str, key: &str) -> Option<String> {
+        let output = run_external(
+            ExternalTool::Gsettings,
+            ["get", schema, key],
+            None,
+            PROBE_TIMEOUT,
+        )
+        .ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn settings_proxy(connection: &Connection) -> zbus::Result<Proxy<'_>> {
+        Proxy::new(
+            connection,
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+        )
+    }
+
+    /// 旧版门户只有 Read，返回值多包了一层 variant
+    fn unwrap_variant<'a>(value: &'a Value<'a>) -> &'a Value<'a> {
+        match value {
+            Value::Value(inner) => unwrap_variant(inner),
+            value => value,
+        }
+    }
+
+    fn read(proxy: &Proxy<'_>, namespace: &str, key: &str) -> Option<OwnedValue> {
+        proxy
+            .call::<_, _, OwnedValue>("ReadOne", &(namespace, key))
+            .or_else(|_| proxy.call::<_, _, OwnedValue>("Read", &(namespace, key)))
+            .ok()
+    }
+
+    fn read_u32(proxy: &Proxy<'_>, namespace: &str, key: &str) -> Option<u32> {
+        let value = read(proxy, namespace, key)?;
+        match unwrap_variant(&value) {
+            Value::U32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn read_bool(proxy: &Proxy<'_>, namespace: &str, key: &str) -> Option<bool> {
+        let value = read(proxy, namespace, key)?;
+        match unwrap_variant(&value) {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// accent-color 为 0–1 的 (r, g, b)，超出范围表示未设置
+    fn read_accent(proxy: &Proxy<'_>) -> Option<String> {
+        let value = read(proxy, APPEARANCE, "accent-color")?;
+        let Value::Structure(fields) = unwrap_variant(&value) else {
+            return None;
+        };
+        let rgb: Vec<f64> = fields
+            .fields()
+            .iter()
+            .filter_map(|field| match field {
+                Value::F64(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+        hex(rgb.try_into().ok()?)
+    }
+
+    pub fn from_portal() -> Option<SystemAppearance> {
+        let connection = Connection::session().ok()?;
+        let proxy = settings_proxy(&connection).ok()?;
+        let color_scheme = match read_u32(&proxy, APPEARANCE, "color-scheme")? {
+            1 => ColorScheme::Dark,
+            2 => ColorScheme::Light,
+            _ => ColorScheme::NoPreference,
+        };
+        // 新版门户提供 reduced-motion；GNOME 的门户后端还转发了 gsettings 中的动画开关
+        let reduced_motion = read_u32(&proxy, APPEARANCE, "reduced-motion")
+            .map(|value| value == 1)
+            .or_else(|| {
+                read_bool(&proxy, GNOME_INTERFACE, "enable-animations").map(|enabled| !enabled)
+            })
+            .unwrap_or(false);
+        Some(SystemAppearance {
+            color_scheme,
+            accent_color: read_accent(&proxy),
+            reduced_motion,
+            source: "portal",
+        })
+    }
+
+    /// GNOME 47 起的强调色名称，取 libadwaita 的色值
+    fn gnome_accent(name: &str) -> Option<&'static str> {
+        Some(match name {
+            "blue" => "#3584e4",
+            "teal" => "#2190a4",
+            "green" => "#3a944a",
+            "yellow" => "#c88800",
+            "orange" => "#ed5b00",
+            "red" => "#e62d42",
+            "pink" => "#d56199",
+            "purple" => "#9141ac",
+            "slate" => "#6f8396",
+            _ => return None,
+        })
+    }
+
+    pub fn from_gsettings() -> Option<SystemAppearance> {
+        let scheme = gsettings(GNOME_INTERFACE, "color-scheme")?;
+        let color_scheme = match scheme.trim_matches('\'') {
+            "prefer-dark" => ColorScheme::Dark,
+            "prefer-light" => ColorScheme::Light,
+            _ => ColorScheme::NoPreference,
+        };
+        Some(SystemAppearance {
+            color_scheme,
+            accent_color: gsettings(GNOME_INTERFACE, "accent-color")
+                .and_then(|name| gnome_accent(name.trim_matches('\'')))
+                .map(str::to_string),
+            reduced_motion: gsettings(GNOME_INTERFACE, "enable-animations")
+                .is_some_and(|value| value == "false"),
+            source: "gsettings",
+        })
+    }
+
+    /// 门户的 SettingChanged 信号，外观相关的设置变化时回调
+    pub fn watch(on_change: impl Fn()) -> zbus::Result<()> {
+        let connection = Connection::session()?;
+        let proxy = settings_proxy(&connection)?;
+        for message in proxy.receive_signal("SettingChanged")? {
+            let Ok((namespace, _key, _value)) =
+                message.body().deserialize::<(String, String, OwnedValue)>()
+            else {
+                continue;
+            };
+            if namespace == APPEARANCE || namespace == GNOME_INTERFACE {
+                on_change();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+fn reg_query(key: &str, name: &str) -> Option<String> {
+    use crate::external::{run_external, ExternalTool, PROBE_TIMEOUT};
+
+    let output = run_external(
+        ExternalTool::Reg,
+        ["query", key, "/v", name],
+        None,
+        PROBE_TIMEOUT,
+    )
+    .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// reg query 输出中某个 DWORD 值
+#[cfg(windows)]
+fn reg_dword(key: &str, name: &str) -> Option<u32> {
+    let stdout = reg_query(key, name)?;
+    let value = stdout
+        .lines()
+        .find(|line| line.trim_start().starts_with(name))?
+        .split_whitespace()
+        .last()?;
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+#[cfg(windows)]
+fn reg_string(key: &str, name: &str) -> Option<String> {
+    let stdout = reg_query(key, name)?;
+    stdout
+        .lines()
+        .find(|line| line.trim_start().starts_with(name))?
+        .split_whitespace()
+        .nth(2)
+        .map(str::to_string)
+}
+
+#[cfg(windows)]
+fn from_registry() -> Option<SystemAppearance> {
+    let light = reg_dword(
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+        "AppsUseLightTheme",
+    )?;
+    // AccentColor 按 0xAABBGGRR 存放
+    let accent_color =
+        reg_dword(r"HKCU\Software\Microsoft\Windows\DWM", "AccentColor").map(|abgr| {
+            let [r, g, b, _] = abgr.to_le_bytes();
+            format!("#{r:02x}{g:02x}{b:02x}")
+        });
+    Some(SystemAppearance {
+        color_scheme: if light == 0 {
+            ColorScheme::Dark
+        } else {
+            ColorScheme::Light
+        },
+        accent_color,
+        // MinAnimate 为 0 表示关闭了窗口动画
+        reduced_motion: reg_string(r"HKCU\Control Panel\Desktop\WindowMetrics", "MinAnimate")
+            .is_some_and(|value| value == "0"),
+        source: "registry",
+    })
+}
+
+fn detect() -> SystemAppearance {
+    #[cfg(target_os = "linux")]
+    let detected = linux::from_portal().or_else(linux::from_gsettings);
+    #[cfg(windows)]
+    let detected = from_registry();
+    #[cfg(not(any(target_os = "linux", windows)))]
+    let detected = None;
+    detected.unwrap_or_default()
+}
+
+fn current() -> SystemAppearance {
+    if let Some(cached) = CURRENT.lock().ok().and_then(|c| c.clone()) {
+        return cached;
+    }
+    let appearance = detect();
+    if let Ok(mut cached) = CURRENT.lock() {
+        *cached = Some(appearance.clone());
+    }
+    appearance
+}
+
+/// 监听门户的设置变化，外观改变时发出 system-appearance-changed 事件
+#[cfg(target_os = "linux")]
+pub fn watch(app: &tauri::AppHandle) {
+    use tauri::Emitter;
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let result = linux::watch(|| {
+            let appearance = detect();
+            let changed = CURRENT
+                .lock()
+                .map(|mut cached| {
+                    let changed = cached.as_ref() != Some(&appearance);
+                    *cached = Some(appearance.clone());
+                    changed
+                })
+                .unwrap_or(false);
+            if changed {
+                tracing::debug!(?appearance, "系统外观已变化");
+                let _ = app.emit("system-appearance-changed", &appearance);
+            }
+        });
+        if let Err(err) = result {
+            tracing::info!(error = %err, "无法监听系统外观变化");
+        }
+    });
+}
+
+/// 深浅色主题、强调色与减少动画偏好
+#[tauri::command]
+pub async fn get_system_appearance() -> Result<SystemAppearance, AppError> {
+    crate::run_blocking(|| Ok(current())).await
+}
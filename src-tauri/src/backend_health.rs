@@ -0,0 +1,232 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::capture::{self, CaptureBackend};
+use crate::error::AppError;
+use crate::external::ExternalTool;
+
+/// 连续失败这么多次后不再优先尝试该后端
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+static HEALTH: Mutex<Health> = Mutex::new(Health {
+    session: None,
+    failures: BTreeMap::new(),
+    demoted: BTreeMap::new(),
+});
+
+/// 后端被降级的原因
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DemotionReason {
+    /// 截图程序已不存在
+    MissingBinary { program: &'static str },
+    /// 不支持当前的会话类型（如 Wayland 下的 xcap）
+    SessionMismatch { session: String },
+    /// 运行中连续失败，error 为最后一次的错误代码
+    ConsecutiveFailures { count: u32, error: &'static str },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Demotion {
+    pub reason: DemotionReason,
+    /// Unix 时间（秒）
+    pub at: i64,
+}
+
+impl Demotion {
+    fn now(reason: DemotionReason) -> Self {
+        Self {
+            reason,
+            at: chrono::Local::now().timestamp(),
+        }
+    }
+}
+
+/// 键为后端名称；降级的后端排在尝试顺序的最后，其他后端都失败时仍会尝试，成功后恢复
+struct Health {
+    /// 上次检查时的会话类型
+    session: Option<String>,
+    failures: BTreeMap<&'static str, u32>,
+    demoted: BTreeMap<&'static str, Demotion>,
+}
+
+/// 当前的会话类型（wayland、x11 等），未知时为 unknown
+pub fn session_type() -> String {
+    match std::env::var("XDG_SESSION_TYPE") {
+        Ok(value) if !value.is_empty() => value,
+        _ if std::env::var("WAYLAND_DISPLAY").is_ok() => "wayland".into(),
+        _ if std::env::var("DISPLAY").is_ok() => "x11".into(),
+        _ => "unknown".into(),
+    }
+}
+
+/// 后端不适用于当前环境的原因；会话类型未知时不按会话判断
+fn unsuitable(backend: CaptureBackend, session: &str) -> Option<DemotionReason> {
+    let mismatch = || DemotionReason::SessionMismatch {
+        session: session.to_string(),
+    };
+    match backend {
+        CaptureBackend::Grim if !ExternalTool::Grim.available() => {
+            Some(DemotionReason::MissingBinary { program: "grim" })
+        }
+        CaptureBackend::Grim if session == "x11" => Some(mismatch()),
+        // Wayland 下只能通过 XWayland 截到 X 客户端，且常常要等到超时
+        CaptureBackend::Xcap if session == "wayland" => Some(mismatch()),
+        CaptureBackend::GnomeScreenshot if !ExternalTool::GnomeScreenshot.available() => {
+            Some(DemotionReason::MissingBinary {
+                program: "gnome-screenshot",
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn is_demoted(backend: CaptureBackend) -> bool {
+    HEALTH
+        .lock()
+        .is_ok_and(|health| health.demoted.contains_key(backend.name()))
+}
+
+/// 上次成功的后端已被降级时清除，重新按环境选择
+fn clear_demoted_preference() {
+    if let Some(preferred) = capture::get_preferred_backend().filter(|b| is_demoted(*b)) {
+        tracing::info!(backend = preferred.name(), "清除已降级的首选截图后端");
+        capture::clear_preferred_backend();
+        capture::preselect_backend();
+    }
+}
+
+/// 按当前会话检查各后端，不适用的降级；启动时和会话类型变化后调用。
+/// 运行中连续失败造成的降级保留到该后端再次成功
+pub fn validate() {
+    let session = session_type();
+    let Ok(mut health) = HEALTH.lock() else {
+        return;
+    };
+    health.session = Some(session.clone());
+    health.demoted.retain(|_, demotion| {
+        matches!(demotion.reason, DemotionReason::ConsecutiveFailures { .. })
+    });
+    for backend in CaptureBackend::ALL {
+        if let Some(reason) = unsuitable(backend, &session) {
+            tracing::info!(
+                backend = backend.name(),
+                session = %session,
+                ?reason,
+                "截图后端不适用于当前环境，已降级"
+            );
+            health.demoted.insert(backend.name(), Demotion::now(reason));
+        }
+    }
+    drop(health);
+
+    if let Some(configured) = capture::capture_config().backend.filter(|b| is_demoted(*b)) {
+        tracing::warn!(
+            backend = configured.name(),
+            "设置中指定的截图后端不适用于当前环境，暂不优先尝试"
+        );
+    }
+    clear_demoted_preference();
+}
+
+/// 每次截图前调用，会话类型与上次检查时不同（或从未检查过）时重新检查
+pub fn check_session() {
+    let session = session_type();
+    let changed = HEALTH
+        .lock()
+        .is_ok_and(|health| health.session.as_deref() != Some(session.as_str()));
+    if changed {
+        tracing::info!(session = %session, "会话类型已变化，重新检查截图后端");
+        validate();
+    }
+}
+
+pub fn record_success(backend: CaptureBackend) {
+    let Ok(mut health) = HEALTH.lock() else {
+        return;
+    };
+    health.failures.remove(backend.name());
+    if health.demoted.remove(backend.name()).is_some() {
+        tracing::info!(backend = backend.name(), "截图后端恢复正常，取消降级");
+    }
+}
+
+pub fn record_failure(backend: CaptureBackend, err: &AppError) {
+    let Ok(mut health) = HEALTH.lock() else {
+        return;
+    };
+    let count = {
+        let count = health.failures.entry(backend.name()).or_default();
+        *count += 1;
+        *count
+    };
+    if count < MAX_CONSECUTIVE_FAILURES || health.demoted.contains_key(backend.name()) {
+        return;
+    }
+    tracing::warn!(
+        backend = backend.name(),
+        count,
+        error = err.code(),
+        "截图后端连续失败，已降级"
+    );
+    health.demoted.insert(
+        backend.name(),
+        Demotion::now(DemotionReason::ConsecutiveFailures {
+            count,
+            error: err.code(),
+        }),
+    );
+    drop(health);
+    clear_demoted_preference();
+}
+
+#[derive(Serialize)]
+pub struct BackendHealth {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub consecutive_failures: u32,
+    /// 被降级时的原因
+    pub demoted: Option<Demotion>,
+}
+
+#[derive(Serialize)]
+pub struct CaptureBackendState {
+    /// 设置中指定优先使用的后端
+    pub configured: Option<&'static str>,
+    /// 上次成功、下次优先尝试的后端
+    pub preferred: Option<&'static str>,
+    pub session_type: String,
+    /// 已启用的按下次截图的尝试顺序在前，禁用的排在后面
+    pub backends: Vec<BackendHealth>,
+}
+
+/// 当前的首选截图后端及各后端的状态，设置页据此显示后端为何被降级
+#[cfg_attr(feature = "tauri", tauri::command)]
+pub fn get_capture_backend() -> Result<CaptureBackendState, AppError> {
+    let config = capture::capture_config();
+    let order = capture::backend_order(&config);
+    let health = HEALTH
+        .lock()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let disabled = CaptureBackend::ALL
+        .into_iter()
+        .filter(|backend| !order.contains(backend));
+    let backends = order
+        .iter()
+        .map(|backend| (*backend, true))
+        .chain(disabled.map(|backend| (backend, false)))
+        .map(|(backend, enabled)| BackendHealth {
+            name: backend.name(),
+            enabled,
+            consecutive_failures: health.failures.get(backend.name()).copied().unwrap_or(0),
+            demoted: health.demoted.get(backend.name()).cloned(),
+        })
+        .collect();
+    Ok(CaptureBackendState {
+        configured: config.backend.map(CaptureBackend::name),
+        preferred: capture::get_preferred_backend().map(CaptureBackend::name),
+        session_type: session_type(),
+        backends,
+    })
+}
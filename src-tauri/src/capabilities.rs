@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter};
+
+use crate::capture::CaptureFeatures;
+
+/// 第一次查询时探测，之后复用；refresh 时重新探测
+static CACHE: RwLock<Option<Capabilities>> = RwLock::new(None);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BackendCapabilities {
+    pub name: String,
+    /// 是否在设置中启用
+    pub enabled: bool,
+    pub available: bool,
+    #[serde(flatten)]
+    pub features: CaptureFeatures,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OcrCapabilities {
+    /// 已安装 tesseract 且版本满足要求
+    pub available: bool,
+    pub version: Option<String>,
+    pub languages: Vec<String>,
+    /// 已安装方向与文字检测（osd）数据
+    pub osd: bool,
+    /// 已安装的竖排模型，如 chi_sim_vert
+    pub vertical: Vec<String>,
+}
+
+/// 剪贴板支持写入的格式，由 clipboard::capabilities 登记
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ClipboardCapabilities {
+    /// 能连接到剪贴板
+    pub available: bool,
+    pub text: bool,
+    pub image: bool,
+    pub html: bool,
+    /// text/uri-list（复制文件）
+    pub uri_list: bool,
+    /// X11 的 PRIMARY 选区
+    pub primary: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SoundCapabilities {
+    /// 有默认音频输出设备，可播放提示音
+    pub available: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PlatformFacts {
+    pub os: &'static str,
+    /// wayland / x11 / unknown
+    pub session_type: String,
+    pub desktop: Option<String>,
+    /// 会话总线上有 xdg-desktop-portal
+    pub portal: bool,
+}
+
+/// 当前系统上各功能能否使用，前端据此隐藏或禁用不可用的按钮
+#[derive(Clone, Debug, Serialize)]
+pub struct Capabilities {
+    /// 按截图时的尝试顺序排列，禁用的后端排在后面
+    pub capture: Vec<BackendCapabilities>,
+    pub ocr: OcrCapabilities,
+    pub clipboard: ClipboardCapabilities,
+    pub sound: SoundCapabilities,
+    pub platform: PlatformFacts,
+}
+
+fn capture() -> Vec<BackendCapabilities> {
+    crate::diagnostics::probe_backends()
+        .into_iter()
+        .filter_map(|probe| {
+            let backend = crate::capture::CaptureBackend::from_name(&probe.name)?;
+            Some(BackendCapabilities {
+                name: probe.name,
+                enabled: probe.enabled,
+                available: probe.available,
+                features: backend.features(),
+            })
+        })
+        .collect()
+}
+
+fn ocr() -> OcrCapabilities {
+    let info = crate::tesseract::ensure().ok();
+    let languages = if info.is_some() {
+        crate::tesseract::languages()
+    } else {
+        Vec::new()
+    };
+    OcrCapabilities {
+        available: info.is_some(),
+        version: info.map(|info| info.version_line),
+        osd: languages.iter().any(|l| l == "osd"),
+        vertical: languages
+            .iter()
+            .filter(|l| l.ends_with("_vert"))
+            .cloned()
+            .collect(),
+        languages,
+    }
+}
+
+fn platform() -> PlatformFacts {
+    PlatformFacts {
+        os: std::env::consts::OS,
+        session_type: crate::backend_health::session_type(),
+        desktop: std::env::var("XDG_CURRENT_DESKTOP").ok(),
+        portal: crate::share::portal_available(),
+    }
+}
+
+/// 汇总各功能登记的能力，新增功能时在这里加上对应的探测
+fn collect() -> Capabilities {
+    Capabilities {
+        capture: capture(),
+        ocr: ocr(),
+        clipboard: crate::clipboard::capabilities(),
+        sound: SoundCapabilities {
+            available: crate::sound::available(),
+        },
+        platform: platform(),
+    }
+}
+
+/// 缓存的能力矩阵；refresh 为 true 时重新检测 tesseract 并重新探测
+fn get(refresh: bool) -> Capabilities {
+    if !refresh {
+        if let Some(cached) = CACHE.read().ok().and_then(|c| c.clone()) {
+            return cached;
+        }
+    } else {
+        crate::tesseract::reset();
+        crate::sound::reset();
+    }
+    let capabilities = collect();
+    if let Ok(mut cache) = CACHE.write() {
+        *cache = Some(capabilities.clone());
+    }
+    capabilities
+}
+
+/// 前端启动时获取一次并缓存，安装语言包、截图工具等之后传 refresh 重新探测；
+/// 重新探测的结果通过 capabilities-changed 事件通知所有窗口
+#[tauri::command]
+pub async fn get_capabilities(
+    app: AppHandle,
+    refresh: Option<bool>,
+) -> Result<Capabilities, String> {
+    let refresh = refresh.unwrap_or(false);
+    let capabilities = tauri::async_runtime::spawn_blocking(move || get(refresh))
+        .await
+        .map_err(|e| e.to_string())?;
+    if refresh {
+        let _ = app.emit("capabilities-changed", &capabilities);
+    }
+    Ok(capabilities)
+}
@@ -0,0 +1,463 @@
+use serde::Serialize;
+use std::sync::{mpsc, Mutex, OnceLock, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use xcap::Monitor;
+
+use crate::backend_health;
+use crate::color;
+use crate::error::AppError;
+use crate::external::{self, ExternalError, ExternalTool};
+use crate::frame::Frame;
+use crate::night_light;
+use crate::timings::{self, Timings};
+
+/// 截图后端
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureBackend {
+    Grim,
+    Xcap,
+    GnomeScreenshot,
+}
+
+impl CaptureBackend {
+    /// 默认的尝试顺序
+    pub const ALL: [CaptureBackend; 3] = [
+        CaptureBackend::Grim,
+        CaptureBackend::Xcap,
+        CaptureBackend::GnomeScreenshot,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CaptureBackend::Grim => "grim",
+            CaptureBackend::Xcap => "xcap",
+            CaptureBackend::GnomeScreenshot => "gnome-screenshot",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "grim" => Some(CaptureBackend::Grim),
+            "xcap" => Some(CaptureBackend::Xcap),
+            "gnome-screenshot" => Some(CaptureBackend::GnomeScreenshot),
+            _ => None,
+        }
+    }
+
+    /// 当前实现下该后端支持的能力，能力矩阵由此生成，修改截图方式时同步更新
+    pub(crate) fn features(self) -> CaptureFeatures {
+        // 选区都是整屏截图后裁剪，指定显示器时直接用 xcap 截取该显示器
+        let output_selection = self == CaptureBackend::Xcap;
+        CaptureFeatures {
+            region: true,
+            cursor: false,
+            output_selection,
+        }
+    }
+
+    pub(crate) fn failed(self, detail: impl ToString) -> AppError {
+        AppError::BackendFailed {
+            backend: self.name().to_string(),
+            detail: detail.to_string(),
+        }
+    }
+}
+
+/// 截图后端在当前实现下支持的能力，由 CaptureBackend::features 登记
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct CaptureFeatures {
+    /// 可只截取选区
+    pub region: bool,
+    /// 截图中可包含鼠标指针
+    pub cursor: bool,
+    /// 可指定截取哪个显示器
+    pub output_selection: bool,
+}
+
+static PREFERRED_BACKEND: OnceLock<Mutex<Option<CaptureBackend>>> = OnceLock::new();
+
+fn preferred_backend_state() -> &'static Mutex<Option<CaptureBackend>> {
+    PREFERRED_BACKEND.get_or_init(|| Mutex::new(None))
+}
+
+pub(crate) fn set_preferred_backend(backend: CaptureBackend) {
+    if let Ok(mut guard) = preferred_backend_state().lock() {
+        *guard = Some(backend);
+    }
+}
+
+pub(crate) fn clear_preferred_backend() {
+    if let Ok(mut guard) = preferred_backend_state().lock() {
+        *guard = None;
+    }
+}
+
+pub(crate) fn get_preferred_backend() -> Option<CaptureBackend> {
+    preferred_backend_state()
+        .lock()
+        .ok()
+        .and_then(|guard| *guard)
+}
+
+/// 设置中的截图配置，设置变化时更新；命令行模式下使用默认值
+#[derive(Clone)]
+pub(crate) struct CaptureConfig {
+    pub backend: Option<CaptureBackend>,
+    /// 按优先级排列的已启用后端，None 时按默认顺序全部启用
+    pub enabled: Option<Vec<CaptureBackend>>,
+    pub grim_timeout: Duration,
+    pub xcap_timeout: Duration,
+    /// 按截图自带的 ICC 配置转换为 sRGB
+    pub srgb_normalize: bool,
+    pub night_light: night_light::NightLightSettings,
+}
+
+impl CaptureConfig {
+    pub fn enabled_backends(&self) -> Vec<CaptureBackend> {
+        self.enabled
+            .clone()
+            .unwrap_or_else(|| CaptureBackend::ALL.to_vec())
+    }
+}
+
+static CAPTURE_CONFIG: RwLock<CaptureConfig> = RwLock::new(CaptureConfig {
+    backend: None,
+    enabled: None,
+    grim_timeout: Duration::from_millis(500),
+    xcap_timeout: Duration::from_millis(1500),
+    srgb_normalize: true,
+    night_light: night_light::NightLightSettings {
+        compensate: false,
+        temperature_k: None,
+    },
+});
+
+pub(crate) fn capture_config() -> CaptureConfig {
+    CAPTURE_CONFIG
+        .read()
+        .map(|c| c.clone())
+        .unwrap_or_else(|e| e.into_inner().clone())
+}
+
+pub(crate) fn set_capture_config(config: CaptureConfig) {
+    if let Ok(mut current) = CAPTURE_CONFIG.write() {
+        *current = config;
+    }
+}
+
+pub(crate) fn preselect_backend() {
+    // 简单的环境探测，避免第一次截图时走到不可用后端造成长时间阻塞
+    if get_preferred_backend().is_some() {
+        return;
+    }
+
+    let is_wayland = std::env::var("WAYLAND_DISPLAY").is_ok();
+    if is_wayland && ExternalTool::Grim.available() {
+        set_preferred_backend(CaptureBackend::Grim);
+        return;
+    }
+
+    // Wayland 会话通常也有 XWayland 的 DISPLAY，xcap 被降级时不选
+    if std::env::var("DISPLAY").is_ok() && !backend_health::is_demoted(CaptureBackend::Xcap) {
+        set_preferred_backend(CaptureBackend::Xcap);
+    }
+}
+
+/// 一次成功的截图及所用的后端
+pub struct CaptureResult {
+    pub frame: Frame,
+    pub backend: CaptureBackend,
+    /// 截图做过的夜灯补偿
+    pub night_light: Option<night_light::Compensation>,
+}
+
+/// 截图过程的观察者，每个后端尝试一次调用一次；界面据此发出事件，
+/// 使用统计和错误记录也由调用方在这里完成，不需要时传入 &()
+pub trait CaptureObserver {
+    fn attempt(&self, backend: CaptureBackend, duration_ms: u64, error: Option<&AppError>);
+}
+
+impl CaptureObserver for () {
+    fn attempt(&self, _backend: CaptureBackend, _duration_ms: u64, _error: Option<&AppError>) {}
+}
+
+/// 不依赖窗口的截图入口，命令行模式和快速截图复用；指定显示器时使用 xcap
+pub fn capture(
+    monitor: Option<u32>,
+    observer: &dyn CaptureObserver,
+) -> Result<CaptureResult, AppError> {
+    let mut timings = Timings::default();
+    let result = match monitor {
+        None => grab_screen(&mut timings, observer),
+        Some(id) => capture_monitor(id, &mut timings, observer),
+    }?;
+    timings::finish(timings::Kind::Capture, &timings);
+    Ok(result)
+}
+
+fn capture_monitor(
+    id: u32,
+    timings: &mut Timings,
+    observer: &dyn CaptureObserver,
+) -> Result<CaptureResult, AppError> {
+    let failed = |e: xcap::XCapError| CaptureBackend::Xcap.failed(e);
+    let monitor = Monitor::all()
+        .map_err(failed)?
+        .into_iter()
+        .find(|m| m.id().ok() == Some(id))
+        .ok_or_else(|| AppError::invalid(format!("未找到显示器: {id}")))?;
+    let started = Instant::now();
+    let result = monitor.capture_image().map_err(failed);
+    timings.record("capture.xcap", started);
+    let duration_ms = started.elapsed().as_millis() as u64;
+    observer.attempt(CaptureBackend::Xcap, duration_ms, result.as_ref().err());
+    let image = result?;
+    let (frame, night_light) =
+        night_light::compensate(Frame::Image(image.into()), &capture_config().night_light)?;
+    Ok(CaptureResult {
+        frame,
+        backend: CaptureBackend::Xcap,
+        night_light,
+    })
+}
+
+/// 下次截图时各后端的尝试顺序
+pub(crate) fn backend_order(config: &CaptureConfig) -> Vec<CaptureBackend> {
+    let mut order = Vec::new();
+    let enabled = config.enabled_backends();
+
+    // 设置中指定的后端优先，其次是上次成功的后端，都必须是已启用的
+    for backend in [config.backend, get_preferred_backend()]
+        .into_iter()
+        .flatten()
+    {
+        if enabled.contains(&backend) && !order.contains(&backend) {
+            order.push(backend);
+        }
+    }
+
+    for backend in enabled {
+        if !order.contains(&backend) {
+            order.push(backend);
+        }
+    }
+
+    // 降级的后端排在最后，其他后端都失败时仍会尝试
+    order.sort_by_key(|backend| backend_health::is_demoted(*backend));
+    order
+}
+
+/// 按配置的顺序尝试各截图后端，每次尝试都通知 observer
+pub(crate) fn grab_screen(
+    timings: &mut Timings,
+    observer: &dyn CaptureObserver,
+) -> Result<CaptureResult, AppError> {
+    backend_health::check_session();
+    let mut last_err = AppError::NoDisplay;
+    let config = capture_config();
+
+    for backend in backend_order(&config) {
+        let started = Instant::now();
+        let result = match backend {
+            CaptureBackend::Grim => capture_with_grim(config.grim_timeout),
+            CaptureBackend::Xcap => {
+                capture_with_timeout(backend, config.xcap_timeout, capture_with_xcap)
+            }
+            CaptureBackend::GnomeScreenshot => capture_with_gnome_screenshot(),
+        };
+
+        let duration_ms = started.elapsed().as_millis() as u64;
+        timings.record(format!("capture.{}", backend.name()), started);
+        observer.attempt(backend, duration_ms, result.as_ref().err());
+
+        match result {
+            Ok(data) => {
+                tracing::info!(backend = backend.name(), duration_ms, "截图成功");
+                backend_health::record_success(backend);
+                set_preferred_backend(backend);
+                let data = if config.srgb_normalize {
+                    timings.time("color", || color::normalize(data))?
+                } else {
+                    data
+                };
+                let (frame, night_light) = timings.time("night_light", || {
+                    night_light::compensate(data, &config.night_light)
+                })?;
+                return Ok(CaptureResult {
+                    frame,
+                    backend,
+                    night_light,
+                });
+            }
+            Err(err) => {
+                tracing::warn!(
+                    backend = backend.name(),
+                    duration_ms,
+                    error = err.code(),
+                    detail = %err,
+                    "截图后端失败"
+                );
+                backend_health::record_failure(backend, &err);
+                last_err = err;
+            }
+        }
+    }
+
+    Err(last_err)
+}
+
+fn capture_with_timeout<F>(
+    backend: CaptureBackend,
+    timeout: Duration,
+    capture: F,
+) -> Result<Frame, AppError>
+where
+    F: FnOnce() -> Result<Frame, AppError> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(capture());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(res) => res,
+        Err(_) => Err(AppError::Timeout {
+            what: backend.name().to_string(),
+            ms: timeout.as_millis() as u64,
+        }),
+    }
+}
+
+/// 直接返回像素，需要 PNG 时再编码
+fn capture_with_xcap() -> Result<Frame, AppError> {
+    let failed = |e: xcap::XCapError| CaptureBackend::Xcap.failed(e);
+    let monitors = Monitor::all().map_err(failed)?;
+    let monitor = monitors.into_iter().next().ok_or(AppError::NoDisplay)?;
+    let image = monitor.capture_image().map_err(failed)?;
+    Ok(Frame::Image(image.into()))
+}
+
+fn capture_with_grim(timeout: Duration) -> Result<Frame, AppError> {
+    match external::run_external(ExternalTool::Grim, ["-"], None, timeout) {
+        Ok(output) => Ok(Frame::Png(output.stdout)),
+        Err(ExternalError::Timeout { timeout, .. }) => Err(AppError::Timeout {
+            what: CaptureBackend::Grim.name().to_string(),
+            ms: timeout.as_millis() as u64,
+        }),
+        Err(err) => Err(CaptureBackend::Grim.failed(err)),
+    }
+}
+
+pub(crate) const GNOME_SCREENSHOT_TMP: &str = "/tmp/prinsp_screenshot.png";
+/// gnome-screenshot 写完文件的最长等待时间
+const GNOME_SCREENSHOT_TIMEOUT: Duration = Duration::from_millis(1500);
+
+fn capture_with_gnome_screenshot() -> Result<Frame, AppError> {
+    let failed = |e: String| CaptureBackend::GnomeScreenshot.failed(e);
+    let tmp_file = GNOME_SCREENSHOT_TMP;
+    let _ = std::fs::remove_file(tmp_file);
+
+    external::run_external(
+        ExternalTool::GnomeScreenshot,
+        ["-f", tmp_file],
+        None,
+        GNOME_SCREENSHOT_TIMEOUT,
+    )
+    .map_err(|e| failed(e.to_string()))?;
+
+    let data = std::fs::read(tmp_file).map_err(|e| failed(format!("read file: {e}")))?;
+    let _ = std::fs::remove_file(tmp_file);
+
+    Ok(Frame::Png(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模拟耗时 delay 的截图后端
+    fn slow_capture(delay: Duration) -> impl FnOnce() -> Result<Frame, AppError> + Send {
+        move || {
+            thread::sleep(delay);
+            Ok(Frame::Png(Vec::new()))
+        }
+    }
+
+    fn capture_with_configured_timeout(delay: Duration) -> Result<Frame, AppError> {
+        let timeout = capture_config().xcap_timeout;
+        capture_with_timeout(CaptureBackend::Xcap, timeout, slow_capture(delay))
+    }
+
+    fn config(backend: Option<CaptureBackend>, enabled: &[CaptureBackend]) -> CaptureConfig {
+        CaptureConfig {
+            backend,
+            enabled: Some(enabled.to_vec()),
+            ..capture_config()
+        }
+    }
+
+    #[test]
+    fn backend_names_round_trip() {
+        for backend in CaptureBackend::ALL {
+            assert_eq!(CaptureBackend::from_name(backend.name()), Some(backend));
+        }
+        assert_eq!(CaptureBackend::from_name("scrot"), None);
+    }
+
+    #[test]
+    fn backend_order_puts_configured_and_preferred_first() {
+        use CaptureBackend::*;
+        clear_preferred_backend();
+        let all = [Grim, Xcap, GnomeScreenshot];
+        assert_eq!(backend_order(&config(None, &all)), all);
+        assert_eq!(
+            backend_order(&config(Some(GnomeScreenshot), &all)),
+            [GnomeScreenshot, Grim, Xcap]
+        );
+
+        // 上次成功的后端排在设置指定的之后
+        set_preferred_backend(Xcap);
+        assert_eq!(
+            backend_order(&config(Some(GnomeScreenshot), &all)),
+            [GnomeScreenshot, Xcap, Grim]
+        );
+        // 未启用的后端即使被指定也不尝试
+        assert_eq!(
+            backend_order(&config(Some(Grim), &[GnomeScreenshot])),
+            [GnomeScreenshot]
+        );
+        clear_preferred_backend();
+    }
+
+    #[test]
+    fn timeout_passes_results_and_errors_through() {
+        let frame = capture_with_timeout(CaptureBackend::Grim, Duration::from_secs(2), || {
+            Ok(Frame::Png(vec![1, 2, 3]))
+        });
+        assert!(matches!(frame, Ok(Frame::Png(png)) if png == [1, 2, 3]));
+        let err = capture_with_timeout(CaptureBackend::Grim, Duration::from_secs(2), || {
+            Err(CaptureBackend::Grim.failed("exit 1"))
+        });
+        assert!(matches!(err, Err(AppError::BackendFailed { backend, .. }) if backend == "grim"));
+    }
+
+    #[test]
+    fn updated_timeout_applies_to_the_next_capture() {
+        let with_timeout = |ms| CaptureConfig {
+            xcap_timeout: Duration::from_millis(ms),
+            ..capture_config()
+        };
+        set_capture_config(with_timeout(50));
+        match capture_with_configured_timeout(Duration::from_millis(400)) {
+            Err(AppError::Timeout { what, ms }) => {
+                assert_eq!(what, "xcap");
+                assert_eq!(ms, 50);
+            }
+            _ => panic!("50ms 预算下应超时"),
+        }
+
+        set_capture_config(with_timeout(2000));
+        assert!(capture_with_configured_timeout(Duration::from_millis(100)).is_ok());
+    }
+}
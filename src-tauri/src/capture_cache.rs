@@ -0,0 +1,488 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{Manager, Runtime, UriSchemeContext};
+
+use crate::capture_id::CaptureId;
+use crate::edits::{EditHistory, EditOp, EditStack, EditState, MAX_EDITS};
+use crate::error::AppError;
+use crate::frame::{self, Frame};
+use crate::night_light::Compensation;
+use crate::pre_encode::{self, Prepared, Variant, Variants};
+use crate::timings::Timings;
+
+/// 截图预览协议，prinsp://capture/<id>
+pub const SCHEME: &str = "prinsp";
+/// 截图在缓存中保留的最长时间
+const TTL: Duration = Duration::from_secs(600);
+
+struct Entry {
+    /// 原图；编辑步数超出上限后为合并了最早几步的图
+    png: Vec<u8>,
+    /// 解码后的截图，后端内部识别时直接使用，grim 等后端的截图在第一次用到时解码
+    image: Option<Arc<DynamicImage>>,
+    created: Instant,
+    edits: EditStack,
+    capture_id: CaptureId,
+    /// 截图时做过的夜灯补偿
+    night_light: Option<Compensation>,
+    /// 后台为当前状态准备的编码结果，随截图一起过期
+    variants: Variants,
+}
+
+/// 最近截图的 PNG，由 prinsp:// 协议直接提供给 WebView，不经过 IPC 和 JS 内存
+#[derive(Default)]
+pub struct CaptureCache {
+    entries: Mutex<HashMap<u64, Entry>>,
+    next_id: AtomicU64,
+}
+
+/// 截图命令的返回值，前端把 preview_url 作为图片地址
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptureHandle {
+    /// 缓存编号，用于 prinsp:// 预览地址
+    pub id: u64,
+    /// 截图操作的编号，与触发、截图和识别的事件中的一致
+    pub capture_id: CaptureId,
+    pub width: u32,
+    pub height: u32,
+    pub preview_url: String,
+    /// 请求耗时（with_timings）时附带各阶段耗时
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Timings>,
+    /// 截图已按夜灯色温补偿，像素与屏幕显示的不同
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_light: Option<Compensation>,
+}
+
+/// 后续命令指定截图的方式：缓存编号，或截图操作的编号
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum CaptureRef {
+    Cache(u64),
+    Capture(CaptureId),
+}
+
+/// Windows 和 Android 上自定义协议要写成 http://<协议>.<主机>/；
+/// 编辑后的状态带上状态编号，每个地址的内容不变
+fn preview_url(id: u64, state: u64) -> String {
+    let base = if cfg!(any(windows, target_os = "android")) {
+        format!("http://{SCHEME}.capture/{id}")
+    } else {
+        format!("{SCHEME}://capture/{id}")
+    };
+    match state {
+        0 => base,
+        state => format!("{base}?state={state}"),
+    }
+}
+
+/// 只读取 PNG 头部得到尺寸
+fn png_dimensions(png: &[u8]) -> Result<(u32, u32), AppError> {
+    image::ImageReader::with_format(Cursor::new(png), image::ImageFormat::Png)
+        .into_dimensions()
+        .map_err(AppError::invalid)
+}
+
+impl CaptureCache {
+    /// 开始新的截图时丢弃之前的截图
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    pub fn insert(
+        &self,
+        frame: Frame,
+        capture_id: CaptureId,
+        night_light: Option<Compensation>,
+    ) -> Result<CaptureHandle, AppError> {
+        match frame {
+            Frame::Png(png) => self.store(png, None, capture_id, night_light),
+            Frame::Image(image) => self.insert_shared(Arc::new(image), capture_id, night_light),
+        }
+    }
+
+    /// 放入已解码的截图，与识别任务共用同一份像素
+    pub fn insert_shared(
+        &self,
+        image: Arc<DynamicImage>,
+        capture_id: CaptureId,
+        night_light: Option<Compensation>,
+    ) -> Result<CaptureHandle, AppError> {
+        self.store(
+            frame::encode_png(&image)?,
+            Some(image),
+            capture_id,
+            night_light,
+        )
+    }
+
+    fn store(
+        &self,
+        png: Vec<u8>,
+        image: Option<Arc<DynamicImage>>,
+        capture_id: CaptureId,
+        night_light: Option<Compensation>,
+    ) -> Result<CaptureHandle, AppError> {
+        let (width, height) = match &image {
+            Some(image) => (image.width(), image.height()),
+            None => png_dimensions(&png)?,
+        };
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|e| AppError::invalid(e.to_string()))?;
+        entries.retain(|_, entry| entry.created.elapsed() < TTL);
+        entries.insert(
+            id,
+            Entry {
+                png,
+                image,
+                created: Instant::now(),
+                edits: EditStack::default(),
+                capture_id: capture_id.clone(),
+                night_light,
+                variants: Variants::default(),
+            },
+        );
+        Ok(CaptureHandle {
+            id,
+            capture_id,
+            width,
+            height,
+            preview_url: preview_url(id, 0),
+            timings: None,
+            night_light,
+        })
+    }
+
+    /// 按缓存编号或截图编号找到缓存中的截图
+    pub fn resolve(&self, capture: &CaptureRef) -> Result<u64, AppError> {
+        match capture {
+            CaptureRef::Cache(id) => Ok(*id),
+            CaptureRef::Capture(capture_id) => self
+                .entries
+                .lock()
+                .map_err(|e| AppError::Internal(e.to_string()))?
+                .iter()
+                .find(|(_, entry)| &entry.capture_id == capture_id)
+                .map(|(id, _)| *id)
+                .ok_or_else(|| AppError::invalid(format!("截图不存在或已过期: {capture_id}"))),
+        }
+    }
+
+    /// 该次截图做过的夜灯补偿；多显示器时各截图的补偿相同
+    pub fn night_light(&self, capture_id: &CaptureId) -> Option<Compensation> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .values()
+            .filter(|entry| &entry.capture_id == capture_id)
+            .find_map(|entry| entry.night_light)
+    }
+
+    /// 截图操作的编号
+    pub fn capture_id(&self, id: u64) -> Option<CaptureId> {
+        let entries = self.entries.lock().ok()?;
+        entries.get(&id).map(|entry| entry.capture_id.clone())
+    }
+
+    /// 当前状态（编辑后）的 PNG
+    pub fn get(&self, id: u64) -> Option<Vec<u8>> {
+        self.state_png(id, None)
+    }
+
+    /// 指定编号状态的 PNG，None 为当前状态；原图直接返回，编辑后的状态按需计算并编码
+    fn state_png(&self, id: u64, state: Option<u64>) -> Option<Vec<u8>> {
+        {
+            let mut entries = self.entries.lock().ok()?;
+            if entries.get(&id)?.created.elapsed() >= TTL {
+                entries.remove(&id);
+                return None;
+            }
+            let entry = entries.get(&id)?;
+            let index = match state {
+                Some(state) => entry.edits.index_of(state)?,
+                None => entry.edits.position(),
+            };
+            if index == 0 {
+                return Some(entry.png.clone());
+            }
+        }
+        let (_, image) = self.materialize(id, state).ok()?;
+        frame::encode_png(&image).ok()
+    }
+
+    /// 当前状态（编辑后）的截图，不经过 PNG 编解码
+    pub fn image(&self, id: u64) -> Result<Arc<DynamicImage>, AppError> {
+        self.materialize(id, None).map(|(_, image)| image)
+    }
+
+    /// 当前状态的编号与图片
+    pub fn current(&self, id: u64) -> Result<(u64, Arc<DynamicImage>), AppError> {
+        self.materialize(id, None)
+    }
+
+    /// 截图仍在缓存中且仍处于该状态
+    pub fn is_current(&self, id: u64, serial: u64) -> bool {
+        self.with_entry(id, |entry| entry.edits.current_serial() == serial)
+            .unwrap_or(false)
+    }
+
+    pub fn variant(&self, id: u64, serial: u64, variant: Variant) -> Option<Prepared> {
+        self.with_entry(id, |entry| entry.variants.get(serial, variant))
+            .ok()
+            .flatten()
+    }
+
+    /// 放入预编码结果；状态已改变、截图已过期或超出内存上限时丢弃并返回 false
+    pub fn put_variant(&self, id: u64, serial: u64, variant: Variant, prepared: Prepared) -> bool {
+        let Ok(mut entries) = self.entries.lock() else {
+            return false;
+        };
+        let used: usize = entries.values().map(|entry| entry.variants.size()).sum();
+        if used + prepared.size() > pre_encode::MAX_BYTES {
+            return false;
+        }
+        match entries
+            .get_mut(&id)
+            .filter(|entry| entry.created.elapsed() < TTL)
+        {
+            Some(entry) if entry.edits.current_serial() == serial => {
+                entry.variants.put(serial, variant, prepared);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 原图，不经过 PNG 编解码
+    fn base_image(&self, id: u64) -> Result<Arc<DynamicImage>, AppError> {
+        let missing = || AppError::invalid(format!("截图不存在或已过期: {id}"));
+        let png = {
+            let entries = self
+                .entries
+                .lock()
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            let entry = entries
+                .get(&id)
+                .filter(|entry| entry.created.elapsed() < TTL)
+                .ok_or_else(missing)?;
+            if let Some(image) = &entry.image {
+                return Ok(image.clone());
+            }
+            entry.png.clone()
+        };
+        // 解码较慢，不占用锁
+        let image = Arc::new(Frame::Png(png).into_image()?);
+        if let Ok(mut entries) = self.entries.lock() {
+            if let Some(entry) = entries.get_mut(&id) {
+                entry.image = Some(image.clone());
+            }
+        }
+        Ok(image)
+    }
+
+    /// 计算指定编号的状态（None 为当前状态），返回状态编号与图片；
+    /// 从最近缓存的中间状态或原图开始重放编辑，计算时不占用锁
+    fn materialize(
+        &self,
+        id: u64,
+        state: Option<u64>,
+    ) -> Result<(u64, Arc<DynamicImage>), AppError> {
+        let missing = || AppError::invalid(format!("截图不存在或已过期: {id}"));
+        let (serial, start, ops) = {
+            let entries = self
+                .entries
+                .lock()
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            let entry = entries
+                .get(&id)
+                .filter(|entry| entry.created.elapsed() < TTL)
+                .ok_or_else(missing)?;
+            let stack = &entry.edits;
+            let target = match state {
+                Some(state) => stack
+                    .index_of(state)
+                    .ok_or_else(|| AppError::invalid(format!("编辑状态不存在: {state}")))?,
+                None => stack.position(),
+            };
+            let (start, ops) = stack.plan(target);
+            (stack.serial_at(target), start, ops)
+        };
+        let mut image = match start {
+            Some(image) => image,
+            None => self.base_image(id)?,
+        };
+        if ops.is_empty() {
+            return Ok((serial, image));
+        }
+        for op in &ops {
+            image = Arc::new(op.apply(&image)?);
+        }
+        if let Ok(mut entries) = self.entries.lock() {
+            if let Some(entry) = entries.get_mut(&id) {
+                entry.edits.remember(serial, image.clone());
+            }
+        }
+        Ok((serial, image))
+    }
+
+    fn with_entry<T>(&self, id: u64, f: impl FnOnce(&mut Entry) -> T) -> Result<T, AppError> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let entry = entries
+            .get_mut(&id)
+            .filter(|entry| entry.created.elapsed() < TTL)
+            .ok_or_else(|| AppError::invalid(format!("截图不存在或已过期: {id}")))?;
+        Ok(f(entry))
+    }
+
+    fn edit_state(&self, id: u64) -> Result<EditState, AppError> {
+        let (serial, image) = self.materialize(id, None)?;
+        let (position, can_redo, capture_id, night_light) = self.with_entry(id, |entry| {
+            (
+                entry.edits.position(),
+                entry.edits.can_redo(),
+                entry.capture_id.clone(),
+                entry.night_light,
+            )
+        })?;
+        Ok(EditState {
+            capture: CaptureHandle {
+                id,
+                capture_id,
+                width: image.width(),
+                height: image.height(),
+                preview_url: preview_url(id, serial),
+                timings: None,
+                night_light,
+            },
+            position,
+            can_undo: position > 0,
+            can_redo,
+        })
+    }
+
+    /// 在当前状态上追加一步编辑；编辑期间截图被撤销、重做或再次编辑时报错，由用户重试
+    pub fn edit(&self, id: u64, op: EditOp) -> Result<EditState, AppError> {
+        op.validate()?;
+        // 超出上限时先把最早的一步合并到原图，保证编辑记录和中间状态占用的内存有上限
+        if self.with_entry(id, |entry| entry.edits.position())? >= MAX_EDITS {
+            let first = self.with_entry(id, |entry| entry.edits.serial_at(1))?;
+            let (_, base) = self.materialize(id, Some(first))?;
+            let png = frame::encode_png(&base)?;
+            self.with_entry(id, |entry| {
+                if entry.edits.index_of(first) == Some(1) {
+                    entry.png = png;
+                    entry.image = Some(base);
+                    entry.edits.fold_oldest();
+                }
+            })?;
+        }
+        let (serial, current) = self.materialize(id, None)?;
+        let next = Arc::new(op.apply(&current)?);
+        self.with_entry(id, |entry| {
+            if entry.edits.current_serial() != serial {
+                return Err(AppError::invalid("截图已被其他操作修改，请重试"));
+            }
+            let serial = entry.edits.push(op);
+            entry.edits.remember(serial, next);
+            entry.variants.clear();
+            // 编辑期间保留截图
+            entry.created = Instant::now();
+            Ok(())
+        })??;
+        self.edit_state(id)
+    }
+
+    pub fn undo_edit(&self, id: u64) -> Result<EditState, AppError> {
+        let moved = self.with_entry(id, |entry| {
+            let moved = entry.edits.undo();
+            if moved {
+                entry.variants.clear();
+            }
+            moved
+        })?;
+        if !moved {
+            return Err(AppError::invalid("没有可撤销的编辑"));
+        }
+        self.edit_state(id)
+    }
+
+    pub fn redo_edit(&self, id: u64) -> Result<EditState, AppError> {
+        let moved = self.with_entry(id, |entry| {
+            let moved = entry.edits.redo();
+            if moved {
+                entry.variants.clear();
+            }
+            moved
+        })?;
+        if !moved {
+            return Err(AppError::invalid("没有可重做的编辑"));
+        }
+        self.edit_state(id)
+    }
+
+    pub fn edit_history(&self, id: u64) -> Result<EditHistory, AppError> {
+        self.with_entry(id, |entry| entry.edits.history())
+    }
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(code)
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+/// prinsp:// 协议处理：只响应主窗口和遮罩窗口，未知或已过期的 id 返回 404
+pub fn handle_request<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let label = ctx.webview_label();
+    if label != "main" && !label.starts_with(crate::overlay::OVERLAY_PREFIX) {
+        return status(StatusCode::FORBIDDEN);
+    }
+    let Some(id) = request
+        .uri()
+        .path()
+        .rsplit('/')
+        .next()
+        .and_then(|id| id.parse::<u64>().ok())
+    else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    // 不带 state 时为原图
+    let state = request
+        .uri()
+        .query()
+        .and_then(|query| query.strip_prefix("state="))
+        .and_then(|state| state.parse::<u64>().ok())
+        .unwrap_or(0);
+    let Some(png) = ctx
+        .app_handle()
+        .state::<CaptureCache>()
+        .state_png(id, Some(state))
+    else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, "image/png")
+        // id 与状态编号不会复用，内容不会变化
+        .header(header::CACHE_CONTROL, "private, max-age=600, immutable")
+        // 裁剪时要把截图画到 canvas 上，需允许跨源读取
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(png)
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
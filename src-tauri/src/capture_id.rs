@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::capture::{CaptureBackend, CaptureObserver};
+use crate::error::AppError;
+use crate::ocr_queue::Recognized;
+
+/// 已触发、尚未开始截图的编号，截图命令没有带编号时取用
+static PENDING: Mutex<Option<CaptureId>> = Mutex::new(None);
+
+/// 一次截图操作的编号，触发截图时生成；按时间排序的 UUID v7
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CaptureId(String);
+
+impl CaptureId {
+    pub fn generate() -> Self {
+        Self(Uuid::now_v7().to_string())
+    }
+
+    /// 请求头等处传入的编号，需为 UUID
+    pub fn parse(value: &str) -> Option<Self> {
+        Uuid::parse_str(value.trim())
+            .ok()
+            .map(|uuid| Self(uuid.to_string()))
+    }
+}
+
+impl fmt::Display for CaptureId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 触发截图后记下编号，前端随后调用的截图命令沿用它
+pub fn set_pending(id: CaptureId) {
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = Some(id);
+    }
+}
+
+/// 截图开始时确定编号：优先用前端传入的，其次是触发时记下的，都没有时生成新的
+pub fn take(requested: Option<CaptureId>) -> CaptureId {
+    let pending = PENDING.lock().ok().and_then(|mut pending| pending.take());
+    requested.or(pending).unwrap_or_else(CaptureId::generate)
+}
+
+/// 截图被取消，丢弃尚未使用的编号
+pub fn forget() {
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = None;
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct CaptureStarted<'a> {
+    capture_id: &'a CaptureId,
+}
+
+/// capture-backend-attempt 事件：每个截图后端尝试一次发一个
+#[derive(Clone, Serialize)]
+struct BackendAttempt<'a> {
+    capture_id: &'a CaptureId,
+    backend: &'static str,
+    ok: bool,
+    duration_ms: u64,
+    /// 失败时的错误代码
+    error: Option<&'static str>,
+}
+
+/// capture-finished 事件
+#[derive(Clone, Serialize)]
+struct CaptureFinished<'a> {
+    capture_id: &'a CaptureId,
+    ok: bool,
+    /// 截图在缓存中的编号，可用于 prinsp:// 预览和后续的编辑、识别
+    cache_id: Option<u64>,
+    error: Option<&'static str>,
+}
+
+/// ocr-complete 事件：交互式识别结束，取消的识别不发送
+#[derive(Clone, Serialize)]
+struct OcrComplete<'a> {
+    capture_id: Option<&'a CaptureId>,
+    ok: bool,
+    chars: usize,
+    error: Option<&'static str>,
+}
+
+/// 一次截图过程中发给界面的事件；命令行模式没有窗口，不创建
+pub struct CaptureEvents {
+    app: AppHandle,
+    id: CaptureId,
+}
+
+impl CaptureEvents {
+    /// 开始截图，发出 capture-started
+    pub fn start(app: &AppHandle, id: CaptureId) -> Self {
+        tracing::debug!(capture_id = %id, "开始截图");
+        let _ = app.emit("capture-started", CaptureStarted { capture_id: &id });
+        Self {
+            app: app.clone(),
+            id,
+        }
+    }
+
+    pub fn id(&self) -> &CaptureId {
+        &self.id
+    }
+
+    /// 截图结束（成功时附带缓存编号），发出 capture-finished
+    pub fn finish(&self, result: Result<Option<u64>, &AppError>) {
+        let finished = CaptureFinished {
+            capture_id: &self.id,
+            ok: result.is_ok(),
+            cache_id: result.ok().flatten(),
+            error: result.err().map(AppError::code),
+        };
+        let _ = self.app.emit("capture-finished", finished);
+    }
+}
+
+impl CaptureObserver for CaptureEvents {
+    /// 发出 capture-backend-attempt
+    fn attempt(&self, backend: CaptureBackend, duration_ms: u64, error: Option<&AppError>) {
+        let attempt = BackendAttempt {
+            capture_id: &self.id,
+            backend: backend.name(),
+            ok: error.is_none(),
+            duration_ms,
+            error: error.map(AppError::code),
+        };
+        let _ = self.app.emit("capture-backend-attempt", attempt);
+    }
+}
+
+/// 交互式识别结束后发出 ocr-complete
+pub fn ocr_complete(
+    app: &AppHandle,
+    capture_id: Option<&CaptureId>,
+    result: &Result<Recognized, AppError>,
+) {
+    if matches!(result, Err(AppError::Cancelled)) {
+        return;
+    }
+    let complete = OcrComplete {
+        capture_id,
+        ok: result.is_ok(),
+        chars: result
+            .as_ref()
+            .map_or(0, |recognized| recognized.text.chars().count()),
+        error: result.as_ref().err().map(AppError::code),
+    };
+    let _ = app.emit("ocr-complete", complete);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ids_are_uuids_and_parse_back() {
+        let id = CaptureId::generate();
+        assert_eq!(CaptureId::parse(&format!(" {id} ")), Some(id.clone()));
+        assert_ne!(CaptureId::generate(), id);
+        assert_eq!(CaptureId::parse("not-a-uuid"), None);
+        // 大写或不带连字符的写法统一为标准形式
+        let upper = id.to_string().to_uppercase().replace('-', "");
+        assert_eq!(CaptureId::parse(&upper), Some(id));
+    }
+
+    #[test]
+    fn requested_id_wins_over_the_pending_one() {
+        let pending = CaptureId::generate();
+        let requested = CaptureId::generate();
+        set_pending(pending.clone());
+        assert_eq!(take(Some(requested.clone())), requested);
+        // 已被取走，之后生成新的
+        assert_ne!(take(None), pending);
+
+        set_pending(pending.clone());
+        assert_eq!(take(None), pending);
+
+        set_pending(pending.clone());
+        forget();
+        assert_ne!(take(None), pending);
+    }
+
+    #[test]
+    fn events_carry_the_id_as_a_string() {
+        let id = CaptureId::generate();
+        let finished = CaptureFinished {
+            capture_id: &id,
+            ok: false,
+            cache_id: None,
+            error: Some("timeout"),
+        };
+        assert_eq!(
+            serde_json::to_value(finished).unwrap(),
+            json!({ "capture_id": id.to_string(), "ok": false, "cache_id": null, "error": "timeout" })
+        );
+        let complete = OcrComplete {
+            capture_id: Some(&id),
+            ok: true,
+            chars: 3,
+            error: None,
+        };
+        assert_eq!(
+            serde_json::to_value(complete).unwrap()["capture_id"],
+            json!(id)
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::notify::{self, Notice, NotifyEvent};
+use crate::poll_watch::{self, Watches};
+
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+/// 比较前缩小到的最长边，足以发现变化，又不必逐像素比较整屏
+const DIFF_MAX_SIDE: u32 = 320;
+/// 灰度差超过此值才算变化，过滤压缩和抗锯齿带来的细微差异
+const PIXEL_TOLERANCE: u8 = 24;
+const THUMBNAIL_MAX_SIDE: u32 = 240;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangeWatchOptions {
+    /// 显示在事件和通知中的名称
+    pub label: Option<String>,
+    /// 变化时发送桌面通知
+    pub notify: bool,
+    /// 变化区域的宽和高都小于此值（原图像素）时忽略，如时钟、闪烁的光标
+    pub min_region_px: u32,
+}
+
+impl Default for ChangeWatchOptions {
+    fn default() -> Self {
+        Self {
+            label: None,
+            notify: false,
+            min_region_px: 32,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ChangeWatchInfo {
+    pub id: u64,
+    /// 为空时监视整个显示器
+    pub region: Option<Region>,
+    pub monitor: Option<u32>,
+    pub interval_s: f64,
+    pub threshold_pct: f64,
+    pub options: ChangeWatchOptions,
+    /// 触发的次数
+    pub changes: u64,
+    pub started_at: i64,
+}
+
+pub type ChangeWatches = Watches<ChangeWatchInfo>;
+
+/// screen-changed 事件
+#[derive(Clone, Serialize)]
+struct ScreenChanged {
+    id: u64,
+    label: Option<String>,
+    /// 变化像素占比（百分比）
+    changed_pct: f64,
+    /// 变化区域，相对于监视区域的像素坐标
+    bbox: Region,
+    /// 变化区域的 PNG 缩略图 base64
+    thumbnail: String,
+    at: i64,
+}
+
+/// change-watch-stopped 事件：连续失败后自动停止
+#[derive(Clone, Serialize)]
+struct ChangeWatchStopped {
+    id: u64,
+    reason: String,
+}
+
+/// 缩小后的灰度帧，scale 为原图与缩小图的边长比
+struct Sample {
+    gray: GrayImage,
+    scale: f64,
+}
+
+fn sample(image: &DynamicImage) -> Sample {
+    let side = image.width().max(image.height()).max(1);
+    let gray = if side > DIFF_MAX_SIDE {
+        image
+            .resize(DIFF_MAX_SIDE, DIFF_MAX_SIDE, FilterType::Triangle)
+            .to_luma8()
+    } else {
+        image.to_luma8()
+    };
+    let scale = image.width() as f64 / gray.width().max(1) as f64;
+    Sample { gray, scale }
+}
+
+/// 两帧的差异：变化像素占比及外接矩形（原图坐标）
+struct Diff {
+    changed_pct: f64,
+    bbox: Option<Region>,
+}
+
+/// 找出变化像素的连通区域，丢弃宽和高都小于 min_side（缩小图像素）的小区域后统计
+fn diff(prev: &GrayImage, next: &GrayImage, min_side: u32, scale: f64) -> Diff {
+    let (w, h) = next.dimensions();
+    if prev.dimensions() != (w, h) {
+        // 分辨率变化视为整屏变化
+        return Diff {
+            changed_pct: 100.0,
+            bbox: Some(Region {
+                x: 0,
+                y: 0,
+                width: (w as f64 * scale).round() as u32,
+                height: (h as f64 * scale).round() as u32,
+            }),
+        };
+    }
+    let mut changed: Vec<bool> = prev
+        .pixels()
+        .zip(next.pixels())
+        .map(|(a, b)| a.0[0].abs_diff(b.0[0]) > PIXEL_TOLERANCE)
+        .collect();
+    let mut kept = 0usize;
+    let mut bbox: Option<(u32, u32, u32, u32)> = None;
+    let mut stack = Vec::new();
+    for start in 0..changed.len() {
+        if !changed[start] {
+            continue;
+        }
+        changed[start] = false;
+        stack.push(start);
+        let (mut x0, mut y0, mut x1, mut y1) = (w, h, 0, 0);
+        let mut count = 0usize;
+        while let Some(i) = stack.pop() {
+            let (x, y) = ((i as u32) % w, (i as u32) / w);
+            count += 1;
+            (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+            let neighbors = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < w).then(|| i + 1),
+                (y > 0).then(|| i - w as usize),
+                (y + 1 < h).then(|| i + w as usize),
+            ];
+            for n in neighbors.into_iter().flatten() {
+                if changed[n] {
+                    changed[n] = false;
+                    stack.push(n);
+                }
+            }
+        }
+        if x1 - x0 + 1 < min_side && y1 - y0 + 1 < min_side {
+            continue;
+        }
+        kept += count;
+        bbox = Some(match bbox {
+            Some((a0, b0, a1, b1)) => (a0.min(x0), b0.min(y0), a1.max(x1), b1.max(y1)),
+            None => (x0, y0, x1, y1),
+        });
+    }
+    let scaled = |v: u32| (v as f64 * scale).round() as u32;
+    Diff {
+        changed_pct: kept as f64 * 100.0 / (w as f64 * h as f64).max(1.0),
+        bbox: bbox.map(|(x0, y0, x1, y1)| Region {
+            x: scaled(x0),
+            y: scaled(y0),
+            width: scaled(x1 - x0 + 1).max(1),
+            height: scaled(y1 - y0 + 1).max(1),
+        }),
+    }
+}
+
+fn thumbnail(image: &DynamicImage, bbox: Region) -> Result<String, AppError> {
+    let x = bbox.x.min(image.width().saturating_sub(1));
+    let y = bbox.y.min(image.height().saturating_sub(1));
+    let width = bbox.width.min(image.width() - x).max(1);
+    let height = bbox.height.min(image.height() - y).max(1);
+    let crop = image.crop_imm(x, y, width, height);
+    let thumb = if width.max(height) > THUMBNAIL_MAX_SIDE {
+        crop.resize(THUMBNAIL_MAX_SIDE, THUMBNAIL_MAX_SIDE, FilterType::Triangle)
+    } else {
+        crop
+    };
+    Ok(STANDARD.encode(crate::frame::encode_png(&thumb)?))
+}
+
+fn capture(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+) -> Result<DynamicImage, AppError> {
+    let frame = crate::quick::poll_area_without_windows(app, monitor, region)
+        .map_err(AppError::Internal)?;
+    let frame = match region {
+        Some(region) => crate::headless::crop(frame, region)?,
+        None => frame,
+    };
+    frame.into_image()
+}
+
+fn run(app: AppHandle, info: Arc<Mutex<ChangeWatchInfo>>, stop: Arc<AtomicBool>) {
+    let (id, region, monitor, interval, threshold, options) = {
+        let info = info.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            info.id,
+            info.region,
+            info.monitor,
+            Duration::from_secs_f64(info.interval_s),
+            info.threshold_pct,
+            info.options.clone(),
+        )
+    };
+    let mut previous: Option<Sample> = None;
+    // 滞回：触发后要等变化降到阈值一半以下才会再次触发，持续播放的动画只通知一次
+    let mut armed = true;
+
+    let result = poll_watch::poll(id, interval, &stop, || {
+        let image = capture(&app, monitor, region)?;
+        let current = sample(&image);
+        if let Some(prev) = &previous {
+            let min_side = (options.min_region_px as f64 / current.scale)
+                .ceil()
+                .max(1.0) as u32;
+            let result = diff(&prev.gray, &current.gray, min_side, current.scale);
+            if armed && result.changed_pct >= threshold {
+                if let Some(bbox) = result.bbox {
+                    armed = false;
+                    if let Ok(mut info) = info.lock() {
+                        info.changes += 1;
+                    }
+                    tracing::info!(
+                        id,
+                        changed_pct = result.changed_pct,
+                        ?bbox,
+                        "监视区域的画面已变化"
+                    );
+                    notify_change(&app, id, &options, &image, result.changed_pct, bbox);
+                }
+            } else if !armed && result.changed_pct < threshold / 2.0 {
+                armed = true;
+            }
+        }
+        previous = Some(current);
+        Ok(())
+    });
+    if let Err(err) = result {
+        let _ = app.emit(
+            "change-watch-stopped",
+            ChangeWatchStopped {
+                id,
+                reason: err.message(),
+            },
+        );
+    }
+    app.state::<ChangeWatches>().finished(id);
+    tracing::info!(id, "画面监视已结束");
+}
+
+fn notify_change(
+    app: &AppHandle,
+    id: u64,
+    options: &ChangeWatchOptions,
+    image: &DynamicImage,
+    changed_pct: f64,
+    bbox: Region,
+) {
+    if options.notify {
+        let summary = options.label.as_deref().unwrap_or("监视区域的画面已变化");
+        let mut notice = Notice::new(
+            NotifyEvent::Capture,
+            summary,
+            format!("变化 {changed_pct:.1}%"),
+        );
+        notice.thumbnail = Some(image.clone());
+        notify::notify(app, notice);
+    }
+    let thumbnail = thumbnail(image, bbox).unwrap_or_else(|err| {
+        tracing::warn!(id, error = %err, "生成变化缩略图失败");
+        String::new()
+    });
+    let _ = app.emit(
+        "screen-changed",
+        ScreenChanged {
+            id,
+            label: options.label.clone(),
+            changed_pct,
+            bbox,
+            thumbnail,
+            at: chrono::Local::now().timestamp_millis(),
+        },
+    );
+}
+
+/// 定时截取显示器或其中的区域，与上一帧比较，变化像素占比达到 threshold_pct 时
+/// 发出 screen-changed 事件；返回监视 id
+#[tauri::command]
+pub fn start_change_watch(
+    app: AppHandle,
+    monitor: Option<u32>,
+    rect: Option<Region>,
+    interval_s: f64,
+    threshold_pct: f64,
+    options: Option<ChangeWatchOptions>,
+) -> Result<u64, AppError> {
+    if rect.is_some_and(|r| r.width == 0 || r.height == 0) {
+        return Err(AppError::invalid("监视区域为空"));
+    }
+    if !interval_s.is_finite() || interval_s < MIN_INTERVAL.as_secs_f64() {
+        return Err(AppError::invalid(format!(
+            "监视间隔不能小于 {} 秒",
+            MIN_INTERVAL.as_secs_f64()
+        )));
+    }
+    if !(threshold_pct > 0.0 && threshold_pct <= 100.0) {
+        return Err(AppError::invalid("变化阈值必须在 0 到 100 之间"));
+    }
+    let handle = app.clone();
+    let id = app.state::<ChangeWatches>().start(
+        "画面",
+        |id| ChangeWatchInfo {
+            id,
+            region: rect,
+            monitor,
+            interval_s,
+            threshold_pct,
+            options: options.unwrap_or_default(),
+            changes: 0,
+            started_at: chrono::Local::now().timestamp_millis(),
+        },
+        move |info, stop| run(handle, info, stop),
+    )?;
+    tracing::info!(
+        id,
+        ?rect,
+        ?monitor,
+        interval_s,
+        threshold_pct,
+        "开始监视画面变化"
+    );
+    Ok(id)
+}
+
+/// 停止监视，返回是否找到该监视
+#[tauri::command]
+pub fn stop_change_watch(app: AppHandle, id: u64) -> bool {
+    app.state::<ChangeWatches>().stop(id)
+}
+
+#[tauri::command]
+pub fn list_change_watches(app: AppHandle) -> Vec<ChangeWatchInfo> {
+    app.state::<ChangeWatches>().list()
+}
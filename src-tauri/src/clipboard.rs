@@ -0,0 +1,65 @@
+use arboard::Clipboard;
+use image::{DynamicImage, RgbaImage};
+
+use crate::capabilities::ClipboardCapabilities;
+use crate::error::AppError;
+use crate::error_report::{self, ErrorSource, Failure};
+use crate::stats;
+
+pub fn set_text(text: String) -> Result<(), AppError> {
+    let chars = text.chars().count();
+    let result = Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text));
+    log_clipboard("text", result.map_err(AppError::from))?;
+    tracing::debug!(chars, "文字已复制到剪贴板");
+    stats::record_copy();
+    Ok(())
+}
+
+/// 剪贴板中的文字，没有文字时返回 ClipboardUnavailable
+pub fn get_text() -> Result<String, AppError> {
+    Ok(Clipboard::new()?.get_text()?)
+}
+
+fn log_clipboard(kind: &str, result: Result<(), AppError>) -> Result<(), AppError> {
+    result.inspect_err(|err| {
+        tracing::warn!(kind, error = err.code(), detail = %err, "写入剪贴板失败");
+        error_report::record(Failure::new(
+            ErrorSource::Clipboard,
+            err.code(),
+            err.message(),
+            format!("{kind}: {err}"),
+        ));
+    })
+}
+
+pub fn set_image(img: &DynamicImage) -> Result<(), AppError> {
+    set_rgba(&img.to_rgba8())
+}
+
+/// 直接写入 RGBA 像素，预编码好的剪贴板数据不再复制
+pub fn set_rgba(rgba: &RgbaImage) -> Result<(), AppError> {
+    let img_data = arboard::ImageData {
+        width: rgba.width() as usize,
+        height: rgba.height() as usize,
+        bytes: rgba.as_raw().into(),
+    };
+
+    let (width, height) = (img_data.width, img_data.height);
+    let result = Clipboard::new().and_then(|mut clipboard| clipboard.set_image(img_data));
+    log_clipboard("image", result.map_err(AppError::from))?;
+    tracing::debug!(width, height, "图片已复制到剪贴板");
+    stats::record_copy();
+    Ok(())
+}
+
+/// 剪贴板支持写入的格式，新增格式时同步更新
+pub fn capabilities() -> ClipboardCapabilities {
+    ClipboardCapabilities {
+        available: Clipboard::new().is_ok(),
+        text: true,
+        image: true,
+        html: false,
+        uri_list: false,
+        primary: false,
+    }
+}
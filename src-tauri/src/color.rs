@@ -0,0 +1,428 @@
+use image::{DynamicImage, RgbaImage};
+use rayon::prelude::*;
+use std::io::Cursor;
+
+use crate::error::AppError;
+use crate::frame::Frame;
+
+/// ICC 中 sRGB 的原色（已按 Bradford 适配到 D50 的 PCS 白点），按列为 R、G、B
+const SRGB_D50: [[f64; 3]; 3] = [
+    [0.436_074_7, 0.385_064_9, 0.143_080_4],
+    [0.222_504_5, 0.716_878_6, 0.060_616_9],
+    [0.013_932_2, 0.097_104_5, 0.714_173_3],
+];
+
+/// 线性值编码回 sRGB 时的查表精度
+const ENCODE_STEPS: usize = 4096;
+
+/// 与 sRGB 相差不超过该值时视为 sRGB，不做转换
+const MATRIX_TOLERANCE: f64 = 0.002;
+
+/// 矩阵/曲线型 RGB 配置，能覆盖显示器常见的 Display P3、Adobe RGB 等
+struct Profile {
+    /// 原色，按列为 R、G、B 在 PCS（XYZ，D50）中的坐标
+    matrix: [[f64; 3]; 3],
+    /// 各通道 8 位值到线性值
+    curves: [[f32; 256]; 3],
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn s15_fixed16(data: &[u8], at: usize) -> Option<f64> {
+    Some(be_u32(data, at)? as i32 as f64 / 65536.0)
+}
+
+fn tag<'a>(icc: &'a [u8], signature: &[u8; 4]) -> Option<&'a [u8]> {
+    let count = be_u32(icc, 128)? as usize;
+    (0..count).find_map(|i| {
+        let entry = 132 + i * 12;
+        if icc.get(entry..entry + 4)? != signature {
+            return None;
+        }
+        let offset = be_u32(icc, entry + 4)? as usize;
+        let size = be_u32(icc, entry + 8)? as usize;
+        icc.get(offset..offset.checked_add(size)?)
+    })
+}
+
+fn xyz(icc: &[u8], signature: &[u8; 4]) -> Option<[f64; 3]> {
+    let data = tag(icc, signature)?;
+    if data.get(..4)? != b"XYZ " {
+        return None;
+    }
+    Some([
+        s15_fixed16(data, 8)?,
+        s15_fixed16(data, 12)?,
+        s15_fixed16(data, 16)?,
+    ])
+}
+
+/// 解析 curv / para 曲线，返回输入为 0–1 的求值函数
+fn curve(icc: &[u8], signature: &[u8; 4]) -> Option<Box<dyn Fn(f64) -> f64>> {
+    let data = tag(icc, signature)?;
+    match data.get(..4)? {
+        b"curv" => {
+            let count = be_u32(data, 8)? as usize;
+            match count {
+                0 => Some(Box::new(|x| x)),
+                1 => {
+                    let gamma = be_u16(data, 12)? as f64 / 256.0;
+                    Some(Box::new(move |x| x.powf(gamma)))
+                }
+                _ => {
+                    let table = (0..count)
+                        .map(|i| Some(be_u16(data, 12 + i * 2)? as f64 / 65535.0))
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(Box::new(move |x| {
+                        let pos = x * (table.len() - 1) as f64;
+                        let i = (pos.floor() as usize).min(table.len() - 2);
+                        let t = pos - i as f64;
+                        table[i] * (1.0 - t) + table[i + 1] * t
+                    }))
+                }
+            }
+        }
+        b"para" => {
+            let kind = be_u16(data, 8)?;
+            let count = [1, 3, 4, 5, 7].get(kind as usize).copied()?;
+            let mut p = [0.0; 7];
+            for (i, value) in p.iter_mut().enumerate().take(count) {
+                *value = s15_fixed16(data, 12 + i * 4)?;
+            }
+            let [g, a, b, c, d, e, f] = p;
+            Some(Box::new(move |x: f64| match kind {
+                0 => x.powf(g),
+                1 if x >= -b / a => (a * x + b).powf(g),
+                1 => 0.0,
+                2 if x >= -b / a => (a * x + b).powf(g) + c,
+                2 => c,
+                3 if x >= d => (a * x + b).powf(g),
+                3 => c * x,
+                _ if x >= d => (a * x + b).powf(g) + e,
+                _ => c * x + f,
+            }))
+        }
+        _ => None,
+    }
+}
+
+impl Profile {
+    /// 只支持 RGB→XYZ 的矩阵/曲线型配置，查找表型（A2B0）配置返回 None
+    fn parse(icc: &[u8]) -> Option<Self> {
+        if icc.get(16..20)? != b"RGB " || icc.get(20..24)? != b"XYZ " {
+            return None;
+        }
+        let [r, g, b] = [xyz(icc, b"rXYZ")?, xyz(icc, b"gXYZ")?, xyz(icc, b"bXYZ")?];
+        let matrix = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+        let mut curves = [[0.0f32; 256]; 3];
+        for (lut, signature) in curves.iter_mut().zip([b"rTRC", b"gTRC", b"bTRC"]) {
+            let eval = curve(icc, signature)?;
+            for (i, value) in lut.iter_mut().enumerate() {
+                *value = eval(i as f64 / 255.0).clamp(0.0, 1.0) as f32;
+            }
+        }
+        Some(Self { matrix, curves })
+    }
+
+    /// 原色与曲线都与 sRGB 一致（在 8 位精度内）
+    fn is_srgb(&self, to_srgb: &[[f64; 3]; 3]) -> bool {
+        let identity = (0..3).all(|row| {
+            (0..3).all(|col| {
+                let expected = if row == col { 1.0 } else { 0.0 };
+                (to_srgb[row][col] - expected).abs() < MATRIX_TOLERANCE
+            })
+        });
+        identity
+            && self.curves.iter().all(|lut| {
+                lut.iter()
+                    .enumerate()
+                    .all(|(i, &v)| (srgb_decode(i as f64 / 255.0) - v as f64).abs() < 0.5 / 255.0)
+            })
+    }
+}
+
+fn srgb_decode(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn srgb_encode(v: f64) -> f64 {
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn invert(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < 1e-9 {
+        return None;
+    }
+    let mut inv = [[0.0; 3]; 3];
+    for (row, out) in inv.iter_mut().enumerate() {
+        for (col, value) in out.iter_mut().enumerate() {
+            // 伴随矩阵的转置
+            let (r1, r2) = ((col + 1) % 3, (col + 2) % 3);
+            let (c1, c2) = ((row + 1) % 3, (row + 2) % 3);
+            *value = (m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]) / det;
+        }
+    }
+    Some(inv)
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut out = [[0.0; 3]; 3];
+    for (row, values) in out.iter_mut().enumerate() {
+        for (col, value) in values.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[row][k] * b[k][col]).sum();
+        }
+    }
+    out
+}
+
+/// 逐像素转换为 sRGB，透明度不变
+fn convert(image: &mut RgbaImage, profile: &Profile, to_srgb: &[[f64; 3]; 3]) {
+    let m = to_srgb.map(|row| row.map(|v| v as f32));
+    let encode: Vec<u8> = (0..ENCODE_STEPS)
+        .map(|i| (srgb_encode(i as f64 / (ENCODE_STEPS - 1) as f64) * 255.0).round() as u8)
+        .collect();
+    let [r_lut, g_lut, b_lut] = &profile.curves;
+    let width = image.width() as usize;
+    image
+        .as_mut()
+        .par_chunks_mut(width.max(1) * 4)
+        .for_each(|row| {
+            for px in row.chunks_exact_mut(4) {
+                let lin = [
+                    r_lut[px[0] as usize],
+                    g_lut[px[1] as usize],
+                    b_lut[px[2] as usize],
+                ];
+                for (channel, coeffs) in px.iter_mut().zip(&m) {
+                    let v = coeffs[0] * lin[0] + coeffs[1] * lin[1] + coeffs[2] * lin[2];
+                    let index = (v.clamp(0.0, 1.0) * (ENCODE_STEPS - 1) as f32).round() as usize;
+                    *channel = encode[index];
+                }
+            }
+        });
+}
+
+/// PNG 中的 ICC 配置；已标记为 sRGB 的图片不需要转换，返回 None
+fn embedded_profile(png: &[u8]) -> Option<Vec<u8>> {
+    let reader = png::Decoder::new(Cursor::new(png)).read_info().ok()?;
+    let info = reader.info();
+    if info.srgb.is_some() {
+        return None;
+    }
+    info.icc_profile.as_ref().map(|icc| icc.to_vec())
+}
+
+/// 按截图 PNG 中嵌入的 ICC 配置转换为 sRGB，使各后端的截图颜色一致；
+/// 没有配置、本就是 sRGB 或配置无法解析时原样返回
+pub fn normalize(frame: Frame) -> Result<Frame, AppError> {
+    let Frame::Png(png) = &frame else {
+        // xcap 直接返回的像素没有色彩配置信息，按 sRGB 处理
+        return Ok(frame);
+    };
+    let Some(icc) = embedded_profile(png) else {
+        return Ok(frame);
+    };
+    let Some(profile) = Profile::parse(&icc) else {
+        tracing::warn!(bytes = icc.len(), "不支持的 ICC 色彩配置，保留原始像素");
+        return Ok(frame);
+    };
+    let Some(to_srgb) = invert(&SRGB_D50).map(|inv| multiply(&inv, &profile.matrix)) else {
+        return Ok(frame);
+    };
+    if profile.is_srgb(&to_srgb) {
+        return Ok(frame);
+    }
+    let mut image = frame.into_image()?.into_rgba8();
+    convert(&mut image, &profile, &to_srgb);
+    tracing::debug!(bytes = icc.len(), "已按 ICC 色彩配置转换为 sRGB");
+    Ok(Frame::Image(DynamicImage::ImageRgba8(image)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::borrow::Cow;
+
+    fn fixed16(v: f64) -> [u8; 4] {
+        ((v * 65536.0).round() as i32).to_be_bytes()
+    }
+
+    /// sRGB 的分段曲线（para 类型 3）
+    fn srgb_curve() -> Vec<u8> {
+        let mut data = b"para\0\0\0\0\0\x03\0\0".to_vec();
+        for v in [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045] {
+            data.extend(fixed16(v));
+        }
+        data
+    }
+
+    /// 线性曲线（没有表项的 curv）
+    fn linear_curve() -> Vec<u8> {
+        b"curv\0\0\0\0\0\0\0\0".to_vec()
+    }
+
+    /// 矩阵/曲线型 RGB 配置，primaries 为 R、G、B 原色的 XYZ，三个通道共用同一条曲线
+    fn icc(primaries: [[f64; 3]; 3], curve: &[u8]) -> Vec<u8> {
+        let mut header = vec![0u8; 128];
+        header[16..20].copy_from_slice(b"RGB ");
+        header[20..24].copy_from_slice(b"XYZ ");
+        let mut data = Vec::new();
+        let mut entries = Vec::new();
+        let start = 128 + 4 + 6 * 12;
+        for (signature, xyz) in [b"rXYZ", b"gXYZ", b"bXYZ"].into_iter().zip(primaries) {
+            entries.push((signature, start + data.len(), 20));
+            data.extend(b"XYZ \0\0\0\0");
+            xyz.iter().for_each(|v| data.extend(fixed16(*v)));
+        }
+        let curve_at = start + data.len();
+        data.extend(curve);
+        for signature in [b"rTRC", b"gTRC", b"bTRC"] {
+            entries.push((signature, curve_at, curve.len()));
+        }
+        let mut icc = header;
+        icc.extend((entries.len() as u32).to_be_bytes());
+        for (signature, offset, size) in entries {
+            icc.extend(signature);
+            icc.extend((offset as u32).to_be_bytes());
+            icc.extend((size as u32).to_be_bytes());
+        }
+        icc.extend(data);
+        icc
+    }
+
+    /// SRGB_D50 的三列
+    fn srgb_primaries() -> [[f64; 3]; 3] {
+        [0, 1, 2].map(|col| SRGB_D50.map(|row| row[col]))
+    }
+
+    fn png_with(image: &RgbaImage, icc: Option<Vec<u8>>, srgb_chunk: bool) -> Vec<u8> {
+        let mut info = png::Info::with_size(image.width(), image.height());
+        info.color_type = png::ColorType::Rgba;
+        info.bit_depth = png::BitDepth::Eight;
+        info.icc_profile = icc.map(Cow::Owned);
+        if srgb_chunk {
+            info.srgb = Some(png::SrgbRenderingIntent::Perceptual);
+        }
+        let mut buf = Vec::new();
+        let mut writer = png::Encoder::with_info(&mut buf, info)
+            .unwrap()
+            .write_header()
+            .unwrap();
+        writer.write_image_data(image.as_raw()).unwrap();
+        writer.finish().unwrap();
+        buf
+    }
+
+    fn sample() -> RgbaImage {
+        RgbaImage::from_fn(16, 9, |x, y| {
+            Rgba([
+                (x * 16) as u8,
+                (y * 28) as u8,
+                ((x + y) * 9) as u8,
+                255 - y as u8,
+            ])
+        })
+    }
+
+    fn pixels(frame: Frame) -> RgbaImage {
+        frame.into_image().unwrap().into_rgba8()
+    }
+
+    fn is_unchanged(frame: &Frame, png: &[u8]) -> bool {
+        matches!(frame, Frame::Png(data) if data == png)
+    }
+
+    #[test]
+    fn srgb_grim_output_matches_xcap_pixels() {
+        let image = sample();
+        let xcap = normalize(Frame::Image(DynamicImage::ImageRgba8(image.clone()))).unwrap();
+        let grim = png_with(&image, Some(icc(srgb_primaries(), &srgb_curve())), false);
+        let grim = normalize(Frame::Png(grim)).unwrap();
+        assert_eq!(pixels(grim), pixels(xcap));
+    }
+
+    #[test]
+    fn untagged_or_srgb_tagged_png_is_left_as_is() {
+        let image = sample();
+        let untagged = png_with(&image, None, false);
+        assert!(is_unchanged(
+            &normalize(Frame::Png(untagged.clone())).unwrap(),
+            &untagged
+        ));
+
+        // 有 sRGB 块时忽略 ICC 配置
+        let [r, g, b] = srgb_primaries();
+        let tagged = png_with(&image, Some(icc([g, r, b], &srgb_curve())), true);
+        assert!(is_unchanged(
+            &normalize(Frame::Png(tagged.clone())).unwrap(),
+            &tagged
+        ));
+    }
+
+    #[test]
+    fn wide_gamut_profile_is_converted_to_srgb() {
+        // 红绿原色互换的配置：文件中的红色在屏幕上是 sRGB 的绿色
+        let [r, g, b] = srgb_primaries();
+        let image = RgbaImage::from_fn(4, 1, |x, _| Rgba([200, 50, 10 * x as u8, 60 * x as u8]));
+        let png = png_with(&image, Some(icc([g, r, b], &srgb_curve())), false);
+        let converted = pixels(normalize(Frame::Png(png)).unwrap());
+        for (src, out) in image.pixels().zip(converted.pixels()) {
+            let expected = [src[1], src[0], src[2]];
+            for (got, want) in out.0[..3].iter().zip(expected) {
+                assert!(got.abs_diff(want) <= 1, "{out:?} vs {expected:?}");
+            }
+            assert_eq!(out[3], src[3]);
+        }
+    }
+
+    #[test]
+    fn linear_curve_is_reencoded_with_srgb_gamma() {
+        let image = RgbaImage::from_pixel(2, 2, Rgba([128, 0, 255, 255]));
+        let png = png_with(&image, Some(icc(srgb_primaries(), &linear_curve())), false);
+        let converted = pixels(normalize(Frame::Png(png)).unwrap());
+        let expected = (srgb_encode(128.0 / 255.0) * 255.0).round() as u8;
+        let px = converted.get_pixel(0, 0);
+        assert!(px[0].abs_diff(expected) <= 1, "{px:?}");
+        assert_eq!((px[1], px[2]), (0, 255));
+    }
+
+    #[test]
+    fn unsupported_profiles_are_ignored() {
+        let image = sample();
+        let mut gray = icc(srgb_primaries(), &srgb_curve());
+        gray[16..20].copy_from_slice(b"GRAY");
+        assert!(Profile::parse(&gray).is_none());
+        for profile in [gray, vec![0; 64]] {
+            let png = png_with(&image, Some(profile), false);
+            assert!(is_unchanged(
+                &normalize(Frame::Png(png.clone())).unwrap(),
+                &png
+            ));
+        }
+    }
+
+    #[test]
+    fn encoded_captures_are_tagged_srgb() {
+        let png = crate::frame::encode_png(&DynamicImage::ImageRgba8(sample())).unwrap();
+        let reader = png::Decoder::new(Cursor::new(png)).read_info().unwrap();
+        assert!(reader.info().srgb.is_some());
+    }
+}
@@ -0,0 +1,772 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::ipc::{InvokeBody, Request};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::capture::{self, CaptureBackend, CaptureObserver};
+use crate::capture_cache::{CaptureCache, CaptureHandle, CaptureRef};
+use crate::capture_id::{self, CaptureEvents, CaptureId};
+use crate::clipboard;
+use crate::error::AppError;
+use crate::error_report::{self, ErrorSource, Failure};
+use crate::frame::Frame;
+use crate::history::HistoryState;
+use crate::ocr_queue::{self, JobInput};
+use crate::preprocess::check_ocr_size;
+use crate::session::{self, RetriggerPolicy};
+use crate::timings::{self, Timings};
+use crate::{
+    durable, extract, geometry, image_input, launch, notify, overlay, pre_encode, recovery,
+    run_blocking, save, settings, stats, text_diff, translate, tray, trigger, webhook,
+};
+
+/// 当前截图会话的状态
+#[derive(Serialize)]
+pub struct CaptureSessionState {
+    active: bool,
+    session: u64,
+    /// 已进行的时间（毫秒）
+    elapsed_ms: Option<u64>,
+    retrigger: RetriggerPolicy,
+}
+
+#[tauri::command]
+pub fn get_capture_session_state(app: AppHandle) -> CaptureSessionState {
+    let active = trigger::capture_in_progress(&app);
+    CaptureSessionState {
+        active,
+        session: session::current(),
+        elapsed_ms: session::active_since()
+            .filter(|_| active)
+            .map(|since| since.elapsed().as_millis() as u64),
+        retrigger: trigger::retrigger_policy(&app),
+    }
+}
+
+#[tauri::command]
+pub fn hide_window(app: AppHandle, window: WebviewWindow) -> Result<(), AppError> {
+    // 截图界面全部收起后截图结束；在其他显示器上框选时其余窗口先收起，会话仍在进行
+    let main_visible = overlay::is_overlay(&window) && trigger::main_window_visible(&app);
+    if !main_visible && !overlay::has_overlays(&app, Some(window.label())) {
+        session::end();
+    }
+    // 额外的遮罩窗口用完即关闭
+    if overlay::is_overlay(&window) {
+        return Ok(window.close()?);
+    }
+    Ok(window.hide()?)
+}
+
+/// 截图相关命令只能由主窗口或遮罩窗口调用，设置窗口调用时拒绝
+pub fn ensure_capture_window(window: &WebviewWindow) -> Result<(), AppError> {
+    if window.label() == "main" || overlay::is_overlay(window) {
+        Ok(())
+    } else {
+        Err(AppError::invalid(format!(
+            "窗口 {} 不能执行截图操作",
+            window.label()
+        )))
+    }
+}
+
+#[tauri::command]
+pub fn show_window_fullscreen(app: AppHandle, window: WebviewWindow) -> Result<(), AppError> {
+    ensure_capture_window(&window)?;
+    geometry::remember(&app, &window);
+    window.set_fullscreen(true)?;
+    window.set_decorations(false)?;
+    window.show()?;
+    Ok(window.set_focus()?)
+}
+
+#[tauri::command]
+pub fn restore_window(app: AppHandle, window: WebviewWindow) -> Result<(), AppError> {
+    ensure_capture_window(&window)?;
+    // 关闭其他显示器上的遮罩窗口
+    overlay::close_overlays(&app, Some(window.label()));
+    if overlay::is_overlay(&window) {
+        // 在额外窗口中完成截图时，主窗口的遮罩也一并收起
+        if let Some(main) = app.get_webview_window("main") {
+            let _ = main.set_fullscreen(false);
+            let _ = main.set_decorations(true);
+            let _ = geometry::restore(&app, &main);
+            let _ = main.hide();
+        }
+        return Ok(());
+    }
+    window.set_fullscreen(false)?;
+    window.set_decorations(true)?;
+    geometry::restore(&app, &window).map_err(AppError::window)
+}
+
+/// 取消当前截图：丢弃进行中的识别结果和缓存的截图，收起遮罩窗口
+#[tauri::command]
+pub fn cancel_capture(app: AppHandle, window: WebviewWindow) -> Result<(), AppError> {
+    session::cancel();
+    capture_id::forget();
+    restore_window(app.clone(), window.clone())?;
+    hide_window(app.clone(), window)?;
+    overlay::clear_captures(&app);
+    app.state::<CaptureCache>().clear();
+    let _ = std::fs::remove_file(capture::GNOME_SCREENSHOT_TMP);
+    Ok(app.emit("capture-cancelled", ())?)
+}
+
+/// capture_id 为 start-capture 事件中的编号，不传时沿用最近一次触发的编号
+#[tauri::command]
+pub async fn capture_screen_hidden(
+    window: WebviewWindow,
+    with_timings: Option<bool>,
+    capture_id: Option<CaptureId>,
+) -> Result<CaptureHandle, AppError> {
+    ensure_capture_window(&window)?;
+    session::begin();
+    let app = window.app_handle().clone();
+    let _busy = tray::busy(&app);
+    // 不经过遮罩时没有显示器信息，丢弃上次遮罩留下的换算依据
+    overlay::clear_captures(&app);
+    // 隐藏窗口
+    window.hide()?;
+    let events = CaptureEvents::start(&app, capture_id::take(capture_id));
+    run_blocking(move || {
+        let mut timings = Timings::default();
+        // 等待窗口完全隐藏（减少等待时间）
+        timings.time("hide_wait", || thread::sleep(Duration::from_millis(80)));
+        cache_capture(&app, timings, with_timings.unwrap_or(false), &events)
+    })
+    .await
+}
+
+/// 截图放入缓存后只返回 id 与尺寸，图片由 WebView 通过 prinsp:// 协议直接加载；
+/// with_timings 为 true 时附带各阶段耗时
+#[tauri::command]
+pub async fn capture_screen(
+    app: AppHandle,
+    with_timings: Option<bool>,
+    capture_id: Option<CaptureId>,
+) -> Result<CaptureHandle, AppError> {
+    let events = CaptureEvents::start(&app, capture_id::take(capture_id));
+    run_blocking(move || {
+        cache_capture(
+            &app,
+            Timings::default(),
+            with_timings.unwrap_or(false),
+            &events,
+        )
+    })
+    .await
+}
+
+/// 兼容仍按 base64 读取截图的前端，过渡期结束后移除
+#[tauri::command]
+pub async fn capture_screen_b64(app: AppHandle) -> Result<String, AppError> {
+    let events = CaptureEvents::start(&app, capture_id::take(None));
+    run_blocking(move || {
+        let mut timings = Timings::default();
+        let frame = capture::grab_screen(&mut timings, &Recording(&events));
+        events.finish(frame.as_ref().map(|_| None));
+        let frame = frame?.frame;
+        let png = timings.time("png_encode", || frame.into_png())?;
+        timings::finish(timings::Kind::Capture, &timings);
+        Ok(STANDARD.encode(png))
+    })
+    .await
+}
+
+/// 把各后端的尝试记入使用统计和错误记录，再交给界面的观察者
+pub(crate) struct Recording<'a>(pub &'a dyn CaptureObserver);
+
+impl CaptureObserver for Recording<'_> {
+    fn attempt(&self, backend: CaptureBackend, duration_ms: u64, error: Option<&AppError>) {
+        match error {
+            None => stats::record_capture(backend.name()),
+            Some(err) => error_report::record(Failure::new(
+                ErrorSource::Capture,
+                format!("{}:{}", err.code(), backend.name()),
+                err.message(),
+                err.to_string(),
+            )),
+        }
+        self.0.attempt(backend, duration_ms, error);
+    }
+}
+
+/// 截图放入缓存，结束时发出带缓存编号的 capture-finished
+fn cache_capture(
+    app: &AppHandle,
+    timings: Timings,
+    with_timings: bool,
+    events: &CaptureEvents,
+) -> Result<CaptureHandle, AppError> {
+    let result = grab_and_cache(app, timings, with_timings, events);
+    events.finish(result.as_ref().map(|capture| Some(capture.id)));
+    result
+}
+
+fn grab_and_cache(
+    app: &AppHandle,
+    mut timings: Timings,
+    with_timings: bool,
+    events: &CaptureEvents,
+) -> Result<CaptureHandle, AppError> {
+    let cache = app.state::<CaptureCache>();
+    cache.clear();
+    let result = capture::grab_screen(&mut timings, &Recording(events))?;
+    // grim 等后端已经是 PNG，只需读取尺寸
+    let stage = match result.frame {
+        Frame::Image(_) => "png_encode",
+        Frame::Png(_) => "cache",
+    };
+    let mut capture = timings.time(stage, || {
+        cache.insert(result.frame, events.id().clone(), result.night_light)
+    })?;
+    pre_encode::schedule(app, capture.id);
+    recovery::record_capture(app, &capture);
+    timings::finish(timings::Kind::Capture, &timings);
+    capture.timings = with_timings.then_some(timings);
+    Ok(capture)
+}
+
+/// 命令的原始字节参数（前端 invoke 时直接传 Uint8Array / ArrayBuffer）
+fn raw_body<'a>(request: &'a Request<'_>) -> Result<&'a [u8], AppError> {
+    match request.body() {
+        InvokeBody::Raw(data) => Ok(data),
+        InvokeBody::Json(_) => Err(AppError::invalid("需要以原始字节传入图片")),
+    }
+}
+
+/// 请求头 capture-id 中的截图编号
+fn request_capture_id(request: &Request<'_>) -> Result<Option<CaptureId>, AppError> {
+    request
+        .headers()
+        .get("capture-id")
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(CaptureId::parse)
+                .ok_or_else(|| AppError::invalid("capture-id 格式错误"))
+        })
+        .transpose()
+}
+
+/// 识别命令的返回值：默认只有文字；请求耗时（with_timings）、顺带翻译、图片被缩小或不是 PNG 时返回对象
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum OcrResponse {
+    Text(String),
+    Detailed(Box<OcrDetails>),
+}
+
+/// 以对象返回的识别结果，未请求或没有的项不输出
+#[derive(Serialize)]
+pub struct OcrDetails {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<Timings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    translation: Option<translate::Translation>,
+    /// 翻译整体失败（如未配置）时的原因，识别结果照常返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    translation_error: Option<translate::TranslateError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extraction: Option<extract::Extraction>,
+    /// 提取模板不存在或无效时的原因，识别结果照常返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extraction_error: Option<AppError>,
+    /// 与剪贴板文字的比较结果
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<text_diff::TextDiff>,
+    /// 剪贴板中没有文字等原因，识别结果照常返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff_error: Option<AppError>,
+    /// 以字节传入的图片识别出的格式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<image_input::InputFormat>,
+}
+
+impl OcrResponse {
+    /// and_translate 为 true 时把识别结果翻译到设置中的目标语言；extract_with 为提取模板名称；
+    /// reference 为识别前读取的剪贴板文字，有则与识别结果比较
+    async fn new(
+        app: &AppHandle,
+        recognized: ocr_queue::Recognized,
+        with_timings: bool,
+        and_translate: bool,
+        extract_with: Option<&str>,
+        reference: Option<Result<String, AppError>>,
+    ) -> Self {
+        // PNG 以外的图片也返回对象，附带识别出的格式
+        let other_format = recognized
+            .format
+            .is_some_and(|f| f != image_input::InputFormat::Png);
+        if !with_timings
+            && !and_translate
+            && extract_with.is_none()
+            && recognized.warning.is_none()
+            && !other_format
+            && reference.is_none()
+        {
+            return OcrResponse::Text(recognized.text);
+        }
+        let (extraction, extraction_error) =
+            match extract_with.map(|name| extract::extract(app, &recognized.text, name)) {
+                Some(Ok(extraction)) => (Some(extraction), None),
+                Some(Err(err)) => (None, Some(err)),
+                None => (None, None),
+            };
+        let (translation, translation_error) = if and_translate {
+            match translate::translate_to_default(app, &recognized.text).await {
+                Ok(translation) => (Some(translation), None),
+                Err(err) => (None, Some(err)),
+            }
+        } else {
+            (None, None)
+        };
+        let (diff, diff_error) = match reference {
+            Some(Ok(reference)) => (Some(text_diff::compare(&recognized.text, &reference)), None),
+            Some(Err(err)) => (None, Some(err)),
+            None => (None, None),
+        };
+        OcrResponse::Detailed(Box::new(OcrDetails {
+            text: recognized.text,
+            timings: with_timings.then_some(recognized.timings),
+            warning: recognized.warning,
+            translation,
+            translation_error,
+            extraction,
+            extraction_error,
+            format: recognized.format,
+            diff,
+            diff_error,
+        }))
+    }
+}
+
+/// 识别图片中的文字，图片以原始字节传入，支持 PNG、JPEG、WebP、BMP、GIF、TIFF（开启 heic 功能时还有 HEIC）；
+/// 请求头 with-timings: true 时附带耗时，ocr-and-translate: true 时同时返回译文，
+/// extract-with: <模板名称> 时同时提取字段，diff-clipboard: true 时与剪贴板文字比较，
+/// capture-id: <截图编号> 时 ocr-complete 事件带上该编号
+#[tauri::command]
+pub async fn ocr_image(app: AppHandle, request: Request<'_>) -> Result<OcrResponse, AppError> {
+    let data = raw_body(&request)?.to_vec();
+    let capture_id = request_capture_id(&request)?;
+    // 只读文件头，过小的选区不必排队解码
+    if let Some((width, height)) = image_input::dimensions(&data) {
+        check_ocr_size(width, height)?;
+    }
+    let header = |name: &str| request.headers().get(name).is_some_and(|v| v == "true");
+    let with_timings = header("with-timings");
+    let and_translate = header("ocr-and-translate");
+    let extract_with = request
+        .headers()
+        .get("extract-with")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string);
+    let diff_clipboard = header("diff-clipboard");
+    let (recognized, reference) = run_blocking({
+        let app = app.clone();
+        move || {
+            let reference = diff_clipboard.then(clipboard::get_text);
+            let recognized = ocr_interactive(&app, JobInput::Png(data), capture_id)?;
+            Ok((recognized, reference))
+        }
+    })
+    .await?;
+    Ok(OcrResponse::new(
+        &app,
+        recognized,
+        with_timings,
+        and_translate,
+        extract_with.as_deref(),
+        reference,
+    )
+    .await)
+}
+
+/// 兼容以 base64 传入图片的前端，过渡期结束后移除
+#[tauri::command]
+pub async fn ocr_image_b64(
+    app: AppHandle,
+    base64_data: String,
+    with_timings: Option<bool>,
+    ocr_and_translate: Option<bool>,
+    extract_with: Option<String>,
+    diff_clipboard: Option<bool>,
+    capture_id: Option<CaptureId>,
+) -> Result<OcrResponse, AppError> {
+    let (recognized, reference) = run_blocking({
+        let app = app.clone();
+        move || {
+            let data = STANDARD.decode(&base64_data).map_err(AppError::invalid)?;
+            let reference = diff_clipboard.unwrap_or(false).then(clipboard::get_text);
+            let recognized = ocr_interactive(&app, JobInput::Png(data), capture_id)?;
+            Ok((recognized, reference))
+        }
+    })
+    .await?;
+    Ok(OcrResponse::new(
+        &app,
+        recognized,
+        with_timings.unwrap_or(false),
+        ocr_and_translate.unwrap_or(false),
+        extract_with.as_deref(),
+        reference,
+    )
+    .await)
+}
+
+/// 识别缓存中截图的一个区域（截图像素坐标，超出部分被裁掉），省去前端裁剪、编码和传输 PNG；
+/// capture_id 为缓存编号或截图操作的编号
+#[tauri::command]
+pub async fn ocr_capture_region(
+    app: AppHandle,
+    capture_id: CaptureRef,
+    region: Option<launch::Region>,
+    with_timings: Option<bool>,
+    ocr_and_translate: Option<bool>,
+    extract_with: Option<String>,
+    diff_clipboard: Option<bool>,
+) -> Result<OcrResponse, AppError> {
+    let (recognized, reference) = run_blocking({
+        let app = app.clone();
+        move || {
+            let reference = diff_clipboard.unwrap_or(false).then(clipboard::get_text);
+            let cache = app.state::<CaptureCache>();
+            let recognized = recognize_cached(&cache, &capture_id, region, |input, id| {
+                ocr_interactive(&app, input, id)
+            })?;
+            Ok((recognized, reference))
+        }
+    })
+    .await?;
+    Ok(OcrResponse::new(
+        &app,
+        recognized,
+        with_timings.unwrap_or(false),
+        ocr_and_translate.unwrap_or(false),
+        extract_with.as_deref(),
+        reference,
+    )
+    .await)
+}
+
+/// 取缓存中截图（编辑后）的一个区域交给 recognize，并带上该截图操作的编号
+fn recognize_cached(
+    cache: &CaptureCache,
+    capture_id: &CaptureRef,
+    region: Option<launch::Region>,
+    recognize: impl FnOnce(JobInput, Option<CaptureId>) -> Result<ocr_queue::Recognized, AppError>,
+) -> Result<ocr_queue::Recognized, AppError> {
+    let id = cache.resolve(capture_id)?;
+    let mut image = cache.image(id)?;
+    if let Some(r) = region {
+        let cropped = image.crop_imm(r.x, r.y, r.width, r.height);
+        if cropped.width() == 0 || cropped.height() == 0 {
+            return Err(AppError::invalid("识别区域为空"));
+        }
+        image = Arc::new(cropped);
+    }
+    recognize(JobInput::Image(image), cache.capture_id(id))
+}
+
+/// 交互式识别，结束后发出带截图编号的 ocr-complete 事件
+fn ocr_interactive(
+    app: &AppHandle,
+    input: JobInput,
+    capture_id: Option<CaptureId>,
+) -> Result<ocr_queue::Recognized, AppError> {
+    let result = recognize_interactive(app, input);
+    capture_id::ocr_complete(app, capture_id.as_ref(), &result);
+    if let (Some(id), Ok(recognized)) = (&capture_id, &result) {
+        app.state::<HistoryState>()
+            .remember_confidence(id, recognized.confidence);
+    }
+    result
+}
+
+/// 交互式识别排在后台识别任务之前
+fn recognize_interactive(
+    app: &AppHandle,
+    input: JobInput,
+) -> Result<ocr_queue::Recognized, AppError> {
+    let session = session::current();
+    let _busy = tray::busy(app);
+    let lang = app.state::<settings::SettingsState>().get().ocr_lang;
+    let recognized = app
+        .state::<ocr_queue::OcrQueue>()
+        .recognize(input, lang, ocr_queue::JobPriority::High, Some(session))
+        .inspect_err(|err| {
+            if !matches!(err, AppError::Cancelled) {
+                // 错误由前端显示，这里只更新托盘并记录
+                error_report::report(
+                    app,
+                    Failure {
+                        notify: false,
+                        ..Failure::new(ErrorSource::Ocr, err.code(), "文字识别失败", err.message())
+                    },
+                );
+            }
+        })?;
+    // 识别期间截图已被取消
+    if !session::is_current(session) {
+        return Err(AppError::Cancelled);
+    }
+    let text = &recognized.text;
+    if !text.is_empty() {
+        let preview: String = text.chars().take(120).collect();
+        let mut notice = notify::Notice::new(notify::NotifyEvent::Ocr, "文字识别完成", preview);
+        notice.copy = Some(notify::CopyPayload::Text(text.clone()));
+        notify::notify(app, notice);
+    }
+    let result = if text.is_empty() {
+        "result.no_text"
+    } else {
+        "result.recognized"
+    };
+    tray::record(app, "tray.last_ocr", result, true);
+    webhook::fire(
+        app,
+        webhook::WebhookEvent::OcrComplete,
+        webhook::Payload {
+            text: Some(recognized.text.clone()),
+            ..Default::default()
+        },
+    );
+    Ok(recognized)
+}
+
+#[tauri::command]
+pub fn copy_text_to_clipboard(text: String) -> Result<(), AppError> {
+    clipboard::set_text(text)
+}
+
+#[tauri::command]
+pub async fn read_clipboard_text() -> Result<String, AppError> {
+    run_blocking(clipboard::get_text).await
+}
+
+/// 图片以原始 PNG 字节传入；请求体为空并带有 capture-id 请求头时，复制缓存中该截图编辑后的当前状态
+#[tauri::command]
+pub async fn copy_to_clipboard(app: AppHandle, request: Request<'_>) -> Result<(), AppError> {
+    let data = raw_body(&request)?.to_vec();
+    let capture_id = request_capture_id(&request)?;
+    run_blocking(move || {
+        copy_image(
+            &app.state::<CaptureCache>(),
+            &data,
+            capture_id,
+            clipboard::set_image,
+        )
+    })
+    .await
+}
+
+/// 把 PNG 字节交给 set_image；字节为空并给出截图编号时改用缓存中该截图的当前状态
+fn copy_image(
+    cache: &CaptureCache,
+    data: &[u8],
+    capture_id: Option<CaptureId>,
+    set_image: impl FnOnce(&image::DynamicImage) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    let img = match capture_id {
+        Some(capture_id) if data.is_empty() => {
+            cache.image(cache.resolve(&CaptureRef::Capture(capture_id))?)?
+        }
+        _ => Arc::new(image::load_from_memory(data).map_err(AppError::invalid)?),
+    };
+    set_image(&img)
+}
+
+/// 兼容以 base64 传入图片的前端，过渡期结束后移除
+#[tauri::command]
+pub async fn copy_to_clipboard_b64(base64_data: String) -> Result<(), AppError> {
+    run_blocking(move || {
+        let data = STANDARD.decode(&base64_data).map_err(AppError::invalid)?;
+        let img = image::load_from_memory(&data).map_err(AppError::invalid)?;
+        clipboard::set_image(&img)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn save_image_to_file(
+    app: AppHandle,
+    base64_data: String,
+    path: String,
+    quality: Option<u8>,
+) -> Result<(), String> {
+    let data = STANDARD.decode(&base64_data).map_err(|e| e.to_string())?;
+    let img = image::load_from_memory(&data).map_err(|e| e.to_string())?;
+    let save_path = Path::new(&path);
+    let format = save::ExportFormat::from_path(save_path)?;
+    let quality = quality.unwrap_or(save::DEFAULT_QUALITY);
+    let encoded = save::encode_in_background(&app, img, format, quality).await?;
+    durable::atomic_write(save_path, &encoded)?;
+    save::remember_dir(save_path);
+    webhook::fire(
+        &app,
+        webhook::WebhookEvent::CaptureSaved,
+        webhook::Payload {
+            path: Some(path.clone()),
+            image: Some((&encoded, format)),
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edits::{self, EditOp};
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use serde_json::json;
+
+    /// 记下每次尝试的观察者
+    #[derive(Default)]
+    struct Attempts(std::sync::Mutex<Vec<(CaptureBackend, bool)>>);
+
+    impl CaptureObserver for Attempts {
+        fn attempt(&self, backend: CaptureBackend, _duration_ms: u64, error: Option<&AppError>) {
+            self.0.lock().unwrap().push((backend, error.is_none()));
+        }
+    }
+
+    #[test]
+    fn recording_reports_failures_and_forwards_attempts() {
+        let attempts = Attempts::default();
+        let recording = Recording(&attempts);
+        let err = AppError::Timeout {
+            what: "recording-test".into(),
+            ms: 7,
+        };
+        recording.attempt(CaptureBackend::GnomeScreenshot, 7, Some(&err));
+        recording.attempt(CaptureBackend::Xcap, 3, None);
+
+        // 失败记入最近的错误，成功的尝试不记录
+        let code = format!("{}:gnome-screenshot", err.code());
+        let recent = error_report::get_recent_errors();
+        let recorded = recent.iter().find(|e| e.code == code).unwrap();
+        assert!(recorded.detail.contains("recording-test"));
+        assert!(!recent.iter().any(|e| e.code.ends_with(":xcap")));
+        assert_eq!(
+            *attempts.0.lock().unwrap(),
+            [
+                (CaptureBackend::GnomeScreenshot, false),
+                (CaptureBackend::Xcap, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn one_capture_id_follows_crop_ocr_and_copy() {
+        let cache = CaptureCache::default();
+        let capture_id = CaptureId::generate();
+        let image = RgbaImage::from_fn(40, 30, |x, _| Rgba([x as u8 * 6, 0, 0, 255]));
+        let frame = Frame::Image(DynamicImage::ImageRgba8(image));
+        let handle = cache.insert(frame, capture_id.clone(), None).unwrap();
+        // 同时进行的另一次截图不受影响
+        let other_id = CaptureId::generate();
+        let blank = Frame::Image(DynamicImage::new_rgba8(5, 5));
+        let other = cache.insert(blank, other_id.clone(), None).unwrap();
+
+        // 裁剪：前端以截图编号指定截图
+        let by_capture: CaptureRef = serde_json::from_value(json!(capture_id)).unwrap();
+        let region = launch::Region {
+            x: 10,
+            y: 5,
+            width: 20,
+            height: 10,
+        };
+        let state = edits::edit_cached(&cache, &by_capture, EditOp::Crop { region }).unwrap();
+        assert_eq!(state.capture.id, handle.id);
+
+        // 识别：假的识别后端收到裁剪后截图中的区域和同一编号
+        let region = launch::Region {
+            x: 2,
+            y: 0,
+            width: 100,
+            height: 4,
+        };
+        let recognized = recognize_cached(&cache, &by_capture, Some(region), |input, id| {
+            let JobInput::Image(image) = input else {
+                panic!("应直接传入图片");
+            };
+            assert_eq!((image.width(), image.height()), (18, 4));
+            assert_eq!(id.as_ref(), Some(&capture_id));
+            Ok(ocr_queue::Recognized {
+                text: format!("{}", image.to_rgba8().get_pixel(0, 0)[0]),
+                warning: None,
+                confidence: Some(90.0),
+                timings: Timings::default(),
+                format: None,
+            })
+        })
+        .unwrap();
+        assert_eq!(recognized.text, "72");
+        let empty = launch::Region {
+            x: 30,
+            y: 0,
+            width: 5,
+            height: 5,
+        };
+        let err = recognize_cached(&cache, &by_capture, Some(empty), |_, _| unreachable!());
+        assert!(matches!(err.err().unwrap(), AppError::InvalidInput(_)));
+
+        // 复制：请求体为空时按 capture-id 请求头复制裁剪后的状态，假的剪贴板记下图片
+        let header = CaptureId::parse(&capture_id.to_string()).unwrap();
+        let mut copied = None;
+        copy_image(&cache, &[], Some(header), |img| {
+            copied = Some(img.to_rgba8());
+            Ok(())
+        })
+        .unwrap();
+        let copied = copied.unwrap();
+        assert_eq!(copied.dimensions(), (20, 10));
+        assert_eq!(copied.get_pixel(0, 0)[0], 60);
+        // 请求体不为空时复制传入的图片
+        let png = crate::frame::encode_png(&DynamicImage::new_rgba8(3, 2)).unwrap();
+        copy_image(&cache, &png, Some(other_id.clone()), |img| {
+            assert_eq!(img.width(), 3);
+            Ok(())
+        })
+        .unwrap();
+        let unknown = Some(CaptureId::generate());
+        assert!(copy_image(&cache, &[], unknown, |_| unreachable!()).is_err());
+
+        assert_eq!(cache.capture_id(other.id), Some(other_id));
+        assert_eq!(cache.image(other.id).unwrap().width(), 5);
+        let by_cache: CaptureRef = serde_json::from_value(json!(other.id)).unwrap();
+        assert_eq!(cache.resolve(&by_cache).unwrap(), other.id);
+    }
+
+    #[test]
+    fn ocr_response_is_text_or_an_object_of_present_fields() {
+        let text = OcrResponse::Text("hello".into());
+        assert_eq!(serde_json::to_value(text).unwrap(), json!("hello"));
+        let detailed = OcrResponse::Detailed(Box::new(OcrDetails {
+            text: "hello".into(),
+            timings: None,
+            warning: Some("已缩小".into()),
+            translation: None,
+            translation_error: None,
+            extraction: None,
+            extraction_error: None,
+            diff: None,
+            diff_error: None,
+            format: None,
+        }));
+        assert_eq!(
+            serde_json::to_value(detailed).unwrap(),
+            json!({ "text": "hello", "warning": "已缩小" })
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::ocr_queue::{JobInput, JobPriority, OcrQueue};
+use crate::save::{self, ExportFormat};
+use crate::settings::SettingsState;
+use crate::trigger::{CaptureTrigger, TriggerSource};
+
+/// 会话总线上的服务名称与对象路径
+pub const BUS_NAME: &str = "io.github.prinsp.Prinsp";
+const OBJECT_PATH: &str = "/io/github/prinsp/Prinsp";
+
+/// 正在提供服务的连接；关闭设置时释放名称并断开
+#[derive(Default)]
+pub struct DbusService(Mutex<Option<zbus::blocking::Connection>>);
+
+/// 返回给调用方的 D-Bus 错误，名称为 io.github.prinsp.Prinsp.Error.<变体名>
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "io.github.prinsp.Prinsp.Error")]
+pub enum DbusError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    NoDisplay(String),
+    BackendFailed(String),
+    TesseractMissing(String),
+    LanguageMissing(String),
+    OcrFailed(String),
+    ClipboardUnavailable(String),
+    Timeout(String),
+    Cancelled(String),
+    InvalidArgs(String),
+    Failed(String),
+}
+
+impl From<AppError> for DbusError {
+    fn from(err: AppError) -> Self {
+        let message = match err.detail() {
+            Some(detail) => format!("{}: {detail}", err.message()),
+            None => err.message(),
+        };
+        match err {
+            AppError::NoDisplay => DbusError::NoDisplay(message),
+            AppError::BackendFailed { .. } => DbusError::BackendFailed(message),
+            AppError::TesseractMissing | AppError::TesseractTooOld { .. } => {
+                DbusError::TesseractMissing(message)
+            }
+            AppError::LanguageMissing { .. } => DbusError::LanguageMissing(message),
+            AppError::OcrFailed(_) => DbusError::OcrFailed(message),
+            AppError::ClipboardUnavailable(_) => DbusError::ClipboardUnavailable(message),
+            AppError::Timeout { .. } => DbusError::Timeout(message),
+            AppError::Cancelled => DbusError::Cancelled(message),
+            AppError::InvalidInput(_) | AppError::UnsupportedFormat { .. } => {
+                DbusError::InvalidArgs(message)
+            }
+            AppError::Window(_) | AppError::Io(_) | AppError::Internal(_) => {
+                DbusError::Failed(message)
+            }
+        }
+    }
+}
+
+struct Service {
+    app: AppHandle,
+}
+
+/// 空字符串表示整个屏幕，其余按显示器 id 或名称查找
+fn resolve_monitor(monitor: &str) -> Result<Option<u32>, AppError> {
+    let monitor = monitor.trim();
+    if monitor.is_empty() {
+        return Ok(None);
+    }
+    if let Ok(id) = monitor.parse() {
+        return Ok(Some(id));
+    }
+    crate::overlay::list_monitors()
+        .map_err(AppError::Internal)?
+        .into_iter()
+        .find(|m| m.name == monitor || m.mirrors.iter().any(|name| name == monitor))
+        .map(|m| Some(m.id))
+        .ok_or_else(|| AppError::invalid(format!("未找到显示器: {monitor}")))
+}
+
+#[zbus::interface(name = "io.github.prinsp.Prinsp")]
+impl Service {
+    /// 截取整个屏幕或指定显示器并复制到剪贴板，不显示界面。
+    /// monitor 为显示器 id 或名称，空字符串表示整个屏幕。
+    async fn capture_to_clipboard(&self, monitor: String) -> Result<(), DbusError> {
+        let app = self.app.clone();
+        crate::run_blocking(move || {
+            let monitor = resolve_monitor(&monitor)?;
+            crate::quick::quick_capture(&app, monitor, None).map_err(AppError::Internal)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// 截取整个屏幕并保存到 path（必须是绝对路径，格式按扩展名决定），返回保存的路径。
+    #[zbus(out_args("path"))]
+    async fn capture_to_file(&self, path: String) -> Result<String, DbusError> {
+        let app = self.app.clone();
+        let path = crate::run_blocking(move || {
+            let path = PathBuf::from(path);
+            if !path.is_absolute() {
+                return Err(AppError::invalid("需要绝对路径"));
+            }
+            let format = ExportFormat::from_path(&path).map_err(AppError::invalid)?;
+            let frame =
+                crate::quick::capture_without_windows(&app, None).map_err(AppError::Internal)?;
+            let data = match format {
+                ExportFormat::Png => frame.into_png()?,
+                format => save::encode_image(&frame.into_image()?, format, save::DEFAULT_QUALITY)
+                    .map_err(AppError::Internal)?,
+            };
+            crate::durable::atomic_write(&path, &data).map_err(AppError::Internal)?;
+            tracing::info!(path = %path.display(), "已通过 D-Bus 截图保存");
+            Ok(path)
+        })
+        .await?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// 截取整个屏幕，识别其中 x、y、width、height 指定的区域（截图像素坐标），返回识别出的文字。
+    #[zbus(out_args("text"))]
+    async fn ocr_region(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<String, DbusError> {
+        let app = self.app.clone();
+        let region = Region {
+            x,
+            y,
+            width,
+            height,
+        };
+        let recognized = crate::run_blocking(move || {
+            let frame =
+                crate::quick::capture_without_windows(&app, None).map_err(AppError::Internal)?;
+            let image = crate::headless::crop(frame, region)?.into_image()?;
+            let lang = app.state::<SettingsState>().get().ocr_lang;
+            app.state::<OcrQueue>().recognize(
+                JobInput::Image(Arc::new(image)),
+                lang,
+                JobPriority::High,
+                None,
+            )
+        })
+        .await?;
+        Ok(recognized.text)
+    }
+
+    /// 开始交互式截图，与按下截图快捷键相同；立即返回，不等待截图完成。
+    async fn trigger_interactive_capture(&self) {
+        CaptureTrigger::new(TriggerSource::Dbus, "capture").send(&self.app);
+    }
+}
+
+fn connect(app: &AppHandle) -> zbus::Result<zbus::blocking::Connection> {
+    zbus::blocking::connection::Builder::session()?
+        .serve_at(OBJECT_PATH, Service { app: app.clone() })?
+        .name(BUS_NAME)?
+        .build()
+}
+
+/// 按设置开启或关闭服务；在后台线程连接总线，不阻塞调用方
+pub fn apply(app: &AppHandle, enabled: bool) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let state = app.state::<DbusService>();
+        let mut slot = state.0.lock().unwrap_or_else(|e| e.into_inner());
+        if !enabled {
+            if let Some(connection) = slot.take() {
+                let _ = connection.release_name(BUS_NAME);
+                tracing::info!("已停止 D-Bus 服务");
+            }
+            return;
+        }
+        if slot.is_some() {
+            return;
+        }
+        match connect(&app) {
+            Ok(connection) => {
+                tracing::info!(name = BUS_NAME, "已启动 D-Bus 服务");
+                *slot = Some(connection);
+            }
+            // 另一个实例（如不同配置目录下的开发版本）已注册，本实例不提供服务
+            Err(zbus::Error::NameTaken) => {
+                tracing::warn!(name = BUS_NAME, "D-Bus 名称已被占用，不启动服务");
+            }
+            Err(err) => tracing::warn!(error = %err, "无法启动 D-Bus 服务"),
+        }
+    });
+}
+
+/// 启动时按设置开启服务
+pub fn start(app: &AppHandle) {
+    app.manage(DbusService::default());
+    if app.state::<SettingsState>().get().dbus_service {
+        apply(app, true);
+    }
+}
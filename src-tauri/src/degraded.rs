@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::webview::{PageLoadEvent, PageLoadPayload};
+use tauri::{AppHandle, Manager, Webview};
+
+use crate::i18n::{t, tf};
+use crate::notify::{self, Notice, NotifyEvent};
+
+/// 启动后等待主窗口前端握手（get_launch_info）的时长，WebKitGTK 冷启动可能较慢
+const READY_TIMEOUT: Duration = Duration::from_secs(20);
+/// 页面已加载完成后再等前端握手的时长
+const LOADED_GRACE: Duration = Duration::from_secs(5);
+
+static READY: AtomicBool = AtomicBool::new(false);
+static DEGRADED: AtomicBool = AtomicBool::new(false);
+
+/// 主窗口界面无法使用，托盘和快捷键只提供不经过前端的操作
+pub fn active() -> bool {
+    DEGRADED.load(Ordering::Relaxed)
+}
+
+/// 启动时调用：超时仍未收到前端握手时切换到简化模式
+pub fn watch(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(READY_TIMEOUT);
+        if !READY.load(Ordering::Relaxed) {
+            enter(&app, t("degraded.reason_timeout"));
+        }
+    });
+}
+
+/// Builder::on_page_load 回调：主窗口页面加载完成后前端仍没有握手，说明脚本没能运行
+pub fn on_page_load(webview: &Webview, payload: &PageLoadPayload<'_>) {
+    if webview.label() != "main" || !matches!(payload.event(), PageLoadEvent::Finished) {
+        return;
+    }
+    let app = webview.app_handle().clone();
+    thread::spawn(move || {
+        thread::sleep(LOADED_GRACE);
+        if !READY.load(Ordering::Relaxed) {
+            enter(&app, t("degraded.reason_script"));
+        }
+    });
+}
+
+/// 主窗口前端完成初始化；之前已切换到简化模式时恢复完整菜单
+pub fn frontend_ready(app: &AppHandle) {
+    READY.store(true, Ordering::Relaxed);
+    if DEGRADED.swap(false, Ordering::Relaxed) {
+        tracing::info!("界面已恢复，退出简化模式");
+        crate::tray::set_degraded(app, false);
+    }
+}
+
+fn enter(app: &AppHandle, reason: String) {
+    if DEGRADED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    tracing::warn!(reason = %reason, "界面无法加载，切换到简化模式");
+    crate::tray::set_degraded(app, true);
+    notify::notify(
+        app,
+        Notice::new(
+            NotifyEvent::Hint,
+            t("degraded.title"),
+            tf("degraded.body", &[("reason", &reason)]),
+        ),
+    );
+}
@@ -0,0 +1,312 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use xcap::Monitor;
+
+use crate::backend_health;
+use crate::capture::CaptureBackend;
+use crate::dnd::{DndState, DndStatus};
+use crate::external::{run_external, ExternalTool, PROBE_TIMEOUT};
+use crate::i18n::{t, tf};
+use crate::notify::{self, Notice, NotifyEvent};
+use crate::warmup::WarmupReport;
+
+#[derive(Clone, Serialize)]
+pub struct BackendProbe {
+    pub name: String,
+    /// 是否在设置中启用
+    pub enabled: bool,
+    pub available: bool,
+    /// 不可用的原因或探测到的信息
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub available: bool,
+    pub version: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DirInfo {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub writable: bool,
+}
+
+/// 排查问题所需的环境信息
+#[derive(Serialize)]
+pub struct DiagnosticsReport {
+    pub app_version: String,
+    /// wayland / x11 / unknown
+    pub session_type: String,
+    pub desktop: Option<String>,
+    pub backends: Vec<BackendProbe>,
+    pub preferred_backend: Option<String>,
+    pub tesseract: ToolInfo,
+    pub ocr_languages: Vec<String>,
+    pub tessdata_prefix: Option<String>,
+    /// 本次启动的识别预热，未启用或尚未执行时为空
+    pub ocr_warmup: Option<WarmupReport>,
+    pub dnd: DndState,
+    pub clipboard_tools: Vec<ToolInfo>,
+    pub directories: Vec<DirInfo>,
+}
+
+/// 只检查是否可用，不真正截图（gnome-screenshot 会闪屏并播放快门声）
+fn probe_backend(backend: CaptureBackend, enabled: bool) -> BackendProbe {
+    let result = match backend {
+        CaptureBackend::Grim if !ExternalTool::Grim.available() => Err("未安装 grim".to_string()),
+        CaptureBackend::Grim if std::env::var("WAYLAND_DISPLAY").is_err() => {
+            Err("不是 Wayland 会话".to_string())
+        }
+        CaptureBackend::Grim => Ok("已安装".to_string()),
+        CaptureBackend::Xcap => match Monitor::all() {
+            Ok(monitors) if monitors.is_empty() => Err("未找到显示器".to_string()),
+            Ok(monitors) => Ok(format!("{} 个显示器", monitors.len())),
+            Err(err) => Err(err.to_string()),
+        },
+        CaptureBackend::GnomeScreenshot if ExternalTool::GnomeScreenshot.available() => {
+            Ok("已安装".to_string())
+        }
+        CaptureBackend::GnomeScreenshot => Err("未安装 gnome-screenshot".to_string()),
+    };
+    BackendProbe {
+        name: backend.name().to_string(),
+        enabled,
+        available: result.is_ok(),
+        detail: result.unwrap_or_else(|err| err),
+    }
+}
+
+/// 已启用的按优先级在前，禁用的排在后面
+pub fn probe_backends() -> Vec<BackendProbe> {
+    let enabled = crate::capture::capture_config().enabled_backends();
+    let disabled = CaptureBackend::ALL
+        .into_iter()
+        .filter(|b| !enabled.contains(b));
+    enabled
+        .iter()
+        .map(|b| probe_backend(*b, true))
+        .chain(disabled.map(|b| probe_backend(b, false)))
+        .collect()
+}
+
+fn tool_info(tool: ExternalTool, version_arg: Option<&str>) -> ToolInfo {
+    let available = tool.available();
+    let version = version_arg
+        .filter(|_| available)
+        .and_then(|arg| run_external(tool.clone(), [arg], None, PROBE_TIMEOUT).ok())
+        .and_then(|out| out.text().lines().next().map(|l| l.trim().to_string()));
+    ToolInfo {
+        name: tool.program().to_string(),
+        available,
+        version,
+    }
+}
+
+/// 使用识别时缓存的检测结果
+fn tesseract_info() -> ToolInfo {
+    let info = crate::tesseract::detected();
+    ToolInfo {
+        name: "tesseract".into(),
+        available: info.is_some(),
+        version: info.map(|info| info.version_line),
+    }
+}
+
+/// 尝试在目录中写入临时文件
+fn writable(dir: &Path) -> bool {
+    if std::fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+    let probe = dir.join(".prinsp-write-test");
+    let ok = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    ok
+}
+
+fn dir_info(name: &str, path: tauri::Result<PathBuf>) -> DirInfo {
+    let path = path.ok();
+    DirInfo {
+        name: name.to_string(),
+        writable: path.as_deref().is_some_and(writable),
+        path,
+    }
+}
+
+fn collect(app: &AppHandle) -> DiagnosticsReport {
+    let paths = app.path();
+    DiagnosticsReport {
+        app_version: app.package_info().version.to_string(),
+        session_type: backend_health::session_type(),
+        desktop: std::env::var("XDG_CURRENT_DESKTOP").ok(),
+        backends: probe_backends(),
+        preferred_backend: crate::capture::get_preferred_backend().map(|b| b.name().to_string()),
+        tesseract: tesseract_info(),
+        ocr_languages: crate::tesseract::languages(),
+        tessdata_prefix: std::env::var("TESSDATA_PREFIX").ok(),
+        ocr_warmup: crate::warmup::report(),
+        dnd: crate::dnd::state(app),
+        clipboard_tools: vec![
+            tool_info(ExternalTool::WlCopy, Some("--version")),
+            tool_info(ExternalTool::Xclip, Some("-version")),
+        ],
+        directories: vec![
+            dir_info("config", paths.app_config_dir()),
+            dir_info("data", paths.app_data_dir()),
+        ],
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "✅"
+    } else {
+        "❌"
+    }
+}
+
+/// 渲染成可以直接贴到 issue 里的 Markdown
+fn to_markdown(report: &DiagnosticsReport) -> String {
+    let mut md = String::from("### PrinSp 诊断信息\n\n");
+    md.push_str(&format!("- 版本: {}\n", report.app_version));
+    md.push_str(&format!("- 会话类型: {}\n", report.session_type));
+    md.push_str(&format!(
+        "- 桌面环境: {}\n",
+        report.desktop.as_deref().unwrap_or("未知")
+    ));
+    md.push_str(&format!(
+        "- 当前截图后端: {}\n",
+        report.preferred_backend.as_deref().unwrap_or("无")
+    ));
+    let dnd = match report.dnd.status {
+        DndStatus::Active if report.dnd.suppressing => "开启（不通知）",
+        DndStatus::Active => "开启（始终通知）",
+        DndStatus::Inactive => "关闭",
+        DndStatus::Unknown => "未知",
+    };
+    md.push_str(&format!("- 勿扰模式: {dnd}（{}）\n", report.dnd.source));
+
+    md.push_str(
+        "\n#### 截图后端\n\n| 后端 | 启用 | 可用 | 说明 |\n|------|------|------|------|\n",
+    );
+    for probe in &report.backends {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            probe.name,
+            yes_no(probe.enabled),
+            yes_no(probe.available),
+            probe.detail
+        ));
+    }
+
+    md.push_str("\n#### OCR\n\n");
+    md.push_str(&format!(
+        "- tesseract: {} {}\n",
+        yes_no(report.tesseract.available),
+        report.tesseract.version.as_deref().unwrap_or_default()
+    ));
+    md.push_str(&format!(
+        "- 已安装语言: {}\n",
+        if report.ocr_languages.is_empty() {
+            "无".to_string()
+        } else {
+            report.ocr_languages.join(", ")
+        }
+    ));
+    md.push_str(&format!(
+        "- TESSDATA_PREFIX: {}\n",
+        report.tessdata_prefix.as_deref().unwrap_or("未设置")
+    ));
+    let warmup = match &report.ocr_warmup {
+        None => "未执行".to_string(),
+        Some(WarmupReport::Done { lang, duration_ms }) => format!("{duration_ms} ms（{lang}）"),
+        Some(WarmupReport::Skipped { reason }) => format!("已跳过：{reason}"),
+        Some(WarmupReport::Failed { lang, error }) => format!("失败（{lang}）：{error}"),
+    };
+    md.push_str(&format!("- 启动预热: {warmup}\n"));
+
+    md.push_str("\n#### 剪贴板工具\n\n");
+    for tool in &report.clipboard_tools {
+        md.push_str(&format!(
+            "- {}: {} {}\n",
+            tool.name,
+            yes_no(tool.available),
+            tool.version.as_deref().unwrap_or_default()
+        ));
+    }
+
+    md.push_str("\n#### 目录\n\n");
+    for dir in &report.directories {
+        let path = dir
+            .path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "未知".into());
+        md.push_str(&format!(
+            "- {}: `{}` 可写 {}\n",
+            dir.name,
+            path,
+            yes_no(dir.writable)
+        ));
+    }
+    md
+}
+
+/// 诊断信息复制到剪贴板并发出通知，界面无法加载时由托盘调用
+pub fn spawn_notify(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let report = collect(&app);
+        let copied = crate::clipboard::set_text(to_markdown(&report)).is_ok();
+        let mut body = format!(
+            "{} · {}",
+            report.session_type,
+            report.desktop.as_deref().unwrap_or("?")
+        );
+        let unavailable: Vec<&str> = report
+            .backends
+            .iter()
+            .filter(|probe| probe.enabled && !probe.available)
+            .map(|probe| probe.name.as_str())
+            .collect();
+        if !unavailable.is_empty() {
+            body.push('\n');
+            body.push_str(&tf(
+                "diagnostics.unavailable_backends",
+                &[("names", &unavailable.join(", "))],
+            ));
+        }
+        if copied {
+            body.push('\n');
+            body.push_str(&t("diagnostics.copied"));
+        }
+        notify::notify(
+            &app,
+            Notice::new(NotifyEvent::Hint, t("diagnostics.title"), body),
+        );
+    });
+}
+
+#[tauri::command]
+pub fn probe_capture_backends() -> Vec<BackendProbe> {
+    probe_backends()
+}
+
+#[tauri::command]
+pub async fn run_diagnostics(app: AppHandle) -> Result<DiagnosticsReport, String> {
+    tauri::async_runtime::spawn_blocking(move || collect(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 复制 Markdown 格式的诊断信息，返回复制的内容
+#[tauri::command]
+pub async fn copy_diagnostics_to_clipboard(app: AppHandle) -> Result<String, String> {
+    let report = run_diagnostics(app).await?;
+    let markdown = to_markdown(&report);
+    crate::clipboard::set_text(markdown.clone())?;
+    Ok(markdown)
+}
@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+/// 检测结果保留这么久，连续的通知和提示音不必每次都查询
+const CACHE_TTL: Duration = Duration::from_secs(5);
+
+static CACHE: Mutex<Option<(Instant, DndStatus, &'static str)>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DndStatus {
+    Active,
+    Inactive,
+    /// 桌面不提供勿扰状态或无法读取，按未开启处理
+    Unknown,
+}
+
+/// 桌面的勿扰状态，诊断面板中显示
+#[derive(Clone, Debug, Serialize)]
+pub struct DndState {
+    pub status: DndStatus,
+    /// 读取来源：notifications（通知服务的 Inhibited 属性，KDE 等）/ gsettings（GNOME）/ none
+    pub source: &'static str,
+    /// 设置中开启了“始终通知”
+    pub always_notify: bool,
+    /// 当前是否因勿扰而不弹出通知、不播放提示音
+    pub suppressing: bool,
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use zbus::blocking::{Connection, Proxy};
+
+    use super::DndStatus;
+    use crate::external::{run_external, ExternalTool, PROBE_TIMEOUT};
+
+    fn status(active: bool) -> DndStatus {
+        if active {
+            DndStatus::Active
+        } else {
+            DndStatus::Inactive
+        }
+    }
+
+    /// KDE 等通知服务提供的 Inhibited 属性；GNOME Shell 没有，调用失败
+    pub fn from_notifications() -> Option<DndStatus> {
+        let connection = Connection::session().ok()?;
+        let proxy = Proxy::new(
+            &connection,
+            "org.freedesktop.Notifications",
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.Notifications",
+        )
+        .ok()?;
+        proxy.get_property::<bool>("Inhibited").ok().map(status)
+    }
+
+    /// GNOME 的勿扰开关即关闭通知横幅
+    pub fn from_gsettings() -> Option<DndStatus> {
+        let output = run_external(
+            ExternalTool::Gsettings,
+            ["get", "org.gnome.desktop.notifications", "show-banners"],
+            None,
+            PROBE_TIMEOUT,
+        )
+        .ok()?;
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "true" => Some(status(false)),
+            "false" => Some(status(true)),
+            _ => None,
+        }
+    }
+}
+
+fn detect() -> (DndStatus, &'static str) {
+    #[cfg(target_os = "linux")]
+    let detected = linux::from_notifications()
+        .map(|status| (status, "notifications"))
+        .or_else(|| linux::from_gsettings().map(|status| (status, "gsettings")));
+    #[cfg(not(target_os = "linux"))]
+    let detected = None;
+    let detected = detected.unwrap_or((DndStatus::Unknown, "none"));
+    if let Ok(mut cache) = CACHE.lock() {
+        *cache = Some((Instant::now(), detected.0, detected.1));
+    }
+    detected
+}
+
+fn cached() -> (DndStatus, &'static str) {
+    match CACHE.lock().ok().and_then(|cache| *cache) {
+        Some((at, status, source)) if at.elapsed() < CACHE_TTL => (status, source),
+        _ => detect(),
+    }
+}
+
+/// 桌面当前处于勿扰模式；可能查询 D-Bus 或 gsettings，不要在主线程调用
+pub fn is_active() -> bool {
+    cached().0 == DndStatus::Active
+}
+
+fn always_notify(app: &AppHandle) -> bool {
+    app.try_state::<SettingsState>()
+        .is_some_and(|settings| settings.get().notifications.always_notify)
+}
+
+/// 是否不弹出通知：处于勿扰模式且未开启“始终通知”
+pub fn suppressing(app: &AppHandle) -> bool {
+    !always_notify(app) && is_active()
+}
+
+/// 重新读取桌面的勿扰状态
+pub fn state(app: &AppHandle) -> DndState {
+    let (status, source) = detect();
+    let always_notify = always_notify(app);
+    DndState {
+        status,
+        source,
+        always_notify,
+        suppressing: status == DndStatus::Active && !always_notify,
+    }
+}
+
+#[tauri::command]
+pub async fn get_dnd_state(app: AppHandle) -> Result<DndState, AppError> {
+    crate::run_blocking(move || Ok(state(&app))).await
+}
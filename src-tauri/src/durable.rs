@@ -0,0 +1,362 @@
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// 临时文件编号，同一文件的并发写入各用各的临时文件
+static TMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 同目录下的临时文件，以 . 开头避免出现在文件管理器中
+fn sibling(path: &Path, prefix: &str, suffix: &str) -> Result<PathBuf, String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("无效路径: {}", path.display()))?;
+    let mut name = OsString::from(prefix);
+    name.push(file_name);
+    name.push(suffix);
+    Ok(path.with_file_name(name))
+}
+
+/// 写入用的临时文件：.settings.json.<进程号>-<编号>.tmp
+fn tmp_path(path: &Path) -> Result<PathBuf, String> {
+    let seq = TMP_SEQ.fetch_add(1, Ordering::Relaxed);
+    sibling(path, ".", &format!(".{}-{seq}.tmp", std::process::id()))
+}
+
+/// 上一版内容的备份：settings.json.bak
+fn backup_path(path: &Path) -> Result<PathBuf, String> {
+    sibling(path, "", ".bak")
+}
+
+/// 改名后同步目录，保证改名本身已落盘
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => File::open(dir)?.sync_all(),
+        None => File::open(".")?.sync_all(),
+    }
+}
+
+/// 其他平台无法打开目录同步，只依赖改名
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// 写入过程中可能被中断的位置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// 临时文件已写入并同步，尚未改名
+    TempWritten,
+    /// 已保留 .bak，尚未改名
+    BackupKept,
+    /// 已改名，目录尚未同步
+    Renamed,
+}
+
+#[cfg(test)]
+thread_local! {
+    static CRASH_AT: std::cell::Cell<Option<Step>> = const { std::cell::Cell::new(None) };
+}
+
+/// 测试时在指定步骤直接返回，不做任何清理，模拟进程在此处被杀
+#[cfg(test)]
+fn crashed_at(step: Step) -> bool {
+    CRASH_AT.with(|at| at.get() == Some(step))
+}
+
+#[cfg(not(test))]
+fn crashed_at(_step: Step) -> bool {
+    false
+}
+
+fn write_tmp(tmp: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(tmp)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+fn replace(path: &Path, data: &[u8], keep_backup: bool) -> Result<(), String> {
+    let context = |e: std::io::Error| format!("写入 {}: {e}", path.display());
+    if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(context)?;
+    }
+    let tmp = tmp_path(path)?;
+    if let Err(err) = write_tmp(&tmp, data) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(context(err));
+    }
+    if crashed_at(Step::TempWritten) {
+        return Err("simulated crash".into());
+    }
+    if keep_backup {
+        backup_previous(path);
+        if crashed_at(Step::BackupKept) {
+            return Err("simulated crash".into());
+        }
+    }
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        context(e)
+    })?;
+    if crashed_at(Step::Renamed) {
+        return Err("simulated crash".into());
+    }
+    sync_dir(path).map_err(context)
+}
+
+/// 把当前内容留作 .bak；优先用硬链接，不复制数据。失败只记录日志，不影响写入
+fn backup_previous(path: &Path) {
+    let Ok(backup) = backup_path(path) else {
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+    let _ = std::fs::remove_file(&backup);
+    let result =
+        std::fs::hard_link(path, &backup).or_else(|_| std::fs::copy(path, &backup).map(|_| ()));
+    if let Err(err) = result {
+        tracing::debug!(path = %backup.display(), error = %err, "保留备份失败");
+    }
+}
+
+/// 先写同目录临时文件并同步到磁盘，再改名替换，最后同步目录；
+/// 任何一步中断，原文件都保持写入前的内容
+pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), String> {
+    replace(path, data, false)
+}
+
+/// 同 atomic_write，并把被替换的内容保留为 .bak，供 read_recovering 在主文件损坏时使用
+pub fn write_with_backup(path: &Path, data: &[u8]) -> Result<(), String> {
+    replace(path, data, true)
+}
+
+/// 同目录下的 <文件名>.bak 及 <文件名>.*.bak，按修改时间从新到旧
+fn backups(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let name = name.to_string_lossy();
+    let prefix = format!("{name}.");
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(PathBuf, SystemTime)> = read_dir
+        .flatten()
+        .filter(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name.starts_with(&prefix) && file_name.ends_with(".bak")
+        })
+        .map(|entry| {
+            let modified = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (entry.path(), modified)
+        })
+        .collect();
+    found.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    found.into_iter().map(|(path, _)| path).collect()
+}
+
+/// 依次尝试备份，返回第一个能解析的
+pub fn read_backup<T, E: Display>(path: &Path, parse: impl Fn(&[u8]) -> Result<T, E>) -> Option<T> {
+    backups(path).into_iter().find_map(|backup| {
+        let data = std::fs::read(&backup).ok()?;
+        match parse(&data) {
+            Ok(value) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    backup = %backup.display(),
+                    "主文件无法读取，已从备份恢复"
+                );
+                Some(value)
+            }
+            Err(err) => {
+                tracing::debug!(backup = %backup.display(), error = %err, "备份也无法解析");
+                None
+            }
+        }
+    })
+}
+
+/// 读取并解析文件；不存在时返回 None，无法读取或解析时改用最新的可用备份
+pub fn read_recovering<T, E: Display>(
+    path: &Path,
+    parse: impl Fn(&[u8]) -> Result<T, E>,
+) -> Option<T> {
+    let error = match std::fs::read(path) {
+        Ok(data) => match parse(&data) {
+            Ok(value) => return Some(value),
+            Err(err) => err.to_string(),
+        },
+        Err(err) if err.kind() == ErrorKind::NotFound => return None,
+        Err(err) => err.to_string(),
+    };
+    tracing::warn!(path = %path.display(), error = %error, "文件已损坏，尝试读取备份");
+    read_backup(path, parse)
+}
+
+/// 删除文件及其 .bak，不存在时忽略
+pub fn remove(path: &Path) -> std::io::Result<()> {
+    for target in std::iter::once(path.to_path_buf()).chain(backup_path(path).ok()) {
+        match std::fs::remove_file(&target) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// 在 step 处模拟崩溃执行一次写入
+    fn crash_during(step: Step, write: impl FnOnce() -> Result<(), String>) {
+        CRASH_AT.with(|at| at.set(Some(step)));
+        let result = write();
+        CRASH_AT.with(|at| at.set(None));
+        assert_eq!(result, Err("simulated crash".into()));
+    }
+
+    fn leftover_tmp(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.to_string_lossy().ends_with(".tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn crash_before_rename_keeps_old_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        atomic_write(&path, b"old").unwrap();
+        crash_during(Step::TempWritten, || atomic_write(&path, b"new"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        // 被杀时来不及删除临时文件，下次写入不受影响
+        assert_eq!(leftover_tmp(dir.path()).len(), 1);
+        atomic_write(&path, b"newer").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"newer");
+    }
+
+    #[test]
+    fn crash_after_backup_keeps_old_content_and_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.json");
+        write_with_backup(&path, b"v1").unwrap();
+        write_with_backup(&path, b"v2").unwrap();
+        crash_during(Step::BackupKept, || write_with_backup(&path, b"v3"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"v2");
+        assert_eq!(std::fs::read(backup_path(&path).unwrap()).unwrap(), b"v2");
+    }
+
+    #[test]
+    fn crash_after_rename_has_new_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.json");
+        atomic_write(&path, b"old").unwrap();
+        crash_during(Step::Renamed, || atomic_write(&path, b"new"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert!(leftover_tmp(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn first_write_crash_leaves_no_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/state.json");
+        crash_during(Step::TempWritten, || write_with_backup(&path, b"data"));
+        assert!(!path.exists());
+        assert_eq!(
+            read_recovering(&path, |d| std::str::from_utf8(d).map(str::to_owned)),
+            None
+        );
+    }
+
+    #[test]
+    fn corrupt_file_is_read_from_the_newest_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kv.json");
+        write_with_backup(&path, b"1").unwrap();
+        write_with_backup(&path, b"2").unwrap();
+        std::fs::write(&path, b"not a number").unwrap();
+        let parse = |d: &[u8]| std::str::from_utf8(d).unwrap().parse::<u32>();
+        assert_eq!(read_recovering(&path, parse), Some(1));
+        // 较新的带时间戳备份优先
+        let newer = dir.path().join("kv.json.1.bak");
+        std::fs::write(&newer, b"7").unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(&newer)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(read_recovering(&path, parse), Some(7));
+    }
+
+    #[test]
+    fn temp_files_are_unique() {
+        let path = Path::new("/data/settings.json");
+        let a = tmp_path(path).unwrap();
+        let b = tmp_path(path).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), path.parent());
+        assert!(a
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(".settings.json."));
+    }
+
+    #[test]
+    fn concurrent_writes_do_not_clobber_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Arc::new(dir.path().join("history.json"));
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for _ in 0..20 {
+                        write_with_backup(&path, &[i; 4096]).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let data = std::fs::read(&*path).unwrap();
+        assert_eq!(data.len(), 4096);
+        assert!(data.iter().all(|&b| b == data[0]));
+        assert!(leftover_tmp(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn remove_deletes_file_and_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        write_with_backup(&path, b"1").unwrap();
+        write_with_backup(&path, b"2").unwrap();
+        remove(&path).unwrap();
+        assert!(!path.exists());
+        assert!(!backup_path(&path).unwrap().exists());
+        // 不存在时忽略
+        remove(&path).unwrap();
+    }
+}
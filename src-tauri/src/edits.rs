@@ -0,0 +1,648 @@
+use image::{DynamicImage, Rgba};
+use imageproc::drawing::{draw_filled_rect_mut, draw_polygon_mut};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+use crate::capture_cache::{CaptureCache, CaptureHandle, CaptureRef};
+use crate::error::AppError;
+use crate::launch::Region;
+
+/// 每张截图最多可撤销的步数，超出时最早的一步合并到原图
+pub const MAX_EDITS: usize = 50;
+/// 每张截图缓存的中间状态数（不含原图），4K 截图每个约 33 MB
+const MAX_MATERIALIZED: usize = 3;
+/// 箭头头部的长度与张角，与前端标注一致
+const ARROW_HEAD: f32 = 15.0;
+const ARROW_ANGLE: f32 = std::f32::consts::PI / 6.0;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// 顺时针旋转 90°
+    RotateCw,
+    Rotate180,
+    /// 逆时针旋转 90°
+    RotateCcw,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ShapePoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeStyle {
+    /// #rrggbb 或 #rrggbbaa
+    pub color: String,
+    pub line_width: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeKind {
+    Rect,
+    Arrow,
+    Mosaic,
+    /// 后端没有字体，文字标注只能在前端绘制
+    Text,
+}
+
+/// 标注图形，与前端的 Annotation 相同，坐标为截图像素
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Shape {
+    #[serde(rename = "type")]
+    pub kind: ShapeKind,
+    pub points: Vec<ShapePoint>,
+    pub style: ShapeStyle,
+}
+
+/// 一步编辑，只保存参数，图片在需要时重新计算
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum EditOp {
+    Crop { region: Region },
+    Redact { regions: Vec<Region> },
+    Transform { transform: Transform },
+    Annotate { shapes: Vec<Shape> },
+}
+
+fn parse_color(color: &str) -> Result<Rgba<u8>, AppError> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    let invalid = || AppError::invalid(format!("无效的颜色: {color}"));
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, alpha]))
+}
+
+fn two_points(shape: &Shape) -> Option<(ShapePoint, ShapePoint)> {
+    match shape.points.as_slice() {
+        [a, b, ..] => Some((*a, *b)),
+        _ => None,
+    }
+}
+
+/// 两点围成的区域，限制在图片范围内
+fn bounds(a: ShapePoint, b: ShapePoint, width: u32, height: u32) -> Option<Region> {
+    let clamp = |v: f32, max: u32| v.round().clamp(0.0, max as f32) as u32;
+    let (x0, x1) = (clamp(a.x.min(b.x), width), clamp(a.x.max(b.x), width));
+    let (y0, y1) = (clamp(a.y.min(b.y), height), clamp(a.y.max(b.y), height));
+    (x1 > x0 && y1 > y0).then(|| Region {
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    })
+}
+
+fn point(x: f32, y: f32) -> Point<i32> {
+    Point::new(x.round() as i32, y.round() as i32)
+}
+
+/// 多边形首尾重合时 imageproc 会 panic，退化的图形直接跳过
+fn fill_polygon(image: &mut image::RgbaImage, points: &[Point<i32>], color: Rgba<u8>) {
+    if points.len() >= 3 && points.first() != points.last() {
+        draw_polygon_mut(image, points, color);
+    }
+}
+
+fn draw_rect(
+    image: &mut image::RgbaImage,
+    a: ShapePoint,
+    b: ShapePoint,
+    line: f32,
+    color: Rgba<u8>,
+) {
+    let (x0, x1) = (a.x.min(b.x), a.x.max(b.x));
+    let (y0, y1) = (a.y.min(b.y), a.y.max(b.y));
+    let half = line / 2.0;
+    let width = line.round().max(1.0) as u32;
+    let outer_w = (x1 - x0 + line).round().max(1.0) as u32;
+    let outer_h = (y1 - y0 + line).round().max(1.0) as u32;
+    let (left, top) = ((x0 - half).round() as i32, (y0 - half).round() as i32);
+    // 与 canvas 的 strokeRect 一样，线宽以边为中心
+    for rect in [
+        Rect::at(left, top).of_size(outer_w, width),
+        Rect::at(left, (y1 - half).round() as i32).of_size(outer_w, width),
+        Rect::at(left, top).of_size(width, outer_h),
+        Rect::at((x1 - half).round() as i32, top).of_size(width, outer_h),
+    ] {
+        draw_filled_rect_mut(image, rect, color);
+    }
+}
+
+fn draw_arrow(
+    image: &mut image::RgbaImage,
+    from: ShapePoint,
+    to: ShapePoint,
+    line: f32,
+    color: Rgba<u8>,
+) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let length = dx.hypot(dy);
+    if length < 1.0 {
+        return;
+    }
+    // 线段画成有宽度的四边形
+    let (nx, ny) = (-dy / length * line / 2.0, dx / length * line / 2.0);
+    fill_polygon(
+        image,
+        &[
+            point(from.x + nx, from.y + ny),
+            point(to.x + nx, to.y + ny),
+            point(to.x - nx, to.y - ny),
+            point(from.x - nx, from.y - ny),
+        ],
+        color,
+    );
+    let angle = dy.atan2(dx);
+    let head = |a: f32| point(to.x - ARROW_HEAD * a.cos(), to.y - ARROW_HEAD * a.sin());
+    fill_polygon(
+        image,
+        &[
+            point(to.x, to.y),
+            head(angle - ARROW_ANGLE),
+            head(angle + ARROW_ANGLE),
+        ],
+        color,
+    );
+}
+
+impl EditOp {
+    /// 提交前检查参数，避免无效的编辑进入记录
+    pub fn validate(&self) -> Result<(), AppError> {
+        match self {
+            EditOp::Crop { region } if region.width == 0 || region.height == 0 => {
+                Err(AppError::invalid("裁剪区域为空"))
+            }
+            EditOp::Redact { regions } if regions.is_empty() => {
+                Err(AppError::invalid("没有需要打码的区域"))
+            }
+            EditOp::Annotate { shapes } => {
+                if shapes.is_empty() {
+                    return Err(AppError::invalid("没有标注"));
+                }
+                for shape in shapes {
+                    if shape.kind == ShapeKind::Text {
+                        return Err(AppError::invalid("文字标注只能在前端绘制"));
+                    }
+                    if shape.points.len() < 2 {
+                        return Err(AppError::invalid("标注至少需要两个点"));
+                    }
+                    if !(shape.style.line_width > 0.0 && shape.style.line_width <= 100.0) {
+                        return Err(AppError::invalid("线宽必须在 0 到 100 之间"));
+                    }
+                    parse_color(&shape.style.color)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn apply(&self, image: &DynamicImage) -> Result<DynamicImage, AppError> {
+        let (width, height) = (image.width(), image.height());
+        match self {
+            EditOp::Crop { region } => {
+                let cropped = image.crop_imm(region.x, region.y, region.width, region.height);
+                if cropped.width() == 0 || cropped.height() == 0 {
+                    return Err(AppError::invalid("裁剪区域超出图片范围"));
+                }
+                Ok(cropped)
+            }
+            EditOp::Redact { regions } => {
+                let mut rgba = image.to_rgba8();
+                crate::redact::pixelate(&mut rgba, regions);
+                Ok(DynamicImage::ImageRgba8(rgba))
+            }
+            EditOp::Transform { transform } => Ok(match transform {
+                Transform::RotateCw => image.rotate90(),
+                Transform::Rotate180 => image.rotate180(),
+                Transform::RotateCcw => image.rotate270(),
+                Transform::FlipHorizontal => image.fliph(),
+                Transform::FlipVertical => image.flipv(),
+            }),
+            EditOp::Annotate { shapes } => {
+                let mut rgba = image.to_rgba8();
+                for shape in shapes {
+                    let Some((a, b)) = two_points(shape) else {
+                        continue;
+                    };
+                    let color = parse_color(&shape.style.color)?;
+                    let line = shape.style.line_width;
+                    match shape.kind {
+                        ShapeKind::Rect => draw_rect(&mut rgba, a, b, line, color),
+                        ShapeKind::Arrow => draw_arrow(&mut rgba, a, b, line, color),
+                        ShapeKind::Mosaic => {
+                            if let Some(region) = bounds(a, b, width, height) {
+                                crate::redact::pixelate(&mut rgba, &[region]);
+                            }
+                        }
+                        ShapeKind::Text => {}
+                    }
+                }
+                Ok(DynamicImage::ImageRgba8(rgba))
+            }
+        }
+    }
+}
+
+/// 一张截图的编辑记录：只保存每一步的参数，图片从原图或缓存的中间状态重新计算。
+///
+/// 每个状态有不变的编号（原图为 0），预览地址按编号区分，撤销、重做后 WebView 的缓存仍然有效
+#[derive(Default)]
+pub struct EditStack {
+    /// （编号，操作），编号从 1 开始递增
+    ops: Vec<(u64, EditOp)>,
+    /// 已应用的步数，撤销后小于 ops.len()
+    position: usize,
+    next_serial: u64,
+    /// 原图的编号，最早的一步合并到原图后变为该步的编号
+    base_serial: u64,
+    /// 最近用到的中间状态，最近的在后
+    materialized: Vec<(u64, Arc<DynamicImage>)>,
+    /// 超出上限后合并到原图、无法再撤销的步数
+    folded: usize,
+}
+
+impl EditStack {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// 第 index 步之后的状态编号，0 为原图
+    pub fn serial_at(&self, index: usize) -> u64 {
+        match index {
+            0 => self.base_serial,
+            i => self.ops[i - 1].0,
+        }
+    }
+
+    pub fn current_serial(&self) -> u64 {
+        self.serial_at(self.position)
+    }
+
+    /// 编号对应的步数；已被新的编辑覆盖或已合并的状态返回 None
+    pub fn index_of(&self, serial: u64) -> Option<usize> {
+        if serial == self.base_serial {
+            return Some(0);
+        }
+        self.ops
+            .iter()
+            .position(|(s, _)| *s == serial)
+            .map(|i| i + 1)
+    }
+
+    /// 计算第 target 步的状态需要的起点与操作：从不超过 target 的最近缓存开始，没有时从原图开始（None）
+    pub fn plan(&self, target: usize) -> (Option<Arc<DynamicImage>>, Vec<EditOp>) {
+        let start = self
+            .materialized
+            .iter()
+            .filter_map(|(serial, image)| Some((self.index_of(*serial)?, image)))
+            .filter(|(index, _)| *index <= target && *index > 0)
+            .max_by_key(|(index, _)| *index);
+        let (from, image) = match start {
+            Some((index, image)) => (index, Some(image.clone())),
+            None => (0, None),
+        };
+        let ops = self.ops[from..target]
+            .iter()
+            .map(|(_, op)| op.clone())
+            .collect();
+        (image, ops)
+    }
+
+    /// 记下计算出的状态，超出数量时丢弃最久未用的
+    pub fn remember(&mut self, serial: u64, image: Arc<DynamicImage>) {
+        if serial == self.base_serial || self.index_of(serial).is_none() {
+            return;
+        }
+        self.materialized.retain(|(s, _)| *s != serial);
+        self.materialized.push((serial, image));
+        if self.materialized.len() > MAX_MATERIALIZED {
+            self.materialized.remove(0);
+        }
+    }
+
+    /// 在当前位置之后追加一步，已撤销的步骤被丢弃；返回新状态的编号
+    pub fn push(&mut self, op: EditOp) -> u64 {
+        for (serial, _) in self.ops.drain(self.position..) {
+            self.materialized.retain(|(s, _)| *s != serial);
+        }
+        self.next_serial = self.next_serial.max(self.base_serial) + 1;
+        self.ops.push((self.next_serial, op));
+        self.position = self.ops.len();
+        self.next_serial
+    }
+
+    /// 最早的一步已合并到原图，原图改为该步之后的状态
+    pub fn fold_oldest(&mut self) {
+        if self.ops.is_empty() {
+            return;
+        }
+        let (serial, _) = self.ops.remove(0);
+        self.base_serial = serial;
+        self.position = self.position.saturating_sub(1);
+        self.materialized.retain(|(s, _)| *s != serial);
+        self.folded += 1;
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.ops.len()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if !self.can_redo() {
+            return false;
+        }
+        self.position += 1;
+        true
+    }
+
+    pub fn history(&self) -> EditHistory {
+        EditHistory {
+            position: self.position,
+            edits: self.ops.iter().map(|(_, op)| op.clone()).collect(),
+            folded: self.folded,
+        }
+    }
+}
+
+/// 编辑、撤销、重做后的状态，前端把 capture.preview_url 作为新的图片地址
+#[derive(Debug, Serialize)]
+pub struct EditState {
+    pub capture: CaptureHandle,
+    /// 已应用的步数
+    pub position: usize,
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EditHistory {
+    /// 已应用的步数，edits 中此后的为已撤销、可重做的步骤
+    pub position: usize,
+    pub edits: Vec<EditOp>,
+    /// 超出上限后合并到原图、无法再撤销的步数
+    pub folded: usize,
+}
+
+/// 在缓存中的截图上追加一步编辑；capture_id 为缓存编号或截图操作的编号
+pub async fn edit(
+    app: AppHandle,
+    capture_id: CaptureRef,
+    op: EditOp,
+) -> Result<EditState, AppError> {
+    crate::run_blocking(move || {
+        let state = edit_cached(&app.state::<CaptureCache>(), &capture_id, op)?;
+        crate::pre_encode::schedule(&app, state.capture.id);
+        Ok(state)
+    })
+    .await
+}
+
+/// 按缓存编号或截图编号找到截图并追加一步编辑，不做预编码
+pub fn edit_cached(
+    cache: &CaptureCache,
+    capture_id: &CaptureRef,
+    op: EditOp,
+) -> Result<EditState, AppError> {
+    cache.edit(cache.resolve(capture_id)?, op)
+}
+
+/// 裁剪缓存中的截图（截图像素坐标），作为一步编辑记录，可撤销
+#[tauri::command]
+pub async fn crop_image(
+    app: AppHandle,
+    capture_id: CaptureRef,
+    region: Region,
+) -> Result<EditState, AppError> {
+    edit(app, capture_id, EditOp::Crop { region }).await
+}
+
+/// 在缓存中的截图上绘制矩形、箭头和马赛克标注，文字标注不支持
+#[tauri::command]
+pub async fn apply_annotations(
+    app: AppHandle,
+    capture_id: CaptureRef,
+    shapes: Vec<Shape>,
+) -> Result<EditState, AppError> {
+    edit(app, capture_id, EditOp::Annotate { shapes }).await
+}
+
+/// 旋转或翻转缓存中的截图
+#[tauri::command]
+pub async fn transform_image(
+    app: AppHandle,
+    capture_id: CaptureRef,
+    transform: Transform,
+) -> Result<EditState, AppError> {
+    edit(app, capture_id, EditOp::Transform { transform }).await
+}
+
+#[tauri::command]
+pub async fn undo_edit(app: AppHandle, id: CaptureRef) -> Result<EditState, AppError> {
+    crate::run_blocking(move || {
+        let cache = app.state::<CaptureCache>();
+        let id = cache.resolve(&id)?;
+        let state = cache.undo_edit(id)?;
+        crate::pre_encode::schedule(&app, id);
+        Ok(state)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn redo_edit(app: AppHandle, id: CaptureRef) -> Result<EditState, AppError> {
+    crate::run_blocking(move || {
+        let cache = app.state::<CaptureCache>();
+        let id = cache.resolve(&id)?;
+        let state = cache.redo_edit(id)?;
+        crate::pre_encode::schedule(&app, id);
+        Ok(state)
+    })
+    .await
+}
+
+/// 截图的编辑记录，包括已撤销、可重做的步骤
+#[tauri::command]
+pub fn get_edit_history(
+    cache: State<'_, CaptureCache>,
+    id: CaptureRef,
+) -> Result<EditHistory, AppError> {
+    cache.edit_history(cache.resolve(&id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+    fn shape(kind: ShapeKind, points: &[(f32, f32)], color: &str, line_width: f32) -> Shape {
+        Shape {
+            kind,
+            points: points.iter().map(|&(x, y)| ShapePoint { x, y }).collect(),
+            style: ShapeStyle {
+                color: color.into(),
+                line_width,
+            },
+        }
+    }
+
+    fn crop(x: u32, width: u32) -> EditOp {
+        EditOp::Crop {
+            region: Region {
+                x,
+                y: 0,
+                width,
+                height: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn colors_accept_rgb_and_rgba_hex() {
+        assert_eq!(parse_color("#ff0000").unwrap(), RED);
+        assert_eq!(parse_color("00ff0080").unwrap(), Rgba([0, 255, 0, 128]));
+        for bad in ["#fff", "#gg0000", "#ff00001", "#ff00é"] {
+            assert!(parse_color(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected_before_recording() {
+        assert!(crop(0, 0).validate().is_err());
+        assert!(EditOp::Redact { regions: vec![] }.validate().is_err());
+        let annotate = |shape| EditOp::Annotate {
+            shapes: vec![shape],
+        };
+        let rect = [(1.0, 1.0), (5.0, 5.0)];
+        assert!(annotate(shape(ShapeKind::Rect, &rect, "#ff0000", 2.0))
+            .validate()
+            .is_ok());
+        assert!(annotate(shape(ShapeKind::Text, &rect, "#ff0000", 2.0))
+            .validate()
+            .is_err());
+        assert!(
+            annotate(shape(ShapeKind::Arrow, &rect[..1], "#ff0000", 2.0))
+                .validate()
+                .is_err()
+        );
+        assert!(annotate(shape(ShapeKind::Rect, &rect, "#ff0000", 0.0))
+            .validate()
+            .is_err());
+        assert!(annotate(shape(ShapeKind::Rect, &rect, "red", 2.0))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn crops_and_transforms_change_the_size() {
+        let image = DynamicImage::new_rgba8(20, 10);
+        assert_eq!(crop(5, 100).apply(&image).unwrap().width(), 15);
+        assert!(crop(20, 5).apply(&image).is_err());
+        let rotated = EditOp::Transform {
+            transform: Transform::RotateCw,
+        }
+        .apply(&image)
+        .unwrap();
+        assert_eq!((rotated.width(), rotated.height()), (10, 20));
+    }
+
+    #[test]
+    fn rectangles_are_stroked_around_the_edges() {
+        let image = DynamicImage::new_rgba8(20, 20);
+        let op = EditOp::Annotate {
+            shapes: vec![
+                shape(ShapeKind::Rect, &[(15.0, 15.0), (5.0, 5.0)], "#ff0000", 2.0),
+                // 退化的箭头直接跳过
+                shape(ShapeKind::Arrow, &[(3.0, 3.0), (3.2, 3.0)], "#ff0000", 2.0),
+            ],
+        };
+        let drawn = op.apply(&image).unwrap().to_rgba8();
+        for (x, y) in [(5, 5), (15, 10), (10, 15), (4, 4)] {
+            assert_eq!(*drawn.get_pixel(x, y), RED, "({x}, {y})");
+        }
+        assert_eq!(drawn.get_pixel(10, 10)[3], 0);
+        assert_eq!(drawn.get_pixel(3, 3)[3], 0);
+    }
+
+    #[test]
+    fn shape_bounds_are_clamped_to_the_image() {
+        let p = |x, y| ShapePoint { x, y };
+        let region = bounds(p(30.0, -5.0), p(8.4, 12.6), 20, 10).unwrap();
+        assert_eq!(
+            region,
+            Region {
+                x: 8,
+                y: 0,
+                width: 12,
+                height: 10
+            }
+        );
+        assert!(bounds(p(25.0, 0.0), p(30.0, 5.0), 20, 10).is_none());
+    }
+
+    #[test]
+    fn undo_redo_and_new_edits_keep_serials_stable() {
+        let mut stack = EditStack::default();
+        assert_eq!(stack.push(crop(0, 1)), 1);
+        assert_eq!(stack.push(crop(1, 1)), 2);
+        assert!(stack.undo());
+        assert_eq!(stack.current_serial(), 1);
+        assert!(stack.can_redo());
+        // 撤销后的新编辑丢弃可重做的步骤，编号不复用
+        assert_eq!(stack.push(crop(2, 1)), 3);
+        assert!(!stack.can_redo());
+        assert_eq!(stack.index_of(2), None);
+        assert!(stack.undo() && stack.undo() && !stack.undo());
+        assert_eq!(stack.current_serial(), 0);
+        assert!(stack.redo() && stack.redo() && !stack.redo());
+
+        stack.fold_oldest();
+        assert_eq!(stack.history().folded, 1);
+        assert_eq!((stack.position(), stack.serial_at(0)), (1, 1));
+        assert_eq!(stack.index_of(3), Some(1));
+    }
+
+    #[test]
+    fn plan_starts_from_the_nearest_remembered_state() {
+        let mut stack = EditStack::default();
+        for x in 0..4 {
+            stack.push(crop(x, 1));
+        }
+        let image = Arc::new(DynamicImage::new_rgba8(1, 1));
+        stack.remember(2, image.clone());
+        let (start, ops) = stack.plan(4);
+        assert!(start.is_some());
+        assert_eq!(ops.len(), 2);
+        let (start, ops) = stack.plan(1);
+        assert!(start.is_none());
+        assert_eq!(ops.len(), 1);
+
+        // 超出数量时丢弃最久未用的
+        for serial in [1, 3, 4] {
+            stack.remember(serial, image.clone());
+        }
+        let (_, ops) = stack.plan(2);
+        assert_eq!(ops.len(), 1);
+    }
+}
@@ -0,0 +1,132 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::i18n;
+
+/// 截图、识别、剪贴板与窗口命令的错误。
+///
+/// 序列化为 { code, message, detail }：前端按 code 区分错误类型，message 是当前语言的提示
+/// （文案键为 error.<code>），detail 为原始错误信息，只用于排查
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("no display available")]
+    NoDisplay,
+    #[error("{backend} failed: {detail}")]
+    BackendFailed { backend: String, detail: String },
+    #[error("tesseract not installed")]
+    TesseractMissing,
+    #[error("tesseract too old: {version}")]
+    TesseractTooOld { version: String },
+    #[error("tesseract language data missing: {lang}")]
+    LanguageMissing { lang: String },
+    #[error("ocr failed: {0}")]
+    OcrFailed(String),
+    #[error("clipboard unavailable: {0}")]
+    ClipboardUnavailable(String),
+    #[error("{what} timed out after {ms} ms")]
+    Timeout { what: String, ms: u64 },
+    #[error("capture cancelled")]
+    Cancelled,
+    #[error("window operation failed: {0}")]
+    Window(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+    #[error("unsupported image format: {format}")]
+    UnsupportedFormat { format: String, hint: String },
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NoDisplay => "no_display",
+            AppError::BackendFailed { .. } => "backend_failed",
+            AppError::TesseractMissing => "tesseract_missing",
+            AppError::TesseractTooOld { .. } => "tesseract_too_old",
+            AppError::LanguageMissing { .. } => "language_missing",
+            AppError::OcrFailed(_) => "ocr_failed",
+            AppError::ClipboardUnavailable(_) => "clipboard_unavailable",
+            AppError::Timeout { .. } => "timeout",
+            AppError::Cancelled => "cancelled",
+            AppError::Window(_) => "window",
+            AppError::Io(_) => "io",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::UnsupportedFormat { .. } => "unsupported_format",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    /// 当前语言的提示文字
+    pub fn message(&self) -> String {
+        let key = format!("error.{}", self.code());
+        match self {
+            AppError::BackendFailed { backend, .. } => i18n::tf(&key, &[("backend", backend)]),
+            AppError::TesseractTooOld { version } => i18n::tf(&key, &[("version", version)]),
+            AppError::LanguageMissing { lang } => i18n::tf(&key, &[("lang", lang)]),
+            AppError::UnsupportedFormat { format, .. } => i18n::tf(&key, &[("format", format)]),
+            AppError::Timeout { what, ms } => {
+                i18n::tf(&key, &[("what", what), ("ms", &ms.to_string())])
+            }
+            _ => i18n::t(&key),
+        }
+    }
+
+    /// 原始错误信息，没有更多内容时为空
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            AppError::BackendFailed { detail, .. } => Some(detail.clone()),
+            AppError::UnsupportedFormat { hint, .. } => Some(hint.clone()),
+            AppError::OcrFailed(detail)
+            | AppError::ClipboardUnavailable(detail)
+            | AppError::Window(detail)
+            | AppError::InvalidInput(detail)
+            | AppError::Internal(detail) => Some(detail.clone()),
+            AppError::Io(err) => Some(err.to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn window(err: impl ToString) -> Self {
+        AppError::Window(err.to_string())
+    }
+
+    pub fn invalid(err: impl ToString) -> Self {
+        AppError::InvalidInput(err.to_string())
+    }
+}
+
+#[cfg(feature = "tauri")]
+impl From<tauri::Error> for AppError {
+    fn from(err: tauri::Error) -> Self {
+        AppError::window(err)
+    }
+}
+
+impl From<arboard::Error> for AppError {
+    fn from(err: arboard::Error) -> Self {
+        AppError::ClipboardUnavailable(err.to_string())
+    }
+}
+
+/// 仍返回 String 的调用方（命令行模式、流水线等）使用本地化的提示
+impl From<AppError> for String {
+    fn from(err: AppError) -> Self {
+        match err.detail() {
+            Some(detail) => format!("{}: {detail}", err.message()),
+            None => err.message(),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.message())?;
+        state.serialize_field("detail", &self.detail())?;
+        state.end()
+    }
+}
@@ -0,0 +1,354 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::i18n::tf;
+use crate::notify::{self, Notice, NotifyEvent};
+
+/// 同一错误在该时间内只通知一次，其余合并到窗口结束时的汇总通知
+const WINDOW: Duration = Duration::from_secs(60);
+/// get_recent_errors 保留的条数
+const MAX_RECENT: usize = 100;
+
+static FUNNEL: Mutex<Funnel> = Mutex::new(Funnel::new());
+
+/// 出错的功能，与 code 一起作为合并的依据
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorSource {
+    Capture,
+    Ocr,
+    Clipboard,
+    Upload,
+}
+
+impl ErrorSource {
+    /// 失败时更新的托盘提示
+    fn tray_action(self) -> Option<&'static str> {
+        match self {
+            ErrorSource::Capture => Some("tray.last_capture"),
+            ErrorSource::Ocr => Some("tray.last_ocr"),
+            ErrorSource::Upload => Some("tray.last_upload"),
+            ErrorSource::Clipboard => None,
+        }
+    }
+}
+
+/// 一次失败
+#[derive(Clone, Debug)]
+pub struct Failure {
+    pub source: ErrorSource,
+    /// 错误码，如 AppError::code；相同 source 与 code 的失败会合并
+    pub code: String,
+    /// 通知标题
+    pub summary: String,
+    pub detail: String,
+    /// 弹出桌面通知；调用方界面已显示错误时关闭
+    pub notify: bool,
+    /// 更新托盘图标与提示；调用方已自行更新时关闭
+    pub tray: bool,
+}
+
+impl Failure {
+    pub fn new(
+        source: ErrorSource,
+        code: impl Into<String>,
+        summary: impl Into<String>,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            source,
+            code: code.into(),
+            summary: summary.into(),
+            detail: detail.into(),
+            notify: true,
+            tray: true,
+        }
+    }
+}
+
+/// get_recent_errors 返回的一条记录
+#[derive(Clone, Debug, Serialize)]
+pub struct ReportedError {
+    pub source: ErrorSource,
+    pub code: String,
+    pub summary: String,
+    pub detail: String,
+    /// Unix 时间（秒）
+    pub at: i64,
+    /// 与之前的相同错误合并，没有单独通知
+    pub aggregated: bool,
+}
+
+/// 窗口内的一组相同错误
+struct Group {
+    failure: Failure,
+    started: Instant,
+    /// 窗口内被合并的次数
+    suppressed: u32,
+    flush_scheduled: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    /// 窗口内第一次出现，立即通知
+    Deliver,
+    /// 已合并；wait 为 Some 时需在该时长后调用 flush 发送汇总
+    Suppress { wait: Option<Duration> },
+}
+
+/// 按滑动窗口合并相同错误，只做记账，通知由调用方发送
+struct Funnel {
+    groups: Vec<Group>,
+    recent: VecDeque<ReportedError>,
+}
+
+impl Funnel {
+    const fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    fn submit(&mut self, failure: &Failure, now: Instant, at: i64) -> Decision {
+        self.groups
+            .retain(|g| g.flush_scheduled || now.duration_since(g.started) < WINDOW);
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|g| g.failure.source == failure.source && g.failure.code == failure.code);
+        let decision = match group {
+            Some(group) => {
+                group.suppressed += 1;
+                group.failure.detail = failure.detail.clone();
+                let wait = (!group.flush_scheduled)
+                    .then(|| WINDOW.saturating_sub(now.duration_since(group.started)));
+                group.flush_scheduled = true;
+                Decision::Suppress { wait }
+            }
+            None => {
+                self.groups.push(Group {
+                    failure: failure.clone(),
+                    started: now,
+                    suppressed: 0,
+                    flush_scheduled: false,
+                });
+                Decision::Deliver
+            }
+        };
+        self.recent.push_back(ReportedError {
+            source: failure.source,
+            code: failure.code.clone(),
+            summary: failure.summary.clone(),
+            detail: failure.detail.clone(),
+            at,
+            aggregated: decision != Decision::Deliver,
+        });
+        while self.recent.len() > MAX_RECENT {
+            self.recent.pop_front();
+        }
+        decision
+    }
+
+    /// 窗口结束时取出被合并的次数；汇总通知作为新窗口的第一次通知，持续出错时每个窗口只通知一次
+    fn flush(&mut self, source: ErrorSource, code: &str, now: Instant) -> Option<(Failure, u32)> {
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|g| g.failure.source == source && g.failure.code == code)?;
+        group.flush_scheduled = false;
+        if group.suppressed == 0 {
+            return None;
+        }
+        let count = group.suppressed;
+        group.suppressed = 0;
+        group.started = now;
+        Some((group.failure.clone(), count))
+    }
+
+    fn recent(&self) -> Vec<ReportedError> {
+        self.recent.iter().rev().cloned().collect()
+    }
+}
+
+fn now_secs() -> i64 {
+    chrono::Local::now().timestamp()
+}
+
+fn submit(failure: &Failure) -> Option<Decision> {
+    let mut funnel = FUNNEL.lock().ok()?;
+    Some(funnel.submit(failure, Instant::now(), now_secs()))
+}
+
+fn deliver(app: &AppHandle, failure: &Failure, summary: String) {
+    crate::sound::play_feedback(app, crate::sound::FeedbackKind::Error);
+    if failure.tray {
+        if let Some(action) = failure.source.tray_action() {
+            crate::tray::record(app, action, "result.failed", false);
+        }
+    }
+    if failure.notify {
+        notify::notify(
+            app,
+            Notice::new(NotifyEvent::Error, summary, failure.detail.clone()),
+        );
+    }
+}
+
+/// 只记录，不通知；用于调用方会把错误返回给界面的情况
+pub fn record(failure: Failure) {
+    submit(&failure);
+}
+
+/// 记录失败并通知；窗口内重复的相同错误合并为一条“×N”的汇总通知
+pub fn report(app: &AppHandle, failure: Failure) {
+    match submit(&failure) {
+        Some(Decision::Deliver) | None => deliver(app, &failure, failure.summary.clone()),
+        Some(Decision::Suppress { wait: Some(wait) }) => {
+            let app = app.clone();
+            thread::spawn(move || {
+                thread::sleep(wait);
+                let flushed = FUNNEL.lock().ok().and_then(|mut funnel| {
+                    funnel.flush(failure.source, &failure.code, Instant::now())
+                });
+                if let Some((failure, count)) = flushed {
+                    let summary = tf(
+                        "error.repeated",
+                        &[
+                            ("summary", &failure.summary),
+                            ("count", &count.to_string()),
+                            ("minutes", &(WINDOW.as_secs() / 60).to_string()),
+                        ],
+                    );
+                    deliver(&app, &failure, summary);
+                }
+            });
+        }
+        Some(Decision::Suppress { wait: None }) => {}
+    }
+}
+
+/// 最近的错误，新的在前，供诊断面板显示
+#[tauri::command]
+pub fn get_recent_errors() -> Vec<ReportedError> {
+    FUNNEL.lock().map(|f| f.recent()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(source: ErrorSource, code: &str) -> Failure {
+        Failure::new(source, code, "截图失败", format!("{code} 详情"))
+    }
+
+    fn grim() -> Failure {
+        failure(ErrorSource::Capture, "timeout:grim")
+    }
+
+    #[test]
+    fn repeats_within_the_window_are_aggregated() {
+        let mut funnel = Funnel::new();
+        let start = Instant::now();
+        assert_eq!(funnel.submit(&grim(), start, 0), Decision::Deliver);
+
+        // 第一次重复时安排在窗口结束时汇总，之后的重复只计数
+        let later = start + Duration::from_secs(10);
+        assert_eq!(
+            funnel.submit(&grim(), later, 10),
+            Decision::Suppress {
+                wait: Some(WINDOW - Duration::from_secs(10))
+            }
+        );
+        for i in 0..3 {
+            let at = later + Duration::from_secs(i);
+            assert_eq!(
+                funnel.submit(&grim(), at, 10),
+                Decision::Suppress { wait: None }
+            );
+        }
+
+        let (flushed, count) = funnel
+            .flush(ErrorSource::Capture, "timeout:grim", start + WINDOW)
+            .unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(flushed.code, "timeout:grim");
+    }
+
+    #[test]
+    fn different_errors_are_not_merged() {
+        let mut funnel = Funnel::new();
+        let now = Instant::now();
+        assert_eq!(funnel.submit(&grim(), now, 0), Decision::Deliver);
+        let xcap = failure(ErrorSource::Capture, "timeout:xcap");
+        assert_eq!(funnel.submit(&xcap, now, 0), Decision::Deliver);
+        let ocr = failure(ErrorSource::Ocr, "timeout:grim");
+        assert_eq!(funnel.submit(&ocr, now, 0), Decision::Deliver);
+    }
+
+    #[test]
+    fn errors_after_a_quiet_window_notify_again() {
+        let mut funnel = Funnel::new();
+        let start = Instant::now();
+        funnel.submit(&grim(), start, 0);
+        let after = start + WINDOW + Duration::from_secs(1);
+        assert_eq!(funnel.submit(&grim(), after, 61), Decision::Deliver);
+    }
+
+    #[test]
+    fn flush_starts_a_new_window() {
+        let mut funnel = Funnel::new();
+        let start = Instant::now();
+        funnel.submit(&grim(), start, 0);
+        funnel.submit(&grim(), start, 0);
+        let flushed_at = start + WINDOW;
+        assert!(funnel
+            .flush(ErrorSource::Capture, "timeout:grim", flushed_at)
+            .is_some());
+
+        // 汇总后仍在出错：汇总通知算作新窗口的第一次通知
+        let again = flushed_at + Duration::from_secs(5);
+        assert_eq!(
+            funnel.submit(&grim(), again, 65),
+            Decision::Suppress {
+                wait: Some(WINDOW - Duration::from_secs(5))
+            }
+        );
+        // 之后不再出错时，窗口结束不发送汇总
+        assert!(funnel
+            .flush(ErrorSource::Capture, "timeout:grim", flushed_at + WINDOW)
+            .is_some());
+        assert!(funnel
+            .flush(
+                ErrorSource::Capture,
+                "timeout:grim",
+                flushed_at + WINDOW * 2
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn recent_errors_are_listed_newest_first_and_capped() {
+        let mut funnel = Funnel::new();
+        let now = Instant::now();
+        funnel.submit(&grim(), now, 1);
+        funnel.submit(&grim(), now, 2);
+        let recent = funnel.recent();
+        assert_eq!(recent.iter().map(|e| e.at).collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(
+            recent.iter().map(|e| e.aggregated).collect::<Vec<_>>(),
+            [true, false]
+        );
+
+        for i in 0..MAX_RECENT as i64 * 2 {
+            funnel.submit(&grim(), now, i);
+        }
+        let recent = funnel.recent();
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert_eq!(recent[0].at, MAX_RECENT as i64 * 2 - 1);
+    }
+}
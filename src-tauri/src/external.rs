@@ -0,0 +1,526 @@
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 查询版本、读取设置等很快就该结束的调用的超时
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// 等待退出时检查超时和取消的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// 进程退出后等待输出读完的时间；它启动的后台进程可能一直占着管道
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+/// 标准输出的上限，超出时结束进程
+const MAX_STDOUT: usize = 16 * 1024 * 1024;
+/// grim 把整幅截图写到标准输出，多个 4K 显示器也在此之内
+const MAX_IMAGE_STDOUT: usize = 1024 * 1024 * 1024;
+/// 错误输出只保留开头这么多字节
+const MAX_STDERR: usize = 64 * 1024;
+/// 日志中参数的长度上限
+const MAX_LOGGED_ARGS: usize = 200;
+
+/// 允许调用的外部程序。除 User 外只按固定的程序名在 PATH 中查找，
+/// 前端或导入的设置都无法让后端执行其他程序
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExternalTool {
+    Grim,
+    GnomeScreenshot,
+    Tesseract,
+    Gsettings,
+    Hyprctl,
+    Swaymsg,
+    WfRecorder,
+    Ffmpeg,
+    Kill,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Lp,
+    #[cfg(target_os = "macos")]
+    Lpr,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    XdgOpen,
+    #[cfg(target_os = "linux")]
+    XdgEmail,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    DbusSend,
+    SpdSay,
+    EspeakNg,
+    WlCopy,
+    Xclip,
+    #[cfg(target_os = "macos")]
+    Say,
+    #[cfg(target_os = "macos")]
+    Osascript,
+    #[cfg(target_os = "macos")]
+    Open,
+    #[cfg(windows)]
+    Explorer,
+    #[cfg(windows)]
+    PowerShell,
+    #[cfg(windows)]
+    Reg,
+    /// 用户在设置文件中配置的程序（截图脚本），只接受绝对路径
+    User(PathBuf),
+}
+
+impl ExternalTool {
+    /// 在 PATH 中查找的程序名
+    pub fn program(&self) -> &str {
+        match self {
+            ExternalTool::Grim => "grim",
+            ExternalTool::GnomeScreenshot => "gnome-screenshot",
+            ExternalTool::Tesseract => "tesseract",
+            ExternalTool::Gsettings => "gsettings",
+            ExternalTool::Hyprctl => "hyprctl",
+            ExternalTool::Swaymsg => "swaymsg",
+            ExternalTool::WfRecorder => "wf-recorder",
+            ExternalTool::Ffmpeg => "ffmpeg",
+            ExternalTool::Kill => "kill",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            ExternalTool::Lp => "lp",
+            #[cfg(target_os = "macos")]
+            ExternalTool::Lpr => "lpr",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            ExternalTool::XdgOpen => "xdg-open",
+            #[cfg(target_os = "linux")]
+            ExternalTool::XdgEmail => "xdg-email",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            ExternalTool::DbusSend => "dbus-send",
+            ExternalTool::SpdSay => "spd-say",
+            ExternalTool::EspeakNg => "espeak-ng",
+            ExternalTool::WlCopy => "wl-copy",
+            ExternalTool::Xclip => "xclip",
+            #[cfg(target_os = "macos")]
+            ExternalTool::Say => "say",
+            #[cfg(target_os = "macos")]
+            ExternalTool::Osascript => "osascript",
+            #[cfg(target_os = "macos")]
+            ExternalTool::Open => "open",
+            #[cfg(windows)]
+            ExternalTool::Explorer => "explorer.exe",
+            #[cfg(windows)]
+            ExternalTool::PowerShell => "powershell.exe",
+            #[cfg(windows)]
+            ExternalTool::Reg => "reg.exe",
+            ExternalTool::User(path) => path.to_str().unwrap_or("script"),
+        }
+    }
+
+    /// 可执行文件的完整路径
+    pub fn resolve(&self) -> Result<PathBuf, ExternalError> {
+        let found = match self {
+            ExternalTool::User(path) if !path.is_absolute() => {
+                return Err(ExternalError::NotAllowed {
+                    program: self.program().to_string(),
+                })
+            }
+            ExternalTool::User(path) => Some(path.clone()).filter(|p| is_executable(p)),
+            _ => find_in_path(self.program()),
+        };
+        found.ok_or_else(|| ExternalError::NotFound {
+            program: self.program().to_string(),
+        })
+    }
+
+    pub fn available(&self) -> bool {
+        self.resolve().is_ok()
+    }
+
+    fn stdout_limit(&self) -> usize {
+        match self {
+            ExternalTool::Grim => MAX_IMAGE_STDOUT,
+            _ => MAX_STDOUT,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExternalError {
+    #[error("未找到 {program}")]
+    NotFound { program: String },
+    #[error("{program} 不是绝对路径，不允许执行")]
+    NotAllowed { program: String },
+    #[error("无法启动 {program}: {detail}")]
+    Spawn { program: String, detail: String },
+    #[error("{program} 退出状态 {status}: {stderr}")]
+    Exit {
+        program: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+    #[error("{program} 超时（超过 {} ms），已结束", timeout.as_millis())]
+    Timeout { program: String, timeout: Duration },
+    #[error("{program} 的输出超过 {limit} 字节，已结束")]
+    OutputTooLarge { program: String, limit: usize },
+    #[error("{program} 已取消")]
+    Cancelled { program: String },
+}
+
+/// 正常退出的程序的输出
+pub struct ExternalOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl ExternalOutput {
+    /// 部分工具（老版本 tesseract、xclip）把版本等信息写到 stderr
+    pub fn text(&self) -> String {
+        let text = if self.stdout.is_empty() {
+            &self.stderr
+        } else {
+            &self.stdout
+        };
+        String::from_utf8_lossy(text).into_owned()
+    }
+}
+
+/// 在 PATH 中查找可执行文件，不经过 shell
+fn find_in_path(cmd: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(cmd))
+        .find(|path| is_executable(path))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+fn describe_args(command: &Command) -> String {
+    let args: Vec<_> = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy())
+        .collect();
+    let args = args.join(" ");
+    match args.char_indices().nth(MAX_LOGGED_ARGS) {
+        Some((end, _)) => format!("{}…", &args[..end]),
+        None => args,
+    }
+}
+
+/// 长时间运行或不等待退出的程序（录制、朗读、打开文件）：只解析路径，由调用方管理进程
+pub fn command(tool: &ExternalTool) -> Result<Command, ExternalError> {
+    let path = tool.resolve()?;
+    tracing::debug!(program = tool.program(), "启动外部程序");
+    Ok(Command::new(path))
+}
+
+/// 在后台线程读取的管道
+struct Capture {
+    buf: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Receiver<()>,
+}
+
+impl Capture {
+    /// 最多保留 limit 字节；之后的内容读出丢弃，overflow 置位
+    fn start(
+        pipe: Option<impl Read + Send + 'static>,
+        limit: usize,
+        overflow: Option<Arc<AtomicBool>>,
+    ) -> Self {
+        let buf = Arc::new(Mutex::new(Vec::new()));
+        let (tx, done) = mpsc::channel();
+        let shared = buf.clone();
+        thread::spawn(move || {
+            if let Some(mut pipe) = pipe {
+                let mut chunk = [0u8; 64 * 1024];
+                let mut len = 0;
+                loop {
+                    let n = match pipe.read(&mut chunk) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => n,
+                    };
+                    let keep = n.min(limit - len);
+                    if keep > 0 {
+                        if let Ok(mut buf) = shared.lock() {
+                            buf.extend_from_slice(&chunk[..keep]);
+                        }
+                        len += keep;
+                    }
+                    if keep < n {
+                        if let Some(flag) = &overflow {
+                            flag.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                    }
+                }
+            }
+            let _ = tx.send(());
+        });
+        Self { buf, done }
+    }
+
+    /// 等待读完，最迟到 deadline；程序启动的后台进程占着管道时只取已读到的部分
+    fn finish(self, deadline: Instant) -> Vec<u8> {
+        let _ = self
+            .done
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        self.buf
+            .lock()
+            .map(|mut buf| std::mem::take(&mut *buf))
+            .unwrap_or_default()
+    }
+}
+
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// 运行外部程序直到退出，超时则结束进程。stdin 为空时不连接标准输入
+pub fn run_external<I, S>(
+    tool: ExternalTool,
+    args: I,
+    stdin: Option<Vec<u8>>,
+    timeout: Duration,
+) -> Result<ExternalOutput, ExternalError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    run_external_cancellable(tool, args, stdin, timeout, &|| false)
+}
+
+/// 同 run_external，期间 cancelled 返回 true 时结束进程并返回 Cancelled
+pub fn run_external_cancellable<I, S>(
+    tool: ExternalTool,
+    args: I,
+    stdin: Option<Vec<u8>>,
+    timeout: Duration,
+    cancelled: &dyn Fn() -> bool,
+) -> Result<ExternalOutput, ExternalError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let program = tool.program().to_string();
+    let mut command = Command::new(tool.resolve()?);
+    command
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let started = Instant::now();
+    let result = wait_output(&tool, &mut command, stdin, timeout, cancelled);
+    tracing::debug!(
+        program = %program,
+        args = %describe_args(&command),
+        duration_ms = started.elapsed().as_millis() as u64,
+        ok = result.is_ok(),
+        "外部程序已结束"
+    );
+    result
+}
+
+fn wait_output(
+    tool: &ExternalTool,
+    command: &mut Command,
+    stdin: Option<Vec<u8>>,
+    timeout: Duration,
+    cancelled: &dyn Fn() -> bool,
+) -> Result<ExternalOutput, ExternalError> {
+    let program = || tool.program().to_string();
+    let mut child = command.spawn().map_err(|e| ExternalError::Spawn {
+        program: program(),
+        detail: e.to_string(),
+    })?;
+    if let (Some(mut pipe), Some(data)) = (child.stdin.take(), stdin) {
+        // 程序提前退出时写入会失败，以退出状态和 stderr 为准
+        thread::spawn(move || {
+            let _ = pipe.write_all(&data);
+        });
+    }
+    let overflow = Arc::new(AtomicBool::new(false));
+    let limit = tool.stdout_limit();
+    let stdout = Capture::start(child.stdout.take(), limit, Some(overflow.clone()));
+    let stderr = Capture::start(child.stderr.take(), MAX_STDERR, None);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => {}
+            Err(e) => {
+                kill(&mut child);
+                return Err(ExternalError::Spawn {
+                    program: program(),
+                    detail: e.to_string(),
+                });
+            }
+        }
+        if overflow.load(Ordering::Relaxed) {
+            kill(&mut child);
+            return Err(ExternalError::OutputTooLarge {
+                program: program(),
+                limit,
+            });
+        }
+        if cancelled() {
+            kill(&mut child);
+            return Err(ExternalError::Cancelled { program: program() });
+        }
+        if Instant::now() >= deadline {
+            kill(&mut child);
+            return Err(ExternalError::Timeout {
+                program: program(),
+                timeout,
+            });
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let grace = Instant::now() + OUTPUT_GRACE;
+    let stdout = stdout.finish(grace);
+    let stderr = stderr.finish(grace);
+    if overflow.load(Ordering::Relaxed) {
+        return Err(ExternalError::OutputTooLarge {
+            program: program(),
+            limit,
+        });
+    }
+    if !status.success() {
+        return Err(ExternalError::Exit {
+            program: program(),
+            status,
+            stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+        });
+    }
+    Ok(ExternalOutput { stdout, stderr })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::RwLock;
+
+    /// 写脚本时其他线程正好 fork 会让 exec 报 ETXTBSY，写入与运行互斥
+    static SCRIPTS: RwLock<()> = RwLock::new(());
+
+    /// 临时目录中的假程序
+    fn script(dir: &tempfile::TempDir, name: &str, body: &str) -> ExternalTool {
+        let path = dir.path().join(name);
+        let _writing = SCRIPTS.write().unwrap();
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        ExternalTool::User(path)
+    }
+
+    fn run(
+        tool: ExternalTool,
+        args: &[&str],
+        stdin: Option<&[u8]>,
+        timeout: Duration,
+    ) -> Result<ExternalOutput, ExternalError> {
+        let _running = SCRIPTS.read().unwrap();
+        run_external(tool, args, stdin.map(<[u8]>::to_vec), timeout)
+    }
+
+    #[test]
+    fn output_and_stdin_are_passed_through() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = script(&dir, "echo", r#"printf '%s|' "$@"; cat"#);
+        let output = run(tool, &["a b", "c"], Some(b"input"), PROBE_TIMEOUT).unwrap();
+        assert_eq!(output.stdout, b"a b|c|input");
+        assert_eq!(output.text(), "a b|c|input");
+    }
+
+    #[test]
+    fn version_on_stderr_is_used_as_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = script(&dir, "old", "echo 'old 1.0' >&2");
+        let output = run(tool, &[], None, PROBE_TIMEOUT).unwrap();
+        assert_eq!(output.text().trim(), "old 1.0");
+    }
+
+    #[test]
+    fn missing_or_disallowed_programs_are_not_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = ExternalTool::User(dir.path().join("missing"));
+        assert!(matches!(
+            run(missing, &[], None, PROBE_TIMEOUT),
+            Err(ExternalError::NotFound { .. })
+        ));
+        // 没有执行权限的文件按不存在处理
+        let plain = dir.path().join("plain");
+        std::fs::write(&plain, "#!/bin/sh\n").unwrap();
+        assert!(matches!(
+            ExternalTool::User(plain).resolve(),
+            Err(ExternalError::NotFound { .. })
+        ));
+        assert!(matches!(
+            ExternalTool::User("relative.sh".into()).resolve(),
+            Err(ExternalError::NotAllowed { .. })
+        ));
+    }
+
+    #[test]
+    fn non_zero_exit_reports_status_and_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = script(
+            &dir,
+            "fail",
+            "echo partial; echo '  bad input  ' >&2; exit 3",
+        );
+        match run(tool, &[], None, PROBE_TIMEOUT) {
+            Err(ExternalError::Exit { status, stderr, .. }) => {
+                assert_eq!(status.code(), Some(3));
+                assert_eq!(stderr, "bad input");
+            }
+            other => panic!("应为 Exit 错误: {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn slow_program_is_killed_on_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = script(&dir, "slow", "exec sleep 30");
+        let started = Instant::now();
+        let result = run(tool, &[], None, Duration::from_millis(200));
+        assert!(matches!(result, Err(ExternalError::Timeout { .. })));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn cancelled_program_is_killed() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = script(&dir, "slow", "exec sleep 30");
+        let _running = SCRIPTS.read().unwrap();
+        let started = Instant::now();
+        let cancel_after = started + Duration::from_millis(100);
+        let result =
+            run_external_cancellable(tool, Vec::<&str>::new(), None, PROBE_TIMEOUT, &|| {
+                Instant::now() >= cancel_after
+            });
+        assert!(matches!(result, Err(ExternalError::Cancelled { .. })));
+        assert!(started.elapsed() < PROBE_TIMEOUT);
+    }
+
+    #[test]
+    fn oversized_output_is_cut_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = script(&dir, "flood", "exec cat /dev/zero");
+        let result = run(tool, &[], None, Duration::from_secs(30));
+        assert!(matches!(
+            result,
+            Err(ExternalError::OutputTooLarge {
+                limit: MAX_STDOUT,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,368 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+/// 值只在标签所在行及下一行中查找，避免匹配到很远处的无关内容
+const VALUE_LINES: usize = 2;
+
+/// 提取模板中的一个字段
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtractionField {
+    pub name: String,
+    /// 标签正则，如 `(?i)order\s*(no|number|#)`
+    pub label: String,
+    /// 值正则，从标签之后开始匹配；有捕获组时取第一个捕获组
+    pub value: String,
+}
+
+/// 从识别文字中提取字段的模板
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtractionTemplate {
+    pub name: String,
+    pub fields: Vec<ExtractionField>,
+}
+
+/// 至少含一个数字，避免把下一个标签当作编号
+const NUMBER: &str = r"(?i)\b([a-z0-9-]*\d[a-z0-9-]{2,})";
+const AMOUNT: &str = r"[$¥€£]?\s*(\d[\d,]*(?:\.\d{1,2})?)";
+const DATE: &str = r"(\d{4}[-/.年]\d{1,2}[-/.月]\d{1,2}日?|\d{1,2}/\d{1,2}/\d{2,4})";
+
+/// 内置的示例模板，设置中同名的模板优先
+fn builtin_templates() -> Vec<ExtractionTemplate> {
+    let field = |name: &str, label: &str, value: &str| ExtractionField {
+        name: name.into(),
+        label: label.into(),
+        value: value.into(),
+    };
+    vec![
+        ExtractionTemplate {
+            name: "invoice".into(),
+            fields: vec![
+                field(
+                    "invoice_number",
+                    r"(?i)invoice\s*(no\.?|number|#)|发票号码",
+                    NUMBER,
+                ),
+                field("date", r"(?i)(invoice\s*)?date|开票日期", DATE),
+                field("tax", r"(?i)\b(vat|tax)\b|税额", AMOUNT),
+                field("total", r"(?i)\b(grand\s*)?total\b|价税合计", AMOUNT),
+            ],
+        },
+        ExtractionTemplate {
+            name: "receipt".into(),
+            fields: vec![
+                field(
+                    "order_number",
+                    r"(?i)order\s*(no\.?|number|id|#)|订单号|订单编号",
+                    NUMBER,
+                ),
+                field(
+                    "tracking_number",
+                    r"(?i)tracking\s*(no\.?|number|#)?|运单号|快递单号",
+                    NUMBER,
+                ),
+                field("date", r"(?i)(order\s*)?date|下单时间|日期", DATE),
+                field("total", r"(?i)\b(order\s*)?total\b|实付款|合计", AMOUNT),
+            ],
+        },
+    ]
+}
+
+struct Compiled {
+    name: String,
+    label: Regex,
+    value: Regex,
+}
+
+fn compile(template: &ExtractionTemplate) -> Result<Vec<Compiled>, AppError> {
+    template
+        .fields
+        .iter()
+        .map(|field| {
+            let regex = |part: &'static str, pattern: &str| {
+                // detail 中用 ^ 标出出错位置
+                Regex::new(pattern).map_err(|e| {
+                    AppError::invalid(format!(
+                        "提取模板 {} 的字段 {} 的 {part} 正则无效:\n{e}",
+                        template.name, field.name
+                    ))
+                })
+            };
+            Ok(Compiled {
+                name: field.name.clone(),
+                label: regex("label", &field.label)?,
+                value: regex("value", &field.value)?,
+            })
+        })
+        .collect()
+}
+
+pub fn validate(template: &ExtractionTemplate) -> Result<(), AppError> {
+    if template.name.trim().is_empty() {
+        return Err(AppError::invalid("模板名称不能为空"));
+    }
+    if template.fields.is_empty() {
+        return Err(AppError::invalid(format!(
+            "提取模板 {} 没有字段",
+            template.name
+        )));
+    }
+    for (i, field) in template.fields.iter().enumerate() {
+        if field.name.trim().is_empty() {
+            return Err(AppError::invalid(format!(
+                "提取模板 {} 的字段名不能为空",
+                template.name
+            )));
+        }
+        if template.fields[..i].iter().any(|f| f.name == field.name) {
+            return Err(AppError::invalid(format!(
+                "提取模板 {} 的字段名重复: {}",
+                template.name, field.name
+            )));
+        }
+    }
+    compile(template).map(|_| ())
+}
+
+/// 设置中的模板，启动时校验
+pub fn validate_all(templates: &[ExtractionTemplate]) -> Result<(), String> {
+    for (i, template) in templates.iter().enumerate() {
+        validate(template).map_err(|e| e.detail().unwrap_or_default())?;
+        if templates[..i].iter().any(|t| t.name == template.name) {
+            return Err(format!("提取模板名称重复: {}", template.name));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldStatus {
+    Found,
+    Missing,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExtractedField {
+    pub name: String,
+    pub status: FieldStatus,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Extraction {
+    pub template: String,
+    pub fields: Vec<ExtractedField>,
+    /// 未找到的字段数
+    pub missing: usize,
+}
+
+/// 标签之后、到标签下一行末尾为止的文字
+fn after_label(text: &str, end: usize) -> &str {
+    let rest = &text[end..];
+    let limit = rest
+        .match_indices('\n')
+        .nth(VALUE_LINES - 1)
+        .map_or(rest.len(), |(i, _)| i);
+    &rest[..limit]
+}
+
+/// 每个标签出现处依次尝试，取第一个找到值的位置
+fn find_value(field: &Compiled, text: &str) -> Option<String> {
+    field.label.find_iter(text).find_map(|label| {
+        let captures = field.value.captures(after_label(text, label.end()))?;
+        let value = captures.get(1).or_else(|| captures.get(0))?;
+        Some(value.as_str().trim().to_string()).filter(|v| !v.is_empty())
+    })
+}
+
+fn apply(template: &ExtractionTemplate, text: &str) -> Result<Extraction, AppError> {
+    let fields: Vec<ExtractedField> = compile(template)?
+        .iter()
+        .map(|field| {
+            let value = find_value(field, text);
+            ExtractedField {
+                name: field.name.clone(),
+                status: if value.is_some() {
+                    FieldStatus::Found
+                } else {
+                    FieldStatus::Missing
+                },
+                value,
+            }
+        })
+        .collect();
+    Ok(Extraction {
+        template: template.name.clone(),
+        missing: fields.iter().filter(|f| f.value.is_none()).count(),
+        fields,
+    })
+}
+
+fn templates(app: &AppHandle) -> Vec<ExtractionTemplate> {
+    let mut templates = app.state::<SettingsState>().get().extraction_templates;
+    for builtin in builtin_templates() {
+        if !templates.iter().any(|t| t.name == builtin.name) {
+            templates.push(builtin);
+        }
+    }
+    templates
+}
+
+fn find_template(app: &AppHandle, name: &str) -> Result<ExtractionTemplate, AppError> {
+    templates(app)
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| AppError::invalid(format!("未找到提取模板: {name}")))
+}
+
+/// 按模板提取字段，供识别命令的 extract_with 选项调用
+pub fn extract(app: &AppHandle, text: &str, template_name: &str) -> Result<Extraction, AppError> {
+    apply(&find_template(app, template_name)?, text)
+}
+
+/// 按模板从识别文字中提取字段，返回每个字段是否找到及其值
+#[tauri::command]
+pub fn extract_fields(
+    app: AppHandle,
+    text: String,
+    template_name: String,
+) -> Result<Extraction, AppError> {
+    extract(&app, &text, &template_name)
+}
+
+#[derive(Serialize)]
+pub struct TemplateInfo {
+    #[serde(flatten)]
+    pub template: ExtractionTemplate,
+    /// 内置模板，未被设置中的同名模板覆盖
+    pub builtin: bool,
+}
+
+#[tauri::command]
+pub fn list_extraction_templates(app: AppHandle) -> Vec<TemplateInfo> {
+    let custom = app.state::<SettingsState>().get().extraction_templates;
+    templates(&app)
+        .into_iter()
+        .map(|template| TemplateInfo {
+            builtin: !custom.iter().any(|t| t.name == template.name),
+            template,
+        })
+        .collect()
+}
+
+/// 校验后保存到设置，同名模板（包括内置模板）被替换
+#[tauri::command]
+pub fn save_extraction_template(
+    app: AppHandle,
+    def: ExtractionTemplate,
+) -> Result<Vec<TemplateInfo>, AppError> {
+    validate(&def)?;
+    app.state::<SettingsState>()
+        .update(|s| {
+            match s
+                .extraction_templates
+                .iter_mut()
+                .find(|t| t.name == def.name)
+            {
+                Some(existing) => *existing = def,
+                None => s.extraction_templates.push(def),
+            }
+        })
+        .map_err(AppError::InvalidInput)?;
+    Ok(list_extraction_templates(app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVOICE: &str =
+        "ACME Corp\nInvoice No: INV-2024-0042\nDate: 2024-03-15\nVAT 12.50\nTotal\n$ 1,234.56";
+
+    fn builtin(name: &str) -> ExtractionTemplate {
+        builtin_templates()
+            .into_iter()
+            .find(|t| t.name == name)
+            .unwrap()
+    }
+
+    fn values(extraction: &Extraction) -> Vec<(&str, Option<&str>)> {
+        extraction
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.value.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn builtin_templates_are_valid() {
+        assert!(validate_all(&builtin_templates()).is_ok());
+    }
+
+    #[test]
+    fn invoice_fields_are_found_after_their_labels() {
+        let extraction = apply(&builtin("invoice"), INVOICE).unwrap();
+        assert_eq!(
+            values(&extraction),
+            [
+                ("invoice_number", Some("INV-2024-0042")),
+                ("date", Some("2024-03-15")),
+                ("tax", Some("12.50")),
+                ("total", Some("1,234.56")),
+            ]
+        );
+        assert_eq!(extraction.missing, 0);
+
+        let receipt = apply(&builtin("receipt"), "订单编号：A1234567\n实付款 ¥88.00").unwrap();
+        assert_eq!(
+            values(&receipt),
+            [
+                ("order_number", Some("A1234567")),
+                ("tracking_number", None),
+                ("date", None),
+                ("total", Some("88.00")),
+            ]
+        );
+        assert_eq!(receipt.missing, 2);
+    }
+
+    #[test]
+    fn values_are_only_searched_on_the_label_line_and_the_next() {
+        let template = builtin("invoice");
+        let far = apply(&template, "Total\n\n$ 5.00").unwrap();
+        assert_eq!(far.fields[3].value, None);
+        // 第一个标签后没有值时尝试下一处标签
+        let second = apply(&template, "Total due later\n\n\nGrand total: 9.99").unwrap();
+        assert_eq!(second.fields[3].value.as_deref(), Some("9.99"));
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        let field = |name: &str, value: &str| ExtractionField {
+            name: name.into(),
+            label: "Label".into(),
+            value: value.into(),
+        };
+        let template = |fields| ExtractionTemplate {
+            name: "custom".into(),
+            fields,
+        };
+        let detail = |t: &ExtractionTemplate| validate(t).unwrap_err().detail().unwrap();
+
+        assert_eq!(detail(&template(vec![])), "提取模板 custom 没有字段");
+        let duplicate = template(vec![field("a", r"\d+"), field("a", r"\w+")]);
+        assert_eq!(detail(&duplicate), "提取模板 custom 的字段名重复: a");
+        let broken = template(vec![field("a", r"(\d+")]);
+        assert!(detail(&broken).starts_with("提取模板 custom 的字段 a 的 value 正则无效"));
+
+        let ok = template(vec![field("a", r"\d+")]);
+        assert_eq!(
+            validate_all(&[ok.clone(), ok]).unwrap_err(),
+            "提取模板名称重复: custom"
+        );
+    }
+}
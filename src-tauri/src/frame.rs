@@ -0,0 +1,77 @@
+use image::{DynamicImage, RgbaImage};
+use std::borrow::Cow;
+use std::io::Write;
+
+use crate::error::AppError;
+
+/// 一次截图的结果：xcap 直接得到像素，grim 等命令行工具输出 PNG。
+///
+/// 后端内部的流程（快速截图、命令行识别、缓存中截图的识别）按需转换，
+/// 不必先编码成 PNG 再解码回来
+pub enum Frame {
+    Png(Vec<u8>),
+    Image(DynamicImage),
+}
+
+impl Frame {
+    pub fn to_png(&self) -> Result<Cow<'_, [u8]>, AppError> {
+        match self {
+            Frame::Png(png) => Ok(Cow::Borrowed(png)),
+            Frame::Image(image) => encode_png(image).map(Cow::Owned),
+        }
+    }
+
+    pub fn into_png(self) -> Result<Vec<u8>, AppError> {
+        match self {
+            Frame::Png(png) => Ok(png),
+            Frame::Image(image) => encode_png(&image),
+        }
+    }
+
+    pub fn into_image(self) -> Result<DynamicImage, AppError> {
+        match self {
+            Frame::Png(png) => image::load_from_memory(&png).map_err(AppError::invalid),
+            Frame::Image(image) => Ok(image),
+        }
+    }
+}
+
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, AppError> {
+    let png = match image {
+        // 截图本身就是 RGBA，避免再复制一份
+        DynamicImage::ImageRgba8(rgba) => encode_png_fast(rgba),
+        image => encode_png_fast(&image.to_rgba8()),
+    };
+    png.map_err(AppError::Internal)
+}
+
+/// 每次写入编码器的行数
+const PNG_WRITE_ROWS: usize = 64;
+/// 每个 IDAT 块的大小
+const PNG_CHUNK_SIZE: usize = 1 << 20;
+
+/// 使用快速 PNG 压缩，按行分段写入。
+///
+/// 压缩数据直接写入输出，不像整幅编码那样先在内部缓存一份；多显示器的大截图峰值内存约减半
+fn encode_png_fast(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let (width, height) = image.dimensions();
+    let mut buf = Vec::new();
+    let mut encoder = png::Encoder::new(&mut buf, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Fast);
+    encoder.set_filter(png::Filter::Sub);
+    // 截图已统一转换为 sRGB
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    let mut stream = writer
+        .stream_writer_with_size(PNG_CHUNK_SIZE)
+        .map_err(|e| e.to_string())?;
+    let rows_len = PNG_WRITE_ROWS * width as usize * 4;
+    for rows in image.as_raw().chunks(rows_len.max(1)) {
+        stream.write_all(rows).map_err(|e| e.to_string())?;
+    }
+    stream.finish().map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(buf)
+}
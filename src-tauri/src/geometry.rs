@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+/// 没有记录时恢复到的默认大小（与 tauri.conf.json 一致）
+const DEFAULT_SIZE: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+#[derive(Clone, Copy)]
+struct SavedGeometry {
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    maximized: bool,
+}
+
+/// 全屏前的窗口位置与大小（窗口标签 → 几何信息）
+#[derive(Default)]
+pub struct WindowGeometry(Mutex<HashMap<String, SavedGeometry>>);
+
+/// 进入全屏前记录窗口几何信息；已经全屏时不覆盖之前的记录
+pub fn remember(app: &AppHandle, window: &WebviewWindow) {
+    if window.is_fullscreen().unwrap_or(false) {
+        return;
+    }
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+        return;
+    };
+    let saved = SavedGeometry {
+        position,
+        size,
+        maximized: window.is_maximized().unwrap_or(false),
+    };
+    if let Ok(mut map) = app.state::<WindowGeometry>().0.lock() {
+        map.insert(window.label().to_string(), saved);
+    }
+}
+
+/// 显示器的位置与大小
+type Area = (PhysicalPosition<i32>, PhysicalSize<u32>);
+
+fn area(monitor: &Monitor) -> Area {
+    (*monitor.position(), *monitor.size())
+}
+
+fn intersects((m_pos, m_size): Area, pos: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> bool {
+    pos.x < m_pos.x + m_size.width as i32
+        && pos.x + size.width as i32 > m_pos.x
+        && pos.y < m_pos.y + m_size.height as i32
+        && pos.y + size.height as i32 > m_pos.y
+}
+
+/// 把窗口放到显示器中央，窗口大于显示器时缩小
+fn center_on((m_pos, m_size): Area, size: PhysicalSize<u32>) -> Area {
+    let size = PhysicalSize::new(size.width.min(m_size.width), size.height.min(m_size.height));
+    let position = PhysicalPosition::new(
+        m_pos.x + (m_size.width - size.width) as i32 / 2,
+        m_pos.y + (m_size.height - size.height) as i32 / 2,
+    );
+    (position, size)
+}
+
+/// 退出全屏后恢复记录的位置与大小；原显示器已断开时移到现有显示器上
+pub fn restore(app: &AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let saved = app
+        .state::<WindowGeometry>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(window.label());
+
+    let monitors = window.available_monitors().unwrap_or_default();
+    let fallback_monitor = window
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .or_else(|| monitors.first().cloned());
+
+    let (position, size, maximized) = match saved {
+        Some(g)
+            if monitors
+                .iter()
+                .any(|m| intersects(area(m), g.position, g.size)) =>
+        {
+            (Some(g.position), g.size, g.maximized)
+        }
+        Some(g) => {
+            let placed = fallback_monitor
+                .as_ref()
+                .map(|m| center_on(area(m), g.size));
+            (
+                placed.map(|p| p.0),
+                placed.map_or(g.size, |p| p.1),
+                g.maximized,
+            )
+        }
+        None => {
+            let placed = fallback_monitor
+                .as_ref()
+                .map(|m| center_on(area(m), DEFAULT_SIZE));
+            (
+                placed.map(|p| p.0),
+                placed.map_or(DEFAULT_SIZE, |p| p.1),
+                false,
+            )
+        }
+    };
+
+    window.set_size(size).map_err(|e| e.to_string())?;
+    match position {
+        Some(position) => window.set_position(position).map_err(|e| e.to_string())?,
+        None => window.center().map_err(|e| e.to_string())?,
+    }
+    if maximized {
+        window.maximize().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: Area = (PhysicalPosition::new(0, 0), PhysicalSize::new(1920, 1080));
+    const RIGHT: Area = (
+        PhysicalPosition::new(1920, 0),
+        PhysicalSize::new(1280, 1024),
+    );
+
+    #[test]
+    fn windows_touching_only_the_edge_do_not_intersect() {
+        let size = PhysicalSize::new(400, 300);
+        assert!(intersects(LEFT, PhysicalPosition::new(100, 100), size));
+        // 部分超出显示器仍算在其上
+        assert!(intersects(RIGHT, PhysicalPosition::new(1700, 900), size));
+        assert!(!intersects(LEFT, PhysicalPosition::new(1920, 0), size));
+        assert!(!intersects(LEFT, PhysicalPosition::new(-400, 0), size));
+        assert!(!intersects(RIGHT, PhysicalPosition::new(2000, 1024), size));
+    }
+
+    #[test]
+    fn centering_shrinks_windows_larger_than_the_monitor() {
+        let (position, size) = center_on(RIGHT, DEFAULT_SIZE);
+        assert_eq!(position, PhysicalPosition::new(1920 + 240, 212));
+        assert_eq!(size, DEFAULT_SIZE);
+
+        let (position, size) = center_on(RIGHT, PhysicalSize::new(3000, 600));
+        assert_eq!(position, PhysicalPosition::new(1920, 212));
+        assert_eq!(size, PhysicalSize::new(1280, 600));
+    }
+}
@@ -0,0 +1,123 @@
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use crate::capture;
+use crate::commands::Recording;
+use crate::error::AppError;
+use crate::frame::Frame;
+use crate::launch::{Cli, Region};
+use crate::ocr::{self, OcrOptions};
+use crate::stats;
+
+pub fn crop(frame: Frame, region: Region) -> Result<Frame, AppError> {
+    let img = frame.into_image()?;
+    // 区域可能来自命令行或本地接口，坐标相加要防止 u32 溢出
+    let fits =
+        |start: u32, len: u32, limit: u32| start.checked_add(len).is_some_and(|end| end <= limit);
+    if !fits(region.x, region.width, img.width()) || !fits(region.y, region.height, img.height()) {
+        return Err(AppError::invalid(format!(
+            "区域 {},{},{},{} 超出截图范围 {}x{}",
+            region.x,
+            region.y,
+            region.width,
+            region.height,
+            img.width(),
+            img.height()
+        )));
+    }
+    Ok(Frame::Image(img.crop_imm(
+        region.x,
+        region.y,
+        region.width,
+        region.height,
+    )))
+}
+
+/// 命令行模式：不创建窗口，截图（可选识别文字）后写入文件或标准输出
+pub fn run(cli: &Cli) -> Result<(), String> {
+    let to_stdout = cli.output.as_deref() == Some(std::path::Path::new("-"));
+    if cli.ocr && to_stdout {
+        return Err("--ocr 与 --output - 不能同时使用".into());
+    }
+    if !cli.ocr && cli.output.is_none() {
+        return Err("需要指定 --output 或 --ocr".into());
+    }
+
+    if cli.delay > 0 {
+        thread::sleep(Duration::from_secs(cli.delay));
+    }
+
+    let mut frame = capture::capture(cli.monitor, &Recording(&()))?.frame;
+    if let Some(region) = cli.region {
+        frame = crop(frame, region)?;
+    }
+
+    match cli.output.as_deref() {
+        Some(_) if to_stdout => std::io::stdout()
+            .lock()
+            .write_all(&frame.to_png()?)
+            .map_err(|e| e.to_string())?,
+        Some(path) => crate::durable::atomic_write(path, &frame.to_png()?)?,
+        None => {}
+    }
+
+    if cli.ocr {
+        let ocr = ocr::recognize_text(
+            &frame.into_image()?,
+            &OcrOptions::new(crate::settings::DEFAULT_OCR_LANG),
+            &mut Default::default(),
+            &|| false,
+        )?;
+        stats::record_ocr(ocr.text.chars().count(), ocr.duration_ms);
+        if let Some(warning) = ocr.warning {
+            eprintln!("{warning}");
+        }
+        println!("{}", ocr.text);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, GenericImageView};
+
+    fn frame() -> Frame {
+        Frame::Image(DynamicImage::new_rgba8(10, 8))
+    }
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn crop_inside_bounds() {
+        let img = crop(frame(), region(2, 1, 8, 7))
+            .unwrap()
+            .into_image()
+            .unwrap();
+        assert_eq!(img.dimensions(), (8, 7));
+    }
+
+    #[test]
+    fn crop_rejects_out_of_range_regions() {
+        let err = crop(frame(), region(3, 0, 8, 1)).err().unwrap();
+        assert_eq!(err.code(), "invalid_input");
+        assert!(crop(frame(), region(0, 1, 1, 8)).is_err());
+    }
+
+    #[test]
+    fn crop_rejects_overflowing_regions() {
+        // 坐标相加溢出 u32 时返回错误而不是 panic
+        let err = crop(frame(), region(u32::MAX, 0, 1, 1)).err().unwrap();
+        assert_eq!(err.code(), "invalid_input");
+        assert!(crop(frame(), region(0, u32::MAX, 1, 1)).is_err());
+        assert!(crop(frame(), region(1, 0, u32::MAX, 1)).is_err());
+    }
+}
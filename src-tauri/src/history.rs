@@ -0,0 +1,1033 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::capture_cache::CaptureCache;
+use crate::capture_id::CaptureId;
+use crate::error::AppError;
+use crate::night_light::Compensation;
+use crate::ocr::{OcrOptions, OcrOverrides};
+use crate::ocr_queue::{self, JobInput, JobPriority, OcrBatch, OcrQueue, Recognized};
+use crate::settings::SettingsState;
+
+/// 截图来源信息，随截图一起记入历史
+#[derive(Default)]
+pub struct CaptureSource {
+    pub monitor: Option<String>,
+    pub backend: Option<String>,
+    /// 截图触发时的前台应用名
+    pub app: Option<String>,
+    /// 截图做过的夜灯补偿
+    pub night_light: Option<Compensation>,
+}
+
+/// 记住置信度的截图数，加入历史通常紧跟在识别之后
+const MAX_REMEMBERED_CONFIDENCES: usize = 8;
+
+/// find_similar 默认的汉明距离上限，64 位哈希中相差不超过 8 位视为相似
+const DEFAULT_MAX_DISTANCE: u32 = 8;
+
+/// 历史去重设置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryDedupSettings {
+    /// 窗口期内已有完全相同的截图时不另存图片，只记录引用
+    pub enabled: bool,
+    pub window_minutes: u64,
+}
+
+impl HistoryDedupSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=1440).contains(&self.window_minutes) {
+            return Err("去重窗口需在 1–1440 分钟之间".into());
+        }
+        Ok(())
+    }
+}
+
+impl Default for HistoryDedupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_minutes: 10,
+        }
+    }
+}
+
+/// 单条截图历史
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Unix 秒
+    pub timestamp: i64,
+    pub image_path: String,
+    #[serde(default)]
+    pub monitor: Option<String>,
+    #[serde(default)]
+    pub ocr_text: Option<String>,
+    /// 截图时使用的后端
+    #[serde(default)]
+    pub backend: Option<String>,
+    /// 图片的 64 位差异哈希（16 位十六进制），用于查找相似截图
+    #[serde(default)]
+    pub phash: Option<String>,
+    /// 与该条历史完全相同的重复截图，不另存图片，image_path 指向原图
+    #[serde(default)]
+    pub duplicate_of: Option<u64>,
+    /// 截图时的前台应用（已转为可用于文件名的形式）
+    #[serde(default)]
+    pub app: Option<String>,
+    /// 识别 ocr_text 时使用的参数，重新识别时沿用
+    #[serde(default)]
+    pub ocr_options: Option<OcrOptions>,
+    /// 识别结果各词置信度（0–100）的平均值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_confidence: Option<f32>,
+    /// 截图按夜灯色温补偿过
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub night_light: Option<Compensation>,
+    /// 从文件夹导入的原始路径，再次导入时据此跳过
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported_from: Option<String>,
+    /// image_path 直接指向用户自己的文件（导入时未复制），删除历史时不删除该文件，也不受保留策略限制
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+    /// 缩略图路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_path: Option<String>,
+}
+
+impl HistoryEntry {
+    fn date_label(&self) -> String {
+        Local
+            .timestamp_opt(self.timestamp, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
+
+    /// 参与索引的全部文本：OCR 文本 + 日期 + 显示器名
+    fn searchable_text(&self) -> String {
+        let mut text = self.date_label();
+        if let Some(monitor) = &self.monitor {
+            text.push('\n');
+            text.push_str(monitor);
+        }
+        if let Some(ocr) = &self.ocr_text {
+            text.push('\n');
+            text.push_str(ocr);
+        }
+        text
+    }
+}
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    pub entry: HistoryEntry,
+    /// 首个匹配位置附近的片段
+    pub snippet: String,
+    /// 高亮区间（按字符计，相对 snippet）
+    pub highlight_start: usize,
+    pub highlight_len: usize,
+    pub match_count: usize,
+}
+
+/// 简单倒排索引：ASCII 按单词、中日韩文字按二元组切分
+#[derive(Default)]
+struct SearchIndex {
+    postings: HashMap<String, HashSet<u64>>,
+}
+
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// 分词：连续 ASCII 字母数字为一个词，CJK 连续段切为二元组（单字段保留单字）
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+
+    let flush_cjk = |run: &mut Vec<char>, tokens: &mut Vec<String>| {
+        if run.len() == 1 {
+            tokens.push(run[0].to_string());
+        } else {
+            for pair in run.windows(2) {
+                tokens.push(pair.iter().collect());
+            }
+        }
+        run.clear();
+    };
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            cjk_run.push(c);
+        } else if c.is_alphanumeric() {
+            if !cjk_run.is_empty() {
+                flush_cjk(&mut cjk_run, &mut tokens);
+            }
+            word.extend(c.to_lowercase());
+        } else {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !cjk_run.is_empty() {
+                flush_cjk(&mut cjk_run, &mut tokens);
+            }
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    if !cjk_run.is_empty() {
+        flush_cjk(&mut cjk_run, &mut tokens);
+    }
+    tokens
+}
+
+impl SearchIndex {
+    fn insert(&mut self, entry: &HistoryEntry) {
+        for token in tokenize(&entry.searchable_text()) {
+            self.postings.entry(token).or_default().insert(entry.id);
+        }
+    }
+
+    fn remove(&mut self, id: u64) {
+        self.postings.retain(|_, ids| {
+            ids.remove(&id);
+            !ids.is_empty()
+        });
+    }
+
+    /// 返回包含全部查询词的条目 id；单个 CJK 字按前缀匹配二元组
+    fn candidates(&self, query_tokens: &[String]) -> HashSet<u64> {
+        let mut result: Option<HashSet<u64>> = None;
+        for token in query_tokens {
+            let ids: HashSet<u64> = if token.chars().count() == 1 && token.chars().all(is_cjk) {
+                self.postings
+                    .iter()
+                    .filter(|(k, _)| k.starts_with(token.as_str()) || k.ends_with(token.as_str()))
+                    .flat_map(|(_, ids)| ids.iter().copied())
+                    .collect()
+            } else {
+                self.postings.get(token).cloned().unwrap_or_default()
+            };
+            result = Some(match result {
+                Some(prev) => prev.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        result.unwrap_or_default()
+    }
+}
+
+/// 差异哈希（dHash）：缩小为 9×8 灰度图，逐行比较相邻像素
+pub fn dhash(image: &image::DynamicImage) -> u64 {
+    let small = image
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y).0[0] < small.get_pixel(x + 1, y).0[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+pub fn format_hash(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+fn parse_hash(hash: &str) -> Option<u64> {
+    u64::from_str_radix(hash, 16).ok()
+}
+
+fn hash_png(png: &[u8]) -> Option<u64> {
+    image::load_from_memory(png).ok().map(|img| dhash(&img))
+}
+
+#[derive(Serialize)]
+pub struct SimilarEntry {
+    pub entry: HistoryEntry,
+    /// 与查询图片哈希的汉明距离，0 为完全相同
+    pub distance: u32,
+}
+
+pub struct HistoryState {
+    dir: PathBuf,
+    entries: Mutex<Vec<HistoryEntry>>,
+    index: Mutex<SearchIndex>,
+    /// 最近几次交互式识别的置信度，按截图编号记下，加入历史时取用
+    confidences: Mutex<VecDeque<(CaptureId, f32)>>,
+}
+
+impl HistoryState {
+    /// 启动时读取索引文件并建立内存搜索索引
+    pub fn load(dir: PathBuf) -> Self {
+        let entries: Vec<HistoryEntry> =
+            crate::durable::read_recovering(&dir.join("index.json"), |data| {
+                serde_json::from_slice(data)
+            })
+            .unwrap_or_default();
+
+        let mut index = SearchIndex::default();
+        for entry in &entries {
+            index.insert(entry);
+        }
+
+        Self {
+            dir,
+            entries: Mutex::new(entries),
+            index: Mutex::new(index),
+            confidences: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 记下该次截图最近一次识别的置信度
+    pub fn remember_confidence(&self, capture_id: &CaptureId, confidence: Option<f32>) {
+        let Ok(mut confidences) = self.confidences.lock() else {
+            return;
+        };
+        confidences.retain(|(id, _)| id != capture_id);
+        if let Some(confidence) = confidence {
+            if confidences.len() >= MAX_REMEMBERED_CONFIDENCES {
+                confidences.pop_front();
+            }
+            confidences.push_back((capture_id.clone(), confidence));
+        }
+    }
+
+    fn confidence_for(&self, capture_id: &CaptureId) -> Option<f32> {
+        let confidences = self.confidences.lock().ok()?;
+        confidences
+            .iter()
+            .find(|(id, _)| id == capture_id)
+            .map(|(_, confidence)| *confidence)
+    }
+
+    fn persist(&self, entries: &[HistoryEntry]) -> Result<(), String> {
+        let data = serde_json::to_vec_pretty(entries).map_err(|e| e.to_string())?;
+        crate::durable::write_with_backup(&self.dir.join("index.json"), &data)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 新条目的编号：当前毫秒数，同一毫秒内导入多条时顺延
+    fn next_id(entries: &[HistoryEntry]) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let last = entries.iter().map(|e| e.id).max().unwrap_or(0);
+        now.max(last + 1)
+    }
+
+    /// 一次加入多条导入的历史，只写一次索引文件；编号在加入时分配
+    pub fn insert_imported(
+        &self,
+        imported: Vec<HistoryEntry>,
+    ) -> Result<Vec<HistoryEntry>, String> {
+        let mut entries = self.entries.lock().map_err(|e| e.to_string())?;
+        let mut added = Vec::with_capacity(imported.len());
+        for mut entry in imported {
+            entry.id = Self::next_id(&entries);
+            entries.push(entry.clone());
+            added.push(entry);
+        }
+        self.persist(&entries)?;
+        if let Ok(mut index) = self.index.lock() {
+            for entry in &added {
+                index.insert(entry);
+            }
+        }
+        Ok(added)
+    }
+
+    /// dedup_window 不为空时，窗口期内已有哈希完全相同的截图则只记录对原图的引用
+    pub fn add(
+        &self,
+        png: &[u8],
+        source: CaptureSource,
+        ocr_text: Option<String>,
+        ocr_options: Option<OcrOptions>,
+        ocr_confidence: Option<f32>,
+        dedup_window: Option<Duration>,
+    ) -> Result<HistoryEntry, String> {
+        let CaptureSource {
+            monitor,
+            backend,
+            app,
+            night_light,
+        } = source;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?;
+        let timestamp = now.as_secs() as i64;
+        let hash = hash_png(png);
+
+        let mut entries = self.entries.lock().map_err(|e| e.to_string())?;
+        let id = Self::next_id(&entries);
+        let original = dedup_window.zip(hash).and_then(|(window, hash)| {
+            let since = timestamp - window.as_secs() as i64;
+            entries.iter().rev().find(|e| {
+                e.timestamp >= since && e.phash.as_deref().and_then(parse_hash) == Some(hash)
+            })
+        });
+        let (image_path, duplicate_of) = match original {
+            Some(original) => (
+                original.image_path.clone(),
+                Some(original.duplicate_of.unwrap_or(original.id)),
+            ),
+            None => {
+                let path = self.dir.join(format!("{id}.png"));
+                crate::durable::atomic_write(&path, png)?;
+                (path.to_string_lossy().into_owned(), None)
+            }
+        };
+
+        let ocr_text = ocr_text.filter(|t| !t.trim().is_empty());
+        let entry = HistoryEntry {
+            id,
+            timestamp,
+            image_path,
+            monitor,
+            ocr_options: ocr_options.filter(|_| ocr_text.is_some()),
+            ocr_confidence: ocr_confidence.filter(|_| ocr_text.is_some()),
+            ocr_text,
+            backend,
+            phash: hash.map(format_hash),
+            duplicate_of,
+            app,
+            night_light,
+            imported_from: None,
+            external: false,
+            thumbnail_path: None,
+        };
+        if let Some(original) = duplicate_of {
+            tracing::info!(id, original, "截图与已有历史相同，只记录引用");
+        }
+        entries.push(entry.clone());
+        self.persist(&entries)?;
+        if let Ok(mut index) = self.index.lock() {
+            index.insert(&entry);
+        }
+        Ok(entry)
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.entries.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// 删除指定历史及其图片文件，返回释放的字节数；原图被删除时引用它的重复记录一并删除
+    pub fn remove(&self, ids: &HashSet<u64>) -> Result<u64, String> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut entries = self.entries.lock().map_err(|e| e.to_string())?;
+        let duplicates: Vec<u64> = entries
+            .iter()
+            .filter(|e| {
+                e.duplicate_of
+                    .is_some_and(|original| ids.contains(&original))
+            })
+            .map(|e| e.id)
+            .collect();
+        let mut ids = ids.clone();
+        ids.extend(duplicates);
+        let mut freed = 0u64;
+        entries.retain(|entry| {
+            if !ids.contains(&entry.id) {
+                return true;
+            }
+            if let Some(thumbnail) = &entry.thumbnail_path {
+                let _ = std::fs::remove_file(thumbnail);
+            }
+            // 重复记录与原图共用图片文件，导入时引用的用户文件不删除
+            if entry.duplicate_of.is_some() || entry.external {
+                return false;
+            }
+            if let Ok(meta) = std::fs::metadata(&entry.image_path) {
+                freed += meta.len();
+            }
+            let _ = std::fs::remove_file(&entry.image_path);
+            false
+        });
+        self.persist(&entries)?;
+        if let Ok(mut index) = self.index.lock() {
+            for id in ids {
+                index.remove(id);
+            }
+        }
+        Ok(freed)
+    }
+
+    /// 去掉图片已丢失的条目并重写索引文件，返回清理的条目数
+    pub fn vacuum(&self) -> Result<usize, String> {
+        let missing: HashSet<u64> = self
+            .entries()
+            .iter()
+            .filter(|e| !Path::new(&e.image_path).exists())
+            .map(|e| e.id)
+            .collect();
+        let count = missing.len();
+        self.remove(&missing)?;
+        Ok(count)
+    }
+
+    pub fn get(&self, id: u64) -> Option<HistoryEntry> {
+        self.entries
+            .lock()
+            .ok()
+            .and_then(|entries| entries.iter().find(|e| e.id == id).cloned())
+    }
+
+    /// 更新识别文字及其参数，并重建该条的搜索索引
+    fn update_ocr(
+        &self,
+        id: u64,
+        recognized: Recognized,
+        options: OcrOptions,
+    ) -> Result<HistoryEntry, String> {
+        let mut entries = self.entries.lock().map_err(|e| e.to_string())?;
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| format!("历史记录不存在: {id}"))?;
+        entry.ocr_text = Some(recognized.text).filter(|t| !t.trim().is_empty());
+        entry.ocr_confidence = recognized.confidence.filter(|_| entry.ocr_text.is_some());
+        entry.ocr_options = Some(options);
+        let entry = entry.clone();
+        self.persist(&entries)?;
+        if let Ok(mut index) = self.index.lock() {
+            index.remove(id);
+            index.insert(&entry);
+        }
+        Ok(entry)
+    }
+
+    /// 补算旧历史缺少的哈希并写回索引文件
+    fn ensure_hashes(&self) -> Result<(), String> {
+        let mut entries = self.entries.lock().map_err(|e| e.to_string())?;
+        let mut changed = false;
+        for entry in entries.iter_mut().filter(|e| e.phash.is_none()) {
+            let hash = image::open(&entry.image_path).ok().map(|img| dhash(&img));
+            if let Some(hash) = hash {
+                entry.phash = Some(format_hash(hash));
+                changed = true;
+            }
+        }
+        if changed {
+            self.persist(&entries)?;
+        }
+        Ok(())
+    }
+
+    /// 哈希距离不超过 max_distance 的历史，按距离排序；exclude 为查询的历史本身
+    pub fn similar(&self, hash: u64, max_distance: u32, exclude: Option<u64>) -> Vec<SimilarEntry> {
+        let mut similar: Vec<SimilarEntry> = self
+            .entries()
+            .into_iter()
+            .filter(|e| Some(e.id) != exclude)
+            .filter_map(|entry| {
+                let distance = (parse_hash(entry.phash.as_deref()?)? ^ hash).count_ones();
+                (distance <= max_distance).then_some(SimilarEntry { entry, distance })
+            })
+            .collect();
+        similar.sort_by_key(|s| (s.distance, std::cmp::Reverse(s.entry.timestamp)));
+        similar
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query_tokens = tokenize(query);
+        if query_tokens.is_empty() {
+            return Vec::new();
+        }
+
+        let ids = match self.index.lock() {
+            Ok(index) => index.candidates(&query_tokens),
+            Err(_) => return Vec::new(),
+        };
+        let entries = self.entries();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let mut hits: Vec<(f64, SearchHit)> = entries
+            .into_iter()
+            .filter(|e| ids.contains(&e.id))
+            .map(|entry| {
+                let text = entry.searchable_text();
+                let match_count = count_matches(&text, &query_tokens);
+                let (snippet, highlight_start, highlight_len) = make_snippet(&text, query.trim());
+                // 排名：匹配次数 + 时间衰减（越新越靠前）
+                let age_days = (now - entry.timestamp).max(0) as f64 / 86400.0;
+                let score = match_count as f64 + 5.0 / (1.0 + age_days);
+                (
+                    score,
+                    SearchHit {
+                        entry,
+                        snippet,
+                        highlight_start,
+                        highlight_len,
+                        match_count,
+                    },
+                )
+            })
+            .collect();
+
+        hits.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        hits.into_iter().take(limit).map(|(_, hit)| hit).collect()
+    }
+}
+
+fn count_matches(text: &str, query_tokens: &[String]) -> usize {
+    let tokens = tokenize(text);
+    tokens.iter().filter(|t| query_tokens.contains(t)).count()
+}
+
+/// 在首个匹配处截取片段，返回 (片段, 高亮起点, 高亮长度)，均按字符计
+fn make_snippet(text: &str, query: &str) -> (String, usize, usize) {
+    const CONTEXT: usize = 30;
+
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    let needle: Vec<char> = query.chars().flat_map(|c| c.to_lowercase()).collect();
+
+    // to_lowercase 可能改变长度，长度不一致时退化为不高亮
+    let found = if lower.len() == chars.len() && !needle.is_empty() {
+        lower
+            .windows(needle.len())
+            .position(|w| w == needle.as_slice())
+    } else {
+        None
+    };
+
+    let (pos, len) = match found {
+        Some(pos) => (pos, needle.len()),
+        None => (0, 0),
+    };
+
+    let start = pos.saturating_sub(CONTEXT);
+    let end = (pos + len + CONTEXT).min(chars.len());
+    let mut snippet: String = chars[start..end]
+        .iter()
+        .map(|&c| if c == '\n' { ' ' } else { c })
+        .collect();
+    let mut highlight_start = pos - start;
+    if start > 0 {
+        snippet.insert(0, '…');
+        highlight_start += 1;
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    (snippet, highlight_start, len)
+}
+
+#[tauri::command]
+pub fn add_to_history(
+    app: AppHandle,
+    state: State<'_, HistoryState>,
+    base64_data: String,
+    monitor: Option<String>,
+    ocr_text: Option<String>,
+    ocr_options: Option<OcrOptions>,
+    capture_id: Option<CaptureId>,
+) -> Result<HistoryEntry, String> {
+    let png = STANDARD.decode(&base64_data).map_err(|e| e.to_string())?;
+    let backend = crate::capture::get_preferred_backend().map(|b| b.name().to_string());
+    let settings = app.state::<SettingsState>().get();
+    let dedup = settings.history_dedup;
+    let window = dedup
+        .enabled
+        .then(|| Duration::from_secs(dedup.window_minutes * 60));
+    // 前端没有给出时，识别用的是设置中的语言和默认参数
+    let ocr_options = ocr_options.unwrap_or_else(|| OcrOptions::new(settings.ocr_lang));
+    let source = CaptureSource {
+        monitor,
+        backend,
+        app: capture_id
+            .as_ref()
+            .and_then(crate::active_app::for_capture)
+            .map(|a| a.name),
+        night_light: capture_id
+            .as_ref()
+            .and_then(|id| app.state::<CaptureCache>().night_light(id)),
+    };
+    let confidence = capture_id.as_ref().and_then(|id| state.confidence_for(id));
+    let entry = state.add(
+        &png,
+        source,
+        ocr_text,
+        Some(ocr_options),
+        confidence,
+        window,
+    )?;
+    crate::tray::refresh_recent(&app);
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn list_history(state: State<'_, HistoryState>) -> Vec<HistoryEntry> {
+    let mut entries = state.entries();
+    entries.reverse();
+    entries
+}
+
+/// 历史截图重新识别时使用的参数：保存的参数加上覆盖项
+fn reocr_options(
+    app: &AppHandle,
+    entry: &HistoryEntry,
+    override_options: Option<OcrOverrides>,
+) -> Result<OcrOptions, AppError> {
+    let options = entry
+        .ocr_options
+        .clone()
+        .unwrap_or_else(|| OcrOptions::new(app.state::<SettingsState>().get().ocr_lang))
+        .merge(override_options.unwrap_or_default());
+    options.validate()?;
+    Ok(options)
+}
+
+/// 等待重新识别的结果，更新该条的文字与搜索索引并发送 history-item-updated 事件
+pub(crate) fn apply_reocr(
+    app: &AppHandle,
+    id: u64,
+    rx: &mpsc::Receiver<Result<Recognized, AppError>>,
+    options: OcrOptions,
+) {
+    let recognized = match ocr_queue::wait_result(rx) {
+        Ok(recognized) => recognized,
+        Err(err) => {
+            tracing::warn!(id, error = %err, "重新识别历史截图失败");
+            return;
+        }
+    };
+    match app
+        .state::<HistoryState>()
+        .update_ocr(id, recognized, options)
+    {
+        Ok(entry) => {
+            let _ = app.emit("history-item-updated", &entry);
+        }
+        // 识别期间该条已被删除
+        Err(err) => tracing::warn!(id, error = %err, "更新历史识别结果失败"),
+    }
+}
+
+/// 用保存的识别参数（可部分覆盖）重新识别历史截图，返回识别任务编号。
+///
+/// 完成后更新该条的文字与搜索索引，并发送 history-item-updated 事件；
+/// 任务状态也可通过 ocr-job-finished 事件或 get_job_status 查询
+#[tauri::command]
+pub fn reocr_history_item(
+    app: AppHandle,
+    state: State<'_, HistoryState>,
+    id: u64,
+    override_options: Option<OcrOverrides>,
+) -> Result<u64, AppError> {
+    let entry = state
+        .get(id)
+        .ok_or_else(|| AppError::invalid(format!("历史记录不存在: {id}")))?;
+    let options = reocr_options(&app, &entry, override_options)?;
+
+    let (job_id, rx) = app.state::<OcrQueue>().submit(
+        JobInput::File(entry.image_path.into()),
+        options.clone(),
+        JobPriority::Normal,
+        None,
+    );
+    std::thread::spawn(move || apply_reocr(&app, id, &rx, options));
+    Ok(job_id)
+}
+
+/// 作为一个批次重新识别多条历史截图，进度通过 ocr-batch-progress 事件发送，可用 cancel_batch 取消；
+/// 每条完成后与 reocr_history_item 一样更新并发送 history-item-updated 事件
+#[tauri::command]
+pub fn reocr_history_items(
+    app: AppHandle,
+    state: State<'_, HistoryState>,
+    ids: Vec<u64>,
+    override_options: Option<OcrOverrides>,
+) -> Result<OcrBatch, AppError> {
+    if ids.is_empty() {
+        return Err(AppError::invalid("没有要识别的历史截图"));
+    }
+    let mut items = Vec::with_capacity(ids.len());
+    let mut targets = Vec::with_capacity(ids.len());
+    for id in ids {
+        let entry = state
+            .get(id)
+            .ok_or_else(|| AppError::invalid(format!("历史记录不存在: {id}")))?;
+        let options = reocr_options(&app, &entry, override_options.clone())?;
+        targets.push((id, options.clone()));
+        items.push((JobInput::File(entry.image_path.into()), options));
+    }
+    let (batch, receivers) = app
+        .state::<OcrQueue>()
+        .submit_batch(items, JobPriority::Normal);
+    std::thread::spawn(move || {
+        // 按提交顺序等待；先完成的结果留在各自的通道中
+        for ((id, options), rx) in targets.into_iter().zip(receivers) {
+            apply_reocr(&app, id, &rx, options);
+        }
+    });
+    Ok(batch)
+}
+
+/// 查找与某条历史（传 id）或一张图片（传 PNG 的 base64）相似的历史，max_distance 为汉明距离上限
+#[tauri::command]
+pub fn find_similar(
+    state: State<'_, HistoryState>,
+    id_or_base64: String,
+    max_distance: Option<u32>,
+) -> Result<Vec<SimilarEntry>, String> {
+    state.ensure_hashes()?;
+    let (hash, exclude) = match id_or_base64.trim().parse::<u64>() {
+        Ok(id) => {
+            let entry = state
+                .get(id)
+                .ok_or_else(|| format!("历史记录不存在: {id}"))?;
+            let hash = entry
+                .phash
+                .as_deref()
+                .and_then(parse_hash)
+                .ok_or_else(|| format!("无法读取历史图片: {}", entry.image_path))?;
+            (hash, Some(id))
+        }
+        Err(_) => {
+            let png = STANDARD
+                .decode(id_or_base64.trim())
+                .map_err(|e| e.to_string())?;
+            (hash_png(&png).ok_or("无法解析图片")?, None)
+        }
+    };
+    Ok(state.similar(
+        hash,
+        max_distance.unwrap_or(DEFAULT_MAX_DISTANCE).min(64),
+        exclude,
+    ))
+}
+
+#[tauri::command]
+pub fn search_history(
+    state: State<'_, HistoryState>,
+    query: String,
+    limit: Option<usize>,
+) -> Vec<SearchHit> {
+    state.search(&query, limit.unwrap_or(50))
+}
+
+#[derive(Serialize)]
+struct ExportMeta<'a> {
+    id: u64,
+    timestamp: i64,
+    datetime: String,
+    monitor: Option<&'a str>,
+    backend: Option<&'a str>,
+    app_version: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct ExportResult {
+    pub path: String,
+    pub size: u64,
+    pub entries: usize,
+}
+
+/// 把一条历史写入 zip：PNG 已压缩直接存储，文本类内容使用 deflate
+fn write_entry_to_zip(
+    zip: &mut ZipWriter<Cursor<Vec<u8>>>,
+    entry: &HistoryEntry,
+    prefix: &str,
+    app_version: &str,
+) -> Result<(), String> {
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let png =
+        std::fs::read(&entry.image_path).map_err(|e| format!("读取 {}: {e}", entry.image_path))?;
+    // 导入的历史可能是 JPEG 等其他格式
+    let ext = Path::new(&entry.image_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png");
+    zip.start_file(format!("{prefix}screenshot.{ext}"), stored)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&png).map_err(|e| e.to_string())?;
+
+    zip.start_file(format!("{prefix}text.txt"), deflated)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(entry.ocr_text.as_deref().unwrap_or_default().as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let meta = ExportMeta {
+        id: entry.id,
+        timestamp: entry.timestamp,
+        datetime: entry.date_label(),
+        monitor: entry.monitor.as_deref(),
+        backend: entry.backend.as_deref(),
+        app_version,
+    };
+    let meta_json = serde_json::to_vec_pretty(&meta).map_err(|e| e.to_string())?;
+    zip.start_file(format!("{prefix}meta.json"), deflated)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&meta_json).map_err(|e| e.to_string())
+}
+
+fn write_bundle(
+    app: &AppHandle,
+    entries: &[HistoryEntry],
+    path: &str,
+    per_entry_folder: bool,
+) -> Result<ExportResult, String> {
+    if entries.is_empty() {
+        return Err("没有可导出的历史记录".into());
+    }
+
+    let app_version = app.package_info().version.to_string();
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for entry in entries {
+        let prefix = if per_entry_folder {
+            format!("{}/", entry.id)
+        } else {
+            String::new()
+        };
+        write_entry_to_zip(&mut zip, entry, &prefix, &app_version)?;
+    }
+    let data = zip.finish().map_err(|e| e.to_string())?.into_inner();
+
+    let target = Path::new(path);
+    crate::durable::atomic_write(target, &data)?;
+    crate::save::remember_dir(target);
+    Ok(ExportResult {
+        path: path.to_string(),
+        size: data.len() as u64,
+        entries: entries.len(),
+    })
+}
+
+/// 导出单条历史：截图、识别文字与元数据打包为一个 zip
+#[tauri::command]
+pub fn export_history_item(
+    app: AppHandle,
+    state: State<'_, HistoryState>,
+    id: u64,
+    path: String,
+) -> Result<ExportResult, String> {
+    let entry = state
+        .get(id)
+        .ok_or_else(|| format!("历史记录不存在: {id}"))?;
+    write_bundle(&app, &[entry], &path, false)
+}
+
+/// 按 id 列表或时间范围（Unix 秒，闭区间）导出多条历史，每条一个子目录
+#[tauri::command]
+pub fn export_history_range(
+    app: AppHandle,
+    state: State<'_, HistoryState>,
+    path: String,
+    ids: Option<Vec<u64>>,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<ExportResult, String> {
+    let entries: Vec<HistoryEntry> = state
+        .entries()
+        .into_iter()
+        .filter(|e| ids.as_ref().is_none_or(|ids| ids.contains(&e.id)))
+        .filter(|e| from.is_none_or(|from| e.timestamp >= from))
+        .filter(|e| to.is_none_or(|to| e.timestamp <= to))
+        .collect();
+    write_bundle(&app, &entries, &path, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(seed: u8) -> Vec<u8> {
+        let image = image::RgbImage::from_fn(16, 16, |x, y| {
+            image::Rgb([(x * 16) as u8 ^ seed, (y * 16) as u8, seed])
+        });
+        crate::frame::encode_png(&image::DynamicImage::ImageRgb8(image)).unwrap()
+    }
+
+    #[test]
+    fn entries_record_the_app_of_their_own_capture() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = HistoryState::load(dir.path().to_path_buf());
+        let source = |app: Option<&str>| CaptureSource {
+            app: app.map(str::to_string),
+            ..Default::default()
+        };
+        let first = state
+            .add(&png(1), source(Some("firefox")), None, None, None, None)
+            .unwrap();
+        let second = state
+            .add(&png(2), source(None), None, None, None, None)
+            .unwrap();
+        assert_eq!(first.app.as_deref(), Some("firefox"));
+        assert_eq!(second.app, None);
+
+        let reloaded = HistoryState::load(dir.path().to_path_buf()).entries();
+        assert_eq!(reloaded[0].app.as_deref(), Some("firefox"));
+        assert_eq!(reloaded[1].app, None);
+    }
+
+    #[test]
+    fn confidence_of_the_capture_is_stored_with_its_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = HistoryState::load(dir.path().to_path_buf());
+        let (first, second) = (CaptureId::generate(), CaptureId::generate());
+        state.remember_confidence(&first, Some(91.0));
+        state.remember_confidence(&second, Some(40.0));
+        // 同一截图再次识别时取最近一次
+        state.remember_confidence(&first, Some(88.5));
+        assert_eq!(state.confidence_for(&first), Some(88.5));
+        state.remember_confidence(&second, None);
+        assert_eq!(state.confidence_for(&second), None);
+
+        let text = Some("Hello".to_string());
+        let with_text = state
+            .add(
+                &png(3),
+                CaptureSource::default(),
+                text,
+                None,
+                Some(88.5),
+                None,
+            )
+            .unwrap();
+        assert_eq!(with_text.ocr_confidence, Some(88.5));
+        // 没有识别文字时不记录置信度
+        let without_text = state
+            .add(
+                &png(4),
+                CaptureSource::default(),
+                None,
+                None,
+                Some(50.0),
+                None,
+            )
+            .unwrap();
+        assert_eq!(without_text.ocr_confidence, None);
+        let reloaded = HistoryState::load(dir.path().to_path_buf()).entries();
+        assert_eq!(reloaded[0].ocr_confidence, Some(88.5));
+    }
+}
@@ -0,0 +1,384 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+use crate::history::{self, HistoryEntry, HistoryState};
+use crate::ocr::OcrOptions;
+use crate::ocr_queue::{JobInput, JobPriority, OcrQueue};
+use crate::settings::SettingsState;
+
+/// 默认导入的扩展名
+const DEFAULT_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp", "gif", "tif", "tiff"];
+/// 缩略图的最长边
+const THUMBNAIL_MAX_SIDE: u32 = 256;
+/// 每处理这么多个文件写一次索引，中途取消或退出时已处理的部分不丢失
+const FLUSH_EVERY: usize = 50;
+/// 进度事件的最小间隔
+const PROGRESS_INTERVAL_MS: u128 = 200;
+
+static NEXT_IMPORT: AtomicU64 = AtomicU64::new(1);
+/// 进行中的导入：编号 → 取消标记
+static IMPORTS: Mutex<Option<HashMap<u64, Arc<AtomicBool>>>> = Mutex::new(None);
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// 包括子文件夹
+    pub recursive: bool,
+    /// 只导入这些扩展名（不区分大小写），为空时导入常见图片格式
+    pub extensions: Option<Vec<String>>,
+    /// 按文件修改时间过滤（Unix 秒，含两端）
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// 复制到历史目录；为空时按设置 history_import_copy
+    pub copy: Option<bool>,
+    /// 识别语言，为空时使用设置中的语言
+    pub lang: Option<String>,
+    /// 导入后在后台识别文字，建立搜索索引
+    pub ocr: Option<bool>,
+}
+
+/// history-import-progress 事件的内容
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ImportProgress {
+    pub import_id: u64,
+    /// 扫描到的符合条件的文件数
+    pub discovered: usize,
+    /// 已处理的文件数，包括跳过和失败的
+    pub processed: usize,
+    pub imported: usize,
+    /// 之前已导入过的文件
+    pub skipped_existing: usize,
+    /// 与已有历史的图片哈希相同
+    pub skipped_duplicates: usize,
+    pub failed: usize,
+    /// 识别文字的批次，可用 cancel_batch 单独取消
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ocr_batch_id: Option<u64>,
+    pub cancelled: bool,
+    pub done: bool,
+}
+
+fn imports() -> std::sync::MutexGuard<'static, Option<HashMap<u64, Arc<AtomicBool>>>> {
+    IMPORTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn wanted(path: &Path, extensions: &HashSet<String>) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext.to_ascii_lowercase()))
+}
+
+/// 按修改时间排序（旧的在前）的待导入文件；无法读取的子目录跳过
+fn discover(dir: &Path, options: &ImportOptions) -> Vec<(PathBuf, i64)> {
+    let extensions: HashSet<String> = match &options.extensions {
+        Some(list) if !list.is_empty() => list
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+            .collect(),
+        _ => DEFAULT_EXTENSIONS
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+    };
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            tracing::debug!(dir = %dir.display(), "无法读取文件夹，跳过");
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                if options.recursive {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if !meta.is_file() || !wanted(&path, &extensions) {
+                continue;
+            }
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64);
+            if options.since.is_some_and(|since| modified < since)
+                || options.until.is_some_and(|until| modified > until)
+            {
+                continue;
+            }
+            files.push((path, modified));
+        }
+    }
+    files.sort_by_key(|(_, modified)| *modified);
+    files
+}
+
+fn write_thumbnail(image: &DynamicImage, dir: &Path, name: &str) -> Result<PathBuf, AppError> {
+    let thumb = image.thumbnail(THUMBNAIL_MAX_SIDE, THUMBNAIL_MAX_SIDE);
+    let path = dir.join(format!("{name}.png"));
+    crate::durable::atomic_write(&path, &crate::frame::encode_png(&thumb)?)
+        .map_err(AppError::Internal)?;
+    Ok(path)
+}
+
+/// 导入期间的状态：已有历史的路径与哈希、待写入的条目
+struct Importer {
+    app: AppHandle,
+    progress: ImportProgress,
+    cancel: Arc<AtomicBool>,
+    copy: bool,
+    history_dir: PathBuf,
+    /// 历史目录中副本与缩略图的文件名前缀，取开始导入的时间，避免与之前的导入重名
+    file_prefix: String,
+    known_paths: HashSet<String>,
+    known_hashes: HashSet<String>,
+    pending: Vec<HistoryEntry>,
+    /// 已写入的条目，导入结束后提交识别
+    added: Vec<HistoryEntry>,
+    last_emit: Instant,
+}
+
+impl Importer {
+    fn emit(&mut self, force: bool) {
+        if force || self.last_emit.elapsed().as_millis() >= PROGRESS_INTERVAL_MS {
+            self.last_emit = Instant::now();
+            let _ = self.app.emit("history-import-progress", &self.progress);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), AppError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let added = self
+            .app
+            .state::<HistoryState>()
+            .insert_imported(std::mem::take(&mut self.pending))
+            .map_err(AppError::Internal)?;
+        self.added.extend(added);
+        Ok(())
+    }
+
+    /// 导入一个文件，已导入过或重复的跳过并计数
+    fn import_file(&mut self, path: &Path, modified: i64) -> Result<(), AppError> {
+        let source = path.to_string_lossy().into_owned();
+        if self.known_paths.contains(&source) {
+            self.progress.skipped_existing += 1;
+            return Ok(());
+        }
+        let data = std::fs::read(path)?;
+        let (image, _) = crate::image_input::decode(&data)?;
+        let hash = history::format_hash(history::dhash(&image));
+        if !self.known_hashes.insert(hash.clone()) {
+            self.progress.skipped_duplicates += 1;
+            return Ok(());
+        }
+        let name = format!("{}-{}", self.file_prefix, self.progress.processed);
+        let image_path = if self.copy {
+            let ext = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("png")
+                .to_ascii_lowercase();
+            let target = self.history_dir.join(format!("{name}.{ext}"));
+            crate::durable::atomic_write(&target, &data).map_err(AppError::Internal)?;
+            target.to_string_lossy().into_owned()
+        } else {
+            source.clone()
+        };
+        let thumbnail = match write_thumbnail(&image, &self.history_dir.join("thumbs"), &name) {
+            Ok(thumbnail) => Some(thumbnail),
+            Err(err) => {
+                tracing::debug!(path = %path.display(), error = %err, "生成缩略图失败");
+                None
+            }
+        };
+        self.known_paths.insert(source.clone());
+        self.pending.push(HistoryEntry {
+            id: 0,
+            timestamp: modified,
+            image_path,
+            monitor: None,
+            ocr_text: None,
+            backend: None,
+            phash: Some(hash),
+            duplicate_of: None,
+            app: None,
+            ocr_options: None,
+            ocr_confidence: None,
+            night_light: None,
+            imported_from: Some(source),
+            external: !self.copy,
+            thumbnail_path: thumbnail.map(|p| p.to_string_lossy().into_owned()),
+        });
+        self.progress.imported += 1;
+        Ok(())
+    }
+}
+
+/// 在后台线程中逐个导入，结束后把新条目作为一个批次提交识别
+fn run(mut importer: Importer, files: Vec<(PathBuf, i64)>, ocr: Option<OcrOptions>) {
+    for (path, modified) in files {
+        if importer.cancel.load(Ordering::SeqCst) {
+            importer.progress.cancelled = true;
+            break;
+        }
+        if let Err(err) = importer.import_file(&path, modified) {
+            tracing::warn!(path = %path.display(), error = %err, "导入图片失败");
+            importer.progress.failed += 1;
+        }
+        importer.progress.processed += 1;
+        if importer.pending.len() >= FLUSH_EVERY {
+            if let Err(err) = importer.flush() {
+                tracing::warn!(error = %err, "写入历史索引失败，停止导入");
+                break;
+            }
+        }
+        importer.emit(false);
+    }
+    if let Err(err) = importer.flush() {
+        tracing::warn!(error = %err, "写入历史索引失败");
+    }
+    crate::tray::refresh_recent(&importer.app);
+
+    // 取消导入时不再识别已导入的部分，再次导入同一文件夹也会跳过它们，可用 reocr_history_items 补上
+    if let Some(options) = ocr.filter(|_| !importer.progress.cancelled) {
+        let targets: Vec<(u64, String)> = importer
+            .added
+            .iter()
+            .map(|entry| (entry.id, entry.image_path.clone()))
+            .collect();
+        if !targets.is_empty() {
+            let items = targets
+                .iter()
+                .map(|(_, path)| (JobInput::File(path.into()), options.clone()))
+                .collect();
+            let (batch, receivers) = importer
+                .app
+                .state::<OcrQueue>()
+                .submit_batch(items, JobPriority::Low);
+            importer.progress.ocr_batch_id = Some(batch.batch_id);
+            let app = importer.app.clone();
+            std::thread::spawn(move || {
+                for ((id, _), rx) in targets.into_iter().zip(receivers) {
+                    history::apply_reocr(&app, id, &rx, options.clone());
+                }
+            });
+        }
+    }
+
+    importer.progress.done = true;
+    tracing::info!(
+        import_id = importer.progress.import_id,
+        imported = importer.progress.imported,
+        skipped_existing = importer.progress.skipped_existing,
+        skipped_duplicates = importer.progress.skipped_duplicates,
+        failed = importer.progress.failed,
+        cancelled = importer.progress.cancelled,
+        "文件夹导入结束"
+    );
+    importer.emit(true);
+    if let Some(map) = imports().as_mut() {
+        map.remove(&importer.progress.import_id);
+    }
+}
+
+/// 把文件夹中的图片导入历史，返回导入编号；进度通过 history-import-progress 事件发送。
+///
+/// 已导入过的文件（按原始路径）和与已有历史哈希相同的图片会跳过，中途取消后再次导入即可接着处理。
+/// 导入的条目以文件修改时间为截图时间，识别文字在低优先级的批次中进行
+#[tauri::command]
+pub fn import_folder(
+    app: AppHandle,
+    path: String,
+    options: Option<ImportOptions>,
+) -> Result<u64, AppError> {
+    let options = options.unwrap_or_default();
+    let dir = PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(AppError::invalid(format!("文件夹不存在: {path}")));
+    }
+    if options
+        .since
+        .zip(options.until)
+        .is_some_and(|(since, until)| since > until)
+    {
+        return Err(AppError::invalid("开始时间晚于结束时间"));
+    }
+    let settings = app.state::<SettingsState>().get();
+    let ocr = options.ocr.unwrap_or(true).then(|| {
+        OcrOptions::new(
+            options
+                .lang
+                .clone()
+                .unwrap_or_else(|| settings.ocr_lang.clone()),
+        )
+    });
+    if let Some(ocr) = &ocr {
+        ocr.validate()?;
+    }
+
+    let history = app.state::<HistoryState>();
+    let entries = history.entries();
+    let known_paths = entries
+        .iter()
+        .flat_map(|e| [e.imported_from.clone(), Some(e.image_path.clone())])
+        .flatten()
+        .collect();
+    let known_hashes = entries.iter().filter_map(|e| e.phash.clone()).collect();
+
+    let import_id = NEXT_IMPORT.fetch_add(1, Ordering::SeqCst);
+    let cancel = Arc::new(AtomicBool::new(false));
+    imports()
+        .get_or_insert_with(HashMap::new)
+        .insert(import_id, cancel.clone());
+    let importer = Importer {
+        app: app.clone(),
+        progress: ImportProgress {
+            import_id,
+            ..ImportProgress::default()
+        },
+        cancel,
+        copy: options.copy.unwrap_or(settings.history_import_copy),
+        history_dir: history.dir().to_path_buf(),
+        file_prefix: format!("import-{}", chrono::Local::now().format("%Y%m%d%H%M%S%3f")),
+        known_paths,
+        known_hashes,
+        pending: Vec::new(),
+        added: Vec::new(),
+        last_emit: Instant::now(),
+    };
+    std::thread::spawn(move || {
+        let files = discover(&dir, &options);
+        let mut importer = importer;
+        importer.progress.discovered = files.len();
+        tracing::info!(import_id, dir = %dir.display(), files = files.len(), "开始导入文件夹");
+        importer.emit(true);
+        run(importer, files, ocr);
+    });
+    Ok(import_id)
+}
+
+/// 停止导入，已导入的条目保留；返回导入是否仍在进行
+#[tauri::command]
+pub fn cancel_import(import_id: u64) -> bool {
+    match imports().as_ref().and_then(|map| map.get(&import_id)) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::history::{HistoryEntry, HistoryState};
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexFormat {
+    Csv,
+    /// 一个 JSON 数组，每条历史一个对象
+    Json,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct IndexExportOptions {
+    /// 是否导出识别文字；关闭时该列为空，便于分享统计结果
+    pub include_text: bool,
+    /// 识别文字最多保留的字符数，超出部分以 … 结尾
+    pub max_text_chars: Option<usize>,
+}
+
+impl Default for IndexExportOptions {
+    fn default() -> Self {
+        Self {
+            include_text: true,
+            max_text_chars: None,
+        }
+    }
+}
+
+/// 导出的一行；CSV 与 JSON 使用相同的列
+#[derive(Serialize)]
+struct IndexRow<'a> {
+    id: u64,
+    /// Unix 秒
+    timestamp: i64,
+    /// 本地时间，RFC 3339
+    datetime: String,
+    monitor: Option<&'a str>,
+    app: Option<&'a str>,
+    image_path: &'a str,
+    width: Option<u32>,
+    height: Option<u32>,
+    ocr_lang: Option<&'a str>,
+    ocr_text: Option<String>,
+    /// 识别结果各词置信度的平均值，没有识别文字时为空
+    mean_confidence: Option<f32>,
+}
+
+fn truncate(text: &str, max_chars: Option<usize>) -> String {
+    match max_chars {
+        Some(max) if text.chars().count() > max => {
+            let mut truncated: String = text.chars().take(max).collect();
+            truncated.push('…');
+            truncated
+        }
+        _ => text.to_string(),
+    }
+}
+
+fn row<'a>(entry: &'a HistoryEntry, options: &IndexExportOptions) -> IndexRow<'a> {
+    // 只读取文件头，图片丢失时尺寸为空
+    let dimensions = image::image_dimensions(&entry.image_path).ok();
+    IndexRow {
+        id: entry.id,
+        timestamp: entry.timestamp,
+        datetime: Local
+            .timestamp_opt(entry.timestamp, 0)
+            .single()
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+        monitor: entry.monitor.as_deref(),
+        app: entry.app.as_deref(),
+        image_path: &entry.image_path,
+        width: dimensions.map(|(w, _)| w),
+        height: dimensions.map(|(_, h)| h),
+        ocr_lang: entry.ocr_options.as_ref().map(|o| o.lang.as_str()),
+        ocr_text: entry
+            .ocr_text
+            .as_deref()
+            .filter(|_| options.include_text)
+            .map(|text| truncate(text, options.max_text_chars)),
+        mean_confidence: entry.ocr_confidence,
+    }
+}
+
+/// 逐行写入文件，不在内存中拼出完整内容
+fn write_index(
+    entries: &[HistoryEntry],
+    path: &str,
+    format: IndexFormat,
+    options: &IndexExportOptions,
+) -> Result<usize, AppError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    match format {
+        IndexFormat::Csv => {
+            // 带 BOM，Excel 打开时按 UTF-8 识别中文
+            file.write_all("\u{feff}".as_bytes())?;
+            let mut writer = csv::Writer::from_writer(file);
+            for entry in entries {
+                writer
+                    .serialize(row(entry, options))
+                    .map_err(|e| AppError::Internal(e.to_string()))?;
+            }
+            writer.flush()?;
+        }
+        IndexFormat::Json => {
+            file.write_all(b"[\n")?;
+            for (i, entry) in entries.iter().enumerate() {
+                if i > 0 {
+                    file.write_all(b",\n")?;
+                }
+                serde_json::to_writer(&mut file, &row(entry, options))
+                    .map_err(|e| AppError::Internal(e.to_string()))?;
+            }
+            file.write_all(b"\n]\n")?;
+            file.flush()?;
+        }
+    }
+    Ok(entries.len())
+}
+
+/// 把全部历史的索引导出为 CSV 或 JSON（每条一行，按时间先后），返回写入的行数
+#[tauri::command]
+pub async fn export_history_index(
+    app: AppHandle,
+    path: String,
+    format: IndexFormat,
+    options: Option<IndexExportOptions>,
+) -> Result<usize, AppError> {
+    crate::run_blocking(move || {
+        let mut entries = app.state::<HistoryState>().entries();
+        entries.sort_by_key(|e| (e.timestamp, e.id));
+        let rows = write_index(&entries, &path, format, &options.unwrap_or_default())?;
+        tracing::info!(rows, ?format, path = %path, "已导出历史索引");
+        Ok(rows)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn entry(id: u64, text: Option<&str>, confidence: Option<f32>) -> HistoryEntry {
+        serde_json::from_value(json!({
+            "id": id,
+            "timestamp": 1_700_000_000 + id as i64,
+            "image_path": format!("/nonexistent/{id}.png"),
+            "ocr_text": text,
+            "ocr_options": text.map(|_| json!({ "lang": "eng" })),
+            "ocr_confidence": confidence,
+        }))
+        .unwrap()
+    }
+
+    fn export(format: IndexFormat, options: &IndexExportOptions) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index");
+        let entries = [
+            entry(1, Some("Hello, \"world\""), Some(87.5)),
+            entry(2, None, None),
+        ];
+        let rows = write_index(&entries, path.to_str().unwrap(), format, options).unwrap();
+        assert_eq!(rows, 2);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn json_export_carries_mean_confidence() {
+        let rows: Vec<Value> =
+            serde_json::from_str(&export(IndexFormat::Json, &Default::default())).unwrap();
+        assert_eq!(rows[0]["mean_confidence"], json!(87.5));
+        assert_eq!(rows[0]["ocr_lang"], json!("eng"));
+        assert_eq!(rows[0]["ocr_text"], json!("Hello, \"world\""));
+        // 图片丢失时尺寸为空
+        assert_eq!(rows[0]["width"], Value::Null);
+        assert_eq!(rows[1]["mean_confidence"], Value::Null);
+    }
+
+    #[test]
+    fn csv_export_quotes_fields_and_truncates_text() {
+        let options = IndexExportOptions {
+            include_text: true,
+            max_text_chars: Some(6),
+        };
+        let csv = export(IndexFormat::Csv, &options);
+        let csv = csv.strip_prefix('\u{feff}').unwrap();
+        let mut lines = csv.lines();
+        let header = lines.next().unwrap();
+        assert!(header.ends_with("ocr_lang,ocr_text,mean_confidence"));
+        assert!(lines.next().unwrap().ends_with(",eng,\"Hello,…\",87.5"));
+        assert!(lines.next().unwrap().ends_with(",,,"));
+    }
+
+    #[test]
+    fn text_can_be_left_out() {
+        let options = IndexExportOptions {
+            include_text: false,
+            max_text_chars: None,
+        };
+        let rows: Vec<Value> = serde_json::from_str(&export(IndexFormat::Json, &options)).unwrap();
+        assert_eq!(rows[0]["ocr_text"], Value::Null);
+        assert_eq!(rows[0]["mean_confidence"], json!(87.5));
+    }
+}
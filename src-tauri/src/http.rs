@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsState;
+
+/// 本机地址始终直连（AnkiConnect 等）
+const LOCAL_HOSTS: &str = "localhost,127.0.0.1,::1";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 代理配置；未设置地址时使用 HTTP_PROXY / HTTPS_PROXY / ALL_PROXY / NO_PROXY 环境变量
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    /// http://、https:// 或 socks5:// 地址
+    pub url: Option<String>,
+    /// 不走代理的主机，写法同 NO_PROXY
+    pub no_proxy: Vec<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// test_proxy 默认探测的地址
+    pub probe_url: String,
+}
+
+impl Default for ProxySettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            no_proxy: Vec::new(),
+            username: None,
+            password: None,
+            probe_url: "https://github.com".into(),
+        }
+    }
+}
+
+impl ProxySettings {
+    fn configured_url(&self) -> Option<&str> {
+        self.url.as_deref().map(str::trim).filter(|u| !u.is_empty())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(url) = self.configured_url() {
+            reqwest::Proxy::all(url).map_err(|e| format!("代理地址无效: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// 所有出站请求共用的客户端，代理设置变化时重建
+pub struct HttpClient(RwLock<reqwest::Client>);
+
+impl HttpClient {
+    pub fn new(proxy: &ProxySettings) -> Self {
+        let client = build_client(proxy).unwrap_or_else(|err| {
+            tracing::warn!(error = %err, "创建 HTTP 客户端失败，改用默认配置");
+            reqwest::Client::new()
+        });
+        Self(RwLock::new(client))
+    }
+}
+
+fn build_client(proxy: &ProxySettings) -> Result<reqwest::Client, String> {
+    let mut builder =
+        reqwest::Client::builder().user_agent(concat!("PrinSp/", env!("CARGO_PKG_VERSION")));
+    if let Some(url) = proxy.configured_url() {
+        let mut rule = reqwest::Proxy::all(url).map_err(|e| format!("代理地址无效: {e}"))?;
+        if let Some(username) = proxy.username.as_deref().filter(|u| !u.is_empty()) {
+            rule = rule.basic_auth(username, proxy.password.as_deref().unwrap_or_default());
+        }
+        let no_proxy = std::iter::once(LOCAL_HOSTS)
+            .chain(proxy.no_proxy.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(",");
+        builder = builder.proxy(rule.no_proxy(reqwest::NoProxy::from_string(&no_proxy)));
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// 取共享客户端（内部为引用计数，克隆开销很小）
+pub fn client(app: &AppHandle) -> reqwest::Client {
+    app.try_state::<HttpClient>()
+        .and_then(|state| state.0.read().ok().map(|c| c.clone()))
+        .unwrap_or_default()
+}
+
+pub fn rebuild(app: &AppHandle, proxy: &ProxySettings) -> Result<(), String> {
+    let client = build_client(proxy)?;
+    let state = app.state::<HttpClient>();
+    *state.0.write().map_err(|e| e.to_string())? = client;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProxyError {
+    InvalidUrl { detail: String },
+    Timeout { secs: u64 },
+    Connect { detail: String },
+    Network { detail: String },
+}
+
+impl std::fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProxyError::InvalidUrl { detail } => write!(f, "地址无效: {detail}"),
+            ProxyError::Timeout { secs } => write!(f, "连接超时（超过 {secs} 秒）"),
+            ProxyError::Connect { detail } => write!(f, "无法连接: {detail}"),
+            ProxyError::Network { detail } => write!(f, "网络错误: {detail}"),
+        }
+    }
+}
+
+fn map_probe_error(err: reqwest::Error) -> ProxyError {
+    if err.is_timeout() {
+        return ProxyError::Timeout {
+            secs: PROBE_TIMEOUT.as_secs(),
+        };
+    }
+    let mut detail = err.to_string();
+    let mut source = err.source();
+    while let Some(inner) = source {
+        detail = format!("{detail}: {inner}");
+        source = inner.source();
+    }
+    if err.is_connect() {
+        ProxyError::Connect { detail }
+    } else {
+        ProxyError::Network { detail }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProxyTestResult {
+    pub url: String,
+    pub status: u16,
+    pub latency_ms: u64,
+}
+
+/// 通过当前代理对探测地址发 HEAD 请求，返回延迟
+#[tauri::command]
+pub async fn test_proxy(
+    app: AppHandle,
+    probe_url: Option<String>,
+) -> Result<ProxyTestResult, ProxyError> {
+    let url = probe_url.unwrap_or_else(|| app.state::<SettingsState>().get().proxy.probe_url);
+    let parsed = reqwest::Url::parse(&url).map_err(|e| ProxyError::InvalidUrl {
+        detail: e.to_string(),
+    })?;
+    let started = Instant::now();
+    let response = client(&app)
+        .head(parsed)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .map_err(map_probe_error)?;
+    Ok(ProxyTestResult {
+        url,
+        status: response.status().as_u16(),
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+#[cfg(feature = "tauri")]
+use tauri::{AppHandle, Manager};
+
+#[cfg(feature = "tauri")]
+use crate::settings::SettingsState;
+
+/// 支持的语言及其内嵌的文案表
+const CATALOGS: &[(&str, &str)] = &[
+    ("zh-CN", include_str!("../locales/zh-CN.json")),
+    ("en-US", include_str!("../locales/en-US.json")),
+];
+const FALLBACK: &str = "zh-CN";
+
+static PARSED: OnceLock<HashMap<&'static str, HashMap<String, String>>> = OnceLock::new();
+static LOCALE: RwLock<&'static str> = RwLock::new(FALLBACK);
+
+fn catalogs() -> &'static HashMap<&'static str, HashMap<String, String>> {
+    PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(locale, json)| (*locale, serde_json::from_str(json).unwrap_or_default()))
+            .collect()
+    })
+}
+
+/// 把 zh_CN.UTF-8、en、en_GB 等写法对应到支持的语言
+fn match_locale(raw: &str) -> Option<&'static str> {
+    let lang = raw
+        .split(['.', '@'])
+        .next()?
+        .replace('_', "-")
+        .to_lowercase();
+    if lang.is_empty() || lang == "c" || lang == "posix" {
+        return None;
+    }
+    CATALOGS
+        .iter()
+        .map(|(locale, _)| *locale)
+        .find(|locale| locale.to_lowercase() == lang)
+        .or_else(|| {
+            let prefix = lang.split('-').next()?;
+            CATALOGS
+                .iter()
+                .map(|(locale, _)| *locale)
+                .find(|locale| locale.to_lowercase().starts_with(prefix))
+        })
+}
+
+/// 按 LC_ALL / LC_MESSAGES / LANG 顺序检测系统语言
+fn detect_system() -> &'static str {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find_map(|value| match_locale(&value))
+        .unwrap_or(FALLBACK)
+}
+
+/// 设置中指定了语言则使用，否则跟随系统
+#[cfg(feature = "tauri")]
+pub fn init(app: &AppHandle) {
+    let configured = app.state::<SettingsState>().get().locale;
+    let locale = configured
+        .as_deref()
+        .and_then(match_locale)
+        .unwrap_or_else(detect_system);
+    if let Ok(mut current) = LOCALE.write() {
+        *current = locale;
+    }
+}
+
+pub fn current() -> &'static str {
+    LOCALE.read().map(|l| *l).unwrap_or(FALLBACK)
+}
+
+/// 取当前语言的文案，缺失时退回中文，再缺失时返回 key 本身
+pub fn t(key: &str) -> String {
+    let catalogs = catalogs();
+    catalogs
+        .get(current())
+        .and_then(|c| c.get(key))
+        .or_else(|| catalogs.get(FALLBACK).and_then(|c| c.get(key)))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// 带参数的文案，替换其中的 {name} 占位符
+pub fn tf(key: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(t(key), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), value)
+    })
+}
+
+/// 切换界面语言（"auto" 表示跟随系统），保存到设置并重建托盘菜单文字
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn set_locale(app: AppHandle, locale: String) -> Result<String, String> {
+    let configured = if locale == "auto" {
+        None
+    } else {
+        let matched = match_locale(&locale)
+            .ok_or_else(|| tf("error.unsupported_locale", &[("locale", &locale)]))?;
+        Some(matched.to_string())
+    };
+    app.state::<SettingsState>()
+        .update(|s| s.locale = configured)?;
+    init(&app);
+    crate::tray::apply_locale(&app);
+    Ok(current().to_string())
+}
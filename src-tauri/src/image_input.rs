@@ -0,0 +1,162 @@
+use image::DynamicImage;
+use serde::Serialize;
+
+use crate::error::AppError;
+
+/// 按文件头识别出的图片格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Bmp,
+    Gif,
+    Tiff,
+    /// iPhone 等拍摄的 HEIF / HEIC 照片
+    Heic,
+    Avif,
+}
+
+impl InputFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            InputFormat::Png => "PNG",
+            InputFormat::Jpeg => "JPEG",
+            InputFormat::Webp => "WebP",
+            InputFormat::Bmp => "BMP",
+            InputFormat::Gif => "GIF",
+            InputFormat::Tiff => "TIFF",
+            InputFormat::Heic => "HEIC",
+            InputFormat::Avif => "AVIF",
+        }
+    }
+
+    fn decoder(self) -> Option<image::ImageFormat> {
+        match self {
+            InputFormat::Png => Some(image::ImageFormat::Png),
+            InputFormat::Jpeg => Some(image::ImageFormat::Jpeg),
+            InputFormat::Webp => Some(image::ImageFormat::WebP),
+            InputFormat::Bmp => Some(image::ImageFormat::Bmp),
+            InputFormat::Gif => Some(image::ImageFormat::Gif),
+            InputFormat::Tiff => Some(image::ImageFormat::Tiff),
+            InputFormat::Heic | InputFormat::Avif => None,
+        }
+    }
+}
+
+/// ISO BMFF（HEIF / AVIF）文件 ftyp 盒中的主品牌与兼容品牌
+fn ftyp_brands(data: &[u8]) -> Option<Vec<&[u8]>> {
+    if data.get(4..8)? != b"ftyp" {
+        return None;
+    }
+    let size = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let ftyp = data.get(8..size.min(data.len()))?;
+    let major = ftyp.get(..4)?;
+    // 主品牌后是 4 字节的版本号，之后是兼容品牌列表
+    let compatible = ftyp.get(8..).unwrap_or_default().chunks_exact(4);
+    Some(std::iter::once(major).chain(compatible).collect())
+}
+
+/// 只看文件头判断格式，不解码
+pub fn sniff(data: &[u8]) -> Option<InputFormat> {
+    if let Some(brands) = ftyp_brands(data) {
+        // AVIF 也带 mif1 品牌，先按 avif 判断
+        if brands.iter().any(|b| matches!(*b, b"avif" | b"avis")) {
+            return Some(InputFormat::Avif);
+        }
+        if brands.iter().any(|b| {
+            matches!(
+                *b,
+                b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"mif1" | b"msf1"
+            )
+        }) {
+            return Some(InputFormat::Heic);
+        }
+        return None;
+    }
+    match image::guess_format(data).ok()? {
+        image::ImageFormat::Png => Some(InputFormat::Png),
+        image::ImageFormat::Jpeg => Some(InputFormat::Jpeg),
+        image::ImageFormat::WebP => Some(InputFormat::Webp),
+        image::ImageFormat::Bmp => Some(InputFormat::Bmp),
+        image::ImageFormat::Gif => Some(InputFormat::Gif),
+        image::ImageFormat::Tiff => Some(InputFormat::Tiff),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "heic")]
+fn decode_heic(data: &[u8]) -> Result<DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib = LibHeif::new();
+    let context = HeifContext::read_from_bytes(data).map_err(|e| e.to_string())?;
+    let handle = context.primary_image_handle().map_err(|e| e.to_string())?;
+    let decoded = lib
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(|e| e.to_string())?;
+    let plane = decoded
+        .planes()
+        .interleaved
+        .ok_or_else(|| "缺少交错的 RGBA 数据".to_string())?;
+    let (width, height) = (plane.width, plane.height);
+    let row = width as usize * 4;
+    // 每行末尾可能有填充
+    let pixels = plane
+        .data
+        .chunks(plane.stride)
+        .take(height as usize)
+        .flat_map(|line| &line[..row])
+        .copied()
+        .collect();
+    image::RgbaImage::from_raw(width, height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| "像素数据长度不符".to_string())
+}
+
+/// 只读取文件头得到尺寸；HEIC 等无法直接读取的格式返回 None
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let decoder = sniff(data)?.decoder()?;
+    image::ImageReader::with_format(std::io::Cursor::new(data), decoder)
+        .into_dimensions()
+        .ok()
+}
+
+/// 识别格式后解码；不支持的格式返回 UnsupportedFormat，文件损坏时返回指明格式的 InvalidInput
+pub fn decode(data: &[u8]) -> Result<(DynamicImage, InputFormat), AppError> {
+    let format = sniff(data).ok_or_else(|| AppError::invalid("无法识别的图片格式"))?;
+    let result = match format {
+        #[cfg(feature = "heic")]
+        InputFormat::Heic => decode_heic(data),
+        #[cfg(not(feature = "heic"))]
+        InputFormat::Heic => {
+            return Err(AppError::UnsupportedFormat {
+                format: format.label().into(),
+                hint: "当前版本未开启 HEIC 支持（以 --features heic 编译），或先转换为 JPEG / PNG"
+                    .into(),
+            })
+        }
+        format => match format.decoder() {
+            Some(decoder) => {
+                image::load_from_memory_with_format(data, decoder).map_err(|e| e.to_string())
+            }
+            None => {
+                return Err(AppError::UnsupportedFormat {
+                    format: format.label().into(),
+                    hint: "请先转换为 PNG 或 JPEG".into(),
+                })
+            }
+        },
+    };
+    match result {
+        Ok(image) => Ok((image, format)),
+        Err(err) => {
+            tracing::debug!(format = format.label(), error = %err, "图片解码失败");
+            Err(AppError::invalid(format!(
+                "{} 图片已损坏或无法解析",
+                format.label()
+            )))
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, State};
+
+/// 单个值序列化后的上限
+const MAX_VALUE_BYTES: usize = 64 * 1024;
+/// 所有键值合计的上限
+const MAX_TOTAL_BYTES: usize = 1024 * 1024;
+const MAX_KEY_LEN: usize = 128;
+
+/// 前端自己的界面偏好（主题、遮罩透明度、工具栏布局等），与 Settings 分开存放在数据目录下的
+/// ui-store.json，清除 WebView 数据后仍然保留
+pub struct KvStore {
+    path: PathBuf,
+    entries: RwLock<BTreeMap<String, Value>>,
+}
+
+/// kv-changed 事件内容，value 为空表示已删除
+#[derive(Clone, Serialize)]
+pub struct KvChanged {
+    pub key: String,
+    pub value: Option<Value>,
+}
+
+/// 键只允许字母、数字和 . _ -，如 overlay.target；不能含路径分隔符
+fn validate_key(key: &str) -> Result<(), String> {
+    let valid_chars = key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if key.is_empty() || key.len() > MAX_KEY_LEN || !valid_chars || key.starts_with('.') {
+        return Err(format!("无效的键: {key}"));
+    }
+    Ok(())
+}
+
+fn entry_size(key: &str, value: &Value) -> usize {
+    key.len() + value.to_string().len()
+}
+
+impl KvStore {
+    /// 文件不存在、文件和备份都无法解析时从空白开始
+    pub fn load(path: PathBuf) -> Self {
+        let entries = crate::durable::read_recovering(&path, |data| serde_json::from_slice(data))
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: RwLock::new(entries),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<Value> {
+        self.entries.read().ok()?.get(key).cloned()
+    }
+
+    /// 在副本上修改，落盘成功后才替换内存中的内容
+    fn modify(&self, f: impl FnOnce(&mut BTreeMap<String, Value>)) -> Result<(), String> {
+        let mut guard = self.entries.write().map_err(|e| e.to_string())?;
+        let mut entries = guard.clone();
+        f(&mut entries);
+        let total: usize = entries.iter().map(|(k, v)| entry_size(k, v)).sum();
+        if total > MAX_TOTAL_BYTES {
+            return Err(format!("存储已满（上限 {} KB）", MAX_TOTAL_BYTES / 1024));
+        }
+        let data = serde_json::to_vec_pretty(&entries).map_err(|e| e.to_string())?;
+        crate::durable::write_with_backup(&self.path, &data)?;
+        *guard = entries;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn kv_get(state: State<'_, KvStore>, key: String) -> Result<Option<Value>, String> {
+    validate_key(&key)?;
+    Ok(state.get(&key))
+}
+
+/// value_json 为 JSON 文本，保存后通知所有窗口
+#[tauri::command]
+pub fn kv_set(
+    app: AppHandle,
+    state: State<'_, KvStore>,
+    key: String,
+    value_json: String,
+) -> Result<(), String> {
+    validate_key(&key)?;
+    let value: Value =
+        serde_json::from_str(&value_json).map_err(|e| format!("值不是有效的 JSON: {e}"))?;
+    if entry_size(&key, &value) > MAX_VALUE_BYTES {
+        return Err(format!("值过大（上限 {} KB）", MAX_VALUE_BYTES / 1024));
+    }
+    if state.get(&key).as_ref() == Some(&value) {
+        return Ok(());
+    }
+    state.modify(|entries| {
+        entries.insert(key.clone(), value.clone());
+    })?;
+    let _ = app.emit(
+        "kv-changed",
+        KvChanged {
+            key,
+            value: Some(value),
+        },
+    );
+    Ok(())
+}
+
+/// 返回键是否存在
+#[tauri::command]
+pub fn kv_delete(app: AppHandle, state: State<'_, KvStore>, key: String) -> Result<bool, String> {
+    validate_key(&key)?;
+    if state.get(&key).is_none() {
+        return Ok(false);
+    }
+    state.modify(|entries| {
+        entries.remove(&key);
+    })?;
+    let _ = app.emit("kv-changed", KvChanged { key, value: None });
+    Ok(true)
+}
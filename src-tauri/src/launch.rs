@@ -0,0 +1,327 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+use crate::trigger::{CaptureTrigger, TriggerSource};
+
+/// 截图区域，格式 x,y,w,h
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn parse_region(s: &str) -> Result<Region, String> {
+    let parts = s
+        .split(',')
+        .map(|p| p.trim().parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("区域格式应为 x,y,w,h: {s}"))?;
+    match parts.as_slice() {
+        [x, y, w, h] if *w > 0 && *h > 0 => Ok(Region {
+            x: *x,
+            y: *y,
+            width: *w,
+            height: *h,
+        }),
+        _ => Err(format!("区域格式应为 x,y,w,h: {s}")),
+    }
+}
+
+#[derive(Parser, Debug, Default)]
+#[command(name = "prinsp", version, about = "Linux 截图与文字识别工具")]
+pub struct Cli {
+    /// 立即开始截图
+    #[arg(long)]
+    pub capture: bool,
+    /// 截图后识别文字（--no-gui 时输出到标准输出）
+    #[arg(long)]
+    pub ocr: bool,
+    /// 截图区域 x,y,w,h（相对于所选显示器或整个屏幕）
+    #[arg(long, value_parser = parse_region)]
+    pub region: Option<Region>,
+    /// 显示器 id
+    #[arg(long)]
+    pub monitor: Option<u32>,
+    /// 延迟截图秒数
+    #[arg(long, default_value_t = 0)]
+    pub delay: u64,
+    /// 输出文件，- 表示标准输出
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// 不启动界面，截图后直接退出
+    #[arg(long)]
+    pub no_gui: bool,
+    /// 只显示托盘图标启动（开机自启时使用）
+    #[arg(long)]
+    pub hidden: bool,
+}
+
+/// 再次启动时转交给运行中实例的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchAction {
+    Capture,
+    OcrCapture,
+    Show,
+}
+
+impl Cli {
+    /// 结果要写到标准输出时只能在当前进程执行，不能转交
+    pub fn needs_stdout(&self) -> bool {
+        self.ocr || self.output.as_deref() == Some(std::path::Path::new("-"))
+    }
+
+    fn action(&self) -> LaunchAction {
+        if self.ocr {
+            LaunchAction::OcrCapture
+        } else if self.capture {
+            LaunchAction::Capture
+        } else {
+            LaunchAction::Show
+        }
+    }
+}
+
+pub fn run_action(app: &AppHandle, action: LaunchAction) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    match action {
+        LaunchAction::Capture => CaptureTrigger::new(TriggerSource::Launch, "capture").send(app),
+        LaunchAction::OcrCapture => {
+            CaptureTrigger::new(TriggerSource::Launch, "ocr_capture").send(app)
+        }
+        LaunchAction::Show => {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
+
+/// 本次启动的方式
+pub struct LaunchState {
+    autostart: bool,
+    /// 启动后待执行一次的截图，前端取走后清除
+    capture_pending: AtomicBool,
+}
+
+impl LaunchState {
+    /// 自启动（--hidden）时不触发截图；否则按 --capture 或设置决定
+    pub fn new(cli: &Cli, capture_on_launch: bool) -> Self {
+        Self {
+            autostart: cli.hidden,
+            capture_pending: AtomicBool::new(!cli.hidden && (cli.capture || capture_on_launch)),
+        }
+    }
+
+    /// 启动后是否会立即截图；截图流程自己显示窗口
+    pub fn capture_pending(&self) -> bool {
+        self.capture_pending.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Serialize)]
+pub struct LaunchInfo {
+    pub autostart: bool,
+    pub capture_on_launch: bool,
+}
+
+/// 前端加载后调用，决定是否立即开始截图
+#[tauri::command]
+pub fn get_launch_info(app: AppHandle, state: State<'_, LaunchState>) -> LaunchInfo {
+    crate::degraded::frontend_ready(&app);
+    LaunchInfo {
+        autostart: state.autostart,
+        capture_on_launch: state.capture_pending.swap(false, Ordering::SeqCst),
+    }
+}
+
+/// 第二个进程转交过来的请求
+#[derive(Debug)]
+enum Forwarded {
+    /// --no-gui：在已运行的实例中直接截图写文件
+    Headless(Box<Cli>),
+    Action(LaunchAction),
+}
+
+/// 解析第二个进程的参数；无法解析时只显示主窗口
+fn parse_forwarded(args: &[String], cwd: &str) -> Forwarded {
+    let Ok(mut cli) = Cli::try_parse_from(args) else {
+        return Forwarded::Action(LaunchAction::Show);
+    };
+    if cli.no_gui {
+        // 相对路径按调用方的工作目录解析
+        cli.output = cli.output.map(|path| PathBuf::from(cwd).join(path));
+        return Forwarded::Headless(Box::new(cli));
+    }
+    Forwarded::Action(cli.action())
+}
+
+/// 单实例插件回调：第二个进程把参数转交过来后退出
+pub fn on_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+    match parse_forwarded(&args, &cwd) {
+        Forwarded::Headless(cli) => {
+            // 脚本调用：不弹出界面
+            std::thread::spawn(move || {
+                if let Err(err) = crate::headless::run(&cli) {
+                    tracing::warn!(error = %err, "命令行截图失败");
+                }
+            });
+        }
+        Forwarded::Action(action) => {
+            tracing::info!(?action, "收到再次启动请求");
+            run_action(app, action);
+        }
+    }
+}
+
+/// 与 tauri.conf.json 中 identifier 对应的单实例 D-Bus 名称
+#[cfg(target_os = "linux")]
+const SINGLE_INSTANCE_ID: &str = "com_daisheng_prinsp";
+
+/// 已有界面实例在运行时，通过单实例插件的 D-Bus 接口转交参数；返回是否转交成功
+#[cfg(target_os = "linux")]
+pub fn forward_to_running_instance() -> bool {
+    forward_to(SINGLE_INSTANCE_ID)
+}
+
+/// 实例退出（包括崩溃）后 D-Bus 名称随连接释放，调用失败即视为没有运行中的实例
+#[cfg(target_os = "linux")]
+fn forward_to(id: &str) -> bool {
+    let Ok(connection) = zbus::blocking::Connection::session() else {
+        return false;
+    };
+    let args: Vec<String> = std::env::args().collect();
+    let cwd = std::env::current_dir()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    connection
+        .call_method(
+            Some(format!("org.{id}.SingleInstance").as_str()),
+            format!("/org/{id}/SingleInstance").as_str(),
+            Some("org.SingleInstance.DBus"),
+            "ExecuteCallback",
+            &(args, cwd),
+        )
+        .is_ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn forward_to_running_instance() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("prinsp")
+            .chain(list.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    fn action(list: &[&str]) -> LaunchAction {
+        match parse_forwarded(&args(list), "/home/user") {
+            Forwarded::Action(action) => action,
+            other => panic!("意外的结果 {other:?}"),
+        }
+    }
+
+    #[test]
+    fn forwarded_flags_map_to_actions() {
+        assert_eq!(action(&[]), LaunchAction::Show);
+        assert_eq!(action(&["--capture"]), LaunchAction::Capture);
+        assert_eq!(action(&["--ocr"]), LaunchAction::OcrCapture);
+        // 同时给出时识别优先
+        assert_eq!(action(&["--capture", "--ocr"]), LaunchAction::OcrCapture);
+        assert_eq!(action(&["--hidden"]), LaunchAction::Show);
+    }
+
+    #[test]
+    fn unparsable_arguments_only_show_the_window() {
+        assert_eq!(action(&["--no-such-flag"]), LaunchAction::Show);
+        assert_eq!(action(&["--region", "1,2,3"]), LaunchAction::Show);
+        assert_eq!(action(&["--delay", "soon"]), LaunchAction::Show);
+    }
+
+    #[test]
+    fn headless_output_is_resolved_against_the_caller_cwd() {
+        let forwarded = parse_forwarded(
+            &args(&["--no-gui", "--output", "shot.png", "--monitor", "1"]),
+            "/home/user/pictures",
+        );
+        let Forwarded::Headless(cli) = forwarded else {
+            panic!("应在运行中的实例执行命令行截图");
+        };
+        assert_eq!(
+            cli.output.as_deref(),
+            Some(std::path::Path::new("/home/user/pictures/shot.png"))
+        );
+        assert_eq!(cli.monitor, Some(1));
+
+        let Forwarded::Headless(cli) =
+            parse_forwarded(&args(&["--no-gui", "--output", "/tmp/a.png"]), "/home/user")
+        else {
+            panic!("应在运行中的实例执行命令行截图");
+        };
+        assert_eq!(
+            cli.output.as_deref(),
+            Some(std::path::Path::new("/tmp/a.png"))
+        );
+    }
+
+    #[test]
+    fn stdout_output_cannot_be_forwarded() {
+        let cli = Cli::try_parse_from(args(&["--no-gui", "--output", "-"])).unwrap();
+        assert!(cli.needs_stdout());
+        let cli = Cli::try_parse_from(args(&["--no-gui", "--ocr"])).unwrap();
+        assert!(cli.needs_stdout());
+        let cli = Cli::try_parse_from(args(&["--no-gui", "--output", "a.png"])).unwrap();
+        assert!(!cli.needs_stdout());
+    }
+
+    #[test]
+    fn region_parsing() {
+        assert_eq!(
+            parse_region("10, 20,300,400"),
+            Ok(Region {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 400
+            })
+        );
+        assert!(parse_region("10,20,0,400").is_err());
+        assert!(parse_region("10,20,300").is_err());
+        assert!(parse_region("-1,20,300,400").is_err());
+    }
+
+    #[test]
+    fn hidden_launch_never_captures() {
+        let cli = Cli::try_parse_from(args(&["--hidden", "--capture"])).unwrap();
+        let state = LaunchState::new(&cli, true);
+        assert!(state.autostart);
+        assert!(!state.capture_pending());
+
+        let cli = Cli::try_parse_from(args(&[])).unwrap();
+        assert!(LaunchState::new(&cli, true).capture_pending());
+        assert!(!LaunchState::new(&cli, false).capture_pending());
+        let cli = Cli::try_parse_from(args(&["--capture"])).unwrap();
+        assert!(LaunchState::new(&cli, false).capture_pending());
+    }
+
+    /// 持有名称的实例已退出时 D-Bus 名称无人占用，转交失败，本进程自行启动
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn forwarding_fails_when_no_instance_owns_the_name() {
+        let id = format!("com_daisheng_prinsp_test_{}", std::process::id());
+        assert!(!forward_to(&id));
+    }
+}
@@ -1,568 +1,460 @@
-use arboard::Clipboard;
-use base64::{engine::general_purpose::STANDARD, Engine};
-use image::codecs::png::{CompressionType, FilterType, PngEncoder};
-use image::{imageops::invert, GrayImage, ImageEncoder, Pixel, RgbImage};
-use imageproc::contrast::{otsu_level, threshold};
-use imageproc::distance_transform::Norm;
-use imageproc::filter::median_filter;
-use imageproc::morphology::close;
-use rusty_tesseract::{Args, Image as TessImage};
-use std::collections::HashMap;
-use std::path::Path;
-use std::process::Command;
-use std::sync::{mpsc, Mutex, OnceLock};
-use std::thread;
-use std::time::Duration;
-use tauri::{
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager, WebviewWindow,
-};
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, ShortcutState};
-use xcap::Monitor;
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum CaptureBackend {
-    Grim,
-    Xcap,
-    GnomeScreenshot,
-}
-
-static PREFERRED_BACKEND: OnceLock<Mutex<Option<CaptureBackend>>> = OnceLock::new();
-
-fn preferred_backend_state() -> &'static Mutex<Option<CaptureBackend>> {
-    PREFERRED_BACKEND.get_or_init(|| Mutex::new(None))
-}
-
-fn set_preferred_backend(backend: CaptureBackend) {
-    if let Ok(mut guard) = preferred_backend_state().lock() {
-        *guard = Some(backend);
-    }
-}
-
-fn get_preferred_backend() -> Option<CaptureBackend> {
-    preferred_backend_state()
-        .lock()
-        .ok()
-        .and_then(|guard| *guard)
-}
-
-fn command_exists(cmd: &str) -> bool {
-    Command::new("sh")
-        .arg("-c")
-        .arg(format!("command -v {cmd} >/dev/null 2>&1"))
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
-}
-
-fn ensure_tesseract_installed() -> Result<(), String> {
-    if command_exists("tesseract") {
-        Ok(())
-    } else {
-        Err("未找到 tesseract，可先安装：sudo apt install tesseract-ocr tesseract-ocr-chi-sim（或对应发行版包名）".to_string())
-    }
-}
-
-fn preselect_backend() {
-    // 简单的环境探测，避免第一次截图时走到不可用后端造成长时间阻塞
-    if get_preferred_backend().is_some() {
-        return;
-    }
-
-    let is_wayland = std::env::var("WAYLAND_DISPLAY").is_ok();
-    if is_wayland && command_exists("grim") {
-        set_preferred_backend(CaptureBackend::Grim);
-        return;
-    }
-
-    if std::env::var("DISPLAY").is_ok() {
-        set_preferred_backend(CaptureBackend::Xcap);
-    }
-}
-
-#[tauri::command]
-fn hide_window(window: WebviewWindow) -> Result<(), String> {
-    window.hide().map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-fn register_global_shortcut(app: AppHandle, shortcut: String) -> Result<(), String> {
-    let manager = app.global_shortcut();
-    manager
-        .unregister_all()
-        .map_err(|e| format!("unregister_all: {e}"))?;
-
-    let normalized = normalize_shortcut(&shortcut);
-    let shortcut_label = normalized.clone();
-    manager
-        .on_shortcut(normalized.as_str(), move |handle, _shortcut, event| {
-            if event.state == ShortcutState::Pressed {
-                let _ = handle.emit("start-capture", ());
-            }
-        })
-        .map_err(|e| format!("register {shortcut_label}: {e}"))?;
-
-    Ok(())
-}
-
-fn normalize_shortcut(input: &str) -> String {
-    // 转为插件要求的小写形式，并去掉多余空格
-    input
-        .split('+')
-        .map(|p| p.trim().to_lowercase())
-        .collect::<Vec<_>>()
-        .join("+")
-}
-
-#[tauri::command]
-fn show_window_fullscreen(window: WebviewWindow) -> Result<(), String> {
-    window.set_fullscreen(true).map_err(|e| e.to_string())?;
-    window.set_decorations(false).map_err(|e| e.to_string())?;
-    window.show().map_err(|e| e.to_string())?;
-    window.set_focus().map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-fn restore_window(window: WebviewWindow) -> Result<(), String> {
-    window.set_fullscreen(false).map_err(|e| e.to_string())?;
-    window.set_decorations(true).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-#[tauri::command]
-fn capture_screen_hidden(window: WebviewWindow) -> Result<String, String> {
-    // 隐藏窗口
-    window.hide().map_err(|e| e.to_string())?;
-    // 等待窗口完全隐藏（减少等待时间）
-    thread::sleep(Duration::from_millis(80));
-    // 截图
-    capture_screen()
-}
-
-#[tauri::command]
-fn capture_screen() -> Result<String, String> {
-    let mut last_err = String::new();
-    let mut order = Vec::new();
-
-    if let Some(preferred) = get_preferred_backend() {
-        order.push(preferred);
-    }
-
-    for backend in [CaptureBackend::Grim, CaptureBackend::Xcap, CaptureBackend::GnomeScreenshot] {
-        if !order.contains(&backend) {
-            order.push(backend);
-        }
-    }
-
-    for backend in order {
-        let result = match backend {
-            // grim 超时缩短到 500ms，快速失败
-            CaptureBackend::Grim => capture_with_timeout("grim", Duration::from_millis(500), capture_with_grim),
-            CaptureBackend::Xcap => capture_with_timeout("xcap", Duration::from_millis(1500), capture_with_xcap),
-            CaptureBackend::GnomeScreenshot => capture_with_gnome_screenshot(),
-        };
-
-        match result {
-            Ok(data) => {
-                set_preferred_backend(backend);
-                return Ok(data);
-            }
-            Err(err) => last_err = err,
-        }
-    }
-
-    Err(last_err)
-}
-
-fn capture_with_timeout<F>(name: &str, timeout: Duration, capture: F) -> Result<String, String>
+// 不带 tauri 特性时只编译截图、识别和预处理核心，其中供界面使用的部分不会被用到
+#![cfg_attr(not(feature = "tauri"), allow(dead_code))]
+
+#[cfg(feature = "tauri")]
+use clap::Parser;
+#[cfg(feature = "tauri")]
+use tauri::Manager;
+
+#[cfg(feature = "tauri")]
+use capture_cache::CaptureCache;
+
+mod backend_health;
+pub mod capture;
+mod color;
+mod error;
+mod external;
+mod frame;
+mod i18n;
+mod night_light;
+pub mod ocr;
+mod postprocess_rules;
+pub mod preprocess;
+mod tesseract;
+mod timings;
+
+// 界面和命令层
+#[cfg(feature = "tauri")]
+mod active_app;
+#[cfg(feature = "tauri")]
+mod anki;
+#[cfg(feature = "tauri")]
+mod appearance;
+#[cfg(feature = "tauri")]
+mod capabilities;
+#[cfg(feature = "tauri")]
+mod capture_cache;
+#[cfg(feature = "tauri")]
+mod capture_id;
+#[cfg(feature = "tauri")]
+mod change_watch;
+#[cfg(feature = "tauri")]
+mod clipboard;
+#[cfg(feature = "tauri")]
+mod commands;
+#[cfg(all(feature = "tauri", target_os = "linux"))]
+mod dbus;
+#[cfg(feature = "tauri")]
+mod degraded;
+#[cfg(feature = "tauri")]
+mod diagnostics;
+#[cfg(feature = "tauri")]
+mod dnd;
+#[cfg(feature = "tauri")]
+mod durable;
+#[cfg(feature = "tauri")]
+mod edits;
+#[cfg(feature = "tauri")]
+mod error_report;
+#[cfg(feature = "tauri")]
+mod extract;
+#[cfg(feature = "tauri")]
+mod geometry;
+#[cfg(feature = "tauri")]
+mod headless;
+#[cfg(feature = "tauri")]
+mod history;
+#[cfg(feature = "tauri")]
+mod history_import;
+#[cfg(feature = "tauri")]
+mod history_index;
+#[cfg(feature = "tauri")]
+mod http;
+#[cfg(feature = "tauri")]
+mod image_input;
+#[cfg(feature = "tauri")]
+mod kv;
+#[cfg(feature = "tauri")]
+mod launch;
+#[cfg(feature = "tauri")]
+mod local_api;
+#[cfg(feature = "tauri")]
+mod logging;
+#[cfg(feature = "tauri")]
+mod notes;
+#[cfg(feature = "tauri")]
+mod notify;
+#[cfg(feature = "tauri")]
+mod ocr_queue;
+#[cfg(feature = "tauri")]
+mod overlay;
+#[cfg(feature = "tauri")]
+mod pin;
+#[cfg(feature = "tauri")]
+mod pipeline;
+#[cfg(feature = "tauri")]
+mod poll_watch;
+#[cfg(feature = "tauri")]
+mod pre_encode;
+#[cfg(feature = "tauri")]
+mod print;
+#[cfg(feature = "tauri")]
+mod qr;
+#[cfg(feature = "tauri")]
+mod quick;
+#[cfg(feature = "tauri")]
+mod recording;
+#[cfg(feature = "tauri")]
+mod recovery;
+#[cfg(feature = "tauri")]
+mod redact;
+#[cfg(feature = "tauri")]
+mod save;
+#[cfg(feature = "tauri")]
+mod script_hook;
+#[cfg(feature = "tauri")]
+mod selection_preset;
+#[cfg(feature = "tauri")]
+mod session;
+#[cfg(feature = "tauri")]
+mod settings;
+#[cfg(feature = "tauri")]
+mod share;
+#[cfg(feature = "tauri")]
+mod shortcuts;
+#[cfg(feature = "tauri")]
+mod sound;
+#[cfg(feature = "tauri")]
+mod speech;
+#[cfg(feature = "tauri")]
+mod stats;
+#[cfg(feature = "tauri")]
+mod storage;
+#[cfg(feature = "tauri")]
+mod text_diff;
+#[cfg(feature = "tauri")]
+mod text_watch;
+#[cfg(feature = "tauri")]
+mod translate;
+#[cfg(feature = "tauri")]
+mod tray;
+#[cfg(feature = "tauri")]
+mod trigger;
+#[cfg(feature = "tauri")]
+mod upload;
+#[cfg(feature = "tauri")]
+mod warmup;
+#[cfg(all(feature = "tauri", target_os = "linux"))]
+mod watchdog;
+#[cfg(feature = "tauri")]
+mod webhook;
+#[cfg(feature = "tauri")]
+mod window_trim;
+#[cfg(all(feature = "tauri", target_os = "linux"))]
+mod xrandr;
+
+pub use capture::{CaptureBackend, CaptureResult};
+pub use error::AppError;
+pub use frame::Frame;
+pub use ocr::{OcrOptions, OcrResult};
+pub use timings::Timings;
+
+/// 在阻塞线程池中执行截图、识别等耗时任务，命令线程和界面不会被卡住
+#[cfg(feature = "tauri")]
+async fn run_blocking<T, F>(task: F) -> Result<T, AppError>
 where
-    F: FnOnce() -> Result<String, String> + Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
 {
-    let (tx, rx) = mpsc::channel();
-    thread::spawn(move || {
-        let _ = tx.send(capture());
-    });
-
-    match rx.recv_timeout(timeout) {
-        Ok(res) => res,
-        Err(_) => Err(format!("{name} 截图超时（超过 {:?}）", timeout)),
-    }
-}
-
-fn capture_with_xcap() -> Result<String, String> {
-    let monitors = Monitor::all().map_err(|e| e.to_string())?;
-    let monitor = monitors.into_iter().next().ok_or("No monitor found")?;
-    let image = monitor.capture_image().map_err(|e| e.to_string())?;
-
-    // 使用快速 PNG 压缩
-    let mut buf = Vec::new();
-    let encoder = PngEncoder::new_with_quality(&mut buf, CompressionType::Fast, FilterType::Sub);
-    encoder
-        .write_image(
-            image.as_raw(),
-            image.width(),
-            image.height(),
-            image::ExtendedColorType::Rgba8,
-        )
-        .map_err(|e| e.to_string())?;
-    Ok(STANDARD.encode(&buf))
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
 }
 
-fn capture_with_grim() -> Result<String, String> {
-    let output = Command::new("grim")
-        .arg("-")
-        .output()
-        .map_err(|e| format!("grim: {}", e))?;
-
-    if !output.status.success() {
-        return Err(format!("grim: {}", String::from_utf8_lossy(&output.stderr)));
-    }
-
-    Ok(STANDARD.encode(&output.stdout))
-}
-
-fn capture_with_gnome_screenshot() -> Result<String, String> {
-    let tmp_file = "/tmp/prinsp_screenshot.png";
-    let _ = std::fs::remove_file(tmp_file);
-
-    let mut child = Command::new("gnome-screenshot")
-        .arg("-f")
-        .arg(tmp_file)
-        .spawn()
-        .map_err(|e| format!("gnome-screenshot: {}", e))?;
-
-    // 等待最多 1.5 秒
-    for _ in 0..15 {
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                if !status.success() {
-                    return Err("gnome-screenshot failed".to_string());
-                }
-                break;
-            }
-            Ok(None) => thread::sleep(Duration::from_millis(100)),
-            Err(e) => return Err(format!("gnome-screenshot: {}", e)),
+#[cfg(feature = "tauri")]
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let cli = launch::Cli::parse();
+    if cli.no_gui {
+        // 已有界面实例时交给它执行，需要输出到终端的请求只能在本进程完成
+        if !cli.needs_stdout() && launch::forward_to_running_instance() {
+            return;
         }
-    }
-
-    let data = std::fs::read(tmp_file).map_err(|e| format!("read file: {}", e))?;
-    let _ = std::fs::remove_file(tmp_file);
-
-    Ok(STANDARD.encode(&data))
-}
-
-/// 颜色通道增强：对彩色文字（如红色）提升与背景的对比度
-fn channel_emphasized_gray(img: &RgbImage) -> GrayImage {
-    let (w, h) = img.dimensions();
-    let n = (w as u64) * (h as u64);
-
-    // 计算各通道均值
-    let mut sum = [0u64; 3];
-    for p in img.pixels() {
-        let channels = p.channels();
-        sum[0] += channels[0] as u64;
-        sum[1] += channels[1] as u64;
-        sum[2] += channels[2] as u64;
-    }
-    let mean = [
-        (sum[0] / n) as f32,
-        (sum[1] / n) as f32,
-        (sum[2] / n) as f32,
-    ];
-
-    // 计算各通道对比度
-    let mut contrast = [0f32; 3];
-    for p in img.pixels() {
-        let channels = p.channels();
-        contrast[0] += (channels[0] as f32 - mean[0]).abs();
-        contrast[1] += (channels[1] as f32 - mean[1]).abs();
-        contrast[2] += (channels[2] as f32 - mean[2]).abs();
-    }
-
-    // 选择对比度最高的通道
-    let best = contrast
-        .iter()
-        .enumerate()
-        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-        .map(|(i, _)| i)
-        .unwrap_or(0);
-
-    // 计算增强后的灰度值并找出范围
-    let mut values: Vec<f32> = Vec::with_capacity((w * h) as usize);
-    for p in img.pixels() {
-        let channels = p.channels();
-        let r = channels[0] as f32;
-        let g = channels[1] as f32;
-        let b = channels[2] as f32;
-        // 对红色通道最高的情况，使用 R - 0.5G - 0.5B 增强红色文字
-        let v = if best == 0 {
-            r - 0.5 * g - 0.5 * b
-        } else if best == 1 {
-            g - 0.5 * r - 0.5 * b
-        } else {
-            b - 0.5 * r - 0.5 * g
-        };
-        values.push(v);
-    }
-
-    // 线性拉伸到 0-255
-    let min_v = values.iter().cloned().fold(f32::INFINITY, f32::min);
-    let max_v = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-    let span = (max_v - min_v).max(1.0);
-
-    let mut out = GrayImage::new(w, h);
-    for (i, v) in values.iter().enumerate() {
-        let norm = ((v - min_v) / span * 255.0).clamp(0.0, 255.0) as u8;
-        let x = (i as u32) % w;
-        let y = (i as u32) / w;
-        out.put_pixel(x, y, image::Luma([norm]));
-    }
-    out
-}
-
-/// 根据二值化后的像素占比判断是否为暗底亮字
-fn is_dark_background(binary: &GrayImage) -> bool {
-    let (mut dark, mut light) = (0usize, 0usize);
-    for p in binary.pixels() {
-        if p[0] < 128 { dark += 1; } else { light += 1; }
-    }
-    dark > light
-}
-
-/// 图像预处理：颜色增强→放大→去噪→自适应二值化→闭运算→暗底反转
-fn preprocess_for_ocr(dyn_img: &image::DynamicImage) -> GrayImage {
-    let rgb = dyn_img.to_rgb8();
-    let (w, h) = rgb.dimensions();
-
-    // 颜色增强的灰度转换
-    let enhanced_gray = channel_emphasized_gray(&rgb);
-
-    // 2倍放大，提升小字识别率
-    let resized = image::imageops::resize(&enhanced_gray, w * 2, h * 2, image::imageops::FilterType::Lanczos3);
-
-    // 中值滤波去噪（保边缘）
-    let denoised = median_filter(&resized, 1, 1);
-
-    // Otsu 自适应阈值二值化
-    let thr = otsu_level(&denoised);
-    let binary = threshold(&denoised, thr, imageproc::contrast::ThresholdType::Binary);
-
-    // 闭运算填补细笔画断裂
-    let mut closed = close(&binary, Norm::L1, 1);
-
-    // 若为暗底亮字则反转，使之变为白底黑字
-    if is_dark_background(&closed) {
-        invert(&mut closed);
-    }
-
-    closed
-}
-
-/// 后处理：规范空白，保留段落结构
-fn postprocess_ocr_text(text: &str) -> String {
-    let mut result = Vec::new();
-    let mut prev_empty = false;
-
-    for line in text.lines() {
-        // 仅压缩连续空格，保留行内容
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            // 保留单个空行作为段落分隔
-            if !prev_empty && !result.is_empty() {
-                result.push(String::new());
-            }
-            prev_empty = true;
-        } else {
-            // 压缩连续空格但保留单个空格
-            let normalized: String = trimmed
-                .chars()
-                .fold((String::new(), false), |(mut s, was_space), c| {
-                    if c.is_whitespace() {
-                        if !was_space {
-                            s.push(' ');
-                        }
-                        (s, true)
-                    } else {
-                        s.push(c);
-                        (s, false)
-                    }
-                })
-                .0;
-            result.push(normalized);
-            prev_empty = false;
+        if let Err(err) = headless::run(&cli) {
+            eprintln!("prinsp: {err}");
+            std::process::exit(1);
         }
+        return;
     }
 
-    // 移除末尾空行
-    while result.last().map_or(false, |s| s.is_empty()) {
-        result.pop();
-    }
-
-    result.join("\n")
-}
-
-#[tauri::command]
-fn ocr_image(base64_data: String) -> Result<String, String> {
-    ensure_tesseract_installed()?;
-
-    let data = STANDARD.decode(&base64_data).map_err(|e| e.to_string())?;
-    let dyn_img = image::load_from_memory(&data).map_err(|e| e.to_string())?;
-
-    let processed = preprocess_for_ocr(&dyn_img);
-    let processed_dyn = image::DynamicImage::ImageLuma8(processed);
-    let img = TessImage::from_dynamic_image(&processed_dyn).map_err(|e| e.to_string())?;
-
-    let mut args = Args::default();
-    args.lang = "chi_sim+eng".into(); // 中文优先
-    args.dpi = Some(350); // 中文对分辨率更敏感
-    args.psm = Some(7); // 单行文本（适合标题类）
-    args.oem = Some(1); // 仅 LSTM 引擎
-    args.config_variables = {
-        let mut vars = HashMap::new();
-        vars.insert("preserve_interword_spaces".into(), "1".into());
-        vars.insert("textord_heavy_nr".into(), "1".into());
-        vars.insert("textord_min_linesize".into(), "2.5".into());
-        vars.insert("textord_space_size_is_variable".into(), "1".into());
-        // 关闭词典，提升生僻字/特殊符号识别
-        vars.insert("load_system_dawg".into(), "F".into());
-        vars.insert("load_freq_dawg".into(), "F".into());
-        vars
-    };
-
-    let raw_text = rusty_tesseract::image_to_string(&img, &args).map_err(|e| {
-        let msg = e.to_string();
-        if msg.contains("Failed loading language") || msg.contains("traineddata") {
-            "Tesseract 语言数据缺失，请安装 tesseract-ocr-chi-sim 并确认 TESSDATA_PREFIX 配置".to_string()
-        } else {
-            msg
-        }
-    })?;
-
-    Ok(postprocess_ocr_text(&raw_text))
-}
-
-#[tauri::command]
-fn copy_text_to_clipboard(text: String) -> Result<(), String> {
-    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
-    clipboard.set_text(text).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-#[tauri::command]
-fn copy_to_clipboard(base64_data: String) -> Result<(), String> {
-    let data = STANDARD.decode(&base64_data).map_err(|e| e.to_string())?;
-    let img = image::load_from_memory(&data).map_err(|e| e.to_string())?;
-    let rgba = img.to_rgba8();
-
-    let img_data = arboard::ImageData {
-        width: rgba.width() as usize,
-        height: rgba.height() as usize,
-        bytes: rgba.into_raw().into(),
-    };
-
-    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
-    clipboard.set_image(img_data).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-#[tauri::command]
-fn save_image_to_file(base64_data: String, path: String) -> Result<(), String> {
-    let data = STANDARD.decode(&base64_data).map_err(|e| e.to_string())?;
-    let img = image::load_from_memory(&data).map_err(|e| e.to_string())?;
-    let save_path = Path::new(&path);
-    img.save(save_path).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
     // Wayland 环境下强制使用 X11 后端，以支持全局快捷键（XWayland）
     if std::env::var("WAYLAND_DISPLAY").is_ok() && std::env::var("GDK_BACKEND").is_err() {
         unsafe { std::env::set_var("GDK_BACKEND", "x11") };
     }
 
     tauri::Builder::default()
+        // 单实例插件需最先注册，第二次启动时把参数交给已运行的实例
+        .plugin(tauri_plugin_single_instance::init(
+            launch::on_second_instance,
+        ))
         .plugin(tauri_plugin_opener::init())
+        .register_uri_scheme_protocol(capture_cache::SCHEME, capture_cache::handle_request)
         .plugin(tauri_plugin_dialog::init())
+        .on_page_load(degraded::on_page_load)
+        .on_window_event(|window, event| {
+            // 只拦截主窗口，钉图等其他窗口照常关闭
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" && tray::hide_to_tray(window.app_handle(), window) {
+                    api.prevent_close();
+                }
+            }
+            pin::on_window_event(window, event);
+        })
         .invoke_handler(tauri::generate_handler![
-            capture_screen,
-            capture_screen_hidden,
-            register_global_shortcut,
-            copy_to_clipboard,
-            copy_text_to_clipboard,
-            save_image_to_file,
-            hide_window,
-            show_window_fullscreen,
-            restore_window,
-            ocr_image
+            commands::capture_screen,
+            commands::capture_screen_b64,
+            commands::capture_screen_hidden,
+            commands::get_capture_session_state,
+            shortcuts::register_global_shortcut,
+            shortcuts::validate_shortcut,
+            shortcuts::set_shortcuts_enabled,
+            shortcuts::get_shortcuts_state,
+            shortcuts::get_current_shortcuts,
+            shortcuts::reregister_shortcuts,
+            quick::quick_capture_to_clipboard,
+            quick::quick_ocr,
+            quick::read_at_cursor,
+            qr::generate_qr,
+            redact::detect_sensitive_regions,
+            redact::redact_regions,
+            redact::auto_redact,
+            edits::crop_image,
+            edits::apply_annotations,
+            edits::transform_image,
+            edits::undo_edit,
+            edits::redo_edit,
+            edits::get_edit_history,
+            recording::start_recording,
+            recording::stop_recording,
+            share::share_file,
+            extract::extract_fields,
+            extract::list_extraction_templates,
+            extract::save_extraction_template,
+            selection_preset::list_selection_presets,
+            selection_preset::save_selection_preset,
+            selection_preset::delete_selection_preset,
+            selection_preset::resolve_selection_preset,
+            diagnostics::run_diagnostics,
+            diagnostics::probe_capture_backends,
+            diagnostics::copy_diagnostics_to_clipboard,
+            backend_health::get_capture_backend,
+            dnd::get_dnd_state,
+            capabilities::get_capabilities,
+            error_report::get_recent_errors,
+            http::test_proxy,
+            commands::copy_to_clipboard,
+            commands::copy_to_clipboard_b64,
+            commands::copy_text_to_clipboard,
+            commands::read_clipboard_text,
+            text_diff::compare_text,
+            postprocess_rules::test_postprocess_rules,
+            window_trim::trim_capture,
+            appearance::get_system_appearance,
+            stats::get_stats,
+            stats::reset_stats,
+            commands::save_image_to_file,
+            commands::hide_window,
+            commands::show_window_fullscreen,
+            commands::restore_window,
+            commands::cancel_capture,
+            overlay::show_overlay,
+            overlay::take_overlay_capture,
+            overlay::list_monitors,
+            overlay::get_overlay_geometry,
+            commands::ocr_image,
+            commands::ocr_image_b64,
+            commands::ocr_capture_region,
+            tesseract::recheck_tesseract,
+            timings::get_last_timings,
+            translate::translate_text,
+            history::add_to_history,
+            history::list_history,
+            history::search_history,
+            history::find_similar,
+            history::reocr_history_item,
+            history::reocr_history_items,
+            history_import::import_folder,
+            history_import::cancel_import,
+            history_index::export_history_index,
+            history::export_history_item,
+            history::export_history_range,
+            storage::get_storage_usage,
+            storage::run_cleanup_now,
+            print::print_image,
+            local_api::regenerate_local_api_token,
+            speech::speak_text,
+            speech::stop_speaking,
+            settings::get_settings,
+            settings::update_settings,
+            settings::reset_settings,
+            settings::export_settings,
+            settings::import_settings,
+            save::save_ocr_text,
+            save::reveal_in_file_manager,
+            save::estimate_encoded_size,
+            notes::export_to_markdown_note,
+            anki::export_to_anki,
+            upload::upload_image,
+            pipeline::finish_capture,
+            tray::really_quit,
+            launch::get_launch_info,
+            i18n::set_locale,
+            tray::get_tray_click,
+            tray::set_tray_click,
+            settings::open_settings_window,
+            kv::kv_get,
+            kv::kv_set,
+            kv::kv_delete,
+            logging::get_recent_logs,
+            logging::open_log_directory,
+            ocr_queue::enqueue_ocr,
+            ocr_queue::get_job_status,
+            ocr_queue::cancel_job,
+            ocr_queue::enqueue_ocr_batch,
+            ocr_queue::cancel_batch,
+            text_watch::start_text_watch,
+            text_watch::stop_text_watch,
+            text_watch::list_text_watches,
+            change_watch::start_change_watch,
+            change_watch::stop_change_watch,
+            change_watch::list_change_watches,
+            webhook::test_webhook,
+            recovery::get_recovery_state,
+            recovery::save_recovery_annotations,
+            recovery::clear_recovery_state,
+            pin::pin_image,
+            pin::arrange_pins,
+            pin::list_pins,
+            pin::get_pin_image,
+            pin::close_pin
         ])
-        .setup(|app| {
-            preselect_backend();
+        .setup(move |app| {
+            // 级别在读取设置后再调整
+            logging::init(&app.path().app_log_dir()?, false);
+            capture::preselect_backend();
+            app.manage(overlay::OverlayCaptures::default());
+            app.manage(CaptureCache::default());
+            app.manage(geometry::WindowGeometry::default());
+            app.manage(text_watch::TextWatches::default());
+            app.manage(change_watch::ChangeWatches::default());
+
+            let history_dir = app.path().app_data_dir()?.join("history");
+            app.manage(history::HistoryState::load(history_dir));
+            let kv_path = app.path().app_data_dir()?.join("ui-store.json");
+            app.manage(kv::KvStore::load(kv_path));
+            let recovery_dir = app.path().app_data_dir()?.join("recovery");
+            app.manage(recovery::Recovery::new(recovery_dir));
+            let pins_dir = app.path().app_data_dir()?.join("pins");
+            app.manage(pin::Pins::new(pins_dir));
+
+            let settings_path = app.path().app_config_dir()?.join("settings.json");
+            let settings_state = settings::SettingsState::load(settings_path);
+            let startup = settings_state.get();
+            logging::set_debug(startup.debug_logging);
+            settings::apply_capture_settings(&startup);
+            backend_health::validate();
+            postprocess_rules::apply_settings(&startup.postprocess_rules);
+            stats::init(app.handle(), startup.stats_enabled);
+            app.manage(http::HttpClient::new(&startup.proxy));
+            app.manage(ocr_queue::OcrQueue::new(
+                app.handle(),
+                ocr_queue::worker_count(startup.ocr_workers),
+            ));
+            app.manage(settings_state);
+            i18n::init(app.handle());
+            let launch_state = launch::LaunchState::new(&cli, startup.capture_on_launch);
+            if !cli.hidden && !startup.start_hidden && !launch_state.capture_pending() {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                }
+            }
+            app.manage(launch_state);
+            pin::restore(app.handle());
 
             // 注册全局快捷键插件
             #[cfg(desktop)]
             {
-                app.handle().plugin(
-                    tauri_plugin_global_shortcut::Builder::new()
-                        .with_shortcuts(["ctrl+shift+a"])?
-                        .with_handler(|app, shortcut, event| {
-                            if event.state == ShortcutState::Pressed {
-                                if shortcut.matches(Modifiers::CONTROL | Modifiers::SHIFT, Code::KeyA) {
-                                    if let Some(window) = app.get_webview_window("main") {
-                                        let _ = window.emit("start-capture", ());
-                                    }
-                                }
-                            }
-                        })
-                        .build(),
-                )?;
+                app.handle()
+                    .plugin(tauri_plugin_global_shortcut::Builder::new().build())?;
+                app.manage(shortcuts::ShortcutRegistry::default());
             }
 
-            let quit = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-            let settings = MenuItem::with_id(app, "settings", "设置", true, None::<&str>)?;
-            let capture = MenuItem::with_id(app, "capture", "截图", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&capture, &settings, &quit])?;
+            tray::init(app.handle())?;
 
-            let _tray = TrayIconBuilder::new()
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .show_menu_on_left_click(true)
-                .tooltip("PrinSp 截图工具")
-                .on_tray_icon_event(|tray, event| match event {
-                    TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } => {
-                        if let Some(window) = tray.app_handle().get_webview_window("main") {
-                            let _ = window.emit("start-capture", ());
-                        }
-                    }
-                    _ => {}
-                })
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    "settings" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = window.emit("open-settings", ());
-                        }
-                    }
-                    "capture" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("start-capture", ());
-                        }
-                    }
-                    _ => {}
-                })
-                .build(app)?;
+            #[cfg(desktop)]
+            if let Err(err) = shortcuts::init(app.handle()) {
+                tracing::error!(error = %err, "注册快捷键失败");
+            }
+            #[cfg(target_os = "linux")]
+            {
+                watchdog::start(app.handle());
+                appearance::watch(app.handle());
+                dbus::start(app.handle());
+            }
+
+            storage::start_janitor(app.handle());
+            degraded::watch(app.handle());
+            local_api::start(app.handle());
+            warmup::start(app.handle(), startup.ocr_warmup);
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                local_api::stop(app);
+                stats::flush();
+                // 正常退出时不需要恢复
+                app.state::<recovery::Recovery>().clear();
+            }
+        });
+}
+
+#[cfg(all(test, feature = "tauri"))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn long_blocking_work_does_not_delay_window_commands() {
+        // 比运行时工作线程更多的“识别”同时进行，每个都要等到窗口收起后才结束
+        let workers = std::thread::available_parallelism().map_or(4, |n| n.get());
+        let mut releases = Vec::new();
+        let jobs: Vec<_> = (0..workers * 2)
+            .map(|_| {
+                let (tx, rx) = mpsc::channel::<()>();
+                releases.push(tx);
+                tauri::async_runtime::spawn(run_blocking(move || {
+                    rx.recv_timeout(Duration::from_secs(5))
+                        .map_err(|_| AppError::Internal("识别期间其他命令被阻塞".into()))
+                }))
+            })
+            .collect();
+
+        // 模拟同时到达的 hide_window：不必等任何识别结束
+        let started = Instant::now();
+        let hidden =
+            tauri::async_runtime::block_on(tauri::async_runtime::spawn(async { Instant::now() }))
+                .unwrap();
+        assert!(hidden.duration_since(started) < Duration::from_secs(1));
+
+        for tx in releases {
+            tx.send(()).unwrap();
+        }
+        for job in jobs {
+            tauri::async_runtime::block_on(job).unwrap().unwrap();
+        }
+    }
 }
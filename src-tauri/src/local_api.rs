@@ -0,0 +1,366 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::ocr_queue::{JobInput, JobPriority, OcrQueue};
+use crate::save::{self, ExportFormat};
+use crate::settings::SettingsState;
+
+/// 上传图片的大小上限
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// 供自动化脚本使用的本机 HTTP 接口，只监听 127.0.0.1
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// 每个请求都要带 Authorization: Bearer <token>；开启时为空则自动生成
+    pub token: String,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 38517,
+            token: String::new(),
+        }
+    }
+}
+
+// 手写 Debug，避免令牌出现在日志里
+impl std::fmt::Debug for LocalApiSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalApiSettings")
+            .field("enabled", &self.enabled)
+            .field("port", &self.port)
+            .field("token", &"***")
+            .finish()
+    }
+}
+
+impl LocalApiSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.port == 0 {
+            return Err("本机接口端口不能为 0".into());
+        }
+        Ok(())
+    }
+}
+
+struct Running {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+    settings: LocalApiSettings,
+}
+
+/// 正在运行的服务；设置变化或退出时停止
+#[derive(Default)]
+pub struct LocalApi(Mutex<Option<Running>>);
+
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 24];
+    getrandom::fill(&mut bytes).map_err(|e| format!("生成令牌失败: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// 开启时还没有令牌则生成一个并保存，返回最新设置
+fn ensure_token(app: &AppHandle, settings: &LocalApiSettings) -> Result<LocalApiSettings, String> {
+    if !settings.token.is_empty() {
+        return Ok(settings.clone());
+    }
+    let token = generate_token()?;
+    let saved = app
+        .state::<SettingsState>()
+        .update(|s| s.local_api.token = token)?;
+    Ok(saved.local_api)
+}
+
+/// 按设置启动、重启或停止服务
+pub fn apply(app: &AppHandle, settings: &LocalApiSettings) -> Result<(), String> {
+    let state = app.state::<LocalApi>();
+    let mut slot = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    // 端口和令牌都没变时保持运行
+    let unchanged = slot.as_ref().is_some_and(|running| {
+        settings.enabled
+            && running.settings.port == settings.port
+            && running.settings.token == settings.token
+    });
+    if unchanged {
+        return Ok(());
+    }
+    if let Some(running) = slot.take() {
+        stop_running(running);
+    }
+    if !settings.enabled {
+        return Ok(());
+    }
+    let settings = ensure_token(app, settings)?;
+    let server = Server::http(("127.0.0.1", settings.port))
+        .map(Arc::new)
+        .map_err(|e| format!("本机接口无法监听端口 {}: {e}", settings.port))?;
+    let thread = {
+        let server = server.clone();
+        let app = app.clone();
+        let token = settings.token.clone();
+        thread::spawn(move || serve(&app, &server, &token))
+    };
+    tracing::info!(port = settings.port, "本机接口已启动");
+    *slot = Some(Running {
+        server,
+        thread,
+        settings,
+    });
+    Ok(())
+}
+
+fn stop_running(running: Running) {
+    running.server.unblock();
+    let _ = running.thread.join();
+    tracing::info!(port = running.settings.port, "本机接口已停止");
+}
+
+/// 启动时按设置开启服务，失败只记录日志
+pub fn start(app: &AppHandle) {
+    app.manage(LocalApi::default());
+    let settings = app.state::<SettingsState>().get().local_api;
+    if let Err(err) = apply(app, &settings) {
+        tracing::warn!(error = %err, "本机接口启动失败");
+    }
+}
+
+/// 程序退出时关闭监听
+pub fn stop(app: &AppHandle) {
+    if let Some(state) = app.try_state::<LocalApi>() {
+        let running = state.0.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(running) = running {
+            stop_running(running);
+        }
+    }
+}
+
+/// 重新生成令牌，旧令牌立即失效
+#[tauri::command]
+pub fn regenerate_local_api_token(app: AppHandle) -> Result<String, String> {
+    let token = generate_token()?;
+    let saved = app
+        .state::<SettingsState>()
+        .update(|s| s.local_api.token = token)?;
+    apply(&app, &saved.local_api)?;
+    Ok(saved.local_api.token)
+}
+
+fn serve(app: &AppHandle, server: &Server, token: &str) {
+    // unblock() 后迭代结束
+    for request in server.incoming_requests() {
+        let app = app.clone();
+        let token = token.to_string();
+        // 截图、识别较慢，每个请求单独一个线程
+        thread::spawn(move || {
+            let method = request.method().clone();
+            let url = request.url().to_string();
+            let status = handle(&app, request, &token);
+            tracing::debug!(%method, %url, status, "本机接口请求");
+        });
+    }
+}
+
+/// 逐字节比较全部内容，耗时与不匹配的位置无关
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn json_response(status: u16, body: &impl Serialize) -> Response<std::io::Cursor<Vec<u8>>> {
+    let data = serde_json::to_vec(body).unwrap_or_default();
+    Response::from_data(data)
+        .with_status_code(status)
+        .with_header(content_type("application/json"))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("合法的响应头")
+}
+
+fn error_response(err: &AppError) -> Response<std::io::Cursor<Vec<u8>>> {
+    let status = match err {
+        AppError::InvalidInput(_) => 400,
+        AppError::UnsupportedFormat { .. } => 415,
+        AppError::Timeout { .. } => 504,
+        AppError::NoDisplay | AppError::TesseractMissing | AppError::LanguageMissing { .. } => 503,
+        _ => 500,
+    };
+    json_response(status, err)
+}
+
+/// 处理一个请求并回复，返回状态码
+fn handle(app: &AppHandle, mut request: Request, token: &str) -> u16 {
+    // 只监听回环地址，这里再检查一次来源
+    if !request.remote_addr().is_some_and(|a| a.ip().is_loopback()) {
+        let _ = request.respond(Response::empty(403));
+        return 403;
+    }
+    let authorized = header(&request, "Authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|given| token_matches(given.trim(), token));
+    if !authorized {
+        let body = json!({ "code": "unauthorized", "message": "缺少或错误的令牌" });
+        let _ =
+            request.respond(json_response(401, &body).with_header(
+                Header::from_bytes("WWW-Authenticate", "Bearer").expect("合法的响应头"),
+            ));
+        return 401;
+    }
+
+    let method = request.method().clone();
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let response = match (method, path.as_str()) {
+        (Method::Get, "/health") => json_response(200, &health()),
+        (Method::Post, "/capture") => {
+            match read_body(&mut request).and_then(|body| capture(app, &body)) {
+                Ok((data, format)) => {
+                    Response::from_data(data).with_header(content_type(format.mime_type()))
+                }
+                Err(err) => error_response(&err),
+            }
+        }
+        (Method::Post, "/ocr") => {
+            let lang = query_param(request.url(), "lang");
+            match read_body(&mut request).and_then(|body| ocr(app, body, lang)) {
+                Ok(body) => json_response(200, &body),
+                Err(err) => error_response(&err),
+            }
+        }
+        (_, "/health" | "/capture" | "/ocr") => {
+            Response::from_data(Vec::new()).with_status_code(405)
+        }
+        _ => Response::from_data(Vec::new()).with_status_code(404),
+    };
+    let status = response.status_code().0;
+    let _ = request.respond(response);
+    status
+}
+
+fn read_body(request: &mut Request) -> Result<Vec<u8>, AppError> {
+    if request
+        .body_length()
+        .is_some_and(|len| len > MAX_BODY_BYTES)
+    {
+        return Err(AppError::invalid("请求体过大"));
+    }
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES as u64 + 1)
+        .read_to_end(&mut body)?;
+    if body.len() > MAX_BODY_BYTES {
+        return Err(AppError::invalid("请求体过大"));
+    }
+    Ok(body)
+}
+
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let query = url.split_once('?')?.1;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[derive(Serialize)]
+struct Health {
+    version: &'static str,
+    backends: Vec<crate::diagnostics::BackendProbe>,
+    tesseract: Option<crate::tesseract::TesseractInfo>,
+}
+
+fn health() -> Health {
+    Health {
+        version: env!("CARGO_PKG_VERSION"),
+        backends: crate::diagnostics::probe_backends(),
+        tesseract: crate::tesseract::detected(),
+    }
+}
+
+/// POST /capture 的请求体，均可省略
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct CaptureRequest {
+    monitor: Option<u32>,
+    region: Option<Region>,
+    /// 选区预设名称，指定时忽略 monitor 与 region
+    preset: Option<String>,
+    format: Option<ExportFormat>,
+}
+
+fn capture(app: &AppHandle, body: &[u8]) -> Result<(Vec<u8>, ExportFormat), AppError> {
+    let options: CaptureRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        CaptureRequest::default()
+    } else {
+        serde_json::from_slice(body).map_err(AppError::invalid)?
+    };
+    let format = options.format.unwrap_or(ExportFormat::Png);
+    let (monitor, region) = match &options.preset {
+        Some(name) => {
+            let resolved = crate::selection_preset::resolve(app, name)?;
+            (Some(resolved.monitor), Some(resolved.region))
+        }
+        None => (options.monitor, options.region),
+    };
+    let mut frame = crate::quick::capture_area_without_windows(app, monitor, region)
+        .map_err(AppError::Internal)?;
+    if let Some(region) = region {
+        frame = crate::headless::crop(frame, region)?;
+    }
+    let data = match format {
+        ExportFormat::Png => frame.into_png()?,
+        format => save::encode_image(&frame.into_image()?, format, save::DEFAULT_QUALITY)
+            .map_err(AppError::Internal)?,
+    };
+    Ok((data, format))
+}
+
+fn ocr(
+    app: &AppHandle,
+    body: Vec<u8>,
+    lang: Option<String>,
+) -> Result<serde_json::Value, AppError> {
+    if body.is_empty() {
+        return Err(AppError::invalid("需要在请求体中上传图片"));
+    }
+    let lang = lang.unwrap_or_else(|| app.state::<SettingsState>().get().ocr_lang);
+    let recognized =
+        app.state::<OcrQueue>()
+            .recognize(JobInput::Png(body), lang, JobPriority::High, None)?;
+    Ok(json!({
+        "text": recognized.text,
+        "warning": recognized.warning,
+        "timings": recognized.timings,
+    }))
+}
@@ -0,0 +1,217 @@
+use chrono::Local;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Layer, Registry};
+
+const LOG_FILE: &str = "prinsp.log";
+/// 单个日志文件的大小上限
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// 保留的旧日志文件数（prinsp.log.1 ~ prinsp.log.3）
+const ROTATED_FILES: u32 = 3;
+/// 内存中保留的最近记录数
+const RECENT_CAPACITY: usize = 500;
+
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static RECENT: Mutex<VecDeque<(Level, LogRecord)>> = Mutex::new(VecDeque::new());
+
+/// 一条日志记录，供界面中的日志查看器显示
+#[derive(Clone, Serialize)]
+pub struct LogRecord {
+    pub time: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// 结构化字段，如 backend、duration_ms、error
+    pub fields: BTreeMap<String, String>,
+}
+
+/// 超过大小上限时把 prinsp.log 依次改名为 .1、.2……，最旧的被覆盖
+struct RotatingFile {
+    dir: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn rotated(&self, index: u32) -> PathBuf {
+        self.dir.join(format!("{LOG_FILE}.{index}"))
+    }
+
+    fn rotate(&mut self) {
+        self.file = None;
+        for index in (1..ROTATED_FILES).rev() {
+            let _ = std::fs::rename(self.rotated(index), self.rotated(index + 1));
+        }
+        let _ = std::fs::rename(self.dir.join(LOG_FILE), self.rotated(1));
+        self.size = 0;
+    }
+
+    fn file(&mut self) -> io::Result<&mut File> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => {
+                std::fs::create_dir_all(&self.dir)?;
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.dir.join(LOG_FILE))?;
+                self.size = file.metadata().map(|m| m.len()).unwrap_or(0);
+                file
+            }
+        };
+        Ok(self.file.insert(file))
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file()?;
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_FILE_BYTES {
+            self.rotate();
+        }
+        let written = self.file()?.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 收集事件的 message 与其余字段
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+}
+
+/// 把事件写入内存中的环形缓冲区
+struct RecentLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let record = LogRecord {
+            time: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        };
+        if let Ok(mut recent) = RECENT.lock() {
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back((*metadata.level(), record));
+        }
+    }
+}
+
+fn verbosity(debug: bool) -> LevelFilter {
+    if debug {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::INFO
+    }
+}
+
+/// 初始化日志：写入日志目录下的 prinsp.log，并在内存中保留最近的记录
+pub fn init(dir: &Path, debug: bool) {
+    let (level, handle) = reload::Layer::new(verbosity(debug));
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(Mutex::new(RotatingFile::new(dir.to_path_buf())));
+    let result = tracing_subscriber::registry()
+        .with(level)
+        .with(file_layer)
+        .with(RecentLayer)
+        .try_init();
+    if let Err(err) = result {
+        eprintln!("[logging] 初始化日志失败: {err}");
+        return;
+    }
+    let _ = LEVEL.set(handle);
+    let _ = LOG_DIR.set(dir.to_path_buf());
+}
+
+/// 运行时切换 info / debug 级别
+pub fn set_debug(debug: bool) {
+    if let Some(handle) = LEVEL.get() {
+        if let Err(err) = handle.reload(verbosity(debug)) {
+            tracing::warn!(error = %err, "切换日志级别失败");
+        }
+    }
+}
+
+/// 返回最近的日志记录，level_filter 为最低级别（error / warn / info / debug / trace）
+#[tauri::command]
+pub fn get_recent_logs(level_filter: Option<String>) -> Result<Vec<LogRecord>, String> {
+    let max_level = match level_filter.as_deref() {
+        Some(level) => Level::from_str(level).map_err(|_| format!("无效的日志级别: {level}"))?,
+        None => Level::TRACE,
+    };
+    let recent = RECENT.lock().map_err(|e| e.to_string())?;
+    Ok(recent
+        .iter()
+        .filter(|(level, _)| *level <= max_level)
+        .map(|(_, record)| record.clone())
+        .collect())
+}
+
+/// 在文件管理器中打开日志目录
+#[tauri::command]
+pub fn open_log_directory(app: AppHandle) -> Result<(), String> {
+    let dir = LOG_DIR.get().ok_or("日志未初始化")?;
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| e.to_string())
+}
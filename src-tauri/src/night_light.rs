@@ -0,0 +1,337 @@
+use chrono::{Local, Timelike};
+use image::DynamicImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+use crate::external::{run_external, ExternalTool, PROBE_TIMEOUT};
+use crate::frame::Frame;
+
+/// 不做调整的色温，与 GNOME 夜灯关闭时一致
+const NEUTRAL_K: u32 = 6500;
+/// 可补偿的色温范围；更低时蓝色通道几乎为 0，已无法还原
+const MIN_K: u32 = 1700;
+const MAX_K: u32 = 10000;
+/// 通道增益的下限，避免接近 0 的通道被放大成噪点
+const MIN_MULTIPLIER: f64 = 0.1;
+/// 自动检测结果的缓存时间，夜灯渐变较慢，不必每次截图都查询
+const DETECT_TTL: Duration = Duration::from_secs(30);
+
+static DETECTED: Mutex<Option<(Instant, Option<u32>)>> = Mutex::new(None);
+
+/// 夜灯（GNOME Night Light、wlsunset 等）补偿设置
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NightLightSettings {
+    /// 截图后按夜灯色温反向调整白平衡，还原未偏色的画面
+    pub compensate: bool,
+    /// 手动指定的色温（K），wlsunset 等无法查询的工具使用；为空时从 GNOME 读取
+    pub temperature_k: Option<u32>,
+}
+
+impl NightLightSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.temperature_k {
+            Some(k) if !(MIN_K..=MAX_K).contains(&k) => {
+                Err(format!("夜灯色温必须在 {MIN_K}K 到 {MAX_K}K 之间"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureSource {
+    /// 设置中手动指定
+    Manual,
+    /// 从 GNOME 夜灯读取
+    Gnome,
+}
+
+/// 截图已按该色温做过补偿，像素与屏幕上看到的不同
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compensation {
+    pub temperature_k: u32,
+    pub source: TemperatureSource,
+}
+
+/// 色温对应的白点 RGB（0–1），Tanner Helland 对黑体辐射的近似
+fn kelvin_to_rgb(kelvin: u32) -> [f64; 3] {
+    let t = kelvin.clamp(1000, 40000) as f64 / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_727_446 * (t - 60.0).powf(-0.133_204_759_2)
+    };
+    let green = if t <= 66.0 {
+        99.470_802_586_1 * t.ln() - 161.119_568_166_1
+    } else {
+        288.122_169_528_3 * (t - 60.0).powf(-0.075_514_849_2)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_731_223_1 * (t - 10.0).ln() - 305.044_792_730_7
+    };
+    [red, green, blue].map(|c| c.clamp(0.0, 255.0) / 255.0)
+}
+
+/// 夜灯在该色温下对各通道的增益，以 6500K 为 1
+fn multipliers(kelvin: u32) -> [f64; 3] {
+    let target = kelvin_to_rgb(kelvin);
+    let neutral = kelvin_to_rgb(NEUTRAL_K);
+    [0, 1, 2].map(|i| (target[i] / neutral[i]).clamp(MIN_MULTIPLIER, 1.0))
+}
+
+/// 各通道的反向查表：夜灯按增益缩小了编码值，这里除回去
+fn inverse_tables(kelvin: u32) -> [[u8; 256]; 3] {
+    multipliers(kelvin).map(|gain| {
+        let mut table = [0u8; 256];
+        for (value, out) in table.iter_mut().enumerate() {
+            *out = (value as f64 / gain).round().min(255.0) as u8;
+        }
+        table
+    })
+}
+
+/// 读取 GNOME 夜灯当前的色温；未开启或不在生效时段返回 None
+fn gnome_temperature() -> Option<u32> {
+    let from_dbus = || -> zbus::Result<Option<u32>> {
+        let connection = zbus::blocking::Connection::session()?;
+        let proxy = zbus::blocking::Proxy::new(
+            &connection,
+            "org.gnome.SettingsDaemon.Color",
+            "/org/gnome/SettingsDaemon/Color",
+            "org.gnome.SettingsDaemon.Color",
+        )?;
+        if !proxy.get_property::<bool>("NightLightActive")? {
+            return Ok(None);
+        }
+        Ok(Some(proxy.get_property::<u32>("Temperature")?))
+    };
+    match from_dbus() {
+        Ok(temperature) => temperature,
+        // 没有 gsd-color 服务时退回 gsettings，按设置的时段和色温自行判断
+        Err(err) => {
+            tracing::debug!(error = %err, "读取 GNOME 夜灯状态失败，改用 gsettings");
+            let now = Local::now();
+            GsettingsNightLight::read()?
+                .temperature_at(now.hour() as f64 + now.minute() as f64 / 60.0)
+        }
+    }
+}
+
+/// gsettings 中的夜灯配置
+#[derive(Clone, Copy, Debug)]
+struct GsettingsNightLight {
+    enabled: bool,
+    /// 按日落日出自动开启；无 gsd-color 时算不出日出日落
+    automatic: bool,
+    /// 手动时段，单位为小时，可跨午夜
+    from: f64,
+    to: f64,
+    temperature: u32,
+}
+
+impl GsettingsNightLight {
+    const SCHEMA: &'static str = "org.gnome.settings-daemon.plugins.color";
+
+    fn read() -> Option<Self> {
+        let get = |key| gsettings(Self::SCHEMA, key);
+        Some(Self {
+            enabled: get("night-light-enabled")? == "true",
+            automatic: get("night-light-schedule-automatic")? == "true",
+            from: get("night-light-schedule-from")?.parse().ok()?,
+            to: get("night-light-schedule-to")?.parse().ok()?,
+            temperature: get("night-light-temperature")?
+                .trim_start_matches("uint32 ")
+                .parse()
+                .ok()?,
+        })
+    }
+
+    /// 该时刻（小时）夜灯是否生效及其色温
+    fn temperature_at(&self, hour: f64) -> Option<u32> {
+        if !self.enabled {
+            return None;
+        }
+        if self.automatic {
+            tracing::debug!("GNOME 夜灯按日落自动开启，无法判断是否生效，不做补偿");
+            return None;
+        }
+        let active = if self.from <= self.to {
+            (self.from..self.to).contains(&hour)
+        } else {
+            hour >= self.from || hour < self.to
+        };
+        active.then_some(self.temperature)
+    }
+}
+
+fn gsettings(schema: &str, key: &str) -> Option<String> {
+    let output = run_external(
+        ExternalTool::Gsettings,
+        ["get", schema, key],
+        None,
+        PROBE_TIMEOUT,
+    )
+    .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn detected_temperature() -> Option<u32> {
+    if let Ok(cached) = DETECTED.lock() {
+        if let Some((at, temperature)) = *cached {
+            if at.elapsed() < DETECT_TTL {
+                return temperature;
+            }
+        }
+    }
+    let temperature = gnome_temperature();
+    if let Ok(mut cached) = DETECTED.lock() {
+        *cached = Some((Instant::now(), temperature));
+    }
+    temperature
+}
+
+/// 当前应使用的补偿；手动色温优先，接近 6500K 时不调整
+fn resolve(settings: &NightLightSettings) -> Option<Compensation> {
+    if !settings.compensate {
+        return None;
+    }
+    let compensation = match settings.temperature_k {
+        Some(temperature_k) => Compensation {
+            temperature_k,
+            source: TemperatureSource::Manual,
+        },
+        None => Compensation {
+            temperature_k: detected_temperature()?,
+            source: TemperatureSource::Gnome,
+        },
+    };
+    (compensation.temperature_k < NEUTRAL_K - 50).then_some(compensation)
+}
+
+/// 截图进入后续流程前反向调整白平衡；未开启或夜灯未生效时原样返回。
+/// 同时返回做过的补偿，随截图结果写入截图信息和历史记录
+pub fn compensate(
+    frame: Frame,
+    settings: &NightLightSettings,
+) -> Result<(Frame, Option<Compensation>), AppError> {
+    let Some(compensation) = resolve(settings) else {
+        return Ok((frame, None));
+    };
+    let tables = inverse_tables(compensation.temperature_k);
+    let mut image = frame.into_image()?.into_rgba8();
+    image.par_chunks_mut(4).for_each(|pixel| {
+        for (channel, table) in pixel.iter_mut().zip(&tables) {
+            *channel = table[*channel as usize];
+        }
+    });
+    tracing::debug!(
+        temperature_k = compensation.temperature_k,
+        source = ?compensation.source,
+        "已补偿夜灯色温"
+    );
+    Ok((
+        Frame::Image(DynamicImage::ImageRgba8(image)),
+        Some(compensation),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(from: f64, to: f64) -> GsettingsNightLight {
+        GsettingsNightLight {
+            enabled: true,
+            automatic: false,
+            from,
+            to,
+            temperature: 2700,
+        }
+    }
+
+    #[test]
+    fn neutral_temperature_keeps_channels() {
+        for gain in multipliers(NEUTRAL_K) {
+            assert!((gain - 1.0).abs() < 1e-9);
+        }
+        let rgb = kelvin_to_rgb(NEUTRAL_K);
+        assert_eq!(rgb[0], 1.0);
+        assert!(rgb[1] > 0.95 && rgb[2] > 0.95, "{rgb:?}");
+    }
+
+    #[test]
+    fn warmer_temperature_dims_blue_first() {
+        let mut last_blue = 1.0;
+        for kelvin in [6000, 5000, 4000, 3000, 2000] {
+            let [red, green, blue] = multipliers(kelvin);
+            assert_eq!(red, 1.0);
+            assert!(blue < green && green < 1.0, "{kelvin}K: {green} {blue}");
+            assert!(blue < last_blue);
+            last_blue = blue;
+        }
+    }
+
+    #[test]
+    fn gains_are_clamped() {
+        // 1000K 以下蓝色通道为 0，增益按下限处理
+        assert_eq!(multipliers(1000)[2], MIN_MULTIPLIER);
+        assert_eq!(kelvin_to_rgb(500), kelvin_to_rgb(1000));
+        // 高于 6500K 偏蓝，红色增益被限制在 1 以下不放大
+        assert!(multipliers(MAX_K).iter().all(|&g| g <= 1.0));
+    }
+
+    #[test]
+    fn inverse_tables_are_monotonic_and_capped() {
+        for kelvin in [MIN_K, 3400, NEUTRAL_K] {
+            for table in inverse_tables(kelvin) {
+                assert_eq!(table[0], 0);
+                assert!(table.windows(2).all(|w| w[0] <= w[1]));
+                assert_eq!(table[255], 255);
+            }
+        }
+        let identity: [u8; 256] = std::array::from_fn(|i| i as u8);
+        assert_eq!(inverse_tables(NEUTRAL_K), [identity; 3]);
+        // 补偿夜灯缩小的编码值
+        let [red, _, blue] = inverse_tables(3400);
+        assert_eq!(red[100], 100);
+        assert!(blue[100] > 100);
+    }
+
+    #[test]
+    fn gsettings_schedule_within_a_day() {
+        let night = schedule(8.0, 18.5);
+        assert_eq!(night.temperature_at(7.99), None);
+        assert_eq!(night.temperature_at(8.0), Some(2700));
+        assert_eq!(night.temperature_at(18.4), Some(2700));
+        assert_eq!(night.temperature_at(18.5), None);
+    }
+
+    #[test]
+    fn gsettings_schedule_wraps_midnight() {
+        let night = schedule(20.0, 6.0);
+        assert_eq!(night.temperature_at(21.0), Some(2700));
+        assert_eq!(night.temperature_at(0.5), Some(2700));
+        assert_eq!(night.temperature_at(6.0), None);
+        assert_eq!(night.temperature_at(12.0), None);
+    }
+
+    #[test]
+    fn gsettings_disabled_or_automatic_is_not_compensated() {
+        let mut night = schedule(0.0, 24.0);
+        assert_eq!(night.temperature_at(12.0), Some(2700));
+        night.automatic = true;
+        assert_eq!(night.temperature_at(12.0), None);
+        night.automatic = false;
+        night.enabled = false;
+        assert_eq!(night.temperature_at(12.0), None);
+    }
+}
@@ -0,0 +1,137 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsState;
+
+/// 导出到笔记库（Obsidian 等）的配置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteExportOptions {
+    /// 笔记库根目录
+    pub vault_path: Option<String>,
+    /// 图片存放的子目录，相对于笔记库
+    pub attachments_dir: String,
+    /// 笔记路径模板，相对于笔记库，支持 {date}
+    pub note_template: String,
+    /// 追加内容模板，支持 {date} {time} {image} {image_name} {ocr_text} {ocr_quote}
+    pub content_template: String,
+}
+
+impl Default for NoteExportOptions {
+    fn default() -> Self {
+        Self {
+            vault_path: None,
+            attachments_dir: "attachments".into(),
+            note_template: "{date}.md".into(),
+            content_template: "\n## {time}\n\n![[{image}]]\n\n{ocr_quote}\n".into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct NoteExportResult {
+    pub image_path: String,
+    pub note_path: String,
+}
+
+/// 模板展开后的相对路径不能跳出笔记库
+fn vault_relative(template: &str, date: &str) -> Result<PathBuf, String> {
+    let rel = PathBuf::from(template.replace("{date}", date));
+    let escapes = rel
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if rel.as_os_str().is_empty() || escapes {
+        return Err(format!("路径模板必须是笔记库内的相对路径: {template}"));
+    }
+    Ok(rel)
+}
+
+fn resolve_vault(options: &NoteExportOptions) -> Result<PathBuf, String> {
+    let vault = options
+        .vault_path
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+        .ok_or("尚未配置笔记库路径")?;
+    let vault = PathBuf::from(vault);
+    if !vault.is_dir() {
+        return Err(format!("笔记库目录不存在: {}", vault.display()));
+    }
+    Ok(vault)
+}
+
+fn quote(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("> {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 以追加方式一次性写入整段内容；笔记被其他编辑器打开时也不会覆盖其内容
+fn append_to_note(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建 {}: {e}", parent.display()))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("打开笔记 {}: {e}", path.display()))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("写入笔记 {}: {e}", path.display()))
+}
+
+/// 把截图存入笔记库附件目录，并在当天笔记末尾追加图片和 OCR 文本
+#[tauri::command]
+pub fn export_to_markdown_note(
+    app: AppHandle,
+    base64_data: String,
+    ocr_text: Option<String>,
+    options: Option<NoteExportOptions>,
+) -> Result<NoteExportResult, String> {
+    let options = options.unwrap_or_else(|| app.state::<SettingsState>().get().note_export);
+    let vault = resolve_vault(&options)?;
+
+    let png = STANDARD.decode(&base64_data).map_err(|e| e.to_string())?;
+    image::guess_format(&png).map_err(|e| e.to_string())?;
+
+    let now = Local::now();
+    let date = now.format("%Y-%m-%d").to_string();
+    let attachments = vault.join(vault_relative(&options.attachments_dir, &date)?);
+    let note_path = vault.join(vault_relative(&options.note_template, &date)?);
+
+    std::fs::create_dir_all(&attachments)
+        .map_err(|e| format!("创建 {}: {e}", attachments.display()))?;
+    let image_name = format!("screenshot_{}.png", now.format("%Y%m%d_%H%M%S"));
+    let image_path = attachments.join(&image_name);
+    crate::durable::atomic_write(&image_path, &png)?;
+
+    // Obsidian 的 ![[...]] 使用相对于笔记库的路径
+    let image_rel = image_path
+        .strip_prefix(&vault)
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|_| image_name.clone());
+    let text = ocr_text.unwrap_or_default();
+    let ocr_quote = if text.trim().is_empty() {
+        String::new()
+    } else {
+        quote(text.trim())
+    };
+    let content = options
+        .content_template
+        .replace("{date}", &date)
+        .replace("{time}", &now.format("%H:%M:%S").to_string())
+        .replace("{image}", &image_rel)
+        .replace("{image_name}", &image_name)
+        .replace("{ocr_text}", text.trim())
+        .replace("{ocr_quote}", &ocr_quote);
+
+    append_to_note(&note_path, &content)?;
+    Ok(NoteExportResult {
+        image_path: image_path.to_string_lossy().into_owned(),
+        note_path: note_path.to_string_lossy().into_owned(),
+    })
+}
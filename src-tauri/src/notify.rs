@@ -0,0 +1,165 @@
+use image::DynamicImage;
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::thread;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsState;
+
+/// 各类事件是否弹出桌面通知
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub capture: bool,
+    pub ocr: bool,
+    pub upload: bool,
+    pub errors: bool,
+    /// 桌面处于勿扰模式时仍然弹出通知、播放提示音
+    pub always_notify: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            capture: true,
+            ocr: true,
+            upload: true,
+            errors: true,
+            always_notify: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum NotifyEvent {
+    Capture,
+    Ocr,
+    Upload,
+    Error,
+    /// 一次性的使用提示，不受设置开关影响
+    Hint,
+}
+
+/// 通知“复制”按钮再次复制的内容
+pub enum CopyPayload {
+    Image(DynamicImage),
+    Text(String),
+}
+
+pub struct Notice {
+    pub event: NotifyEvent,
+    pub summary: String,
+    pub body: String,
+    pub thumbnail: Option<DynamicImage>,
+    /// 有文件时提供“打开”“分享”按钮
+    pub file: Option<PathBuf>,
+    pub copy: Option<CopyPayload>,
+}
+
+impl Notice {
+    pub fn new(event: NotifyEvent, summary: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            event,
+            summary: summary.into(),
+            body: body.into(),
+            thumbnail: None,
+            file: None,
+            copy: None,
+        }
+    }
+}
+
+fn enabled(app: &AppHandle, event: NotifyEvent) -> bool {
+    let settings = app.state::<SettingsState>().get().notifications;
+    match event {
+        NotifyEvent::Capture => settings.capture,
+        NotifyEvent::Ocr => settings.ocr,
+        NotifyEvent::Upload => settings.upload,
+        NotifyEvent::Error => settings.errors,
+        NotifyEvent::Hint => true,
+    }
+}
+
+fn run_action(app: &AppHandle, action: &str, file: Option<PathBuf>, copy: Option<CopyPayload>) {
+    match action {
+        "open" => {
+            if let Some(path) = file {
+                let _ = crate::save::reveal_path(app, &path);
+            }
+        }
+        "share" => {
+            if let Some(path) = file {
+                if let Err(err) = crate::share::share_path(&path) {
+                    tracing::warn!(error = %err, "从通知分享失败");
+                }
+            }
+        }
+        "copy" => match copy {
+            Some(CopyPayload::Image(img)) => {
+                let _ = crate::clipboard::set_image(&img);
+            }
+            Some(CopyPayload::Text(text)) => {
+                let _ = crate::clipboard::set_text(text);
+            }
+            None => {}
+        },
+        _ => {}
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show(app: AppHandle, mut notification: Notification, notice: Notice) {
+    if let Some(thumb) = &notice.thumbnail {
+        // 服务器支持 image-data 提示时显示缩略图
+        let thumb = thumb.thumbnail(256, 256).to_rgba8();
+        let (w, h) = thumb.dimensions();
+        if let Ok(image) = notify_rust::Image::from_rgba(w as i32, h as i32, thumb.into_raw()) {
+            notification.image_data(image);
+        }
+    }
+    if let Ok(handle) = notification.show() {
+        let Notice { file, copy, .. } = notice;
+        handle.wait_for_action(|action| run_action(&app, action, file, copy));
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn show(_app: AppHandle, notification: Notification, _notice: Notice) {
+    let _ = notification.show();
+}
+
+/// 在后台线程发送通知并等待按钮回调，按设置过滤事件类型；
+/// 勿扰模式中不弹出，只写入日志和托盘提示文字
+pub fn notify(app: &AppHandle, notice: Notice) {
+    if !enabled(app, notice.event) {
+        return;
+    }
+
+    let mut notification = Notification::new();
+    notification
+        .appname("PrinSp")
+        .summary(&notice.summary)
+        .body(&notice.body);
+    if notice.file.is_some() {
+        notification.action("open", "打开");
+        notification.action("share", "分享");
+    }
+    if notice.copy.is_some() {
+        notification.action("copy", "复制");
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        if crate::dnd::suppressing(&app) {
+            tracing::info!(
+                summary = %notice.summary,
+                body = %notice.body,
+                "勿扰模式中，未弹出通知"
+            );
+            crate::tray::record_notice(&app, &notice.summary, &notice.body);
+            return;
+        }
+        show(app, notification, notice)
+    });
+}
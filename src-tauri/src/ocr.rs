@@ -0,0 +1,265 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::error::AppError;
+use crate::postprocess_rules;
+use crate::preprocess::{gray_for_ocr, preprocess_for_ocr};
+use crate::tesseract;
+use crate::timings::Timings;
+
+/// tesseract 的页面分割模式默认按单行文本识别
+const DEFAULT_PSM: u32 = 7;
+
+/// 一次识别的完整参数，随历史记录保存，重新识别时沿用
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcrOptions {
+    pub lang: String,
+    /// tesseract 的 --psm
+    #[serde(default = "default_psm")]
+    pub psm: u32,
+    /// 是否做增强、放大、二值化等预处理；关闭时只转为灰度
+    #[serde(default = "default_preprocess")]
+    pub preprocess: bool,
+}
+
+fn default_psm() -> u32 {
+    DEFAULT_PSM
+}
+
+fn default_preprocess() -> bool {
+    true
+}
+
+impl OcrOptions {
+    pub fn new(lang: impl Into<String>) -> Self {
+        Self {
+            lang: lang.into(),
+            psm: DEFAULT_PSM,
+            preprocess: true,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.lang.trim().is_empty() {
+            return Err(AppError::invalid("识别语言不能为空"));
+        }
+        // 0 只做方向检测，不输出文字
+        if !(1..=13).contains(&self.psm) {
+            return Err(AppError::invalid(format!(
+                "psm 需在 1–13 之间: {}",
+                self.psm
+            )));
+        }
+        Ok(())
+    }
+
+    /// 用 overrides 中给出的项覆盖
+    pub fn merge(mut self, overrides: OcrOverrides) -> Self {
+        if let Some(lang) = overrides.lang {
+            self.lang = lang;
+        }
+        if let Some(psm) = overrides.psm {
+            self.psm = psm;
+        }
+        if let Some(preprocess) = overrides.preprocess {
+            self.preprocess = preprocess;
+        }
+        self
+    }
+}
+
+impl From<String> for OcrOptions {
+    fn from(lang: String) -> Self {
+        Self::new(lang)
+    }
+}
+
+/// 重新识别时要覆盖的参数，未给出的沿用原来的
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct OcrOverrides {
+    pub lang: Option<String>,
+    pub psm: Option<u32>,
+    pub preprocess: Option<bool>,
+}
+
+/// 识别出的文字；图片过大被先行缩小时附带提示
+pub struct OcrResult {
+    pub text: String,
+    pub warning: Option<String>,
+    /// 各词置信度（0–100）的平均值，没有识别到词时为空
+    pub confidence: Option<f32>,
+    /// 识别耗时，调用方据此记入使用统计
+    pub duration_ms: u64,
+}
+
+/// 识别图片中的文字，不依赖窗口，识别队列和命令行模式都调用；
+/// cancelled 返回 true 时尽快停止。不记入使用统计，由调用方决定
+pub fn recognize_text(
+    image: &DynamicImage,
+    options: &OcrOptions,
+    timings: &mut Timings,
+    cancelled: &dyn Fn() -> bool,
+) -> Result<OcrResult, AppError> {
+    let started = Instant::now();
+    let lang = options.lang.as_str();
+    let mut result = run_tesseract(image, options, timings, cancelled);
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match &mut result {
+        Ok(ocr) => {
+            ocr.duration_ms = duration_ms;
+            let chars = ocr.text.chars().count();
+            tracing::info!(lang, duration_ms, chars, "文字识别完成");
+        }
+        Err(err) => {
+            tracing::warn!(lang, duration_ms, error = err.code(), detail = %err, "文字识别失败")
+        }
+    }
+    result
+}
+
+/// 传给 tesseract 的配置变量
+const TESSERACT_VARIABLES: &[(&str, &str)] = &[
+    ("preserve_interword_spaces", "1"),
+    ("textord_heavy_nr", "1"),
+    ("textord_min_linesize", "2.5"),
+    ("textord_space_size_is_variable", "1"),
+    // 关闭词典，提升生僻字/特殊符号识别
+    ("load_system_dawg", "F"),
+    ("load_freq_dawg", "F"),
+];
+
+fn run_tesseract(
+    dyn_img: &DynamicImage,
+    ocr: &OcrOptions,
+    timings: &mut Timings,
+    cancelled: &dyn Fn() -> bool,
+) -> Result<OcrResult, AppError> {
+    tesseract::ensure()?;
+
+    let (processed, warning) = if ocr.preprocess {
+        preprocess_for_ocr(dyn_img, timings)?
+    } else {
+        gray_for_ocr(dyn_img, timings)?
+    };
+    let options = tesseract::Options {
+        lang: &ocr.lang,
+        dpi: 350,     // 中文对分辨率更敏感
+        psm: ocr.psm, // 默认 7：单行文本（适合标题类）
+        oem: 1,       // 仅 LSTM 引擎
+        variables: TESSERACT_VARIABLES,
+        // 逐词输出带置信度，文字按行拼回
+        configs: &["tsv"],
+    };
+
+    let started = Instant::now();
+    let tsv = tesseract::run(&processed, &options, cancelled);
+    timings.record("tesseract", started);
+    drop(processed);
+    let words = tesseract::parse_tsv(&tsv?);
+
+    let text = timings.time("postprocess", || {
+        postprocess_rules::apply(postprocess_ocr_text(&tesseract::tsv_text(&words)))
+    });
+    Ok(OcrResult {
+        text,
+        warning,
+        confidence: tesseract::mean_confidence(&words),
+        duration_ms: 0,
+    })
+}
+
+/// 后处理：规范空白，保留段落结构
+pub fn postprocess_ocr_text(text: &str) -> String {
+    let mut result = Vec::new();
+    let mut prev_empty = false;
+
+    for line in text.lines() {
+        // 仅压缩连续空格，保留行内容
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            // 保留单个空行作为段落分隔
+            if !prev_empty && !result.is_empty() {
+                result.push(String::new());
+            }
+            prev_empty = true;
+        } else {
+            // 压缩连续空格但保留单个空格
+            let normalized: String = trimmed
+                .chars()
+                .fold((String::new(), false), |(mut s, was_space), c| {
+                    if c.is_whitespace() {
+                        if !was_space {
+                            s.push(' ');
+                        }
+                        (s, true)
+                    } else {
+                        s.push(c);
+                        (s, false)
+                    }
+                })
+                .0;
+            result.push(normalized);
+            prev_empty = false;
+        }
+    }
+
+    // 移除末尾空行
+    while result.last().is_some_and(|s| s.is_empty()) {
+        result.pop();
+    }
+
+    result.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn postprocess_collapses_spaces_and_keeps_paragraphs() {
+        let raw = "  标题   一  \n\n\n\n第二段\t\t文字\n  \n末行\n\n  \n";
+        assert_eq!(postprocess_ocr_text(raw), "标题 一\n\n第二段 文字\n\n末行");
+        // 开头的空行不保留
+        assert_eq!(postprocess_ocr_text("\n\n  a  b"), "a b");
+        assert_eq!(postprocess_ocr_text(" \n \n"), "");
+    }
+
+    #[test]
+    fn options_default_and_validate() {
+        let options: OcrOptions = serde_json::from_str(r#"{"lang":"chi_sim"}"#).unwrap();
+        assert_eq!(options, OcrOptions::new("chi_sim"));
+        assert_eq!(options.psm, DEFAULT_PSM);
+        assert!(options.validate().is_ok());
+
+        assert!(OcrOptions::new(" ").validate().is_err());
+        for psm in [0, 14] {
+            let options = OcrOptions {
+                psm,
+                ..OcrOptions::new("eng")
+            };
+            assert!(options.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn overrides_replace_only_given_fields() {
+        let merged = OcrOptions::new("eng").merge(OcrOverrides {
+            psm: Some(6),
+            ..Default::default()
+        });
+        assert_eq!(merged.lang, "eng");
+        assert_eq!(merged.psm, 6);
+        assert!(merged.preprocess);
+
+        let merged = merged.merge(OcrOverrides {
+            lang: Some("jpn".into()),
+            psm: None,
+            preprocess: Some(false),
+        });
+        assert_eq!(
+            (merged.lang.as_str(), merged.psm, merged.preprocess),
+            ("jpn", 6, false)
+        );
+    }
+}
@@ -0,0 +1,833 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::capture_cache::CaptureCache;
+use crate::error::AppError;
+use crate::history::HistoryState;
+use crate::image_input::{self, InputFormat};
+use crate::ocr::{self, OcrOptions};
+use crate::settings::SettingsState;
+use crate::stats;
+use crate::timings::{self, Timings};
+
+/// 已结束的任务保留状态供查询的数量
+const FINISHED_CAPACITY: usize = 200;
+
+/// 未配置时的工作线程数：不超过 2，且最多占用一半的 CPU 核心
+pub fn worker_count(configured: Option<usize>) -> usize {
+    configured.unwrap_or_else(|| {
+        let cores = thread::available_parallelism().map_or(1, |n| n.get());
+        (cores / 2).clamp(1, 2)
+    })
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    /// 交互式识别，排在所有后台任务之前
+    High,
+    #[default]
+    Normal,
+    /// 定时监视等周期性任务，队列中没有其他任务时才执行
+    Low,
+}
+
+/// 待识别的图片
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OcrSource {
+    /// 缓存中的截图
+    Capture { id: u64 },
+    /// 历史记录中的截图
+    History { id: u64 },
+    /// base64 编码的 PNG
+    Png { data: String },
+}
+
+#[derive(Deserialize)]
+pub struct OcrRequest {
+    pub source: OcrSource,
+    /// 为空时使用设置中的识别语言
+    #[serde(default)]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub priority: JobPriority,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        warning: Option<String>,
+    },
+    Failed {
+        code: String,
+        message: String,
+    },
+    Cancelled,
+}
+
+/// ocr-job-finished 事件的内容
+#[derive(Clone, Serialize)]
+struct JobFinished {
+    job_id: u64,
+    status: JobStatus,
+}
+
+/// enqueue_ocr_batch 的返回值，job_ids 与请求的顺序一致
+#[derive(Clone, Debug, Serialize)]
+pub struct OcrBatch {
+    pub batch_id: u64,
+    pub job_ids: Vec<u64>,
+}
+
+/// ocr-batch-progress 事件的内容，批次中每个任务结束时发送一次
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchProgress {
+    pub batch_id: u64,
+    /// 已结束的任务数，包括失败和取消的
+    pub completed: usize,
+    pub total: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    /// 按已完成任务的平均耗时估算的剩余时间；还没有任务完成时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_ms: Option<u64>,
+    pub done: bool,
+}
+
+/// 一批识别任务的进度
+struct Batch {
+    jobs: Vec<u64>,
+    completed: usize,
+    failed: usize,
+    cancelled: usize,
+    /// 已识别任务的耗时，排队时间不计
+    busy: Duration,
+    measured: u32,
+}
+
+/// 识别任务的图片；历史记录只保存路径，开始识别时才读取文件，排队期间不占用内存
+pub enum JobInput {
+    /// 图片文件的字节，按文件头识别格式（PNG、JPEG、WebP 等）
+    Png(Vec<u8>),
+    File(PathBuf),
+    /// 已解码的图片（如缓存中的截图），不经过 PNG 编解码
+    Image(Arc<DynamicImage>),
+}
+
+impl JobInput {
+    /// 从字节解码时同时返回识别出的格式
+    fn load(
+        self,
+        timings: &mut Timings,
+    ) -> Result<(Arc<DynamicImage>, Option<InputFormat>), AppError> {
+        let data = match self {
+            JobInput::Png(data) => data,
+            JobInput::File(path) => std::fs::read(path)?,
+            JobInput::Image(image) => return Ok((image, None)),
+        };
+        let (image, format) = timings.time("decode", || image_input::decode(&data))?;
+        Ok((Arc::new(image), Some(format)))
+    }
+}
+
+/// 识别结果及各阶段耗时
+pub struct Recognized {
+    pub text: String,
+    /// 图片过大被先行缩小时的提示
+    pub warning: Option<String>,
+    /// 各词置信度的平均值
+    pub confidence: Option<f32>,
+    pub timings: Timings,
+    /// 以字节传入的图片识别出的格式
+    pub format: Option<InputFormat>,
+}
+
+type Reply = mpsc::Sender<Result<Recognized, AppError>>;
+
+/// 任务的取消条件：调用了 cancel_job，或任务所属的截图会话已被取消（Esc）或被新的截图取代
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+    session: Option<u64>,
+}
+
+impl CancelToken {
+    fn new(session: Option<u64>) -> Self {
+        Self {
+            flag: Arc::default(),
+            session,
+        }
+    }
+
+    fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+            || self.session.is_some_and(|s| !crate::session::is_current(s))
+    }
+}
+
+struct Job {
+    id: u64,
+    input: JobInput,
+    options: OcrOptions,
+    queued_at: Instant,
+    cancel: CancelToken,
+    /// 等待结果的调用方（交互式识别）
+    reply: Option<Reply>,
+    /// 计入使用统计，启动预热不计入
+    counted: bool,
+}
+
+impl Job {
+    /// 编号在加入队列时分配
+    fn new(
+        input: JobInput,
+        options: OcrOptions,
+        session: Option<u64>,
+        reply: Option<Reply>,
+    ) -> Self {
+        Self {
+            id: 0,
+            input,
+            options,
+            queued_at: Instant::now(),
+            cancel: CancelToken::new(session),
+            reply,
+            counted: true,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    high: VecDeque<Job>,
+    normal: VecDeque<Job>,
+    low: VecDeque<Job>,
+    status: HashMap<u64, JobStatus>,
+    /// 正在识别的任务，取消时据此结束 tesseract 进程
+    running: HashMap<u64, CancelToken>,
+    finished: VecDeque<u64>,
+    next_id: u64,
+    /// 未结束的批次
+    batches: HashMap<u64, Batch>,
+    /// 任务所属的批次
+    job_batch: HashMap<u64, u64>,
+    /// 期望的工作线程数
+    workers: usize,
+    /// 仍在运行的工作线程数
+    spawned: usize,
+}
+
+impl QueueState {
+    fn finish(&mut self, id: u64, status: JobStatus) {
+        self.running.remove(&id);
+        self.status.insert(id, status);
+        self.finished.push_back(id);
+        while self.finished.len() > FINISHED_CAPACITY {
+            if let Some(old) = self.finished.pop_front() {
+                self.status.remove(&old);
+            }
+        }
+    }
+
+    /// 记下批次中一个任务的结束，返回要发送的进度；duration 为识别耗时，排队时取消的为 None
+    fn batch_finished(
+        &mut self,
+        id: u64,
+        status: &JobStatus,
+        duration: Option<Duration>,
+    ) -> Option<BatchProgress> {
+        let batch_id = self.job_batch.remove(&id)?;
+        let batch = self.batches.get_mut(&batch_id)?;
+        batch.completed += 1;
+        match status {
+            JobStatus::Failed { .. } => batch.failed += 1,
+            JobStatus::Cancelled => batch.cancelled += 1,
+            _ => {}
+        }
+        if let Some(duration) = duration {
+            batch.busy += duration;
+            batch.measured += 1;
+        }
+        let total = batch.jobs.len();
+        let remaining = total - batch.completed;
+        // 剩余任务由多个工作线程同时识别
+        let eta_ms = (batch.measured > 0).then(|| {
+            let mean = batch.busy / batch.measured;
+            let rounds = remaining.div_ceil(self.workers.clamp(1, remaining.max(1)));
+            (mean * rounds as u32).as_millis() as u64
+        });
+        let progress = BatchProgress {
+            batch_id,
+            completed: batch.completed,
+            total,
+            failed: batch.failed,
+            cancelled: batch.cancelled,
+            eta_ms,
+            done: remaining == 0,
+        };
+        if progress.done {
+            self.batches.remove(&batch_id);
+        }
+        Some(progress)
+    }
+
+    /// 按优先级取出下一个任务并标记为识别中
+    fn next_job(&mut self) -> Option<Job> {
+        let job = self
+            .high
+            .pop_front()
+            .or_else(|| self.normal.pop_front())
+            .or_else(|| self.low.pop_front())?;
+        self.status.insert(job.id, JobStatus::Running);
+        self.running.insert(job.id, job.cancel.clone());
+        Some(job)
+    }
+
+    /// 所有未结束批次合计的（已结束，总数），没有批次时为 None
+    fn batch_totals(&self) -> Option<(usize, usize)> {
+        (!self.batches.is_empty()).then(|| {
+            self.batches
+                .values()
+                .fold((0, 0), |(completed, total), batch| {
+                    (completed + batch.completed, total + batch.jobs.len())
+                })
+        })
+    }
+}
+
+type Shared = Arc<(Mutex<QueueState>, Condvar)>;
+
+fn lock(shared: &Shared) -> MutexGuard<'_, QueueState> {
+    shared.0.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 后台文字识别队列：固定数量的工作线程按优先级依次识别，不依赖任何窗口
+pub struct OcrQueue {
+    app: AppHandle,
+    shared: Shared,
+}
+
+impl OcrQueue {
+    pub fn new(app: &AppHandle, workers: usize) -> Self {
+        let queue = Self {
+            app: app.clone(),
+            shared: Shared::default(),
+        };
+        queue.set_workers(workers);
+        queue
+    }
+
+    /// 调整工作线程数：多出的线程处理完当前任务后退出
+    pub fn set_workers(&self, workers: usize) {
+        let mut state = lock(&self.shared);
+        state.workers = workers.max(1);
+        while state.spawned < state.workers {
+            let index = state.spawned;
+            let app = self.app.clone();
+            let shared = self.shared.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("ocr-worker-{index}"))
+                .spawn(move || worker(app, shared, index));
+            if let Err(err) = spawned {
+                tracing::warn!(error = %err, "启动识别线程失败");
+                break;
+            }
+            state.spawned += 1;
+        }
+        self.shared.1.notify_all();
+    }
+
+    fn enqueue(&self, job: Job, priority: JobPriority) -> u64 {
+        let mut state = lock(&self.shared);
+        let id = Self::push(&mut state, job, priority, None);
+        self.shared.1.notify_one();
+        id
+    }
+
+    fn push(
+        state: &mut QueueState,
+        mut job: Job,
+        priority: JobPriority,
+        batch: Option<u64>,
+    ) -> u64 {
+        state.next_id += 1;
+        let id = state.next_id;
+        job.id = id;
+        match priority {
+            JobPriority::High => state.high.push_back(job),
+            JobPriority::Normal => state.normal.push_back(job),
+            JobPriority::Low => state.low.push_back(job),
+        }
+        state.status.insert(id, JobStatus::Queued);
+        if let Some(batch) = batch {
+            state.job_batch.insert(id, batch);
+        }
+        tracing::debug!(job_id = id, ?priority, batch, "识别任务已加入队列");
+        id
+    }
+
+    /// 一次加入一批任务，进度通过 ocr-batch-progress 事件发送；返回批次编号与各任务的结果通道
+    pub fn submit_batch(
+        &self,
+        items: Vec<(JobInput, OcrOptions)>,
+        priority: JobPriority,
+    ) -> (OcrBatch, Vec<mpsc::Receiver<Result<Recognized, AppError>>>) {
+        let mut state = lock(&self.shared);
+        state.next_id += 1;
+        let batch_id = state.next_id;
+        let mut job_ids = Vec::with_capacity(items.len());
+        let mut receivers = Vec::with_capacity(items.len());
+        for (input, options) in items {
+            let (tx, rx) = mpsc::channel();
+            let id = Self::push(
+                &mut state,
+                Job::new(input, options, None, Some(tx)),
+                priority,
+                Some(batch_id),
+            );
+            job_ids.push(id);
+            receivers.push(rx);
+        }
+        if !job_ids.is_empty() {
+            state.batches.insert(
+                batch_id,
+                Batch {
+                    jobs: job_ids.clone(),
+                    completed: 0,
+                    failed: 0,
+                    cancelled: 0,
+                    busy: Duration::ZERO,
+                    measured: 0,
+                },
+            );
+        }
+        let totals = state.batch_totals();
+        drop(state);
+        self.shared.1.notify_all();
+        tracing::info!(batch_id, jobs = job_ids.len(), "识别批次已加入队列");
+        crate::tray::set_ocr_progress(&self.app, totals);
+        (OcrBatch { batch_id, job_ids }, receivers)
+    }
+
+    /// 加入队列，结果从返回的通道取得；options 可以只给识别语言。
+    ///
+    /// 指定截图会话时，会话被取消或开始新的截图都会取消任务
+    pub fn submit(
+        &self,
+        input: JobInput,
+        options: impl Into<OcrOptions>,
+        priority: JobPriority,
+        session: Option<u64>,
+    ) -> (u64, mpsc::Receiver<Result<Recognized, AppError>>) {
+        let (tx, rx) = mpsc::channel();
+        let id = self.enqueue(Job::new(input, options.into(), session, Some(tx)), priority);
+        (id, rx)
+    }
+
+    /// 以最低优先级加入不计入使用统计的任务，供启动预热和后台监视使用
+    pub fn submit_uncounted(
+        &self,
+        input: JobInput,
+        options: OcrOptions,
+    ) -> mpsc::Receiver<Result<Recognized, AppError>> {
+        let (tx, rx) = mpsc::channel();
+        let mut job = Job::new(input, options, None, Some(tx));
+        job.counted = false;
+        self.enqueue(job, JobPriority::Low);
+        rx
+    }
+
+    /// 加入队列并等待结果，在阻塞线程中调用
+    pub fn recognize(
+        &self,
+        input: JobInput,
+        options: impl Into<OcrOptions>,
+        priority: JobPriority,
+        session: Option<u64>,
+    ) -> Result<Recognized, AppError> {
+        let (_, rx) = self.submit(input, options, priority, session);
+        wait_result(&rx)
+    }
+
+    /// 同 recognize，但以最低优先级执行且不计入使用统计，供文字监视的定时识别使用
+    pub fn recognize_uncounted(
+        &self,
+        input: JobInput,
+        options: impl Into<OcrOptions>,
+    ) -> Result<Recognized, AppError> {
+        wait_result(&self.submit_uncounted(input, options.into()))
+    }
+
+    pub fn status(&self, id: u64) -> Option<JobStatus> {
+        lock(&self.shared).status.get(&id).cloned()
+    }
+
+    /// 排队中的任务直接移除；正在识别的任务结束 tesseract 进程
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut state = lock(&self.shared);
+        match state.status.get(&id) {
+            Some(JobStatus::Queued) => {
+                state.high.retain(|job| job.id != id);
+                state.normal.retain(|job| job.id != id);
+                state.low.retain(|job| job.id != id);
+                state.finish(id, JobStatus::Cancelled);
+                let progress = state.batch_finished(id, &JobStatus::Cancelled, None);
+                let totals = state.batch_totals();
+                drop(state);
+                emit_finished(&self.app, id, JobStatus::Cancelled);
+                if let Some(progress) = progress {
+                    emit_progress(&self.app, progress, totals);
+                }
+                true
+            }
+            Some(JobStatus::Running) => {
+                if let Some(token) = state.running.get(&id) {
+                    token.cancel();
+                }
+                state.status.insert(id, JobStatus::Cancelled);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 取消批次中尚未结束的任务，返回取消的数量；批次不存在或已结束时返回 None
+    pub fn cancel_batch(&self, batch_id: u64) -> Option<usize> {
+        let jobs = lock(&self.shared).batches.get(&batch_id)?.jobs.clone();
+        let cancelled = jobs.into_iter().filter(|&id| self.cancel(id)).count();
+        tracing::info!(batch_id, cancelled, "已取消识别批次");
+        Some(cancelled)
+    }
+}
+
+/// 等待 submit 提交的任务结束
+pub fn wait_result(
+    rx: &mpsc::Receiver<Result<Recognized, AppError>>,
+) -> Result<Recognized, AppError> {
+    // 排队时被取消的任务直接丢弃，发送端随之关闭
+    rx.recv().unwrap_or(Err(AppError::Cancelled))
+}
+
+fn emit_finished(app: &AppHandle, job_id: u64, status: JobStatus) {
+    let _ = app.emit("ocr-job-finished", JobFinished { job_id, status });
+}
+
+fn emit_progress(app: &AppHandle, progress: BatchProgress, totals: Option<(usize, usize)>) {
+    if progress.done {
+        tracing::info!(
+            batch_id = progress.batch_id,
+            total = progress.total,
+            failed = progress.failed,
+            cancelled = progress.cancelled,
+            "识别批次已结束"
+        );
+    }
+    let _ = app.emit("ocr-batch-progress", progress);
+    crate::tray::set_ocr_progress(app, totals);
+}
+
+fn worker(app: AppHandle, shared: Shared, index: usize) {
+    loop {
+        let job = {
+            let mut state = lock(&shared);
+            loop {
+                if index >= state.workers {
+                    state.spawned -= 1;
+                    // 可能占用了分给其他线程的唤醒
+                    shared.1.notify_one();
+                    return;
+                }
+                if let Some(job) = state.next_job() {
+                    break job;
+                }
+                state = shared.1.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        let Job {
+            id,
+            input,
+            options,
+            queued_at,
+            cancel,
+            reply,
+            counted,
+        } = job;
+        let started = Instant::now();
+        let mut timings = Timings::default();
+        timings.record("queue_wait", queued_at);
+        // 排队期间所属的截图已被取消时不再识别；图片数据在识别完成后立即释放
+        let result = if cancel.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            input.load(&mut timings).and_then(|(image, format)| {
+                ocr::recognize_text(&image, &options, &mut timings, &|| cancel.is_cancelled())
+                    .inspect(|ocr| {
+                        if counted {
+                            stats::record_ocr(ocr.text.chars().count(), ocr.duration_ms);
+                        }
+                    })
+                    .map(|ocr| (ocr, format))
+            })
+        };
+        timings::finish(timings::Kind::Ocr, &timings);
+        let result = result.map(|(ocr, format)| Recognized {
+            text: ocr.text,
+            warning: ocr.warning,
+            confidence: ocr.confidence,
+            timings,
+            format,
+        });
+
+        let mut state = lock(&shared);
+        let result = if cancel.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            result
+        };
+        let status = match &result {
+            Ok(recognized) => JobStatus::Done {
+                text: recognized.text.clone(),
+                warning: recognized.warning.clone(),
+            },
+            Err(AppError::Cancelled) => JobStatus::Cancelled,
+            Err(err) => JobStatus::Failed {
+                code: err.code().to_string(),
+                message: err.message(),
+            },
+        };
+        state.finish(id, status.clone());
+        let progress = state.batch_finished(id, &status, Some(started.elapsed()));
+        let totals = state.batch_totals();
+        drop(state);
+        if let Some(reply) = reply {
+            let _ = reply.send(result);
+        }
+        emit_finished(&app, id, status);
+        if let Some(progress) = progress {
+            emit_progress(&app, progress, totals);
+        }
+    }
+}
+
+/// 加入后台识别队列，返回任务编号；完成后发送 ocr-job-finished 事件
+#[tauri::command]
+pub fn enqueue_ocr(
+    app: AppHandle,
+    queue: State<'_, OcrQueue>,
+    request: OcrRequest,
+) -> Result<u64, AppError> {
+    let (input, options) = request_job(&app, request.source, request.lang)?;
+    Ok(queue.enqueue(Job::new(input, options, None, None), request.priority))
+}
+
+fn request_job(
+    app: &AppHandle,
+    source: OcrSource,
+    lang: Option<String>,
+) -> Result<(JobInput, OcrOptions), AppError> {
+    let input = match source {
+        OcrSource::Capture { id } => JobInput::Image(app.state::<CaptureCache>().image(id)?),
+        OcrSource::History { id } => app
+            .state::<HistoryState>()
+            .get(id)
+            .map(|entry| JobInput::File(entry.image_path.into()))
+            .ok_or_else(|| AppError::invalid(format!("历史记录不存在: {id}")))?,
+        OcrSource::Png { data } => JobInput::Png(STANDARD.decode(data).map_err(AppError::invalid)?),
+    };
+    let lang = lang.unwrap_or_else(|| app.state::<SettingsState>().get().ocr_lang);
+    Ok((input, lang.into()))
+}
+
+/// 一次加入多个识别任务，返回批次编号与各任务编号；每个任务结束时发送 ocr-job-finished
+/// 与带总进度和预计剩余时间的 ocr-batch-progress 事件。各任务的优先级取第一个请求的
+#[tauri::command]
+pub fn enqueue_ocr_batch(
+    app: AppHandle,
+    queue: State<'_, OcrQueue>,
+    requests: Vec<OcrRequest>,
+) -> Result<OcrBatch, AppError> {
+    if requests.is_empty() {
+        return Err(AppError::invalid("没有要识别的图片"));
+    }
+    let priority = requests[0].priority;
+    let items = requests
+        .into_iter()
+        .map(|request| request_job(&app, request.source, request.lang))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (batch, _) = queue.submit_batch(items, priority);
+    Ok(batch)
+}
+
+/// 取消批次中排队的任务并结束正在识别的任务，返回取消的数量
+#[tauri::command]
+pub fn cancel_batch(queue: State<'_, OcrQueue>, batch_id: u64) -> Result<usize, AppError> {
+    queue
+        .cancel_batch(batch_id)
+        .ok_or_else(|| AppError::invalid(format!("识别批次不存在或已结束: {batch_id}")))
+}
+
+#[tauri::command]
+pub fn get_job_status(queue: State<'_, OcrQueue>, job_id: u64) -> Result<JobStatus, AppError> {
+    queue
+        .status(job_id)
+        .ok_or_else(|| AppError::invalid(format!("识别任务不存在: {job_id}")))
+}
+
+/// 返回任务是否被取消（已结束的任务返回 false）
+#[tauri::command]
+pub fn cancel_job(queue: State<'_, OcrQueue>, job_id: u64) -> bool {
+    queue.cancel(job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn job() -> Job {
+        let image = Arc::new(DynamicImage::new_rgba8(1, 1));
+        Job::new(JobInput::Image(image), OcrOptions::new("eng"), None, None)
+    }
+
+    /// 一批 3 个任务、2 个工作线程的队列状态
+    fn batch_state() -> (QueueState, Vec<u64>) {
+        let mut state = QueueState {
+            workers: 2,
+            ..Default::default()
+        };
+        let jobs: Vec<u64> = (0..3)
+            .map(|_| OcrQueue::push(&mut state, job(), JobPriority::Normal, Some(100)))
+            .collect();
+        state.batches.insert(
+            100,
+            Batch {
+                jobs: jobs.clone(),
+                completed: 0,
+                failed: 0,
+                cancelled: 0,
+                busy: Duration::ZERO,
+                measured: 0,
+            },
+        );
+        (state, jobs)
+    }
+
+    #[test]
+    fn worker_count_defaults_to_at_most_two() {
+        assert_eq!(worker_count(Some(4)), 4);
+        assert!((1..=2).contains(&worker_count(None)));
+    }
+
+    #[test]
+    fn jobs_run_by_priority_then_in_order() {
+        let mut state = QueueState::default();
+        let low = OcrQueue::push(&mut state, job(), JobPriority::Low, None);
+        let normal = OcrQueue::push(&mut state, job(), JobPriority::Normal, None);
+        let high = OcrQueue::push(&mut state, job(), JobPriority::High, None);
+        let second_normal = OcrQueue::push(&mut state, job(), JobPriority::Normal, None);
+        assert!(matches!(state.status[&low], JobStatus::Queued));
+
+        let order: Vec<u64> = std::iter::from_fn(|| state.next_job().map(|job| job.id)).collect();
+        assert_eq!(order, [high, normal, second_normal, low]);
+        assert!(matches!(state.status[&low], JobStatus::Running));
+        assert_eq!(state.running.len(), 4);
+    }
+
+    #[test]
+    fn only_recent_finished_jobs_keep_their_status() {
+        let mut state = QueueState::default();
+        for id in 1..=FINISHED_CAPACITY as u64 + 1 {
+            state.finish(id, JobStatus::Cancelled);
+        }
+        assert!(!state.status.contains_key(&1));
+        assert!(state.status.contains_key(&2));
+        assert_eq!(state.finished.len(), FINISHED_CAPACITY);
+    }
+
+    #[test]
+    fn batch_progress_estimates_the_remaining_time() {
+        let (mut state, jobs) = batch_state();
+        assert_eq!(state.batch_totals(), Some((0, 3)));
+
+        let done = JobStatus::Done {
+            text: "a".into(),
+            warning: None,
+        };
+        let first = state
+            .batch_finished(jobs[0], &done, Some(Duration::from_millis(100)))
+            .unwrap();
+        // 剩余 2 个任务由 2 个线程同时识别
+        assert_eq!(
+            (first.completed, first.eta_ms, first.done),
+            (1, Some(100), false)
+        );
+
+        let failed = JobStatus::Failed {
+            code: "ocr_failed".into(),
+            message: String::new(),
+        };
+        let second = state
+            .batch_finished(jobs[1], &failed, Some(Duration::from_millis(300)))
+            .unwrap();
+        assert_eq!((second.failed, second.eta_ms), (1, Some(200)));
+        assert_eq!(state.batch_totals(), Some((2, 3)));
+
+        // 排队时取消的任务不计入耗时
+        let last = state
+            .batch_finished(jobs[2], &JobStatus::Cancelled, None)
+            .unwrap();
+        assert_eq!((last.cancelled, last.eta_ms, last.done), (1, Some(0), true));
+        assert_eq!(state.batch_totals(), None);
+        assert!(state.batch_finished(jobs[2], &done, None).is_none());
+    }
+
+    #[test]
+    fn inputs_are_decoded_with_their_format() {
+        let mut timings = Timings::default();
+        let image = Arc::new(DynamicImage::new_rgba8(3, 2));
+        let (loaded, format) = JobInput::Image(image.clone()).load(&mut timings).unwrap();
+        assert!(Arc::ptr_eq(&loaded, &image) && format.is_none());
+
+        let png = crate::frame::encode_png(&image).unwrap();
+        let (loaded, format) = JobInput::Png(png).load(&mut timings).unwrap();
+        assert_eq!((loaded.width(), format), (3, Some(InputFormat::Png)));
+        assert_eq!(timings.stages.len(), 1);
+        assert!(JobInput::Png(b"not an image".to_vec())
+            .load(&mut timings)
+            .is_err());
+    }
+
+    #[test]
+    fn dropped_jobs_are_reported_as_cancelled() {
+        let (tx, rx) = mpsc::channel();
+        drop(tx);
+        assert!(matches!(wait_result(&rx), Err(AppError::Cancelled)));
+        let done = JobStatus::Done {
+            text: "hi".into(),
+            warning: None,
+        };
+        assert_eq!(
+            serde_json::to_value(done).unwrap(),
+            json!({ "state": "done", "text": "hi" })
+        );
+    }
+}
@@ -0,0 +1,482 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+use xcap::Monitor;
+
+use crate::capture::{self, CaptureBackend, CaptureObserver};
+use crate::capture_cache::{CaptureCache, CaptureHandle};
+use crate::capture_id::{self, CaptureEvents, CaptureId};
+use crate::error::AppError;
+use crate::frame::Frame;
+use crate::night_light::Compensation;
+use crate::settings::SettingsState;
+use crate::timings::Timings;
+
+/// 额外遮罩窗口的标签前缀，后接显示器 id
+pub const OVERLAY_PREFIX: &str = "overlay-";
+
+/// 遮罩窗口对应的截图；截图被取走后仍保留显示器信息，用于换算坐标
+struct PendingCapture {
+    capture: Option<CaptureHandle>,
+    monitor: MonitorInfo,
+    image_width: u32,
+    image_height: u32,
+}
+
+/// 各窗口的截图（窗口标签 → 截图）
+#[derive(Default)]
+pub struct OverlayCaptures(Mutex<HashMap<String, PendingCapture>>);
+
+#[derive(Clone, Serialize)]
+pub struct MonitorInfo {
+    pub id: u32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub primary: bool,
+    /// 按缩放比例换算的逻辑尺寸
+    pub logical_width: f64,
+    pub logical_height: f64,
+    /// 镜像显示同一区域的其他输出名称（X11）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct OverlayWindow {
+    pub label: String,
+    pub monitor: MonitorInfo,
+    /// 截图的实际像素尺寸，分数缩放下可能与显示器尺寸不同
+    pub image_width: u32,
+    pub image_height: u32,
+}
+
+/// 窗口 CSS 像素到截图像素的换算：image = css * scale + offset
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct OverlayTransform {
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+/// 遮罩窗口实际的内容区域（物理像素）
+#[derive(Serialize)]
+pub struct OverlayGeometry {
+    pub label: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    /// 窗口不是遮罩（没有对应截图）时为空，前端按图片与窗口尺寸之比换算
+    pub transform: Option<OverlayTransform>,
+}
+
+/// 计算窗口到截图的坐标换算。
+///
+/// 不能假定 CSS 像素与截图像素 1:1：GNOME 125%、150% 分数缩放（X11 / XWayland）下，
+/// 窗口内容区域的位置与显示器原点有几个像素的偏差，截图尺寸也可能与显示器报告的尺寸不同
+pub fn overlay_transform(
+    monitor: &MonitorInfo,
+    image_size: (u32, u32),
+    window_position: (i32, i32),
+    window_scale: f64,
+) -> OverlayTransform {
+    let image_scale_x = image_size.0 as f64 / monitor.width.max(1) as f64;
+    let image_scale_y = image_size.1 as f64 / monitor.height.max(1) as f64;
+    OverlayTransform {
+        scale_x: window_scale * image_scale_x,
+        scale_y: window_scale * image_scale_y,
+        offset_x: (window_position.0 - monitor.x) as f64 * image_scale_x,
+        offset_y: (window_position.1 - monitor.y) as f64 * image_scale_y,
+    }
+}
+
+enum OverlayTarget {
+    Monitor(u32),
+    Cursor,
+    All,
+}
+
+impl OverlayTarget {
+    fn parse(target: &str) -> Result<Self, String> {
+        match target {
+            "cursor" => Ok(OverlayTarget::Cursor),
+            "all" => Ok(OverlayTarget::All),
+            id => id
+                .parse()
+                .map(OverlayTarget::Monitor)
+                .map_err(|_| format!("无效的显示器: {target}")),
+        }
+    }
+}
+
+fn monitor_info(monitor: &Monitor) -> Result<MonitorInfo, String> {
+    let err = |e: xcap::XCapError| e.to_string();
+    let width = monitor.width().map_err(err)?;
+    let height = monitor.height().map_err(err)?;
+    let scale_factor = monitor.scale_factor().map_err(err)?;
+    Ok(MonitorInfo {
+        id: monitor.id().map_err(err)?,
+        name: monitor.name().map_err(err)?,
+        x: monitor.x().map_err(err)?,
+        y: monitor.y().map_err(err)?,
+        width,
+        height,
+        scale_factor,
+        primary: monitor.is_primary().unwrap_or(false),
+        logical_width: width as f64 / scale_factor.max(0.1) as f64,
+        logical_height: height as f64 / scale_factor.max(0.1) as f64,
+        mirrors: Vec::new(),
+    })
+}
+
+/// 按目标挑出要显示遮罩的显示器，主窗口使用第一个
+fn select_monitors(app: &AppHandle, target: &OverlayTarget) -> Result<Vec<Monitor>, String> {
+    let mut monitors = Monitor::all().map_err(|e| e.to_string())?;
+    match target {
+        OverlayTarget::All => {
+            // 主显示器放在最前，由主窗口负责
+            monitors.sort_by_key(|m| !m.is_primary().unwrap_or(false));
+            Ok(monitors)
+        }
+        OverlayTarget::Monitor(id) => monitors
+            .into_iter()
+            .find(|m| m.id().ok() == Some(*id))
+            .map(|m| vec![m])
+            .ok_or_else(|| format!("未找到显示器: {id}")),
+        OverlayTarget::Cursor => {
+            let pos = app.cursor_position().map_err(|e| e.to_string())?;
+            Ok(vec![monitor_at(pos.x as i32, pos.y as i32)?])
+        }
+    }
+}
+
+fn capture_monitor(
+    monitor: &Monitor,
+    events: &CaptureEvents,
+) -> Result<(Frame, Option<Compensation>), AppError> {
+    let started = Instant::now();
+    let result = monitor
+        .capture_image()
+        .map_err(|e| CaptureBackend::Xcap.failed(e));
+    let duration_ms = started.elapsed().as_millis() as u64;
+    events.attempt(CaptureBackend::Xcap, duration_ms, result.as_ref().err());
+    crate::night_light::compensate(
+        Frame::Image(result?.into()),
+        &capture::capture_config().night_light,
+    )
+}
+
+/// 把窗口移到显示器左上角后全屏，全屏会落在窗口当前所在的显示器上
+fn fullscreen_on(
+    app: &AppHandle,
+    window: &WebviewWindow,
+    info: &MonitorInfo,
+) -> Result<(), String> {
+    crate::geometry::remember(app, window);
+    window.set_fullscreen(false).map_err(|e| e.to_string())?;
+    window
+        .set_position(PhysicalPosition::new(info.x, info.y))
+        .map_err(|e| e.to_string())?;
+    window.set_decorations(false).map_err(|e| e.to_string())?;
+    window.set_fullscreen(true).map_err(|e| e.to_string())?;
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())
+}
+
+fn create_overlay_window(app: &AppHandle, label: &str) -> Result<WebviewWindow, String> {
+    if let Some(existing) = app.get_webview_window(label) {
+        return Ok(existing);
+    }
+    WebviewWindowBuilder::new(app, label, WebviewUrl::App("index.html".into()))
+        .title("PrinSp")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// 关闭所有额外的遮罩窗口（except 为调用者自身时保留）
+pub fn close_overlays(app: &AppHandle, except: Option<&str>) {
+    for (label, window) in app.webview_windows() {
+        if label.starts_with(OVERLAY_PREFIX) && Some(label.as_str()) != except {
+            let _ = window.close();
+        }
+    }
+    if let Ok(mut captures) = app.state::<OverlayCaptures>().0.lock() {
+        captures.retain(|label, _| Some(label.as_str()) == except);
+    }
+}
+
+/// 丢弃尚未取走的截图
+pub fn clear_captures(app: &AppHandle) {
+    if let Ok(mut captures) = app.state::<OverlayCaptures>().0.lock() {
+        captures.clear();
+    }
+}
+
+/// 是否还有除 except 之外的遮罩窗口
+pub fn has_overlays(app: &AppHandle, except: Option<&str>) -> bool {
+    app.webview_windows()
+        .keys()
+        .any(|label| label.starts_with(OVERLAY_PREFIX) && Some(label.as_str()) != except)
+}
+
+pub fn is_overlay(window: &WebviewWindow) -> bool {
+    window.label().starts_with(OVERLAY_PREFIX)
+}
+
+/// 光标等坐标所在的显示器；X11 下按 XRandR 的布局判断，避免 xcap 的顺序与实际排列不一致时选错
+pub fn monitor_at(x: i32, y: i32) -> Result<Monitor, String> {
+    let id = list_monitors()?
+        .into_iter()
+        .find(|m| x >= m.x && y >= m.y && x < m.x + m.width as i32 && y < m.y + m.height as i32)
+        .map(|m| m.id);
+    match id {
+        Some(id) => Monitor::all()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|m| m.id().ok() == Some(id))
+            .ok_or_else(|| format!("未找到显示器: {id}")),
+        None => Monitor::from_point(x, y).map_err(|e| e.to_string()),
+    }
+}
+
+/// 按桌面排列返回显示器；X11 下以 XRandR 的布局为准
+#[tauri::command]
+pub fn list_monitors() -> Result<Vec<MonitorInfo>, String> {
+    let monitors = Monitor::all()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(monitor_info)
+        .collect::<Result<Vec<_>, _>>()?;
+    #[cfg(target_os = "linux")]
+    let monitors = crate::xrandr::reconcile(monitors);
+    Ok(monitors)
+}
+
+/// 在指定显示器（id / "cursor" / "all"）上显示选区遮罩；每个窗口通过 take_overlay_capture 取自己显示器的截图。
+/// 各显示器的截图共用同一个截图编号，capture_id 为 start-capture 事件中的编号
+#[tauri::command]
+pub fn show_overlay(
+    app: AppHandle,
+    window: WebviewWindow,
+    target: String,
+    capture_id: Option<CaptureId>,
+) -> Result<Vec<OverlayWindow>, String> {
+    crate::commands::ensure_capture_window(&window)?;
+    let target = OverlayTarget::parse(&target)?;
+    crate::session::begin();
+    let _busy = crate::tray::busy(&app);
+    let monitors = select_monitors(&app, &target)?;
+    let events = CaptureEvents::start(&app, capture_id::take(capture_id));
+
+    // 快捷键或托盘触发时窗口本就隐藏，直接截图；窗口或遮罩可见时先隐藏，避免截进画面
+    let instant = app.state::<SettingsState>().get().instant_overlay;
+    let hide_first = !instant || window.is_visible().unwrap_or(true) || has_overlays(&app, None);
+    close_overlays(&app, None);
+    let mut timings = Timings::default();
+    if hide_first {
+        window.hide().map_err(|e| e.to_string())?;
+        // 等待窗口完全隐藏
+        timings.time("hide_wait", || thread::sleep(Duration::from_millis(80)));
+    }
+
+    let mut shots = Vec::new();
+    for monitor in &monitors {
+        let info = monitor_info(monitor)?;
+        let frame = timings.time(format!("capture.monitor{}", info.id), || {
+            capture_monitor(monitor, &events)
+        });
+        match frame {
+            Ok((frame, night_light)) => shots.push((info, frame, night_light)),
+            Err(err) => {
+                events.finish(Err(&err));
+                return Err(err.into());
+            }
+        }
+    }
+    crate::stats::record_capture(CaptureBackend::Xcap.name());
+
+    let cache = app.state::<CaptureCache>();
+    cache.clear();
+    let mut windows = Vec::new();
+    for (i, (info, frame, night_light)) in shots.into_iter().enumerate() {
+        let capture = timings.time("png_encode", || {
+            cache.insert(frame, events.id().clone(), night_light)
+        })?;
+        crate::pre_encode::schedule(&app, capture.id);
+        if i == 0 {
+            events.finish(Ok(Some(capture.id)));
+        }
+        let (image_width, image_height) = (capture.width, capture.height);
+        let overlay = if i == 0 {
+            crate::recovery::record_capture(&app, &capture);
+            window.clone()
+        } else {
+            create_overlay_window(&app, &format!("{OVERLAY_PREFIX}{}", info.id))?
+        };
+        let label = overlay.label().to_string();
+        app.state::<OverlayCaptures>()
+            .0
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(
+                label.clone(),
+                PendingCapture {
+                    capture: Some(capture),
+                    monitor: info.clone(),
+                    image_width,
+                    image_height,
+                },
+            );
+        fullscreen_on(&app, &overlay, &info)?;
+        windows.push(OverlayWindow {
+            label,
+            monitor: info,
+            image_width,
+            image_height,
+        });
+    }
+    crate::timings::finish(crate::timings::Kind::Capture, &timings);
+    if let Some(since) = crate::session::active_since() {
+        tracing::info!(
+            latency_ms = since.elapsed().as_millis() as u64,
+            instant = !hide_first,
+            "遮罩已显示"
+        );
+    }
+    Ok(windows)
+}
+
+/// 取走当前窗口对应显示器的截图，图片通过 prinsp:// 协议加载
+#[tauri::command]
+pub fn take_overlay_capture(
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<CaptureHandle, String> {
+    app.state::<OverlayCaptures>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get_mut(window.label())
+        .and_then(|pending| pending.capture.take())
+        .ok_or_else(|| "没有待显示的截图".to_string())
+}
+
+/// 返回遮罩窗口全屏后的实际位置、大小与缩放，以及到截图像素的换算
+#[tauri::command]
+pub fn get_overlay_geometry(
+    app: AppHandle,
+    window_label: String,
+) -> Result<OverlayGeometry, String> {
+    let window = app
+        .get_webview_window(&window_label)
+        .ok_or_else(|| format!("窗口不存在: {window_label}"))?;
+    let position = window.inner_position().map_err(|e| e.to_string())?;
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let scale_factor = window.scale_factor().map_err(|e| e.to_string())?;
+    let transform = app
+        .state::<OverlayCaptures>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&window_label)
+        .map(|capture| {
+            overlay_transform(
+                &capture.monitor,
+                (capture.image_width, capture.image_height),
+                (position.x, position.y),
+                scale_factor,
+            )
+        });
+    Ok(OverlayGeometry {
+        label: window_label,
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        scale_factor,
+        transform,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(x: i32, y: i32, width: u32, height: u32, scale_factor: f32) -> MonitorInfo {
+        MonitorInfo {
+            id: 1,
+            name: "DP-1".into(),
+            x,
+            y,
+            width,
+            height,
+            scale_factor,
+            primary: true,
+            logical_width: width as f64 / scale_factor as f64,
+            logical_height: height as f64 / scale_factor as f64,
+            mirrors: Vec::new(),
+        }
+    }
+
+    /// 把窗口内的 CSS 坐标换算为截图像素
+    fn apply(t: OverlayTransform, css: (f64, f64)) -> (f64, f64) {
+        (
+            css.0 * t.scale_x + t.offset_x,
+            css.1 * t.scale_y + t.offset_y,
+        )
+    }
+
+    #[test]
+    fn identity_without_scaling() {
+        let t = overlay_transform(&monitor(0, 0, 1920, 1080, 1.0), (1920, 1080), (0, 0), 1.0);
+        assert_eq!(
+            t,
+            OverlayTransform {
+                scale_x: 1.0,
+                scale_y: 1.0,
+                offset_x: 0.0,
+                offset_y: 0.0,
+            }
+        );
+        assert_eq!(apply(t, (100.0, 50.0)), (100.0, 50.0));
+    }
+
+    #[test]
+    fn xwayland_125_with_window_offset() {
+        // 第二块屏，窗口内容区域比显示器原点偏了几个像素
+        let t = overlay_transform(
+            &monitor(1920, 0, 2560, 1440, 1.25),
+            (2560, 1440),
+            (1922, 3),
+            1.25,
+        );
+        assert_eq!((t.scale_x, t.scale_y), (1.25, 1.25));
+        assert_eq!((t.offset_x, t.offset_y), (2.0, 3.0));
+        assert_eq!(apply(t, (0.0, 0.0)), (2.0, 3.0));
+        assert_eq!(apply(t, (2047.2, 1149.6)), (2561.0, 1440.0));
+    }
+
+    #[test]
+    fn xwayland_150_with_image_larger_than_monitor() {
+        // XWayland 报告缩放后的尺寸，截图却是物理像素
+        let t = overlay_transform(&monitor(0, 0, 1280, 720, 1.0), (1920, 1080), (2, -1), 1.0);
+        assert_eq!((t.scale_x, t.scale_y), (1.5, 1.5));
+        assert_eq!((t.offset_x, t.offset_y), (3.0, -1.5));
+        assert_eq!(apply(t, (100.0, 100.0)), (153.0, 148.5));
+    }
+}
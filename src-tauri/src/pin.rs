@@ -0,0 +1,826 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::ipc::Response;
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, Window, WindowEvent,
+};
+use uuid::Uuid;
+
+use crate::capture_cache::{CaptureCache, CaptureRef};
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::run_blocking;
+use crate::settings::SettingsState;
+
+/// 钉图窗口的标签前缀，后接钉图编号
+pub const PIN_PREFIX: &str = "pin-";
+const PINS_FILE: &str = "pins.json";
+/// 钉图与选区、网格中钉图之间的间距
+const GAP: i32 = 8;
+/// 层叠时相邻钉图的错开距离
+const CASCADE_STEP: i32 = 32;
+/// 错开位置的尝试次数上限
+const MAX_CASCADE: usize = 64;
+/// 重新排列时与显示器边缘的距离
+const MARGIN: i32 = 16;
+/// 拖动窗口时延迟写入位置，避免反复写文件
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+/// 取不到显示器信息时不限制位置
+const UNBOUNDED: Rect = Rect {
+    x: 0,
+    y: 0,
+    width: i32::MAX as u32,
+    height: i32::MAX as u32,
+};
+
+/// 桌面上的矩形，物理像素
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    fn right(&self) -> i32 {
+        self.x.saturating_add(self.width as i32)
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y.saturating_add(self.height as i32)
+    }
+
+    fn center(&self) -> (i32, i32) {
+        (
+            self.x.saturating_add((self.width / 2) as i32),
+            self.y.saturating_add((self.height / 2) as i32),
+        )
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    fn fits_in(&self, outer: &Rect) -> bool {
+        self.x >= outer.x
+            && self.y >= outer.y
+            && self.right() <= outer.right()
+            && self.bottom() <= outer.bottom()
+    }
+}
+
+/// 桌面坐标中的一点，物理像素
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinLayout {
+    /// 从显示器左上角起沿对角线错开
+    Cascade,
+    /// 平铺成网格，放不下的钉图按比例缩小
+    Grid,
+}
+
+/// 钉在桌面上的截图
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    pub id: String,
+    /// 窗口在桌面上的位置与大小
+    pub bounds: Rect,
+}
+
+impl Pin {
+    fn label(&self) -> String {
+        format!("{PIN_PREFIX}{}", self.id)
+    }
+}
+
+struct Entry {
+    pin: Pin,
+    png: Arc<Vec<u8>>,
+}
+
+/// 打开的钉图。开启 persist_pins 时图片和位置写在数据目录的 pins 下，重启后恢复
+pub struct Pins {
+    dir: PathBuf,
+    entries: Mutex<Vec<Entry>>,
+    save_scheduled: AtomicBool,
+}
+
+impl Pins {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            entries: Mutex::default(),
+            save_scheduled: AtomicBool::new(false),
+        }
+    }
+
+    fn image_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.png"))
+    }
+
+    pub fn list(&self) -> Vec<Pin> {
+        self.entries
+            .lock()
+            .map(|entries| entries.iter().map(|e| e.pin.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    fn png(&self, id: &str) -> Option<Arc<Vec<u8>>> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .iter()
+            .find(|e| e.pin.id == id)
+            .map(|e| e.png.clone())
+    }
+
+    fn add(&self, pin: Pin, png: Vec<u8>, persist: bool) -> Result<(), String> {
+        if persist {
+            crate::durable::atomic_write(&self.image_path(&pin.id), &png)?;
+        }
+        self.entries.lock().map_err(|e| e.to_string())?.push(Entry {
+            pin,
+            png: Arc::new(png),
+        });
+        if persist {
+            self.write_list()?;
+        }
+        Ok(())
+    }
+
+    /// 更新钉图位置，钉图不存在时返回 false
+    fn set_bounds(&self, id: &str, update: impl FnOnce(&mut Rect)) -> bool {
+        let Ok(mut entries) = self.entries.lock() else {
+            return false;
+        };
+        match entries.iter_mut().find(|e| e.pin.id == id) {
+            Some(entry) => {
+                update(&mut entry.pin.bounds);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove(&self, id: &str, persist: bool) -> Result<bool, String> {
+        let removed = {
+            let mut entries = self.entries.lock().map_err(|e| e.to_string())?;
+            let before = entries.len();
+            entries.retain(|e| e.pin.id != id);
+            entries.len() != before
+        };
+        if removed && persist {
+            crate::durable::remove(&self.image_path(id)).map_err(|e| e.to_string())?;
+            self.write_list()?;
+        }
+        Ok(removed)
+    }
+
+    fn write_list(&self) -> Result<(), String> {
+        let data = serde_json::to_vec(&self.list()).map_err(|e| e.to_string())?;
+        crate::durable::write_with_backup(&self.dir.join(PINS_FILE), &data)
+    }
+
+    /// 开启保存后把已打开的钉图全部写入
+    fn write_all(&self) -> Result<(), String> {
+        let images: Vec<(String, Arc<Vec<u8>>)> = self
+            .entries
+            .lock()
+            .map_err(|e| e.to_string())?
+            .iter()
+            .map(|e| (e.pin.id.clone(), e.png.clone()))
+            .collect();
+        for (id, png) in images {
+            crate::durable::atomic_write(&self.image_path(&id), &png)?;
+        }
+        self.write_list()
+    }
+
+    /// 删除保存的钉图，不影响已打开的窗口
+    fn clear_saved(&self) {
+        match std::fs::remove_dir_all(&self.dir) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                tracing::warn!(path = %self.dir.display(), error = %err, "删除保存的钉图失败")
+            }
+        }
+    }
+
+    /// 读取保存的钉图，图片缺失的跳过
+    fn load(&self) -> Vec<Pin> {
+        let path = self.dir.join(PINS_FILE);
+        let saved: Vec<Pin> =
+            crate::durable::read_recovering(&path, |data| serde_json::from_slice(data))
+                .unwrap_or_default();
+        let mut loaded = Vec::new();
+        let Ok(mut entries) = self.entries.lock() else {
+            return loaded;
+        };
+        for pin in saved {
+            match std::fs::read(self.image_path(&pin.id)) {
+                Ok(png) => {
+                    loaded.push(pin.clone());
+                    entries.push(Entry {
+                        pin,
+                        png: Arc::new(png),
+                    });
+                }
+                Err(err) => {
+                    tracing::warn!(id = %pin.id, error = %err, "钉图图片缺失，不再恢复");
+                }
+            }
+        }
+        loaded
+    }
+}
+
+/// 按比例缩小到不超过给定大小，不放大
+fn fit_within(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    if width <= max_width && height <= max_height {
+        return (width, height);
+    }
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    (
+        ((width as f64 * scale) as u32).clamp(1, max_width.max(1)),
+        ((height as f64 * scale) as u32).clamp(1, max_height.max(1)),
+    )
+}
+
+/// 把窗口完整地移到显示器内，比显示器大时先按比例缩小
+fn clamp_onto(rect: Rect, monitor: Rect) -> Rect {
+    let (width, height) = fit_within(rect.width, rect.height, monitor.width, monitor.height);
+    Rect {
+        x: rect
+            .x
+            .clamp(monitor.x, monitor.right().saturating_sub(width as i32)),
+        y: rect
+            .y
+            .clamp(monitor.y, monitor.bottom().saturating_sub(height as i32)),
+        width,
+        height,
+    }
+}
+
+/// 点所在的显示器；不在任何显示器上时取中心最近的
+fn monitor_at(monitors: &[Rect], x: i32, y: i32) -> Option<Rect> {
+    let distance = |m: &Rect| {
+        let (cx, cy) = m.center();
+        (cx as i64 - x as i64).pow(2) + (cy as i64 - y as i64).pow(2)
+    };
+    monitors
+        .iter()
+        .find(|m| m.contains(x, y))
+        .or_else(|| monitors.iter().min_by_key(|m| distance(m)))
+        .copied()
+}
+
+/// 默认放在选区右侧，放不下时依次尝试左侧、下方、上方，都放不下时贴着显示器边缘
+fn beside_selection(selection: Rect, width: u32, height: u32, monitor: Rect) -> Rect {
+    let (width, height) = fit_within(width, height, monitor.width, monitor.height);
+    let at = |(x, y): (i32, i32)| Rect {
+        x,
+        y,
+        width,
+        height,
+    };
+    let candidates = [
+        (selection.right() + GAP, selection.y),
+        (selection.x - GAP - width as i32, selection.y),
+        (selection.x, selection.bottom() + GAP),
+        (selection.x, selection.y - GAP - height as i32),
+    ];
+    let chosen = candidates
+        .into_iter()
+        .map(at)
+        .find(|r| r.fits_in(&monitor))
+        .unwrap_or_else(|| at(candidates[0]));
+    clamp_onto(chosen, monitor)
+}
+
+/// 左上角与已有钉图重合时沿对角线错开，到显示器边缘后从显示器左上角重新开始
+fn cascade(rect: Rect, monitor: Rect, occupied: &[Rect]) -> Rect {
+    let taken = |r: &Rect| {
+        occupied
+            .iter()
+            .any(|o| (o.x - r.x).abs() < CASCADE_STEP / 2 && (o.y - r.y).abs() < CASCADE_STEP / 2)
+    };
+    let mut placed = clamp_onto(rect, monitor);
+    for _ in 0..MAX_CASCADE {
+        if !taken(&placed) {
+            break;
+        }
+        let next = Rect {
+            x: placed.x + CASCADE_STEP,
+            y: placed.y + CASCADE_STEP,
+            ..placed
+        };
+        placed = if next.fits_in(&monitor) {
+            next
+        } else {
+            clamp_onto(
+                Rect {
+                    x: monitor.x,
+                    y: monitor.y,
+                    ..placed
+                },
+                monitor,
+            )
+        };
+    }
+    placed
+}
+
+/// 同一显示器上钉图的新位置，与 sizes 的顺序对应
+fn arrange(layout: PinLayout, monitor: Rect, sizes: &[(u32, u32)]) -> Vec<Rect> {
+    let area = Rect {
+        x: monitor.x + MARGIN,
+        y: monitor.y + MARGIN,
+        width: monitor.width.saturating_sub(2 * MARGIN as u32).max(1),
+        height: monitor.height.saturating_sub(2 * MARGIN as u32).max(1),
+    };
+    match layout {
+        PinLayout::Cascade => sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &(width, height))| {
+                let offset = i as i32 * CASCADE_STEP;
+                let rect = Rect {
+                    x: area.x + offset,
+                    y: area.y + offset,
+                    width,
+                    height,
+                };
+                clamp_onto(rect, area)
+            })
+            .collect(),
+        PinLayout::Grid => {
+            let count = sizes.len().max(1) as u32;
+            let columns = (count as f64).sqrt().ceil() as u32;
+            let rows = count.div_ceil(columns);
+            let gap = GAP as u32;
+            let cell_width = (area.width.saturating_sub(gap * (columns - 1)) / columns).max(1);
+            let cell_height = (area.height.saturating_sub(gap * (rows - 1)) / rows).max(1);
+            sizes
+                .iter()
+                .enumerate()
+                .map(|(i, &(width, height))| {
+                    let (column, row) = (i as u32 % columns, i as u32 / columns);
+                    let (width, height) = fit_within(width, height, cell_width, cell_height);
+                    Rect {
+                        x: area.x + (column * (cell_width + gap)) as i32,
+                        y: area.y + (row * (cell_height + gap)) as i32,
+                        width,
+                        height,
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+fn monitor_rects(app: &AppHandle) -> Vec<Rect> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| Rect {
+            x: m.position().x,
+            y: m.position().y,
+            width: m.size().width,
+            height: m.size().height,
+        })
+        .collect()
+}
+
+fn persist_enabled(app: &AppHandle) -> bool {
+    app.state::<SettingsState>().get().persist_pins
+}
+
+fn place(window: &WebviewWindow, bounds: Rect) -> Result<(), AppError> {
+    window.set_size(PhysicalSize::new(bounds.width, bounds.height))?;
+    window.set_position(PhysicalPosition::new(bounds.x, bounds.y))?;
+    Ok(())
+}
+
+fn open_window(app: &AppHandle, pin: &Pin) -> Result<(), AppError> {
+    let window = WebviewWindowBuilder::new(app, pin.label(), WebviewUrl::App("index.html".into()))
+        .title("PrinSp")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()?;
+    place(&window, pin.bounds)?;
+    window.show()?;
+    Ok(())
+}
+
+/// 把截图的一个区域钉在桌面上。region 为截图像素坐标，为空时钉整张截图；
+/// position 为窗口左上角的桌面坐标，为空时放在选区旁边。位置总是完整落在一个显示器上，
+/// 与已有钉图重合时错开
+#[tauri::command]
+pub async fn pin_image(
+    app: AppHandle,
+    window: WebviewWindow,
+    capture_id: CaptureRef,
+    region: Option<Region>,
+    position: Option<Point>,
+) -> Result<Pin, AppError> {
+    let (png, width, height) = run_blocking({
+        let app = app.clone();
+        move || {
+            let cache = app.state::<CaptureCache>();
+            let id = cache.resolve(&capture_id)?;
+            let mut image = cache.image(id)?;
+            if let Some(r) = region {
+                let cropped = image.crop_imm(r.x, r.y, r.width, r.height);
+                if cropped.width() == 0 || cropped.height() == 0 {
+                    return Err(AppError::invalid("钉图区域为空"));
+                }
+                image = Arc::new(cropped);
+            }
+            let png = crate::frame::encode_png(&image)?;
+            Ok((png, image.width(), image.height()))
+        }
+    })
+    .await?;
+
+    // 遮罩窗口铺满所在显示器，截图像素即该显示器上的物理像素
+    let origin = window
+        .current_monitor()?
+        .map(|m| *m.position())
+        .unwrap_or(PhysicalPosition::new(0, 0));
+    let (offset_x, offset_y) = region.map(|r| (r.x, r.y)).unwrap_or_default();
+    let selection = Rect {
+        x: origin.x + offset_x as i32,
+        y: origin.y + offset_y as i32,
+        width,
+        height,
+    };
+    let monitors = monitor_rects(&app);
+    let pins = app.state::<Pins>();
+    let occupied: Vec<Rect> = pins.list().iter().map(|p| p.bounds).collect();
+    let bounds = match position {
+        Some(p) => {
+            let monitor = monitor_at(&monitors, p.x, p.y).unwrap_or(UNBOUNDED);
+            let rect = Rect {
+                x: p.x,
+                y: p.y,
+                width,
+                height,
+            };
+            cascade(rect, monitor, &occupied)
+        }
+        None => {
+            let (x, y) = selection.center();
+            let monitor = monitor_at(&monitors, x, y).unwrap_or(UNBOUNDED);
+            cascade(
+                beside_selection(selection, width, height, monitor),
+                monitor,
+                &occupied,
+            )
+        }
+    };
+
+    let pin = Pin {
+        id: Uuid::now_v7().simple().to_string(),
+        bounds,
+    };
+    pins.add(pin.clone(), png, persist_enabled(&app))
+        .map_err(AppError::Internal)?;
+    if let Err(err) = open_window(&app, &pin) {
+        let _ = pins.remove(&pin.id, persist_enabled(&app));
+        return Err(err);
+    }
+    Ok(pin)
+}
+
+/// 按布局重新排列所有钉图，每个钉图留在它当前所在的显示器上
+#[tauri::command]
+pub fn arrange_pins(app: AppHandle, layout: PinLayout) -> Result<Vec<Pin>, AppError> {
+    let monitors = monitor_rects(&app);
+    let pins = app.state::<Pins>();
+    let current = pins.list();
+    let mut groups: Vec<(Rect, Vec<&Pin>)> = Vec::new();
+    for pin in &current {
+        let (x, y) = pin.bounds.center();
+        let monitor = monitor_at(&monitors, x, y).unwrap_or(UNBOUNDED);
+        match groups.iter_mut().find(|(m, _)| *m == monitor) {
+            Some((_, group)) => group.push(pin),
+            None => groups.push((monitor, vec![pin])),
+        }
+    }
+    for (monitor, group) in groups {
+        let sizes: Vec<_> = group
+            .iter()
+            .map(|p| (p.bounds.width, p.bounds.height))
+            .collect();
+        for (pin, bounds) in group.iter().zip(arrange(layout, monitor, &sizes)) {
+            pins.set_bounds(&pin.id, |b| *b = bounds);
+            if let Some(window) = app.get_webview_window(&pin.label()) {
+                place(&window, bounds)?;
+            }
+        }
+    }
+    if persist_enabled(&app) {
+        pins.write_list().map_err(AppError::Internal)?;
+    }
+    Ok(pins.list())
+}
+
+/// 当前打开的钉图
+#[tauri::command]
+pub fn list_pins(app: AppHandle) -> Vec<Pin> {
+    app.state::<Pins>().list()
+}
+
+/// 钉图窗口加载图片，返回原始 PNG 字节
+#[tauri::command]
+pub fn get_pin_image(app: AppHandle, id: String) -> Result<Response, AppError> {
+    let png = app
+        .state::<Pins>()
+        .png(&id)
+        .ok_or_else(|| AppError::invalid(format!("钉图不存在: {id}")))?;
+    Ok(Response::new(png.to_vec()))
+}
+
+#[tauri::command]
+pub fn close_pin(app: AppHandle, id: String) -> Result<(), AppError> {
+    app.state::<Pins>()
+        .remove(&id, persist_enabled(&app))
+        .map_err(AppError::Internal)?;
+    if let Some(window) = app.get_webview_window(&format!("{PIN_PREFIX}{id}")) {
+        window.destroy()?;
+    }
+    Ok(())
+}
+
+fn schedule_save(app: &AppHandle) {
+    if !persist_enabled(app)
+        || app
+            .state::<Pins>()
+            .save_scheduled
+            .swap(true, Ordering::SeqCst)
+    {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(SAVE_DELAY);
+        let pins = app.state::<Pins>();
+        pins.save_scheduled.store(false, Ordering::SeqCst);
+        if let Err(err) = pins.write_list() {
+            tracing::warn!(error = %err, "保存钉图位置失败");
+        }
+    });
+}
+
+/// 钉图窗口移动、缩放后记下新位置；用户关闭窗口时不再恢复
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let Some(id) = window.label().strip_prefix(PIN_PREFIX) else {
+        return;
+    };
+    let app = window.app_handle();
+    let pins = app.state::<Pins>();
+    let updated = match event {
+        WindowEvent::Moved(position) => pins.set_bounds(id, |b| {
+            b.x = position.x;
+            b.y = position.y;
+        }),
+        WindowEvent::Resized(size) => pins.set_bounds(id, |b| {
+            b.width = size.width;
+            b.height = size.height;
+        }),
+        WindowEvent::CloseRequested { .. } => {
+            if let Err(err) = pins.remove(id, persist_enabled(app)) {
+                tracing::warn!(id, error = %err, "删除钉图失败");
+            }
+            false
+        }
+        _ => false,
+    };
+    if updated {
+        schedule_save(app);
+    }
+}
+
+/// 启动时恢复保存的钉图；未开启保存时清掉上次留下的文件
+pub fn restore(app: &AppHandle) {
+    let pins = app.state::<Pins>();
+    if !persist_enabled(app) {
+        pins.clear_saved();
+        return;
+    }
+    let monitors = monitor_rects(app);
+    for mut pin in pins.load() {
+        // 原显示器已断开时移到最近的显示器上
+        let (x, y) = pin.bounds.center();
+        if let Some(monitor) = monitor_at(&monitors, x, y) {
+            let bounds = clamp_onto(pin.bounds, monitor);
+            if bounds != pin.bounds {
+                pins.set_bounds(&pin.id, |b| *b = bounds);
+                pin.bounds = bounds;
+            }
+        }
+        if let Err(err) = open_window(app, &pin) {
+            tracing::warn!(id = %pin.id, error = %err, "恢复钉图失败");
+        }
+    }
+}
+
+/// 切换 persist_pins：开启时写入已打开的钉图，关闭时删除保存的文件
+pub fn apply_persist(app: &AppHandle, persist: bool) {
+    let pins = app.state::<Pins>();
+    if persist {
+        if let Err(err) = pins.write_all() {
+            tracing::warn!(error = %err, "保存钉图失败");
+        }
+    } else {
+        pins.clear_saved();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    const RIGHT: Rect = Rect {
+        x: 1920,
+        y: -200,
+        width: 1280,
+        height: 1024,
+    };
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn pin_goes_right_of_the_selection() {
+        let selection = rect(100, 100, 300, 200);
+        assert_eq!(
+            beside_selection(selection, 300, 200, LEFT),
+            rect(408, 100, 300, 200)
+        );
+    }
+
+    #[test]
+    fn pin_falls_back_to_the_other_sides() {
+        // 右侧放不下，放到左侧
+        let selection = rect(1500, 100, 300, 200);
+        assert_eq!(
+            beside_selection(selection, 300, 200, LEFT),
+            rect(1192, 100, 300, 200)
+        );
+        // 左右都放不下，放到下方
+        let wide = rect(50, 100, 1800, 200);
+        assert_eq!(
+            beside_selection(wide, 1800, 200, LEFT),
+            rect(50, 308, 1800, 200)
+        );
+        // 四周都放不下时贴着显示器边缘，仍完整落在显示器上
+        let full = rect(0, 0, 1920, 1080);
+        let placed = beside_selection(full, 1920, 1080, LEFT);
+        assert!(placed.fits_in(&LEFT), "{placed:?}");
+    }
+
+    #[test]
+    fn pin_stays_on_the_selection_monitor() {
+        // 选区在右侧显示器的右边缘，不会跨到左侧显示器
+        let selection = rect(3000, 0, 150, 100);
+        let monitor = monitor_at(&[LEFT, RIGHT], 3075, 50).unwrap();
+        assert_eq!(monitor, RIGHT);
+        let placed = beside_selection(selection, 150, 100, monitor);
+        assert_eq!(placed, rect(2842, 0, 150, 100));
+        assert!(placed.fits_in(&RIGHT));
+    }
+
+    #[test]
+    fn explicit_position_is_clamped_onto_the_monitor() {
+        let placed = cascade(rect(1900, 1000, 400, 300), LEFT, &[]);
+        assert_eq!(placed, rect(1520, 780, 400, 300));
+        // 大于显示器时按比例缩小
+        let huge = clamp_onto(rect(-50, -50, 3840, 1080), LEFT);
+        assert_eq!(huge, rect(0, 0, 1920, 540));
+    }
+
+    #[test]
+    fn point_off_every_monitor_uses_the_nearest() {
+        assert_eq!(monitor_at(&[LEFT, RIGHT], 5000, 0), Some(RIGHT));
+        assert_eq!(monitor_at(&[LEFT, RIGHT], -300, 500), Some(LEFT));
+        assert_eq!(monitor_at(&[], 0, 0), None);
+    }
+
+    #[test]
+    fn overlapping_pins_cascade() {
+        let first = rect(200, 200, 300, 200);
+        let second = cascade(first, LEFT, &[first]);
+        assert_eq!(second, rect(232, 232, 300, 200));
+        let third = cascade(first, LEFT, &[first, second]);
+        assert_eq!(third, rect(264, 264, 300, 200));
+        // 不重合的不错开
+        assert_eq!(cascade(first, LEFT, &[rect(600, 200, 10, 10)]), first);
+    }
+
+    #[test]
+    fn cascade_wraps_at_the_monitor_edge() {
+        let corner = rect(1620, 880, 300, 200);
+        let placed = cascade(corner, LEFT, &[corner]);
+        assert_eq!(placed, rect(0, 0, 300, 200));
+    }
+
+    #[test]
+    fn cascade_layout_offsets_each_pin() {
+        let placed = arrange(PinLayout::Cascade, LEFT, &[(300, 200), (400, 300)]);
+        assert_eq!(placed, [rect(16, 16, 300, 200), rect(48, 48, 400, 300)]);
+    }
+
+    #[test]
+    fn grid_layout_tiles_and_shrinks() {
+        let sizes = [(300, 200), (2000, 1000), (100, 100)];
+        let placed = arrange(PinLayout::Grid, LEFT, &sizes);
+        // 3 个钉图排成 2 列 2 行
+        let (cell_width, cell_height) = ((1888 - 8) / 2, (1048 - 8) / 2);
+        assert_eq!(placed[0], rect(16, 16, 300, 200));
+        assert_eq!(placed[1].x, 16 + cell_width as i32 + 8);
+        assert!(placed[1].width <= cell_width && placed[1].height <= cell_height);
+        assert_eq!(placed[2], rect(16, 16 + cell_height as i32 + 8, 100, 100));
+        for (i, a) in placed.iter().enumerate() {
+            for b in &placed[i + 1..] {
+                let apart =
+                    a.right() <= b.x || b.right() <= a.x || a.bottom() <= b.y || b.bottom() <= a.y;
+                assert!(apart, "{a:?} {b:?}");
+            }
+        }
+    }
+
+    fn pin(id: &str, x: i32) -> Pin {
+        Pin {
+            id: id.into(),
+            bounds: rect(x, 10, 40, 30),
+        }
+    }
+
+    #[test]
+    fn saved_pins_are_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let pins = Pins::new(dir.path().join("pins"));
+        pins.add(pin("a", 0), vec![1, 2, 3], true).unwrap();
+        pins.add(pin("b", 100), vec![4], true).unwrap();
+        assert!(pins.set_bounds("b", |b| b.x = 500));
+        pins.write_list().unwrap();
+        pins.remove("a", true).unwrap();
+
+        let restored = Pins::new(dir.path().join("pins"));
+        assert_eq!(restored.load(), [pin("b", 500)]);
+        assert_eq!(restored.list(), [pin("b", 500)]);
+        assert_eq!(*restored.png("b").unwrap(), vec![4]);
+        assert!(!dir.path().join("pins/a.png").exists());
+    }
+
+    #[test]
+    fn pins_are_not_written_without_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let pins = Pins::new(dir.path().join("pins"));
+        pins.add(pin("a", 0), vec![1], false).unwrap();
+        assert!(!dir.path().join("pins").exists());
+        // 之后开启保存时写入已打开的钉图，关闭时删除
+        pins.write_all().unwrap();
+        assert_eq!(Pins::new(dir.path().join("pins")).load(), [pin("a", 0)]);
+        pins.clear_saved();
+        assert!(!dir.path().join("pins").exists());
+        assert_eq!(pins.list(), [pin("a", 0)]);
+    }
+
+    #[test]
+    fn missing_images_are_skipped_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let pins = Pins::new(dir.path().to_path_buf());
+        pins.add(pin("a", 0), vec![1], true).unwrap();
+        pins.add(pin("b", 0), vec![2], true).unwrap();
+        std::fs::remove_file(dir.path().join("a.png")).unwrap();
+        assert_eq!(Pins::new(dir.path().to_path_buf()).load(), [pin("b", 0)]);
+    }
+}
@@ -0,0 +1,486 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Local};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::capture_cache::{CaptureCache, CaptureRef};
+use crate::capture_id::CaptureId;
+use crate::error::AppError;
+use crate::error_report::{self, ErrorSource, Failure};
+use crate::notify::{self, CopyPayload, Notice, NotifyEvent};
+use crate::pre_encode::{self, Prepared, Variant};
+use crate::save::ExportFormat;
+use crate::settings::{Settings, SettingsState};
+use crate::sound::{self, FeedbackKind};
+use crate::webhook::{self, WebhookEvent};
+use crate::{clipboard, durable, redact, script_hook, share, upload};
+
+/// 可用的截图后续动作；upload 可写成 `upload:<配置名>` 指定上传配置
+pub const KNOWN_ACTIONS: &[&str] = &["copy_image", "copy_text", "save", "upload", "share"];
+
+/// 命名的动作组合，可在托盘中切换
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipelinePreset {
+    pub name: String,
+    pub actions: Vec<String>,
+}
+
+pub fn validate_action(action: &str) -> Result<(), String> {
+    let base = action.split(':').next().unwrap_or(action);
+    if KNOWN_ACTIONS.contains(&base) {
+        Ok(())
+    } else {
+        Err(format!("未知的截图后续动作: {action}"))
+    }
+}
+
+/// 动作名与参数，如 `upload:图床` 拆为 upload 和 图床
+pub(crate) fn split_action(action: &str) -> (&str, Option<&str>) {
+    match action.split_once(':') {
+        Some((base, arg)) => (base, Some(arg)),
+        None => (action, None),
+    }
+}
+
+/// upload 动作使用的上传配置：指定了名称时用指定的，否则为第一个
+pub(crate) fn upload_profile_name(settings: &Settings, arg: Option<&str>) -> Option<String> {
+    arg.map(str::to_string)
+        .or_else(|| settings.upload_profiles.first().map(|p| p.name.clone()))
+}
+
+/// 当前生效的动作列表：有激活的预设时使用预设，否则使用 post_capture_actions
+pub fn active_actions(settings: &Settings) -> Vec<String> {
+    settings
+        .active_pipeline
+        .as_ref()
+        .and_then(|name| settings.pipeline_presets.iter().find(|p| &p.name == name))
+        .map(|p| p.actions.clone())
+        .unwrap_or_else(|| settings.post_capture_actions.clone())
+}
+
+/// 各动作共享的截图数据，图片只解码一次；
+/// 直接使用缓存中的截图时，各动作要用的编码结果优先取后台预编码好的
+struct CaptureContext {
+    image: Arc<DynamicImage>,
+    /// 前端传来的 PNG（已经过截图脚本和自动打码）
+    png: Option<Arc<Vec<u8>>>,
+    /// 缓存中的截图：缓存编号与编辑状态编号
+    cached: Option<(u64, u64)>,
+    ocr_text: Option<String>,
+    /// 截图触发时的前台应用名
+    app_name: Option<String>,
+}
+
+impl CaptureContext {
+    async fn prepared(&self, app: &AppHandle, variant: Variant) -> Result<Prepared, String> {
+        if let (Variant::Png, Some(png)) = (variant, &self.png) {
+            return Ok(Prepared::Bytes(png.clone()));
+        }
+        let app = app.clone();
+        let image = self.image.clone();
+        let cached = self.cached;
+        tauri::async_runtime::spawn_blocking(move || match cached {
+            Some((id, serial)) => {
+                pre_encode::get_or_encode(&app.state::<CaptureCache>(), id, serial, &image, variant)
+            }
+            None => pre_encode::encode(&image, variant),
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn png(&self, app: &AppHandle) -> Result<Arc<Vec<u8>>, String> {
+        self.prepared(app, Variant::Png).await?.into_bytes()
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ActionOutcome {
+    /// finish_capture 传入的截图编号
+    pub capture_id: Option<CaptureId>,
+    pub action: String,
+    pub ok: bool,
+    /// 成功时为结果（保存路径、上传链接等），失败时为错误信息
+    pub detail: Option<String>,
+}
+
+/// 自动保存文件名的默认模板
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "screenshot_{date}_{time}";
+
+pub fn validate_filename_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("文件名模板不能为空".into());
+    }
+    if template.contains(['/', '\\']) {
+        return Err(format!("文件名模板不能包含路径分隔符: {template}"));
+    }
+    Ok(())
+}
+
+/// 按模板生成自动保存的文件名（不含扩展名），支持 {app} {date} {time}；
+/// 没有识别到前台应用时 {app} 为 screenshot
+pub fn render_filename(template: &str, app: Option<&str>, now: DateTime<Local>) -> String {
+    template
+        .replace("{app}", app.unwrap_or("screenshot"))
+        .replace("{date}", &now.format("%Y%m%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string())
+}
+
+/// 模板不含 {time} 时可能重名，依次加 _1、_2
+fn unique_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let mut path = dir.join(format!("{stem}.{ext}"));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("{stem}_{n}.{ext}"));
+        n += 1;
+    }
+    path
+}
+
+pub(crate) fn save_dir(app: &AppHandle, settings: &Settings) -> Result<PathBuf, String> {
+    match &settings.save_dir {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => app
+            .path()
+            .picture_dir()
+            .map(|dir| dir.join("PrinSp"))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// 按文件名模板保存到自动保存目录，返回保存的路径；不依赖窗口，托盘的简化模式也会调用
+pub(crate) fn save_png(
+    app: &AppHandle,
+    settings: &Settings,
+    png: &[u8],
+    app_name: Option<&str>,
+) -> Result<PathBuf, String> {
+    let dir = save_dir(app, settings)?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let stem = render_filename(&settings.filename_template, app_name, Local::now());
+    let path = unique_path(&dir, &stem, "png");
+    durable::atomic_write(&path, png)?;
+    Ok(path)
+}
+
+async fn run_action(
+    app: &AppHandle,
+    settings: &Settings,
+    action: &str,
+    ctx: &CaptureContext,
+) -> Result<Option<String>, String> {
+    let (base, arg) = split_action(action);
+
+    match base {
+        "copy_image" => {
+            let rgba = ctx
+                .prepared(app, Variant::ClipboardRgba)
+                .await?
+                .into_rgba()?;
+            clipboard::set_rgba(&rgba)
+                .map(|_| None)
+                .map_err(String::from)
+        }
+        "copy_text" => match &ctx.ocr_text {
+            Some(text) => clipboard::set_text(text.clone())
+                .map(|_| None)
+                .map_err(String::from),
+            None => Err("没有可复制的识别文字".into()),
+        },
+        "save" => {
+            let png = ctx.png(app).await?;
+            let path = save_png(app, settings, &png, ctx.app_name.as_deref())?;
+            let path = path.to_string_lossy().into_owned();
+            webhook::fire(
+                app,
+                WebhookEvent::CaptureSaved,
+                webhook::Payload {
+                    text: ctx.ocr_text.clone(),
+                    path: Some(path.clone()),
+                    image: Some((png.as_slice(), ExportFormat::Png)),
+                    ..Default::default()
+                },
+            );
+            Ok(Some(path))
+        }
+        "upload" => {
+            let name = upload_profile_name(settings, arg).ok_or("未配置上传配置")?;
+            let profile = upload::find_profile(app, &name).map_err(|e| e.to_string())?;
+            let (data, format) = match profile.jpeg_max_side {
+                Some(max_side) => (
+                    ctx.prepared(app, Variant::UploadJpeg { max_side })
+                        .await?
+                        .into_bytes()?,
+                    ExportFormat::Jpeg,
+                ),
+                None => (ctx.png(app).await?, ExportFormat::Png),
+            };
+            let result = upload::upload_encoded(app, data.to_vec(), format, &profile, None)
+                .await
+                .map_err(|e| e.to_string())?;
+            let _ = clipboard::set_text(result.url.clone());
+            let _ = app.emit("upload-complete", &result);
+            webhook::fire(
+                app,
+                WebhookEvent::UploadComplete,
+                webhook::Payload {
+                    url: Some(result.url.clone()),
+                    image: Some((data.as_slice(), format)),
+                    ..Default::default()
+                },
+            );
+            let mut notice = Notice::new(NotifyEvent::Upload, "上传完成，链接已复制", &result.url);
+            notice.copy = Some(CopyPayload::Text(result.url.clone()));
+            notify::notify(app, notice);
+            Ok(Some(result.url))
+        }
+        "share" => {
+            // 分享目标（邮件客户端等）稍后才读取文件，临时文件不删除
+            let name = format!("prinsp_share_{}.png", Local::now().format("%Y%m%d_%H%M%S"));
+            let path = std::env::temp_dir().join(name);
+            durable::atomic_write(&path, &ctx.png(app).await?)?;
+            let mechanism = tauri::async_runtime::spawn_blocking(move || share::share_path(&path))
+                .await
+                .map_err(|e| e.to_string())??;
+            Ok(Some(mechanism.as_str().to_string()))
+        }
+        _ => Err(format!("未知的截图后续动作: {action}")),
+    }
+}
+
+/// 截图脚本和自动打码，前端传来的截图和开启了这两项时的缓存截图都要经过
+async fn process_png(
+    app: &AppHandle,
+    settings: &Settings,
+    png: Vec<u8>,
+    capture_id: Option<&CaptureId>,
+    outcomes: &mut Vec<ActionOutcome>,
+) -> Result<Vec<u8>, String> {
+    let png = tauri::async_runtime::spawn_blocking({
+        let app = app.clone();
+        move || script_hook::run_enabled(&app, png)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    // 打码失败时不继续，避免把未打码的截图保存或上传出去
+    if !settings.redact.auto_redact {
+        return Ok(png);
+    }
+    let (png, regions) = crate::run_blocking({
+        let app = app.clone();
+        move || redact::auto_redact_png(&app, &png, None)
+    })
+    .await
+    .map_err(|e| format!("自动打码失败: {}", e.message()))?;
+    let outcome = ActionOutcome {
+        capture_id: capture_id.cloned(),
+        action: "auto_redact".into(),
+        ok: true,
+        detail: Some(format!("已打码 {} 处", regions.len())),
+    };
+    let _ = app.emit("post-capture-action", &outcome);
+    outcomes.push(outcome);
+    Ok(png)
+}
+
+async fn decode_context(png: Vec<u8>, ocr_text: Option<String>) -> Result<CaptureContext, String> {
+    let (png, image) = crate::run_blocking(move || {
+        let image = image::load_from_memory(&png).map_err(AppError::invalid)?;
+        Ok((png, image))
+    })
+    .await
+    .map_err(String::from)?;
+    Ok(CaptureContext {
+        image: Arc::new(image),
+        png: Some(Arc::new(png)),
+        cached: None,
+        ocr_text,
+        app_name: None,
+    })
+}
+
+/// 前端确认最终图片后调用，按设置顺序执行后续动作，并逐个发出 post-capture-action 事件；
+/// 事件和返回值带上 capture_id，前端据此对应到触发的截图。
+/// 最终图片就是缓存中截图的当前状态（未在前端裁剪或标注）时 base64_data 传 null
+/// 并给出 cache_id（多显示器时各截图的 capture_id 相同），各动作直接使用后台预编码好的结果
+#[tauri::command]
+pub async fn finish_capture(
+    app: AppHandle,
+    base64_data: Option<String>,
+    ocr_text: Option<String>,
+    capture_id: Option<CaptureId>,
+    cache_id: Option<u64>,
+) -> Result<Vec<ActionOutcome>, String> {
+    let _busy = crate::tray::busy(&app);
+    let settings = app.state::<SettingsState>().get();
+    let ocr_text = ocr_text.filter(|t| !t.trim().is_empty());
+    let mut outcomes = Vec::new();
+    let mut ctx = match base64_data {
+        Some(data) => {
+            let png = STANDARD.decode(&data).map_err(|e| e.to_string())?;
+            let png = process_png(&app, &settings, png, capture_id.as_ref(), &mut outcomes).await?;
+            decode_context(png, ocr_text).await?
+        }
+        None => {
+            let cache = app.state::<CaptureCache>();
+            let id = match (cache_id, &capture_id) {
+                (Some(id), _) => id,
+                (None, Some(capture)) => cache.resolve(&CaptureRef::Capture(capture.clone()))?,
+                (None, None) => return Err("缺少截图数据".into()),
+            };
+            if pre_encode::final_differs(&settings) {
+                let png = cache.get(id).ok_or("截图不存在或已过期")?;
+                let png =
+                    process_png(&app, &settings, png, capture_id.as_ref(), &mut outcomes).await?;
+                decode_context(png, ocr_text).await?
+            } else {
+                let (serial, image) = crate::run_blocking({
+                    let app = app.clone();
+                    move || app.state::<CaptureCache>().current(id)
+                })
+                .await?;
+                CaptureContext {
+                    image,
+                    png: None,
+                    cached: Some((id, serial)),
+                    ocr_text,
+                    app_name: None,
+                }
+            }
+        }
+    };
+
+    ctx.app_name = capture_id
+        .as_ref()
+        .and_then(crate::active_app::for_capture)
+        .map(|a| a.name);
+
+    for action in active_actions(&settings) {
+        let outcome = match run_action(&app, &settings, &action, &ctx).await {
+            Ok(detail) => ActionOutcome {
+                capture_id: capture_id.clone(),
+                action,
+                ok: true,
+                detail,
+            },
+            Err(err) => ActionOutcome {
+                capture_id: capture_id.clone(),
+                action,
+                ok: false,
+                detail: Some(err),
+            },
+        };
+        let _ = app.emit("post-capture-action", &outcome);
+        outcomes.push(outcome);
+    }
+
+    notify_outcomes(&app, Arc::unwrap_or_clone(ctx.image), &outcomes);
+    Ok(outcomes)
+}
+
+/// 托盘提示中显示的结果（文案 key）
+fn outcome_summary(outcomes: &[ActionOutcome]) -> &'static str {
+    let done = |name: &str| outcomes.iter().any(|o| o.ok && o.action.starts_with(name));
+    if outcomes.iter().any(|o| !o.ok) {
+        "result.partial_failure"
+    } else if done("save") {
+        "result.saved"
+    } else if done("upload") {
+        "result.uploaded"
+    } else if done("copy") {
+        "result.copied"
+    } else {
+        "result.done"
+    }
+}
+
+fn notify_outcomes(app: &AppHandle, image: DynamicImage, outcomes: &[ActionOutcome]) {
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter(|o| !o.ok)
+        .map(|o| format!("{}: {}", o.action, o.detail.as_deref().unwrap_or_default()))
+        .collect();
+    crate::tray::record(
+        app,
+        "tray.last_capture",
+        outcome_summary(outcomes),
+        failed.is_empty(),
+    );
+    if !failed.is_empty() {
+        // 按失败的动作合并，托盘已在上面更新
+        let actions: Vec<&str> = outcomes
+            .iter()
+            .filter(|o| !o.ok)
+            .map(|o| o.action.as_str())
+            .collect();
+        error_report::report(
+            app,
+            Failure {
+                tray: false,
+                ..Failure::new(
+                    ErrorSource::Capture,
+                    format!("actions:{}", actions.join(",")),
+                    "截图后续动作失败",
+                    failed.join("\n"),
+                )
+            },
+        );
+    }
+
+    if !outcomes.iter().any(|o| o.ok) {
+        return;
+    }
+    // 有失败时已由 error_report 播放出错提示音
+    if failed.is_empty() {
+        let copied = outcomes
+            .iter()
+            .any(|o| o.ok && o.action.starts_with("copy_"));
+        let kind = if copied {
+            FeedbackKind::Copy
+        } else {
+            FeedbackKind::Capture
+        };
+        sound::play_feedback(app, kind);
+    }
+    let saved = outcomes
+        .iter()
+        .find(|o| o.ok && o.action == "save")
+        .and_then(|o| o.detail.clone())
+        .map(PathBuf::from);
+    let body = match &saved {
+        Some(path) => format!("已保存到 {}", path.display()),
+        None => "已完成".to_string(),
+    };
+    let mut notice = Notice::new(NotifyEvent::Capture, "截图完成", body);
+    notice.thumbnail = Some(image.clone());
+    notice.file = saved;
+    notice.copy = Some(CopyPayload::Image(image));
+    notify::notify(app, notice);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn filename_template_uses_the_captured_app() {
+        let now = Local.with_ymd_and_hms(2024, 5, 1, 9, 3, 7).unwrap();
+        assert_eq!(
+            render_filename("{app}_{date}", Some("firefox"), now),
+            "firefox_20240501"
+        );
+        assert_eq!(
+            render_filename("{app}_{date}_{time}", None, now),
+            "screenshot_20240501_090307"
+        );
+        assert_eq!(
+            render_filename("{app}-{app}", Some("code"), now),
+            "code-code"
+        );
+        assert_eq!(render_filename("shot", Some("code"), now), "shot");
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+
+/// 同时运行的监视数上限，每个监视都会定时截图
+const MAX_WATCHES: usize = 8;
+/// 连续失败这么多次后停止监视
+const MAX_FAILURES: u32 = 5;
+/// 等待下一次轮询时检查停止标志的间隔
+const STOP_POLL: Duration = Duration::from_millis(200);
+
+struct Watch<I> {
+    info: Arc<Mutex<I>>,
+    stop: Arc<AtomicBool>,
+}
+
+/// 定时轮询的监视（文字监视、画面监视）：编号、信息和停止标志
+pub struct Watches<I> {
+    watches: Mutex<HashMap<u64, Watch<I>>>,
+    next_id: Mutex<u64>,
+}
+
+impl<I> Default for Watches<I> {
+    fn default() -> Self {
+        Self {
+            watches: Mutex::new(HashMap::new()),
+            next_id: Mutex::new(0),
+        }
+    }
+}
+
+impl<I: Clone + Send + 'static> Watches<I> {
+    /// 登记一个监视并在新线程中运行 run；what 为超出上限时错误信息中的名称
+    pub fn start(
+        &self,
+        what: &str,
+        info: impl FnOnce(u64) -> I,
+        run: impl FnOnce(Arc<Mutex<I>>, Arc<AtomicBool>) + Send + 'static,
+    ) -> Result<u64, AppError> {
+        let mut watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        if watches.len() >= MAX_WATCHES {
+            return Err(AppError::invalid(format!(
+                "最多同时监视 {MAX_WATCHES} 个{what}"
+            )));
+        }
+        let id = {
+            let mut next = self.next_id.lock().unwrap_or_else(|e| e.into_inner());
+            *next += 1;
+            *next
+        };
+        let info = Arc::new(Mutex::new(info(id)));
+        let stop = Arc::new(AtomicBool::new(false));
+        watches.insert(
+            id,
+            Watch {
+                info: info.clone(),
+                stop: stop.clone(),
+            },
+        );
+        thread::spawn(move || run(info, stop));
+        Ok(id)
+    }
+
+    /// 停止监视，返回是否找到该监视
+    pub fn stop(&self, id: u64) -> bool {
+        match self.remove(id) {
+            Some(stop) => {
+                stop.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 监视线程结束时移除登记
+    pub fn finished(&self, id: u64) {
+        self.remove(id);
+    }
+
+    fn remove(&self, id: u64) -> Option<Arc<AtomicBool>> {
+        self.watches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id)
+            .map(|w| w.stop)
+    }
+
+    /// 按编号排序的监视信息
+    pub fn list(&self) -> Vec<I> {
+        let mut watches = self
+            .watches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(&id, w)| (id, w.info.lock().unwrap_or_else(|e| e.into_inner()).clone()))
+            .collect::<Vec<_>>();
+        watches.sort_by_key(|(id, _)| *id);
+        watches.into_iter().map(|(_, info)| info).collect()
+    }
+}
+
+/// 等待一个间隔，期间被停止时返回 false
+pub fn sleep_unless_stopped(interval: Duration, stop: &AtomicBool) -> bool {
+    let deadline = Instant::now() + interval;
+    while Instant::now() < deadline {
+        if stop.load(Ordering::SeqCst) {
+            return false;
+        }
+        thread::sleep(STOP_POLL.min(deadline.saturating_duration_since(Instant::now())));
+    }
+    !stop.load(Ordering::SeqCst)
+}
+
+/// 每隔 interval 调用一次 tick，直到被停止（返回 Ok）或连续失败 MAX_FAILURES 次（返回最后的错误）
+pub fn poll(
+    id: u64,
+    interval: Duration,
+    stop: &AtomicBool,
+    mut tick: impl FnMut() -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    let mut failures = 0;
+    loop {
+        match tick() {
+            Ok(()) => failures = 0,
+            Err(err) => {
+                failures += 1;
+                tracing::warn!(id, failures, error = %err, "监视轮询失败");
+                if failures >= MAX_FAILURES {
+                    return Err(err);
+                }
+            }
+        }
+        if !sleep_unless_stopped(interval, stop) {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn sleep_returns_early_when_stopped() {
+        let stop = AtomicBool::new(true);
+        let started = Instant::now();
+        assert!(!sleep_unless_stopped(Duration::from_secs(10), &stop));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        stop.store(false, Ordering::SeqCst);
+        assert!(sleep_unless_stopped(Duration::from_millis(1), &stop));
+    }
+
+    #[test]
+    fn poll_gives_up_after_consecutive_failures() {
+        let stop = AtomicBool::new(false);
+        let mut calls = 0;
+        // 成功会重置失败计数
+        let result = poll(1, Duration::ZERO, &stop, || {
+            calls += 1;
+            if calls == 3 {
+                Ok(())
+            } else {
+                Err(AppError::Internal(format!("第 {calls} 次")))
+            }
+        });
+        assert_eq!(calls, 3 + MAX_FAILURES);
+        assert_eq!(result.unwrap_err().detail().as_deref(), Some("第 8 次"));
+    }
+
+    #[test]
+    fn poll_stops_when_flagged() {
+        let stop = AtomicBool::new(false);
+        let mut calls = 0;
+        let result = poll(1, Duration::ZERO, &stop, || {
+            calls += 1;
+            stop.store(calls == 2, Ordering::SeqCst);
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn registry_starts_lists_and_stops() {
+        let watches = Watches::<String>::default();
+        let (tx, rx) = mpsc::channel();
+        let mut ids = Vec::new();
+        for _ in 0..MAX_WATCHES {
+            let tx = tx.clone();
+            let id = watches
+                .start(
+                    "区域",
+                    |id| format!("watch {id}"),
+                    move |_, stop| {
+                        tx.send(stop).unwrap();
+                    },
+                )
+                .unwrap();
+            ids.push(id);
+        }
+        assert_eq!(ids, (1..=MAX_WATCHES as u64).collect::<Vec<_>>());
+        let err = watches
+            .start("区域", |_| String::new(), |_, _| {})
+            .unwrap_err();
+        assert_eq!(err.detail().as_deref(), Some("最多同时监视 8 个区域"));
+        assert_eq!(watches.list()[1], "watch 2");
+
+        let flags: Vec<_> = (0..MAX_WATCHES).map(|_| rx.recv().unwrap()).collect();
+        assert!(watches.stop(3));
+        assert!(!watches.stop(3));
+        assert_eq!(flags.iter().filter(|f| f.load(Ordering::SeqCst)).count(), 1);
+        watches.finished(4);
+        assert_eq!(watches.list().len(), MAX_WATCHES - 2);
+    }
+}
@@ -0,0 +1,197 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc;
+use std::sync::RwLock;
+use std::time::Duration;
+#[cfg(feature = "tauri")]
+use tauri::{AppHandle, Manager};
+
+#[cfg(feature = "tauri")]
+use crate::{error::AppError, settings::SettingsState};
+
+/// 编译后的正则大小上限，超出时视为过于复杂
+const SIZE_LIMIT: usize = 1 << 20;
+/// 在试探文本上运行的时间上限
+const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// 当前生效的规则，设置变化时重新编译；命令行模式下为空
+static ACTIVE: RwLock<Vec<(Regex, String)>> = RwLock::new(Vec::new());
+
+/// 用户定义的识别结果后处理规则，按顺序在内置整理之后执行
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostprocessRule {
+    /// 正则表达式
+    pub pattern: String,
+    /// 替换内容，可用 $1、${name} 引用捕获组
+    #[serde(default)]
+    pub replacement: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 规则执行后的文字，便于排查顺序问题
+#[derive(Clone, Debug, Serialize)]
+pub struct RuleStep {
+    pub index: usize,
+    pub pattern: String,
+    pub enabled: bool,
+    /// 本条规则匹配的次数，未启用时为 0
+    pub matches: usize,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RuleTestResult {
+    /// 内置整理后的文字，即第一条规则的输入
+    pub base: String,
+    pub steps: Vec<RuleStep>,
+}
+
+/// 容易让回溯型引擎卡住的输入：长串重复字符后跟一个不匹配的结尾
+fn probes() -> [String; 3] {
+    [
+        format!("{}!", "a".repeat(20_000)),
+        format!("{}\n", " ".repeat(20_000)),
+        format!("{}|", "文字 1".repeat(5_000)),
+    ]
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .size_limit(SIZE_LIMIT)
+        .dfa_size_limit(SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("后处理规则 {pattern} 无效: {e}"))
+}
+
+/// 在另一个线程里用试探文本跑一遍替换，超时则拒绝；超时的线程跑完后自行结束
+fn probe(regex: Regex, replacement: String) -> Result<(), String> {
+    let pattern = regex.as_str().to_string();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for text in probes() {
+            let _ = regex.replace_all(&text, replacement.as_str());
+        }
+        let _ = tx.send(());
+    });
+    rx.recv_timeout(PROBE_TIMEOUT)
+        .map_err(|_| format!("后处理规则 {pattern} 过于复杂，执行超时"))
+}
+
+pub fn validate(rule: &PostprocessRule) -> Result<(), String> {
+    if rule.pattern.is_empty() {
+        return Err("后处理规则的正则不能为空".into());
+    }
+    let regex = compile(&rule.pattern)?;
+    probe(regex, rule.replacement.clone())
+}
+
+pub fn validate_all(rules: &[PostprocessRule]) -> Result<(), String> {
+    rules.iter().try_for_each(validate)
+}
+
+/// 设置变化时重新编译已启用的规则；已通过校验，编译失败的规则跳过
+pub fn apply_settings(rules: &[PostprocessRule]) {
+    let compiled = rules
+        .iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| match compile(&rule.pattern) {
+            Ok(regex) => Some((regex, rule.replacement.clone())),
+            Err(err) => {
+                tracing::warn!(error = %err, "跳过后处理规则");
+                None
+            }
+        })
+        .collect();
+    if let Ok(mut active) = ACTIVE.write() {
+        *active = compiled;
+    }
+}
+
+/// 依次执行当前生效的规则
+pub fn apply(text: String) -> String {
+    let Ok(active) = ACTIVE.read() else {
+        return text;
+    };
+    active.iter().fold(text, |text, (regex, replacement)| {
+        regex.replace_all(&text, replacement.as_str()).into_owned()
+    })
+}
+
+/// 用样例文字逐条试运行规则；rules 为空时使用已保存的规则
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn test_postprocess_rules(
+    app: AppHandle,
+    sample_text: String,
+    rules: Option<Vec<PostprocessRule>>,
+) -> Result<RuleTestResult, AppError> {
+    let rules = rules.unwrap_or_else(|| app.state::<SettingsState>().get().postprocess_rules);
+    crate::run_blocking(move || {
+        let base = crate::ocr::postprocess_ocr_text(&sample_text);
+        let mut text = base.clone();
+        let mut steps = Vec::with_capacity(rules.len());
+        for (index, rule) in rules.into_iter().enumerate() {
+            validate(&rule).map_err(AppError::InvalidInput)?;
+            let mut matches = 0;
+            if rule.enabled {
+                let regex = compile(&rule.pattern).map_err(AppError::InvalidInput)?;
+                matches = regex.find_iter(&text).count();
+                text = regex
+                    .replace_all(&text, rule.replacement.as_str())
+                    .into_owned();
+            }
+            steps.push(RuleStep {
+                index,
+                pattern: rule.pattern,
+                enabled: rule.enabled,
+                matches,
+                text: text.clone(),
+            });
+        }
+        Ok(RuleTestResult { base, steps })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, replacement: &str, enabled: bool) -> PostprocessRule {
+        PostprocessRule {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+            enabled,
+        }
+    }
+
+    #[test]
+    fn empty_or_invalid_patterns_are_rejected() {
+        assert!(validate(&rule(r"(\d+)-(\d+)", "$2-$1", true)).is_ok());
+        assert_eq!(
+            validate(&rule("", "x", true)).unwrap_err(),
+            "后处理规则的正则不能为空"
+        );
+        let err = validate_all(&[rule("a", "b", true), rule("(", "", false)]).unwrap_err();
+        assert!(err.starts_with("后处理规则 ( 无效"));
+    }
+
+    #[test]
+    fn enabled_rules_run_in_order() {
+        apply_settings(&[
+            rule(r"(\d{4})/(\d{2})", "$1-$2", true),
+            rule("-", "_", false),
+            // 已保存的规则不应无效，万一无效则跳过
+            rule("(", "", true),
+            rule(r"(?<year>\d{4})-", "${year}年", true),
+        ]);
+        assert_eq!(apply("2024/03 与 1999/12".into()), "2024年03 与 1999年12");
+        apply_settings(&[]);
+        assert_eq!(apply("2024/03".into()), "2024/03");
+    }
+}
@@ -0,0 +1,315 @@
+use image::{DynamicImage, RgbaImage};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+use crate::capture_cache::CaptureCache;
+use crate::pipeline;
+use crate::save::{self, ExportFormat};
+use crate::settings::{Settings, SettingsState};
+use crate::upload;
+
+/// 缓存中所有截图的预编码结果合计不超过这么多字节，超出时不再放入，用到时现场编码
+pub const MAX_BYTES: usize = 192 << 20;
+
+/// 截图后续动作要用到的编码结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// 复制图片时写入剪贴板的 RGBA 像素
+    ClipboardRgba,
+    /// 保存、分享和上传原图用的 PNG，比缓存中快速编码的体积小
+    Png,
+    /// 上传配置要求的缩小后的 JPEG
+    UploadJpeg { max_side: u32 },
+}
+
+#[derive(Clone)]
+pub enum Prepared {
+    Rgba(Arc<RgbaImage>),
+    Bytes(Arc<Vec<u8>>),
+}
+
+impl Prepared {
+    pub fn size(&self) -> usize {
+        match self {
+            Prepared::Rgba(rgba) => rgba.as_raw().len(),
+            Prepared::Bytes(bytes) => bytes.len(),
+        }
+    }
+
+    pub fn into_rgba(self) -> Result<Arc<RgbaImage>, String> {
+        match self {
+            Prepared::Rgba(rgba) => Ok(rgba),
+            Prepared::Bytes(_) => Err("预编码结果类型不符".into()),
+        }
+    }
+
+    pub fn into_bytes(self) -> Result<Arc<Vec<u8>>, String> {
+        match self {
+            Prepared::Bytes(bytes) => Ok(bytes),
+            Prepared::Rgba(_) => Err("预编码结果类型不符".into()),
+        }
+    }
+}
+
+/// 一张截图某个编辑状态的预编码结果，状态改变时整体丢弃
+#[derive(Default)]
+pub struct Variants {
+    serial: u64,
+    prepared: HashMap<Variant, Prepared>,
+}
+
+impl Variants {
+    pub fn size(&self) -> usize {
+        self.prepared.values().map(Prepared::size).sum()
+    }
+
+    pub fn get(&self, serial: u64, variant: Variant) -> Option<Prepared> {
+        if self.serial != serial {
+            return None;
+        }
+        self.prepared.get(&variant).cloned()
+    }
+
+    /// 放入指定状态的结果；传入较旧状态的由调用方先行排除
+    pub fn put(&mut self, serial: u64, variant: Variant, prepared: Prepared) {
+        if self.serial != serial {
+            self.prepared.clear();
+            self.serial = serial;
+        }
+        self.prepared.insert(variant, prepared);
+    }
+
+    pub fn clear(&mut self) {
+        self.prepared.clear();
+    }
+}
+
+/// 截图脚本和自动打码会改变最终图片，开启时不能直接使用缓存中的截图
+pub fn final_differs(settings: &Settings) -> bool {
+    settings.redact.auto_redact || settings.capture_hooks.iter().any(|hook| hook.enabled)
+}
+
+/// 当前动作列表需要的编码结果
+fn needed(app: &AppHandle, settings: &Settings) -> Vec<Variant> {
+    if final_differs(settings) {
+        return Vec::new();
+    }
+    let mut variants = Vec::new();
+    for action in pipeline::active_actions(settings) {
+        let (base, arg) = pipeline::split_action(&action);
+        let variant = match base {
+            "copy_image" => Variant::ClipboardRgba,
+            "save" | "share" => Variant::Png,
+            "upload" => match pipeline::upload_profile_name(settings, arg)
+                .and_then(|name| upload::find_profile(app, &name).ok())
+                .and_then(|profile| profile.jpeg_max_side)
+            {
+                Some(max_side) => Variant::UploadJpeg { max_side },
+                None => Variant::Png,
+            },
+            _ => continue,
+        };
+        if !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+    variants
+}
+
+pub fn encode(image: &DynamicImage, variant: Variant) -> Result<Prepared, String> {
+    match variant {
+        Variant::ClipboardRgba => Ok(Prepared::Rgba(Arc::new(image.to_rgba8()))),
+        Variant::Png => save::encode_image(image, ExportFormat::Png, save::DEFAULT_QUALITY)
+            .map(|png| Prepared::Bytes(Arc::new(png))),
+        Variant::UploadJpeg { max_side } => {
+            upload::encode_jpeg(image, max_side).map(|jpeg| Prepared::Bytes(Arc::new(jpeg)))
+        }
+    }
+}
+
+/// 缓存中截图当前状态的编码结果：预编码已完成时直接使用，
+/// 尚未完成、已失效或超出内存上限时现场编码
+pub fn get_or_encode(
+    cache: &CaptureCache,
+    id: u64,
+    serial: u64,
+    image: &DynamicImage,
+    variant: Variant,
+) -> Result<Prepared, String> {
+    if let Some(prepared) = cache.variant(id, serial, variant) {
+        tracing::debug!(id, ?variant, "使用预编码结果");
+        return Ok(prepared);
+    }
+    tracing::debug!(id, ?variant, "没有预编码结果，现场编码");
+    encode(image, variant)
+}
+
+/// 截图放入缓存或编辑后调用，在后台线程中为当前状态准备后续动作要用的编码结果，
+/// 完成 finish_capture 时直接交出
+pub fn schedule(app: &AppHandle, id: u64) {
+    let variants = needed(app, &app.state::<SettingsState>().get());
+    if variants.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let cache = app.state::<CaptureCache>();
+        let Ok((serial, image)) = cache.current(id) else {
+            return;
+        };
+        for variant in variants {
+            // 截图已被编辑或丢弃时不再继续
+            if !cache.is_current(id, serial) {
+                return;
+            }
+            let start = Instant::now();
+            match encode(&image, variant) {
+                Ok(prepared) => {
+                    let stored = cache.put_variant(id, serial, variant, prepared);
+                    tracing::debug!(
+                        id,
+                        ?variant,
+                        stored,
+                        duration_ms = start.elapsed().as_millis() as u64,
+                        "预编码完成"
+                    );
+                }
+                Err(err) => tracing::warn!(id, ?variant, error = %err, "预编码失败"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_id::CaptureId;
+    use crate::edits::EditOp;
+    use crate::frame::Frame;
+    use crate::launch::Region;
+    use image::Rgba;
+    use std::time::Duration;
+
+    /// 带噪点的截图，PNG 压缩不掉，编码耗时接近真实截图
+    fn noisy(width: u32, height: u32) -> DynamicImage {
+        let mut seed = 0x2545_f491_u32;
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |_, _| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let [r, g, b, _] = seed.to_le_bytes();
+            Rgba([r, g, b, 255])
+        }))
+    }
+
+    fn cached(cache: &CaptureCache, image: DynamicImage) -> u64 {
+        cache
+            .insert(Frame::Image(image), CaptureId::generate(), None)
+            .unwrap()
+            .id
+    }
+
+    fn bytes(len: usize) -> Prepared {
+        Prepared::Bytes(Arc::new(vec![0; len]))
+    }
+
+    #[test]
+    fn variants_are_keyed_by_capture() {
+        let cache = CaptureCache::default();
+        let a = cached(&cache, noisy(8, 8));
+        let b = cached(&cache, noisy(8, 8));
+        let (serial, _) = cache.current(a).unwrap();
+        assert!(cache.put_variant(a, serial, Variant::Png, bytes(3)));
+        assert!(cache.variant(a, serial, Variant::Png).is_some());
+        // 其他截图和其他编码结果不受影响
+        assert!(cache.variant(b, serial, Variant::Png).is_none());
+        assert!(cache.variant(a, serial, Variant::ClipboardRgba).is_none());
+        // 截图不存在时丢弃
+        assert!(!cache.put_variant(b + 1, serial, Variant::Png, bytes(3)));
+    }
+
+    #[test]
+    fn edits_invalidate_variants() {
+        let cache = CaptureCache::default();
+        let id = cached(&cache, noisy(20, 20));
+        let (serial, image) = cache.current(id).unwrap();
+        let prepared = encode(&image, Variant::ClipboardRgba).unwrap();
+        assert!(cache.put_variant(id, serial, Variant::ClipboardRgba, prepared));
+
+        let region = Region {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+        };
+        cache.edit(id, EditOp::Crop { region }).unwrap();
+        assert!(!cache.is_current(id, serial));
+        assert!(cache.variant(id, serial, Variant::ClipboardRgba).is_none());
+        // 编辑前开始的预编码晚到时不能放入
+        let late = encode(&image, Variant::ClipboardRgba).unwrap();
+        assert!(!cache.put_variant(id, serial, Variant::ClipboardRgba, late));
+        // 现场编码得到编辑后的图
+        let (edited, image) = cache.current(id).unwrap();
+        let rgba = get_or_encode(&cache, id, edited, &image, Variant::ClipboardRgba)
+            .unwrap()
+            .into_rgba()
+            .unwrap();
+        assert_eq!(rgba.dimensions(), (10, 10));
+
+        // 撤销回到原来的状态也重新准备
+        let undone = encode(&image, Variant::Png).unwrap();
+        assert!(cache.put_variant(id, edited, Variant::Png, undone));
+        cache.undo_edit(id).unwrap();
+        assert!(cache.variant(id, edited, Variant::Png).is_none());
+        let (serial, _) = cache.current(id).unwrap();
+        assert!(cache.variant(id, serial, Variant::Png).is_none());
+    }
+
+    #[test]
+    fn memory_is_bounded_and_freed_with_the_capture() {
+        let cache = CaptureCache::default();
+        let first = cached(&cache, noisy(8, 8));
+        let second = cached(&cache, noisy(8, 8));
+        let (serial, _) = cache.current(first).unwrap();
+        let big = Arc::new(vec![0; MAX_BYTES - 100]);
+        assert!(cache.put_variant(first, serial, Variant::Png, Prepared::Bytes(big.clone())));
+        assert_eq!(Arc::strong_count(&big), 2);
+        // 上限按所有截图合计
+        let (other, _) = cache.current(second).unwrap();
+        assert!(!cache.put_variant(second, other, Variant::Png, bytes(200)));
+        assert!(cache.put_variant(second, other, Variant::Png, bytes(100)));
+
+        // 开始新的截图时随旧截图一起丢弃，内存随即释放
+        cache.clear();
+        assert_eq!(Arc::strong_count(&big), 1);
+        let third = cached(&cache, noisy(8, 8));
+        let (serial, _) = cache.current(third).unwrap();
+        assert!(cache.put_variant(third, serial, Variant::Png, bytes(MAX_BYTES)));
+    }
+
+    #[test]
+    fn finish_uses_prepared_bytes_without_encoding() {
+        let cache = CaptureCache::default();
+        let id = cached(&cache, noisy(1920, 1080));
+        let (serial, image) = cache.current(id).unwrap();
+
+        // 没有预编码时 finish_capture 现场编码 PNG
+        let start = Instant::now();
+        let encoded = get_or_encode(&cache, id, serial, &image, Variant::Png).unwrap();
+        let encoding = start.elapsed();
+        assert!(encoding >= Duration::from_millis(20), "{encoding:?}");
+
+        // 与 schedule 相同，后台编码好放入缓存
+        assert!(cache.put_variant(id, serial, Variant::Png, encoded.clone()));
+        let start = Instant::now();
+        let handed = get_or_encode(&cache, id, serial, &image, Variant::Png).unwrap();
+        let prepared = start.elapsed();
+        assert!(prepared < Duration::from_millis(5), "{prepared:?}");
+        assert!(prepared * 20 < encoding, "{prepared:?} vs {encoding:?}");
+        // 交出的就是同一份字节
+        let (handed, encoded) = (handed.into_bytes().unwrap(), encoded.into_bytes().unwrap());
+        assert!(Arc::ptr_eq(&handed, &encoded));
+    }
+}
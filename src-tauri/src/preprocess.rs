@@ -0,0 +1,526 @@
+use image::{imageops::invert, DynamicImage, GrayImage, RgbImage};
+use imageproc::contrast::threshold;
+use imageproc::distance_transform::Norm;
+use imageproc::filter::median_filter;
+use imageproc::morphology::close;
+use rayon::prelude::*;
+
+use crate::error::AppError;
+use crate::timings::Timings;
+
+/// 增强通道时用到的统计：各通道的直方图，以及以每个通道为主时增强值的范围
+struct ChannelStats {
+    histogram: [[u64; 256]; 3],
+    /// 以通道 i 为主时 2 倍增强值（2c - 另两个通道之和）的最小、最大值
+    range: [(i32, i32); 3],
+}
+
+impl Default for ChannelStats {
+    fn default() -> Self {
+        Self {
+            histogram: [[0; 256]; 3],
+            range: [(i32::MAX, i32::MIN); 3],
+        }
+    }
+}
+
+impl ChannelStats {
+    fn add_row(mut self, row: &[u8]) -> Self {
+        for p in row.chunks_exact(3) {
+            let c = [p[0] as i32, p[1] as i32, p[2] as i32];
+            let total = c[0] + c[1] + c[2];
+            for i in 0..3 {
+                self.histogram[i][p[i] as usize] += 1;
+                let v = 3 * c[i] - total;
+                let (min, max) = &mut self.range[i];
+                *min = (*min).min(v);
+                *max = (*max).max(v);
+            }
+        }
+        self
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for i in 0..3 {
+            for (a, b) in self.histogram[i].iter_mut().zip(other.histogram[i]) {
+                *a += b;
+            }
+            self.range[i].0 = self.range[i].0.min(other.range[i].0);
+            self.range[i].1 = self.range[i].1.max(other.range[i].1);
+        }
+        self
+    }
+}
+
+/// 颜色通道增强：对彩色文字（如红色）提升与背景的对比度
+pub fn channel_emphasized_gray(img: &RgbImage) -> GrayImage {
+    let (w, h) = img.dimensions();
+    let n = (w as u64) * (h as u64);
+    let mut out = GrayImage::new(w, h);
+    if n == 0 {
+        return out;
+    }
+    let row_len = w as usize * 3;
+
+    // 第一遍：统计各通道直方图和增强值范围，按行并行
+    let stats = img
+        .as_raw()
+        .par_chunks_exact(row_len)
+        .fold(ChannelStats::default, ChannelStats::add_row)
+        .reduce(ChannelStats::default, ChannelStats::merge);
+
+    // 由直方图得到各通道的均值和对比度（与均值的绝对差之和），选择对比度最高的通道
+    let contrast = stats.histogram.map(|histogram| {
+        let sum: u64 = (0..256u64).map(|v| v * histogram[v as usize]).sum();
+        let mean = (sum / n) as i64;
+        (0..256i64)
+            .map(|v| (v - mean).unsigned_abs() * histogram[v as usize])
+            .sum::<u64>()
+    });
+    let best = (0..3).max_by_key(|&i| contrast[i]).unwrap_or(0);
+    let (others0, others1) = match best {
+        0 => (1, 2),
+        1 => (0, 2),
+        _ => (0, 1),
+    };
+
+    // 纯色或灰度图片的增强值处处相同，没有可拉伸的范围，直接按亮度转换
+    let (min_v, max_v) = stats.range[best];
+    if min_v == max_v {
+        return image::imageops::grayscale(img);
+    }
+
+    // 第二遍：计算增强值（如 R - 0.5G - 0.5B 增强红色文字）并线性拉伸到 0-255，直接写入结果
+    let min_v = min_v as f32 / 2.0;
+    let span = (max_v as f32 / 2.0 - min_v).max(1.0);
+    out.par_chunks_exact_mut(w as usize)
+        .zip(img.as_raw().par_chunks_exact(row_len))
+        .for_each(|(dst, src)| {
+            for (d, p) in dst.iter_mut().zip(src.chunks_exact(3)) {
+                let v = p[best] as f32 - 0.5 * p[others0] as f32 - 0.5 * p[others1] as f32;
+                *d = ((v - min_v) / span * 255.0).clamp(0.0, 255.0) as u8;
+            }
+        });
+    out
+}
+
+/// 根据二值化后的像素占比判断是否为暗底亮字
+fn is_dark_background(binary: &GrayImage) -> bool {
+    let (mut dark, mut light) = (0usize, 0usize);
+    for p in binary.pixels() {
+        if p[0] < 128 {
+            dark += 1;
+        } else {
+            light += 1;
+        }
+    }
+    dark > light
+}
+
+/// Otsu 阈值，算法与 imageproc::contrast::otsu_level 相同。
+///
+/// imageproc 用 u32 计算灰度值与像素数的乘积，放大后的大图（如 4K 截图）白色像素超过约 1600 万时会溢出
+fn otsu_level(image: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for &p in image.as_raw() {
+        histogram[p as usize] += 1;
+    }
+    let total_weight = image.as_raw().len() as u64;
+    let total_pixel_sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(t, &count)| (t as u64 * count) as f64)
+        .sum();
+
+    let mut background_pixel_sum = 0f64;
+    let mut background_weight = 0u64;
+    let mut largest_variance = 0f64;
+    let mut best_threshold = 0u8;
+    for (t, &count) in histogram.iter().enumerate() {
+        background_weight += count;
+        if background_weight == 0 {
+            continue;
+        }
+        let foreground_weight = total_weight - background_weight;
+        if foreground_weight == 0 {
+            break;
+        }
+        background_pixel_sum += (t as u64 * count) as f64;
+        let background_mean = background_pixel_sum / background_weight as f64;
+        let foreground_mean = (total_pixel_sum - background_pixel_sum) / foreground_weight as f64;
+        let variance = background_weight as f64
+            * foreground_weight as f64
+            * (background_mean - foreground_mean).powi(2);
+        if variance > largest_variance {
+            largest_variance = variance;
+            best_threshold = t as u8;
+        }
+    }
+    best_threshold
+}
+
+/// 识别输入的像素上限，超过时先缩小；放大 2 倍后每个中间结果都是输入的 4 倍
+pub const MAX_OCR_PIXELS: u64 = 16_000_000;
+/// 识别输入的最小边长，更小的选区里不可能有可识别的文字，且会让滤波和放大退化
+const MIN_OCR_SIDE: u32 = 4;
+
+pub fn check_ocr_size(width: u32, height: u32) -> Result<(), AppError> {
+    if width < MIN_OCR_SIDE || height < MIN_OCR_SIDE {
+        return Err(AppError::InvalidInput(format!(
+            "图片太小（{width}×{height}），至少需要 {MIN_OCR_SIDE}×{MIN_OCR_SIDE} 像素"
+        )));
+    }
+    Ok(())
+}
+
+/// 放大时每段处理的源图行数
+const UPSCALE_BAND_ROWS: u32 = 64;
+/// Lanczos3 放大 2 倍时每侧需要的额外源图行数
+const UPSCALE_BAND_MARGIN: u32 = 4;
+
+/// Lanczos3 放大 2 倍，按行分段进行。
+///
+/// imageops::resize 的中间结果是每像素 16 字节的浮点图，整幅放大一张 4K 截图要多占用约 500MB；
+/// 分段时每段多取几行作为边缘，结果与整幅放大完全一致
+pub fn upscale_2x(src: &GrayImage) -> GrayImage {
+    let (w, h) = src.dimensions();
+    let mut out = GrayImage::new(w * 2, h * 2);
+    let band_len = (w as usize * 2) * (UPSCALE_BAND_ROWS as usize * 2);
+    out.par_chunks_mut(band_len.max(1))
+        .enumerate()
+        .for_each(|(band, dst)| {
+            let y0 = band as u32 * UPSCALE_BAND_ROWS;
+            let top = y0.saturating_sub(UPSCALE_BAND_MARGIN);
+            let bottom = (y0 + UPSCALE_BAND_ROWS + UPSCALE_BAND_MARGIN).min(h);
+            let part = image::imageops::crop_imm(src, 0, top, w, bottom - top);
+            let resized = image::imageops::resize(
+                &*part,
+                w * 2,
+                (bottom - top) * 2,
+                image::imageops::FilterType::Lanczos3,
+            );
+            let skip = (y0 - top) as usize * 2 * (w as usize * 2);
+            dst.copy_from_slice(&resized.as_raw()[skip..skip + dst.len()]);
+        });
+    out
+}
+
+/// 超过像素上限的图片按比例缩小，返回缩小后的图片和提示
+pub fn limit_ocr_pixels(dyn_img: &DynamicImage) -> Option<(DynamicImage, String)> {
+    let (w, h) = (dyn_img.width(), dyn_img.height());
+    let pixels = w as u64 * h as u64;
+    if pixels <= MAX_OCR_PIXELS {
+        return None;
+    }
+    let scale = (MAX_OCR_PIXELS as f64 / pixels as f64).sqrt();
+    let new_w = ((w as f64 * scale) as u32).max(1);
+    let new_h = ((h as f64 * scale) as u32).max(1);
+    tracing::warn!(
+        width = w,
+        height = h,
+        new_width = new_w,
+        new_height = new_h,
+        "识别图片过大，先缩小"
+    );
+    // thumbnail 按区域取平均，不需要浮点中间图
+    let downscaled = dyn_img.thumbnail_exact(new_w, new_h);
+    let warning = format!("图片过大（{w}×{h}），已缩小到 {new_w}×{new_h} 后识别，小字可能识别不准");
+    Some((downscaled, warning))
+}
+
+/// 图像预处理：颜色增强→放大→去噪→自适应二值化→闭运算→暗底反转。
+///
+/// 每个阶段结束后立即释放上一阶段的图片；超过像素上限的输入先缩小，同时返回提示
+pub fn preprocess_for_ocr(
+    dyn_img: &DynamicImage,
+    timings: &mut Timings,
+) -> Result<(GrayImage, Option<String>), AppError> {
+    check_ocr_size(dyn_img.width(), dyn_img.height())?;
+    let limited = timings.time("downscale", || limit_ocr_pixels(dyn_img));
+    let (dyn_img, warning) = match &limited {
+        Some((downscaled, warning)) => (downscaled, Some(warning.clone())),
+        None => (dyn_img, None),
+    };
+    let rgb = timings.time("to_rgb", || dyn_img.to_rgb8());
+    drop(limited);
+
+    // 颜色增强的灰度转换
+    let enhanced_gray = timings.time("channel_emphasis", || channel_emphasized_gray(&rgb));
+    drop(rgb);
+
+    // 2倍放大，提升小字识别率
+    let resized = timings.time("resize", || upscale_2x(&enhanced_gray));
+    drop(enhanced_gray);
+
+    // 中值滤波去噪（保边缘）
+    let denoised = timings.time("median", || median_filter(&resized, 1, 1));
+    drop(resized);
+
+    // Otsu 自适应阈值二值化
+    let binary = timings.time("threshold", || {
+        let thr = otsu_level(&denoised);
+        threshold(&denoised, thr, imageproc::contrast::ThresholdType::Binary)
+    });
+    drop(denoised);
+
+    // 闭运算填补细笔画断裂；若为暗底亮字则反转，使之变为白底黑字
+    let processed = timings.time("close", || {
+        let mut closed = close(&binary, Norm::L1, 1);
+        if is_dark_background(&closed) {
+            invert(&mut closed);
+        }
+        closed
+    });
+    Ok((processed, warning))
+}
+
+/// 关闭预处理时只限制像素数并转为灰度
+pub fn gray_for_ocr(
+    dyn_img: &DynamicImage,
+    timings: &mut Timings,
+) -> Result<(GrayImage, Option<String>), AppError> {
+    check_ocr_size(dyn_img.width(), dyn_img.height())?;
+    let limited = timings.time("downscale", || limit_ocr_pixels(dyn_img));
+    let (dyn_img, warning) = match &limited {
+        Some((downscaled, warning)) => (downscaled, Some(warning.clone())),
+        None => (dyn_img, None),
+    };
+    Ok((timings.time("to_gray", || dyn_img.to_luma8()), warning))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{imageops, Luma, Pixel, Rgb};
+
+    /// 固定种子的伪随机数，测试图片每次相同
+    fn noise(seed: u32) -> impl FnMut() -> u8 {
+        let mut state = seed.wrapping_mul(2_654_435_761) | 1;
+        move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 24) as u8
+        }
+    }
+
+    fn noisy_rgb(w: u32, h: u32, seed: u32) -> RgbImage {
+        let mut next = noise(seed);
+        RgbImage::from_fn(w, h, |_, _| Rgb([next(), next(), next()]))
+    }
+
+    fn noisy_gray(w: u32, h: u32, seed: u32) -> GrayImage {
+        let mut next = noise(seed);
+        GrayImage::from_fn(w, h, |_, _| Luma([next()]))
+    }
+
+    /// 白底红字，红色通道对比度最高
+    fn red_text(w: u32, h: u32) -> RgbImage {
+        RgbImage::from_fn(w, h, |x, y| {
+            if (x / 3 + y / 5) % 4 == 0 {
+                Rgb([200, 30, 40])
+            } else {
+                Rgb([245, 245, 240])
+            }
+        })
+    }
+
+    /// 原来三遍遍历、使用浮点中间数组的实现，作为对照
+    fn reference_emphasized_gray(img: &RgbImage) -> GrayImage {
+        let (w, h) = img.dimensions();
+        let n = (w as u64) * (h as u64);
+        let mut sum = [0u64; 3];
+        for p in img.pixels() {
+            for (s, c) in sum.iter_mut().zip(p.channels()) {
+                *s += *c as u64;
+            }
+        }
+        let mean = sum.map(|s| (s / n) as f32);
+        let mut contrast = [0f32; 3];
+        for p in img.pixels() {
+            for i in 0..3 {
+                contrast[i] += (p.channels()[i] as f32 - mean[i]).abs();
+            }
+        }
+        let best = contrast
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let values: Vec<f32> = img
+            .pixels()
+            .map(|p| {
+                let [r, g, b] = p.0.map(|c| c as f32);
+                match best {
+                    0 => r - 0.5 * g - 0.5 * b,
+                    1 => g - 0.5 * r - 0.5 * b,
+                    _ => b - 0.5 * r - 0.5 * g,
+                }
+            })
+            .collect();
+        let min_v = values.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max_v = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let span = (max_v - min_v).max(1.0);
+        let mut out = GrayImage::new(w, h);
+        for (i, v) in values.iter().enumerate() {
+            let norm = ((v - min_v) / span * 255.0).clamp(0.0, 255.0) as u8;
+            out.put_pixel(i as u32 % w, i as u32 / w, Luma([norm]));
+        }
+        out
+    }
+
+    #[test]
+    fn emphasized_gray_matches_reference() {
+        let sizes = [(1, 2), (2, 1), (17, 3), (64, 64), (100, 65), (257, 130)];
+        for (seed, (w, h)) in sizes.into_iter().enumerate() {
+            let img = noisy_rgb(w, h, seed as u32);
+            assert_eq!(
+                channel_emphasized_gray(&img),
+                reference_emphasized_gray(&img),
+                "{w}×{h}"
+            );
+        }
+        let img = red_text(300, 77);
+        assert_eq!(
+            channel_emphasized_gray(&img),
+            reference_emphasized_gray(&img)
+        );
+    }
+
+    #[test]
+    fn banded_upscale_matches_whole_image_resize() {
+        // 覆盖不足一段、恰好整段、跨段边界和末段不满 64 行的高度
+        let heights = [1, 2, 5, 63, 64, 65, 67, 128, 129, 200];
+        for (seed, h) in heights.into_iter().enumerate() {
+            for w in [1, 9, 70] {
+                let src = noisy_gray(w, h, seed as u32 * 7 + w);
+                let whole = imageops::resize(&src, w * 2, h * 2, imageops::FilterType::Lanczos3);
+                assert_eq!(upscale_2x(&src), whole, "{w}×{h}");
+            }
+        }
+    }
+
+    #[test]
+    fn upscale_of_empty_image_is_empty() {
+        assert_eq!(upscale_2x(&GrayImage::new(0, 0)).dimensions(), (0, 0));
+        assert_eq!(upscale_2x(&GrayImage::new(5, 0)).dimensions(), (10, 0));
+    }
+
+    #[test]
+    fn otsu_level_matches_imageproc() {
+        for (seed, (w, h)) in [(1, 1), (3, 7), (64, 64), (200, 90)]
+            .into_iter()
+            .enumerate()
+        {
+            let img = noisy_gray(w, h, seed as u32 + 100);
+            assert_eq!(otsu_level(&img), imageproc::contrast::otsu_level(&img));
+        }
+        let text = red_text(120, 80);
+        let gray = channel_emphasized_gray(&text);
+        assert_eq!(otsu_level(&gray), imageproc::contrast::otsu_level(&gray));
+    }
+
+    #[test]
+    fn otsu_level_handles_large_bright_images() {
+        // 白色像素数 × 255 超出 u32
+        let mut img = GrayImage::from_pixel(5000, 4000, Luma([255]));
+        for p in img.as_mut().iter_mut().step_by(7) {
+            *p = 10;
+        }
+        assert_eq!(otsu_level(&img), 10);
+    }
+
+    #[test]
+    fn oversized_input_is_downscaled_with_a_warning() {
+        // 恰好在上限内的图片原样使用
+        let at_limit = DynamicImage::new_luma8(4000, 4000);
+        assert!(limit_ocr_pixels(&at_limit).is_none());
+
+        let img = DynamicImage::new_luma8(8000, 2001);
+        let (downscaled, warning) = limit_ocr_pixels(&img).expect("超过上限应缩小");
+        let (w, h) = (downscaled.width(), downscaled.height());
+        assert!(w as u64 * h as u64 <= MAX_OCR_PIXELS);
+        assert_eq!((w, h), (7998, 2000));
+        assert!(warning.contains("8000×2001"));
+        assert!(warning.contains("7998×2000"));
+    }
+
+    #[test]
+    fn tiny_inputs_are_rejected() {
+        for (w, h) in [(0, 0), (1, 1), (1, 500), (500, 1), (3, 100), (0, 40)] {
+            let err = check_ocr_size(w, h).expect_err("过小的图片应被拒绝");
+            assert!(matches!(err, AppError::InvalidInput(_)), "{w}×{h}: {err:?}");
+            // 预处理前就拒绝，不会在滤波或放大时出错
+            let img = DynamicImage::new_rgb8(w, h);
+            let mut timings = Timings::default();
+            assert!(preprocess_for_ocr(&img, &mut timings).is_err(), "{w}×{h}");
+            assert!(gray_for_ocr(&img, &mut timings).is_err(), "{w}×{h}");
+        }
+        assert!(check_ocr_size(MIN_OCR_SIDE, MIN_OCR_SIDE).is_ok());
+        assert!(check_ocr_size(MIN_OCR_SIDE, 5000).is_ok());
+    }
+
+    #[test]
+    fn solid_colour_falls_back_to_grayscale() {
+        // 纯色图片各通道增强值处处相同，按亮度转换
+        for colour in [[200, 30, 30], [0, 0, 0], [255, 255, 255], [12, 90, 240]] {
+            let img = RgbImage::from_pixel(23, 9, Rgb(colour));
+            assert_eq!(
+                channel_emphasized_gray(&img),
+                imageops::grayscale(&img),
+                "{colour:?}"
+            );
+        }
+        // 灰度图片的增强值同样全为 0
+        let gray = RgbImage::from_fn(40, 6, |x, _| Rgb([(x * 6) as u8; 3]));
+        assert_eq!(channel_emphasized_gray(&gray), imageops::grayscale(&gray));
+    }
+
+    #[test]
+    fn solid_colour_preprocesses_to_a_uniform_image() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([30, 60, 200])));
+        let (processed, warning) = preprocess_for_ocr(&img, &mut Timings::default()).unwrap();
+        assert!(warning.is_none());
+        assert_eq!(processed.dimensions(), (8, 8));
+        let first = processed.as_raw()[0];
+        assert!(processed.as_raw().iter().all(|&p| p == first));
+    }
+
+    /// 进程的峰值常驻内存（KiB）
+    #[cfg(target_os = "linux")]
+    fn peak_rss_kib() -> u64 {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[cfg_attr(
+        debug_assertions,
+        ignore = "未优化构建下需要数分钟，用 cargo test --release 运行"
+    )]
+    fn huge_input_stays_within_memory_budget() {
+        // 8000×8000 的 RGB 输入本身占 192MB；不先缩小时放大后的各阶段中间结果合计远超 1GB
+        const BUDGET_KIB: u64 = 512 * 1024;
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(8000, 8000, |x, y| {
+            if (x / 6 + y / 9) % 5 == 0 {
+                Rgb([20, 20, 20])
+            } else {
+                Rgb([235, 235, 230])
+            }
+        }));
+        // 清零峰值统计，只计算预处理期间的增长
+        let _ = std::fs::write("/proc/self/clear_refs", "5");
+        let before = peak_rss_kib();
+        let (processed, warning) = preprocess_for_ocr(&img, &mut Timings::default()).unwrap();
+        let grown = peak_rss_kib().saturating_sub(before);
+        assert!(warning.is_some());
+        assert!(processed.width() as u64 * processed.height() as u64 <= 4 * MAX_OCR_PIXELS);
+        assert!(grown < BUDGET_KIB, "峰值内存增长 {} MiB", grown / 1024);
+    }
+}
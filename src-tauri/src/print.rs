@@ -0,0 +1,179 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::external::{self, ExternalTool};
+
+/// 交给打印队列或系统打印动词的最长等待时间
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PrintOptions {
+    pub copies: u32,
+    pub fit_to_page: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            copies: 1,
+            fit_to_page: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PrintError {
+    InvalidInput {
+        detail: String,
+    },
+    /// 系统中没有可用的打印方式
+    NoPrintMechanism {
+        hint: String,
+    },
+    Failed {
+        detail: String,
+    },
+}
+
+#[derive(Serialize)]
+pub struct PrintResult {
+    /// 使用的打印方式（lp / lpr / xdg-open / shell）
+    pub method: &'static str,
+    /// 打印队列任务号，仅 lp/lpr 可用
+    pub job_id: Option<String>,
+}
+
+fn write_temp_png(app: &AppHandle, png: &[u8]) -> Result<PathBuf, PrintError> {
+    let dir = crate::storage::temp_dir(app).unwrap_or_else(std::env::temp_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| PrintError::Failed {
+        detail: e.to_string(),
+    })?;
+    let path = dir.join(format!(
+        "print_{}.png",
+        chrono::Local::now().timestamp_millis()
+    ));
+    crate::durable::atomic_write(&path, png).map_err(|detail| PrintError::Failed { detail })?;
+    Ok(path)
+}
+
+/// lp 输出形如 "request id is Printer-12 (1 file(s))"
+fn parse_lp_job_id(stdout: &str) -> Option<String> {
+    stdout
+        .split("request id is ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(str::to_string)
+}
+
+/// method 为结果中记录的打印方式（lp / lpr）
+fn run_spooler(
+    tool: ExternalTool,
+    method: &'static str,
+    args: &[String],
+    file: &Path,
+) -> Result<PrintResult, PrintError> {
+    let args = args.iter().map(OsStr::new).chain([file.as_os_str()]);
+    let output = external::run_external(tool, args, None, SUBMIT_TIMEOUT).map_err(|e| {
+        PrintError::Failed {
+            detail: e.to_string(),
+        }
+    })?;
+    Ok(PrintResult {
+        method,
+        job_id: parse_lp_job_id(&String::from_utf8_lossy(&output.stdout)),
+    })
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn print_file(file: &Path, options: &PrintOptions) -> Result<PrintResult, PrintError> {
+    if ExternalTool::Lp.available() {
+        let mut args = vec!["-n".to_string(), options.copies.max(1).to_string()];
+        if options.fit_to_page {
+            args.extend(["-o".to_string(), "fit-to-page".to_string()]);
+        }
+        return run_spooler(ExternalTool::Lp, "lp", &args, file);
+    }
+
+    // 没有 CUPS 客户端时交给默认看图程序，由用户在其中打印
+    if let Ok(mut command) = external::command(&ExternalTool::XdgOpen) {
+        command.arg(file).spawn().map_err(|e| PrintError::Failed {
+            detail: format!("xdg-open: {e}"),
+        })?;
+        return Ok(PrintResult {
+            method: "xdg-open",
+            job_id: None,
+        });
+    }
+
+    Err(PrintError::NoPrintMechanism {
+        hint: "未找到 lp，请安装 cups-client（sudo apt install cups-client）".into(),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn print_file(file: &Path, options: &PrintOptions) -> Result<PrintResult, PrintError> {
+    let mut args = vec![format!("-#{}", options.copies.max(1))];
+    if options.fit_to_page {
+        args.extend(["-o".to_string(), "fit-to-page".to_string()]);
+    }
+    run_spooler(ExternalTool::Lpr, "lpr", &args, file)
+}
+
+#[cfg(target_os = "windows")]
+fn print_file(file: &Path, options: &PrintOptions) -> Result<PrintResult, PrintError> {
+    // 使用系统关联程序的 Print 动词，份数与缩放由打印对话框决定
+    let _ = options;
+    let script = format!(
+        "Start-Process -FilePath '{}' -Verb Print",
+        file.display().to_string().replace('\'', "''")
+    );
+    let result = external::run_external(
+        ExternalTool::PowerShell,
+        ["-NoProfile", "-Command", &script],
+        None,
+        SUBMIT_TIMEOUT,
+    );
+    match result {
+        Ok(_) => {}
+        Err(external::ExternalError::NotFound { .. }) => {
+            return Err(PrintError::NoPrintMechanism {
+                hint: "未找到 PowerShell".into(),
+            })
+        }
+        Err(err) => {
+            return Err(PrintError::Failed {
+                detail: format!("Print 动词执行失败: {err}"),
+            })
+        }
+    }
+    Ok(PrintResult {
+        method: "shell",
+        job_id: None,
+    })
+}
+
+/// 打印截图：写入临时 PNG 后交给系统打印
+#[tauri::command]
+pub fn print_image(
+    app: AppHandle,
+    base64_data: String,
+    options: Option<PrintOptions>,
+) -> Result<PrintResult, PrintError> {
+    let png = STANDARD
+        .decode(&base64_data)
+        .map_err(|e| PrintError::InvalidInput {
+            detail: e.to_string(),
+        })?;
+    image::guess_format(&png).map_err(|e| PrintError::InvalidInput {
+        detail: e.to_string(),
+    })?;
+
+    let file = write_temp_png(&app, &png)?;
+    print_file(&file, &options.unwrap_or_default())
+}
@@ -0,0 +1,169 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{DynamicImage, GrayImage, Luma};
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+
+/// 二维码四周留白的模块数（规范要求至少 4 个）
+const QUIET_ZONE: u32 = 4;
+const DEFAULT_MODULE_PX: u32 = 8;
+const MAX_MODULE_PX: u32 = 32;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCorrection {
+    /// 约 7% 容错
+    L,
+    /// 约 15% 容错
+    #[default]
+    M,
+    /// 约 25% 容错
+    Q,
+    /// 约 30% 容错
+    H,
+}
+
+impl ErrorCorrection {
+    fn level(self) -> EcLevel {
+        match self {
+            ErrorCorrection::L => EcLevel::L,
+            ErrorCorrection::M => EcLevel::M,
+            ErrorCorrection::Q => EcLevel::Q,
+            ErrorCorrection::H => EcLevel::H,
+        }
+    }
+
+    /// 最大版本（40）按字节模式能容纳的字节数；纯数字、大写字母数字的内容能放下更多
+    fn max_bytes(self) -> usize {
+        match self {
+            ErrorCorrection::L => 2953,
+            ErrorCorrection::M => 2331,
+            ErrorCorrection::Q => 1663,
+            ErrorCorrection::H => 1273,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct QrOptions {
+    pub error_correction: ErrorCorrection,
+    /// 同时把二维码图片复制到剪贴板
+    pub copy: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QrError {
+    InvalidInput {
+        detail: String,
+    },
+    /// 内容超出二维码容量，max_bytes 为所选容错等级下的上限
+    TooLong {
+        bytes: usize,
+        max_bytes: usize,
+        error_correction: ErrorCorrection,
+    },
+    Failed {
+        detail: String,
+    },
+}
+
+impl std::fmt::Display for QrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QrError::InvalidInput { detail } => write!(f, "参数错误: {detail}"),
+            QrError::TooLong {
+                bytes, max_bytes, ..
+            } => write!(
+                f,
+                "内容过长（{bytes} 字节），二维码最多容纳 {max_bytes} 字节"
+            ),
+            QrError::Failed { detail } => write!(f, "生成二维码失败: {detail}"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct QrImage {
+    /// PNG 图片的 base64
+    pub png_base64: String,
+    /// 图片边长（像素）
+    pub size: u32,
+    /// 不含留白的模块数
+    pub modules: u32,
+}
+
+/// 黑白模块放大 module_px 倍，四周留出 QUIET_ZONE 个模块的白边
+fn render(code: &QrCode, module_px: u32) -> GrayImage {
+    let modules = code.width() as u32;
+    let colors = code.to_colors();
+    let side = (modules + QUIET_ZONE * 2) * module_px;
+    GrayImage::from_fn(side, side, |x, y| {
+        let (mx, my) = (x / module_px, y / module_px);
+        let inside = (QUIET_ZONE..QUIET_ZONE + modules).contains(&mx)
+            && (QUIET_ZONE..QUIET_ZONE + modules).contains(&my);
+        let dark = inside
+            && colors[((my - QUIET_ZONE) * modules + (mx - QUIET_ZONE)) as usize] == Color::Dark;
+        Luma([if dark { 0 } else { 255 }])
+    })
+}
+
+/// 生成二维码图片，返回图片和模块数
+fn generate(
+    text: &str,
+    module_px: u32,
+    ec: ErrorCorrection,
+) -> Result<(DynamicImage, u32), QrError> {
+    if text.is_empty() {
+        return Err(QrError::InvalidInput {
+            detail: "内容为空".into(),
+        });
+    }
+    if !(1..=MAX_MODULE_PX).contains(&module_px) {
+        return Err(QrError::InvalidInput {
+            detail: format!("模块大小必须在 1 到 {MAX_MODULE_PX} 像素之间"),
+        });
+    }
+    let code = QrCode::with_error_correction_level(text, ec.level()).map_err(|e| match e {
+        qrcode::types::QrError::DataTooLong => QrError::TooLong {
+            bytes: text.len(),
+            max_bytes: ec.max_bytes(),
+            error_correction: ec,
+        },
+        e => QrError::Failed {
+            detail: e.to_string(),
+        },
+    })?;
+    Ok((render(&code, module_px).into(), code.width() as u32))
+}
+
+/// 把文字（如识别出的链接、上传后的地址）生成二维码；size 为每个模块的像素数
+#[tauri::command]
+pub async fn generate_qr(
+    text: String,
+    size: Option<u32>,
+    options: Option<QrOptions>,
+) -> Result<QrImage, QrError> {
+    let options = options.unwrap_or_default();
+    let module_px = size.unwrap_or(DEFAULT_MODULE_PX);
+    let failed = |detail: String| QrError::Failed { detail };
+    tauri::async_runtime::spawn_blocking(move || {
+        let (image, modules) = generate(&text, module_px, options.error_correction)?;
+        if options.copy {
+            crate::clipboard::set_image(&image).map_err(|e| failed(e.message()))?;
+        }
+        let png = crate::save::encode_image(
+            &image,
+            crate::save::ExportFormat::Png,
+            crate::save::DEFAULT_QUALITY,
+        )
+        .map_err(failed)?;
+        Ok(QrImage {
+            png_base64: STANDARD.encode(png),
+            size: image.width(),
+            modules,
+        })
+    })
+    .await
+    .map_err(|e| failed(e.to_string()))?
+}
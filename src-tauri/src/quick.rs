@@ -0,0 +1,698 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::capture::CaptureObserver;
+use crate::capture_cache::{CaptureCache, CaptureHandle};
+use crate::capture_id::{self, CaptureEvents, CaptureId};
+use crate::commands::Recording;
+use crate::error::AppError;
+use crate::error_report::{self, ErrorSource, Failure};
+use crate::frame::Frame;
+use crate::launch::Region;
+use crate::notify::{self, CopyPayload, Notice, NotifyEvent};
+use crate::ocr_queue::{self, JobInput, JobPriority, JobStatus, OcrQueue, Recognized};
+use crate::session;
+use crate::webhook::{self, WebhookEvent};
+
+/// 按选区预设确定显示器与区域；没有预设时沿用传入的值
+fn preset_target(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+    preset: Option<&str>,
+) -> Result<(Option<u32>, Option<Region>), AppError> {
+    match preset {
+        Some(name) => {
+            let resolved = crate::selection_preset::resolve(app, name)?;
+            Ok((Some(resolved.monitor), Some(resolved.region)))
+        }
+        None => Ok((monitor, region)),
+    }
+}
+
+/// 整屏（或 region 指定的区域）截图直接复制到剪贴板，不显示遮罩；结果只通过通知和托盘提示反馈
+pub fn quick_capture(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+) -> Result<(), String> {
+    let _busy = crate::tray::busy(app);
+    let result = capture_area_without_windows(app, monitor, region).and_then(|frame| {
+        let frame = match region {
+            Some(region) => crate::headless::crop(frame, region)?,
+            None => frame,
+        };
+        // xcap 的截图直接写入剪贴板，不经过 PNG 编解码
+        let image = frame.into_image()?;
+        crate::clipboard::set_image(&image)?;
+        Ok(image)
+    });
+    match result {
+        Ok(image) => {
+            crate::tray::record(app, "tray.last_capture", "result.copied", true);
+            let body = if region.is_some() {
+                "选区已复制到剪贴板"
+            } else {
+                "整屏已复制到剪贴板"
+            };
+            let mut notice = Notice::new(NotifyEvent::Capture, "截图完成", body);
+            notice.thumbnail = Some(image);
+            notify::notify(app, notice);
+            Ok(())
+        }
+        Err(err) => {
+            error_report::report(
+                app,
+                Failure::new(
+                    ErrorSource::Capture,
+                    "capture_failed",
+                    "截图失败",
+                    err.clone(),
+                ),
+            );
+            Err(err)
+        }
+    }
+}
+
+/// 整屏（或 region 指定的区域）截图后按文件名模板保存到自动保存目录，返回保存的路径；
+/// 不经过前端，界面无法加载时托盘和快捷键仍可使用
+pub fn quick_capture_to_file(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+) -> Result<PathBuf, String> {
+    let _busy = crate::tray::busy(app);
+    // 不经过截图界面，焦点仍在被截图的应用上
+    let active_app = crate::active_app::get_active_app_info().map(|a| a.name);
+    let result = capture_area_without_windows(app, monitor, region).and_then(|frame| {
+        let frame = match region {
+            Some(region) => crate::headless::crop(frame, region)?,
+            None => frame,
+        };
+        let png = frame.into_png()?;
+        let settings = app.state::<crate::settings::SettingsState>().get();
+        crate::pipeline::save_png(app, &settings, &png, active_app.as_deref())
+    });
+    match result {
+        Ok(path) => {
+            crate::tray::record(app, "tray.last_capture", "result.saved", true);
+            let mut notice = Notice::new(
+                NotifyEvent::Capture,
+                "截图完成",
+                format!("已保存到 {}", path.display()),
+            );
+            notice.file = Some(path.clone());
+            notify::notify(app, notice);
+            Ok(path)
+        }
+        Err(err) => {
+            error_report::report(
+                app,
+                Failure::new(
+                    ErrorSource::Capture,
+                    "capture_failed",
+                    "截图失败",
+                    err.clone(),
+                ),
+            );
+            Err(err)
+        }
+    }
+}
+
+/// 等待窗口报告已隐藏的上限
+const UNMAP_TIMEOUT: Duration = Duration::from_millis(300);
+/// 窗口隐藏后再等合成器重绘一帧
+const UNMAP_SETTLE: Duration = Duration::from_millis(40);
+
+/// 截图前可能要隐藏的本程序窗口
+trait OwnWindow {
+    fn hide_window(&self);
+    fn show_window(&self);
+    fn visible(&self) -> bool;
+}
+
+impl OwnWindow for WebviewWindow {
+    fn hide_window(&self) {
+        let _ = self.hide();
+    }
+
+    fn show_window(&self) {
+        let _ = self.show();
+    }
+
+    fn visible(&self) -> bool {
+        self.is_visible().unwrap_or(false)
+    }
+}
+
+/// 截图期间隐藏的本程序窗口，离开作用域时按相反顺序恢复显示，截图出错时同样恢复
+struct HiddenWindows<W: OwnWindow>(Vec<W>);
+
+impl<W: OwnWindow> HiddenWindows<W> {
+    fn hide(windows: Vec<W>) -> Self {
+        for window in &windows {
+            window.hide_window();
+        }
+        if !windows.is_empty() {
+            let started = Instant::now();
+            while windows.iter().any(|w| w.visible()) && started.elapsed() < UNMAP_TIMEOUT {
+                thread::sleep(Duration::from_millis(10));
+            }
+            thread::sleep(UNMAP_SETTLE);
+            tracing::debug!(
+                count = windows.len(),
+                wait_ms = started.elapsed().as_millis() as u64,
+                "截图前已隐藏本程序窗口"
+            );
+        }
+        Self(windows)
+    }
+}
+
+impl<W: OwnWindow> Drop for HiddenWindows<W> {
+    fn drop(&mut self) {
+        for window in self.0.iter().rev() {
+            window.show_window();
+        }
+    }
+}
+
+/// 截图范围在桌面上的物理像素坐标 (x, y, 宽, 高)；无法确定时返回 None，按整个桌面处理
+fn capture_bounds(monitor: Option<u32>, region: Option<Region>) -> Option<(i32, i32, u32, u32)> {
+    let (x, y, width, height) = match monitor {
+        Some(id) => {
+            let monitor = xcap::Monitor::all()
+                .ok()?
+                .into_iter()
+                .find(|m| m.id().ok() == Some(id))?;
+            (
+                monitor.x().ok()?,
+                monitor.y().ok()?,
+                monitor.width().ok()?,
+                monitor.height().ok()?,
+            )
+        }
+        None => (0, 0, u32::MAX, u32::MAX),
+    };
+    Some(match region {
+        Some(r) => (
+            x.saturating_add(r.x as i32),
+            y.saturating_add(r.y as i32),
+            r.width,
+            r.height,
+        ),
+        None => (x, y, width, height),
+    })
+}
+
+fn intersects(window: &WebviewWindow, bounds: Option<(i32, i32, u32, u32)>) -> bool {
+    let Some((x, y, width, height)) = bounds else {
+        return true;
+    };
+    let (Ok(pos), Ok(size)) = (window.outer_position(), window.outer_size()) else {
+        return true;
+    };
+    let (left, top) = (pos.x as i64, pos.y as i64);
+    let (right, bottom) = (left + size.width as i64, top + size.height as i64);
+    left < x as i64 + width as i64
+        && right > x as i64
+        && top < y as i64 + height as i64
+        && bottom > y as i64
+}
+
+/// 先隐藏本程序可见的窗口（包括正在显示的遮罩），截图后恢复
+pub fn capture_without_windows(app: &AppHandle, monitor: Option<u32>) -> Result<Frame, String> {
+    capture_area_without_windows(app, monitor, None)
+}
+
+/// 不经过遮罩的截图统一走这里：只隐藏与截图范围相交的本程序窗口，
+/// 等窗口隐藏后截图，截图结束（包括失败）后恢复显示。region 相对于 monitor 左上角
+pub fn capture_area_without_windows(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+) -> Result<Frame, String> {
+    let events = CaptureEvents::start(app, CaptureId::generate());
+    capture_area_with_events(app, monitor, region, &events, &Recording(&events))
+}
+
+/// 文字、画面监视的定时截图：同 capture_area_without_windows，但不计入使用统计和错误报告
+pub fn poll_area_without_windows(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+) -> Result<Frame, String> {
+    let events = CaptureEvents::start(app, CaptureId::generate());
+    capture_area_with_events(app, monitor, region, &events, &events)
+}
+
+/// 同 capture_area_without_windows，截图事件使用调用方的截图编号，
+/// 截图结果交给 observer（用户发起的截图用 Recording 包装以计入统计）
+fn capture_area_with_events(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+    events: &CaptureEvents,
+    observer: &dyn CaptureObserver,
+) -> Result<Frame, String> {
+    let visible: Vec<_> = app
+        .webview_windows()
+        .into_values()
+        .filter(|w| w.is_visible().unwrap_or(false))
+        .collect();
+    let bounds = if visible.is_empty() {
+        None
+    } else {
+        capture_bounds(monitor, region)
+    };
+    let _hidden = HiddenWindows::hide(
+        visible
+            .into_iter()
+            .filter(|w| intersects(w, bounds))
+            .collect(),
+    );
+    let result = crate::capture::capture(monitor, observer);
+    events.finish(result.as_ref().map(|_| None));
+    Ok(result?.frame)
+}
+
+/// quick-ocr-started 事件：识别已在后台开始，截图可通过 prinsp:// 协议预览
+#[derive(Clone, Serialize)]
+struct QuickOcrStarted {
+    job_id: u64,
+    capture: CaptureHandle,
+}
+
+/// quick-ocr-finished 事件
+#[derive(Clone, Serialize)]
+struct QuickOcrFinished {
+    job_id: u64,
+    capture_id: CaptureId,
+    status: JobStatus,
+}
+
+/// 截图后立即交给识别队列，识别进行的同时编码预览并通知前端显示；
+/// 识别结果复制到剪贴板，并通过通知和 quick-ocr-finished 事件反馈。
+///
+/// 识别任务属于本次截图会话，按 Esc 取消或开始新的截图都会结束正在运行的 tesseract
+pub fn capture_and_recognize(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+) -> Result<String, AppError> {
+    let session = session::begin();
+    let _busy = crate::tray::busy(app);
+    let events = CaptureEvents::start(app, CaptureId::generate());
+    let started = start_quick_ocr(app, session, &events, monitor, region)
+        // 截图失败时没有可供取消的界面，直接结束本次截图
+        .inspect_err(|_| session::end());
+    let result = started.and_then(|(job_id, rx)| {
+        let result = ocr_queue::wait_result(&rx);
+        let status = match &result {
+            Ok(recognized) => JobStatus::Done {
+                text: recognized.text.clone(),
+                warning: recognized.warning.clone(),
+            },
+            Err(AppError::Cancelled) => JobStatus::Cancelled,
+            Err(err) => JobStatus::Failed {
+                code: err.code().to_string(),
+                message: err.message(),
+            },
+        };
+        capture_id::ocr_complete(app, Some(events.id()), &result);
+        let finished = QuickOcrFinished {
+            job_id,
+            capture_id: events.id().clone(),
+            status,
+        };
+        let _ = app.emit("quick-ocr-finished", finished);
+        result
+    });
+
+    match result {
+        Ok(recognized) => {
+            let text = recognized.text;
+            if !text.is_empty() {
+                crate::clipboard::set_text(text.clone())?;
+                let preview: String = text.chars().take(120).collect();
+                let mut notice = Notice::new(NotifyEvent::Ocr, "文字识别完成", preview);
+                notice.copy = Some(CopyPayload::Text(text.clone()));
+                notify::notify(app, notice);
+            }
+            let result = if text.is_empty() {
+                "result.no_text"
+            } else {
+                "result.copied"
+            };
+            crate::tray::record(app, "tray.last_ocr", result, true);
+            webhook::fire(
+                app,
+                WebhookEvent::OcrComplete,
+                webhook::Payload {
+                    text: Some(text.clone()),
+                    ..Default::default()
+                },
+            );
+            Ok(text)
+        }
+        Err(AppError::Cancelled) => Err(AppError::Cancelled),
+        Err(err) => {
+            error_report::report(
+                app,
+                Failure::new(ErrorSource::Ocr, err.code(), "文字识别失败", err.message()),
+            );
+            Err(err)
+        }
+    }
+}
+
+type Started = (u64, mpsc::Receiver<Result<Recognized, AppError>>);
+
+/// 截图并提交识别任务，然后在识别进行的同时编码预览
+fn start_quick_ocr(
+    app: &AppHandle,
+    session: u64,
+    events: &CaptureEvents,
+    monitor: Option<u32>,
+    region: Option<Region>,
+) -> Result<Started, AppError> {
+    let frame = capture_area_with_events(app, monitor, region, events, &Recording(events))
+        .map_err(AppError::Internal)?;
+    let mut image = frame.into_image()?;
+    if let Some(r) = region {
+        image = image.crop_imm(r.x, r.y, r.width, r.height);
+        if image.width() == 0 || image.height() == 0 {
+            return Err(AppError::invalid("识别区域为空"));
+        }
+    }
+    let image = Arc::new(image);
+    let lang = app.state::<crate::settings::SettingsState>().get().ocr_lang;
+    let (job_id, rx) = app.state::<OcrQueue>().submit(
+        JobInput::Image(image.clone()),
+        lang,
+        JobPriority::High,
+        Some(session),
+    );
+
+    let cache = app.state::<CaptureCache>();
+    cache.clear();
+    match cache.insert_shared(image, events.id().clone(), None) {
+        Ok(capture) => {
+            let _ = app.emit("quick-ocr-started", QuickOcrStarted { job_id, capture });
+        }
+        // 预览失败不影响识别
+        Err(err) => tracing::warn!(error = %err, "快速识别的预览编码失败"),
+    }
+    Ok((job_id, rx))
+}
+
+/// 在后台线程执行，供快捷键和托盘菜单调用
+/// preset 为选区预设名称，只截取该选区
+pub fn spawn(app: &AppHandle, preset: Option<String>) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let result = preset_target(&app, None, None, preset.as_deref())
+            .map_err(String::from)
+            .and_then(|(monitor, region)| quick_capture(&app, monitor, region));
+        if let Err(err) = result {
+            tracing::warn!(error = %err, "快速截图失败");
+        }
+    });
+}
+
+/// 截图保存到文件，在后台线程执行，供托盘菜单调用
+pub fn spawn_to_file(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(err) = quick_capture_to_file(&app, None, None) {
+            tracing::warn!(error = %err, "截图保存失败");
+        }
+    });
+}
+
+/// 快速识别，在后台线程执行；preset 为选区预设名称
+pub fn spawn_ocr(app: &AppHandle, preset: Option<String>) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let result = preset_target(&app, None, None, preset.as_deref())
+            .and_then(|(monitor, region)| capture_and_recognize(&app, monitor, region));
+        match result {
+            Ok(_) | Err(AppError::Cancelled) => {}
+            Err(err) => tracing::warn!(error = %err, "快速识别失败"),
+        }
+    });
+}
+
+/// 光标处取字的范围
+const MIN_CURSOR_RADIUS: u32 = 8;
+const MAX_CURSOR_RADIUS: u32 = 400;
+/// 光标处取字的区域很小，先放大再交给识别的预处理（其中还会再放大 2 倍）
+const CURSOR_UPSCALE: u32 = 3;
+
+/// 识别光标附近文字的设置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReadAtCursorSettings {
+    /// 以光标为中心、边长 2·radius_px 的正方形区域（物理像素）
+    pub radius_px: u32,
+    /// 识别后朗读
+    pub speak: bool,
+}
+
+impl Default for ReadAtCursorSettings {
+    fn default() -> Self {
+        Self {
+            radius_px: 80,
+            speak: false,
+        }
+    }
+}
+
+impl ReadAtCursorSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_CURSOR_RADIUS..=MAX_CURSOR_RADIUS).contains(&self.radius_px) {
+            return Err(format!(
+                "光标取字半径必须在 {MIN_CURSOR_RADIUS} 到 {MAX_CURSOR_RADIUS} 像素之间"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 以 center 为中心、边长 2·radius 的区域，裁到截图范围内
+fn region_around(center: (u32, u32), radius: u32, bounds: (u32, u32)) -> Option<Region> {
+    let x = center.0.saturating_sub(radius);
+    let y = center.1.saturating_sub(radius);
+    let right = center.0.saturating_add(radius).min(bounds.0);
+    let bottom = center.1.saturating_add(radius).min(bounds.1);
+    (right > x && bottom > y).then_some(Region {
+        x,
+        y,
+        width: right - x,
+        height: bottom - y,
+    })
+}
+
+/// 截取光标所在显示器中光标附近的区域并识别
+fn recognize_at_cursor(app: &AppHandle, radius: u32) -> Result<String, AppError> {
+    let cursor = app
+        .cursor_position()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let (px, py) = (cursor.x as i32, cursor.y as i32);
+    let monitor = crate::overlay::monitor_at(px, py).map_err(AppError::Internal)?;
+    let id = monitor
+        .id()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let origin = (
+        monitor.x().map_err(|e| AppError::Internal(e.to_string()))?,
+        monitor.y().map_err(|e| AppError::Internal(e.to_string()))?,
+    );
+    let center = ((px - origin.0).max(0) as u32, (py - origin.1).max(0) as u32);
+
+    let frame = capture_without_windows(app, Some(id)).map_err(AppError::Internal)?;
+    let image = frame.into_image()?;
+    let region = region_around(center, radius, (image.width(), image.height()))
+        .ok_or_else(|| AppError::invalid("光标不在截图范围内"))?;
+    tracing::debug!(monitor = id, ?region, "识别光标附近的文字");
+    let image = crate::headless::crop(Frame::Image(image), region)?.into_image()?;
+    let image = image.resize_exact(
+        image.width() * CURSOR_UPSCALE,
+        image.height() * CURSOR_UPSCALE,
+        image::imageops::FilterType::CatmullRom,
+    );
+    let lang = app.state::<crate::settings::SettingsState>().get().ocr_lang;
+    let recognized = app.state::<OcrQueue>().recognize(
+        JobInput::Image(Arc::new(image)),
+        lang,
+        JobPriority::High,
+        None,
+    )?;
+    Ok(recognized.text.trim().to_string())
+}
+
+/// 识别光标附近的文字并复制，按设置朗读；没有文字时单独提示，不覆盖剪贴板
+pub fn read_and_copy_at_cursor(app: &AppHandle, radius: Option<u32>) -> Result<String, AppError> {
+    let settings = app
+        .state::<crate::settings::SettingsState>()
+        .get()
+        .read_at_cursor;
+    let radius = radius.unwrap_or(settings.radius_px);
+    if !(MIN_CURSOR_RADIUS..=MAX_CURSOR_RADIUS).contains(&radius) {
+        return Err(AppError::invalid(format!(
+            "半径必须在 {MIN_CURSOR_RADIUS} 到 {MAX_CURSOR_RADIUS} 像素之间"
+        )));
+    }
+    let _busy = crate::tray::busy(app);
+    let text = recognize_at_cursor(app, radius).inspect_err(|err| {
+        error_report::report(
+            app,
+            Failure::new(
+                ErrorSource::Ocr,
+                err.code(),
+                "光标处文字识别失败",
+                err.message(),
+            ),
+        );
+    })?;
+    if text.is_empty() {
+        crate::tray::record(app, "tray.last_ocr", "result.no_text", true);
+        notify::notify(
+            app,
+            Notice::new(NotifyEvent::Ocr, "光标处没有识别到文字", "剪贴板内容未改变"),
+        );
+        return Ok(text);
+    }
+    crate::clipboard::set_text(text.clone())?;
+    crate::tray::record(app, "tray.last_ocr", "result.copied", true);
+    let mut notice = Notice::new(NotifyEvent::Ocr, "光标处的文字已复制", text.clone());
+    notice.copy = Some(CopyPayload::Text(text.clone()));
+    notify::notify(app, notice);
+    if settings.speak {
+        if let Err(err) = crate::speech::speak_text(app.clone(), text.clone(), None) {
+            tracing::warn!(error = ?err, "朗读光标处的文字失败");
+        }
+    }
+    Ok(text)
+}
+
+/// 光标处取字，在后台线程执行，供快捷键调用
+pub fn spawn_read_at_cursor(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(err) = read_and_copy_at_cursor(&app, None) {
+            tracing::warn!(error = %err, "光标处取字失败");
+        }
+    });
+}
+
+/// 识别光标附近边长 2·radius_px 的区域，radius_px 为空时使用设置；返回识别出的文字（可能为空）
+#[tauri::command]
+pub async fn read_at_cursor(app: AppHandle, radius_px: Option<u32>) -> Result<String, AppError> {
+    crate::run_blocking(move || read_and_copy_at_cursor(&app, radius_px)).await
+}
+
+/// 截取指定显示器（或整屏）并识别，region 为截图中的像素区域；
+/// 指定 preset（选区预设名称）时按预设确定显示器与区域。返回识别出的文字
+#[tauri::command]
+pub async fn quick_ocr(
+    app: AppHandle,
+    monitor: Option<u32>,
+    region: Option<Region>,
+    preset: Option<String>,
+) -> Result<String, AppError> {
+    crate::run_blocking(move || {
+        let (monitor, region) = preset_target(&app, monitor, region, preset.as_deref())?;
+        capture_and_recognize(&app, monitor, region)
+    })
+    .await
+}
+
+/// 截图复制到剪贴板；指定 preset（选区预设名称）时只截取该选区
+#[tauri::command]
+pub async fn quick_capture_to_clipboard(
+    app: AppHandle,
+    monitor: Option<u32>,
+    preset: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (monitor, region) = preset_target(&app, monitor, None, preset.as_deref())?;
+        quick_capture(&app, monitor, region)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    struct DummyWindow<'a> {
+        name: &'static str,
+        visible: Cell<bool>,
+        log: &'a RefCell<Vec<String>>,
+    }
+
+    impl<'a> DummyWindow<'a> {
+        fn new(name: &'static str, log: &'a RefCell<Vec<String>>) -> Self {
+            Self {
+                name,
+                visible: Cell::new(true),
+                log,
+            }
+        }
+    }
+
+    impl OwnWindow for DummyWindow<'_> {
+        fn hide_window(&self) {
+            self.visible.set(false);
+            self.log.borrow_mut().push(format!("hide {}", self.name));
+        }
+
+        fn show_window(&self) {
+            self.visible.set(true);
+            self.log.borrow_mut().push(format!("show {}", self.name));
+        }
+
+        fn visible(&self) -> bool {
+            self.visible.get()
+        }
+    }
+
+    fn capture_between(log: &RefCell<Vec<String>>, fail: bool) -> Result<(), String> {
+        let windows = vec![DummyWindow::new("main", log), DummyWindow::new("pin", log)];
+        let hidden = HiddenWindows::hide(windows);
+        assert!(hidden.0.iter().all(|w| !w.visible()));
+        log.borrow_mut().push("capture".into());
+        if fail {
+            return Err("capture failed".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn windows_are_hidden_before_capture_and_restored_in_reverse() {
+        let log = RefCell::new(Vec::new());
+        capture_between(&log, false).unwrap();
+        assert_eq!(
+            log.into_inner(),
+            ["hide main", "hide pin", "capture", "show pin", "show main"]
+        );
+    }
+
+    #[test]
+    fn windows_are_restored_when_capture_fails() {
+        let log = RefCell::new(Vec::new());
+        assert!(capture_between(&log, true).is_err());
+        assert_eq!(
+            log.into_inner(),
+            ["hide main", "hide pin", "capture", "show pin", "show main"]
+        );
+    }
+}
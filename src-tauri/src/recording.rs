@@ -0,0 +1,493 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use xcap::Monitor;
+
+use crate::error::AppError;
+use crate::external::{self, ExternalTool};
+use crate::launch::Region;
+use crate::notify::{self, Notice, NotifyEvent};
+use crate::settings::SettingsState;
+
+const MAX_DURATION_S: u64 = 3600;
+const MAX_FPS: u32 = 60;
+/// 发出 recording-elapsed 事件的间隔
+const TICK: Duration = Duration::from_secs(1);
+/// 启动后等待这么久仍未退出才算启动成功
+const STARTUP_CHECK: Duration = Duration::from_millis(300);
+/// 发送 SIGINT 后等待录制程序写完文件的时间，超时后强制结束
+const FINISH_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 出错时附带的录制程序输出行数
+const STDERR_TAIL: usize = 20;
+const GIF_FPS: u32 = 12;
+/// 转换 GIF 的最长时间，一小时的录像也足够
+const GIF_TIMEOUT: Duration = Duration::from_secs(600);
+/// GIF 最大宽度，更宽的录像按比例缩小
+const GIF_MAX_WIDTH: u32 = 960;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recorder {
+    /// Wayland（wlroots 系）下的 wf-recorder
+    WfRecorder,
+    /// X11 下的 ffmpeg x11grab
+    Ffmpeg,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RecordingOptions {
+    pub fps: u32,
+    /// 达到此时长后自动停止
+    pub max_duration_s: u64,
+    /// 停止后另存一份 GIF
+    pub gif: bool,
+    /// MP4 超过此大小（MB）时不转换 GIF，避免生成过大的文件
+    pub gif_max_mb: u64,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            fps: 30,
+            max_duration_s: 300,
+            gif: false,
+            gif_max_mb: 10,
+        }
+    }
+}
+
+/// 录制程序相关的失败
+fn failed(detail: impl Into<String>) -> AppError {
+    AppError::BackendFailed {
+        backend: "recording".into(),
+        detail: detail.into(),
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct RecordingInfo {
+    pub id: u64,
+    pub recorder: Recorder,
+    pub path: String,
+    pub max_duration_s: u64,
+    pub started_at: i64,
+}
+
+/// stop_recording 的返回值，同时作为 recording-stopped 事件发出
+#[derive(Clone, Serialize)]
+pub struct RecordingResult {
+    pub id: u64,
+    pub path: String,
+    pub gif_path: Option<String>,
+    /// 开启了 GIF 但未转换的原因
+    pub gif_skipped: Option<String>,
+    pub duration_s: f64,
+    /// 达到最长时长后自动停止
+    pub auto_stopped: bool,
+}
+
+/// recording-elapsed 事件
+#[derive(Clone, Serialize)]
+struct RecordingElapsed {
+    id: u64,
+    elapsed_s: u64,
+    max_duration_s: u64,
+}
+
+struct Active {
+    info: RecordingInfo,
+    child: Child,
+    options: RecordingOptions,
+    started: Instant,
+    /// 录制程序最近的错误输出
+    stderr: Arc<Mutex<Vec<String>>>,
+}
+
+/// 同一时间只有一个录制
+static CURRENT: Mutex<Option<Active>> = Mutex::new(None);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn is_recording() -> bool {
+    CURRENT.lock().map(|c| c.is_some()).unwrap_or(false)
+}
+
+fn current_id() -> Option<u64> {
+    CURRENT.lock().ok()?.as_ref().map(|a| a.info.id)
+}
+
+#[cfg(target_os = "linux")]
+fn detect_recorder() -> Result<Recorder, AppError> {
+    if std::env::var("WAYLAND_DISPLAY").is_ok() {
+        if ExternalTool::WfRecorder.available() {
+            return Ok(Recorder::WfRecorder);
+        }
+        return Err(AppError::BackendFailed {
+            backend: "wf-recorder".into(),
+            detail: "未找到 wf-recorder，请安装（sudo apt install wf-recorder）".into(),
+        });
+    }
+    if ExternalTool::Ffmpeg.available() {
+        return Ok(Recorder::Ffmpeg);
+    }
+    Err(AppError::BackendFailed {
+        backend: "ffmpeg".into(),
+        detail: "未找到 ffmpeg，请安装（sudo apt install ffmpeg）".into(),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_recorder() -> Result<Recorder, AppError> {
+    Err(failed("录屏仅支持 Linux"))
+}
+
+/// 区域坐标的原点：指定显示器时为该显示器左上角，否则为整个桌面的左上角
+fn origin(monitor: Option<u32>) -> Result<(i32, i32), AppError> {
+    let monitors = Monitor::all().map_err(|e| failed(e.to_string()))?;
+    let position = |m: &Monitor| Some((m.x().ok()?, m.y().ok()?));
+    match monitor {
+        Some(id) => monitors
+            .iter()
+            .find(|m| m.id().ok() == Some(id))
+            .and_then(position)
+            .ok_or_else(|| AppError::invalid(format!("未找到显示器: {id}"))),
+        None => Ok(monitors
+            .iter()
+            .filter_map(position)
+            .reduce(|(x, y), (mx, my)| (x.min(mx), y.min(my)))
+            .unwrap_or((0, 0))),
+    }
+}
+
+fn build_command(
+    recorder: Recorder,
+    (x, y): (i32, i32),
+    (width, height): (u32, u32),
+    fps: u32,
+    path: &Path,
+) -> Result<Command, AppError> {
+    let tool = match recorder {
+        Recorder::WfRecorder => ExternalTool::WfRecorder,
+        Recorder::Ffmpeg => ExternalTool::Ffmpeg,
+    };
+    let mut command = external::command(&tool).map_err(|e| failed(e.to_string()))?;
+    match recorder {
+        Recorder::WfRecorder => {
+            command
+                .args(["-g", &format!("{x},{y} {width}x{height}")])
+                .args(["-r", &fps.to_string()])
+                .arg("-f")
+                .arg(path);
+        }
+        Recorder::Ffmpeg => {
+            let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".into());
+            command
+                .args(["-hide_banner", "-loglevel", "error", "-y"])
+                .args(["-f", "x11grab", "-framerate", &fps.to_string()])
+                .args(["-video_size", &format!("{width}x{height}")])
+                .args(["-i", &format!("{display}+{x},{y}")])
+                .args([
+                    "-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p",
+                ])
+                .arg(path);
+        }
+    }
+    Ok(command)
+}
+
+/// 在后台读取错误输出，只保留最后几行
+fn collect_stderr(child: &mut Child) -> Arc<Mutex<Vec<String>>> {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    if let Some(stderr) = child.stderr.take() {
+        let lines = lines.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if let Ok(mut lines) = lines.lock() {
+                    if lines.len() >= STDERR_TAIL {
+                        lines.remove(0);
+                    }
+                    lines.push(line);
+                }
+            }
+        });
+    }
+    lines
+}
+
+fn stderr_tail(stderr: &Mutex<Vec<String>>) -> String {
+    stderr.lock().map(|l| l.join("\n")).unwrap_or_default()
+}
+
+/// 发送 SIGINT 让录制程序正常写完文件，超时后强制结束
+fn interrupt(child: &mut Child) {
+    // 已经退出的进程不再发信号，避免进程号被复用
+    if let Ok(Some(_)) = child.try_wait() {
+        return;
+    }
+    let _ = external::run_external(
+        ExternalTool::Kill,
+        ["-INT", &child.id().to_string()],
+        None,
+        external::PROBE_TIMEOUT,
+    );
+    let deadline = Instant::now() + FINISH_TIMEOUT;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    tracing::warn!("录制程序未在 SIGINT 后退出，强制结束");
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// 用 ffmpeg 的调色板滤镜转换 GIF，颜色比直接转换好得多
+fn convert_gif(mp4: &Path) -> Result<PathBuf, String> {
+    if !ExternalTool::Ffmpeg.available() {
+        return Err("未找到 ffmpeg，无法转换 GIF".into());
+    }
+    let gif = mp4.with_extension("gif");
+    let filter = format!(
+        "fps={GIF_FPS},scale='min({GIF_MAX_WIDTH},iw)':-1:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse"
+    );
+    let args = [
+        OsStr::new("-hide_banner"),
+        OsStr::new("-loglevel"),
+        OsStr::new("error"),
+        OsStr::new("-y"),
+        OsStr::new("-i"),
+        mp4.as_os_str(),
+        OsStr::new("-filter_complex"),
+        OsStr::new(&filter),
+        gif.as_os_str(),
+    ];
+    external::run_external(ExternalTool::Ffmpeg, args, None, GIF_TIMEOUT)
+        .map_err(|e| e.to_string())?;
+    Ok(gif)
+}
+
+/// 结束当前录制，auto_stopped 表示达到最长时长
+fn finish(app: &AppHandle, auto_stopped: bool) -> Result<RecordingResult, AppError> {
+    let active = CURRENT
+        .lock()
+        .map_err(|e| failed(e.to_string()))?
+        .take()
+        .ok_or_else(|| AppError::invalid("当前没有录制"))?;
+    crate::tray::set_recording(app, false);
+    let Active {
+        info,
+        mut child,
+        options,
+        started,
+        stderr,
+    } = active;
+    interrupt(&mut child);
+    let duration_s = started.elapsed().as_secs_f64();
+    let path = PathBuf::from(&info.path);
+    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size == 0 {
+        let detail = stderr_tail(&stderr);
+        tracing::warn!(recorder = ?info.recorder, %detail, "录制没有生成文件");
+        let _ = std::fs::remove_file(&path);
+        return Err(failed(if detail.is_empty() {
+            "没有生成录像文件".to_string()
+        } else {
+            detail
+        }));
+    }
+
+    let (mut gif_path, mut gif_skipped) = (None, None);
+    if options.gif {
+        if size > options.gif_max_mb * 1024 * 1024 {
+            gif_skipped = Some(format!("录像超过 {} MB，未转换 GIF", options.gif_max_mb));
+        } else {
+            match convert_gif(&path) {
+                Ok(gif) => gif_path = Some(gif.to_string_lossy().into_owned()),
+                Err(err) => {
+                    tracing::warn!(error = %err, "转换 GIF 失败");
+                    gif_skipped = Some(format!("转换 GIF 失败: {err}"));
+                }
+            }
+        }
+    }
+    let result = RecordingResult {
+        id: info.id,
+        path: info.path,
+        gif_path,
+        gif_skipped,
+        duration_s,
+        auto_stopped,
+    };
+    tracing::info!(id = result.id, path = %result.path, duration_s, size, "录制已结束");
+    let mut notice = Notice::new(
+        NotifyEvent::Capture,
+        "录制已结束",
+        format!("已保存到 {}（{duration_s:.0} 秒）", result.path),
+    );
+    notice.file = Some(path);
+    notify::notify(app, notice);
+    let _ = app.emit("recording-stopped", &result);
+    Ok(result)
+}
+
+/// 每秒发出 recording-elapsed 事件；达到最长时长或录制程序意外退出时结束录制
+fn tick(app: AppHandle, id: u64, max_duration: Duration) {
+    loop {
+        thread::sleep(TICK);
+        let elapsed = {
+            let Ok(mut current) = CURRENT.lock() else {
+                return;
+            };
+            let Some(active) = current.as_mut().filter(|a| a.info.id == id) else {
+                return;
+            };
+            if let Ok(Some(status)) = active.child.try_wait() {
+                tracing::warn!(id, %status, stderr = %stderr_tail(&active.stderr), "录制程序意外退出");
+                None
+            } else {
+                Some(active.started.elapsed())
+            }
+        };
+        let Some(elapsed) = elapsed else {
+            // 文件可能已部分写入，按正常结束处理，失败时提示
+            if let Err(err) = finish(&app, false) {
+                let _ = app.emit("recording-failed", &err);
+                notify::notify(
+                    &app,
+                    Notice::new(NotifyEvent::Error, "录制失败", String::from(err)),
+                );
+            }
+            return;
+        };
+        if elapsed >= max_duration {
+            if current_id() == Some(id) {
+                let _ = finish(&app, true);
+            }
+            return;
+        }
+        let _ = app.emit(
+            "recording-elapsed",
+            RecordingElapsed {
+                id,
+                elapsed_s: elapsed.as_secs(),
+                max_duration_s: max_duration.as_secs(),
+            },
+        );
+    }
+}
+
+fn start(
+    app: &AppHandle,
+    rect: Region,
+    monitor: Option<u32>,
+    options: RecordingOptions,
+) -> Result<RecordingInfo, AppError> {
+    if !(1..=MAX_FPS).contains(&options.fps) {
+        return Err(AppError::invalid(format!("帧率必须在 1 到 {MAX_FPS} 之间")));
+    }
+    if !(1..=MAX_DURATION_S).contains(&options.max_duration_s) {
+        return Err(AppError::invalid(format!(
+            "最长录制时长必须在 1 到 {MAX_DURATION_S} 秒之间"
+        )));
+    }
+    // H.264 的 yuv420p 要求宽高为偶数
+    let size = (rect.width & !1, rect.height & !1);
+    if size.0 == 0 || size.1 == 0 {
+        return Err(AppError::invalid("录制区域太小"));
+    }
+    if is_recording() {
+        return Err(AppError::invalid("已在录制中"));
+    }
+    let recorder = detect_recorder()?;
+    let (ox, oy) = origin(monitor)?;
+    let position = (ox + rect.x as i32, oy + rect.y as i32);
+
+    let settings = app.state::<SettingsState>().get();
+    let dir = crate::pipeline::save_dir(app, &settings).map_err(failed)?;
+    std::fs::create_dir_all(&dir).map_err(|e| failed(e.to_string()))?;
+    let now = chrono::Local::now();
+    let path = dir.join(format!("recording_{}.mp4", now.format("%Y%m%d_%H%M%S")));
+
+    let mut current = CURRENT.lock().map_err(|e| failed(e.to_string()))?;
+    if current.is_some() {
+        return Err(AppError::invalid("已在录制中"));
+    }
+    let mut child = build_command(recorder, position, size, options.fps, &path)?
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(format!("无法启动录制程序: {e}")))?;
+    let stderr = collect_stderr(&mut child);
+    // 区域越界、无权限等错误会让录制程序立即退出
+    thread::sleep(STARTUP_CHECK);
+    if let Ok(Some(status)) = child.try_wait() {
+        thread::sleep(POLL_INTERVAL);
+        let detail = stderr_tail(&stderr);
+        return Err(failed(if detail.is_empty() {
+            format!("录制程序退出 {status}")
+        } else {
+            detail
+        }));
+    }
+
+    let info = RecordingInfo {
+        id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+        recorder,
+        path: path.to_string_lossy().into_owned(),
+        max_duration_s: options.max_duration_s,
+        started_at: now.timestamp_millis(),
+    };
+    let max_duration = Duration::from_secs(options.max_duration_s);
+    *current = Some(Active {
+        info: info.clone(),
+        child,
+        options,
+        started: Instant::now(),
+        stderr,
+    });
+    drop(current);
+    crate::tray::set_recording(app, true);
+    let (handle, id) = (app.clone(), info.id);
+    thread::spawn(move || tick(handle, id, max_duration));
+    tracing::info!(id, ?recorder, ?rect, ?monitor, path = %info.path, "开始录制");
+    Ok(info)
+}
+
+/// 录制区域（所选显示器或整个桌面的像素坐标）为 MP4，保存到自动保存目录；
+/// Wayland 下使用 wf-recorder，X11 下使用 ffmpeg
+#[tauri::command]
+pub async fn start_recording(
+    app: AppHandle,
+    rect: Region,
+    monitor: Option<u32>,
+    options: Option<RecordingOptions>,
+) -> Result<RecordingInfo, AppError> {
+    let options = options.unwrap_or_default();
+    crate::run_blocking(move || start(&app, rect, monitor, options)).await
+}
+
+/// 停止录制并等待文件写完；开启了 GIF 时再转换一份
+#[tauri::command]
+pub async fn stop_recording(app: AppHandle) -> Result<RecordingResult, AppError> {
+    crate::run_blocking(move || finish(&app, false)).await
+}
+
+/// 托盘图标在录制时点击：停止录制
+pub fn stop_from_tray(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(err) = finish(&app, false) {
+            tracing::warn!(error = %err, "从托盘停止录制失败");
+        }
+    });
+}
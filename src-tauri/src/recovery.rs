@@ -0,0 +1,250 @@
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+use crate::capture_cache::{CaptureCache, CaptureHandle};
+use crate::capture_id::CaptureId;
+use crate::frame::Frame;
+
+/// 超过该大小的截图缩小后再保存
+const MAX_IMAGE_BYTES: usize = 8 * 1024 * 1024;
+/// 缩小后的最长边
+const PREVIEW_MAX_SIDE: u32 = 1920;
+/// 标注列表序列化后的上限
+const MAX_ANNOTATION_BYTES: usize = 1024 * 1024;
+/// 两次写入标注的最短间隔，期间的改动合并到下一次写入
+const WRITE_INTERVAL: Duration = Duration::from_secs(2);
+
+const IMAGE_FILE: &str = "capture.png";
+const STATE_FILE: &str = "state.json";
+
+/// 恢复文件中记录的截图信息与标注
+#[derive(Clone, Serialize, Deserialize)]
+struct Saved {
+    /// 写入时的截图缓存 id，标注只接受同一张截图的
+    capture_id: u64,
+    captured_at: i64,
+    width: u32,
+    height: u32,
+    /// 原图过大，保存的是缩小后的预览
+    downscaled: bool,
+    /// 前端的标注图形列表，原样保存
+    annotations: Value,
+}
+
+#[derive(Default)]
+struct Pending {
+    saved: Option<Saved>,
+    last_write: Option<Instant>,
+    flush_scheduled: bool,
+}
+
+/// 崩溃恢复：最近一次截图及其标注写在数据目录的 recovery 下，正常退出或用户处理完后删除
+pub struct Recovery {
+    dir: PathBuf,
+    pending: Arc<Mutex<Pending>>,
+}
+
+/// get_recovery_state 的返回值，截图已放入缓存，前端按 preview_url 加载
+#[derive(Serialize)]
+pub struct RecoveryState {
+    pub capture: CaptureHandle,
+    pub captured_at: i64,
+    /// 原图尺寸，downscaled 时与截图尺寸不同，标注坐标按原图换算
+    pub width: u32,
+    pub height: u32,
+    pub downscaled: bool,
+    pub annotations: Value,
+}
+
+impl Recovery {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            pending: Arc::default(),
+        }
+    }
+
+    fn write_state(dir: &std::path::Path, saved: &Saved) -> Result<(), String> {
+        let data = serde_json::to_vec(saved).map_err(|e| e.to_string())?;
+        crate::durable::write_with_backup(&dir.join(STATE_FILE), &data)
+    }
+
+    /// 删除恢复文件，不存在时忽略
+    pub fn clear(&self) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.saved = None;
+        }
+        for file in [STATE_FILE, IMAGE_FILE] {
+            let path = self.dir.join(file);
+            if let Err(err) = crate::durable::remove(&path) {
+                tracing::warn!(path = %path.display(), error = %err, "删除恢复文件失败");
+            }
+        }
+    }
+}
+
+/// 过大的截图缩小为预览，保证恢复文件大小有上限
+fn shrink(png: Vec<u8>) -> Result<(Vec<u8>, bool), String> {
+    if png.len() <= MAX_IMAGE_BYTES {
+        return Ok((png, false));
+    }
+    let image = Frame::Png(png).into_image().map_err(|e| e.to_string())?;
+    let preview = image.resize(PREVIEW_MAX_SIDE, PREVIEW_MAX_SIDE, FilterType::Triangle);
+    let png = crate::frame::encode_png(&preview).map_err(|e| e.to_string())?;
+    Ok((png, true))
+}
+
+/// 截图放入缓存后记录到恢复文件，在后台写入，不拖慢截图
+pub fn record_capture(app: &AppHandle, capture: &CaptureHandle) {
+    let Some(png) = app.state::<CaptureCache>().get(capture.id) else {
+        return;
+    };
+    let recovery = app.state::<Recovery>();
+    let dir = recovery.dir.clone();
+    let pending = recovery.pending.clone();
+    let saved = Saved {
+        capture_id: capture.id,
+        captured_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default(),
+        width: capture.width,
+        height: capture.height,
+        downscaled: false,
+        annotations: Value::Null,
+    };
+    if let Ok(mut pending) = pending.lock() {
+        pending.saved = Some(saved.clone());
+    }
+    thread::spawn(move || {
+        let result = (|| {
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let (png, downscaled) = shrink(png)?;
+            crate::durable::atomic_write(&dir.join(IMAGE_FILE), &png)?;
+            let mut pending = pending.lock().map_err(|e| e.to_string())?;
+            // 写入期间又有新截图时，以新截图为准
+            let Some(current) = pending.saved.as_mut() else {
+                return Ok(());
+            };
+            if current.capture_id != saved.capture_id {
+                return Ok(());
+            }
+            current.downscaled = downscaled;
+            Recovery::write_state(&dir, current)?;
+            pending.last_write = Some(Instant::now());
+            Ok::<_, String>(())
+        })();
+        if let Err(err) = result {
+            tracing::warn!(error = %err, "写入恢复文件失败");
+        }
+    });
+}
+
+fn flush(dir: &std::path::Path, pending: &Mutex<Pending>) {
+    let Ok(mut pending) = pending.lock() else {
+        return;
+    };
+    pending.flush_scheduled = false;
+    let Some(saved) = &pending.saved else {
+        return;
+    };
+    if let Err(err) = Recovery::write_state(dir, saved) {
+        tracing::warn!(error = %err, "写入恢复文件失败");
+    }
+    pending.last_write = Some(Instant::now());
+}
+
+/// 记录当前截图的标注列表；频繁调用时合并写入，最多每 2 秒写一次
+#[tauri::command]
+pub fn save_recovery_annotations(
+    state: State<'_, Recovery>,
+    capture_id: u64,
+    annotations: Value,
+) -> Result<(), String> {
+    let size = annotations.to_string().len();
+    if size > MAX_ANNOTATION_BYTES {
+        return Err(format!(
+            "标注数据过大（{} KB，上限 {} KB）",
+            size / 1024,
+            MAX_ANNOTATION_BYTES / 1024
+        ));
+    }
+    let mut pending = state.pending.lock().map_err(|e| e.to_string())?;
+    let Some(saved) = pending.saved.as_mut() else {
+        return Ok(());
+    };
+    // 旧截图的标注直接丢弃
+    if saved.capture_id != capture_id {
+        return Ok(());
+    }
+    saved.annotations = annotations;
+    if pending.flush_scheduled {
+        return Ok(());
+    }
+    let wait = pending
+        .last_write
+        .map(|at| WRITE_INTERVAL.saturating_sub(at.elapsed()))
+        .unwrap_or(WRITE_INTERVAL);
+    pending.flush_scheduled = true;
+    let dir = state.dir.clone();
+    let shared = state.pending.clone();
+    thread::spawn(move || {
+        thread::sleep(wait);
+        flush(&dir, &shared);
+    });
+    Ok(())
+}
+
+/// 启动时检查上次是否有未完成的截图；有则放入截图缓存并返回，前端询问是否恢复
+#[tauri::command]
+pub fn get_recovery_state(
+    state: State<'_, Recovery>,
+    cache: State<'_, CaptureCache>,
+) -> Result<Option<RecoveryState>, String> {
+    let state_path = state.dir.join(STATE_FILE);
+    if !state_path.exists() {
+        return Ok(None);
+    }
+    let Some(saved) =
+        crate::durable::read_recovering(&state_path, |data| serde_json::from_slice::<Saved>(data))
+    else {
+        tracing::warn!("恢复文件及其备份已损坏，丢弃");
+        state.clear();
+        return Ok(None);
+    };
+    let Ok(png) = std::fs::read(state.dir.join(IMAGE_FILE)) else {
+        state.clear();
+        return Ok(None);
+    };
+    // 恢复出的截图是新的一次操作
+    let capture = cache
+        .insert(Frame::Png(png), CaptureId::generate(), None)
+        .map_err(|e| e.to_string())?;
+    // 恢复后的截图继续记录标注
+    if let Ok(mut pending) = state.pending.lock() {
+        pending.saved = Some(Saved {
+            capture_id: capture.id,
+            ..saved.clone()
+        });
+    }
+    Ok(Some(RecoveryState {
+        capture,
+        captured_at: saved.captured_at,
+        width: saved.width,
+        height: saved.height,
+        downscaled: saved.downscaled,
+        annotations: saved.annotations,
+    }))
+}
+
+/// 用户完成或放弃恢复后删除恢复文件
+#[tauri::command]
+pub fn clear_recovery_state(state: State<'_, Recovery>) {
+    state.clear();
+}
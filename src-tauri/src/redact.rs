@@ -0,0 +1,483 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{DynamicImage, Rgba, RgbaImage};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+use crate::capture_cache::CaptureRef;
+use crate::edits::{EditOp, EditState};
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::preprocess;
+use crate::settings::SettingsState;
+use crate::tesseract;
+
+/// 马赛克色块边长，与标注中的马赛克工具一致
+const BLOCK_SIZE: u32 = 10;
+/// 打码区域向外扩展的像素，识别出的文字框通常贴着笔画
+const PADDING: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveClass {
+    Email,
+    /// 8 位以上的电话号码
+    Phone,
+    /// 形如 IBAN 的银行账号
+    Iban,
+    /// 长串十六进制或 base64 字符，常见于 API 密钥、令牌
+    Token,
+    /// 设置中的自定义正则
+    Custom,
+}
+
+const BUILTIN_CLASSES: &[SensitiveClass] = &[
+    SensitiveClass::Email,
+    SensitiveClass::Phone,
+    SensitiveClass::Iban,
+    SensitiveClass::Token,
+    SensitiveClass::Custom,
+];
+
+/// 自动打码设置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactSettings {
+    /// 执行截图后续动作和上传前自动打码
+    pub auto_redact: bool,
+    /// 自动打码的类别
+    pub classes: Vec<SensitiveClass>,
+    /// custom 类别使用的正则，按行内文字匹配
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for RedactSettings {
+    fn default() -> Self {
+        Self {
+            auto_redact: false,
+            classes: BUILTIN_CLASSES.to_vec(),
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+impl RedactSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in &self.custom_patterns {
+            Regex::new(pattern).map_err(|e| format!("自定义打码正则 {pattern} 无效: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+fn builtin_pattern(class: SensitiveClass) -> Option<&'static Regex> {
+    static PATTERNS: OnceLock<[Regex; 4]> = OnceLock::new();
+    let [email, phone, iban, token] = PATTERNS.get_or_init(|| {
+        [
+            r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+            r"\+?\d[\d\s().-]{6,}\d",
+            r"\b[A-Z]{2}\d{2}(\s?[A-Z0-9]{4}){2,7}(\s?[A-Z0-9]{1,4})?\b",
+            r"[A-Za-z0-9_+/=-]{24,}",
+        ]
+        .map(|p| Regex::new(p).expect("内置正则有效"))
+    });
+    match class {
+        SensitiveClass::Email => Some(email),
+        SensitiveClass::Phone => Some(phone),
+        SensitiveClass::Iban => Some(iban),
+        SensitiveClass::Token => Some(token),
+        SensitiveClass::Custom => None,
+    }
+}
+
+/// 进一步排除误报：电话至少 8 位数字，令牌需同时含字母和数字
+fn plausible(class: SensitiveClass, text: &str) -> bool {
+    match class {
+        SensitiveClass::Phone => text.chars().filter(char::is_ascii_digit).count() >= 8,
+        SensitiveClass::Token => {
+            text.chars().any(|c| c.is_ascii_digit())
+                && text.chars().any(|c| c.is_ascii_alphabetic())
+        }
+        _ => true,
+    }
+}
+
+/// 识别出的一个词及其在原图中的位置
+struct Word {
+    /// (页, 块, 段, 行)，同一行的词相同
+    line: (u32, u32, u32, u32),
+    text: String,
+    region: Region,
+}
+
+/// 解析 tesseract 的 TSV 输出，坐标按 scale 换算回原图
+fn parse_tsv(tsv: &str, scale: f64) -> Vec<Word> {
+    let scaled = |v: u32| (v as f64 * scale).round() as u32;
+    tesseract::parse_tsv(tsv)
+        .into_iter()
+        .map(|word| Word {
+            line: word.line,
+            text: word.text,
+            region: Region {
+                x: scaled(word.left),
+                y: scaled(word.top),
+                width: scaled(word.width).max(1),
+                height: scaled(word.height).max(1),
+            },
+        })
+        .collect()
+}
+
+/// 逐词识别，返回原图坐标下的词框
+fn recognize_words(image: &DynamicImage, lang: &str) -> Result<Vec<Word>, AppError> {
+    let limited = preprocess::limit_ocr_pixels(image);
+    let source = limited.as_ref().map_or(image, |(img, _)| img);
+    let mut gray = preprocess::channel_emphasized_gray(&source.to_rgb8());
+    // 截图中的字通常较小，像素允许时放大 2 倍
+    if gray.width() as u64 * gray.height() as u64 * 4 <= preprocess::MAX_OCR_PIXELS {
+        gray = preprocess::upscale_2x(&gray);
+    }
+    let scale = image.width() as f64 / gray.width().max(1) as f64;
+    let options = tesseract::Options {
+        lang,
+        dpi: 300,
+        psm: 11, // 稀疏文字，尽量找出所有词
+        oem: 1,
+        variables: &[],
+        configs: &["tsv"],
+    };
+    let tsv = tesseract::run(&gray, &options, &|| false)?;
+    Ok(parse_tsv(&tsv, scale))
+}
+
+/// 识别到的敏感区域
+#[derive(Clone, Debug, Serialize)]
+pub struct SensitiveRegion {
+    pub class: SensitiveClass,
+    #[serde(flatten)]
+    pub region: Region,
+    /// 匹配到的文字，供用户核对
+    pub text: String,
+}
+
+fn union(a: Region, b: Region) -> Region {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    Region {
+        x,
+        y,
+        width: a.x.saturating_add(a.width).max(b.x.saturating_add(b.width)) - x,
+        height: a
+            .y
+            .saturating_add(a.height)
+            .max(b.y.saturating_add(b.height))
+            - y,
+    }
+}
+
+/// 按行拼接词后匹配，跨多个词的电话号码、IBAN 也能找到；命中的词框合并为一个区域
+fn match_words(
+    words: &[Word],
+    classes: &[SensitiveClass],
+    custom: &[Regex],
+) -> Vec<SensitiveRegion> {
+    let mut found = Vec::new();
+    for line in words.chunk_by(|a, b| a.line == b.line) {
+        let mut text = String::new();
+        let mut spans = Vec::with_capacity(line.len());
+        for word in line {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            spans.push((text.len(), text.len() + word.text.len()));
+            text.push_str(&word.text);
+        }
+        for &class in classes {
+            let patterns: Vec<&Regex> = match builtin_pattern(class) {
+                Some(pattern) => vec![pattern],
+                None => custom.iter().collect(),
+            };
+            for m in patterns.iter().flat_map(|p| p.find_iter(&text)) {
+                if !plausible(class, m.as_str()) {
+                    continue;
+                }
+                let region = line
+                    .iter()
+                    .zip(&spans)
+                    .filter(|(_, &(start, end))| start < m.end() && m.start() < end)
+                    .map(|(word, _)| word.region)
+                    .reduce(union);
+                if let Some(region) = region {
+                    found.push(SensitiveRegion {
+                        class,
+                        region,
+                        text: m.as_str().to_string(),
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// 识别图片中的敏感信息；classes 为空时使用设置中的类别
+pub fn detect(
+    app: &AppHandle,
+    image: &DynamicImage,
+    classes: Option<Vec<SensitiveClass>>,
+) -> Result<Vec<SensitiveRegion>, AppError> {
+    let settings = app.state::<SettingsState>().get();
+    let classes = classes.unwrap_or(settings.redact.classes);
+    let custom = settings
+        .redact
+        .custom_patterns
+        .iter()
+        .map(|p| Regex::new(p).map_err(AppError::invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+    let words = recognize_words(image, &settings.ocr_lang)?;
+    let found = match_words(&words, &classes, &custom);
+    tracing::info!(words = words.len(), found = found.len(), "敏感信息检测完成");
+    Ok(found)
+}
+
+/// 把各区域（向外扩展 PADDING）打成马赛克，色块取区域内的平均色
+pub fn pixelate(image: &mut RgbaImage, regions: &[Region]) {
+    let (w, h) = image.dimensions();
+    for region in regions {
+        let x0 = region.x.saturating_sub(PADDING).min(w);
+        let y0 = region.y.saturating_sub(PADDING).min(h);
+        // 先收进图片范围再加宽高和扩展，超大的区域也不会溢出
+        let x1 = region
+            .x
+            .min(w)
+            .saturating_add(region.width)
+            .saturating_add(PADDING)
+            .min(w);
+        let y1 = region
+            .y
+            .min(h)
+            .saturating_add(region.height)
+            .saturating_add(PADDING)
+            .min(h);
+        for by in (y0..y1).step_by(BLOCK_SIZE as usize) {
+            for bx in (x0..x1).step_by(BLOCK_SIZE as usize) {
+                let (bw, bh) = ((x1 - bx).min(BLOCK_SIZE), (y1 - by).min(BLOCK_SIZE));
+                let mut sum = [0u64; 4];
+                for y in by..by + bh {
+                    for x in bx..bx + bw {
+                        for (s, c) in sum.iter_mut().zip(image.get_pixel(x, y).0) {
+                            *s += c as u64;
+                        }
+                    }
+                }
+                let n = (bw * bh) as u64;
+                let avg = Rgba(sum.map(|s| (s / n) as u8));
+                for y in by..by + bh {
+                    for x in bx..bx + bw {
+                        image.put_pixel(x, y, avg);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn decode(base64_data: &str) -> Result<DynamicImage, AppError> {
+    let data = STANDARD.decode(base64_data).map_err(AppError::invalid)?;
+    image::load_from_memory(&data).map_err(AppError::invalid)
+}
+
+fn encode(image: RgbaImage) -> Result<Vec<u8>, AppError> {
+    crate::save::encode_image(
+        &image.into(),
+        crate::save::ExportFormat::Png,
+        crate::save::DEFAULT_QUALITY,
+    )
+    .map_err(AppError::Internal)
+}
+
+/// 检测并打码 PNG，返回打码后的 PNG 和打码的区域；供截图后续动作和上传调用
+pub fn auto_redact_png(
+    app: &AppHandle,
+    png: &[u8],
+    classes: Option<Vec<SensitiveClass>>,
+) -> Result<(Vec<u8>, Vec<SensitiveRegion>), AppError> {
+    let image = image::load_from_memory(png).map_err(AppError::invalid)?;
+    let found = detect(app, &image, classes)?;
+    if found.is_empty() {
+        return Ok((png.to_vec(), found));
+    }
+    let mut rgba = image.into_rgba8();
+    let regions: Vec<Region> = found.iter().map(|r| r.region).collect();
+    pixelate(&mut rgba, &regions);
+    Ok((encode(rgba)?, found))
+}
+
+/// 识别截图中的邮箱、电话、账号、密钥等，返回区域及类别，不修改图片
+#[tauri::command]
+pub async fn detect_sensitive_regions(
+    app: AppHandle,
+    base64_data: String,
+    classes: Option<Vec<SensitiveClass>>,
+) -> Result<Vec<SensitiveRegion>, AppError> {
+    crate::run_blocking(move || detect(&app, &decode(&base64_data)?, classes)).await
+}
+
+/// redact_regions 的返回值：传入图片时为打码后 PNG 的 base64，传入截图 id 时为编辑后的状态
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Redacted {
+    Png(String),
+    Edit(EditState),
+}
+
+/// 把给定区域打成马赛克；传入 capture_id 时在缓存中的截图上打码，作为一步编辑记录，可撤销
+#[tauri::command]
+pub async fn redact_regions(
+    app: AppHandle,
+    base64_data: Option<String>,
+    capture_id: Option<CaptureRef>,
+    regions: Vec<Region>,
+) -> Result<Redacted, AppError> {
+    if let Some(id) = capture_id {
+        let state = crate::edits::edit(app, id, EditOp::Redact { regions }).await?;
+        return Ok(Redacted::Edit(state));
+    }
+    let base64_data =
+        base64_data.ok_or_else(|| AppError::invalid("需要 base64_data 或 capture_id"))?;
+    crate::run_blocking(move || {
+        let mut image = decode(&base64_data)?.into_rgba8();
+        pixelate(&mut image, &regions);
+        Ok(Redacted::Png(STANDARD.encode(encode(image)?)))
+    })
+    .await
+}
+
+#[derive(Serialize)]
+pub struct RedactResult {
+    /// 打码后 PNG 的 base64，没有需要打码的内容时为原图
+    pub base64_data: String,
+    pub regions: Vec<SensitiveRegion>,
+    /// 打码的区域数，供用户核对
+    pub redacted: usize,
+}
+
+/// 检测敏感信息并打码
+#[tauri::command]
+pub async fn auto_redact(
+    app: AppHandle,
+    base64_data: String,
+    classes: Option<Vec<SensitiveClass>>,
+) -> Result<RedactResult, AppError> {
+    crate::run_blocking(move || {
+        let png = STANDARD.decode(&base64_data).map_err(AppError::invalid)?;
+        let (png, regions) = auto_redact_png(&app, &png, classes)?;
+        Ok(RedactResult {
+            base64_data: STANDARD.encode(png),
+            redacted: regions.len(),
+            regions,
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+
+    fn row(line: u32, word: u32, left: u32, text: &str) -> String {
+        format!("5\t1\t1\t1\t{line}\t{word}\t{left}\t10\t40\t12\t91.5\t{text}")
+    }
+
+    fn words(rows: &[String]) -> Vec<Word> {
+        let tsv = std::iter::once(HEADER.to_string())
+            .chain(rows.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        parse_tsv(&tsv, 1.0)
+    }
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn parse_tsv_keeps_words_and_scales() {
+        let tsv = [
+            HEADER.to_string(),
+            // 非单词行和空词被跳过
+            "4\t1\t1\t1\t1\t0\t0\t0\t100\t20\t-1\t".to_string(),
+            row(1, 1, 10, "  "),
+            row(1, 2, 20, "hello"),
+            "5\t1\t1\t1\t1\tx\tbad\t0\t1\t1\t90\tbroken".to_string(),
+        ]
+        .join("\n");
+        let words = parse_tsv(&tsv, 0.5);
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].text, "hello");
+        assert_eq!(words[0].line, (1, 1, 1, 1));
+        assert_eq!(words[0].region, region(10, 5, 20, 6));
+    }
+
+    #[test]
+    fn match_words_merges_words_of_one_match() {
+        let words = words(&[
+            row(1, 1, 0, "Call"),
+            row(1, 2, 50, "+49"),
+            row(1, 3, 100, "170"),
+            row(1, 4, 150, "1234567"),
+            row(2, 1, 0, "mail:"),
+            row(2, 2, 50, "a.b@example.org"),
+        ]);
+        let found = match_words(&words, BUILTIN_CLASSES, &[]);
+        let phone = found
+            .iter()
+            .find(|r| r.class == SensitiveClass::Phone)
+            .unwrap();
+        assert_eq!(phone.text, "+49 170 1234567");
+        assert_eq!(phone.region, region(50, 10, 140, 12));
+        let email = found
+            .iter()
+            .find(|r| r.class == SensitiveClass::Email)
+            .unwrap();
+        assert_eq!(email.region, region(50, 10, 40, 12));
+    }
+
+    #[test]
+    fn match_words_filters_implausible_and_uses_custom() {
+        let words = words(&[row(1, 1, 0, "12-34"), row(1, 2, 50, "ID-4711")]);
+        let custom = [Regex::new(r"ID-\d+").unwrap()];
+        let found = match_words(&words, BUILTIN_CLASSES, &custom);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].class, SensitiveClass::Custom);
+        assert_eq!(found[0].text, "ID-4711");
+        // 未选中的类别不匹配
+        assert!(match_words(&words, &[SensitiveClass::Email], &custom).is_empty());
+    }
+
+    #[test]
+    fn pixelate_clamps_to_image_bounds() {
+        let mut image = RgbaImage::from_fn(20, 20, |x, _| Rgba([(x * 10) as u8, 0, 0, 255]));
+        pixelate(
+            &mut image,
+            &[
+                region(u32::MAX, u32::MAX, u32::MAX, u32::MAX),
+                region(15, 15, u32::MAX, u32::MAX),
+                region(100, 100, 5, 5),
+            ],
+        );
+        // 右下角被打码，左上角（区域 + PADDING 之外）不变
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(11, 11).0, [110, 0, 0, 255]);
+        let block = *image.get_pixel(12, 12);
+        assert_eq!(*image.get_pixel(19, 19), block);
+    }
+}
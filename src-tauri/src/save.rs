@@ -0,0 +1,413 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Local, TimeZone};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageEncoder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::external::{self, ExternalTool};
+use crate::history::HistoryState;
+use crate::settings::SettingsState;
+
+static LAST_SAVE_DIR: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+
+fn last_save_dir_state() -> &'static Mutex<Option<PathBuf>> {
+    LAST_SAVE_DIR.get_or_init(|| Mutex::new(None))
+}
+
+/// 记住最近一次保存的目录，供下次弹出对话框时使用
+pub fn remember_dir(path: &Path) {
+    if let (Some(parent), Ok(mut guard)) = (path.parent(), last_save_dir_state().lock()) {
+        *guard = Some(parent.to_path_buf());
+    }
+}
+
+pub fn last_dir() -> Option<PathBuf> {
+    last_save_dir_state()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+}
+
+/// 弹出保存对话框，默认定位到上次保存的目录
+pub fn pick_save_path(
+    app: &AppHandle,
+    file_name: &str,
+    filter_name: &str,
+    extensions: &[&str],
+) -> Option<PathBuf> {
+    let mut dialog = app
+        .dialog()
+        .file()
+        .set_file_name(file_name)
+        .add_filter(filter_name, extensions);
+    if let Some(dir) = last_dir() {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog.blocking_save_file()?.into_path().ok()
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextFormat {
+    Txt,
+    Md,
+}
+
+impl TextFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            TextFormat::Txt => "txt",
+            TextFormat::Md => "md",
+        }
+    }
+}
+
+fn render_ocr_text(
+    text: &str,
+    format: TextFormat,
+    image_path: Option<&str>,
+    captured_at: i64,
+) -> String {
+    match format {
+        TextFormat::Txt => text.to_string(),
+        TextFormat::Md => {
+            let time = Local
+                .timestamp_opt(captured_at, 0)
+                .single()
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default();
+            let mut out = format!("# 截图文字 {time}\n\n");
+            if let Some(image) = image_path {
+                out.push_str(&format!("![截图](<{image}>)\n\n"));
+            }
+            out.push_str(text);
+            out.push('\n');
+            out
+        }
+    }
+}
+
+/// 保存 OCR 文本为 txt / md，未指定路径时弹出对话框；返回实际写入的路径
+#[tauri::command]
+pub async fn save_ocr_text(
+    app: AppHandle,
+    text: String,
+    format: TextFormat,
+    path: Option<String>,
+    image_path: Option<String>,
+    captured_at: Option<i64>,
+) -> Result<Option<String>, String> {
+    let captured_at = captured_at.unwrap_or_else(|| Local::now().timestamp());
+    let target = match path {
+        Some(p) => PathBuf::from(p),
+        None => {
+            let default_name = format!("ocr_{captured_at}.{}", format.extension());
+            let picked = pick_save_path(&app, &default_name, "Text", &[format.extension()]);
+            match picked {
+                Some(p) => p,
+                // 用户取消
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let mut content = render_ocr_text(&text, format, image_path.as_deref(), captured_at);
+    let crlf = app.state::<SettingsState>().get().crlf_line_endings;
+    if crlf {
+        content = content.replace("\r\n", "\n").replace('\n', "\r\n");
+    }
+
+    // UTF-8 无 BOM
+    crate::durable::atomic_write(&target, content.as_bytes())?;
+    remember_dir(&target);
+    Ok(Some(target.to_string_lossy().into_owned()))
+}
+
+/// 在文件管理器中定位文件时实际使用的方式
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevealMethod {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    FileManager1,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    XdgOpen,
+    #[cfg(target_os = "windows")]
+    Explorer,
+    #[cfg(target_os = "macos")]
+    Finder,
+}
+
+/// 允许定位的目录：历史目录、自动保存目录、图片目录、最近保存目录
+pub(crate) fn allowed_roots(app: &AppHandle) -> Vec<PathBuf> {
+    let mut roots = vec![app.state::<HistoryState>().dir().to_path_buf()];
+    if let Some(dir) = app.state::<SettingsState>().get().save_dir {
+        roots.push(PathBuf::from(dir));
+    }
+    if let Ok(dir) = app.path().picture_dir() {
+        roots.push(dir);
+    }
+    if let Some(dir) = last_dir() {
+        roots.push(dir);
+    }
+    roots
+        .into_iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .collect()
+}
+
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for b in path.to_string_lossy().bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+    uri
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn reveal_platform(path: &Path) -> Result<RevealMethod, String> {
+    // 优先通过 FileManager1 接口定位并选中文件（Nautilus / Dolphin 等均支持）
+    let items = format!("array:string:{}", file_uri(path));
+    let shown = external::run_external(
+        ExternalTool::DbusSend,
+        [
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+            &items,
+            "string:",
+        ],
+        None,
+        external::PROBE_TIMEOUT,
+    );
+    if shown.is_ok() {
+        return Ok(RevealMethod::FileManager1);
+    }
+
+    let parent = path.parent().unwrap_or(path);
+    external::command(&ExternalTool::XdgOpen)
+        .map_err(|e| e.to_string())?
+        .arg(parent)
+        .spawn()
+        .map_err(|e| format!("xdg-open: {e}"))?;
+    Ok(RevealMethod::XdgOpen)
+}
+
+#[cfg(target_os = "windows")]
+fn reveal_platform(path: &Path) -> Result<RevealMethod, String> {
+    external::command(&ExternalTool::Explorer)
+        .map_err(|e| e.to_string())?
+        .arg(format!("/select,{}", path.display()))
+        .spawn()
+        .map_err(|e| format!("explorer: {e}"))?;
+    Ok(RevealMethod::Explorer)
+}
+
+#[cfg(target_os = "macos")]
+fn reveal_platform(path: &Path) -> Result<RevealMethod, String> {
+    external::command(&ExternalTool::Open)
+        .map_err(|e| e.to_string())?
+        .arg("-R")
+        .arg(path)
+        .spawn()
+        .map_err(|e| format!("open: {e}"))?;
+    Ok(RevealMethod::Finder)
+}
+
+/// 在文件管理器中显示并选中文件，仅允许定位本应用保存过文件的目录
+pub fn reveal_path(app: &AppHandle, path: &Path) -> Result<RevealMethod, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("文件不存在 {}: {e}", path.display()))?;
+    if !allowed_roots(app).iter().any(|root| path.starts_with(root)) {
+        return Err(format!("不允许打开该位置: {}", path.display()));
+    }
+    reveal_platform(&path)
+}
+
+#[tauri::command]
+pub fn reveal_in_file_manager(app: AppHandle, path: String) -> Result<RevealMethod, String> {
+    reveal_path(&app, Path::new(&path))
+}
+
+/// 保存/上传时可选的图片格式
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "png" => Ok(ExportFormat::Png),
+            "jpg" | "jpeg" => Ok(ExportFormat::Jpeg),
+            "webp" => Ok(ExportFormat::Webp),
+            "avif" => Ok(ExportFormat::Avif),
+            _ => Err(format!("不支持的图片格式: {ext}")),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Jpeg => "jpg",
+            ExportFormat::Webp => "webp",
+            ExportFormat::Avif => "avif",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "image/png",
+            ExportFormat::Jpeg => "image/jpeg",
+            ExportFormat::Webp => "image/webp",
+            ExportFormat::Avif => "image/avif",
+        }
+    }
+}
+
+/// 默认编码质量
+pub const DEFAULT_QUALITY: u8 = 85;
+
+/// 按格式编码图片；quality 取 1-100，对 JPEG/AVIF 生效，WebP 使用无损编码
+pub fn encode_image(
+    img: &DynamicImage,
+    format: ExportFormat,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let quality = quality.clamp(1, 100);
+    let mut buf = Vec::new();
+    match format {
+        ExportFormat::Png => {
+            img.write_to(&mut std::io::Cursor::new(&mut buf), image::ImageFormat::Png)
+                .map_err(|e| e.to_string())?;
+        }
+        ExportFormat::Jpeg => {
+            // JPEG 不支持透明通道
+            let rgb = img.to_rgb8();
+            JpegEncoder::new_with_quality(&mut buf, quality)
+                .write_image(
+                    rgb.as_raw(),
+                    rgb.width(),
+                    rgb.height(),
+                    image::ExtendedColorType::Rgb8,
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        ExportFormat::Webp => {
+            let rgba = img.to_rgba8();
+            WebPEncoder::new_lossless(&mut buf)
+                .write_image(
+                    rgba.as_raw(),
+                    rgba.width(),
+                    rgba.height(),
+                    image::ExtendedColorType::Rgba8,
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        ExportFormat::Avif => encode_avif(img, quality, &mut buf)?,
+    }
+    Ok(buf)
+}
+
+#[cfg(feature = "avif")]
+fn encode_avif(img: &DynamicImage, quality: u8, buf: &mut Vec<u8>) -> Result<(), String> {
+    use image::codecs::avif::AvifEncoder;
+
+    let rgba = img.to_rgba8();
+    // speed 取 6，在体积与耗时之间折中
+    AvifEncoder::new_with_speed_quality(buf, 6, quality)
+        .write_image(
+            rgba.as_raw(),
+            rgba.width(),
+            rgba.height(),
+            image::ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "avif"))]
+fn encode_avif(_img: &DynamicImage, _quality: u8, _buf: &mut Vec<u8>) -> Result<(), String> {
+    Err("当前版本未启用 AVIF 支持，请使用 --features avif 重新编译".into())
+}
+
+#[derive(Clone, Serialize)]
+struct EncodeProgress {
+    format: ExportFormat,
+    stage: &'static str,
+    elapsed_ms: u64,
+}
+
+/// 在阻塞线程池中编码，开始与结束时发出 encode-progress 事件（AVIF 大图可能耗时数秒）
+pub async fn encode_in_background(
+    app: &AppHandle,
+    img: DynamicImage,
+    format: ExportFormat,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let start = Instant::now();
+    let _ = app.emit(
+        "encode-progress",
+        EncodeProgress {
+            format,
+            stage: "started",
+            elapsed_ms: 0,
+        },
+    );
+    let result = tauri::async_runtime::spawn_blocking(move || encode_image(&img, format, quality))
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit(
+        "encode-progress",
+        EncodeProgress {
+            format,
+            stage: "finished",
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        },
+    );
+    result
+}
+
+#[derive(Serialize)]
+pub struct EncodedSize {
+    pub original: u64,
+    pub encoded: u64,
+}
+
+/// 在内存中试编码，返回编码后的字节数，供前端展示“1.2 MB → 180 KB”
+#[tauri::command]
+pub async fn estimate_encoded_size(
+    app: AppHandle,
+    base64_data: String,
+    format: ExportFormat,
+    quality: Option<u8>,
+) -> Result<EncodedSize, String> {
+    let data = STANDARD.decode(&base64_data).map_err(|e| e.to_string())?;
+    let img = image::load_from_memory(&data).map_err(|e| e.to_string())?;
+    let encoded =
+        encode_in_background(&app, img, format, quality.unwrap_or(DEFAULT_QUALITY)).await?;
+    Ok(EncodedSize {
+        original: data.len() as u64,
+        encoded: encoded.len() as u64,
+    })
+}
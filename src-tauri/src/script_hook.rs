@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::external::{self, ExternalTool};
+use crate::notify::{self, Notice, NotifyEvent};
+use crate::settings::SettingsState;
+
+const MAX_TIMEOUT_S: u64 = 600;
+/// 写入日志的输出长度上限
+const MAX_LOGGED_OUTPUT: usize = 4000;
+/// 命令模板中替换为截图文件路径的占位符
+pub const FILE_PLACEHOLDER: &str = "{file}";
+
+/// 截图后执行的用户脚本。只能在设置文件中手动添加和开启，界面和导入的设置都不能修改
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureHook {
+    pub name: String,
+    /// 命令模板，如 `~/bin/process.sh {file}`；不经过 shell，按空白和引号拆分参数。
+    /// 程序须为绝对路径或以 ~/ 开头，不在 PATH 中查找
+    pub command: String,
+    pub enabled: bool,
+    pub timeout_s: u64,
+}
+
+impl Default for CaptureHook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            enabled: false,
+            timeout_s: 30,
+        }
+    }
+}
+
+pub fn validate(hooks: &[CaptureHook]) -> Result<(), String> {
+    for hook in hooks {
+        if hook.name.trim().is_empty() {
+            return Err("截图脚本名称不能为空".into());
+        }
+        let args = split_command(&hook.command)
+            .map_err(|e| format!("截图脚本 {} 的命令无效: {e}", hook.name))?;
+        let Some(program) = args.first() else {
+            return Err(format!("截图脚本 {} 的命令为空", hook.name));
+        };
+        if !program.starts_with("~/") && !Path::new(program).is_absolute() {
+            return Err(format!(
+                "截图脚本 {} 的程序必须是绝对路径或以 ~/ 开头: {program}",
+                hook.name
+            ));
+        }
+        if !args.iter().any(|arg| arg.contains(FILE_PLACEHOLDER)) {
+            return Err(format!(
+                "截图脚本 {} 的命令中缺少 {FILE_PLACEHOLDER}",
+                hook.name
+            ));
+        }
+        if hook.timeout_s == 0 || hook.timeout_s > MAX_TIMEOUT_S {
+            return Err(format!(
+                "截图脚本 {} 的超时必须在 1 到 {MAX_TIMEOUT_S} 秒之间",
+                hook.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 按空白拆分参数，支持单引号（原样）、双引号和反斜杠转义
+fn split_command(template: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                args.extend(current.take());
+            }
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err("单引号未闭合".into()),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => return Err("双引号未闭合".into()),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err("双引号未闭合".into()),
+                    }
+                }
+            }
+            '\\' => {
+                let c = chars.next().ok_or("命令以反斜杠结尾")?;
+                current.get_or_insert_with(String::new).push(c);
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// 按模板组装命令：开头的 ~/ 展开为主目录，{file} 替换为截图路径。
+/// 每个参数单独传给程序，路径中的空格和特殊字符不会被解释
+fn build_command(
+    app: &AppHandle,
+    hook: &CaptureHook,
+    file: &Path,
+) -> Result<(ExternalTool, Vec<String>), String> {
+    let home = app.path().home_dir().ok();
+    let file = file.to_string_lossy();
+    let mut args = split_command(&hook.command)?.into_iter().map(|arg| {
+        let arg = match (arg.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+            _ => arg,
+        };
+        arg.replace(FILE_PLACEHOLDER, &file)
+    });
+    let program = args.next().ok_or("命令为空")?;
+    Ok((ExternalTool::User(PathBuf::from(program)), args.collect()))
+}
+
+/// 创建本次截图的临时文件，不覆盖已有文件
+fn write_temp(png: &[u8]) -> Result<PathBuf, String> {
+    let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S_%f");
+    let path = std::env::temp_dir().join(format!("prinsp-hook-{}-{stamp}.png", std::process::id()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| format!("创建临时文件失败: {e}"))?;
+    file.write_all(png)
+        .map_err(|e| format!("写入临时文件失败: {e}"))?;
+    Ok(path)
+}
+
+fn truncate(output: &[u8]) -> String {
+    let text = String::from_utf8_lossy(output);
+    let text = text.trim();
+    match text.char_indices().nth(MAX_LOGGED_OUTPUT) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// 执行脚本，返回脚本在标准输出最后一行给出的替换文件
+fn run_hook(app: &AppHandle, hook: &CaptureHook, file: &Path) -> Result<Option<PathBuf>, String> {
+    let (tool, args) = build_command(app, hook, file)?;
+    let result = external::run_external(tool, args, None, Duration::from_secs(hook.timeout_s));
+    // 脚本输出只写日志，可在日志查看器中查看
+    let output = match result {
+        Ok(output) => output,
+        Err(err) => {
+            tracing::info!(hook = %hook.name, error = %err, "截图脚本已结束");
+            return Err(err.to_string());
+        }
+    };
+    tracing::info!(
+        hook = %hook.name,
+        stdout = %truncate(&output.stdout),
+        stderr = %truncate(&output.stderr),
+        "截图脚本已结束"
+    );
+    let replacement = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(PathBuf::from);
+    match replacement {
+        Some(path) if path.is_file() => Ok(Some(path)),
+        // 普通的日志输出不是文件路径，沿用原截图
+        _ => Ok(None),
+    }
+}
+
+/// 读取替换文件并转成 PNG，后续动作都以 PNG 处理
+fn load_replacement(path: &Path) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("读取 {} 失败: {e}", path.display()))?;
+    if data.starts_with(b"\x89PNG") {
+        return Ok(data);
+    }
+    let image = image::load_from_memory(&data)
+        .map_err(|e| format!("{} 不是可识别的图片: {e}", path.display()))?;
+    crate::save::encode_image(
+        &image,
+        crate::save::ExportFormat::Png,
+        crate::save::DEFAULT_QUALITY,
+    )
+}
+
+fn apply_hook(app: &AppHandle, hook: &CaptureHook, png: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let temp = write_temp(png)?;
+    let result = run_hook(app, hook, &temp).and_then(|replacement| {
+        // 脚本可能直接修改临时文件并输出同一路径
+        replacement.map(|path| load_replacement(&path)).transpose()
+    });
+    let _ = std::fs::remove_file(&temp);
+    result
+}
+
+/// 依次执行已开启的截图脚本，每个脚本处理上一个的结果。
+/// 脚本失败时记录日志并提示，继续使用失败前的截图，不会丢失原图
+pub fn run_enabled(app: &AppHandle, png: Vec<u8>) -> Vec<u8> {
+    let hooks = app.state::<SettingsState>().get().capture_hooks;
+    let mut png = png;
+    for hook in hooks.iter().filter(|h| h.enabled) {
+        match apply_hook(app, hook, &png) {
+            Ok(Some(replaced)) => {
+                tracing::info!(hook = %hook.name, bytes = replaced.len(), "截图已被脚本替换");
+                png = replaced;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                tracing::warn!(hook = %hook.name, error = %err, "截图脚本失败");
+                notify::notify(
+                    app,
+                    Notice::new(
+                        NotifyEvent::Error,
+                        "截图脚本失败，已使用原截图",
+                        format!("{}: {err}", hook.name),
+                    ),
+                );
+            }
+        }
+    }
+    png
+}
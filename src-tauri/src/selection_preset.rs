@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::overlay::MonitorInfo;
+use crate::settings::SettingsState;
+
+/// 命名的固定尺寸选区，如文档配图用的 1280×720
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SelectionPreset {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// 选区左上角的固定位置（桌面物理像素）；为空时以光标为中心
+    #[serde(default)]
+    pub position: Option<PresetPosition>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PresetPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// 预设落到具体显示器上的选区
+#[derive(Clone, Debug, Serialize)]
+pub struct ResolvedSelection {
+    pub monitor: u32,
+    /// 相对于显示器左上角
+    pub region: Region,
+    /// 预设大于显示器，尺寸被缩小
+    pub clamped: bool,
+}
+
+pub fn validate(preset: &SelectionPreset) -> Result<(), String> {
+    if preset.name.trim().is_empty() {
+        return Err("选区预设名称不能为空".into());
+    }
+    // 名称用在快捷键动作 quick_capture:<名称> 中
+    if preset.name.contains(':') {
+        return Err(format!("选区预设名称不能包含冒号: {}", preset.name));
+    }
+    if preset.width == 0 || preset.height == 0 {
+        return Err(format!("选区预设 {} 的宽高必须大于 0", preset.name));
+    }
+    Ok(())
+}
+
+pub fn validate_all(presets: &[SelectionPreset]) -> Result<(), String> {
+    for (i, preset) in presets.iter().enumerate() {
+        validate(preset)?;
+        if presets[..i].iter().any(|p| p.name == preset.name) {
+            return Err(format!("选区预设重名: {}", preset.name));
+        }
+    }
+    Ok(())
+}
+
+/// 把长为 len 的区间放进 [start, start + size)，返回起点
+fn clamp_span(origin: i32, len: u32, start: i32, size: u32) -> i32 {
+    let max = start + size.saturating_sub(len) as i32;
+    origin.clamp(start, max)
+}
+
+/// 按锚点所在的显示器计算选区，超出显示器的部分移回显示器内
+fn place(
+    preset: &SelectionPreset,
+    cursor: (i32, i32),
+    monitors: &[MonitorInfo],
+) -> Option<ResolvedSelection> {
+    let anchor = preset.position.map(|p| (p.x, p.y)).unwrap_or(cursor);
+    let contains = |m: &&MonitorInfo| {
+        anchor.0 >= m.x
+            && anchor.1 >= m.y
+            && anchor.0 < m.x + m.width as i32
+            && anchor.1 < m.y + m.height as i32
+    };
+    // 固定位置所在的显示器已断开时落到主显示器
+    let monitor = monitors
+        .iter()
+        .find(contains)
+        .or_else(|| monitors.iter().find(|m| m.primary))
+        .or_else(|| monitors.first())?;
+    let width = preset.width.min(monitor.width);
+    let height = preset.height.min(monitor.height);
+    let (left, top) = match preset.position {
+        Some(p) => (p.x, p.y),
+        None => (
+            cursor.0 - (width / 2) as i32,
+            cursor.1 - (height / 2) as i32,
+        ),
+    };
+    let left = clamp_span(left, width, monitor.x, monitor.width);
+    let top = clamp_span(top, height, monitor.y, monitor.height);
+    Some(ResolvedSelection {
+        monitor: monitor.id,
+        region: Region {
+            x: (left - monitor.x) as u32,
+            y: (top - monitor.y) as u32,
+            width,
+            height,
+        },
+        clamped: width != preset.width || height != preset.height,
+    })
+}
+
+/// 按名称查找预设并落到当前光标或固定位置所在的显示器
+pub fn resolve(app: &AppHandle, name: &str) -> Result<ResolvedSelection, AppError> {
+    let preset = app
+        .state::<SettingsState>()
+        .get()
+        .selection_presets
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| AppError::invalid(format!("未找到选区预设: {name}")))?;
+    let cursor = app
+        .cursor_position()
+        .map(|p| (p.x as i32, p.y as i32))
+        .map_err(AppError::window)?;
+    let monitors = crate::overlay::list_monitors().map_err(AppError::Internal)?;
+    let resolved = place(&preset, cursor, &monitors)
+        .ok_or_else(|| AppError::Internal("未找到显示器".into()))?;
+    if resolved.clamped {
+        tracing::warn!(
+            preset = name,
+            width = resolved.region.width,
+            height = resolved.region.height,
+            "选区预设大于显示器，已缩小"
+        );
+    }
+    Ok(resolved)
+}
+
+#[tauri::command]
+pub fn list_selection_presets(app: AppHandle) -> Vec<SelectionPreset> {
+    app.state::<SettingsState>().get().selection_presets
+}
+
+/// 校验后保存到设置，同名预设被替换
+#[tauri::command]
+pub fn save_selection_preset(
+    app: AppHandle,
+    def: SelectionPreset,
+) -> Result<Vec<SelectionPreset>, AppError> {
+    validate(&def).map_err(AppError::InvalidInput)?;
+    app.state::<SettingsState>()
+        .update(
+            |s| match s.selection_presets.iter_mut().find(|p| p.name == def.name) {
+                Some(existing) => *existing = def,
+                None => s.selection_presets.push(def),
+            },
+        )
+        .map_err(AppError::InvalidInput)?;
+    Ok(list_selection_presets(app))
+}
+
+#[tauri::command]
+pub fn delete_selection_preset(
+    app: AppHandle,
+    name: String,
+) -> Result<Vec<SelectionPreset>, AppError> {
+    let settings = app.state::<SettingsState>();
+    if !settings
+        .get()
+        .selection_presets
+        .iter()
+        .any(|p| p.name == name)
+    {
+        return Err(AppError::invalid(format!("未找到选区预设: {name}")));
+    }
+    settings
+        .update(|s| s.selection_presets.retain(|p| p.name != name))
+        .map_err(AppError::InvalidInput)?;
+    Ok(list_selection_presets(app))
+}
+
+/// 预设在当前光标位置对应的显示器与选区，遮罩据此摆放选框
+#[tauri::command]
+pub fn resolve_selection_preset(
+    app: AppHandle,
+    name: String,
+) -> Result<ResolvedSelection, AppError> {
+    resolve(&app, &name)
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// 截图会话编号：每次开始或取消截图时递增，耗时任务完成后据此判断结果是否已过期
+static SESSION: AtomicU64 = AtomicU64::new(0);
+/// 进行中截图的开始时间：从触发截图到完成或取消
+static ACTIVE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// 截图进行中再次触发时的处理方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetriggerPolicy {
+    /// 忽略新的触发，通知前端提示当前截图
+    #[default]
+    Ignore,
+    /// 取消当前截图并重新开始
+    Restart,
+}
+
+pub fn current() -> u64 {
+    SESSION.load(Ordering::SeqCst)
+}
+
+/// 开始新的截图会话，之前会话中未完成的任务结果将被丢弃
+pub fn begin() -> u64 {
+    mark_active();
+    SESSION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+pub fn cancel() {
+    end();
+    SESSION.fetch_add(1, Ordering::SeqCst);
+}
+
+pub fn is_current(id: u64) -> bool {
+    current() == id
+}
+
+/// 标记截图进行中，已在进行中时保留原来的开始时间
+pub fn mark_active() {
+    if let Ok(mut active) = ACTIVE.lock() {
+        active.get_or_insert_with(Instant::now);
+    }
+}
+
+/// 截图完成或取消
+pub fn end() {
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = None;
+    }
+}
+
+pub fn active_since() -> Option<Instant> {
+    ACTIVE.lock().ok().and_then(|active| *active)
+}
@@ -0,0 +1,737 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder};
+
+use crate::anki::AnkiSettings;
+use crate::capture::{self, CaptureBackend, CaptureConfig};
+use crate::extract::{self, ExtractionTemplate};
+use crate::history::HistoryDedupSettings;
+use crate::http::ProxySettings;
+use crate::local_api::LocalApiSettings;
+use crate::night_light::NightLightSettings;
+use crate::notes::NoteExportOptions;
+use crate::notify::NotificationSettings;
+use crate::pipeline::{self, PipelinePreset};
+use crate::postprocess_rules::{self, PostprocessRule};
+use crate::quick::ReadAtCursorSettings;
+use crate::redact::RedactSettings;
+use crate::script_hook::{self, CaptureHook};
+use crate::selection_preset::{self, SelectionPreset};
+use crate::session::RetriggerPolicy;
+use crate::sound::SoundSettings;
+use crate::storage::RetentionSettings;
+use crate::translate::TranslateSettings;
+use crate::tray::TrayClickSettings;
+use crate::upload::UploadProfile;
+use crate::webhook::{self, Webhook};
+use crate::window_trim::WindowTrimSettings;
+
+/// 独立设置窗口的标签
+pub const SETTINGS_WINDOW: &str = "settings";
+
+/// 默认 OCR 语言，中文优先
+pub const DEFAULT_OCR_LANG: &str = "chi_sim+eng";
+
+/// 后台识别线程数上限
+const MAX_OCR_WORKERS: usize = 16;
+
+/// 各截图后端的超时
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureTimeouts {
+    pub grim_ms: u64,
+    pub xcap_ms: u64,
+}
+
+impl Default for CaptureTimeouts {
+    fn default() -> Self {
+        // grim 不可用时要快速失败
+        Self {
+            grim_ms: 500,
+            xcap_ms: 1500,
+        }
+    }
+}
+
+/// 当前设置文件的结构版本
+pub const SETTINGS_VERSION: u32 = 2;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// 依次把 vN 升级到 vN+1，下标 0 为 v1→v2；修改字段名时在末尾追加并提升 SETTINGS_VERSION
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
+const _: () = assert!(MIGRATIONS.len() as u32 == SETTINGS_VERSION - 1);
+
+/// v1 只有单个 shortcut 字段，对应截图动作
+fn migrate_v1_to_v2(obj: &mut Map<String, Value>) {
+    if let Some(legacy) = obj.remove("shortcut") {
+        if !obj.contains_key("shortcuts") {
+            obj.insert("shortcuts".into(), serde_json::json!({ "capture": legacy }));
+        }
+    }
+}
+
+/// 读取设置文件失败的原因
+enum LoadError {
+    /// 来自更新版本的文件，不能改动
+    TooNew(String),
+    Invalid(String),
+}
+
+impl From<LoadError> for String {
+    fn from(err: LoadError) -> Self {
+        match err {
+            LoadError::TooNew(msg) | LoadError::Invalid(msg) => msg,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    /// 各动作的全局快捷键（动作 → 快捷键），插件要求的小写形式
+    pub shortcuts: BTreeMap<String, String>,
+    /// 默认 OCR 语言
+    pub ocr_lang: String,
+    /// 自动保存目录，为空则每次弹出对话框
+    pub save_dir: Option<String>,
+    /// 自动保存的文件名模板（不含扩展名），支持 {app} {date} {time}
+    pub filename_template: String,
+    /// 首选截图后端（grim / xcap / gnome-screenshot）
+    pub preferred_backend: Option<String>,
+    /// 启用的截图后端，按优先级排列
+    pub capture_backends: Vec<String>,
+    /// 截图后端超时
+    pub capture_timeouts: CaptureTimeouts,
+    /// 按截图自带的 ICC 色彩配置转换为 sRGB，关闭后保留后端输出的原始像素
+    pub srgb_normalize: bool,
+    /// 夜灯色温补偿
+    pub night_light: NightLightSettings,
+    /// 截图完成后依次执行的动作
+    pub post_capture_actions: Vec<String>,
+    /// 命名的动作组合
+    pub pipeline_presets: Vec<PipelinePreset>,
+    /// 当前激活的预设名，为空时使用 post_capture_actions
+    pub active_pipeline: Option<String>,
+    /// 保存文本时使用 Windows 换行符
+    pub crlf_line_endings: bool,
+    /// 图床上传配置
+    pub upload_profiles: Vec<UploadProfile>,
+    /// 按事件类型开关桌面通知
+    pub notifications: NotificationSettings,
+    /// 历史保留策略
+    pub history_retention: RetentionSettings,
+    /// 历史中相同截图去重
+    pub history_dedup: HistoryDedupSettings,
+    /// 导入文件夹时把图片复制到历史目录；关闭时只引用原文件
+    pub history_import_copy: bool,
+    /// 导出到笔记库的默认配置
+    pub note_export: NoteExportOptions,
+    /// Anki 制卡配置
+    pub anki: AnkiSettings,
+    /// 全局快捷键是否暂停
+    pub shortcuts_paused: bool,
+    /// 暂停自动结束的时间（Unix 秒）
+    pub shortcuts_paused_until: Option<i64>,
+    /// 关闭主窗口时隐藏到托盘而不是退出
+    pub close_to_tray: bool,
+    /// 是否已提示过“程序仍在托盘运行”
+    pub tray_hint_shown: bool,
+    /// 启动时只显示托盘图标，不显示主窗口
+    pub start_hidden: bool,
+    /// 启动后立即开始截图（自启动时不生效）
+    pub capture_on_launch: bool,
+    /// 界面语言（zh-CN / en-US），为空时跟随系统
+    pub locale: Option<String>,
+    /// 托盘图标单击、双击、中键对应的动作
+    pub tray_click: TrayClickSettings,
+    /// 网络代理
+    pub proxy: ProxySettings,
+    /// 记录 debug 级别的详细日志
+    pub debug_logging: bool,
+    /// 截图进行中再次按下快捷键或点击托盘时忽略还是重新开始
+    pub capture_retrigger: RetriggerPolicy,
+    /// 窗口已隐藏时先截图再显示遮罩，省去隐藏窗口后的等待；关闭时总是先隐藏窗口再截图
+    pub instant_overlay: bool,
+    /// 后台识别的工作线程数，为空时按 CPU 核心数自动选择
+    pub ocr_workers: Option<usize>,
+    /// 启动后在后台识别一张内置小图，减少第一次识别的等待
+    pub ocr_warmup: bool,
+    /// 翻译服务
+    pub translate: TranslateSettings,
+    /// 在会话总线上提供 io.github.prinsp.Prinsp 服务（仅 Linux）
+    pub dbus_service: bool,
+    /// 本机 HTTP 接口
+    pub local_api: LocalApiSettings,
+    /// 截图保存、识别、上传完成后通知的 Webhook
+    pub webhooks: Vec<Webhook>,
+    /// 截图后执行的用户脚本，只能在设置文件中修改
+    pub capture_hooks: Vec<CaptureHook>,
+    /// 光标处取字
+    pub read_at_cursor: ReadAtCursorSettings,
+    /// 复制、保存、上传前自动打码敏感信息
+    pub redact: RedactSettings,
+    /// 用户定义的字段提取模板，同名时覆盖内置模板
+    pub extraction_templates: Vec<ExtractionTemplate>,
+    /// 固定尺寸的选区预设
+    pub selection_presets: Vec<SelectionPreset>,
+    /// 用户定义的识别结果后处理规则，按顺序执行
+    pub postprocess_rules: Vec<PostprocessRule>,
+    /// 窗口截图去掉透明边距、阴影和标题栏，为空的项按桌面环境取默认值
+    pub window_trim: WindowTrimSettings,
+    /// 截图、复制、出错时的提示音
+    pub sound: SoundSettings,
+    /// 在本地按天统计截图、识别和复制的次数（不记录内容）
+    pub stats_enabled: bool,
+    /// 重启后恢复钉在桌面上的截图
+    pub persist_pins: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            shortcuts: crate::shortcuts::default_shortcuts(),
+            ocr_lang: DEFAULT_OCR_LANG.into(),
+            save_dir: None,
+            filename_template: pipeline::DEFAULT_FILENAME_TEMPLATE.into(),
+            preferred_backend: None,
+            capture_backends: vec!["grim".into(), "xcap".into(), "gnome-screenshot".into()],
+            capture_timeouts: CaptureTimeouts::default(),
+            srgb_normalize: true,
+            night_light: NightLightSettings::default(),
+            post_capture_actions: vec!["copy_image".into()],
+            pipeline_presets: Vec::new(),
+            active_pipeline: None,
+            crlf_line_endings: false,
+            upload_profiles: Vec::new(),
+            notifications: NotificationSettings::default(),
+            history_retention: RetentionSettings::default(),
+            history_dedup: HistoryDedupSettings::default(),
+            history_import_copy: false,
+            note_export: NoteExportOptions::default(),
+            anki: AnkiSettings::default(),
+            shortcuts_paused: false,
+            shortcuts_paused_until: None,
+            close_to_tray: true,
+            tray_hint_shown: false,
+            start_hidden: false,
+            capture_on_launch: true,
+            locale: None,
+            tray_click: TrayClickSettings::default(),
+            proxy: ProxySettings::default(),
+            debug_logging: false,
+            capture_retrigger: RetriggerPolicy::default(),
+            instant_overlay: true,
+            ocr_workers: None,
+            ocr_warmup: true,
+            translate: TranslateSettings::default(),
+            dbus_service: false,
+            local_api: LocalApiSettings::default(),
+            webhooks: Vec::new(),
+            capture_hooks: Vec::new(),
+            read_at_cursor: ReadAtCursorSettings::default(),
+            redact: RedactSettings::default(),
+            extraction_templates: Vec::new(),
+            selection_presets: Vec::new(),
+            postprocess_rules: Vec::new(),
+            window_trim: WindowTrimSettings::default(),
+            sound: SoundSettings::default(),
+            stats_enabled: true,
+            persist_pins: false,
+        }
+    }
+}
+
+impl Settings {
+    /// 从 JSON 解析并按版本依次迁移；缺失字段使用默认值，更高版本直接拒绝
+    fn from_json(data: &[u8]) -> Result<Self, LoadError> {
+        let invalid = |e: serde_json::Error| LoadError::Invalid(format!("设置文件格式错误: {e}"));
+        let mut value: Value = serde_json::from_slice(data).map_err(invalid)?;
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(1) as u32;
+        if version > SETTINGS_VERSION {
+            return Err(LoadError::TooNew(format!(
+                "设置文件版本 {version} 高于当前支持的版本 {SETTINGS_VERSION}，请升级 PrinSp"
+            )));
+        }
+
+        let obj = value
+            .as_object_mut()
+            .ok_or_else(|| LoadError::Invalid("设置文件格式错误: 应为 JSON 对象".into()))?;
+        for migration in &MIGRATIONS[version.max(1) as usize - 1..] {
+            migration(obj);
+        }
+        obj.insert("version".into(), SETTINGS_VERSION.into());
+
+        serde_json::from_value(value).map_err(invalid)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (action, accel) in &self.shortcuts {
+            if accel.trim().is_empty() {
+                return Err(format!("快捷键不能为空: {action}"));
+            }
+        }
+        if self.ocr_lang.trim().is_empty() {
+            return Err("OCR 语言不能为空".into());
+        }
+        if matches!(self.ocr_workers, Some(n) if n == 0 || n > MAX_OCR_WORKERS) {
+            return Err(format!("识别线程数必须在 1 到 {MAX_OCR_WORKERS} 之间"));
+        }
+        if self.capture_timeouts.grim_ms == 0 || self.capture_timeouts.xcap_ms == 0 {
+            return Err("截图超时必须大于 0".into());
+        }
+        let backends = self.capture_backends.iter().chain(&self.preferred_backend);
+        for name in backends {
+            if CaptureBackend::from_name(name).is_none() {
+                return Err(format!("未知的截图后端: {name}"));
+            }
+        }
+        if self.capture_backends.is_empty() {
+            return Err("至少需要启用一个截图后端，否则无法截图".into());
+        }
+        let preset_actions = self.pipeline_presets.iter().flat_map(|p| &p.actions);
+        for action in self.post_capture_actions.iter().chain(preset_actions) {
+            pipeline::validate_action(action)?;
+        }
+        if let Some(name) = &self.active_pipeline {
+            if !self.pipeline_presets.iter().any(|p| &p.name == name) {
+                return Err(format!("未找到动作预设: {name}"));
+            }
+        }
+        self.proxy.validate()?;
+        self.translate.validate()?;
+        self.local_api.validate()?;
+        webhook::validate(&self.webhooks)?;
+        script_hook::validate(&self.capture_hooks)?;
+        self.read_at_cursor.validate()?;
+        self.redact.validate()?;
+        self.night_light.validate()?;
+        self.window_trim.validate()?;
+        self.sound.validate()?;
+        self.history_dedup.validate()?;
+        pipeline::validate_filename_template(&self.filename_template)?;
+        extract::validate_all(&self.extraction_templates)?;
+        selection_preset::validate_all(&self.selection_presets)?;
+        postprocess_rules::validate_all(&self.postprocess_rules)?;
+        for profile in &self.upload_profiles {
+            if profile.name.trim().is_empty() {
+                return Err("上传配置名称不能为空".into());
+            }
+            if let Some(pattern) = &profile.url_regex {
+                regex::Regex::new(pattern)
+                    .map_err(|e| format!("上传配置 {} 的正则无效: {e}", profile.name))?;
+            }
+            if profile.jpeg_max_side == Some(0) {
+                return Err(format!(
+                    "上传配置 {} 的 JPEG 长边上限必须大于 0",
+                    profile.name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct SettingsState {
+    path: PathBuf,
+    current: RwLock<Settings>,
+    /// 设置文件来自更新的版本时不写回，避免覆盖
+    locked: RwLock<Option<String>>,
+}
+
+impl SettingsState {
+    /// 读取配置目录下的 settings.json；不存在时使用默认值，无法解析时先把原文件移到一旁，
+    /// 再尝试上一次写入前保留的 .bak；版本更高时保留原文件不动
+    pub fn load(path: PathBuf) -> Self {
+        let mut locked = None;
+        let settings = match std::fs::read(&path).map(|data| Settings::from_json(&data)) {
+            Ok(Ok(settings)) => settings,
+            Ok(Err(LoadError::TooNew(msg))) => {
+                tracing::warn!(reason = %msg, "设置文件版本较新，只读加载");
+                locked = Some(msg);
+                Settings::default()
+            }
+            Ok(Err(LoadError::Invalid(msg))) => {
+                tracing::warn!(reason = %msg, "设置文件无法读取");
+                Self::backup_aside(&path, "corrupt");
+                crate::durable::read_backup(&path, |data| match Settings::from_json(data) {
+                    Ok(settings) => Ok(settings),
+                    Err(LoadError::Invalid(msg) | LoadError::TooNew(msg)) => Err(msg),
+                })
+                .unwrap_or_default()
+            }
+            Err(_) => Settings::default(),
+        };
+        Self {
+            path,
+            current: RwLock::new(settings),
+            locked: RwLock::new(locked),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current.read().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// 把文件改名为 settings.json.<原因>-<时间>，返回新路径
+    fn backup_aside(path: &Path, reason: &str) -> Option<PathBuf> {
+        let stamp = Local::now().format("%Y%m%d-%H%M%S");
+        let mut name = path.file_name()?.to_os_string();
+        name.push(format!(".{reason}-{stamp}"));
+        let backup = path.with_file_name(name);
+        match std::fs::rename(path, &backup) {
+            Ok(()) => {
+                tracing::warn!(backup = %backup.display(), "原设置文件已备份");
+                Some(backup)
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "备份设置文件失败");
+                None
+            }
+        }
+    }
+
+    fn write_file(path: &Path, settings: &Settings) -> Result<(), String> {
+        let data = serde_json::to_vec_pretty(settings).map_err(|e| e.to_string())?;
+        crate::durable::write_with_backup(path, &data)
+    }
+
+    /// 校验通过后先落盘再替换内存中的设置，任一步失败都不影响当前设置
+    fn replace(&self, settings: Settings) -> Result<(), String> {
+        if let Some(reason) = self.locked.read().map_err(|e| e.to_string())?.clone() {
+            return Err(reason);
+        }
+        settings.validate()?;
+        Self::write_file(&self.path, &settings)?;
+        let mut guard = self.current.write().map_err(|e| e.to_string())?;
+        *guard = settings;
+        Ok(())
+    }
+
+    /// 在当前设置上修改并保存
+    pub fn update(&self, f: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+        let mut settings = self.get();
+        f(&mut settings);
+        self.replace(settings.clone())?;
+        Ok(settings)
+    }
+}
+
+/// settings-changed 事件内容：变化的字段名及新值
+#[derive(Clone, Serialize)]
+pub struct SettingsChanged {
+    pub keys: Vec<String>,
+    pub values: Map<String, Value>,
+}
+
+fn changed_fields(old: &Settings, new: &Settings) -> SettingsChanged {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return SettingsChanged {
+            keys: Vec::new(),
+            values: Map::new(),
+        };
+    };
+    let values: Map<String, Value> = new
+        .into_iter()
+        .filter(|(key, value)| old.get(key) != Some(value))
+        .collect();
+    SettingsChanged {
+        keys: values.keys().cloned().collect(),
+        values,
+    }
+}
+
+/// 按设置更新截图后端的配置
+pub fn apply_capture_settings(settings: &Settings) {
+    capture::set_capture_config(CaptureConfig {
+        backend: settings
+            .preferred_backend
+            .as_deref()
+            .and_then(CaptureBackend::from_name),
+        enabled: Some(
+            settings
+                .capture_backends
+                .iter()
+                .filter_map(|name| CaptureBackend::from_name(name))
+                .collect(),
+        ),
+        grim_timeout: Duration::from_millis(settings.capture_timeouts.grim_ms),
+        xcap_timeout: Duration::from_millis(settings.capture_timeouts.xcap_ms),
+        srgb_normalize: settings.srgb_normalize,
+        night_light: settings.night_light,
+    });
+}
+
+/// 设置保存后让各模块立即生效，并通知所有窗口
+fn apply_changes(app: &AppHandle, old: &Settings, new: &Settings) -> Result<(), String> {
+    let changed = changed_fields(old, new);
+    if changed.keys.is_empty() {
+        return Ok(());
+    }
+    let touched = |key: &str| changed.values.contains_key(key);
+
+    if touched("preferred_backend")
+        || touched("capture_backends")
+        || touched("capture_timeouts")
+        || touched("srgb_normalize")
+        || touched("night_light")
+    {
+        apply_capture_settings(new);
+    }
+    if touched("postprocess_rules") {
+        crate::postprocess_rules::apply_settings(&new.postprocess_rules);
+    }
+    if touched("stats_enabled") {
+        crate::stats::set_enabled(new.stats_enabled);
+    }
+    if touched("persist_pins") {
+        crate::pin::apply_persist(app, new.persist_pins);
+    }
+    if touched("proxy") {
+        if let Err(err) = crate::http::rebuild(app, &new.proxy) {
+            tracing::warn!(error = %err, "重建 HTTP 客户端失败");
+        }
+    }
+    if touched("ocr_workers") {
+        app.state::<crate::ocr_queue::OcrQueue>()
+            .set_workers(crate::ocr_queue::worker_count(new.ocr_workers));
+    }
+    if touched("debug_logging") {
+        crate::logging::set_debug(new.debug_logging);
+    }
+    #[cfg(target_os = "linux")]
+    if touched("dbus_service") {
+        crate::dbus::apply(app, new.dbus_service);
+    }
+    if touched("local_api") {
+        crate::local_api::apply(app, &new.local_api)?;
+    }
+    if touched("locale") {
+        crate::i18n::init(app);
+        crate::tray::apply_locale(app);
+    }
+    let result = if touched("shortcuts") {
+        crate::shortcuts::sync(app, &new.shortcuts)
+    } else {
+        Ok(())
+    };
+
+    let _ = app.emit("settings-changed", &changed);
+    result
+}
+
+/// 把 patch 中的字段合并到 base，对象逐层合并，其余类型直接替换
+fn merge(base: &mut Value, patch: Value) {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, SettingsState>) -> Settings {
+    state.get()
+}
+
+/// 部分更新设置，只需传入要修改的字段
+#[tauri::command]
+pub fn update_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    patch: Value,
+) -> Result<Settings, String> {
+    // 截图脚本会执行任意命令，不接受来自界面的修改
+    if patch.get("capture_hooks").is_some() {
+        return Err("截图脚本只能在设置文件中修改".into());
+    }
+    let old = state.get();
+    let mut value = serde_json::to_value(&old).map_err(|e| e.to_string())?;
+    merge(&mut value, patch);
+    let mut settings: Settings =
+        serde_json::from_value(value).map_err(|e| format!("设置格式错误: {e}"))?;
+    settings.version = SETTINGS_VERSION;
+    state.replace(settings.clone())?;
+    apply_changes(&app, &old, &settings)?;
+    Ok(state.get())
+}
+
+/// 备份当前设置文件（加时间后缀）后恢复默认设置
+#[tauri::command]
+pub fn reset_settings(app: AppHandle, state: State<'_, SettingsState>) -> Result<Settings, String> {
+    if state.path.exists() && SettingsState::backup_aside(&state.path, "bak").is_none() {
+        return Err("备份设置文件失败，未恢复默认设置".into());
+    }
+    *state.locked.write().map_err(|e| e.to_string())? = None;
+    let old = state.get();
+    let settings = Settings::default();
+    state.replace(settings.clone())?;
+    apply_changes(&app, &old, &settings)?;
+    Ok(state.get())
+}
+
+#[tauri::command]
+pub fn export_settings(state: State<'_, SettingsState>, path: String) -> Result<(), String> {
+    let data = serde_json::to_vec_pretty(&state.get()).map_err(|e| e.to_string())?;
+    crate::durable::atomic_write(Path::new(&path), &data)
+}
+
+#[tauri::command]
+pub fn import_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    path: String,
+) -> Result<Settings, String> {
+    let data = std::fs::read(&path).map_err(|e| format!("读取 {path} 失败: {e}"))?;
+    let mut settings = Settings::from_json(&data)?;
+    let old = state.get();
+    // 导入的文件可能来自他人，其中的截图脚本一律不导入
+    settings.capture_hooks = old.capture_hooks.clone();
+    state.replace(settings.clone())?;
+    apply_changes(&app, &old, &settings)?;
+    Ok(state.get())
+}
+
+/// 打开独立的设置窗口，已打开时移到前台；关闭它不影响主窗口
+#[tauri::command]
+pub fn open_settings_window(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(SETTINGS_WINDOW) {
+        let _ = window.unminimize();
+        window.show().map_err(|e| e.to_string())?;
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+    WebviewWindowBuilder::new(&app, SETTINGS_WINDOW, WebviewUrl::App("index.html".into()))
+        .title(crate::i18n::t("window.settings_title"))
+        .inner_size(480.0, 560.0)
+        .min_inner_size(400.0, 420.0)
+        .skip_taskbar(false)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().expect("应为 JSON 对象")
+    }
+
+    fn parse(value: Value) -> Result<Settings, LoadError> {
+        Settings::from_json(value.to_string().as_bytes())
+    }
+
+    #[test]
+    fn v1_shortcut_becomes_the_capture_binding() {
+        let mut obj = object(json!({ "shortcut": "ctrl+shift+a", "ocr_lang": "eng" }));
+        migrate_v1_to_v2(&mut obj);
+        assert!(!obj.contains_key("shortcut"));
+        assert_eq!(obj["shortcuts"], json!({ "capture": "ctrl+shift+a" }));
+        assert_eq!(obj["ocr_lang"], "eng");
+    }
+
+    #[test]
+    fn v1_migration_keeps_an_existing_shortcut_map() {
+        let mut obj = object(json!({
+            "shortcut": "ctrl+shift+a",
+            "shortcuts": { "ocr_capture": "alt+o" },
+        }));
+        migrate_v1_to_v2(&mut obj);
+        assert!(!obj.contains_key("shortcut"));
+        assert_eq!(obj["shortcuts"], json!({ "ocr_capture": "alt+o" }));
+    }
+
+    #[test]
+    fn files_without_version_are_migrated_from_v1() {
+        let Ok(settings) = parse(json!({ "shortcut": "alt+a", "ocr_lang": "eng" })) else {
+            panic!("v1 设置应能读取");
+        };
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.shortcuts.len(), 1);
+        assert_eq!(settings.shortcuts["capture"], "alt+a");
+        assert_eq!(settings.ocr_lang, "eng");
+        // 缺失的字段取默认值
+        assert_eq!(
+            settings.capture_backends,
+            Settings::default().capture_backends
+        );
+    }
+
+    #[test]
+    fn current_version_is_not_migrated_again() {
+        let Ok(settings) = parse(json!({
+            "version": SETTINGS_VERSION,
+            "shortcut": "alt+a",
+            "shortcuts": { "capture": "ctrl+alt+a" },
+        })) else {
+            panic!("当前版本的设置应能读取");
+        };
+        assert_eq!(settings.shortcuts.len(), 1);
+        assert_eq!(settings.shortcuts["capture"], "ctrl+alt+a");
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let result = parse(json!({ "version": SETTINGS_VERSION + 1 }));
+        assert!(matches!(result, Err(LoadError::TooNew(_))));
+        assert!(matches!(parse(json!([1, 2])), Err(LoadError::Invalid(_))));
+        assert!(matches!(
+            Settings::from_json(b"{ not json"),
+            Err(LoadError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn defaults_pass_validation() {
+        Settings::default().validate().unwrap();
+    }
+
+    #[test]
+    fn newer_file_is_left_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        let original = json!({ "version": SETTINGS_VERSION + 1, "future_field": true }).to_string();
+        std::fs::write(&path, &original).unwrap();
+
+        let state = SettingsState::load(path.clone());
+        assert!(state.update(|s| s.ocr_lang = "eng".into()).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn corrupt_file_is_moved_aside_and_defaults_are_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let state = SettingsState::load(path.clone());
+        assert_eq!(state.get().ocr_lang, DEFAULT_OCR_LANG);
+        assert!(!path.exists());
+        let moved: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(moved.len(), 1);
+        assert!(moved[0].starts_with("settings.json.corrupt-"));
+
+        // 之后的保存写入新文件
+        state.update(|s| s.ocr_lang = "eng".into()).unwrap();
+        assert_eq!(SettingsState::load(path).get().ocr_lang, "eng");
+    }
+}
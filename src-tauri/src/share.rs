@@ -0,0 +1,239 @@
+use serde::Serialize;
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+#[cfg(target_os = "linux")]
+use zbus::zvariant::{Fd, Value};
+
+use crate::error::AppError;
+use crate::external::{self, ExternalTool};
+
+/// 邮件客户端等可能在前台运行，等待交接完成的最长时间
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareMechanism {
+    /// xdg-desktop-portal 的 Email 接口，附件为该文件
+    EmailPortal,
+    /// xdg-desktop-portal 的 OpenURI 接口，弹出应用选择
+    OpenUriPortal,
+    XdgEmail,
+    XdgOpen,
+    /// Windows 的 share 动词
+    #[cfg(target_os = "windows")]
+    WindowsShare,
+    /// macOS 邮件附件（AppleScript）
+    #[cfg(target_os = "macos")]
+    MacosMail,
+    #[cfg(target_os = "macos")]
+    MacosOpen,
+}
+
+impl ShareMechanism {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShareMechanism::EmailPortal => "email_portal",
+            ShareMechanism::OpenUriPortal => "open_uri_portal",
+            ShareMechanism::XdgEmail => "xdg_email",
+            ShareMechanism::XdgOpen => "xdg_open",
+            #[cfg(target_os = "windows")]
+            ShareMechanism::WindowsShare => "windows_share",
+            #[cfg(target_os = "macos")]
+            ShareMechanism::MacosMail => "macos_mail",
+            #[cfg(target_os = "macos")]
+            ShareMechanism::MacosOpen => "macos_open",
+        }
+    }
+}
+
+type Attempt = (ShareMechanism, fn(&Path) -> Result<(), String>);
+
+/// 门户接口收到请求后自行弹出窗口，调用成功即视为已分享
+#[cfg(target_os = "linux")]
+fn portal_call<B>(interface: &str, method: &str, body: &B) -> Result<(), String>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let connection =
+        zbus::blocking::Connection::session().map_err(|e| format!("{interface}: {e}"))?;
+    connection
+        .call_method(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            Some(interface),
+            method,
+            body,
+        )
+        .map(|_| ())
+        .map_err(|e| format!("{interface}: {e}"))
+}
+
+/// 会话总线上是否有 xdg-desktop-portal
+#[cfg(target_os = "linux")]
+pub fn portal_available() -> bool {
+    let check = || -> zbus::Result<bool> {
+        let connection = zbus::blocking::Connection::session()?;
+        let proxy = zbus::blocking::fdo::DBusProxy::new(&connection)?;
+        Ok(proxy.name_has_owner("org.freedesktop.portal.Desktop".try_into()?)?)
+    };
+    check().unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn portal_available() -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn email_portal(path: &Path) -> Result<(), String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut options: HashMap<&str, Value> = HashMap::new();
+    options.insert("attachment_fds", vec![Fd::from(&file)].into());
+    portal_call(
+        "org.freedesktop.portal.Email",
+        "ComposeEmail",
+        &("", options),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn open_uri_portal(path: &Path) -> Result<(), String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut options: HashMap<&str, Value> = HashMap::new();
+    // 让用户选择用哪个应用打开
+    options.insert("ask", true.into());
+    portal_call(
+        "org.freedesktop.portal.OpenURI",
+        "OpenFile",
+        &("", Fd::from(&file), options),
+    )
+}
+
+#[cfg(target_os = "linux")]
+const ATTEMPTS: &[Attempt] = &[
+    (ShareMechanism::EmailPortal, email_portal),
+    (ShareMechanism::OpenUriPortal, open_uri_portal),
+    (ShareMechanism::XdgEmail, |path| {
+        run(
+            ExternalTool::XdgEmail,
+            [OsStr::new("--attach"), path.as_os_str()],
+        )
+    }),
+    (ShareMechanism::XdgOpen, |path| {
+        spawn(ExternalTool::XdgOpen, path)
+    }),
+];
+
+#[cfg(target_os = "windows")]
+const ATTEMPTS: &[Attempt] = &[(ShareMechanism::WindowsShare, |path| {
+    run(
+        ExternalTool::PowerShell,
+        [
+            OsStr::new("-NoProfile"),
+            OsStr::new("-Command"),
+            OsStr::new("Start-Process -FilePath $args[0] -Verb share"),
+            path.as_os_str(),
+        ],
+    )
+})];
+
+#[cfg(target_os = "macos")]
+const MAIL_SCRIPT: &str = r#"on run argv
+    tell application "Mail"
+        set theMessage to make new outgoing message with properties {visible:true}
+        tell theMessage to make new attachment with properties {file name:(POSIX file (item 1 of argv))} at after the last paragraph
+        activate
+    end tell
+end run"#;
+
+#[cfg(target_os = "macos")]
+const ATTEMPTS: &[Attempt] = &[
+    (ShareMechanism::MacosMail, |path| {
+        run(
+            ExternalTool::Osascript,
+            [OsStr::new("-e"), OsStr::new(MAIL_SCRIPT), path.as_os_str()],
+        )
+    }),
+    (ShareMechanism::MacosOpen, |path| {
+        spawn(ExternalTool::Open, path)
+    }),
+];
+
+/// 运行并等待退出，退出状态非 0 视为失败
+fn run<'a>(tool: ExternalTool, args: impl IntoIterator<Item = &'a OsStr>) -> Result<(), String> {
+    external::run_external(tool, args, None, HANDOFF_TIMEOUT)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// 只启动，不等待打开的程序退出
+#[cfg(not(target_os = "windows"))]
+fn spawn(tool: ExternalTool, path: &Path) -> Result<(), String> {
+    external::command(&tool)
+        .map_err(|e| e.to_string())?
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("{}: {e}", tool.program()))
+}
+
+/// 依次尝试各分享方式，返回成功的方式；不检查文件位置，供截图后续动作和通知按钮使用
+pub fn share_path(path: &Path) -> Result<ShareMechanism, AppError> {
+    if !path.is_file() {
+        return Err(AppError::invalid(format!("文件不存在: {}", path.display())));
+    }
+    let mut attempts = Vec::new();
+    for (mechanism, attempt) in ATTEMPTS {
+        match attempt(path) {
+            Ok(()) => {
+                tracing::info!(?mechanism, path = %path.display(), "已分享文件");
+                return Ok(*mechanism);
+            }
+            Err(err) => {
+                tracing::debug!(?mechanism, error = %err, "分享方式不可用");
+                attempts.push(err);
+            }
+        }
+    }
+    // detail 为依次尝试的结果
+    Err(AppError::BackendFailed {
+        backend: "share".into(),
+        detail: attempts.join("；"),
+    })
+}
+
+/// 把文件交给系统的分享目标（邮件等），仅允许分享本应用保存过文件的目录
+#[tauri::command]
+pub async fn share_file(app: AppHandle, path: String) -> Result<ShareMechanism, AppError> {
+    let path = PathBuf::from(&path)
+        .canonicalize()
+        .map_err(|_| AppError::invalid(format!("文件不存在: {path}")))?;
+    if !crate::save::allowed_roots(&app)
+        .iter()
+        .any(|root| path.starts_with(root))
+    {
+        return Err(AppError::invalid(format!(
+            "不允许分享该位置: {}",
+            path.display()
+        )));
+    }
+    crate::run_blocking(move || share_path(&path)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_is_invalid_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = share_path(&dir.path().join("missing.png")).unwrap_err();
+        assert_eq!(err.code(), "invalid_input");
+        // 目录也不能分享
+        assert_eq!(share_path(dir.path()).unwrap_err().code(), "invalid_input");
+    }
+}
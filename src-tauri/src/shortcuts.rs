@@ -0,0 +1,514 @@
+use chrono::Local;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::settings::SettingsState;
+use crate::trigger::{CaptureTrigger, TriggerSource};
+
+/// 各动作的默认快捷键
+const DEFAULT_SHORTCUTS: &[(&str, &str)] = &[
+    ("capture", "ctrl+shift+a"),
+    ("quick_capture", "ctrl+shift+s"),
+];
+
+pub fn default_shortcuts() -> BTreeMap<String, String> {
+    DEFAULT_SHORTCUTS
+        .iter()
+        .map(|(action, accel)| (action.to_string(), accel.to_string()))
+        .collect()
+}
+
+/// 已绑定的快捷键（动作 → 快捷键）；暂停期间保留映射，恢复时按此重新注册
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    bindings: Mutex<BTreeMap<String, String>>,
+    /// 每次暂停/恢复递增，用于让过期的自动恢复定时器失效
+    generation: AtomicU64,
+    /// 启动时未能恢复的快捷键，前端监听事件前发生的失败也能查到
+    restore_failures: Mutex<Vec<RestoreFailure>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RestoreFailure {
+    pub action: String,
+    pub shortcut: String,
+    /// 实际改用的快捷键
+    pub fallback: Option<String>,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct CurrentShortcut {
+    pub action: String,
+    pub shortcut: String,
+    /// 是否已在系统中注册（暂停期间为 false）
+    pub registered: bool,
+}
+
+#[derive(Serialize)]
+pub struct CurrentShortcuts {
+    pub shortcuts: Vec<CurrentShortcut>,
+    pub restore_failures: Vec<RestoreFailure>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ShortcutsState {
+    pub enabled: bool,
+    /// 自动恢复的时间（Unix 秒）
+    pub paused_until: Option<i64>,
+    pub bindings: BTreeMap<String, String>,
+}
+
+/// 可以绑定快捷键的动作
+const ACTIONS: &[&str] = &["capture", "quick_capture", "quick_ocr", "read_at_cursor"];
+/// 可以带选区预设的动作，写作 quick_capture:<预设名称>
+const PRESET_ACTIONS: &[&str] = &["quick_capture", "quick_ocr"];
+
+fn register_one(app: &AppHandle, action: &str, accel: &str) -> Result<(), String> {
+    let (base, preset) = match action.split_once(':') {
+        Some((base, preset)) => (base, Some(preset.to_string())),
+        None => (action, None),
+    };
+    let known = match preset {
+        Some(_) => PRESET_ACTIONS.contains(&base),
+        None => ACTIONS.contains(&base),
+    };
+    if !known {
+        return Err(format!("未知的快捷键动作: {action}"));
+    }
+    let trigger = CaptureTrigger {
+        shortcut: Some(accel.to_string()),
+        ..CaptureTrigger::new(TriggerSource::Shortcut, action)
+    };
+    app.global_shortcut()
+        .on_shortcut(accel, move |handle, _shortcut, e| {
+            if e.state != ShortcutState::Pressed {
+                return;
+            }
+            // 快速截图和快速识别在后台完成，不经过前端
+            match trigger.action.split(':').next().unwrap_or_default() {
+                "quick_capture" => crate::quick::spawn(handle, preset.clone()),
+                "quick_ocr" => crate::quick::spawn_ocr(handle, preset.clone()),
+                "read_at_cursor" => crate::quick::spawn_read_at_cursor(handle),
+                _ => trigger.clone().send(handle),
+            }
+        })
+        .map_err(|e| format!("register {accel}: {e}"))
+        .inspect(|_| tracing::info!(action, shortcut = accel, "已注册快捷键"))
+        .inspect_err(|err| tracing::warn!(action, shortcut = accel, error = %err, "注册快捷键失败"))
+}
+
+fn paused(app: &AppHandle) -> bool {
+    app.state::<SettingsState>().get().shortcuts_paused
+}
+
+/// 为动作绑定快捷键并写入设置；暂停期间只记录，恢复时再注册
+pub fn bind(app: &AppHandle, action: &str, accel: &str) -> Result<(), String> {
+    let accel = normalize_shortcut(accel);
+    let registry = app.state::<ShortcutRegistry>();
+    let mut bindings = registry.bindings.lock().map_err(|e| e.to_string())?;
+
+    if !paused(app) {
+        let old = bindings.get(action).cloned();
+        if let Some(old) = &old {
+            let _ = app.global_shortcut().unregister(old.as_str());
+        }
+        if let Err(err) = register_one(app, action, &accel) {
+            // 新快捷键注册失败时恢复原来的
+            if let Some(old) = &old {
+                let _ = register_one(app, action, old);
+            }
+            return Err(err);
+        }
+    }
+    bindings.insert(action.to_string(), accel.clone());
+    drop(bindings);
+
+    let settings = app.state::<SettingsState>();
+    if settings.get().shortcuts.get(action) != Some(&accel) {
+        settings.update(|s| {
+            s.shortcuts.insert(action.to_string(), accel);
+        })?;
+    }
+    Ok(())
+}
+
+/// 设置中的快捷键被直接修改后重新注册；注册失败的动作在设置中恢复为原来的快捷键
+pub fn sync(app: &AppHandle, shortcuts: &BTreeMap<String, String>) -> Result<(), String> {
+    let registry = app.state::<ShortcutRegistry>();
+    let current = registry.bindings.lock().map_err(|e| e.to_string())?.clone();
+    let mut errors = Vec::new();
+
+    for (action, accel) in shortcuts {
+        if current.get(action) == Some(&normalize_shortcut(accel)) {
+            continue;
+        }
+        if let Err(err) = bind(app, action, accel) {
+            errors.push(format!("{action}: {err}"));
+            let old = current.get(action).cloned();
+            app.state::<SettingsState>().update(|s| match old {
+                Some(old) => {
+                    s.shortcuts.insert(action.clone(), old);
+                }
+                None => {
+                    s.shortcuts.remove(action);
+                }
+            })?;
+        }
+    }
+
+    for (action, accel) in &current {
+        if !shortcuts.contains_key(action) {
+            if !paused(app) {
+                let _ = app.global_shortcut().unregister(accel.as_str());
+            }
+            registry
+                .bindings
+                .lock()
+                .map_err(|e| e.to_string())?
+                .remove(action);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+pub fn current_state(app: &AppHandle) -> ShortcutsState {
+    let settings = app.state::<SettingsState>().get();
+    let bindings = app
+        .state::<ShortcutRegistry>()
+        .bindings
+        .lock()
+        .map(|b| b.clone())
+        .unwrap_or_default();
+    ShortcutsState {
+        enabled: !settings.shortcuts_paused,
+        paused_until: settings.shortcuts_paused_until,
+        bindings,
+    }
+}
+
+/// 暂停时注销全部快捷键，恢复时按记录的映射重新注册；状态写入设置以便重启后保持
+pub fn set_enabled(
+    app: &AppHandle,
+    enabled: bool,
+    pause_minutes: Option<u64>,
+) -> Result<ShortcutsState, String> {
+    let registry = app.state::<ShortcutRegistry>();
+    let generation = registry.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let was_paused = paused(app);
+
+    let paused_until = match (enabled, pause_minutes) {
+        (false, Some(minutes)) if minutes > 0 => {
+            Some(Local::now().timestamp() + (minutes * 60) as i64)
+        }
+        _ => None,
+    };
+    app.state::<SettingsState>().update(|s| {
+        s.shortcuts_paused = !enabled;
+        s.shortcuts_paused_until = paused_until;
+    })?;
+
+    if enabled && was_paused {
+        let bindings = registry.bindings.lock().map_err(|e| e.to_string())?.clone();
+        for (action, accel) in &bindings {
+            if let Err(err) = register_one(app, action, accel) {
+                tracing::warn!(action = %action, accel = %accel, error = %err, "恢复快捷键失败");
+            }
+        }
+    } else if !enabled && !was_paused {
+        app.global_shortcut()
+            .unregister_all()
+            .map_err(|e| format!("unregister_all: {e}"))?;
+    }
+
+    if let Some(until) = paused_until {
+        schedule_resume(app, generation, until);
+    }
+
+    let state = current_state(app);
+    crate::tray::refresh_shortcuts_paused(app, !state.enabled);
+    let _ = app.emit("shortcuts-state-changed", &state);
+    Ok(state)
+}
+
+/// 按记录的映射重新注册全部快捷键；休眠唤醒或桌面重启后系统中的注册会丢失
+pub fn reregister(app: &AppHandle) -> Result<ShortcutsState, String> {
+    if !paused(app) {
+        let bindings = app
+            .state::<ShortcutRegistry>()
+            .bindings
+            .lock()
+            .map_err(|e| e.to_string())?
+            .clone();
+        let _ = app.global_shortcut().unregister_all();
+        let errors: Vec<String> = bindings
+            .iter()
+            .filter_map(|(action, accel)| register_one(app, action, accel).err())
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors.join("; "));
+        }
+    }
+    let state = current_state(app);
+    let _ = app.emit("shortcuts-reregistered", &state);
+    Ok(state)
+}
+
+/// 到期后自动恢复；期间用户手动切换过则放弃
+fn schedule_resume(app: &AppHandle, generation: u64, until: i64) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let wait = (until - Local::now().timestamp()).max(0) as u64;
+        thread::sleep(Duration::from_secs(wait));
+        let current = app
+            .state::<ShortcutRegistry>()
+            .generation
+            .load(Ordering::SeqCst);
+        if current == generation {
+            if let Err(err) = set_enabled(&app, true, None) {
+                tracing::warn!(error = %err, "自动恢复快捷键失败");
+            }
+        }
+    });
+}
+
+/// 启动时注册设置中保存的快捷键，失败时退回默认值；并恢复上次的暂停状态
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let settings = app.state::<SettingsState>().get();
+    let mut stored = default_shortcuts();
+    stored.extend(settings.shortcuts.clone());
+
+    for (action, accel) in stored {
+        let Err(error) = bind(app, &action, &accel) else {
+            continue;
+        };
+        tracing::warn!(accel = %accel, error = %error, "恢复快捷键失败");
+        let default = DEFAULT_SHORTCUTS
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, d)| d.to_string())
+            .filter(|d| *d != accel);
+        let fallback = default.filter(|d| bind(app, &action, d).is_ok());
+        let failure = RestoreFailure {
+            action,
+            shortcut: accel,
+            fallback,
+            error,
+        };
+        let _ = app.emit("shortcut-restore-failed", &failure);
+        if let Ok(mut failures) = app.state::<ShortcutRegistry>().restore_failures.lock() {
+            failures.push(failure);
+        }
+    }
+
+    if let Some(until) = settings.shortcuts_paused_until {
+        if until <= Local::now().timestamp() {
+            set_enabled(app, true, None)?;
+        } else {
+            let generation = app
+                .state::<ShortcutRegistry>()
+                .generation
+                .load(Ordering::SeqCst);
+            schedule_resume(app, generation, until);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_shortcuts_enabled(
+    app: AppHandle,
+    enabled: bool,
+    pause_minutes: Option<u64>,
+) -> Result<ShortcutsState, String> {
+    set_enabled(&app, enabled, pause_minutes)
+}
+
+#[tauri::command]
+pub fn reregister_shortcuts(app: AppHandle) -> Result<ShortcutsState, String> {
+    reregister(&app)
+}
+
+#[tauri::command]
+pub fn get_shortcuts_state(app: AppHandle) -> ShortcutsState {
+    current_state(&app)
+}
+
+/// 返回实际生效的快捷键，供设置界面显示
+#[tauri::command]
+pub fn get_current_shortcuts(app: AppHandle) -> CurrentShortcuts {
+    let registry = app.state::<ShortcutRegistry>();
+    let bindings = registry
+        .bindings
+        .lock()
+        .map(|b| b.clone())
+        .unwrap_or_default();
+    let manager = app.global_shortcut();
+    let shortcuts = bindings
+        .into_iter()
+        .map(|(action, shortcut)| CurrentShortcut {
+            registered: manager.is_registered(shortcut.as_str()),
+            action,
+            shortcut,
+        })
+        .collect();
+    CurrentShortcuts {
+        shortcuts,
+        restore_failures: registry
+            .restore_failures
+            .lock()
+            .map(|f| f.clone())
+            .unwrap_or_default(),
+    }
+}
+
+#[tauri::command]
+pub fn register_global_shortcut(app: AppHandle, shortcut: String) -> Result<(), String> {
+    bind(&app, "capture", &shortcut)
+}
+
+pub fn normalize_shortcut(input: &str) -> String {
+    // 转为插件要求的小写形式，并去掉多余空格
+    input
+        .split('+')
+        .map(|p| p.trim().to_lowercase())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// 快捷键校验发现的问题
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShortcutProblem {
+    Empty,
+    UnknownKey {
+        key: String,
+    },
+    /// 没有主键，只有修饰键
+    MissingKey,
+    /// 出现多个主键，或主键写在修饰键之前
+    MultipleKeys,
+    MissingModifier,
+    DuplicateModifier {
+        modifier: String,
+    },
+    /// 已被其他程序占用
+    AlreadyGrabbed {
+        detail: String,
+    },
+}
+
+#[derive(Serialize)]
+pub struct ShortcutValidation {
+    pub normalized: String,
+    pub valid: bool,
+    pub problems: Vec<ShortcutProblem>,
+}
+
+fn modifier_name(token: &str) -> Option<&'static str> {
+    match token {
+        "ctrl" | "control" | "commandorcontrol" | "commandorctrl" | "cmdorctrl"
+        | "cmdorcontrol" => Some("ctrl"),
+        "shift" => Some("shift"),
+        "alt" | "option" => Some("alt"),
+        "super" | "cmd" | "command" => Some("super"),
+        _ => None,
+    }
+}
+
+/// 功能键等可以不带修饰键单独使用
+fn allows_bare_key(key: &str) -> bool {
+    key == "printscreen"
+        || key
+            .strip_prefix('f')
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// 只做语法检查，不触碰系统快捷键
+fn shortcut_syntax_problems(normalized: &str) -> Vec<ShortcutProblem> {
+    if normalized.is_empty() {
+        return vec![ShortcutProblem::Empty];
+    }
+
+    let mut problems = Vec::new();
+    let mut modifiers: Vec<&str> = Vec::new();
+    let mut keys: Vec<&str> = Vec::new();
+    for token in normalized.split('+') {
+        match modifier_name(token) {
+            Some(_) if !keys.is_empty() => problems.push(ShortcutProblem::MultipleKeys),
+            Some(name) => {
+                if modifiers.contains(&name) {
+                    problems.push(ShortcutProblem::DuplicateModifier {
+                        modifier: name.to_string(),
+                    });
+                } else {
+                    modifiers.push(name);
+                }
+            }
+            None => {
+                if token.parse::<Shortcut>().is_err() {
+                    problems.push(ShortcutProblem::UnknownKey {
+                        key: token.to_string(),
+                    });
+                }
+                keys.push(token);
+            }
+        }
+    }
+
+    match keys.as_slice() {
+        [] => problems.push(ShortcutProblem::MissingKey),
+        [key] => {
+            if modifiers.is_empty() && !allows_bare_key(key) {
+                problems.push(ShortcutProblem::MissingModifier);
+            }
+        }
+        _ => problems.push(ShortcutProblem::MultipleKeys),
+    }
+    problems.dedup();
+    problems
+}
+
+/// 校验快捷键，语法无误时再试注册一次以检测是否被其他程序占用；设置界面每次按键都会调用
+#[tauri::command]
+pub fn validate_shortcut(app: AppHandle, accel: String) -> ShortcutValidation {
+    let normalized = normalize_shortcut(&accel);
+    let mut problems = shortcut_syntax_problems(&normalized);
+
+    if problems.is_empty() {
+        match normalized.parse::<Shortcut>() {
+            Ok(shortcut) => {
+                let manager = app.global_shortcut();
+                // 当前已由本程序注册的快捷键无需再试
+                if !manager.is_registered(shortcut) {
+                    match manager.register(shortcut) {
+                        Ok(()) => {
+                            let _ = manager.unregister(shortcut);
+                        }
+                        Err(e) => problems.push(ShortcutProblem::AlreadyGrabbed {
+                            detail: e.to_string(),
+                        }),
+                    }
+                }
+            }
+            Err(_) => problems.push(ShortcutProblem::MultipleKeys),
+        }
+    }
+
+    ShortcutValidation {
+        valid: problems.is_empty(),
+        normalized,
+        problems,
+    }
+}
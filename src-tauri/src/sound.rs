@@ -0,0 +1,184 @@
+use rodio::cpal::traits::HostTrait;
+use rodio::{Decoder, OutputStream, Source};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsState;
+
+const CAPTURE: &[u8] = include_bytes!("../sounds/capture.wav");
+const COPY: &[u8] = include_bytes!("../sounds/copy.wav");
+const ERROR: &[u8] = include_bytes!("../sounds/error.wav");
+
+const STATE_UNKNOWN: u8 = 0;
+const STATE_READY: u8 = 1;
+const STATE_UNAVAILABLE: u8 = 2;
+
+/// 播放线程的状态；打开音频设备失败后不再重试，直到重新探测
+static STATE: AtomicU8 = AtomicU8::new(STATE_UNKNOWN);
+/// 播放线程持有输出流（不能跨线程），这里只保存发送端
+static PLAYER: Mutex<Option<Sender<Request>>> = Mutex::new(None);
+
+/// 提示音、音量，以及是否在勿扰模式中跳过
+type Request = (FeedbackKind, f32, bool);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackKind {
+    Capture,
+    Copy,
+    Error,
+}
+
+impl FeedbackKind {
+    fn asset(self) -> &'static [u8] {
+        match self {
+            FeedbackKind::Capture => CAPTURE,
+            FeedbackKind::Copy => COPY,
+            FeedbackKind::Error => ERROR,
+        }
+    }
+}
+
+/// 操作提示音
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    /// 总开关
+    pub enabled: bool,
+    /// 截图完成
+    pub capture: bool,
+    /// 复制到剪贴板
+    pub copy: bool,
+    /// 出错
+    pub error: bool,
+    /// 音量，0 到 1
+    pub volume: f32,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capture: true,
+            copy: true,
+            error: true,
+            volume: 0.6,
+        }
+    }
+}
+
+impl SoundSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err("提示音音量必须在 0 到 1 之间".into());
+        }
+        Ok(())
+    }
+
+    fn wants(&self, kind: FeedbackKind) -> bool {
+        self.enabled
+            && self.volume > 0.0
+            && match kind {
+                FeedbackKind::Capture => self.capture,
+                FeedbackKind::Copy => self.copy,
+                FeedbackKind::Error => self.error,
+            }
+    }
+}
+
+/// 是否有默认输出设备；只查询设备，不打开输出流
+pub fn available() -> bool {
+    STATE.load(Ordering::Relaxed) != STATE_UNAVAILABLE
+        && rodio::cpal::default_host()
+            .default_output_device()
+            .is_some()
+}
+
+fn play(stream: &rodio::OutputStreamHandle, kind: FeedbackKind, volume: f32) {
+    let source = match Decoder::new(Cursor::new(kind.asset())) {
+        Ok(source) => source,
+        Err(err) => {
+            tracing::debug!(?kind, error = %err, "提示音解码失败");
+            return;
+        }
+    };
+    if let Err(err) = stream.play_raw(source.amplify(volume).convert_samples()) {
+        tracing::debug!(?kind, error = %err, "播放提示音失败");
+    }
+}
+
+/// 第一次播放时启动播放线程；没有音频设备时返回 None 并标记为不可用
+fn player() -> Option<Sender<Request>> {
+    let mut player = PLAYER.lock().ok()?;
+    if let Some(tx) = player.as_ref() {
+        return Some(tx.clone());
+    }
+    let (tx, rx) = mpsc::channel::<Request>();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("sound".into())
+        .spawn(move || {
+            let (_stream, handle) = match OutputStream::try_default() {
+                Ok(pair) => {
+                    let _ = ready_tx.send(true);
+                    pair
+                }
+                Err(err) => {
+                    tracing::info!(error = %err, "无法打开音频设备，已停用提示音");
+                    let _ = ready_tx.send(false);
+                    return;
+                }
+            };
+            for (kind, volume, respect_dnd) in rx {
+                // 查询勿扰状态可能较慢，放在播放线程中
+                if respect_dnd && crate::dnd::is_active() {
+                    tracing::debug!(?kind, "勿扰模式中，不播放提示音");
+                    continue;
+                }
+                play(&handle, kind, volume);
+            }
+        })
+        .ok()?;
+    if ready_rx.recv().unwrap_or(false) {
+        STATE.store(STATE_READY, Ordering::Relaxed);
+        *player = Some(tx.clone());
+        Some(tx)
+    } else {
+        STATE.store(STATE_UNAVAILABLE, Ordering::Relaxed);
+        None
+    }
+}
+
+/// 按设置播放操作提示音；关闭、没有音频设备或播放失败时静默忽略
+pub fn play_feedback(app: &AppHandle, kind: FeedbackKind) {
+    if STATE.load(Ordering::Relaxed) == STATE_UNAVAILABLE {
+        return;
+    }
+    let Some(settings) = app.try_state::<SettingsState>().map(|s| s.get()) else {
+        return;
+    };
+    if !settings.sound.wants(kind) {
+        return;
+    }
+    if let Some(tx) = player() {
+        let _ = tx.send((
+            kind,
+            settings.sound.volume,
+            !settings.notifications.always_notify,
+        ));
+    }
+}
+
+/// 重新探测能力时清除不可用标记，下次播放时再尝试打开设备
+pub fn reset() {
+    let _ = STATE.compare_exchange(
+        STATE_UNAVAILABLE,
+        STATE_UNKNOWN,
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+    );
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::external::{self, ExternalTool};
+use crate::settings::SettingsState;
+
+type Current = Option<(Engine, Child)>;
+
+/// 正在朗读的进程，新的朗读或 stop_speaking 时结束
+static CURRENT: Mutex<Current> = Mutex::new(None);
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SpeakOptions {
+    /// 语速，-100 到 100，0 为引擎默认
+    pub rate: i32,
+    /// 引擎中的声音名称
+    pub voice: Option<String>,
+    /// 语言代码（en、zh），为空时按设置中的 OCR 语言推断
+    pub lang: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Engine {
+    SpdSay,
+    EspeakNg,
+    #[cfg(target_os = "macos")]
+    Say,
+    #[cfg(target_os = "windows")]
+    Sapi,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpeechError {
+    InvalidInput {
+        detail: String,
+    },
+    /// 系统中没有可用的语音合成程序，install 为建议安装的软件包
+    NoEngine {
+        hint: String,
+        install: Vec<&'static str>,
+    },
+    Failed {
+        detail: String,
+    },
+}
+
+#[derive(Serialize)]
+pub struct SpeakResult {
+    pub engine: Engine,
+    pub lang: Option<String>,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn detect_engine() -> Result<Engine, SpeechError> {
+    if ExternalTool::SpdSay.available() {
+        return Ok(Engine::SpdSay);
+    }
+    if ExternalTool::EspeakNg.available() {
+        return Ok(Engine::EspeakNg);
+    }
+    Err(SpeechError::NoEngine {
+        hint: "未找到 spd-say 或 espeak-ng，请安装（sudo apt install speech-dispatcher espeak-ng）"
+            .into(),
+        install: vec!["speech-dispatcher", "espeak-ng"],
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn detect_engine() -> Result<Engine, SpeechError> {
+    if ExternalTool::Say.available() {
+        return Ok(Engine::Say);
+    }
+    Err(SpeechError::NoEngine {
+        hint: "未找到 say 命令".into(),
+        install: Vec::new(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn detect_engine() -> Result<Engine, SpeechError> {
+    if ExternalTool::PowerShell.available() {
+        return Ok(Engine::Sapi);
+    }
+    Err(SpeechError::NoEngine {
+        hint: "未找到 PowerShell，无法调用系统语音".into(),
+        install: vec!["PowerShell"],
+    })
+}
+
+/// tesseract 语言（chi_sim+eng）中第一个语言对应的 ISO 639-1 代码
+fn lang_from_ocr(ocr_lang: &str) -> Option<&'static str> {
+    let first = ocr_lang.split('+').next()?.trim();
+    let lang = match first {
+        "chi_sim" | "chi_tra" | "chi_sim_vert" | "chi_tra_vert" => "zh",
+        "eng" => "en",
+        "jpn" | "jpn_vert" => "ja",
+        "kor" | "kor_vert" => "ko",
+        "deu" => "de",
+        "fra" => "fr",
+        "spa" => "es",
+        "ita" => "it",
+        "por" => "pt",
+        "rus" => "ru",
+        _ => return None,
+    };
+    Some(lang)
+}
+
+impl Engine {
+    fn tool(self) -> ExternalTool {
+        match self {
+            Engine::SpdSay => ExternalTool::SpdSay,
+            Engine::EspeakNg => ExternalTool::EspeakNg,
+            #[cfg(target_os = "macos")]
+            Engine::Say => ExternalTool::Say,
+            #[cfg(target_os = "windows")]
+            Engine::Sapi => ExternalTool::PowerShell,
+        }
+    }
+}
+
+/// 按引擎组装命令；文字从标准输入传入的引擎返回 true
+fn build_command(
+    engine: Engine,
+    text: &str,
+    options: &SpeakOptions,
+    lang: Option<&str>,
+) -> Result<(Command, bool), SpeechError> {
+    let mut command = external::command(&engine.tool()).map_err(|e| SpeechError::Failed {
+        detail: e.to_string(),
+    })?;
+    let rate = options.rate.clamp(-100, 100);
+    let voice = options.voice.as_deref().filter(|v| !v.trim().is_empty());
+    match engine {
+        Engine::SpdSay => {
+            // -w 让进程在读完后才退出，便于判断是否仍在朗读
+            command.args(["-w", "-r", &rate.to_string()]);
+            if let Some(lang) = lang {
+                command.args(["-l", lang]);
+            }
+            if let Some(voice) = voice {
+                command.args(["-y", voice]);
+            }
+            command.arg("--").arg(text);
+            Ok((command, false))
+        }
+        Engine::EspeakNg => {
+            // 默认 175 词/分钟，按比例缩放到 espeak-ng 支持的 80..450
+            let wpm = (175 + rate * 175 / 100).clamp(80, 450);
+            command.args(["--stdin", "-s", &wpm.to_string()]);
+            if let Some(name) = voice.or(lang) {
+                command.args(["-v", name]);
+            }
+            Ok((command, true))
+        }
+        #[cfg(target_os = "macos")]
+        Engine::Say => {
+            let wpm = (175 + rate * 175 / 100).max(50);
+            command.args(["-r", &wpm.to_string()]);
+            if let Some(voice) = voice {
+                command.args(["-v", voice]);
+            }
+            Ok((command, true))
+        }
+        #[cfg(target_os = "windows")]
+        Engine::Sapi => {
+            let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+            let mut script = String::from(
+                "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+                 Add-Type -AssemblyName System.Speech; \
+                 $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; ",
+            );
+            // SAPI 语速为 -10..10
+            script.push_str(&format!("$s.Rate = {}; ", rate / 10));
+            if let Some(voice) = voice {
+                script.push_str(&format!("$s.SelectVoice({}); ", quote(voice)));
+            } else if let Some(lang) = lang {
+                script.push_str(&format!(
+                    "try {{ $s.SelectVoiceByHints('NotSet', 'NotSet', 0, \
+                     [Globalization.CultureInfo]::new({})) }} catch {{}}; ",
+                    quote(lang)
+                ));
+            }
+            script.push_str("$s.Speak([Console]::In.ReadToEnd())");
+            command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+            Ok((command, true))
+        }
+    }
+}
+
+fn current() -> std::sync::MutexGuard<'static, Current> {
+    CURRENT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 结束正在朗读的进程；spd-say 只是把文字交给 speech-dispatcher，还需要取消队列
+fn stop(current: &mut Current) -> bool {
+    let Some((engine, mut child)) = current.take() else {
+        return false;
+    };
+    let running = matches!(child.try_wait(), Ok(None));
+    let _ = child.kill();
+    let _ = child.wait();
+    if engine == Engine::SpdSay && running {
+        let _ = external::run_external(ExternalTool::SpdSay, ["-C"], None, external::PROBE_TIMEOUT);
+    }
+    running
+}
+
+/// 朗读文字，立即返回；正在朗读的内容会被打断
+#[tauri::command]
+pub fn speak_text(
+    app: AppHandle,
+    text: String,
+    options: Option<SpeakOptions>,
+) -> Result<SpeakResult, SpeechError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(SpeechError::InvalidInput {
+            detail: "没有可朗读的文字".into(),
+        });
+    }
+    let options = options.unwrap_or_default();
+    let engine = detect_engine()?;
+    let lang = match options.lang.as_deref().map(str::trim) {
+        Some(lang) if !lang.is_empty() => Some(lang.to_string()),
+        _ => lang_from_ocr(&app.state::<SettingsState>().get().ocr_lang).map(str::to_string),
+    };
+
+    // 持有锁直到新进程登记完成，并发的两次朗读不会同时发声
+    let mut slot = current();
+    stop(&mut slot);
+    let (mut command, via_stdin) = build_command(engine, text, &options, lang.as_deref())?;
+    let mut child = command
+        .stdin(if via_stdin {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| SpeechError::Failed {
+            detail: format!("{engine:?}: {e}"),
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // 长文本可能超过管道缓冲区，在后台写入，进程被结束时写入失败即可
+        let text = text.to_string();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(text.as_bytes());
+        });
+    }
+    tracing::info!(?engine, lang = ?lang, chars = text.chars().count(), "开始朗读");
+    *slot = Some((engine, child));
+    Ok(SpeakResult { engine, lang })
+}
+
+/// 停止朗读，返回此前是否正在朗读
+#[tauri::command]
+pub fn stop_speaking() -> bool {
+    stop(&mut current())
+}
@@ -0,0 +1,236 @@
+use chrono::{Datelike, Duration as DateDuration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+
+/// 内存中的计数每隔这么久写入一次文件，退出时再写一次
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+/// 只保留最近这么多天的统计
+const KEEP_DAYS: i64 = 400;
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 未调用 init 时（命令行模式）不计数
+static STATS: Mutex<Collector> = Mutex::new(Collector {
+    path: None,
+    enabled: false,
+    dirty: false,
+    days: BTreeMap::new(),
+});
+
+/// 一天的计数，只有次数和耗时，不记录截图或文字内容
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DayStats {
+    pub captures: u64,
+    /// 按截图后端统计的成功次数
+    pub backends: BTreeMap<String, u64>,
+    pub ocr_runs: u64,
+    /// 识别出的字符数
+    pub ocr_chars: u64,
+    /// 识别耗时合计（毫秒）
+    pub ocr_ms: u64,
+    /// 复制到剪贴板的次数（文字和图片）
+    pub copies: u64,
+}
+
+impl DayStats {
+    fn add(&mut self, other: &DayStats) {
+        self.captures += other.captures;
+        for (backend, count) in &other.backends {
+            *self.backends.entry(backend.clone()).or_default() += count;
+        }
+        self.ocr_runs += other.ocr_runs;
+        self.ocr_chars += other.ocr_chars;
+        self.ocr_ms += other.ocr_ms;
+        self.copies += other.copies;
+    }
+}
+
+struct Collector {
+    path: Option<PathBuf>,
+    enabled: bool,
+    /// 有尚未写入文件的计数
+    dirty: bool,
+    /// 键为本地日期 YYYY-MM-DD
+    days: BTreeMap<String, DayStats>,
+}
+
+fn today() -> String {
+    Local::now().format(DATE_FORMAT).to_string()
+}
+
+/// 在当天的计数上修改；关闭统计或未初始化时不做任何事
+fn bump(f: impl FnOnce(&mut DayStats)) {
+    let Ok(mut stats) = STATS.lock() else {
+        return;
+    };
+    if !stats.enabled || stats.path.is_none() {
+        return;
+    }
+    f(stats.days.entry(today()).or_default());
+    stats.dirty = true;
+}
+
+pub fn record_capture(backend: &str) {
+    bump(|day| {
+        day.captures += 1;
+        *day.backends.entry(backend.to_string()).or_default() += 1;
+    });
+}
+
+pub fn record_ocr(chars: usize, duration_ms: u64) {
+    bump(|day| {
+        day.ocr_runs += 1;
+        day.ocr_chars += chars as u64;
+        day.ocr_ms += duration_ms;
+    });
+}
+
+pub fn record_copy() {
+    bump(|day| day.copies += 1);
+}
+
+/// 把计数写入文件，同时丢弃过旧的日期；没有新计数时跳过
+pub fn flush() {
+    let Ok(mut stats) = STATS.lock() else {
+        return;
+    };
+    let Some(path) = stats.path.clone().filter(|_| stats.dirty) else {
+        return;
+    };
+    let oldest = (Local::now().date_naive() - DateDuration::days(KEEP_DAYS))
+        .format(DATE_FORMAT)
+        .to_string();
+    stats.days.retain(|date, _| *date >= oldest);
+    let result = serde_json::to_vec(&stats.days)
+        .map_err(|e| e.to_string())
+        .and_then(|data| crate::durable::write_with_backup(&path, &data));
+    match result {
+        Ok(()) => stats.dirty = false,
+        Err(err) => tracing::warn!(error = %err, "写入使用统计失败"),
+    }
+}
+
+/// 读取数据目录下的 stats.json 并定时写回；文件和备份都无法解析时从空白开始
+pub fn init(app: &AppHandle, enabled: bool) {
+    let Ok(path) = app.path().app_data_dir().map(|dir| dir.join("stats.json")) else {
+        return;
+    };
+    let days = crate::durable::read_recovering(&path, |data| serde_json::from_slice(data))
+        .unwrap_or_default();
+    if let Ok(mut stats) = STATS.lock() {
+        stats.path = Some(path);
+        stats.enabled = enabled;
+        stats.days = days;
+    }
+    std::thread::spawn(|| loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        flush();
+    });
+}
+
+/// 设置中开关统计；关闭时保留已有数据，只是不再计数
+pub fn set_enabled(enabled: bool) {
+    if let Ok(mut stats) = STATS.lock() {
+        stats.enabled = enabled;
+    }
+    flush();
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsRange {
+    Today,
+    /// 本周，从周一开始
+    Week,
+    /// 本月
+    Month,
+    All,
+}
+
+impl StatsRange {
+    fn start(self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            StatsRange::Today => Some(today),
+            StatsRange::Week => {
+                Some(today - DateDuration::days(today.weekday().num_days_from_monday() as i64))
+            }
+            StatsRange::Month => today.with_day(1),
+            StatsRange::All => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsSummary {
+    /// 统计范围的第一天，All 时为空
+    pub from: Option<String>,
+    /// 范围内各项合计
+    pub total: DayStats,
+    /// 平均识别耗时（毫秒），没有识别时为空
+    pub avg_ocr_ms: Option<f64>,
+    /// 成功次数最多的截图后端
+    pub top_backend: Option<String>,
+    /// 范围内每天的计数，没有任何操作的日期不出现
+    pub days: BTreeMap<String, DayStats>,
+    /// 当前是否在统计
+    pub enabled: bool,
+    /// 本次启动时识别预热的耗时（毫秒），未预热或失败时为空
+    pub warmup_ms: Option<u64>,
+}
+
+/// 截图、识别和复制的计数汇总，包含尚未写入文件的部分
+#[tauri::command]
+pub fn get_stats(range: StatsRange) -> Result<StatsSummary, AppError> {
+    let stats = STATS
+        .lock()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let from = range
+        .start(Local::now().date_naive())
+        .map(|date| date.format(DATE_FORMAT).to_string());
+    let days: BTreeMap<String, DayStats> = stats
+        .days
+        .iter()
+        .filter(|(date, _)| from.as_ref().is_none_or(|from| *date >= from))
+        .map(|(date, day)| (date.clone(), day.clone()))
+        .collect();
+    let mut total = DayStats::default();
+    for day in days.values() {
+        total.add(day);
+    }
+    let avg_ocr_ms = (total.ocr_runs > 0).then(|| total.ocr_ms as f64 / total.ocr_runs as f64);
+    let top_backend = total
+        .backends
+        .iter()
+        .max_by_key(|(_, count)| **count)
+        .map(|(backend, _)| backend.clone());
+    Ok(StatsSummary {
+        from,
+        total,
+        avg_ocr_ms,
+        top_backend,
+        days,
+        enabled: stats.enabled,
+        warmup_ms: crate::warmup::duration_ms(),
+    })
+}
+
+/// 清空所有统计并删除文件及其备份
+#[tauri::command]
+pub fn reset_stats() -> Result<(), AppError> {
+    let mut stats = STATS
+        .lock()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    stats.days.clear();
+    stats.dirty = false;
+    if let Some(path) = &stats.path {
+        crate::durable::remove(path)?;
+    }
+    tracing::info!("已清空使用统计");
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::history::HistoryState;
+use crate::settings::SettingsState;
+
+/// 历史保留策略，0 表示不限制
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    pub max_entries: usize,
+    pub max_total_mb: u64,
+    pub max_age_days: u64,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            max_entries: 1000,
+            max_total_mb: 2048,
+            max_age_days: 0,
+        }
+    }
+}
+
+/// 临时文件超过该时长即清理
+const TEMP_MAX_AGE: Duration = Duration::from_secs(24 * 3600);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// 应用临时文件目录（复制为文件等功能的中间产物）
+pub fn temp_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_cache_dir().ok().map(|dir| dir.join("tmp"))
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    read_dir
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[derive(Serialize)]
+pub struct StorageUsage {
+    pub history_images: u64,
+    pub history_index: u64,
+    pub temp: u64,
+    pub total: u64,
+}
+
+#[derive(Default, Serialize)]
+pub struct CleanupReport {
+    pub removed_entries: usize,
+    pub removed_missing: usize,
+    pub removed_temp_files: usize,
+    pub freed_bytes: u64,
+}
+
+/// 按保留策略挑出需要删除的历史（从最旧开始）
+fn select_expired(app: &AppHandle, retention: &RetentionSettings) -> HashSet<u64> {
+    let mut entries = app.state::<HistoryState>().entries();
+    // 导入时引用的用户文件不占历史目录的空间，不按保留策略删除
+    entries.retain(|e| !e.external);
+    entries.sort_by_key(|e| e.timestamp);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let mut expired = HashSet::new();
+
+    if retention.max_age_days > 0 {
+        let cutoff = now - (retention.max_age_days * 86400) as i64;
+        expired.extend(
+            entries
+                .iter()
+                .filter(|e| e.timestamp < cutoff)
+                .map(|e| e.id),
+        );
+    }
+
+    let mut remaining: Vec<(u64, u64)> = entries
+        .iter()
+        .filter(|e| !expired.contains(&e.id))
+        .map(|e| {
+            // 重复记录不占用额外空间
+            let size = match e.duplicate_of {
+                Some(_) => 0,
+                None => std::fs::metadata(&e.image_path)
+                    .map(|m| m.len())
+                    .unwrap_or(0),
+            };
+            (e.id, size)
+        })
+        .collect();
+
+    if retention.max_entries > 0 && remaining.len() > retention.max_entries {
+        let excess = remaining.len() - retention.max_entries;
+        expired.extend(remaining.drain(..excess).map(|(id, _)| id));
+    }
+
+    if retention.max_total_mb > 0 {
+        let limit = retention.max_total_mb * 1024 * 1024;
+        let mut total: u64 = remaining.iter().map(|(_, size)| size).sum();
+        for (id, size) in remaining {
+            if total <= limit {
+                break;
+            }
+            total -= size;
+            expired.insert(id);
+        }
+    }
+    expired
+}
+
+/// 删除临时目录中超过 24 小时的文件，以及历史目录里残留的 .tmp 文件
+fn clean_temp_files(app: &AppHandle) -> (usize, u64) {
+    let mut dirs = vec![app.state::<HistoryState>().dir().to_path_buf()];
+    dirs.extend(temp_dir(app));
+
+    let now = SystemTime::now();
+    let (mut count, mut freed) = (0usize, 0u64);
+    for (i, dir) in dirs.iter().enumerate() {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            // 历史目录只处理写入中断留下的临时文件
+            let is_leftover = path.extension().is_some_and(|ext| ext == "tmp");
+            if i == 0 && !is_leftover {
+                continue;
+            }
+            let age = meta
+                .modified()
+                .ok()
+                .and_then(|m| now.duration_since(m).ok())
+                .unwrap_or_default();
+            if age >= TEMP_MAX_AGE && std::fs::remove_file(&path).is_ok() {
+                tracing::info!(path = %path.display(), "删除临时文件");
+                count += 1;
+                freed += meta.len();
+            }
+        }
+    }
+    (count, freed)
+}
+
+pub fn run_cleanup(app: &AppHandle) -> Result<CleanupReport, String> {
+    let retention = app.state::<SettingsState>().get().history_retention;
+    let history = app.state::<HistoryState>();
+    let mut report = CleanupReport {
+        removed_missing: history.vacuum()?,
+        ..Default::default()
+    };
+
+    let expired = select_expired(app, &retention);
+    if !expired.is_empty() {
+        report.freed_bytes += history.remove(&expired)?;
+        report.removed_entries = expired.len();
+        tracing::info!(count = expired.len(), "按保留策略删除历史");
+        crate::tray::refresh_recent(app);
+    }
+
+    let (temp_count, temp_freed) = clean_temp_files(app);
+    report.removed_temp_files = temp_count;
+    report.freed_bytes += temp_freed;
+    Ok(report)
+}
+
+/// 启动时清理一次，之后每天执行
+pub fn start_janitor(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        if let Err(err) = run_cleanup(&app) {
+            tracing::error!(error = %err, "清理失败");
+        }
+        thread::sleep(CLEANUP_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_storage_usage(app: AppHandle) -> StorageUsage {
+    let history_dir = app.state::<HistoryState>().dir().to_path_buf();
+    let history_index = std::fs::metadata(history_dir.join("index.json"))
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let history_images = dir_size(&history_dir).saturating_sub(history_index);
+    let temp = temp_dir(&app).map(|dir| dir_size(&dir)).unwrap_or(0);
+    StorageUsage {
+        history_images,
+        history_index,
+        temp,
+        total: history_images + history_index + temp,
+    }
+}
+
+#[tauri::command]
+pub fn run_cleanup_now(app: AppHandle) -> Result<CleanupReport, String> {
+    run_cleanup(&app)
+}
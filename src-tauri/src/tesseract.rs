@@ -0,0 +1,338 @@
+use image::GrayImage;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::error::AppError;
+use crate::external::{self, ExternalError, ExternalTool, PROBE_TIMEOUT};
+
+/// 识别参数使用仅 LSTM 的 oem 1，需要 tesseract 4.0 及以上
+const MIN_VERSION: (u32, u32) = (4, 0);
+
+/// 单次识别的最长时间，超出后结束 tesseract 进程
+const RUN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 检测结果，None 表示未安装；安装新版本后需调用 recheck_tesseract 重新检测
+static DETECTED: RwLock<Option<Option<TesseractInfo>>> = RwLock::new(None);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TesseractInfo {
+    pub path: PathBuf,
+    /// tesseract --version 的第一行，如 "tesseract 5.3.0"
+    pub version_line: String,
+    /// 解析出的主、次版本号，无法解析时为空
+    pub version: Option<(u32, u32)>,
+}
+
+/// 从 "tesseract 5.3.0" / "tesseract v5.0.0-alpha.20201127" 中取出主、次版本号
+fn parse_version(line: &str) -> Option<(u32, u32)> {
+    let version = line.split_whitespace().nth(1)?.trim_start_matches('v');
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+fn detect() -> Option<TesseractInfo> {
+    let path = ExternalTool::Tesseract.resolve().ok()?;
+    // 老版本把版本信息写到 stderr
+    let output =
+        external::run_external(ExternalTool::Tesseract, ["--version"], None, PROBE_TIMEOUT).ok()?;
+    let version_line = output
+        .text()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    let version = parse_version(&version_line);
+    tracing::info!(path = %path.display(), version = %version_line, "检测到 tesseract");
+    Some(TesseractInfo {
+        path,
+        version_line,
+        version,
+    })
+}
+
+/// 缓存的检测结果，第一次调用时检测
+pub fn detected() -> Option<TesseractInfo> {
+    if let Some(cached) = DETECTED.read().ok().and_then(|d| d.clone()) {
+        return cached;
+    }
+    let info = detect();
+    if let Ok(mut cache) = DETECTED.write() {
+        *cache = Some(info.clone());
+    }
+    info
+}
+
+/// 识别前检查 tesseract 已安装且版本足够新
+pub fn ensure() -> Result<TesseractInfo, AppError> {
+    let info = detected().ok_or(AppError::TesseractMissing)?;
+    // 无法解析版本号时不阻止识别，交给 tesseract 自己报错
+    if info.version.is_some_and(|v| v < MIN_VERSION) {
+        return Err(AppError::TesseractTooOld {
+            version: info.version_line.clone(),
+        });
+    }
+    Ok(info)
+}
+
+/// 识别参数，对应 tesseract 的命令行选项
+pub struct Options<'a> {
+    pub lang: &'a str,
+    pub dpi: u32,
+    pub psm: u32,
+    pub oem: u32,
+    /// 以 -c name=value 传入的配置变量
+    pub variables: &'a [(&'a str, &'a str)],
+    /// 附加的配置文件名，如 "tsv" 输出带坐标的逐词结果
+    pub configs: &'a [&'a str],
+}
+
+/// 识别灰度图中的文字。
+///
+/// 图片以 PGM 格式从标准输入传给 tesseract，不写临时文件，也不需要 PNG 编码；
+/// 识别期间 cancelled 返回 true 时结束 tesseract 进程并返回 Cancelled
+pub fn run(
+    image: &GrayImage,
+    options: &Options,
+    cancelled: &dyn Fn() -> bool,
+) -> Result<String, AppError> {
+    ensure()?;
+    if cancelled() {
+        return Err(AppError::Cancelled);
+    }
+    let mut args = vec![
+        "stdin".to_string(),
+        "stdout".to_string(),
+        "-l".to_string(),
+        options.lang.to_string(),
+        "--dpi".to_string(),
+        options.dpi.to_string(),
+        "--psm".to_string(),
+        options.psm.to_string(),
+        "--oem".to_string(),
+        options.oem.to_string(),
+    ];
+    for (name, value) in options.variables {
+        args.push("-c".to_string());
+        args.push(format!("{name}={value}"));
+    }
+    args.extend(options.configs.iter().map(|c| c.to_string()));
+    let mut input = format!("P5\n{} {}\n255\n", image.width(), image.height()).into_bytes();
+    input.extend_from_slice(image.as_raw());
+
+    let result = external::run_external_cancellable(
+        ExternalTool::Tesseract,
+        args,
+        Some(input),
+        RUN_TIMEOUT,
+        cancelled,
+    );
+    match result {
+        Ok(output) => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Err(ExternalError::Exit { stderr, .. })
+            if stderr.contains("Failed loading language") || stderr.contains("traineddata") =>
+        {
+            Err(AppError::LanguageMissing {
+                lang: options.lang.to_string(),
+            })
+        }
+        Err(ExternalError::Exit { stderr, .. }) => Err(AppError::OcrFailed(stderr)),
+        Err(ExternalError::Cancelled { .. }) => {
+            tracing::info!("识别已取消，已结束 tesseract 进程");
+            Err(AppError::Cancelled)
+        }
+        Err(ExternalError::Timeout { timeout, .. }) => Err(AppError::Timeout {
+            what: "tesseract".into(),
+            ms: timeout.as_millis() as u64,
+        }),
+        Err(ExternalError::NotFound { .. }) => Err(AppError::TesseractMissing),
+        Err(err) => Err(AppError::OcrFailed(err.to_string())),
+    }
+}
+
+/// TSV 输出中的一个词，坐标为传给 tesseract 的图片像素
+#[derive(Clone, Debug, PartialEq)]
+pub struct TsvWord {
+    /// (页, 块, 段, 行)，同一行的词相同
+    pub line: (u32, u32, u32, u32),
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    /// 置信度 0–100
+    pub conf: f32,
+    pub text: String,
+}
+
+/// 解析 tsv 配置的输出，只保留非空的词（level 5）
+pub fn parse_tsv(tsv: &str) -> Vec<TsvWord> {
+    tsv.lines()
+        .skip(1)
+        .filter_map(|row| {
+            let cols: Vec<&str> = row.splitn(12, '\t').collect();
+            let [level, page, block, par, line, _word, left, top, width, height, conf, text] =
+                cols[..]
+            else {
+                return None;
+            };
+            if level != "5" || text.trim().is_empty() {
+                return None;
+            }
+            let num = |s: &str| s.parse::<u32>().ok();
+            Some(TsvWord {
+                line: (num(page)?, num(block)?, num(par)?, num(line)?),
+                left: num(left)?,
+                top: num(top)?,
+                width: num(width)?,
+                height: num(height)?,
+                conf: conf.trim().parse().ok()?,
+                text: text.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}' | '\u{f900}'..='\u{faff}' | '\u{ff00}'..='\u{ffef}')
+}
+
+/// 按行拼回文字，与 txt 输出一致：段落之间空一行；
+/// 中日韩文字之间不加空格（preserve_interword_spaces 下 tesseract 也不加）
+pub fn tsv_text(words: &[TsvWord]) -> String {
+    let paragraph = |w: &TsvWord| (w.line.0, w.line.1, w.line.2);
+    let mut text = String::new();
+    let mut prev: Option<&TsvWord> = None;
+    for word in words {
+        match prev {
+            Some(p) if p.line == word.line => {
+                let joined = p.text.chars().last().is_some_and(is_cjk)
+                    && word.text.chars().next().is_some_and(is_cjk);
+                if !joined {
+                    text.push(' ');
+                }
+            }
+            Some(p) if paragraph(p) == paragraph(word) => text.push('\n'),
+            Some(_) => text.push_str("\n\n"),
+            None => {}
+        }
+        text.push_str(&word.text);
+        prev = Some(word);
+    }
+    text
+}
+
+/// 各词置信度的平均值，没有词时为空
+pub fn mean_confidence(words: &[TsvWord]) -> Option<f32> {
+    let confs: Vec<f32> = words.iter().map(|w| w.conf).filter(|c| *c >= 0.0).collect();
+    (!confs.is_empty()).then(|| confs.iter().sum::<f32>() / confs.len() as f32)
+}
+
+/// 已安装的语言包；tesseract --list-langs 第一行是标题，未安装 tesseract 时为空
+pub fn languages() -> Vec<String> {
+    if detected().is_none() {
+        return Vec::new();
+    }
+    let output = external::run_external(
+        ExternalTool::Tesseract,
+        ["--list-langs"],
+        None,
+        PROBE_TIMEOUT,
+    );
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+    // 老版本把列表写到 stderr
+    output
+        .text()
+        .lines()
+        .skip(1)
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// 丢弃缓存的检测结果，下次使用时重新检测
+pub fn reset() {
+    if let Ok(mut cache) = DETECTED.write() {
+        *cache = None;
+    }
+}
+
+/// 安装或升级 tesseract 后重新检测
+#[cfg_attr(feature = "tauri", tauri::command)]
+pub fn recheck_tesseract() -> Result<TesseractInfo, AppError> {
+    reset();
+    ensure()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+
+    fn tsv(rows: &[&str]) -> String {
+        std::iter::once(HEADER)
+            .chain(rows.iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn versions_are_parsed() {
+        assert_eq!(parse_version("tesseract 5.3.0"), Some((5, 3)));
+        assert_eq!(
+            parse_version("tesseract v5.0.0-alpha.20201127"),
+            Some((5, 0))
+        );
+        assert_eq!(parse_version("tesseract 4"), Some((4, 0)));
+        assert_eq!(parse_version("tesseract"), None);
+    }
+
+    #[test]
+    fn words_keep_position_and_confidence() {
+        let words = parse_tsv(&tsv(&[
+            "1\t1\t0\t0\t0\t0\t0\t0\t200\t40\t-1\t",
+            "5\t1\t1\t1\t1\t1\t10\t5\t30\t12\t96.25\tHello",
+            "5\t1\t1\t1\t1\t2\t45\t5\t20\t12\t-1\t ",
+        ]));
+        assert_eq!(
+            words,
+            vec![TsvWord {
+                line: (1, 1, 1, 1),
+                left: 10,
+                top: 5,
+                width: 30,
+                height: 12,
+                conf: 96.25,
+                text: "Hello".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn text_is_rebuilt_by_line_and_paragraph() {
+        let words = parse_tsv(&tsv(&[
+            "5\t1\t1\t1\t1\t1\t0\t0\t1\t1\t90\tHello",
+            "5\t1\t1\t1\t1\t2\t0\t0\t1\t1\t80\tworld",
+            "5\t1\t1\t1\t2\t1\t0\t0\t1\t1\t70\t第二",
+            "5\t1\t1\t1\t2\t2\t0\t0\t1\t1\t60\t行",
+            "5\t1\t1\t1\t2\t3\t0\t0\t1\t1\t50\tOK",
+            "5\t1\t2\t1\t1\t1\t0\t0\t1\t1\t40\t新段落",
+        ]));
+        assert_eq!(tsv_text(&words), "Hello world\n第二行 OK\n\n新段落");
+        assert_eq!(mean_confidence(&words), Some(65.0));
+    }
+
+    #[test]
+    fn no_words_have_no_confidence() {
+        assert_eq!(tsv_text(&[]), "");
+        assert_eq!(mean_confidence(&[]), None);
+    }
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+use similar::{Algorithm, DiffOp};
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+use crate::history::is_cjk;
+
+/// 超过该时间后退回较粗的比较结果，避免两段长文本差异很大时卡住
+const DIFF_DEADLINE: Duration = Duration::from_secs(1);
+
+/// 一处差异；以参考文字为准，insert 为识别结果中多出的内容，delete 为识别结果中缺少的内容。
+/// 索引为分词后的位置（中日韩文字逐字，其他按词和标点）
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DiffChange {
+    Equal {
+        reference_index: usize,
+        ocr_index: usize,
+        text: String,
+    },
+    Delete {
+        reference_index: usize,
+        ocr_index: usize,
+        text: String,
+    },
+    Insert {
+        reference_index: usize,
+        ocr_index: usize,
+        text: String,
+    },
+    Replace {
+        reference_index: usize,
+        ocr_index: usize,
+        reference_text: String,
+        ocr_text: String,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TextDiff {
+    /// 相同部分占两段文字的比例（0–100）
+    pub similarity: f64,
+    pub reference_tokens: usize,
+    pub ocr_tokens: usize,
+    /// 按顺序排列，包括相同的部分，前端据此显示修订视图
+    pub changes: Vec<DiffChange>,
+}
+
+/// 分词：中日韩文字逐字，连续的字母数字为一个词，其他标点单独成词，空白只作分隔
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (i, c) in text.char_indices() {
+        let in_word = c.is_alphanumeric() && !is_cjk(c);
+        if in_word {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            tokens.push(&text[start..i]);
+        }
+        if !c.is_whitespace() {
+            tokens.push(&text[i..i + c.len_utf8()]);
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// 拼回可读的文字：两个西文词之间加空格，中日韩文字与标点直接相连
+fn join(tokens: &[&str]) -> String {
+    let mut out = String::new();
+    let mut prev_word = false;
+    for token in tokens {
+        let word = token.chars().all(|c| c.is_alphanumeric() && !is_cjk(c));
+        if word && prev_word {
+            out.push(' ');
+        }
+        out.push_str(token);
+        prev_word = word;
+    }
+    out
+}
+
+/// 比较识别结果与参考文字
+pub fn compare(ocr_text: &str, reference_text: &str) -> TextDiff {
+    let reference = tokenize(reference_text);
+    let ocr = tokenize(ocr_text);
+    let ops = similar::capture_diff_deadline(
+        Algorithm::Myers,
+        &reference,
+        0..reference.len(),
+        &ocr,
+        0..ocr.len(),
+        Some(Instant::now() + DIFF_DEADLINE),
+    );
+    let ratio = if reference.is_empty() && ocr.is_empty() {
+        1.0
+    } else {
+        similar::get_diff_ratio(&ops, reference.len(), ocr.len())
+    };
+    let changes = ops
+        .iter()
+        .map(|op| match *op {
+            DiffOp::Equal {
+                old_index,
+                new_index,
+                len,
+            } => DiffChange::Equal {
+                reference_index: old_index,
+                ocr_index: new_index,
+                text: join(&reference[old_index..old_index + len]),
+            },
+            DiffOp::Delete {
+                old_index,
+                old_len,
+                new_index,
+            } => DiffChange::Delete {
+                reference_index: old_index,
+                ocr_index: new_index,
+                text: join(&reference[old_index..old_index + old_len]),
+            },
+            DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } => DiffChange::Insert {
+                reference_index: old_index,
+                ocr_index: new_index,
+                text: join(&ocr[new_index..new_index + new_len]),
+            },
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => DiffChange::Replace {
+                reference_index: old_index,
+                ocr_index: new_index,
+                reference_text: join(&reference[old_index..old_index + old_len]),
+                ocr_text: join(&ocr[new_index..new_index + new_len]),
+            },
+        })
+        .collect();
+    TextDiff {
+        similarity: (ratio as f64 * 1000.0).round() / 10.0,
+        reference_tokens: reference.len(),
+        ocr_tokens: ocr.len(),
+        changes,
+    }
+}
+
+/// 逐词（中日韩文字逐字）比较识别结果与参考文字，用于校对
+#[tauri::command]
+pub async fn compare_text(ocr_text: String, reference_text: String) -> Result<TextDiff, AppError> {
+    crate::run_blocking(move || Ok(compare(&ocr_text, &reference_text))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_split_cjk_per_character_and_keep_punctuation() {
+        let tokens = tokenize("识别 OCR2 text，ok!");
+        assert_eq!(tokens, ["识", "别", "OCR2", "text", "，", "ok", "!"]);
+        assert_eq!(join(&tokens), "识别OCR2 text，ok!");
+        assert_eq!(join(&["hello", "world", "中", "文"]), "hello world中文");
+    }
+
+    #[test]
+    fn changes_are_indexed_by_token() {
+        let diff = compare("识别文宇 hello, world", "识别文字 hello world");
+        assert_eq!((diff.reference_tokens, diff.ocr_tokens), (6, 7));
+        assert_eq!(diff.similarity, 76.9);
+        let changes = serde_json::to_value(&diff.changes).unwrap();
+        assert_eq!(
+            changes,
+            serde_json::json!([
+                { "op": "equal", "reference_index": 0, "ocr_index": 0, "text": "识别文" },
+                {
+                    "op": "replace", "reference_index": 3, "ocr_index": 3,
+                    "reference_text": "字", "ocr_text": "宇"
+                },
+                { "op": "equal", "reference_index": 4, "ocr_index": 4, "text": "hello" },
+                { "op": "insert", "reference_index": 5, "ocr_index": 5, "text": "," },
+                { "op": "equal", "reference_index": 5, "ocr_index": 6, "text": "world" },
+            ])
+        );
+    }
+
+    #[test]
+    fn empty_texts_are_identical() {
+        let diff = compare("", "  ");
+        assert_eq!(diff.similarity, 100.0);
+        assert!(diff.changes.is_empty());
+        let missing = compare("", "全文");
+        assert_eq!(missing.similarity, 0.0);
+        assert!(matches!(
+            &missing.changes[..],
+            [DiffChange::Delete { text, .. }] if text == "全文"
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::notify::{self, CopyPayload, Notice, NotifyEvent};
+use crate::ocr_queue::{JobInput, OcrQueue};
+use crate::poll_watch::{self, Watches};
+use crate::settings::SettingsState;
+
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchOptions {
+    /// 显示在事件和通知中的名称
+    pub label: Option<String>,
+    /// 文字变化时发送桌面通知
+    pub notify: bool,
+    /// 识别语言，为空时使用设置中的语言
+    pub lang: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct WatchInfo {
+    pub id: u64,
+    pub region: Region,
+    pub monitor: Option<u32>,
+    pub interval_s: f64,
+    pub options: WatchOptions,
+    /// 当前确认的文字，第一次读取前为空
+    pub text: Option<String>,
+    pub started_at: i64,
+}
+
+pub type TextWatches = Watches<WatchInfo>;
+
+/// watched-text-changed 事件
+#[derive(Clone, Serialize)]
+struct WatchedTextChanged {
+    id: u64,
+    label: Option<String>,
+    old_text: String,
+    new_text: String,
+    at: i64,
+}
+
+/// text-watch-stopped 事件：连续失败后自动停止
+#[derive(Clone, Serialize)]
+struct TextWatchStopped {
+    id: u64,
+    reason: String,
+}
+
+/// 合并空白，识别结果中的换行、多余空格变化不算文字变化
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 去抖：新文字要连续两次读到相同结果才算变化，避免识别结果来回跳动
+#[derive(Default)]
+struct Debounce {
+    current: Option<String>,
+    candidate: Option<String>,
+}
+
+impl Debounce {
+    /// 返回确认后的变化 (旧文字, 新文字)；第一次读取只作为基准
+    fn feed(&mut self, reading: String) -> Option<(String, String)> {
+        let Some(current) = &self.current else {
+            self.current = Some(reading);
+            return None;
+        };
+        if *current == reading {
+            self.candidate = None;
+            return None;
+        }
+        if self.candidate.as_ref() != Some(&reading) {
+            self.candidate = Some(reading);
+            return None;
+        }
+        self.candidate = None;
+        self.current
+            .replace(reading.clone())
+            .map(|old| (old, reading))
+    }
+}
+
+fn read_text(
+    app: &AppHandle,
+    monitor: Option<u32>,
+    region: Region,
+    lang: &str,
+) -> Result<String, AppError> {
+    let frame = crate::quick::poll_area_without_windows(app, monitor, Some(region))
+        .map_err(AppError::Internal)?;
+    let image = crate::headless::crop(frame, region)?.into_image()?;
+    let recognized = app
+        .state::<OcrQueue>()
+        .recognize_uncounted(JobInput::Image(Arc::new(image)), lang.to_string())?;
+    Ok(normalize(&recognized.text))
+}
+
+fn run(app: AppHandle, info: Arc<Mutex<WatchInfo>>, stop: Arc<AtomicBool>) {
+    let (id, region, monitor, interval, options) = {
+        let info = info.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            info.id,
+            info.region,
+            info.monitor,
+            Duration::from_secs_f64(info.interval_s),
+            info.options.clone(),
+        )
+    };
+    let lang = options
+        .lang
+        .clone()
+        .unwrap_or_else(|| app.state::<SettingsState>().get().ocr_lang);
+    let mut debounce = Debounce::default();
+
+    let result = poll_watch::poll(id, interval, &stop, || {
+        let text = read_text(&app, monitor, region, &lang)?;
+        if let Some((old_text, new_text)) = debounce.feed(text) {
+            tracing::info!(id, %old_text, %new_text, "监视区域的文字已变化");
+            notify_change(&app, id, &options, old_text, new_text);
+        }
+        if let Ok(mut info) = info.lock() {
+            info.text = debounce.current.clone();
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        let _ = app.emit(
+            "text-watch-stopped",
+            TextWatchStopped {
+                id,
+                reason: err.message(),
+            },
+        );
+    }
+    app.state::<TextWatches>().finished(id);
+    tracing::info!(id, "文字监视已结束");
+}
+
+fn notify_change(
+    app: &AppHandle,
+    id: u64,
+    options: &WatchOptions,
+    old_text: String,
+    new_text: String,
+) {
+    if options.notify {
+        let summary = options.label.as_deref().unwrap_or("监视区域的文字已变化");
+        let mut notice = Notice::new(
+            NotifyEvent::Ocr,
+            summary,
+            format!("{old_text} → {new_text}"),
+        );
+        notice.copy = Some(CopyPayload::Text(new_text.clone()));
+        notify::notify(app, notice);
+    }
+    let _ = app.emit(
+        "watched-text-changed",
+        WatchedTextChanged {
+            id,
+            label: options.label.clone(),
+            old_text,
+            new_text,
+            at: chrono::Local::now().timestamp_millis(),
+        },
+    );
+}
+
+/// 定时截取区域（所选显示器或整屏的像素坐标）并识别，文字变化时发出 watched-text-changed 事件；
+/// 返回监视 id
+#[tauri::command]
+pub fn start_text_watch(
+    app: AppHandle,
+    rect: Region,
+    monitor: Option<u32>,
+    interval_s: f64,
+    options: Option<WatchOptions>,
+) -> Result<u64, AppError> {
+    if rect.width == 0 || rect.height == 0 {
+        return Err(AppError::invalid("监视区域为空"));
+    }
+    if !interval_s.is_finite() || interval_s < MIN_INTERVAL.as_secs_f64() {
+        return Err(AppError::invalid(format!(
+            "监视间隔不能小于 {} 秒",
+            MIN_INTERVAL.as_secs()
+        )));
+    }
+    let handle = app.clone();
+    let id = app.state::<TextWatches>().start(
+        "区域",
+        |id| WatchInfo {
+            id,
+            region: rect,
+            monitor,
+            interval_s,
+            options: options.unwrap_or_default(),
+            text: None,
+            started_at: chrono::Local::now().timestamp_millis(),
+        },
+        move |info, stop| run(handle, info, stop),
+    )?;
+    tracing::info!(id, ?rect, ?monitor, interval_s, "开始监视区域文字");
+    Ok(id)
+}
+
+/// 停止监视，返回是否找到该监视
+#[tauri::command]
+pub fn stop_text_watch(app: AppHandle, id: u64) -> bool {
+    app.state::<TextWatches>().stop(id)
+}
+
+#[tauri::command]
+pub fn list_text_watches(app: AppHandle) -> Vec<WatchInfo> {
+    app.state::<TextWatches>().list()
+}
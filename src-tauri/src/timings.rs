@@ -0,0 +1,86 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// 最近一次截图和识别的耗时，供诊断面板查看
+static LAST: Mutex<LastTimings> = Mutex::new(LastTimings {
+    capture: None,
+    ocr: None,
+});
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Stage {
+    pub name: String,
+    pub ms: f64,
+}
+
+/// 一次截图或识别中各阶段的耗时，按发生顺序记录
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Timings {
+    pub stages: Vec<Stage>,
+    pub total_ms: f64,
+}
+
+impl Timings {
+    pub fn record(&mut self, name: impl Into<String>, started: Instant) {
+        let ms = started.elapsed().as_secs_f64() * 1000.0;
+        self.total_ms += ms;
+        self.stages.push(Stage {
+            name: name.into(),
+            ms,
+        });
+    }
+
+    pub fn time<T>(&mut self, name: impl Into<String>, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let value = f();
+        self.record(name, started);
+        value
+    }
+
+    fn summary(&self) -> String {
+        self.stages
+            .iter()
+            .map(|s| format!("{}={:.1}", s.name, s.ms))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Kind {
+    Capture,
+    Ocr,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LastTimings {
+    pub capture: Option<Timings>,
+    pub ocr: Option<Timings>,
+}
+
+/// 一次截图或识别结束：写入 debug 日志并保存为最近一次
+pub fn finish(kind: Kind, timings: &Timings) {
+    tracing::debug!(
+        ?kind,
+        total_ms = timings.total_ms as u64,
+        stages = %timings.summary(),
+        "阶段耗时"
+    );
+    if let Ok(mut last) = LAST.lock() {
+        let slot = match kind {
+            Kind::Capture => &mut last.capture,
+            Kind::Ocr => &mut last.ocr,
+        };
+        *slot = Some(timings.clone());
+    }
+}
+
+/// 最近一次截图和识别的各阶段耗时，不会重新执行
+#[cfg_attr(feature = "tauri", tauri::command)]
+pub fn get_last_timings() -> LastTimings {
+    LAST.lock().map(|last| last.clone()).unwrap_or(LastTimings {
+        capture: None,
+        ocr: None,
+    })
+}
@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SettingsState;
+
+/// 单次请求的文本上限（字节）。DeepL 限制请求体 128 KiB，大模型还受上下文长度限制，
+/// 取一个对两者都安全的值
+const MAX_CHUNK_BYTES: usize = 6000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// 错误信息中保留的响应正文长度
+const MAX_ERROR_BODY: usize = 500;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    #[default]
+    Deepl,
+    /// 兼容 OpenAI chat/completions 接口的服务
+    Openai,
+}
+
+/// 翻译服务配置
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslateSettings {
+    pub provider: Provider,
+    /// 以 ":fx" 结尾的免费版密钥自动使用 api-free.deepl.com
+    pub deepl_api_key: Option<String>,
+    /// 如 https://api.openai.com/v1，请求发往其下的 /chat/completions
+    pub openai_base_url: String,
+    /// 本地部署的服务可以不设置
+    pub openai_api_key: Option<String>,
+    pub openai_model: String,
+    /// 识别后顺带翻译时的目标语言
+    pub target_lang: String,
+}
+
+impl Default for TranslateSettings {
+    fn default() -> Self {
+        Self {
+            provider: Provider::default(),
+            deepl_api_key: None,
+            openai_base_url: "https://api.openai.com/v1".into(),
+            openai_api_key: None,
+            openai_model: "gpt-4o-mini".into(),
+            target_lang: "zh-CN".into(),
+        }
+    }
+}
+
+// 手写 Debug，避免密钥出现在日志里
+impl std::fmt::Debug for TranslateSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redact = |key: &Option<String>| key.as_ref().map(|_| "***");
+        f.debug_struct("TranslateSettings")
+            .field("provider", &self.provider)
+            .field("deepl_api_key", &redact(&self.deepl_api_key))
+            .field("openai_base_url", &self.openai_base_url)
+            .field("openai_api_key", &redact(&self.openai_api_key))
+            .field("openai_model", &self.openai_model)
+            .field("target_lang", &self.target_lang)
+            .finish()
+    }
+}
+
+impl TranslateSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.target_lang.trim().is_empty() {
+            return Err("翻译目标语言不能为空".into());
+        }
+        if self.provider == Provider::Openai {
+            reqwest::Url::parse(self.openai_base_url.trim())
+                .map_err(|e| format!("翻译服务地址无效: {e}"))?;
+            if self.openai_model.trim().is_empty() {
+                return Err("翻译模型不能为空".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranslateError {
+    InvalidInput {
+        detail: String,
+    },
+    /// 缺少密钥等必要配置
+    NotConfigured {
+        detail: String,
+    },
+    Timeout {
+        secs: u64,
+    },
+    Network {
+        detail: String,
+    },
+    Http {
+        status: u16,
+        body: String,
+    },
+    /// 响应中找不到译文
+    InvalidResponse {
+        detail: String,
+    },
+}
+
+impl std::fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranslateError::InvalidInput { detail } => write!(f, "参数错误: {detail}"),
+            TranslateError::NotConfigured { detail } => write!(f, "翻译服务未配置: {detail}"),
+            TranslateError::Timeout { secs } => write!(f, "翻译超时（超过 {secs} 秒）"),
+            TranslateError::Network { detail } => write!(f, "网络错误: {detail}"),
+            TranslateError::Http { status, .. } => write!(f, "翻译服务返回 {status}"),
+            TranslateError::InvalidResponse { detail } => write!(f, "无法解析翻译结果: {detail}"),
+        }
+    }
+}
+
+fn map_request_error(err: reqwest::Error) -> TranslateError {
+    if err.is_timeout() {
+        return TranslateError::Timeout {
+            secs: REQUEST_TIMEOUT.as_secs(),
+        };
+    }
+    let mut detail = err.to_string();
+    let mut source = err.source();
+    while let Some(inner) = source {
+        detail = format!("{detail}: {inner}");
+        source = inner.source();
+    }
+    TranslateError::Network { detail }
+}
+
+/// 某一段翻译失败的原因，该段在译文中保留原文
+#[derive(Clone, Debug, Serialize)]
+pub struct ChunkFailure {
+    pub index: usize,
+    pub error: TranslateError,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Translation {
+    pub text: String,
+    /// 分段数，超过单次请求上限的文本按段落拆分后分别翻译
+    pub chunks: usize,
+    pub failed: Vec<ChunkFailure>,
+}
+
+/// 在段落边界把文本切成不超过 max_bytes 的几段；段落本身过长时依次退到换行、空格，
+/// 最后按字符切开。各段首尾相接即为原文
+fn split_chunks(text: &str, max_bytes: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_bytes {
+        let mut end = max_bytes;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let window = &rest[..end];
+        let cut = window
+            .rfind("\n\n")
+            .map(|i| i + 2)
+            .or_else(|| window.rfind('\n').map(|i| i + 1))
+            .or_else(|| window.rfind(' ').map(|i| i + 1))
+            .filter(|&cut| cut > 0)
+            .unwrap_or(end);
+        chunks.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+struct Translator<'a> {
+    client: reqwest::Client,
+    settings: &'a TranslateSettings,
+    source_lang: Option<&'a str>,
+    target_lang: &'a str,
+}
+
+impl Translator<'_> {
+    async fn translate(&self, text: &str) -> Result<String, TranslateError> {
+        match self.settings.provider {
+            Provider::Deepl => self.deepl(text).await,
+            Provider::Openai => self.openai(text).await,
+        }
+    }
+
+    async fn deepl(&self, text: &str) -> Result<String, TranslateError> {
+        let key = self
+            .settings
+            .deepl_api_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| TranslateError::NotConfigured {
+                detail: "缺少 DeepL API 密钥".into(),
+            })?;
+        let host = if key.ends_with(":fx") {
+            "https://api-free.deepl.com"
+        } else {
+            "https://api.deepl.com"
+        };
+        let mut body = json!({
+            "text": [text],
+            "target_lang": deepl_lang(self.target_lang, true),
+        });
+        if let Some(source) = self.source_lang {
+            body["source_lang"] = deepl_lang(source, false).into();
+        }
+        let request = self
+            .client
+            .post(format!("{host}/v2/translate"))
+            .header("Authorization", format!("DeepL-Auth-Key {key}"))
+            .json(&body);
+        let value = send(request).await?;
+        value["translations"][0]["text"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| TranslateError::InvalidResponse {
+                detail: "缺少 translations[0].text".into(),
+            })
+    }
+
+    async fn openai(&self, text: &str) -> Result<String, TranslateError> {
+        let base = self.settings.openai_base_url.trim().trim_end_matches('/');
+        let source = match self.source_lang {
+            Some(lang) => format!(" from {lang}"),
+            None => String::new(),
+        };
+        let prompt = format!(
+            "Translate the user's text{source} into {}. \
+             Reply with the translation only and keep the original line breaks.",
+            self.target_lang
+        );
+        let mut request = self
+            .client
+            .post(format!("{base}/chat/completions"))
+            .json(&json!({
+                "model": self.settings.openai_model,
+                "temperature": 0,
+                "messages": [
+                    { "role": "system", "content": prompt },
+                    { "role": "user", "content": text },
+                ],
+            }));
+        if let Some(key) = self
+            .settings
+            .openai_api_key
+            .as_deref()
+            .filter(|k| !k.trim().is_empty())
+        {
+            request = request.bearer_auth(key.trim());
+        }
+        let value = send(request).await?;
+        value["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| TranslateError::InvalidResponse {
+                detail: "缺少 choices[0].message.content".into(),
+            })
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<Value, TranslateError> {
+    let response = request
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(map_request_error)?;
+    let status = response.status();
+    let body = response.text().await.map_err(map_request_error)?;
+    if !status.is_success() {
+        return Err(TranslateError::Http {
+            status: status.as_u16(),
+            body: body.chars().take(MAX_ERROR_BODY).collect(),
+        });
+    }
+    serde_json::from_str(&body).map_err(|e| TranslateError::InvalidResponse {
+        detail: e.to_string(),
+    })
+}
+
+/// DeepL 的语言代码：源语言只用主标签（EN），目标语言英语、葡萄牙语需要带地区
+fn deepl_lang(lang: &str, target: bool) -> String {
+    let upper = lang.trim().replace('_', "-").to_uppercase();
+    let primary = upper.split('-').next().unwrap_or_default();
+    match (target, primary) {
+        (true, "EN") if upper == "EN" => "EN-US".into(),
+        (true, "PT") if upper == "PT" => "PT-PT".into(),
+        (true, "EN" | "PT") => upper.clone(),
+        _ => primary.to_string(),
+    }
+}
+
+/// 翻译文本；超过单次请求上限时按段落分段，某段失败时保留该段原文，其余段照常翻译
+pub async fn translate(
+    app: &AppHandle,
+    text: &str,
+    source_lang: Option<&str>,
+    target_lang: &str,
+) -> Result<Translation, TranslateError> {
+    if target_lang.trim().is_empty() {
+        return Err(TranslateError::InvalidInput {
+            detail: "目标语言不能为空".into(),
+        });
+    }
+    let settings = app.state::<SettingsState>().get().translate;
+    let translator = Translator {
+        client: crate::http::client(app),
+        settings: &settings,
+        source_lang: source_lang.map(str::trim).filter(|l| !l.is_empty()),
+        target_lang: target_lang.trim(),
+    };
+
+    let chunks = split_chunks(text, MAX_CHUNK_BYTES);
+    let mut translated = String::with_capacity(text.len());
+    let mut failed = Vec::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        // 段尾的空行不交给翻译服务，按原样接回
+        let body = chunk.trim_end();
+        let trailing = &chunk[body.len()..];
+        if body.trim().is_empty() {
+            translated.push_str(chunk);
+            continue;
+        }
+        match translator.translate(body).await {
+            Ok(text) => translated.push_str(text.trim_end()),
+            // 配置错误每段都会失败，直接返回
+            Err(error @ TranslateError::NotConfigured { .. }) => return Err(error),
+            Err(error) => {
+                tracing::warn!(provider = ?settings.provider, index, %error, "翻译分段失败");
+                translated.push_str(body);
+                failed.push(ChunkFailure { index, error });
+            }
+        }
+        translated.push_str(trailing);
+    }
+    tracing::info!(
+        provider = ?settings.provider,
+        chunks = chunks.len(),
+        failed = failed.len(),
+        "翻译完成"
+    );
+    Ok(Translation {
+        text: translated,
+        chunks: chunks.len(),
+        failed,
+    })
+}
+
+/// 翻译到设置中的目标语言，源语言由服务自动识别
+pub async fn translate_to_default(
+    app: &AppHandle,
+    text: &str,
+) -> Result<Translation, TranslateError> {
+    let target = app.state::<SettingsState>().get().translate.target_lang;
+    translate(app, text, None, &target).await
+}
+
+/// 翻译文本，source_lang 为空时由服务自动识别
+#[tauri::command]
+pub async fn translate_text(
+    app: AppHandle,
+    text: String,
+    source_lang: Option<String>,
+    target_lang: String,
+) -> Result<Translation, TranslateError> {
+    translate(&app, &text, source_lang.as_deref(), &target_lang).await
+}
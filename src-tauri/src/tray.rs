@@ -0,0 +1,653 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Manager, Wry,
+};
+
+use crate::history::HistoryState;
+use crate::i18n::{t, tf};
+use crate::settings::SettingsState;
+use crate::trigger::{CaptureTrigger, TriggerSource};
+
+/// 托盘菜单中显示的最近截图数量
+const RECENT_LIMIT: usize = 5;
+
+/// 托盘菜单句柄，历史变化时原地更新“最近截图”子菜单，切换语言时更新文字
+pub struct TrayMenu {
+    pub menu: Menu<Wry>,
+    capture: MenuItem<Wry>,
+    quick_capture: MenuItem<Wry>,
+    quick_ocr: MenuItem<Wry>,
+    pipeline: Option<MenuItem<Wry>>,
+    recent: Submenu<Wry>,
+    pause_shortcuts: CheckMenuItem<Wry>,
+    pause_hour: MenuItem<Wry>,
+    reregister: MenuItem<Wry>,
+    settings: MenuItem<Wry>,
+    quit: MenuItem<Wry>,
+    /// 简化模式下临时加入的菜单项及其文案 key
+    degraded: Mutex<Vec<(MenuItem<Wry>, &'static str)>>,
+}
+
+/// 简化模式下加入菜单的操作，都不经过前端
+const DEGRADED_ITEMS: &[(&str, &str)] = &[
+    ("capture-to-file", "tray.capture_to_file"),
+    ("read-at-cursor", "tray.read_at_cursor"),
+    ("show-diagnostics", "tray.show_diagnostics"),
+];
+
+/// 出错后保持错误图标的时长
+const ERROR_DISPLAY: Duration = Duration::from_secs(5);
+
+/// 托盘图标句柄与状态：有任务进行时显示忙碌图标，录屏时显示录制图标，失败后短暂显示错误图标
+pub struct TrayStatus {
+    icon: TrayIcon<Wry>,
+    normal: Option<Image<'static>>,
+    busy: Option<Image<'static>>,
+    error: Option<Image<'static>>,
+    recording_icon: Option<Image<'static>>,
+    busy_count: AtomicUsize,
+    recording: AtomicBool,
+    error_until: Mutex<Option<Instant>>,
+}
+
+fn load_icon(bytes: &[u8]) -> Option<Image<'static>> {
+    let rgba = image::load_from_memory(bytes).ok()?.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    Some(Image::new_owned(rgba.into_raw(), w, h))
+}
+
+impl TrayStatus {
+    pub fn new(icon: TrayIcon<Wry>, normal: Option<Image<'static>>) -> Self {
+        Self {
+            icon,
+            normal,
+            busy: load_icon(include_bytes!("../icons/tray-busy.png")),
+            error: load_icon(include_bytes!("../icons/tray-error.png")),
+            recording_icon: load_icon(include_bytes!("../icons/tray-recording.png")),
+            busy_count: AtomicUsize::new(0),
+            recording: AtomicBool::new(false),
+            error_until: Mutex::new(None),
+        }
+    }
+
+    /// 按优先级（错误 > 录制 > 忙碌 > 正常）刷新图标
+    fn apply(&self) {
+        let in_error = self
+            .error_until
+            .lock()
+            .ok()
+            .and_then(|until| *until)
+            .is_some_and(|until| Instant::now() < until);
+        let image = if in_error {
+            &self.error
+        } else if self.recording.load(Ordering::SeqCst) {
+            &self.recording_icon
+        } else if self.busy_count.load(Ordering::SeqCst) > 0 {
+            &self.busy
+        } else {
+            &self.normal
+        };
+        if let Some(image) = image {
+            let _ = self.icon.set_icon(Some(image.clone()));
+        }
+    }
+}
+
+/// 任务进行期间持有，离开作用域后恢复图标
+pub struct BusyGuard(Option<AppHandle>);
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        if let Some(status) = self
+            .0
+            .as_ref()
+            .and_then(|app| app.try_state::<TrayStatus>())
+        {
+            status.busy_count.fetch_sub(1, Ordering::SeqCst);
+            status.apply();
+        }
+    }
+}
+
+/// 截图、识别等耗时任务开始时调用
+pub fn busy(app: &AppHandle) -> BusyGuard {
+    let Some(status) = app.try_state::<TrayStatus>() else {
+        return BusyGuard(None);
+    };
+    status.busy_count.fetch_add(1, Ordering::SeqCst);
+    status.apply();
+    BusyGuard(Some(app.clone()))
+}
+
+/// 批量识别期间在提示文字中显示进度，如“OCR 12/40”；totals 为空时恢复默认提示
+pub fn set_ocr_progress(app: &AppHandle, totals: Option<(usize, usize)>) {
+    let Some(status) = app.try_state::<TrayStatus>() else {
+        return;
+    };
+    let text = match totals {
+        Some((completed, total)) => format!(
+            "{}\n{}",
+            t("tray.tooltip"),
+            tf(
+                "tray.ocr_progress",
+                &[
+                    ("completed", &completed.to_string()),
+                    ("total", &total.to_string()),
+                ],
+            )
+        ),
+        None => t("tray.tooltip"),
+    };
+    let _ = status.icon.set_tooltip(Some(text));
+}
+
+/// 录屏开始、结束时切换录制图标
+pub fn set_recording(app: &AppHandle, recording: bool) {
+    if let Some(status) = app.try_state::<TrayStatus>() {
+        status.recording.store(recording, Ordering::SeqCst);
+        status.apply();
+    }
+}
+
+/// 记录最近一次动作到提示文字，如“上次截图 14:32 已复制”（参数为文案 key）；失败时短暂显示错误图标
+pub fn record(app: &AppHandle, action: &str, result: &str, ok: bool) {
+    let Some(status) = app.try_state::<TrayStatus>() else {
+        return;
+    };
+    let time = Local::now().format("%H:%M");
+    let text = format!("{}\n{} {time} {}", t("tray.tooltip"), t(action), t(result));
+    let _ = status.icon.set_tooltip(Some(text));
+    if ok {
+        return;
+    }
+
+    if let Ok(mut until) = status.error_until.lock() {
+        *until = Some(Instant::now() + ERROR_DISPLAY);
+    }
+    status.apply();
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(ERROR_DISPLAY);
+        if let Some(status) = app.try_state::<TrayStatus>() {
+            status.apply();
+        }
+    });
+}
+
+/// 勿扰模式中未弹出的通知，摘要写入提示文字，如“14:32 截图完成：已保存到 …”
+pub fn record_notice(app: &AppHandle, summary: &str, body: &str) {
+    let Some(status) = app.try_state::<TrayStatus>() else {
+        return;
+    };
+    let time = Local::now().format("%H:%M");
+    let body: String = body
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(80)
+        .collect();
+    let text = format!("{}\n{time} {summary}：{body}", t("tray.tooltip"));
+    let _ = status.icon.set_tooltip(Some(text));
+}
+
+/// “暂停 1 小时”的时长
+const PAUSE_MINUTES: u64 = 60;
+
+fn pipeline_label(settings: &crate::settings::Settings) -> String {
+    let default = t("tray.pipeline_default");
+    let name = settings.active_pipeline.as_deref().unwrap_or(&default);
+    tf("tray.pipeline", &[("name", name)])
+}
+
+pub fn build_menu(app: &AppHandle) -> tauri::Result<TrayMenu> {
+    let capture = MenuItem::with_id(app, "capture", t("tray.capture"), true, None::<&str>)?;
+    let quick_capture = MenuItem::with_id(
+        app,
+        "quick-capture",
+        t("tray.quick_capture"),
+        true,
+        None::<&str>,
+    )?;
+    let quick_ocr = MenuItem::with_id(app, "quick-ocr", t("tray.quick_ocr"), true, None::<&str>)?;
+    let recent = Submenu::with_id(app, "recent", t("tray.recent"), true)?;
+    let current = app.state::<SettingsState>().get();
+    let pause_shortcuts = CheckMenuItem::with_id(
+        app,
+        "pause-shortcuts",
+        t("tray.pause_shortcuts"),
+        true,
+        current.shortcuts_paused,
+        None::<&str>,
+    )?;
+    let pause_hour = MenuItem::with_id(
+        app,
+        "pause-shortcuts-hour",
+        t("tray.pause_shortcuts_hour"),
+        true,
+        None::<&str>,
+    )?;
+    let reregister = MenuItem::with_id(
+        app,
+        "reregister-shortcuts",
+        t("tray.reregister_shortcuts"),
+        true,
+        None::<&str>,
+    )?;
+    let settings = MenuItem::with_id(app, "settings", t("tray.settings"), true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", t("tray.quit"), true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &capture,
+            &quick_capture,
+            &quick_ocr,
+            &recent,
+            &pause_shortcuts,
+            &pause_hour,
+            &reregister,
+            &settings,
+            &quit,
+        ],
+    )?;
+
+    // 定义了动作预设时，在菜单中显示当前生效的流程
+    let pipeline = if current.pipeline_presets.is_empty() {
+        None
+    } else {
+        let label = pipeline_label(&current);
+        let pipeline_item = MenuItem::with_id(app, "pipeline", label, false, None::<&str>)?;
+        menu.insert(&pipeline_item, 3)?;
+        Some(pipeline_item)
+    };
+
+    let tray_menu = TrayMenu {
+        menu,
+        capture,
+        quick_capture,
+        quick_ocr,
+        pipeline,
+        recent,
+        pause_shortcuts,
+        pause_hour,
+        reregister,
+        settings,
+        quit,
+        degraded: Mutex::new(Vec::new()),
+    };
+    fill_recent(app, &tray_menu.recent)?;
+    Ok(tray_menu)
+}
+
+/// 创建托盘图标和菜单，启动时调用一次
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let tray_menu = build_menu(app)?;
+
+    // 图标数据需复制一份长期持有；没有窗口图标时使用打包的 32x32 图标
+    let normal_icon = app
+        .default_window_icon()
+        .map(|icon| icon.clone().to_owned())
+        .or_else(|| load_icon(include_bytes!("../icons/32x32.png")));
+    let mut builder = TrayIconBuilder::new();
+    if let Some(icon) = &normal_icon {
+        builder = builder.icon(icon.clone());
+    }
+    let tray = builder
+        .menu(&tray_menu.menu)
+        .show_menu_on_left_click(true)
+        .tooltip(t("tray.tooltip"))
+        .on_tray_icon_event(|tray, event| handle_icon_event(tray.app_handle(), event))
+        .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()))
+        .build(app)?;
+    app.manage(TrayStatus::new(tray, normal_icon));
+    app.manage(tray_menu);
+    Ok(())
+}
+
+/// 语言切换后更新菜单与提示文字
+pub fn apply_locale(app: &AppHandle) {
+    if let Some(tray_menu) = app.try_state::<TrayMenu>() {
+        let _ = tray_menu.capture.set_text(t("tray.capture"));
+        let _ = tray_menu.quick_capture.set_text(t("tray.quick_capture"));
+        let _ = tray_menu.quick_ocr.set_text(t("tray.quick_ocr"));
+        let _ = tray_menu.recent.set_text(t("tray.recent"));
+        let _ = tray_menu
+            .pause_shortcuts
+            .set_text(t("tray.pause_shortcuts"));
+        let _ = tray_menu
+            .pause_hour
+            .set_text(t("tray.pause_shortcuts_hour"));
+        let _ = tray_menu
+            .reregister
+            .set_text(t("tray.reregister_shortcuts"));
+        let _ = tray_menu.settings.set_text(t("tray.settings"));
+        let _ = tray_menu.quit.set_text(t("tray.quit"));
+        if let Ok(items) = tray_menu.degraded.lock() {
+            for (item, key) in items.iter() {
+                let _ = item.set_text(t(key));
+            }
+        }
+        if let Some(item) = &tray_menu.pipeline {
+            let settings = app.state::<SettingsState>().get();
+            let _ = item.set_text(pipeline_label(&settings));
+        }
+        let _ = fill_recent(app, &tray_menu.recent);
+    }
+    if let Some(status) = app.try_state::<TrayStatus>() {
+        let _ = status.icon.set_tooltip(Some(t("tray.tooltip")));
+    }
+}
+
+/// 切换简化模式：界面无法加载时停用依赖前端的截图和设置，在快速截图之后加入不经过前端的操作
+pub fn set_degraded(app: &AppHandle, degraded: bool) {
+    let Some(tray_menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let Ok(mut items) = tray_menu.degraded.lock() else {
+        return;
+    };
+    let _ = tray_menu.capture.set_enabled(!degraded);
+    let _ = tray_menu.settings.set_enabled(!degraded);
+    for (item, _) in items.drain(..) {
+        let _ = tray_menu.menu.remove(&item);
+    }
+    if !degraded {
+        return;
+    }
+    // 插在“截全屏并识别文字”之后
+    let position = 3 + usize::from(tray_menu.pipeline.is_some());
+    for (i, (id, key)) in DEGRADED_ITEMS.iter().enumerate() {
+        match MenuItem::with_id(app, *id, t(key), true, None::<&str>) {
+            Ok(item) => {
+                let _ = tray_menu.menu.insert(&item, position + i);
+                items.push((item, *key));
+            }
+            Err(err) => tracing::warn!(error = %err, "添加简化模式菜单项失败"),
+        }
+    }
+}
+
+fn fill_recent(app: &AppHandle, recent: &Submenu<Wry>) -> tauri::Result<()> {
+    while recent.remove_at(0)?.is_some() {}
+
+    let mut entries = app.state::<HistoryState>().entries();
+    entries.reverse();
+    entries.truncate(RECENT_LIMIT);
+
+    if entries.is_empty() {
+        let placeholder = MenuItem::with_id(
+            app,
+            "recent-empty",
+            t("tray.recent_empty"),
+            false,
+            None::<&str>,
+        )?;
+        return recent.append(&placeholder);
+    }
+
+    for entry in entries {
+        let label = Local
+            .timestamp_opt(entry.timestamp, 0)
+            .single()
+            .map(|t| t.format("%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| entry.id.to_string());
+        let copy = MenuItem::with_id(
+            app,
+            format!("recent-copy:{}", entry.id),
+            t("tray.recent_copy"),
+            true,
+            None::<&str>,
+        )?;
+        let reveal = MenuItem::with_id(
+            app,
+            format!("recent-reveal:{}", entry.id),
+            t("tray.recent_reveal"),
+            true,
+            None::<&str>,
+        )?;
+        let item = Submenu::with_items(app, label, true, &[&copy, &reveal])?;
+        recent.append(&item)?;
+    }
+    Ok(())
+}
+
+/// 历史记录变化后调用，重建“最近截图”子菜单
+pub fn refresh_recent(app: &AppHandle) {
+    if let Some(tray_menu) = app.try_state::<TrayMenu>() {
+        let _ = fill_recent(app, &tray_menu.recent);
+    }
+}
+
+/// 快捷键暂停状态变化后同步勾选状态
+pub fn refresh_shortcuts_paused(app: &AppHandle, paused: bool) {
+    if let Some(tray_menu) = app.try_state::<TrayMenu>() {
+        let _ = tray_menu.pause_shortcuts.set_checked(paused);
+    }
+}
+
+fn copy_recent(app: &AppHandle, id: u64) -> Result<(), String> {
+    let entry = app
+        .state::<HistoryState>()
+        .get(id)
+        .ok_or_else(|| format!("历史记录不存在: {id}"))?;
+    let img = image::open(&entry.image_path).map_err(|e| e.to_string())?;
+    crate::clipboard::set_image(&img).map_err(String::from)
+}
+
+fn reveal_recent(app: &AppHandle, id: u64) -> Result<(), String> {
+    let entry = app
+        .state::<HistoryState>()
+        .get(id)
+        .ok_or_else(|| format!("历史记录不存在: {id}"))?;
+    crate::save::reveal_path(app, std::path::Path::new(&entry.image_path)).map(|_| ())
+}
+
+/// 主窗口关闭时隐藏到托盘；首次隐藏时提示程序仍在运行
+pub fn hide_to_tray(app: &AppHandle, window: &tauri::Window) -> bool {
+    let settings = app.state::<SettingsState>().get();
+    if !settings.close_to_tray {
+        return false;
+    }
+    let _ = window.hide();
+
+    if !settings.tray_hint_shown {
+        let _ = app
+            .state::<SettingsState>()
+            .update(|s| s.tray_hint_shown = true);
+        crate::notify::notify(
+            app,
+            crate::notify::Notice::new(
+                crate::notify::NotifyEvent::Hint,
+                t("hint.tray_title"),
+                t("hint.tray_body"),
+            ),
+        );
+    }
+    true
+}
+
+/// 点击托盘图标时执行的动作
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayAction {
+    Capture,
+    ShowWindow,
+    OpenSettings,
+    /// 按上次的选区重新截图
+    RepeatRegion,
+    Nothing,
+}
+
+/// 托盘图标各种点击方式对应的动作
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrayClickSettings {
+    pub left: TrayAction,
+    pub double_click: TrayAction,
+    pub middle: TrayAction,
+}
+
+impl Default for TrayClickSettings {
+    fn default() -> Self {
+        Self {
+            left: TrayAction::Capture,
+            double_click: TrayAction::OpenSettings,
+            middle: TrayAction::RepeatRegion,
+        }
+    }
+}
+
+fn show_main(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// 执行托盘动作，source 区分图标点击与菜单
+pub fn run_action(app: &AppHandle, action: TrayAction, source: TriggerSource) {
+    match action {
+        // 界面无法加载时显示诊断信息，截图由 CaptureTrigger 改为快速截图
+        TrayAction::ShowWindow | TrayAction::OpenSettings if crate::degraded::active() => {
+            crate::diagnostics::spawn_notify(app)
+        }
+        TrayAction::Capture => CaptureTrigger::new(source, "capture").send(app),
+        TrayAction::RepeatRegion => CaptureTrigger::new(source, "repeat_region").send(app),
+        TrayAction::ShowWindow => show_main(app),
+        TrayAction::OpenSettings => {
+            if let Err(err) = crate::settings::open_settings_window(app.clone()) {
+                tracing::warn!(error = %err, "打开设置窗口失败");
+            }
+        }
+        TrayAction::Nothing => {}
+    }
+}
+
+/// 按设置中的映射处理托盘图标点击；双击前系统还会先发送一次单击。录屏时左键单击停止录制
+pub fn handle_icon_event(app: &AppHandle, event: TrayIconEvent) {
+    if crate::recording::is_recording() {
+        if let TrayIconEvent::Click {
+            button: MouseButton::Left,
+            button_state: MouseButtonState::Up,
+            ..
+        } = event
+        {
+            crate::recording::stop_from_tray(app);
+            return;
+        }
+    }
+    let clicks = app.state::<SettingsState>().get().tray_click;
+    let action = match event {
+        TrayIconEvent::Click {
+            button: MouseButton::Left,
+            button_state: MouseButtonState::Up,
+            ..
+        } => clicks.left,
+        TrayIconEvent::Click {
+            button: MouseButton::Middle,
+            button_state: MouseButtonState::Up,
+            ..
+        } => clicks.middle,
+        TrayIconEvent::DoubleClick {
+            button: MouseButton::Left,
+            ..
+        } => clicks.double_click,
+        _ => return,
+    };
+    run_action(app, action, TriggerSource::Tray);
+}
+
+#[tauri::command]
+pub fn get_tray_click(state: tauri::State<'_, SettingsState>) -> TrayClickSettings {
+    state.get().tray_click
+}
+
+/// 修改某种点击方式（left / double_click / middle）对应的动作
+#[tauri::command]
+pub fn set_tray_click(
+    app: AppHandle,
+    button: String,
+    action: TrayAction,
+) -> Result<TrayClickSettings, String> {
+    let settings = app
+        .state::<SettingsState>()
+        .update(|s| match button.as_str() {
+            "left" => s.tray_click.left = action,
+            "double_click" => s.tray_click.double_click = action,
+            "middle" => s.tray_click.middle = action,
+            _ => {}
+        })?;
+    Ok(settings.tray_click)
+}
+
+/// 真正退出程序，托盘“退出”与 really_quit 命令共用
+pub fn quit(app: &AppHandle) {
+    app.exit(0);
+}
+
+#[tauri::command]
+pub fn really_quit(app: AppHandle) {
+    quit(&app);
+}
+
+pub fn handle_menu_event(app: &AppHandle, id: &str) {
+    if let Some(entry_id) = id.strip_prefix("recent-copy:") {
+        if let Ok(entry_id) = entry_id.parse() {
+            let _ = copy_recent(app, entry_id);
+        }
+        return;
+    }
+    if let Some(entry_id) = id.strip_prefix("recent-reveal:") {
+        if let Ok(entry_id) = entry_id.parse() {
+            let _ = reveal_recent(app, entry_id);
+        }
+        return;
+    }
+
+    match id {
+        "pause-shortcuts" => {
+            // 勾选项点击后已自动切换，以设置中的状态为准
+            let paused = app.state::<SettingsState>().get().shortcuts_paused;
+            if let Err(err) = crate::shortcuts::set_enabled(app, paused, None) {
+                tracing::warn!(error = %err, "切换快捷键失败");
+                refresh_shortcuts_paused(app, paused);
+            }
+        }
+        "pause-shortcuts-hour" => {
+            if let Err(err) = crate::shortcuts::set_enabled(app, false, Some(PAUSE_MINUTES)) {
+                tracing::warn!(error = %err, "暂停快捷键失败");
+            }
+        }
+        "reregister-shortcuts" => {
+            if let Err(err) = crate::shortcuts::reregister(app) {
+                tracing::warn!(error = %err, "重新注册快捷键失败");
+                crate::notify::notify(
+                    app,
+                    crate::notify::Notice::new(
+                        crate::notify::NotifyEvent::Error,
+                        t("error.reregister_failed"),
+                        err,
+                    ),
+                );
+            }
+        }
+        "quit" => quit(app),
+        "settings" => run_action(app, TrayAction::OpenSettings, TriggerSource::Menu),
+        "capture" => run_action(app, TrayAction::Capture, TriggerSource::Menu),
+        "quick-capture" => crate::quick::spawn(app, None),
+        "quick-ocr" => crate::quick::spawn_ocr(app, None),
+        "capture-to-file" => crate::quick::spawn_to_file(app),
+        "read-at-cursor" => crate::quick::spawn_read_at_cursor(app),
+        "show-diagnostics" => crate::diagnostics::spawn_notify(app),
+        _ => {}
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::capture_id::{self, CaptureId};
+use crate::session::{self, RetriggerPolicy};
+use crate::{active_app, commands, degraded, overlay, quick, settings};
+
+/// 触发截图的来源
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerSource {
+    Shortcut,
+    Tray,
+    Menu,
+    Launch,
+    Dbus,
+}
+
+/// start-capture 事件的内容，前端据此区分截图方式
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptureTrigger {
+    pub source: TriggerSource,
+    /// capture / ocr_capture / repeat_region
+    pub action: String,
+    /// 由快捷键触发时的快捷键
+    pub shortcut: Option<String>,
+    /// 本次截图的编号，前端调用截图命令时传回
+    pub capture_id: CaptureId,
+}
+
+impl CaptureTrigger {
+    pub fn new(source: TriggerSource, action: &str) -> Self {
+        Self {
+            source,
+            action: action.to_string(),
+            shortcut: None,
+            capture_id: CaptureId::generate(),
+        }
+    }
+
+    /// 发给主窗口开始截图；截图进行中时按设置忽略或取消后重新开始
+    pub fn send(mut self, app: &AppHandle) {
+        // 快捷键注册时生成的触发会被反复使用，每次触发都是新的截图
+        self.capture_id = CaptureId::generate();
+        // 界面无法加载时改为不经过前端的快速截图
+        if degraded::active() {
+            tracing::info!(source = ?self.source, action = %self.action, "简化模式，改为截全屏到剪贴板");
+            quick::spawn(app, None);
+            return;
+        }
+        let Some(window) = app.get_webview_window("main") else {
+            return;
+        };
+        if capture_in_progress(app) {
+            // 遮罩还没显示时正在截图，重新开始会和当前截图互相干扰
+            if retrigger_policy(app) == RetriggerPolicy::Ignore || !capture_ui_visible(app) {
+                tracing::info!(source = ?self.source, action = %self.action, "截图进行中，忽略新的触发");
+                let _ = app.emit("capture-busy", &self);
+                return;
+            }
+            tracing::info!(source = ?self.source, action = %self.action, "截图进行中，取消后重新开始");
+            if let Err(err) = commands::cancel_capture(app.clone(), window.clone()) {
+                tracing::warn!(error = %err, "取消当前截图失败");
+            }
+        }
+        session::mark_active();
+        active_app::remember(&self.capture_id);
+        capture_id::set_pending(self.capture_id.clone());
+        let _ = window.emit("start-capture", self);
+    }
+}
+
+/// 触发后等待遮罩显示的最长时间，超过后仍没有截图界面视为上次截图已异常结束
+const CAPTURE_START_GRACE: Duration = Duration::from_secs(15);
+
+pub fn retrigger_policy(app: &AppHandle) -> RetriggerPolicy {
+    app.state::<settings::SettingsState>()
+        .get()
+        .capture_retrigger
+}
+
+pub fn main_window_visible(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|w| w.is_visible().ok())
+        .unwrap_or(false)
+}
+
+/// 截图界面（主窗口或遮罩窗口）是否正在显示
+fn capture_ui_visible(app: &AppHandle) -> bool {
+    overlay::has_overlays(app, None) || main_window_visible(app)
+}
+
+/// 是否有进行中的截图；界面已不在但没有正常结束的会话在此清除
+pub fn capture_in_progress(app: &AppHandle) -> bool {
+    let Some(since) = session::active_since() else {
+        return false;
+    };
+    if since.elapsed() < CAPTURE_START_GRACE || capture_ui_visible(app) {
+        return true;
+    }
+    session::end();
+    false
+}
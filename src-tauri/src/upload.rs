@@ -0,0 +1,408 @@
+use arboard::Clipboard;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use image::DynamicImage;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error as _;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+use crate::error_report::{self, ErrorSource, Failure};
+use crate::redact;
+use crate::save::{self, ExportFormat};
+use crate::settings::SettingsState;
+use crate::webhook::{self, WebhookEvent};
+
+/// 上传请求体的组织方式
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadKind {
+    /// multipart/form-data，图片放在 field_name 字段
+    #[default]
+    Multipart,
+    /// 直接以请求体发送图片（S3 预签名 PUT 等）
+    Raw,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadProfile {
+    pub name: String,
+    pub url: String,
+    pub method: String,
+    pub kind: UploadKind,
+    pub field_name: String,
+    pub headers: HashMap<String, String>,
+    /// 从 JSON 响应中提取链接，如 `$.data.url`
+    pub json_path: Option<String>,
+    /// 从响应文本中提取链接的正则，有捕获组时取第一个
+    pub url_regex: Option<String>,
+    pub timeout_secs: u64,
+    /// 上传前缩小到长边不超过该值并转为 JPEG，为空时上传原图 PNG
+    pub jpeg_max_side: Option<u32>,
+}
+
+impl Default for UploadProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: String::new(),
+            method: "POST".into(),
+            kind: UploadKind::Multipart,
+            field_name: "file".into(),
+            headers: HashMap::new(),
+            json_path: None,
+            url_regex: None,
+            timeout_secs: 60,
+            jpeg_max_side: None,
+        }
+    }
+}
+
+/// 内置的 S3 预签名 PUT 配置，URL 由调用方每次传入
+pub const S3_PRESIGNED_PROFILE: &str = "s3-presigned";
+
+fn builtin_profile(name: &str) -> Option<UploadProfile> {
+    (name == S3_PRESIGNED_PROFILE).then(|| UploadProfile {
+        name: S3_PRESIGNED_PROFILE.into(),
+        method: "PUT".into(),
+        kind: UploadKind::Raw,
+        headers: HashMap::from([("Content-Type".into(), "image/png".into())]),
+        ..Default::default()
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UploadError {
+    InvalidInput { detail: String },
+    ProfileNotFound { name: String },
+    Timeout { secs: u64 },
+    Tls { detail: String },
+    Network { detail: String },
+    Http { status: u16, body: String },
+    UrlNotFound { body: String },
+    RedactFailed { detail: String },
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadError::InvalidInput { detail } => write!(f, "参数错误: {detail}"),
+            UploadError::ProfileNotFound { name } => write!(f, "未找到上传配置: {name}"),
+            UploadError::Timeout { secs } => write!(f, "上传超时（超过 {secs} 秒）"),
+            UploadError::Tls { detail } => write!(f, "TLS 错误: {detail}"),
+            UploadError::Network { detail } => write!(f, "网络错误: {detail}"),
+            UploadError::Http { status, .. } => write!(f, "服务器返回 {status}"),
+            UploadError::UrlNotFound { .. } => write!(f, "无法从响应中提取图片链接"),
+            UploadError::RedactFailed { detail } => write!(f, "自动打码失败，未上传: {detail}"),
+        }
+    }
+}
+
+impl UploadError {
+    /// 与序列化的 kind 相同
+    pub fn code(&self) -> &'static str {
+        match self {
+            UploadError::InvalidInput { .. } => "invalid_input",
+            UploadError::ProfileNotFound { .. } => "profile_not_found",
+            UploadError::Timeout { .. } => "timeout",
+            UploadError::Tls { .. } => "tls",
+            UploadError::Network { .. } => "network",
+            UploadError::Http { .. } => "http",
+            UploadError::UrlNotFound { .. } => "url_not_found",
+            UploadError::RedactFailed { .. } => "redact_failed",
+        }
+    }
+}
+
+fn map_request_error(err: reqwest::Error, timeout_secs: u64) -> UploadError {
+    if err.is_timeout() {
+        return UploadError::Timeout { secs: timeout_secs };
+    }
+
+    // reqwest 不单独区分 TLS 错误，沿错误链查找证书/握手相关信息
+    let mut detail = err.to_string();
+    let mut source = err.source();
+    while let Some(inner) = source {
+        detail = format!("{detail}: {inner}");
+        source = inner.source();
+    }
+    let lower = detail.to_lowercase();
+    if lower.contains("certificate") || lower.contains("tls") || lower.contains("handshake") {
+        UploadError::Tls { detail }
+    } else {
+        UploadError::Network { detail }
+    }
+}
+
+/// 简单 JSONPath：支持 `$.a.b[0].c` 形式
+fn extract_json_path(value: &serde_json::Value, path: &str) -> Option<String> {
+    let mut current = value;
+    let path = path.trim().trim_start_matches('$');
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, indexes) = match segment.find('[') {
+            Some(pos) => (&segment[..pos], &segment[pos..]),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            let i: usize = index.trim_end_matches(']').parse().ok()?;
+            current = current.get(i)?;
+        }
+    }
+    match current {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn extract_url(profile: &UploadProfile, request_url: &str, body: &str) -> Option<String> {
+    if let Some(path) = &profile.json_path {
+        let value: serde_json::Value = serde_json::from_str(body).ok()?;
+        return extract_json_path(&value, path);
+    }
+    if let Some(pattern) = &profile.url_regex {
+        let re = Regex::new(pattern).ok()?;
+        let caps = re.captures(body)?;
+        return caps
+            .get(1)
+            .or_else(|| caps.get(0))
+            .map(|m| m.as_str().to_string());
+    }
+    if profile.kind == UploadKind::Raw {
+        // 预签名 URL 去掉查询参数即为对象地址
+        return Some(
+            request_url
+                .split('?')
+                .next()
+                .unwrap_or(request_url)
+                .to_string(),
+        );
+    }
+    let trimmed = body.trim();
+    trimmed.starts_with("http").then(|| trimmed.to_string())
+}
+
+#[derive(Clone, Serialize)]
+struct UploadProgress {
+    profile: String,
+    sent: u64,
+    total: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct UploadResult {
+    pub profile: String,
+    pub url: String,
+    /// 上传前自动打码的区域数，未开启自动打码时不出现
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redacted: Option<usize>,
+}
+
+const PROGRESS_CHUNK: usize = 64 * 1024;
+
+/// 分块包装请求体，每发送一块发出一次 upload-progress 事件
+fn progress_body(app: &AppHandle, profile: &str, data: Vec<u8>) -> reqwest::Body {
+    let total = data.len() as u64;
+    let data = Bytes::from(data);
+    let app = app.clone();
+    let profile = profile.to_string();
+    let chunks: Vec<Bytes> = (0..data.len())
+        .step_by(PROGRESS_CHUNK)
+        .map(|start| data.slice(start..(start + PROGRESS_CHUNK).min(data.len())))
+        .collect();
+
+    let mut sent = 0u64;
+    let stream = futures_util::stream::iter(chunks.into_iter().map(move |chunk| {
+        sent += chunk.len() as u64;
+        let _ = app.emit(
+            "upload-progress",
+            UploadProgress {
+                profile: profile.clone(),
+                sent,
+                total,
+            },
+        );
+        Ok::<_, std::io::Error>(chunk)
+    }));
+    reqwest::Body::wrap_stream(stream)
+}
+
+/// 按配置的 jpeg_max_side 缩小并编码为 JPEG
+pub fn encode_jpeg(image: &DynamicImage, max_side: u32) -> Result<Vec<u8>, String> {
+    let max_side = max_side.max(1);
+    if image.width().max(image.height()) > max_side {
+        save::encode_image(
+            &image.thumbnail(max_side, max_side),
+            ExportFormat::Jpeg,
+            save::DEFAULT_QUALITY,
+        )
+    } else {
+        save::encode_image(image, ExportFormat::Jpeg, save::DEFAULT_QUALITY)
+    }
+}
+
+/// 上传已编码的图片，format 决定文件名后缀和 MIME 类型
+pub async fn upload_encoded(
+    app: &AppHandle,
+    data: Vec<u8>,
+    format: ExportFormat,
+    profile: &UploadProfile,
+    url_override: Option<String>,
+) -> Result<UploadResult, UploadError> {
+    let url = url_override.unwrap_or_else(|| profile.url.clone());
+    if url.is_empty() {
+        return Err(UploadError::InvalidInput {
+            detail: "上传地址为空".into(),
+        });
+    }
+    let method =
+        reqwest::Method::from_bytes(profile.method.to_uppercase().as_bytes()).map_err(|e| {
+            UploadError::InvalidInput {
+                detail: e.to_string(),
+            }
+        })?;
+
+    let len = data.len() as u64;
+    let body = progress_body(app, &profile.name, data);
+    let mut request = crate::http::client(app)
+        .request(method, &url)
+        .timeout(Duration::from_secs(profile.timeout_secs));
+    for (key, value) in &profile.headers {
+        request = request.header(key, value);
+    }
+    request = match profile.kind {
+        UploadKind::Raw => request.header("Content-Length", len).body(body),
+        UploadKind::Multipart => {
+            let part = reqwest::multipart::Part::stream_with_length(body, len)
+                .file_name(format!(
+                    "prinsp_{}.{}",
+                    chrono::Local::now().timestamp(),
+                    format.extension()
+                ))
+                .mime_str(format.mime_type())
+                .map_err(|e| UploadError::InvalidInput {
+                    detail: e.to_string(),
+                })?;
+            let form = reqwest::multipart::Form::new().part(profile.field_name.clone(), part);
+            request.multipart(form)
+        }
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| map_request_error(e, profile.timeout_secs))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| map_request_error(e, profile.timeout_secs))?;
+
+    if !status.is_success() {
+        return Err(UploadError::Http {
+            status: status.as_u16(),
+            body: text.chars().take(500).collect(),
+        });
+    }
+
+    let link = extract_url(profile, &url, &text).ok_or_else(|| UploadError::UrlNotFound {
+        body: text.chars().take(500).collect(),
+    })?;
+
+    Ok(UploadResult {
+        profile: profile.name.clone(),
+        url: link,
+        redacted: None,
+    })
+}
+
+pub fn find_profile(app: &AppHandle, name: &str) -> Result<UploadProfile, UploadError> {
+    app.state::<SettingsState>()
+        .get()
+        .upload_profiles
+        .into_iter()
+        .find(|p| p.name == name)
+        .or_else(|| builtin_profile(name))
+        .ok_or_else(|| UploadError::ProfileNotFound { name: name.into() })
+}
+
+/// 上传截图，成功后复制链接到剪贴板并发出 upload-complete 事件
+#[tauri::command]
+pub async fn upload_image(
+    app: AppHandle,
+    base64_data: String,
+    profile: String,
+    url: Option<String>,
+) -> Result<UploadResult, UploadError> {
+    let png = STANDARD
+        .decode(&base64_data)
+        .map_err(|e| UploadError::InvalidInput {
+            detail: e.to_string(),
+        })?;
+    let profile = find_profile(&app, &profile)?;
+    let _busy = crate::tray::busy(&app);
+    let (png, redacted) = if app.state::<SettingsState>().get().redact.auto_redact {
+        let (png, regions) = crate::run_blocking({
+            let app = app.clone();
+            move || redact::auto_redact_png(&app, &png, None)
+        })
+        .await
+        .map_err(|e| UploadError::RedactFailed {
+            detail: e.message(),
+        })?;
+        (png, Some(regions.len()))
+    } else {
+        (png, None)
+    };
+    let (data, format) = match profile.jpeg_max_side {
+        Some(max_side) => {
+            let jpeg = crate::run_blocking(move || {
+                let image = image::load_from_memory(&png).map_err(AppError::invalid)?;
+                encode_jpeg(&image, max_side).map_err(AppError::Internal)
+            })
+            .await
+            .map_err(|e| UploadError::InvalidInput {
+                detail: e.message(),
+            })?;
+            (jpeg, ExportFormat::Jpeg)
+        }
+        None => (png, ExportFormat::Png),
+    };
+    let mut result = upload_encoded(&app, data.clone(), format, &profile, url)
+        .await
+        .inspect_err(|err| {
+            // 错误由前端显示，这里只更新托盘并记录
+            error_report::report(
+                &app,
+                Failure {
+                    notify: false,
+                    ..Failure::new(ErrorSource::Upload, err.code(), "上传失败", err.to_string())
+                },
+            )
+        })?;
+    result.redacted = redacted;
+    crate::tray::record(&app, "tray.last_upload", "result.link_copied", true);
+
+    if let Ok(mut clipboard) = Clipboard::new() {
+        let _ = clipboard.set_text(result.url.clone());
+    }
+    let _ = app.emit("upload-complete", &result);
+    webhook::fire(
+        &app,
+        WebhookEvent::UploadComplete,
+        webhook::Payload {
+            url: Some(result.url.clone()),
+            image: Some((&data, format)),
+            ..Default::default()
+        },
+    );
+    Ok(result)
+}
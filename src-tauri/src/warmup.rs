@@ -0,0 +1,116 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::ocr::OcrOptions;
+use crate::ocr_queue::{self, JobInput, OcrQueue};
+
+/// 50×20 的 “OCR” 字样
+const SAMPLE: &[u8] = include_bytes!("../samples/warmup.png");
+/// 启动后等待这么久再预热，不与窗口和托盘的初始化争抢
+const DELAY: Duration = Duration::from_secs(3);
+
+static REPORT: Mutex<Option<WarmupReport>> = Mutex::new(None);
+
+/// 启动预热的结果，诊断信息和使用统计中显示
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum WarmupReport {
+    Done {
+        lang: String,
+        duration_ms: u64,
+    },
+    /// 未安装 tesseract 或语言包
+    Skipped {
+        reason: String,
+    },
+    Failed {
+        lang: String,
+        error: String,
+    },
+}
+
+pub fn report() -> Option<WarmupReport> {
+    REPORT.lock().ok().and_then(|report| report.clone())
+}
+
+/// 预热成功时的耗时
+pub fn duration_ms() -> Option<u64> {
+    match report()? {
+        WarmupReport::Done { duration_ms, .. } => Some(duration_ms),
+        _ => None,
+    }
+}
+
+fn set_report(report: WarmupReport) {
+    if let Ok(mut current) = REPORT.lock() {
+        *current = Some(report);
+    }
+}
+
+/// 缺少的语言包，chi_sim+eng 这样的组合逐个检查
+fn missing_language(lang: &str) -> Option<String> {
+    let installed = crate::tesseract::languages();
+    lang.split('+')
+        .find(|part| !installed.iter().any(|l| l == part))
+        .map(str::to_string)
+}
+
+fn run(app: &AppHandle) {
+    let lang = app.state::<crate::settings::SettingsState>().get().ocr_lang;
+    if crate::tesseract::detected().is_none() {
+        tracing::info!("未安装 tesseract，跳过识别预热");
+        set_report(WarmupReport::Skipped {
+            reason: "未安装 tesseract".into(),
+        });
+        return;
+    }
+    if let Some(missing) = missing_language(&lang) {
+        tracing::info!(lang = %missing, "缺少语言包，跳过识别预热");
+        set_report(WarmupReport::Skipped {
+            reason: format!("缺少语言包 {missing}"),
+        });
+        return;
+    }
+
+    let rx = app
+        .state::<OcrQueue>()
+        .submit_uncounted(JobInput::Png(SAMPLE.to_vec()), OcrOptions::new(&lang));
+    let report = match ocr_queue::wait_result(&rx) {
+        Ok(recognized) => {
+            // 不含在队列中等待用户任务的时间
+            let duration_ms = recognized
+                .timings
+                .stages
+                .iter()
+                .filter(|stage| stage.name != "queue_wait")
+                .map(|stage| stage.ms)
+                .sum::<f64>() as u64;
+            tracing::info!(lang = %lang, duration_ms, "识别预热完成");
+            WarmupReport::Done { lang, duration_ms }
+        }
+        Err(err) => {
+            tracing::warn!(lang = %lang, error = %err, "识别预热失败");
+            WarmupReport::Failed {
+                lang,
+                error: err.message(),
+            }
+        }
+    };
+    set_report(report);
+}
+
+/// 启动后在后台识别一张内置的小图，让 tesseract 和语言包提前载入磁盘缓存，
+/// 以最低优先级排队，用户的识别任务总是先执行
+pub fn start(app: &AppHandle, enabled: bool) {
+    if !enabled {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(DELAY);
+        run(&app);
+    });
+}
@@ -0,0 +1,71 @@
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::i18n::t;
+use crate::notify::{Notice, NotifyEvent};
+
+/// 唤醒后等待桌面环境恢复再注册
+const RESUME_DELAY: Duration = Duration::from_secs(2);
+
+/// 休眠唤醒、GNOME Shell 重启后重新注册全局快捷键
+pub fn start(app: &AppHandle) {
+    let handle = app.clone();
+    thread::spawn(move || {
+        if let Err(err) = watch_sleep(&handle) {
+            tracing::warn!(error = %err, "无法监听休眠信号");
+        }
+    });
+    let handle = app.clone();
+    thread::spawn(move || {
+        if let Err(err) = watch_shell(&handle) {
+            tracing::warn!(error = %err, "无法监听 GNOME Shell");
+        }
+    });
+}
+
+/// logind 的 PrepareForSleep 信号：true 为即将休眠，false 为已唤醒
+fn watch_sleep(app: &AppHandle) -> zbus::Result<()> {
+    let connection = zbus::blocking::Connection::system()?;
+    let proxy = zbus::blocking::Proxy::new(
+        &connection,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    )?;
+    for message in proxy.receive_signal("PrepareForSleep")? {
+        if let Ok(false) = message.body().deserialize::<bool>() {
+            reregister_after(app, "resume");
+        }
+    }
+    Ok(())
+}
+
+/// GNOME Shell 重启后会重新获得总线名称
+fn watch_shell(app: &AppHandle) -> zbus::Result<()> {
+    let connection = zbus::blocking::Connection::session()?;
+    let proxy = zbus::blocking::Proxy::new(
+        &connection,
+        "org.gnome.Shell",
+        "/org/gnome/Shell",
+        "org.gnome.Shell",
+    )?;
+    for owner in proxy.receive_owner_changed()? {
+        if owner.is_some() {
+            reregister_after(app, "shell-restart");
+        }
+    }
+    Ok(())
+}
+
+fn reregister_after(app: &AppHandle, reason: &str) {
+    thread::sleep(RESUME_DELAY);
+    tracing::info!(reason, "重新注册快捷键");
+    if let Err(err) = crate::shortcuts::reregister(app) {
+        tracing::error!(error = %err, "重新注册快捷键失败");
+        crate::notify::notify(
+            app,
+            Notice::new(NotifyEvent::Error, t("error.reregister_failed"), err),
+        );
+    }
+}
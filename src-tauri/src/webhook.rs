@@ -0,0 +1,354 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::save::ExportFormat;
+use crate::settings::SettingsState;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// 首次失败后重试两次，分别等待 2 秒、8 秒
+const RETRY_DELAYS: [Duration; 2] = [Duration::from_secs(2), Duration::from_secs(8)];
+/// 错误信息中保留的响应正文长度
+const MAX_ERROR_BODY: usize = 500;
+/// 请求体签名所在的请求头，值为 sha256=<十六进制 HMAC>
+pub const SIGNATURE_HEADER: &str = "X-Prinsp-Signature";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// 截图已保存到文件
+    CaptureSaved,
+    /// 文字识别完成
+    OcrComplete,
+    /// 图床上传完成
+    UploadComplete,
+}
+
+impl WebhookEvent {
+    fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::CaptureSaved => "capture_saved",
+            WebhookEvent::OcrComplete => "ocr_complete",
+            WebhookEvent::UploadComplete => "upload_complete",
+        }
+    }
+}
+
+/// 截图、识别或上传完成后通知的外部地址
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Webhook {
+    /// 唯一名称，test_webhook 按此查找
+    pub id: String,
+    pub url: String,
+    /// 订阅的事件，为空时订阅全部
+    pub events: Vec<WebhookEvent>,
+    /// 设置后用 HMAC-SHA256 签名请求体
+    pub secret: Option<String>,
+    /// 附带 base64 编码的图片（识别事件没有图片）
+    pub include_image: bool,
+}
+
+// 手写 Debug，避免签名密钥出现在日志里
+impl std::fmt::Debug for Webhook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Webhook")
+            .field("id", &self.id)
+            .field("url", &self.url)
+            .field("events", &self.events)
+            .field("secret", &self.secret.as_ref().map(|_| "***"))
+            .field("include_image", &self.include_image)
+            .finish()
+    }
+}
+
+impl Webhook {
+    fn subscribes(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+
+    fn secret(&self) -> Option<&str> {
+        self.secret.as_deref().filter(|s| !s.is_empty())
+    }
+}
+
+pub fn validate(webhooks: &[Webhook]) -> Result<(), String> {
+    for (i, hook) in webhooks.iter().enumerate() {
+        if hook.id.trim().is_empty() {
+            return Err("Webhook 名称不能为空".into());
+        }
+        if webhooks[..i].iter().any(|h| h.id == hook.id) {
+            return Err(format!("Webhook 名称重复: {}", hook.id));
+        }
+        let url = reqwest::Url::parse(hook.url.trim())
+            .map_err(|e| format!("Webhook {} 的地址无效: {e}", hook.id))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Webhook {} 只支持 http/https 地址", hook.id));
+        }
+    }
+    Ok(())
+}
+
+/// 单次发送的失败原因，决定是否重试；命令返回时转换为 AppError
+#[derive(Debug)]
+enum WebhookError {
+    Timeout { secs: u64 },
+    Network { detail: String },
+    Http { status: u16, body: String },
+}
+
+impl std::fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookError::Timeout { secs } => write!(f, "请求超时（超过 {secs} 秒）"),
+            WebhookError::Network { detail } => write!(f, "网络错误: {detail}"),
+            WebhookError::Http { status, .. } => write!(f, "服务器返回 {status}"),
+        }
+    }
+}
+
+impl WebhookError {
+    /// 网络错误、超时、限流和服务端错误可以重试，其余 4xx 重试也不会成功
+    fn retryable(&self) -> bool {
+        match self {
+            WebhookError::Http { status, .. } => *status == 429 || *status >= 500,
+            WebhookError::Timeout { .. } | WebhookError::Network { .. } => true,
+        }
+    }
+}
+
+impl From<WebhookError> for AppError {
+    fn from(err: WebhookError) -> Self {
+        match err {
+            WebhookError::Timeout { secs } => AppError::Timeout {
+                what: "webhook".into(),
+                ms: secs * 1000,
+            },
+            WebhookError::Network { detail } => AppError::BackendFailed {
+                backend: "webhook".into(),
+                detail,
+            },
+            WebhookError::Http { status, body } => AppError::BackendFailed {
+                backend: "webhook".into(),
+                detail: format!("HTTP {status}: {body}"),
+            },
+        }
+    }
+}
+
+fn map_request_error(err: reqwest::Error) -> WebhookError {
+    if err.is_timeout() {
+        return WebhookError::Timeout {
+            secs: REQUEST_TIMEOUT.as_secs(),
+        };
+    }
+    let mut detail = err.to_string();
+    let mut source = err.source();
+    while let Some(inner) = source {
+        detail = format!("{detail}: {inner}");
+        source = inner.source();
+    }
+    WebhookError::Network { detail }
+}
+
+/// 事件内容，没有的字段不出现在请求体中
+#[derive(Default)]
+pub struct Payload<'a> {
+    pub text: Option<String>,
+    /// 保存的文件路径
+    pub path: Option<String>,
+    /// 上传后的链接
+    pub url: Option<String>,
+    /// 图片数据及格式，仅发给开启了 include_image 的 Webhook
+    pub image: Option<(&'a [u8], ExportFormat)>,
+}
+
+impl Payload<'_> {
+    fn to_json(&self, event: WebhookEvent, with_image: bool) -> Value {
+        let now = chrono::Local::now();
+        let mut body = Map::new();
+        body.insert("event".into(), event.as_str().into());
+        body.insert("timestamp".into(), now.to_rfc3339().into());
+        body.insert("timestamp_ms".into(), now.timestamp_millis().into());
+        let fields = [
+            ("text", &self.text),
+            ("path", &self.path),
+            ("url", &self.url),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                body.insert(key.into(), value.clone().into());
+            }
+        }
+        if let Some((data, format)) = self.image.filter(|_| with_image) {
+            body.insert(
+                "image".into(),
+                json!({ "mime": format.mime_type(), "base64": STANDARD.encode(data) }),
+            );
+        }
+        Value::Object(body)
+    }
+}
+
+/// RFC 2104 HMAC-SHA256
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mac = hmac_sha256(secret.as_bytes(), body);
+    let hex: String = mac.iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+/// 发送一次，返回响应状态码
+async fn send_once(
+    client: &reqwest::Client,
+    hook: &Webhook,
+    event: WebhookEvent,
+    body: &[u8],
+) -> Result<u16, WebhookError> {
+    let mut request = client
+        .post(hook.url.trim())
+        .timeout(REQUEST_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("X-Prinsp-Event", event.as_str())
+        .body(body.to_vec());
+    if let Some(secret) = hook.secret() {
+        request = request.header(SIGNATURE_HEADER, signature(secret, body));
+    }
+    let response = request.send().await.map_err(map_request_error)?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(WebhookError::Http {
+            status: status.as_u16(),
+            body: body.chars().take(MAX_ERROR_BODY).collect(),
+        });
+    }
+    Ok(status.as_u16())
+}
+
+/// 失败时按 RETRY_DELAYS 重试，最终失败只记录日志
+async fn deliver(client: reqwest::Client, hook: Webhook, event: WebhookEvent, body: Vec<u8>) {
+    let mut attempt = 0;
+    loop {
+        match send_once(&client, &hook, event, &body).await {
+            Ok(status) => {
+                tracing::debug!(id = %hook.id, ?event, status, attempt, "Webhook 已送达");
+                return;
+            }
+            Err(err) if err.retryable() && attempt < RETRY_DELAYS.len() => {
+                tracing::debug!(id = %hook.id, ?event, attempt, error = %err, "Webhook 发送失败，稍后重试");
+                sleep(RETRY_DELAYS[attempt]).await;
+                attempt += 1;
+            }
+            Err(err) => {
+                tracing::warn!(id = %hook.id, ?event, attempts = attempt + 1, error = %err, "Webhook 发送失败");
+                return;
+            }
+        }
+    }
+}
+
+async fn sleep(duration: Duration) {
+    let _ = tauri::async_runtime::spawn_blocking(move || std::thread::sleep(duration)).await;
+}
+
+/// 通知订阅了该事件的 Webhook，在后台发送，不等待结果
+pub fn fire(app: &AppHandle, event: WebhookEvent, payload: Payload<'_>) {
+    let hooks = app.state::<SettingsState>().get().webhooks;
+    let hooks: Vec<Webhook> = hooks.into_iter().filter(|h| h.subscribes(event)).collect();
+    if hooks.is_empty() {
+        return;
+    }
+    let client = crate::http::client(app);
+    for hook in hooks {
+        // 图片在这里编码，之后不再借用调用方的数据
+        let body = payload.to_json(event, hook.include_image).to_string();
+        let client = client.clone();
+        tauri::async_runtime::spawn(deliver(client, hook, event, body.into_bytes()));
+    }
+}
+
+/// 向指定 Webhook 发送一条示例事件（不重试），返回响应状态码
+#[tauri::command]
+pub async fn test_webhook(app: AppHandle, id: String) -> Result<u16, AppError> {
+    let hook = app
+        .state::<SettingsState>()
+        .get()
+        .webhooks
+        .into_iter()
+        .find(|h| h.id == id)
+        .ok_or_else(|| AppError::invalid(format!("未找到 Webhook: {id}")))?;
+    let event = hook
+        .events
+        .first()
+        .copied()
+        .unwrap_or(WebhookEvent::CaptureSaved);
+    let payload = Payload {
+        text: Some("PrinSp Webhook 测试".into()),
+        ..Payload::default()
+    };
+    let mut body = payload.to_json(event, false);
+    body["test"] = true.into();
+    let result = send_once(
+        &crate::http::client(&app),
+        &hook,
+        event,
+        body.to_string().as_bytes(),
+    )
+    .await;
+    tracing::info!(id = %hook.id, ok = result.is_ok(), "已发送 Webhook 测试事件");
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_failures_become_app_errors() {
+        let err = AppError::from(WebhookError::Timeout { secs: 15 });
+        assert!(matches!(err, AppError::Timeout { ms: 15000, .. }));
+        let err = AppError::from(WebhookError::Http {
+            status: 404,
+            body: "no such hook".into(),
+        });
+        assert_eq!(err.code(), "backend_failed");
+        assert_eq!(err.detail().as_deref(), Some("HTTP 404: no such hook"));
+    }
+
+    #[test]
+    fn only_transient_failures_are_retried() {
+        let http = |status| WebhookError::Http {
+            status,
+            body: String::new(),
+        };
+        assert!(http(429).retryable());
+        assert!(http(503).retryable());
+        assert!(!http(400).retryable());
+        assert!(WebhookError::Timeout { secs: 1 }.retryable());
+    }
+}
@@ -0,0 +1,317 @@
+use image::{DynamicImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::edits::{EditOp, EditState};
+use crate::error::AppError;
+use crate::launch::Region;
+use crate::settings::SettingsState;
+
+/// 自动检测标题栏时只在顶部这么多行内查找分隔线
+const TITLEBAR_SEARCH: u32 = 80;
+/// 标题栏至少这么高，避免把窗口边框的第一行当作分隔线
+const TITLEBAR_MIN: u32 = 16;
+/// 分隔线上各像素与首个像素的最大通道差
+const UNIFORM_TOLERANCE: u8 = 6;
+
+/// 窗口截图的去边设置；为空的项按桌面环境取默认值
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowTrimSettings {
+    /// 截图后自动去掉透明边距（含阴影）
+    pub trim_margins: Option<bool>,
+    /// 阴影的最大不透明度（0–255），不超过该值的像素视为边距；0 时只去掉完全透明的部分
+    pub shadow_alpha: Option<u8>,
+    /// 去掉顶部的标题栏
+    pub remove_titlebar: Option<bool>,
+    /// 标题栏高度（像素）；为空时按标题栏下方颜色一致的分隔线自动检测
+    pub titlebar_px: Option<u32>,
+}
+
+impl WindowTrimSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.titlebar_px.is_some_and(|px| px > 200) {
+            return Err("标题栏高度不能超过 200 像素".into());
+        }
+        Ok(())
+    }
+}
+
+/// 合并桌面环境默认值后的去边参数
+#[derive(Clone, Copy, Debug)]
+struct TrimOptions {
+    trim_margins: bool,
+    shadow_alpha: u8,
+    remove_titlebar: bool,
+    titlebar_px: Option<u32>,
+}
+
+/// 各桌面环境的默认值：GNOME 的窗口截图带很宽的半透明阴影，KDE 的阴影较窄较淡，
+/// 其他环境通常不带阴影，只去掉完全透明的部分
+fn desktop_defaults(desktop: &str) -> TrimOptions {
+    let desktop = desktop.to_ascii_lowercase();
+    let shadow_alpha = if desktop.contains("gnome") || desktop.contains("unity") {
+        120
+    } else if desktop.contains("kde") {
+        80
+    } else {
+        0
+    };
+    TrimOptions {
+        trim_margins: true,
+        shadow_alpha,
+        remove_titlebar: false,
+        titlebar_px: None,
+    }
+}
+
+fn resolve(settings: &WindowTrimSettings) -> TrimOptions {
+    let defaults = desktop_defaults(&std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default());
+    TrimOptions {
+        trim_margins: settings.trim_margins.unwrap_or(defaults.trim_margins),
+        shadow_alpha: settings.shadow_alpha.unwrap_or(defaults.shadow_alpha),
+        remove_titlebar: settings.remove_titlebar.unwrap_or(defaults.remove_titlebar),
+        titlebar_px: settings.titlebar_px.or(defaults.titlebar_px),
+    }
+}
+
+/// 各边裁掉的像素数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Margins {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+/// 去边结果；edit 为空表示没有可裁掉的部分，截图未改动
+#[derive(Debug, Serialize)]
+pub struct TrimResult {
+    /// 透明边距与阴影
+    pub margins: Margins,
+    /// 在去掉边距之后再从顶部裁掉的标题栏高度
+    pub titlebar: u32,
+    /// 标题栏高度来自自动检测
+    pub titlebar_detected: bool,
+    /// 裁剪作为一步编辑记录，可用 undo_edit 撤销
+    pub edit: Option<EditState>,
+}
+
+/// 不透明度超过 threshold 的像素所在的范围
+fn opaque_bounds(image: &RgbaImage, threshold: u8) -> Option<Region> {
+    let (width, height) = image.dimensions();
+    let solid_row = |y: u32| (0..width).any(|x| image.get_pixel(x, y)[3] > threshold);
+    let solid_col =
+        |x: u32, top: u32, bottom: u32| (top..bottom).any(|y| image.get_pixel(x, y)[3] > threshold);
+    let top = (0..height).find(|&y| solid_row(y))?;
+    let bottom = (top..height).rev().find(|&y| solid_row(y))? + 1;
+    let left = (0..width).find(|&x| solid_col(x, top, bottom))?;
+    let right = (left..width).rev().find(|&x| solid_col(x, top, bottom))? + 1;
+    Some(Region {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
+
+fn uniform_row(image: &RgbaImage, area: Region, y: u32) -> bool {
+    let first = image.get_pixel(area.x, y);
+    (area.x..area.x + area.width).all(|x| {
+        let pixel = image.get_pixel(x, y);
+        (0..4).all(|c| pixel[c].abs_diff(first[c]) <= UNIFORM_TOLERANCE)
+    })
+}
+
+/// 标题栏下方通常是一条颜色一致的横线，且与其上方同样一致的标题栏底色不同；
+/// 返回横线之下的行号（相对于 area）
+fn detect_titlebar(image: &RgbaImage, area: Region) -> Option<u32> {
+    let end = area.y + area.height.min(TITLEBAR_SEARCH);
+    (area.y + TITLEBAR_MIN..end)
+        .find(|&y| {
+            let (above, line) = (image.get_pixel(area.x, y - 1), image.get_pixel(area.x, y));
+            (0..3).any(|c| above[c].abs_diff(line[c]) > UNIFORM_TOLERANCE)
+                && uniform_row(image, area, y)
+                && uniform_row(image, area, y - 1)
+        })
+        .map(|y| y + 1 - area.y)
+}
+
+/// 计算要保留的区域；没有可裁掉的部分时返回 None
+fn plan(image: &DynamicImage, options: &TrimOptions) -> Option<(Region, TrimResult)> {
+    let rgba = image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    let full = Region {
+        x: 0,
+        y: 0,
+        width,
+        height,
+    };
+    let mut area = if options.trim_margins {
+        opaque_bounds(&rgba, options.shadow_alpha)?
+    } else {
+        full
+    };
+    let margins = Margins {
+        top: area.y,
+        right: width - area.x - area.width,
+        bottom: height - area.y - area.height,
+        left: area.x,
+    };
+    let (titlebar, titlebar_detected) = match (options.remove_titlebar, options.titlebar_px) {
+        (false, _) => (0, false),
+        (true, Some(px)) => (px, false),
+        (true, None) => match detect_titlebar(&rgba, area) {
+            Some(px) => (px, true),
+            None => (0, false),
+        },
+    };
+    // 至少保留一行
+    let titlebar = titlebar.min(area.height - 1);
+    area.y += titlebar;
+    area.height -= titlebar;
+    (area != full).then_some((
+        area,
+        TrimResult {
+            margins,
+            titlebar,
+            titlebar_detected,
+            edit: None,
+        },
+    ))
+}
+
+/// 去掉缓存中窗口截图的透明边距、阴影和标题栏，作为一步裁剪记录；
+/// 参数为空的项使用设置及桌面环境的默认值
+#[tauri::command]
+pub async fn trim_capture(
+    app: AppHandle,
+    capture_id: u64,
+    remove_titlebar: Option<bool>,
+    titlebar_px: Option<u32>,
+) -> Result<TrimResult, AppError> {
+    let mut options = resolve(&app.state::<SettingsState>().get().window_trim);
+    if let Some(remove) = remove_titlebar {
+        options.remove_titlebar = remove;
+    }
+    if titlebar_px.is_some() {
+        options.titlebar_px = titlebar_px;
+    }
+    crate::run_blocking(move || {
+        let cache = app.state::<crate::capture_cache::CaptureCache>();
+        let image = cache.image(capture_id)?;
+        let Some((region, mut result)) = plan(&image, &options) else {
+            return Ok(TrimResult {
+                margins: Margins::default(),
+                titlebar: 0,
+                titlebar_detected: false,
+                edit: None,
+            });
+        };
+        tracing::debug!(
+            capture_id,
+            margins = ?result.margins,
+            titlebar = result.titlebar,
+            "已去掉窗口截图的边距"
+        );
+        result.edit = Some(cache.edit(capture_id, EditOp::Crop { region })?);
+        Ok(result)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const TITLEBAR: Rgba<u8> = Rgba([60, 60, 60, 255]);
+    const LINE: Rgba<u8> = Rgba([200, 200, 200, 255]);
+    const BODY: Rgba<u8> = Rgba([250, 250, 250, 255]);
+
+    fn options(shadow_alpha: u8, remove_titlebar: bool, titlebar_px: Option<u32>) -> TrimOptions {
+        TrimOptions {
+            trim_margins: true,
+            shadow_alpha,
+            remove_titlebar,
+            titlebar_px,
+        }
+    }
+
+    /// 40×60 的截图：四周 5 像素半透明阴影、外圈 1 像素完全透明，中间是带 20 行标题栏和分隔线的窗口
+    fn window() -> DynamicImage {
+        let image = RgbaImage::from_fn(40, 60, |x, y| {
+            let edge = x.min(39 - x).min(y).min(59 - y);
+            match (edge, y) {
+                (0, _) => Rgba([0, 0, 0, 0]),
+                (1..=5, _) => Rgba([0, 0, 0, 60]),
+                (_, ..=25) => TITLEBAR,
+                (_, 26) => LINE,
+                _ => BODY,
+            }
+        });
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn defaults_follow_the_desktop() {
+        assert_eq!(desktop_defaults("ubuntu:GNOME").shadow_alpha, 120);
+        assert_eq!(desktop_defaults("KDE").shadow_alpha, 80);
+        assert_eq!(desktop_defaults("sway").shadow_alpha, 0);
+        assert!(!desktop_defaults("GNOME").remove_titlebar);
+        assert!(WindowTrimSettings {
+            titlebar_px: Some(201),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn shadow_is_trimmed_up_to_the_configured_alpha() {
+        let (region, result) = plan(&window(), &options(80, false, None)).unwrap();
+        assert_eq!(
+            region,
+            Region {
+                x: 6,
+                y: 6,
+                width: 28,
+                height: 48
+            }
+        );
+        assert_eq!(
+            result.margins,
+            Margins {
+                top: 6,
+                right: 6,
+                bottom: 6,
+                left: 6
+            }
+        );
+        // 阈值低于阴影时只去掉完全透明的一圈
+        let (region, _) = plan(&window(), &options(0, false, None)).unwrap();
+        assert_eq!((region.x, region.width), (1, 38));
+    }
+
+    #[test]
+    fn titlebar_is_detected_above_the_separator_line() {
+        let (region, result) = plan(&window(), &options(80, true, None)).unwrap();
+        assert!(result.titlebar_detected);
+        // 标题栏与分隔线一起去掉
+        assert_eq!(result.titlebar, 21);
+        assert_eq!((region.y, region.height), (27, 27));
+
+        // 指定的高度不超过窗口，至少保留一行
+        let (region, result) = plan(&window(), &options(80, true, Some(500))).unwrap();
+        assert!(!result.titlebar_detected);
+        assert_eq!((region.y, region.height, result.titlebar), (53, 1, 47));
+    }
+
+    #[test]
+    fn nothing_to_trim_leaves_the_capture_alone() {
+        let opaque = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, BODY));
+        assert!(plan(&opaque, &options(80, true, None)).is_none());
+        let transparent = DynamicImage::new_rgba8(10, 10);
+        assert!(plan(&transparent, &options(0, false, None)).is_none());
+    }
+}
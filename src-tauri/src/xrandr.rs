@@ -0,0 +1,126 @@
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::{self, ConnectionExt as _};
+
+use crate::overlay::MonitorInfo;
+
+/// XRandR 报告的一个显示区域；镜像（克隆）的多个输出合并为一项
+#[derive(Debug)]
+struct Output {
+    name: String,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    primary: bool,
+    /// 与该区域显示相同内容的其他输出
+    mirrors: Vec<String>,
+}
+
+impl Output {
+    fn same_area(&self, info: &MonitorInfo) -> bool {
+        (self.x, self.y, self.width, self.height) == (info.x, info.y, info.width, info.height)
+    }
+}
+
+fn err(e: impl std::fmt::Display) -> String {
+    format!("XRandR: {e}")
+}
+
+/// 按桌面排列（先左后右、先上后下）列出已连接且启用的输出。
+///
+/// 已断开但仍列出的输出、已连接但未分配 CRTC（关闭）的输出都跳过
+fn layout() -> Result<Vec<Output>, String> {
+    let (conn, screen) = x11rb::connect(None).map_err(err)?;
+    let root = conn.setup().roots[screen].root;
+    let resources = conn
+        .randr_get_screen_resources_current(root)
+        .map_err(err)?
+        .reply()
+        .map_err(err)?;
+    let primary = conn
+        .randr_get_output_primary(root)
+        .map_err(err)?
+        .reply()
+        .map_err(err)?
+        .output;
+
+    let mut outputs: Vec<Output> = Vec::new();
+    for &output in &resources.outputs {
+        let info = conn
+            .randr_get_output_info(output, resources.config_timestamp)
+            .map_err(err)?
+            .reply()
+            .map_err(err)?;
+        if info.connection != randr::Connection::CONNECTED || info.crtc == 0 {
+            continue;
+        }
+        let crtc = conn
+            .randr_get_crtc_info(info.crtc, resources.config_timestamp)
+            .map_err(err)?
+            .reply()
+            .map_err(err)?;
+        if crtc.width == 0 || crtc.height == 0 {
+            continue;
+        }
+        let output = Output {
+            name: String::from_utf8_lossy(&info.name).into_owned(),
+            x: crtc.x.into(),
+            y: crtc.y.into(),
+            width: crtc.width.into(),
+            height: crtc.height.into(),
+            primary: output == primary,
+            mirrors: Vec::new(),
+        };
+        // 共用 CRTC 或位置大小完全相同的输出显示的是同一块桌面
+        match outputs.iter_mut().find(|o| {
+            (o.x, o.y, o.width, o.height) == (output.x, output.y, output.width, output.height)
+        }) {
+            Some(existing) if output.primary && !existing.primary => {
+                existing
+                    .mirrors
+                    .push(std::mem::replace(&mut existing.name, output.name));
+                existing.primary = true;
+            }
+            Some(existing) => existing.mirrors.push(output.name),
+            None => outputs.push(output),
+        }
+    }
+    outputs.sort_by_key(|o| (o.x, o.y));
+    Ok(outputs)
+}
+
+/// X11 下以 XRandR 的排列为准：按桌面顺序排列 xcap 的显示器，并使用 XRandR 的名称与主显示器标记。
+///
+/// 按位置和大小对应，显示器 id 仍是 xcap 的 id，截图时按 id 查找不受影响；
+/// Wayland 下或查询失败时原样返回
+pub fn reconcile(monitors: Vec<MonitorInfo>) -> Vec<MonitorInfo> {
+    if std::env::var("WAYLAND_DISPLAY").is_ok() || std::env::var_os("DISPLAY").is_none() {
+        return monitors;
+    }
+    let outputs = match layout() {
+        Ok(outputs) => outputs,
+        Err(err) => {
+            tracing::warn!(error = %err, "读取 XRandR 显示器布局失败，使用 xcap 的顺序");
+            return monitors;
+        }
+    };
+
+    let mut remaining = monitors;
+    let mut ordered = Vec::with_capacity(remaining.len());
+    for output in outputs {
+        let Some(index) = remaining.iter().position(|m| output.same_area(m)) else {
+            tracing::warn!(output = %output.name, "xcap 未报告该输出，无法截取");
+            continue;
+        };
+        let mut info = remaining.remove(index);
+        // xcap 可能把镜像的输出各报告一次，只保留一个
+        remaining.retain(|m| !output.same_area(m));
+        info.name = output.name;
+        info.primary = output.primary;
+        info.mirrors = output.mirrors;
+        ordered.push(info);
+    }
+    // xcap 报告而 XRandR 没有的区域放在最后，仍可按 id 截取
+    ordered.extend(remaining);
+    ordered
+}
@@ -0,0 +1,31 @@
+import { ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import type { Capabilities } from '../types'
+
+// 各窗口启动时获取一次，组件共用
+const capabilities = ref<Capabilities | null>(null)
+let loading: Promise<Capabilities> | null = null
+
+// refresh 为 true 时后端重新探测，如安装语言包或截图工具之后
+export function loadCapabilities(refresh = false): Promise<Capabilities> {
+  if (!refresh && loading) return loading
+  loading = invoke<Capabilities>('get_capabilities', { refresh })
+    .then(result => (capabilities.value = result))
+    .catch(e => {
+      loading = null
+      throw e
+    })
+  return loading
+}
+
+export function useCapabilities() {
+  if (!loading) {
+    loadCapabilities().catch(e => console.error('获取功能支持情况失败:', e))
+    // 其他窗口重新探测后同步
+    listen<Capabilities>('capabilities-changed', event => {
+      capabilities.value = event.payload
+    })
+  }
+  return { capabilities, loadCapabilities }
+}
@@ -0,0 +1,40 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+interface KvChanged {
+  key: string
+  value: unknown | null
+}
+
+// 界面偏好保存在后端的 ui-store.json，清除 WebView 数据后不会丢失
+export async function kvGet<T>(key: string, legacyKey?: string): Promise<T | null> {
+  const value = await invoke<T | null>('kv_get', { key })
+  if (value !== null || !legacyKey) return value
+  // 旧版本存在 localStorage 中，读到后迁移过去
+  const legacy = localStorage.getItem(legacyKey)
+  if (legacy === null) return null
+  let parsed: T
+  try {
+    parsed = JSON.parse(legacy)
+  } catch {
+    parsed = legacy as T
+  }
+  await kvSet(key, parsed)
+  localStorage.removeItem(legacyKey)
+  return parsed
+}
+
+export async function kvSet(key: string, value: unknown) {
+  await invoke('kv_set', { key, valueJson: JSON.stringify(value) })
+}
+
+export async function kvDelete(key: string) {
+  return invoke<boolean>('kv_delete', { key })
+}
+
+// 其他窗口修改后同步，value 为 null 表示已删除
+export function onKvChanged(key: string, handler: (value: unknown | null) => void): Promise<UnlistenFn> {
+  return listen<KvChanged>('kv-changed', event => {
+    if (event.payload.key === key) handler(event.payload.value)
+  })
+}
@@ -0,0 +1,88 @@
+import { computed, ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+import type { OverlayTransform, Selection } from '../types'
+
+const IDENTITY: OverlayTransform = { scale_x: 1, scale_y: 1, offset_x: 0, offset_y: 0 }
+
+interface OverlayGeometry {
+  label: string
+  x: number
+  y: number
+  width: number
+  height: number
+  scale_factor: number
+  transform: OverlayTransform | null
+}
+
+function loadImageSize(url: string): Promise<{ width: number, height: number }> {
+  return new Promise(resolve => {
+    const img = new Image()
+    img.onload = () => resolve({ width: img.width, height: img.height })
+    img.src = url
+  })
+}
+
+// 窗口 CSS 像素与截图像素的换算（分数缩放下不是 1:1）
+export function useOverlayTransform(windowLabel: string) {
+  const transform = ref<OverlayTransform>({ ...IDENTITY })
+  const imageSize = ref({ width: 0, height: 0 })
+
+  async function load(imageUrl: string) {
+    imageSize.value = await loadImageSize(imageUrl)
+    try {
+      const geometry = await invoke<OverlayGeometry>('get_overlay_geometry', { windowLabel })
+      if (geometry.transform) {
+        transform.value = geometry.transform
+        return
+      }
+    } catch (e) {
+      console.error('Overlay geometry error:', e)
+    }
+    // 没有显示器信息时按截图与窗口尺寸之比换算
+    transform.value = {
+      scale_x: imageSize.value.width / window.innerWidth,
+      scale_y: imageSize.value.height / window.innerHeight,
+      offset_x: 0,
+      offset_y: 0
+    }
+  }
+
+  function reset() {
+    transform.value = { ...IDENTITY }
+  }
+
+  // CSS 选区对应的截图像素区域
+  function toImageRect(sel: Selection): Selection {
+    const t = transform.value
+    return {
+      x: Math.round(sel.x * t.scale_x + t.offset_x),
+      y: Math.round(sel.y * t.scale_y + t.offset_y),
+      width: Math.round(sel.width * t.scale_x),
+      height: Math.round(sel.height * t.scale_y)
+    }
+  }
+
+  // 截图像素区域对应的 CSS 选区
+  function fromImageRect(rect: Selection): Selection {
+    const t = transform.value
+    return {
+      x: (rect.x - t.offset_x) / t.scale_x,
+      y: (rect.y - t.offset_y) / t.scale_y,
+      width: rect.width / t.scale_x,
+      height: rect.height / t.scale_y
+    }
+  }
+
+  // 让背景截图与屏幕内容逐像素对齐
+  const backgroundStyle = computed(() => {
+    const t = transform.value
+    return {
+      left: `${-t.offset_x / t.scale_x}px`,
+      top: `${-t.offset_y / t.scale_y}px`,
+      width: `${imageSize.value.width / t.scale_x}px`,
+      height: `${imageSize.value.height / t.scale_y}px`
+    }
+  })
+
+  return { transform, load, reset, toImageRect, fromImageRect, backgroundStyle }
+}
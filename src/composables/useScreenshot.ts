@@ -1,36 +1,64 @@
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
+import { errorMessage } from '../errors'
+import type { CaptureHandle } from '../types'
+
+// 裁剪结果等前端生成的图片用 Blob URL 显示，避免 base64 膨胀
+export function pngToUrl(png: ArrayBuffer | Uint8Array): string {
+  return URL.createObjectURL(new Blob([png], { type: 'image/png' }))
+}
+
+export function canvasToPng(canvas: HTMLCanvasElement): Promise<Uint8Array> {
+  return new Promise((resolve, reject) => {
+    canvas.toBlob(async blob => {
+      if (!blob) return reject(new Error('canvas 编码失败'))
+      resolve(new Uint8Array(await blob.arrayBuffer()))
+    }, 'image/png')
+  })
+}
 
 export function useScreenshot() {
+  // 当前截图的地址，由后端通过 prinsp:// 协议直接提供，不经过 JS 内存
   const screenshotData = ref<string>('')
+  // 缓存中截图的 id，识别文字时由后端直接裁剪
+  const captureId = ref<number | null>(null)
+  // 截图操作的编号，与后端事件中的 capture_id 一致
+  const operationId = ref<string | null>(null)
   const loading = ref(false)
   const error = ref<string>('')
 
+  function setScreenshot(capture: CaptureHandle | null) {
+    screenshotData.value = capture?.preview_url ?? ''
+    captureId.value = capture?.id ?? null
+    operationId.value = capture?.capture_id ?? null
+  }
+
   async function captureScreen() {
     loading.value = true
     error.value = ''
     try {
       console.log('Capturing screen...')
-      screenshotData.value = await invoke<string>('capture_screen')
-      console.log('Screenshot captured, size:', screenshotData.value.length)
+      const capture = await invoke<CaptureHandle>('capture_screen')
+      setScreenshot(capture)
+      console.log('Screenshot captured:', capture.width, 'x', capture.height)
     } catch (e) {
-      error.value = String(e)
+      error.value = errorMessage(e)
       console.error('Screenshot error:', e)
-      alert('截图失败: ' + e)
+      alert('截图失败: ' + error.value)
     } finally {
       loading.value = false
     }
   }
 
-  async function copyToClipboard(base64Data: string) {
+  async function copyToClipboard(png: Uint8Array) {
     try {
-      await invoke('copy_to_clipboard', { base64Data })
+      await invoke('copy_to_clipboard', png)
       console.log('Copied to clipboard')
     } catch (e) {
       console.error('Clipboard error:', e)
-      alert('复制到剪贴板失败: ' + e)
+      alert('复制到剪贴板失败: ' + errorMessage(e))
     }
   }
 
-  return { screenshotData, loading, error, captureScreen, copyToClipboard }
-}
\ No newline at end of file
+  return { screenshotData, captureId, operationId, loading, error, setScreenshot, captureScreen, copyToClipboard }
+}
@@ -0,0 +1,32 @@
+import { ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import type { SystemAppearance } from '../types'
+
+const appearance = ref<SystemAppearance | null>(null)
+let started = false
+
+// 写到根元素上，样式按 data-theme、data-reduced-motion 和 --system-accent 适配
+function apply(value: SystemAppearance) {
+  appearance.value = value
+  const root = document.documentElement
+  root.dataset.theme = value.color_scheme
+  root.dataset.reducedMotion = String(value.reduced_motion)
+  if (value.accent_color) {
+    root.style.setProperty('--system-accent', value.accent_color)
+  } else {
+    root.style.removeProperty('--system-accent')
+  }
+}
+
+// 各窗口启动时调用一次，系统设置变化后自动更新
+export function useSystemAppearance() {
+  if (!started) {
+    started = true
+    invoke<SystemAppearance>('get_system_appearance')
+      .then(apply)
+      .catch(e => console.error('获取系统外观失败:', e))
+    listen<SystemAppearance>('system-appearance-changed', event => apply(event.payload))
+  }
+  return { appearance }
+}
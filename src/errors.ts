@@ -0,0 +1,10 @@
+import type { AppError } from './types'
+
+export function isAppError(e: unknown): e is AppError {
+  return typeof e === 'object' && e !== null && 'code' in e && 'message' in e
+}
+
+// 命令的错误可能是 AppError，也可能是旧命令返回的字符串
+export function errorMessage(e: unknown): string {
+  return isAppError(e) ? e.message : String(e)
+}
@@ -1,4 +1,6 @@
 import { createApp } from "vue";
 import App from "./App.vue";
+import { useSystemAppearance } from "./composables/useSystemAppearance";
 
+useSystemAppearance();
 createApp(App).mount("#app");
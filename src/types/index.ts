@@ -22,4 +22,555 @@ export interface Selection {
   y: number
   width: number
   height: number
-}
\ No newline at end of file
+}
+// 一次截图操作的编号（UUID），触发时生成，随截图相关的事件和返回值传回
+export type CaptureId = string
+
+export interface CaptureTrigger {
+  source: 'shortcut' | 'tray' | 'menu' | 'launch' | 'dbus'
+  action: 'capture' | 'ocr_capture' | 'repeat_region'
+  shortcut: string | null
+  capture_id: CaptureId
+}
+
+// get_capture_session_state 的返回值
+export interface CaptureSessionState {
+  active: boolean
+  session: number
+  elapsed_ms: number | null
+  retrigger: 'ignore' | 'restart'
+}
+
+// 截图命令的返回值，图片通过 preview_url（prinsp:// 协议）加载
+export interface CaptureHandle {
+  // 缓存编号
+  id: number
+  capture_id: CaptureId
+  width: number
+  height: number
+  preview_url: string
+  // 以 withTimings: true 调用时才有
+  timings?: Timings
+  // 截图按夜灯色温补偿过时才有
+  night_light?: NightLightCompensation
+}
+
+// 固定尺寸的选区预设，position 为选区左上角的桌面坐标，为空时以光标为中心
+export interface SelectionPreset {
+  name: string
+  width: number
+  height: number
+  position?: { x: number, y: number } | null
+}
+
+// resolve_selection_preset 的返回值：region 为相对显示器左上角的截图像素
+export interface ResolvedSelection {
+  monitor: number
+  region: Selection
+  clamped: boolean
+}
+
+// 截图使用的夜灯补偿：色温及来源（手动指定或从 GNOME 读取）
+export interface NightLightCompensation {
+  temperature_k: number
+  source: 'manual' | 'gnome'
+}
+
+// 夜灯补偿设置，temperature_k 为 null 时从 GNOME 读取
+export interface NightLightSettings {
+  compensate: boolean
+  temperature_k: number | null
+}
+
+// 设置中的提示音，volume 为 0 到 1
+export interface SoundSettings {
+  enabled: boolean
+  capture: boolean
+  copy: boolean
+  error: boolean
+  volume: number
+}
+
+// get_recovery_state 的返回值：上次未完成的截图，annotations 为前端保存的选区与标注
+export interface RecoveryState {
+  capture: CaptureHandle
+  captured_at: number
+  width: number
+  height: number
+  downscaled: boolean
+  annotations: { selection?: Selection, shapes?: Annotation[] } | null
+}
+
+// 一次截图或识别中各阶段的耗时（毫秒）
+export interface Timings {
+  stages: { name: string, ms: number }[]
+  total_ms: number
+}
+
+// 翻译失败的原因，kind 对应后端 TranslateError
+export interface TranslateError {
+  kind: 'invalid_input' | 'not_configured' | 'timeout' | 'network' | 'http' | 'invalid_response'
+  detail?: string
+  secs?: number
+  status?: number
+  body?: string
+}
+
+// translate_text 的返回值；failed 中的分段在 text 里保留原文
+export interface Translation {
+  text: string
+  chunks: number
+  failed: { index: number, error: TranslateError }[]
+}
+
+// 提取模板中的字段：label、value 为正则，value 有捕获组时取第一个捕获组
+export interface ExtractionField {
+  name: string
+  label: string
+  value: string
+}
+
+export interface ExtractionTemplate {
+  name: string
+  fields: ExtractionField[]
+}
+
+// list_extraction_templates 的返回项，builtin 为未被覆盖的内置模板
+export interface ExtractionTemplateInfo extends ExtractionTemplate {
+  builtin: boolean
+}
+
+// extract_fields 的返回值
+export interface Extraction {
+  template: string
+  fields: { name: string, status: 'found' | 'missing', value: string | null }[]
+  missing: number
+}
+
+// 识别命令的返回值：请求耗时、顺带翻译、提取字段或图片过大被缩小时为对象
+export type OcrResponse = string | {
+  text: string
+  timings?: Timings
+  warning?: string
+  translation?: Translation
+  translation_error?: TranslateError
+  extraction?: Extraction
+  // 模板不存在或无效时为 invalid_input，正则错误的 detail 中用 ^ 标出出错位置
+  extraction_error?: AppError
+  // 以字节传入的图片不是 PNG 时附带识别出的格式
+  format?: 'png' | 'jpeg' | 'webp' | 'bmp' | 'gif' | 'tiff' | 'heic' | 'avif'
+  // 以 diffClipboard 调用时与剪贴板文字的比较结果，读取失败时为 diff_error
+  diff?: TextDiff
+  diff_error?: AppError
+}
+
+// 识别结果与参考文字的一处差异；以参考文字为准，delete 为识别结果缺少的文字，insert 为多出的文字
+export type DiffChange =
+  | { op: 'equal' | 'delete' | 'insert', reference_index: number, ocr_index: number, text: string }
+  | { op: 'replace', reference_index: number, ocr_index: number, reference_text: string, ocr_text: string }
+
+// compare_text 的返回值，similarity 为 0–100
+export interface TextDiff {
+  similarity: number
+  reference_tokens: number
+  ocr_tokens: number
+  changes: DiffChange[]
+}
+
+// 用户定义的识别结果后处理规则，replacement 可用 $1、${name} 引用捕获组
+export interface PostprocessRule {
+  pattern: string
+  replacement: string
+  enabled: boolean
+}
+
+// test_postprocess_rules 的返回值，base 为内置整理后的文字，steps 为每条规则执行后的文字
+export interface RuleTestResult {
+  base: string
+  steps: { index: number, pattern: string, enabled: boolean, matches: number, text: string }[]
+}
+
+// speak_text 的错误；no_engine 时 install 为建议安装的软件包
+export interface SpeechError {
+  kind: 'invalid_input' | 'no_engine' | 'failed'
+  detail?: string
+  hint?: string
+  install?: string[]
+}
+
+// generate_qr 的返回值，size 为图片边长（像素）
+export interface QrImage {
+  png_base64: string
+  size: number
+  modules: number
+}
+
+// generate_qr 的错误；too_long 时 max_bytes 为所选容错等级下的上限
+export interface QrError {
+  kind: 'invalid_input' | 'too_long' | 'failed'
+  detail?: string
+  bytes?: number
+  max_bytes?: number
+  error_correction?: 'l' | 'm' | 'q' | 'h'
+}
+
+// 本机 HTTP 接口设置，令牌在开启时自动生成
+export interface LocalApiSettings {
+  enabled: boolean
+  port: number
+  token: string
+}
+
+// 光标处取字：以光标为中心、边长 2·radius_px 的区域
+export interface ReadAtCursorSettings {
+  radius_px: number
+  speak: boolean
+}
+
+export type SensitiveClass = 'email' | 'phone' | 'iban' | 'token' | 'custom'
+
+// 自动打码设置，custom_patterns 为 custom 类别使用的正则
+export interface RedactSettings {
+  auto_redact: boolean
+  classes: SensitiveClass[]
+  custom_patterns: string[]
+}
+
+// detect_sensitive_regions 返回的区域（原图像素坐标）
+export interface SensitiveRegion {
+  class: SensitiveClass
+  x: number
+  y: number
+  width: number
+  height: number
+  text: string
+}
+
+// auto_redact 的返回值，redacted 为打码的区域数
+export interface RedactResult {
+  base64_data: string
+  regions: SensitiveRegion[]
+  redacted: number
+}
+
+export type WebhookEvent = 'capture_saved' | 'ocr_complete' | 'upload_complete'
+
+// 设置中的 Webhook，events 为空时订阅全部事件
+export interface Webhook {
+  id: string
+  url: string
+  events: WebhookEvent[]
+  secret: string | null
+  include_image: boolean
+}
+
+// list_text_watches 的返回值，text 为当前确认的文字
+export interface TextWatch {
+  id: number
+  region: { x: number, y: number, width: number, height: number }
+  monitor: number | null
+  interval_s: number
+  options: { label: string | null, notify: boolean, lang: string | null }
+  text: string | null
+  started_at: number
+}
+
+// watched-text-changed 事件
+export interface WatchedTextChanged {
+  id: number
+  label: string | null
+  old_text: string
+  new_text: string
+  at: number
+}
+
+// list_change_watches 的返回值，region 为空时监视整个显示器
+export interface ChangeWatch {
+  id: number
+  region: { x: number, y: number, width: number, height: number } | null
+  monitor: number | null
+  interval_s: number
+  threshold_pct: number
+  options: { label: string | null, notify: boolean, min_region_px: number }
+  changes: number
+  started_at: number
+}
+
+// screen-changed 事件，bbox 相对于监视区域，thumbnail 为变化区域的 PNG base64
+export interface ScreenChanged {
+  id: number
+  label: string | null
+  changed_pct: number
+  bbox: { x: number, y: number, width: number, height: number }
+  thumbnail: string
+  at: number
+}
+
+// start_recording 的选项；MP4 超过 gif_max_mb 时不转换 GIF
+export interface RecordingOptions {
+  fps?: number
+  max_duration_s?: number
+  gif?: boolean
+  gif_max_mb?: number
+}
+
+// start_recording 的返回值
+export interface RecordingInfo {
+  id: number
+  recorder: 'wf_recorder' | 'ffmpeg'
+  path: string
+  max_duration_s: number
+  started_at: number
+}
+
+// stop_recording 的返回值，也作为 recording-stopped 事件发出
+export interface RecordingResult {
+  id: number
+  path: string
+  gif_path: string | null
+  gif_skipped: string | null
+  duration_s: number
+  auto_stopped: boolean
+}
+
+// recording-elapsed 事件，录制期间每秒一次
+export interface RecordingElapsed {
+  id: number
+  elapsed_s: number
+  max_duration_s: number
+}
+
+// share_file 成功时使用的分享方式
+export type ShareMechanism = 'email_portal' | 'open_uri_portal' | 'xdg_email' | 'xdg_open' | 'windows_share' | 'macos_mail' | 'macos_open'
+
+// get_recent_errors 的返回值，新的在前
+export interface ReportedError {
+  source: 'capture' | 'ocr' | 'clipboard' | 'upload'
+  code: string
+  summary: string
+  detail: string
+  at: number
+  // 与之前的相同错误合并，没有单独通知
+  aggregated: boolean
+}
+
+// get_last_timings 的返回值
+export interface LastTimings {
+  capture: Timings | null
+  ocr: Timings | null
+}
+
+// get_system_appearance 的返回值及 system-appearance-changed 事件内容
+export interface SystemAppearance {
+  color_scheme: 'dark' | 'light' | 'no_preference'
+  // #rrggbb
+  accent_color: string | null
+  reduced_motion: boolean
+  source: 'portal' | 'gsettings' | 'registry' | 'none'
+}
+
+// 截图后端被降级的原因
+export type DemotionReason =
+  | { kind: 'missing_binary'; program: string }
+  | { kind: 'session_mismatch'; session: string }
+  | { kind: 'consecutive_failures'; count: number; error: string }
+
+export interface BackendHealth {
+  name: string
+  enabled: boolean
+  consecutive_failures: number
+  // Unix 时间（秒）
+  demoted: { reason: DemotionReason; at: number } | null
+}
+
+// get_capture_backend 的结果
+export interface CaptureBackendState {
+  // 设置中指定优先使用的后端
+  configured: string | null
+  // 上次成功、下次优先尝试的后端
+  preferred: string | null
+  session_type: string
+  // 已启用的按尝试顺序在前，禁用的在后
+  backends: BackendHealth[]
+}
+
+// 桌面的勿扰状态
+export interface DndState {
+  status: 'active' | 'inactive' | 'unknown'
+  source: 'notifications' | 'gsettings' | 'none'
+  // 设置中开启了“始终通知”
+  always_notify: boolean
+  // 当前是否因勿扰而不弹出通知、不播放提示音
+  suppressing: boolean
+}
+
+// 使用统计中一天（或范围合计）的计数
+export interface DayStats {
+  captures: number
+  // 按截图后端统计的成功次数
+  backends: Record<string, number>
+  ocr_runs: number
+  ocr_chars: number
+  ocr_ms: number
+  copies: number
+}
+
+// get_stats 的范围，week 从周一开始
+export type StatsRange = 'today' | 'week' | 'month' | 'all'
+
+// get_stats 的返回值，days 的键为本地日期 YYYY-MM-DD
+export interface StatsSummary {
+  from: string | null
+  total: DayStats
+  avg_ocr_ms: number | null
+  top_backend: string | null
+  days: Record<string, DayStats>
+  enabled: boolean
+  // 本次启动识别预热的耗时，未预热时为 null
+  warmup_ms: number | null
+}
+
+// 窗口 CSS 像素到截图像素：image = css * scale + offset
+export interface OverlayTransform {
+  scale_x: number
+  scale_y: number
+  offset_x: number
+  offset_y: number
+}
+
+// 截图、识别、剪贴板与窗口命令返回的错误
+export interface AppError {
+  code: 'no_display' | 'backend_failed' | 'tesseract_missing' | 'tesseract_too_old' | 'language_missing' | 'ocr_failed'
+    | 'clipboard_unavailable' | 'timeout' | 'cancelled' | 'window' | 'io' | 'invalid_input' | 'unsupported_format' | 'internal'
+  message: string
+  detail: string | null
+}
+
+// enqueue_ocr 的参数
+export interface OcrRequest {
+  source: { kind: 'capture', id: number } | { kind: 'history', id: number } | { kind: 'png', data: string }
+  lang?: string | null
+  priority?: 'high' | 'normal' | 'low'
+}
+
+// get_job_status 的返回值，也是 ocr-job-finished 事件中的 status
+export type OcrJobStatus =
+  | { state: 'queued' | 'running' | 'cancelled' }
+  | { state: 'done', text: string, warning?: string }
+  | { state: 'failed', code: AppError['code'], message: string }
+
+// enqueue_ocr_batch、reocr_history_items 的返回值，job_ids 与请求的顺序一致
+export interface OcrBatch {
+  batch_id: number
+  job_ids: number[]
+}
+
+// import_folder 的选项；since、until 为 Unix 秒，按文件修改时间过滤
+export interface ImportOptions {
+  recursive?: boolean
+  extensions?: string[]
+  since?: number
+  until?: number
+  copy?: boolean
+  lang?: string
+  ocr?: boolean
+}
+
+// history-import-progress 事件的内容，processed 包括跳过和失败的文件
+export interface ImportProgress {
+  import_id: number
+  discovered: number
+  processed: number
+  imported: number
+  skipped_existing: number
+  skipped_duplicates: number
+  failed: number
+  ocr_batch_id?: number
+  cancelled: boolean
+  done: boolean
+}
+
+// export_history_index 的选项；include_text 默认为 true
+export interface IndexExportOptions {
+  include_text?: boolean
+  max_text_chars?: number
+}
+
+// ocr-batch-progress 事件的内容，completed 包括失败和取消的任务
+export interface OcrBatchProgress {
+  batch_id: number
+  completed: number
+  total: number
+  failed: number
+  cancelled: number
+  eta_ms?: number
+  done: boolean
+}
+
+// get_capabilities 的返回值：当前系统上各功能能否使用
+export interface Capabilities {
+  capture: {
+    name: string
+    enabled: boolean
+    available: boolean
+    region: boolean
+    cursor: boolean
+    output_selection: boolean
+  }[]
+  ocr: {
+    available: boolean
+    version: string | null
+    languages: string[]
+    osd: boolean
+    vertical: string[]
+  }
+  clipboard: {
+    available: boolean
+    text: boolean
+    image: boolean
+    html: boolean
+    uri_list: boolean
+    primary: boolean
+  }
+  sound: {
+    available: boolean
+  }
+  platform: {
+    os: string
+    session_type: string
+    desktop: string | null
+    portal: boolean
+  }
+}
+
+// crop_image、apply_annotations、transform_image、redact_regions（传入 capture_id）、trim_capture、undo_edit、redo_edit 的返回值
+export interface EditState {
+  capture: CaptureHandle
+  // 已应用的步数
+  position: number
+  can_undo: boolean
+  can_redo: boolean
+}
+
+// 截图上的一步编辑，坐标为截图像素
+export type EditOp =
+  | { op: 'crop', region: Selection }
+  | { op: 'redact', regions: Selection[] }
+  | { op: 'transform', transform: 'rotate_cw' | 'rotate180' | 'rotate_ccw' | 'flip_horizontal' | 'flip_vertical' }
+  | { op: 'annotate', shapes: Annotation[] }
+
+// get_edit_history 的返回值，edits 中 position 之后的为已撤销、可重做的步骤
+export interface EditHistory {
+  position: number
+  edits: EditOp[]
+  folded: number
+}
+
+// trim_capture 的返回值；edit 为空时没有可裁掉的部分，否则可用 undo_edit 撤销
+export interface TrimResult {
+  margins: { top: number, right: number, bottom: number, left: number }
+  // 去掉边距后再从顶部裁掉的标题栏高度
+  titlebar: number
+  titlebar_detected: boolean
+  edit: EditState | null
+}